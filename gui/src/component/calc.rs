@@ -56,7 +56,8 @@ impl Default for LayoutR {
 #[storage(VecMapWithDefault)]
 pub struct ZDepth(pub f32);
 
-// gui支持最多32个裁剪面， 该值按位表示节点被哪些裁剪面裁剪， 等于0时， 表示不被任何裁剪面裁剪， 等于1时， 被第一个裁剪面裁剪， 等于2时，表示被第二个裁剪面裁剪， 等于3表示被第一个和第二个裁剪面共同裁剪。。。。。
+// 节点所在的最内层裁剪区域（OverflowClip中clip的索引）， 等于0时， 表示不被任何裁剪区域裁剪
+// 裁剪区域通过Clip::parent串联， 节点被这条链上所有的裁剪区域共同裁剪
 #[derive(Component, Clone, Default, Deref, DerefMut, Debug)]
 #[storage(VecMapWithDefault)]
 pub struct ByOverflow(pub usize);
@@ -226,6 +227,10 @@ pub enum StyleType1 {
     // AlignItems = 0x20000,
     // AlignSelf = 0x40000,
 	// JustifyContent = 0x80000,
+	Transition = 0x1,
+	Animation = 0x2,
+	ClipPath = 0x4,
+	TextDecoration = 0x8000,
 	Direction = 0x10000,
 	AspectRatio = 0x20000,
 	Order = 0x40000,
@@ -241,6 +246,8 @@ pub enum StyleType1 {
 	
 	Create = 0x8000000,
 	Delete = 0x10000000,
+	TextOverflow = 0x20000000,
+	LineClamp = 0x40000000,
 }
 
 // 样式标记
@@ -298,11 +305,70 @@ pub struct NodeState(pub INode);
 //     // pub node: L,               // 对应的yoga节点
 // }
 
-// // span节点对应的字符布局
-// #[derive(Component, Debug, Clone, Default)]
-// pub struct CharBlock{
-// 	pub chars: Vec<CharNode>,
-// }
+// 文字块， 文字节点的字符及其行布局结果
+// 非图文混排时， 由行布局排列字符， 字符位置相对于节点的边框盒；图文混排时， 字符由父节点的flex布局排列
+#[derive(Component, Debug, Clone, Default)]
+#[storage(DenseVecMap)]
+pub struct CharBlock {
+	pub chars: Vec<CharNode>, // 字符集合， 单词容器后紧跟单词内的字符
+	pub levels: Vec<u8>, // 每个字符的双向嵌入层级， 奇数为从右向左， 为空表示全部从左向右
+	pub ellipsis: usize, // 字符集合末尾省略号的字符数(包含其单词容器)， 省略号不参与折行
+	pub visible: usize, // 可见字符的数量， 文字被截断时， 之后的字符(省略号除外)不显示
+	pub truncated: bool, // 文字是否被截断(超出最大行数， 或最后一行超出内容区宽度)
+	pub runs: Vec<usize>, // 每个字符所在的富文本片段(片段索引加1， 0表示使用节点的文字样式)， 为空表示没有富文本
+	pub solid_glyph: usize, // 实心字形(█)的id， 取其中心的纹理绘制装饰线， 0表示不需要
+	pub lines: Vec<LineBox>,
+	pub style: LineStyle,
+	pub wrap_width: f32, // 折行宽度
+	pub size: (f32, f32), // 内容的宽高
+	pub content_box: (f32, f32, f32, f32), // 上次排列字符时， 节点内容区的x、y、宽、高
+	pub dirty: bool, // 字符或行样式改变， 需要重新折行
+	pub mixed: bool, // 是否为图文混排
+}
+
+impl CharBlock {
+	// 索引处的字符是否显示， 文字被截断时， 只显示截断位置之前的字符及省略号
+	pub fn is_visible(&self, index: usize) -> bool {
+		index < self.visible || (self.truncated && index + self.ellipsis >= self.chars.len())
+	}
+
+	// 省略号之前的文字的字符数
+	pub fn text_len(&self) -> usize {
+		self.chars.len() - self.ellipsis
+	}
+
+	// 索引处的字符所在的富文本片段， 0表示使用节点的文字样式
+	pub fn run(&self, index: usize) -> usize {
+		match self.runs.get(index) {
+			Some(r) => *r,
+			None => 0,
+		}
+	}
+}
+
+// 行盒， 记录一行的字符范围
+#[derive(Debug, Clone, Default)]
+pub struct LineBox {
+	pub start: usize, // 行首字符的索引
+	pub end: usize, // 行尾字符的下一个索引
+	pub width: f32, // 行宽， 不包含行尾的空白符
+	pub height: f32, // 行高， 富文本中字符高度不同时， 为行内最高字符的高度
+	pub gaps: usize, // 行内排列单元的间隔数， 两端对齐时用于分配剩余空间
+	pub hard: bool, // 是否为段落的最后一行(以换行符或文字结尾)
+}
+
+// 行布局属性
+#[derive(Debug, Clone, Default)]
+pub struct LineStyle {
+	pub line_height: f32,
+	pub word_margin: f32, // 空白符额外的宽度
+	pub indent: f32, // 首行缩进
+	pub text_align: TextAlign,
+	pub vertical_align: VerticalAlign,
+	pub wrap: bool, // 是否自动折行
+	pub rtl: bool, // 基础方向是否为从右向左
+	pub line_clamp: usize, // 最大行数， 0表示不限制
+}
 
 // TransformWillChange的矩阵计算结果， 用于优化Transform的频繁改变
 #[derive(Component, Debug, Clone, Default)]
@@ -545,6 +611,7 @@ defines! {
         VERTEX_COLOR: String,
         CLIP_BOX: String,
         BOX_SHADOW_BLUR: String,
+        TEXT_SHADOW_BLUR: String,
     }
 }
 
@@ -559,6 +626,7 @@ defines! {
         HSV: String,
         GRAY: String,
         STROKE: String,
+        TEXT_SHADOW_BLUR: String,
     }
 }
 
@@ -590,6 +658,7 @@ program_paramter! {
         clipBox: ClipBox,
         texture: (HalTexture, HalSampler),
         alpha: UniformValue,
+        blur: UniformValue, // 文字阴影的模糊范围（sdf距离）
     }
 }
 
@@ -620,6 +689,7 @@ program_paramter! {
         clipBox: ClipBox,
         texture: (HalTexture, HalSampler),
         alpha: UniformValue,
+        blur: UniformValue, // 文字阴影的模糊半径（纹素）
     }
 }
 
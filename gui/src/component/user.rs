@@ -115,7 +115,53 @@ pub struct ZIndex(pub isize);
 //超出部分的裁剪方式
 #[derive(Deref, DerefMut, Clone, Component, Default, Serialize, Deserialize, Debug)]
 #[storage(VecMapWithDefault)]
-pub struct Overflow(pub bool);
+pub struct Overflow(pub OverflowType);
+
+impl Overflow {
+    // 是否裁剪超出部分（hidden、scroll、auto都会裁剪）
+    #[inline]
+    pub fn is_clip(&self) -> bool {
+        self.0 != OverflowType::Visible
+    }
+
+    // 是否为滚动容器
+    #[inline]
+    pub fn is_scroll(&self) -> bool {
+        self.0 == OverflowType::Scroll || self.0 == OverflowType::Auto
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumDefault, Serialize, Deserialize)]
+pub enum OverflowType {
+    Visible,
+    Hidden,
+    Scroll, // 可滚动， 设置了ScrollBar时， 总是显示滚动条
+    Auto,   // 可滚动， 设置了ScrollBar时， 只在内容超出时显示滚动条
+}
+
+// 滚动容器的滚动偏移， 子节点整体向左上移动该偏移量， 由ScrollSys根据指针事件和惯性更新， 也可以由scroll_to设置
+// 拖动时允许超出滚动范围（回弹）
+#[derive(Clone, Copy, Component, Default, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScrollOffset {
+    pub x: f32,
+    pub y: f32,
+}
+
+// 滚动条， 滚动容器设置该组件时， 渲染滚动条的滑块
+#[derive(Clone, Component, Serialize, Deserialize, Debug)]
+pub struct ScrollBar {
+    pub width: f32,     // 滑块的宽度（垂直滚动条）或高度（水平滚动条）， 单位： 像素
+    pub color: CgColor, // 滑块的颜色
+}
+
+impl Default for ScrollBar {
+    fn default() -> Self {
+        ScrollBar {
+            width: 4.0,
+            color: CgColor::new(0.0, 0.0, 0.0, 0.4),
+        }
+    }
+}
 //不透明度
 #[derive(Deref, DerefMut, Clone, Component, Debug, Serialize, Deserialize)]
 #[storage(VecMapWithDefault)]
@@ -146,6 +192,17 @@ pub struct ClassName {
     pub other: Vec<usize>,
 }
 
+// 伪类状态， 用于匹配样式表中的伪类选择器（:hover、:active、:disabled）， 值为PseudoClassType的组合
+#[derive(Debug, Clone, Copy, Component, Default, Serialize, Deserialize)]
+pub struct PseudoClass(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PseudoClassType {
+    Hover = 1,
+    Active = 2,
+    Disabled = 4,
+}
+
 // 边框颜色
 #[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
 pub struct BorderColor(pub CgColor);
@@ -195,13 +252,69 @@ pub struct BorderImageSlice {
 #[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
 pub struct BorderImageRepeat(pub BorderImageRepeatType, pub BorderImageRepeatType);
 
-// 圆角， 目前仅支持x分量
+// 圆角， 四个角相互独立， 每个角分别有水平半径和垂直半径（百分比分别相对于宽、高）
 #[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
 pub struct BorderRadius {
+    pub top_left: CornerRadius,
+    pub top_right: CornerRadius,
+    pub bottom_right: CornerRadius,
+    pub bottom_left: CornerRadius,
+}
+
+impl BorderRadius {
+    // 四个角相同的圆角
+    pub fn new(x: LengthUnit, y: LengthUnit) -> Self {
+        let r = CornerRadius { x, y };
+        BorderRadius {
+            top_left: r,
+            top_right: r,
+            bottom_right: r,
+            bottom_left: r,
+        }
+    }
+
+    // 按左上、右上、右下、左下的顺序取角， 超出范围时返回None
+    pub fn corner_mut(&mut self, index: usize) -> Option<&mut CornerRadius> {
+        match index {
+            0 => Some(&mut self.top_left),
+            1 => Some(&mut self.top_right),
+            2 => Some(&mut self.bottom_right),
+            3 => Some(&mut self.bottom_left),
+            _ => None,
+        }
+    }
+}
+
+// 一个角的圆角， x为水平半径， y为垂直半径
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CornerRadius {
     pub x: LengthUnit,
     pub y: LengthUnit,
 }
 
+// 裁剪路径， 坐标相对于布局框的左上角（百分比分别相对于宽、高）
+// 节点设置了overflow时， 用裁剪路径代替内容区域来裁剪子节点
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+pub enum ClipPath {
+    // 圆(半径, 圆心x, 圆心y)， 半径的百分比相对于sqrt(w*w + h*h)/sqrt(2)
+    Circle(LengthUnit, LengthUnit, LengthUnit),
+    // 椭圆(水平半径, 垂直半径, 圆心x, 圆心y)
+    Ellipse(LengthUnit, LengthUnit, LengthUnit, LengthUnit),
+    // 多边形， 依次为每个顶点的(x, y)
+    Polygon(Vec<(LengthUnit, LengthUnit)>),
+}
+
+// 命中测试的方式， 未设置时为Shape
+#[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
+pub struct HitTest(pub HitTestType);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumDefault, Serialize, Deserialize)]
+pub enum HitTestType {
+    Shape,        // 按节点的实际形状（经过变换的布局框及圆角）命中
+    Bounds,       // 按节点在世界坐标系下的包围盒命中
+    OpaquePixels, // 按图片中不透明的像素命中， 图片纹理没有提供AlphaMask时， 同Shape
+}
+
 // 参考CSS的box-shadow的语法
 #[derive(Debug, Clone, Default, Component, Serialize, Deserialize)]
 pub struct BoxShadow {
@@ -224,18 +337,48 @@ pub struct Text {
     pub stroke: Stroke,
     pub text_align: TextAlign,
     pub vertical_align: VerticalAlign,
+    pub text_overflow: TextOverflow, // 文字溢出时的处理方式
+    pub line_clamp: usize,           // 最大行数， 0表示不限制
+    pub decoration: TextDecoration,  // 装饰线(下划线、上划线、删除线)
 }
 
 // 文本内容
 #[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
 pub struct TextContent(pub String, pub Atom);
 
+// 富文本， 为文本内容的不同片段设置各自的样式， 与TextContent同时设置， 片段按start排序且互不重叠
+#[derive(Clone, Component, Default)]
+pub struct TextSpans(pub Vec<TextSpan>);
+
+// 富文本片段， 作用于文本内容中[start, end)范围的字符(按字符计数)， 未设置的属性使用节点的文字样式
+#[derive(Clone, Default)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+    pub color: Option<CgColor>,
+    pub font_size: Option<FontSize>,
+    pub font_weight: Option<usize>,
+    pub font_family: Option<Atom>,
+    pub stroke: Option<Stroke>,
+    pub decoration: Option<TextDecoration>, // 片段的装饰线， None表示使用节点的装饰线
+    pub image: Option<SpanImage>, // 行内图片， 片段中的每个字符显示为该图片
+}
+
+// 行内图片
+#[derive(Clone, Default)]
+pub struct SpanImage {
+    pub url: usize,
+    pub width: f32,  // 为0时使用行高
+    pub height: f32, // 为0时使用行高
+    pub src: Option<Share<TextureRes>>,
+}
+
 #[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
 #[storage(VecMapWithDefault)]
 pub struct TextStyle {
     pub text: Text,
     pub font: Font,
-    pub shadow: TextShadow,
+    pub shadows: Vec<TextShadow>, // 文字阴影列表， 与css一致， 先声明的阴影绘制在上层
 }
 
 #[derive(Debug, Clone, Component, Default, Serialize, Deserialize)]
@@ -258,6 +401,34 @@ pub struct Font {
 #[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct TransformWillChange(pub Transform);
 
+// 过渡， 对应css的transition， 可设置多项， 同一属性以最后一项为准
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Transition(pub Vec<TransitionItem>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionItem {
+    pub property: TransitionProperty,
+    pub duration: usize, // 持续时间， 单位： 毫秒
+    pub timing: TimingFunction,
+    pub delay: usize, // 延迟时间， 单位： 毫秒
+}
+
+// 关键帧动画， 对应css的animation， 可同时播放多个动画， 同一属性以后面的动画为准
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Animation(pub Vec<AnimationItem>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationItem {
+    pub name: Atom, // 关键帧名称， 对应样式表中的@keyframes
+    pub duration: usize, // 单次播放的持续时间， 单位： 毫秒
+    pub timing: TimingFunction,
+    pub delay: usize, // 延迟时间， 单位： 毫秒
+    pub iteration: f32, // 播放次数， std::f32::INFINITY表示无限循环
+    pub direction: AnimationDirection,
+    pub fill_mode: AnimationFillMode,
+    pub play_state: AnimationPlayState,
+}
+
 impl Default for Font {
     fn default() -> Self {
         Self {
@@ -293,7 +464,7 @@ pub enum Color {
     // RGB(CgColor),
     RGBA(CgColor),
     LinearGradient(LinearGradientColor),
-    // RadialGradient(RadialGradientColor),
+    RadialGradient(RadialGradientColor),
 }
 
 impl Color {
@@ -308,14 +479,15 @@ impl Color {
                     }
                 }
                 true
-            } // Color::RadialGradient(g) => {
-              //     for c in g.list.iter() {
-              //         if c.rgba.a < 1.0 {
-              //             return false
-              //         }
-              //     }
-              //     return true;
-              // }
+            }
+            Color::RadialGradient(g) => {
+                for c in g.list.iter() {
+                    if c.rgba.a < 1.0 {
+                        return false;
+                    }
+                }
+                true
+            }
         }
     }
 }
@@ -347,6 +519,22 @@ pub struct RadialGradientColor {
     pub list: Vec<ColorAndPosition>,
 }
 
+impl Hash for RadialGradientColor {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        NotNan::new(self.center.0).unwrap().hash(hasher);
+        NotNan::new(self.center.1).unwrap().hash(hasher);
+        (self.shape as u8).hash(hasher);
+        (self.size as u8).hash(hasher);
+        for l in self.list.iter() {
+            NotNan::new(l.position).unwrap().hash(hasher);
+            NotNan::new(l.rgba.r).unwrap().hash(hasher);
+            NotNan::new(l.rgba.g).unwrap().hash(hasher);
+            NotNan::new(l.rgba.b).unwrap().hash(hasher);
+            NotNan::new(l.rgba.a).unwrap().hash(hasher);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorAndPosition {
     pub position: f32,
@@ -413,8 +601,17 @@ pub fn to_radial_gradient_color(
     }
     RadialGradientColor {
         center: (center_x, center_y),
-        shape: unsafe { transmute(shape) },
-        size: unsafe { transmute(size) },
+        // 不能识别的值使用css的默认值（ellipse farthest-corner）
+        shape: match shape {
+            1 => RadialGradientShape::Circle,
+            _ => RadialGradientShape::Ellipse,
+        },
+        size: match size {
+            0 => RadialGradientSize::ClosestSide,
+            1 => RadialGradientSize::FarthesSide,
+            2 => RadialGradientSize::ClosestCorner,
+            _ => RadialGradientSize::Farthescorner,
+        },
         list: list,
     }
 }
@@ -590,12 +787,60 @@ impl Transform {
 }
 
 //对齐元素中的文本
-#[derive(Debug, Clone, Copy, EnumDefault, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum TextAlign {
-    Left,    //把文本排列到左边。默认值：由浏览器决定。
+    Left,    //把文本排列到左边。
     Right,   //把文本排列到右边。
     Center,  //把文本排列到中间。
     Justify, //实现两端对齐文本效果。
+    Start,   //排列到行的起始边， 从左向右时为左边， 从右向左时为右边。默认值。
+    End,     //排列到行的结束边。
+}
+
+impl Default for TextAlign {
+    fn default() -> Self {
+        TextAlign::Start
+    }
+}
+
+//文字溢出(超出行数限制或内容区宽度)时的处理方式
+#[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
+pub enum TextOverflow {
+    Clip,           //直接裁剪。默认值。
+    Ellipsis,       //在最后一个可见行的末尾显示省略号(…)。
+    Custom(String), //在最后一个可见行的末尾显示指定的字符串。
+}
+
+impl Default for TextOverflow {
+    fn default() -> Self {
+        TextOverflow::Clip
+    }
+}
+
+// 文字装饰线
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TextDecoration {
+    pub line: usize,                 // 装饰线的种类， TextDecorationLine值的组合， 0表示没有装饰线
+    pub color: Option<CgColor>,      // 装饰线的颜色， None表示使用文字颜色
+    pub style: TextDecorationStyle,  // 装饰线的样式
+    pub thickness: f32,              // 装饰线的粗细， 单位：像素， 0表示使用字体建议的粗细
+}
+
+// 装饰线的种类
+#[derive(Debug, Clone, Copy)]
+pub enum TextDecorationLine {
+    Underline = 1,
+    Overline = 2,
+    LineThrough = 4,
+}
+
+// 装饰线的样式
+#[derive(Debug, Clone, Copy, PartialEq, EnumDefault, Serialize, Deserialize)]
+pub enum TextDecorationStyle {
+    Solid,  //实线。默认值。
+    Dashed, //虚线。
+    Dotted, //点线。
+    Wavy,   //波浪线。
 }
 
 //设置元素中空白的处理方式
@@ -604,19 +849,15 @@ pub enum WhiteSpace {
     Normal,  //	默认。空白会被浏览器忽略(其实是所有的空白被合并成一个空格), 超出范围会换行。
     Nowrap, //	空白会被浏览器忽略(其实是所有的空白被合并成一个空格), 超出范围文本也不会换行，文本会在在同一行上继续，直到遇到 <br> 标签为止。
     PreWrap, //	保留所有空白符序列，超出范围会换行。
-    Pre,    //	保留空白符，超出范围不会换行(利用yoga无法支持， 暂不支持)
+    Pre,    //	保留空白符，超出范围不会换行
     PreLine, //	合并空白符序列，如果存在换行符，优先保留换行符， 超出范围会换行。
 }
 
 impl WhiteSpace {
     pub fn allow_wrap(&self) -> bool {
-        // match *self {
-        //     WhiteSpace::Nowrap | WhiteSpace::Pre => false,
-        //     WhiteSpace::Normal | WhiteSpace::PreWrap | WhiteSpace::PreLine => true,
-		// }
-		match *self {
-            WhiteSpace::Nowrap => false,
-            _ => true,
+        match *self {
+            WhiteSpace::Nowrap | WhiteSpace::Pre => false,
+            WhiteSpace::Normal | WhiteSpace::PreWrap | WhiteSpace::PreLine => true,
         }
     }
 
@@ -648,6 +889,55 @@ pub enum VerticalAlign {
     Bottom,
 }
 
+// 可过渡的属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransitionProperty {
+    All,
+    Opacity,
+    BackgroundColor,
+    BorderColor,
+    Color,
+    Transform,
+    Width,
+    Height,
+    Filter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumDefault, Serialize, Deserialize)]
+pub enum AnimationDirection {
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumDefault, Serialize, Deserialize)]
+pub enum AnimationFillMode {
+    None,
+    Forwards,  // 结束后保持最后一帧
+    Backwards, // 延迟期间使用第一帧
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumDefault, Serialize, Deserialize)]
+pub enum AnimationPlayState {
+    Running,
+    Paused,
+}
+
+// 缓动函数
+#[derive(Debug, Clone, Copy, EnumDefault, Serialize, Deserialize)]
+pub enum TimingFunction {
+    Ease,
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    StepStart,
+    StepEnd,
+    CubicBezier(f32, f32, f32, f32),
+}
+
 impl Default for Opacity {
     fn default() -> Opacity {
         Opacity(1.0)
@@ -694,6 +984,69 @@ impl Show {
     }
 }
 
+impl Transition {
+    // 取得某属性对应的过渡项， 后设置的项覆盖前面的项
+    pub fn get(&self, property: TransitionProperty) -> Option<&TransitionItem> {
+        self.0.iter().rev().find(|item| {
+            item.property == property || item.property == TransitionProperty::All
+        })
+    }
+}
+
+impl TimingFunction {
+    // 根据时间进度（0.0 ~ 1.0）计算属性进度
+    pub fn value(&self, t: f32) -> f32 {
+        match *self {
+            TimingFunction::Linear => t,
+            TimingFunction::Ease => cubic_bezier(0.25, 0.1, 0.25, 1.0, t),
+            TimingFunction::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            TimingFunction::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            TimingFunction::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            TimingFunction::StepStart => 1.0,
+            TimingFunction::StepEnd => if t < 1.0 { 0.0 } else { 1.0 },
+            TimingFunction::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+// 三次贝塞尔曲线， 先由x求出曲线参数（牛顿迭代， 不收敛时用二分）， 再求y
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    if x <= 0.0 {
+        return 0.0;
+    } else if x >= 1.0 {
+        return 1.0;
+    }
+    let (cx, cy) = (3.0 * x1, 3.0 * y1);
+    let (bx, by) = (3.0 * (x2 - x1) - cx, 3.0 * (y2 - y1) - cy);
+    let (ax, ay) = (1.0 - cx - bx, 1.0 - cy - by);
+    let sample_x = |t: f32| ((ax * t + bx) * t + cx) * t;
+
+    let mut t = x;
+    for _ in 0..8 {
+        let d = sample_x(t) - x;
+        if d.abs() < 1e-5 {
+            return ((ay * t + by) * t + cy) * t;
+        }
+        let dx = (3.0 * ax * t + 2.0 * bx) * t + cx;
+        if dx.abs() < 1e-6 {
+            break;
+        }
+        t -= d / dx;
+    }
+
+    let (mut lo, mut hi) = (0.0, 1.0);
+    t = x;
+    while hi - lo > 1e-5 {
+        if sample_x(t) < x {
+            lo = t;
+        } else {
+            hi = t;
+        }
+        t = (lo + hi) / 2.0;
+    }
+    ((ay * t + by) * t + cy) * t
+}
+
 impl Default for Show {
     fn default() -> Show {
         Show(ShowType::Visibility as usize)
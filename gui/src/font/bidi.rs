@@ -0,0 +1,263 @@
+/// 双向文字(UAX #9)
+/// 计算每个字符的嵌入层级， 奇数层级从右向左排列， 并按层级将一行中的字符重排为显示顺序
+/// 不支持显式的嵌入及隔离控制符(LRE、 RLI等)， 这些字符被忽略
+/// 双向类别及镜像字符取自ucd
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BidiClass {
+    L,   // 从左向右的字母
+    R,   // 从右向左的字母(希伯来文等)
+    AL,  // 阿拉伯字母
+    EN,  // 欧洲数字
+    ES,  // 数字分隔符(+ -)
+    ET,  // 数字后缀(% $等)
+    AN,  // 阿拉伯数字
+    CS,  // 通用的数字分隔符(, . : /)
+    NSM, // 组合字符
+    BN,  // 可忽略的字符
+    B,   // 段落分隔符
+    S,   // 段内分隔符(制表符)
+    WS,  // 空白
+    ON,  // 其它中性字符
+}
+
+use self::BidiClass::*;
+use ucd::{self, Codepoint};
+
+/// 字符的双向类别
+pub fn bidi_class(c: char) -> BidiClass {
+    match c.bidi_class() {
+        ucd::BidiClass::LeftToRight => L,
+        ucd::BidiClass::RightToLeft => R,
+        ucd::BidiClass::ArabicLetter => AL,
+        ucd::BidiClass::EuropeanNumber => EN,
+        ucd::BidiClass::EuropeanSeparator => ES,
+        ucd::BidiClass::EuropeanTerminator => ET,
+        ucd::BidiClass::ArabicNumber => AN,
+        ucd::BidiClass::CommonSeparator => CS,
+        ucd::BidiClass::NonspacingMark => NSM,
+        ucd::BidiClass::BoundaryNeutral => BN,
+        ucd::BidiClass::ParagraphSeparator => B,
+        ucd::BidiClass::SegmentSeparator => S,
+        ucd::BidiClass::WhiteSpace => WS,
+        ucd::BidiClass::OtherNeutral => ON,
+        // 显式的嵌入及隔离控制符不支持， 按BN忽略
+        ucd::BidiClass::LeftToRightEmbedding
+        | ucd::BidiClass::LeftToRightOverride
+        | ucd::BidiClass::RightToLeftEmbedding
+        | ucd::BidiClass::RightToLeftOverride
+        | ucd::BidiClass::PopDirectionalFormat
+        | ucd::BidiClass::LeftToRightIsolate
+        | ucd::BidiClass::RightToLeftIsolate
+        | ucd::BidiClass::FirstStrongIsolate
+        | ucd::BidiClass::PopDirectionalIsolate => BN,
+    }
+}
+
+/// 字符的镜像字符， 不存在返回None
+pub fn mirror(c: char) -> Option<char> {
+    c.bidi_mirror()
+}
+
+/// 计算每个字符的嵌入层级， rtl表示段落的基础方向为从右向左
+/// classes为字符的双向类别， 段落分隔符(B)之后开始新的段落， 所有段落使用相同的基础方向
+pub fn levels(classes: &[BidiClass], rtl: bool) -> Vec<u8> {
+    let base: u8 = if rtl { 1 } else { 0 };
+    let mut r = vec![base; classes.len()];
+    let mut start = 0;
+    while start < classes.len() {
+        let end = match classes[start..].iter().position(|c| *c == B) {
+            Some(i) => start + i + 1,
+            None => classes.len(),
+        };
+        resolve_paragraph(&classes[start..end], base, &mut r[start..end]);
+        start = end;
+    }
+    r
+}
+
+// 解析一个段落的层级(W1 - W7、 N1 - N2、 I1 - I2、 L1)
+fn resolve_paragraph(classes: &[BidiClass], base: u8, levels: &mut [u8]) {
+    let e = if base % 2 == 0 { L } else { R };
+    // 忽略BN(X9)， 在剩余的字符上解析
+    let index: Vec<usize> = (0..classes.len()).filter(|i| classes[*i] != BN).collect();
+    let mut t: Vec<BidiClass> = index.iter().map(|i| classes[*i]).collect();
+
+    // W1 组合字符使用前一个字符的类别
+    let mut prev = e;
+    for c in t.iter_mut() {
+        if *c == NSM {
+            *c = prev;
+        }
+        prev = *c;
+    }
+    // W2 W3 W7 根据前一个强类型字符修改数字及阿拉伯字母
+    let mut strong = e;
+    for c in t.iter_mut() {
+        match *c {
+            L | R => strong = *c,
+            AL => {
+                strong = AL;
+                *c = R;
+            }
+            EN if strong == AL => *c = AN,
+            _ => (),
+        }
+    }
+    // W4 数字之间的单个分隔符
+    for i in 1..t.len().max(1) - 1 {
+        let (a, b) = (t[i - 1], t[i + 1]);
+        match t[i] {
+            ES if a == EN && b == EN => t[i] = EN,
+            CS if a == EN && b == EN => t[i] = EN,
+            CS if a == AN && b == AN => t[i] = AN,
+            _ => (),
+        }
+    }
+    // W5 与欧洲数字相邻的数字后缀
+    let mut i = 0;
+    while i < t.len() {
+        if t[i] != ET {
+            i += 1;
+            continue;
+        }
+        let s = i;
+        while i < t.len() && t[i] == ET {
+            i += 1;
+        }
+        if (s > 0 && t[s - 1] == EN) || (i < t.len() && t[i] == EN) {
+            for c in t[s..i].iter_mut() {
+                *c = EN;
+            }
+        }
+    }
+    // W6 其余的分隔符按中性字符处理
+    for c in t.iter_mut() {
+        match *c {
+            ES | ET | CS => *c = ON,
+            _ => (),
+        }
+    }
+    // W7
+    let mut strong = e;
+    for c in t.iter_mut() {
+        match *c {
+            L | R => strong = *c,
+            EN if strong == L => *c = L,
+            _ => (),
+        }
+    }
+    // N1 N2 中性字符两侧的方向相同， 使用该方向， 否则使用段落的方向
+    let mut i = 0;
+    while i < t.len() {
+        if !is_neutral(t[i]) {
+            i += 1;
+            continue;
+        }
+        let s = i;
+        while i < t.len() && is_neutral(t[i]) {
+            i += 1;
+        }
+        let before = if s == 0 { e } else { strong_dir(t[s - 1]) };
+        let after = if i == t.len() { e } else { strong_dir(t[i]) };
+        let dir = if before == after { before } else { e };
+        for c in t[s..i].iter_mut() {
+            *c = dir;
+        }
+    }
+    // I1 I2
+    for (k, c) in t.iter().enumerate() {
+        let add = match (base % 2, *c) {
+            (0, R) => 1,
+            (0, AN) | (0, EN) => 2,
+            (1, L) | (1, EN) | (1, AN) => 1,
+            _ => 0,
+        };
+        levels[index[k]] = base + add;
+    }
+    // 被忽略的字符使用前一个字符的层级
+    for i in 0..classes.len() {
+        if classes[i] == BN {
+            levels[i] = if i > 0 { levels[i - 1] } else { base };
+        }
+    }
+    // L1 分隔符及其前面的空白使用段落的层级
+    let mut tail = true;
+    for i in (0..classes.len()).rev() {
+        match classes[i] {
+            B | S => {
+                levels[i] = base;
+                tail = true;
+            }
+            WS | BN if tail => levels[i] = base,
+            _ => tail = false,
+        }
+    }
+}
+
+#[inline]
+fn is_neutral(c: BidiClass) -> bool {
+    match c {
+        B | S | WS | ON => true,
+        _ => false,
+    }
+}
+
+// 数字按从右向左处理(N1)
+#[inline]
+fn strong_dir(c: BidiClass) -> BidiClass {
+    match c {
+        L => L,
+        _ => R,
+    }
+}
+
+/// 将一行的逻辑顺序转换为显示顺序(L2)， 返回按显示顺序排列的索引
+/// 从最高层级到最低的奇数层级， 依次反转不低于该层级的连续序列
+pub fn reorder(levels: &[u8]) -> Vec<usize> {
+    let mut r: Vec<usize> = (0..levels.len()).collect();
+    let max = levels.iter().cloned().max().unwrap_or(0);
+    let min_odd = match levels.iter().cloned().filter(|l| l % 2 == 1).min() {
+        Some(l) => l,
+        None => return r,
+    };
+    let mut level = max;
+    while level >= min_odd {
+        let mut i = 0;
+        while i < r.len() {
+            if levels[r[i]] < level {
+                i += 1;
+                continue;
+            }
+            let s = i;
+            while i < r.len() && levels[r[i]] >= level {
+                i += 1;
+            }
+            r[s..i].reverse();
+        }
+        level -= 1;
+    }
+    r
+}
+
+#[test]
+fn test_bidi() {
+    fn visual(s: &str, rtl: bool) -> String {
+        let chars: Vec<char> = s.chars().collect();
+        let classes: Vec<BidiClass> = chars.iter().map(|c| bidi_class(*c)).collect();
+        let levels = levels(&classes, rtl);
+        reorder(&levels)
+            .into_iter()
+            .map(|i| if levels[i] % 2 == 1 { mirror(chars[i]).unwrap_or(chars[i]) } else { chars[i] })
+            .collect()
+    }
+    // 从左向右的段落中嵌入希伯来文
+    assert_eq!(visual("abc אבג def", false), "abc גבא def");
+    // 从右向左的段落， 数字保持从左向右， 括号镜像
+    assert_eq!(visual("אב (12) ג", true), "ג (12) בא");
+    assert_eq!(visual("אב 3.14%", true), "3.14% בא");
+    // 阿拉伯字母之后的数字为阿拉伯数字
+    assert_eq!(levels(&[AL, EN, EN], false), vec![1, 2, 2]);
+    // 行尾的空白使用段落层级
+    assert_eq!(levels(&[R, WS], false), vec![1, 0]);
+}
@@ -3,13 +3,14 @@ use data_view::GetView;
 use share::Share;
 use slab::Slab;
 /// 字体表， 管理字体的几何信息和图像信息
-use std::{collections::hash_map::Entry, default::Default, str::Chars};
-use ucd::Codepoint;
+use std::{collections::hash_map::Entry, collections::VecDeque, default::Default, vec::IntoIter};
 
 use render::res::TextureRes;
 
 use component::user::*;
 use font::font_tex::*;
+use font::line_break::{break_class, grapheme_end, is_ignorable, Break, BreakClass, LineBreaker};
+use font::native::NativeFont;
 use hash::XHashMap;
 
 // 默认字体尺寸
@@ -23,7 +24,11 @@ pub const INIT_TEX_HEIGHT: u32 = 256;
 const SMALL_FONT: usize = 20;
 
 // 默认sdf字体的大小， 用于作为基准
-const SDF_FONT_SIZE: f32 = 32.0;
+pub const SDF_FONT_SIZE: f32 = 32.0;
+
+// 动态生成sdf时， 距离场的范围(像素)， 字形四周也留出这么多空白
+// msdf着色器中描边的偏移为 stroke.width / 10.0, 范围为5时， 描边的宽度正好对应SDF_FONT_SIZE下的像素
+const SDF_RANGE: f32 = 5.0;
 
 // 粗体字的font-weight
 const BLOD_WEIGHT: usize = 700;
@@ -62,9 +67,14 @@ pub struct FontSheet {
             /* weight */ char,
         ),
         usize, /* slab id */
-    >, // key (font, stroke_width, char) // 永不回收
-    pub char_slab: Slab<(char, Glyph)>, // 永不回收 (char, Glyph, font_size, stroke_width) // 永不回收
+    >, // key (font, stroke_width, char)
+    pub char_slab: Slab<(char, Glyph)>, // (char, Glyph, font_size, stroke_width) 预生成的字形永不回收， 动态生成的字形在不被引用时可回收
+    glyph_uses: XHashMap<usize /* slab id */, GlyphUse>, // 动态生成的字形的引用信息
+    lru: VecDeque<(usize /* slab id */, usize /* tick */)>, // 引用计数为0的字形， 按释放的先后排列， tick不一致的项已失效
+    tick: usize,
+    page_ticks: Vec<usize>, // 每个纹理页最后一次被文字节点使用的时刻， 所有页都已满时回收最久未使用的页
     pub wait_draw_list: Vec<TextInfo>,
+    pub sdf_wait_list: Vec<GlyphImage>, // 动态生成的sdf字形， 等待更新到纹理
     pub wait_draw_map: XHashMap<
         (
             Atom,
@@ -75,6 +85,7 @@ pub struct FontSheet {
         (usize /* TextInfo_Index */, f32 /* v */),
     >,
     measure_char: Box<dyn Fn(&Atom, usize, char) -> f32>,
+    native_fonts: XHashMap<Atom, NativeFont>, // 原生字体， 直接解析字体文件测量和绘制， 不经过measure_char和canvas
 	pub font_tex: FontTex,
 	pub tex_version: usize,
 }
@@ -92,20 +103,101 @@ impl FontSheet {
             char_w_map: XHashMap::default(),
             char_map: XHashMap::default(),
             char_slab: Slab::default(),
+            glyph_uses: XHashMap::default(),
+            lru: VecDeque::new(),
+            tick: 0,
+            page_ticks: Vec::new(),
             wait_draw_list: Vec::new(),
+            sdf_wait_list: Vec::new(),
             wait_draw_map: XHashMap::default(),
             measure_char: measure,
+            native_fonts: XHashMap::default(),
 			font_tex: FontTex::new(texture),
 			tex_version: 0,
         }
     }
 
-	// 清空字形
+	// 清空动态生成的字形， 预生成的字形保留
 	pub fn clear_gylph(&mut self) {
 		self.wait_draw_list.clear();
+		self.sdf_wait_list.clear();
+		self.wait_draw_map.clear();
+		for id in self.glyph_uses.keys() {
+			self.char_slab.remove(*id);
+		}
+		let glyph_uses = &self.glyph_uses;
+		self.char_map.retain(|_, id| !glyph_uses.contains_key(id));
+		self.glyph_uses.clear();
+		self.lru.clear();
+		self.font_tex.clear();
+	}
+
+	// 增加字形的引用
+	pub fn retain_glyph(&mut self, id: usize) {
+		if let Some(r) = self.glyph_uses.get_mut(&id) {
+			r.count += 1;
+		}
+	}
+
+	// 减少字形的引用， 引用为0时放入lru队列， 纹理空间不足时回收
+	pub fn release_glyph(&mut self, id: usize) {
+		if let Some(r) = self.glyph_uses.get_mut(&id) {
+			if r.count == 0 {
+				return;
+			}
+			r.count -= 1;
+			if r.count == 0 {
+				self.tick += 1;
+				r.tick = self.tick;
+				self.lru.push_back((id, self.tick));
+			}
+		}
+	}
+
+	// 记录纹理页被使用
+	pub fn touch_page(&mut self, page: usize) {
+		if self.page_ticks.len() <= page {
+			self.page_ticks.resize(page + 1, 0);
+		}
+		self.tick += 1;
+		self.page_ticks[page] = self.tick;
+	}
+
+	// 最久未被使用的纹理页
+	pub fn lru_page(&self) -> Option<usize> {
+		(0..self.font_tex.pages.len()).min_by_key(|page| self.page_ticks.get(*page).cloned().unwrap_or(0))
+	}
+
+	// 回收一页上所有动态生成的字形（无论是否被引用）， 返回被回收的字形
+	// 引用了这些字形的文字节点需要重新计算字形
+	pub fn clear_page(&mut self, page: usize) -> Vec<usize> {
+		let mut r = Vec::new();
+		for (id, _) in self.glyph_uses.iter() {
+			if self.char_slab[*id].1.page == page {
+				r.push(*id);
+			}
+		}
+		for id in r.iter() {
+			let glyph_use = self.glyph_uses.remove(id).unwrap();
+			self.char_slab.remove(*id);
+			if self.char_map.get(&glyph_use.key) == Some(id) {
+				self.char_map.remove(&glyph_use.key);
+			}
+		}
+		// 等待绘制的字符已被回收， wait_draw_map索引wait_draw_list， 一起清空
+		self.wait_draw_list.retain(|info| info.page != page);
 		self.wait_draw_map.clear();
-		self.char_map.clear();
-		self.char_slab.clear();
+		self.sdf_wait_list.retain(|info| info.page != page);
+		self.font_tex.clear_page(page);
+		r
+	}
+
+	// 字形所在的纹理页
+	pub fn glyph_page(&self, id: usize) -> usize {
+		match self.char_slab.get(id) {
+			Some(r) => r.1.page,
+			None => 0,
+		}
 	}
 	
     pub fn mem_size(&self) -> usize {
@@ -118,6 +210,8 @@ impl FontSheet {
             + self.char_map.capacity()
                 * (std::mem::size_of::<(Atom, usize, usize, char)>() + std::mem::size_of::<usize>())
             + self.char_slab.mem_size()
+            + self.glyph_uses.capacity() * (std::mem::size_of::<usize>() + std::mem::size_of::<GlyphUse>())
+            + self.lru.capacity() * std::mem::size_of::<(usize, usize)>()
             + self.wait_draw_list.capacity() * std::mem::size_of::<TextInfo>()
             + self.wait_draw_map.capacity()
                 * (std::mem::size_of::<(Atom, usize, usize, usize)>()
@@ -141,6 +235,32 @@ impl FontSheet {
         });
     }
 
+    // 添加原生字体(TTF/OTF文件)， 字体的纵向度量取自字体文件， 不需要手工设置修正系数
+    // is_pixel为false时， 字形按需生成sdf， 可以在任意缩放下清晰渲染
+    pub fn add_native_font(&mut self, name: Atom, data: Vec<u8>, is_pixel: bool) -> Result<(), String> {
+        let font = NativeFont::new(data)?;
+        // 字形绘制在字号的顶部对齐的位置， 行高为字体的上下边界之和
+        self.src_map.insert(name.clone(), TexFont {
+            name: name.clone(),
+            is_pixel: is_pixel,
+            factor_t: 0.0,
+            factor_b: font.ascent() + font.descent() - 1.0,
+        });
+        // 同名字体之前测量的宽度已失效
+        self.char_w_map.retain(|k, _| k.0 != name);
+        self.native_fonts.insert(name, font);
+        Ok(())
+    }
+
+    pub fn get_native_font(&self, name: &Atom) -> Option<&NativeFont> {
+        self.native_fonts.get(name)
+    }
+
+    // 字体是否按需生成sdf字形(SDF_FONT_SIZE大小)， 预生成的sdf纹理字体没有字形轮廓， 字形大小与SDF_FONT_SIZE无关， 仍按像素字体渲染
+    pub fn is_sdf(&self, font: &TexFont) -> bool {
+        !font.is_pixel && self.native_fonts.contains_key(&font.name)
+    }
+
     pub fn get_src(&mut self, name: &Atom) -> Option<&TexFont> {
         self.src_map.get(name)
     }
@@ -161,6 +281,24 @@ impl FontSheet {
         None
     }
 
+    // 文字装饰线的度量， 原生字体取自字体文件， 其它字体按常见字体的比例估算
+    pub fn decoration_metrics(&self, font: &TexFont, font_size: f32, stroke_width: f32) -> DecorationMetrics {
+        // 上划线位于上边界， 像素纹理字体的上边界在修正系数之下
+        let top = stroke_width / 2.0;
+        let (top, ascent, underline, strikeout) = match self.native_fonts.get(&font.name) {
+            Some(r) => (top, r.ascent(), r.underline(), r.strikeout()),
+            None => (top + font.factor_t * font_size, 0.8, (-0.1, 0.05), (0.3, 0.05)),
+        };
+        let baseline = top + ascent * font_size;
+        DecorationMetrics {
+            baseline: baseline,
+            underline: baseline - underline.0 * font_size,
+            overline: top,
+            line_through: baseline - strikeout.0 * font_size + strikeout.1 * font_size / 2.0,
+            thickness: (underline.1 * font_size).max(1.0),
+        }
+    }
+
     // 设置FontFace
     pub fn set_face(
         &mut self,
@@ -217,7 +355,10 @@ impl FontSheet {
                 );
             }
             Entry::Vacant(r) => {
-                let mut w = self.measure_char.as_ref()(&font.name, FONT_SIZE as usize, c);
+                let mut w = match self.native_fonts.get(&font.name) {
+                    Some(native) => native.advance(c, FONT_SIZE),
+                    None => self.measure_char.as_ref()(&font.name, FONT_SIZE as usize, c),
+                };
                 if w > 0.0 {
                     if is_blod {
                         w = w * BLOD_FACTOR;
@@ -230,7 +371,8 @@ impl FontSheet {
         (0.0, 0.0)
     }
 
-    // 添加一个字形信息,
+    // 添加一个字形信息， page为None时字形可以在任意页上， 否则字形必须在指定的页上
+    // 新分配的字形引用计数为0， 调用者应该立即retain_glyph
     pub fn calc_gylph(
         &mut self,
         font: &TexFont,
@@ -240,6 +382,7 @@ impl FontSheet {
         scale: f32,
         base_width: f32,
         c: char,
+        page: Option<usize>,
     ) -> usize {
         if font.is_pixel {
             // 像素纹理
@@ -262,110 +405,292 @@ impl FontSheet {
                 0
             };
             // 根据缩放后的字体及勾边大小来查找Glyth, 返回的w需要除以scale
-            let id = match self
-                .char_map
-                .entry((font.name.clone(), fs_scale, sw, weight, c))
-            {
-                Entry::Occupied(e) => *e.get(),
-                Entry::Vacant(r) => {
-                    // 在指定字体及字号下，查找该字符的宽度
-                    let w = (base_width as f32 * font_size as f32 / FONT_SIZE + stroke_width as f32) * scale;
-                    // 将缩放后的实际字号乘字体的修正系数，得到实际能容纳下的行高
-					let height = (font_size as f32 * (font.factor_t + font.factor_b + 1.0) + stroke_width as f32) * scale;
-					
-					let ww = w.ceil();
-					let hh = height.ceil();
-						
-                    let mut line = self.font_tex.alloc_line(hh as usize);
-                    let p = line.alloc(ww);
-
-					// 超出最大纹理范围，需要清空所有文字，重新布局
-					if *(line.last_v) > line.tex_width {
-						return 0; // 0表示异常情况，不能计算字形
-					}
-
-                    let id = self.char_slab.insert((
-                        c,
-                        Glyph {
-                            x: p.x,
-                            y: p.y,
-                            ox: 0.0,
-                            oy: 0.0,
-                            width: w,
-                            height: height as f32,
-                            advance: w,
-                        },
-                    ));
-                    // 将需要渲染的字符放入等待队列
-                    match self
-                        .wait_draw_map
-                        .entry((font.name.clone(), fs_scale, sw, weight))
-                    {
-                        Entry::Occupied(mut e) => {
-                            let mut r = *e.get_mut();
-                            if r.1 == p.y {
-                                let info = &mut self.wait_draw_list[r.0];
-                                info.chars.push(WaitChar {
-                                    ch: c,
-                                    width: ww,
-                                    x: p.x as u32,
-                                    y: p.y as u32,
-                                });
-                                info.size.x += ww;
-                            } else {
-                                r.0 = self.wait_draw_list.len();
-                                r.1 = p.y;
-                                self.wait_draw_list.push(TextInfo {
-                                    font: font.name.clone(),
-                                    font_size: fs_scale,
-                                    stroke_width: sw,
-									weight: weight,
-									top: (fs_scale as f32 * font.factor_t) as usize,
-                                    size: Vector2::new(ww, hh as f32),
-                                    chars: vec![WaitChar {
-                                        ch: c,
-                                        width: ww,
-                                        x: p.x as u32,
-                                        y: p.y as u32,
-                                    }],
-                                });
-                            }
-                        }
-                        Entry::Vacant(r) => {
-                            r.insert((self.wait_draw_list.len(), p.y));
-                            self.wait_draw_list.push(TextInfo {
-                                font: font.name.clone(),
-								font_size: fs_scale,
-								top: (fs_scale as f32 * font.factor_t) as usize,
-                                stroke_width: sw,
-                                weight: weight,
-                                size: Vector2::new(ww, hh),
-                                chars: vec![WaitChar {
-                                    ch: c,
-                                    width: ww,
-                                    x: p.x as u32,
-                                    y: p.y as u32,
-                                }],
-                            });
-                        }
+            let key = (font.name.clone(), fs_scale, sw, weight, c);
+            if let Some(id) = self.char_map.get(&key) {
+                if page.map_or(true, |p| self.char_slab[*id].1.page == p) {
+                    return *id;
+                }
+            }
+            // 在指定字体及字号下，查找该字符的宽度
+            let w = (base_width as f32 * font_size as f32 / FONT_SIZE + stroke_width as f32) * scale;
+            // 将缩放后的实际字号乘字体的修正系数，得到实际能容纳下的行高
+            let height = (font_size as f32 * (font.factor_t + font.factor_b + 1.0) + stroke_width as f32) * scale;
+
+            let ww = w.ceil();
+            let hh = height.ceil();
+
+            // 所有纹理页都已满，需要清空所有文字，重新布局
+            let (page, p) = match self.alloc_glyph(page, ww, hh) {
+                Some(r) => r,
+                None => return 0, // 0表示异常情况，不能计算字形
+            };
+
+            let id = self.char_slab.insert((
+                c,
+                Glyph {
+                    x: p.x,
+                    y: p.y,
+                    ox: 0.0,
+                    oy: 0.0,
+                    width: w,
+                    height: height as f32,
+                    advance: w,
+                    page: page,
+                },
+            ));
+            // 将需要渲染的字符放入等待队列， 同一个TextInfo中的字符在纹理中必须连续
+            let wait_char = WaitChar {
+                ch: c,
+                width: ww,
+                x: p.x as u32,
+                y: p.y as u32,
+            };
+            let index = match self.wait_draw_map.get(&(font.name.clone(), fs_scale, sw, weight)) {
+                Some(r) => {
+                    let info = &self.wait_draw_list[r.0];
+                    let last = &info.chars[info.chars.len() - 1];
+                    if info.page == page && last.y == wait_char.y && last.x as f32 + last.width == p.x {
+                        Some(r.0)
+                    } else {
+                        None
                     }
-                    r.insert(id);
-                    id
                 }
+                None => None,
             };
+            match index {
+                Some(index) => {
+                    let info = &mut self.wait_draw_list[index];
+                    info.chars.push(wait_char);
+                    info.size.x += ww;
+                }
+                None => {
+                    self.wait_draw_map.insert(
+                        (font.name.clone(), fs_scale, sw, weight),
+                        (self.wait_draw_list.len(), p.y),
+                    );
+                    self.wait_draw_list.push(TextInfo {
+                        font: font.name.clone(),
+                        font_size: fs_scale,
+                        stroke_width: sw,
+                        weight: weight,
+                        top: (fs_scale as f32 * font.factor_t) as usize,
+                        size: Vector2::new(ww, hh),
+                        chars: vec![wait_char],
+                        page: page,
+                    });
+                }
+            }
+            self.char_map.insert(key.clone(), id);
+            self.add_glyph_use(id, key, ww);
             return id;
         } else {
             // SDF 字体， 根据字形Glyph计算宽度
             match self.char_map.get(&(font.name.clone(), 0, 0, 0, c)) {
-                Some(id) => return *id,
+                Some(id) => {
+                    // 预生成的字形总在第0页
+                    if page.map_or(true, |p| self.char_slab[*id].1.page == p || !self.glyph_uses.contains_key(id)) {
+                        return *id;
+                    }
+                }
                 _ => (),
             }
+            // 预生成的字体纹理中不存在该字符， 使用原生字体动态生成sdf
+            return self.calc_sdf_gylph(font, c, page);
+        }
+    }
+
+    // 根据字形轮廓生成SDF_FONT_SIZE大小的sdf字形， 与字号、描边、缩放无关， 所有情况共用一个字形
+    fn calc_sdf_gylph(&mut self, font: &TexFont, c: char, page: Option<usize>) -> usize {
+        let (advance, w, h) = match self.native_fonts.get(&font.name) {
+            Some(native) => {
+                let advance = native.advance(c, SDF_FONT_SIZE);
+                (
+                    advance,
+                    (advance + 2.0 * SDF_RANGE).ceil(),
+                    ((native.ascent() + native.descent()) * SDF_FONT_SIZE + 2.0 * SDF_RANGE).ceil(),
+                )
+            }
+            None => return 0,
+        };
+
+        // 所有纹理页都已满，需要清空所有文字，重新布局
+        let (page, p) = match self.alloc_glyph(page, w, h) {
+            Some(r) => r,
+            None => return 0,
+        };
+
+        let data = {
+            let native = &self.native_fonts[&font.name];
+            native.draw_sdf(
+                c,
+                SDF_FONT_SIZE,
+                SDF_RANGE,
+                SDF_RANGE,
+                SDF_RANGE + native.ascent() * SDF_FONT_SIZE,
+                w as usize,
+                h as usize,
+            )
+        };
+        self.sdf_wait_list.push(GlyphImage {
+            x: p.x as u32,
+            y: p.y as u32,
+            width: w as u32,
+            height: h as u32,
+            data: data,
+            page: page,
+        });
+        // 字形左侧留出的空白需要向左偏移， 上下的空白由居中抵消
+        let id = self.char_slab.insert((
+            c,
+            Glyph {
+                x: p.x,
+                y: p.y,
+                ox: -SDF_RANGE,
+                oy: 0.0,
+                width: w,
+                height: h,
+                advance: advance,
+                page: page,
+            },
+        ));
+        let key = (font.name.clone(), 0, 0, 0, c);
+        self.char_map.insert(key.clone(), id);
+        self.add_glyph_use(id, key, w);
+        id
+    }
+
+    fn add_glyph_use(&mut self, id: usize, key: (Atom, usize, usize, usize, char), width: f32) {
+        self.glyph_uses.insert(
+            id,
+            GlyphUse {
+                key: key,
+                width: width,
+                count: 0,
+                tick: 0,
+            },
+        );
+    }
+
+    // 为字形分配纹理区域， 返回所在的页及位置
+    // 空间不足时， 先回收最久未使用的字形， 仍然不足， 则添加新的纹理页
+    fn alloc_glyph(&mut self, page: Option<usize>, width: f32, height: f32) -> Option<(usize, Point2)> {
+        match page {
+            Some(page) => loop {
+                if let Some(p) = self.font_tex.alloc(page, width, height) {
+                    return Some((page, p));
+                }
+                if self.evict_glyph(Some(page)).is_none() {
+                    return None;
+                }
+            },
+            None => {
+                for page in 0..self.font_tex.pages.len() {
+                    if let Some(p) = self.font_tex.alloc(page, width, height) {
+                        return Some((page, p));
+                    }
+                }
+                while let Some(page) = self.evict_glyph(None) {
+                    if let Some(p) = self.font_tex.alloc(page, width, height) {
+                        return Some((page, p));
+                    }
+                }
+                match self.font_tex.add_page() {
+                    Some(page) => self.font_tex.alloc(page, width, height).map(|p| (page, p)),
+                    None => None,
+                }
+            }
+        }
+    }
+
+    // 回收一个最久未使用的字形， 返回被回收字形所在的页
+    // 还未绘制到纹理的字形不回收， 避免与后分配的字形同时绘制到同一区域
+    fn evict_glyph(&mut self, page: Option<usize>) -> Option<usize> {
+        let mut n = self.lru.len();
+        while n > 0 {
+            n -= 1;
+            let (id, tick) = self.lru.pop_front().unwrap();
+            let (glyph_page, x, y, width) = match self.glyph_uses.get(&id) {
+                Some(r) if r.count == 0 && r.tick == tick => {
+                    let glyph = &self.char_slab[id].1;
+                    (glyph.page, glyph.x, glyph.y, r.width)
+                }
+                _ => continue,
+            };
+            if page.map_or(false, |p| p != glyph_page) || self.is_wait_draw(glyph_page, x as u32, y as u32) {
+                self.lru.push_back((id, tick));
+                continue;
+            }
+            let r = self.glyph_uses.remove(&id).unwrap();
+            self.char_slab.remove(id);
+            // 字符可能已在其它页上重新生成， 此时不能删除映射
+            if self.char_map.get(&r.key) == Some(&id) {
+                self.char_map.remove(&r.key);
+            }
+            self.font_tex.free(glyph_page, x, y, width);
+            return Some(glyph_page);
+        }
+        None
+    }
+
+    fn is_wait_draw(&self, page: usize, x: u32, y: u32) -> bool {
+        self.sdf_wait_list.iter().any(|r| r.page == page && r.x == x && r.y == y)
+            || self.wait_draw_list.iter().any(|info| {
+                info.page == page && info.chars.iter().any(|r| r.x == x && r.y == y)
+            })
+    }
+
+    // 使用原生字体绘制等待队列中的字符， 返回需要更新到字体纹理的图像
+    // 非原生字体的字符仍留在等待队列中， 由外部(如canvas)绘制
+    // 动态生成的sdf字形也一并返回
+    pub fn draw_native_text(&mut self) -> Vec<GlyphImage> {
+        let mut images = std::mem::replace(&mut self.sdf_wait_list, Vec::new());
+        if self.native_fonts.len() == 0 || self.wait_draw_list.len() == 0 {
+            return images;
+        }
+        let list = std::mem::replace(&mut self.wait_draw_list, Vec::new());
+        self.wait_draw_map.clear();
+        for info in list.into_iter() {
+            match self.native_fonts.get(&info.font) {
+                Some(font) => {
+                    let height = info.size.y as usize;
+                    for c in info.chars.iter() {
+                        let width = c.width as usize;
+                        images.push(GlyphImage {
+                            x: c.x,
+                            y: c.y,
+                            width: width as u32,
+                            height: height as u32,
+                            page: info.page,
+                            data: font.draw_char(
+                                c.ch,
+                                info.font_size as f32,
+                                info.stroke_width as f32,
+                                info.weight >= BLOD_WEIGHT,
+                                width,
+                                height,
+                            ),
+                        });
+                    }
+                }
+                None => {
+                    self.wait_draw_map.insert(
+                        (info.font.clone(), info.font_size, info.stroke_width, info.weight),
+                        (self.wait_draw_list.len(), info.chars[0].y as f32),
+                    );
+                    self.wait_draw_list.push(info);
+                }
+            }
         }
-        0
+        images
     }
 
     pub fn get_font_tex(&self) -> &Share<TextureRes> {
-        &self.font_tex.texture
+        self.font_tex.texture(0).unwrap()
+    }
+
+    // 取指定页的字体纹理， 新增的页在纹理创建之前返回None
+    pub fn get_page_tex(&self, page: usize) -> Option<&Share<TextureRes>> {
+        self.font_tex.texture(page)
     }
 
     pub fn get_glyph(&self, id: usize) -> Option<&(char, Glyph)> {
@@ -425,6 +750,16 @@ impl TexFont {
     }
 }
 
+// 文字装饰线的度量， 单位：像素， 位置均相对于字体框(高度见TexFont::get_font_height)的顶部
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DecorationMetrics {
+    pub baseline: f32,
+    pub underline: f32,    // 下划线的顶部
+    pub overline: f32,     // 上划线的顶部
+    pub line_through: f32, // 删除线的中线
+    pub thickness: f32,    // 字体建议的粗细
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Glyph {
     pub x: f32,
@@ -434,6 +769,7 @@ pub struct Glyph {
     pub width: f32,
     pub height: f32,
     pub advance: f32,
+    pub page: usize, // 所在的字体纹理页
 }
 
 impl Glyph {
@@ -471,6 +807,7 @@ impl Glyph {
             width: width as f32,
             height: height as f32,
             advance: advance as f32,
+            page: 0,
         }
     }
 }
@@ -484,6 +821,27 @@ pub struct TextInfo {
     pub size: Vector2,
 	pub chars: Vec<WaitChar>,
 	pub top: usize,
+	pub page: usize,
+}
+
+// 原生字体绘制的字符图像(RGBA)， 及其在字体纹理中的位置
+#[derive(Debug)]
+pub struct GlyphImage {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    pub page: usize,
+}
+
+// 动态生成的字形的引用信息
+#[derive(Debug)]
+struct GlyphUse {
+    key: (Atom, usize, usize, usize, char), // 在char_map中的键
+    width: f32, // 分配的纹理宽度
+    count: usize, // 引用计数
+    tick: usize, // 引用计数变为0的时刻
 }
 
 #[derive(Debug)]
@@ -494,115 +852,104 @@ pub struct WaitChar {
     pub y: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 // 劈分结果
 pub enum SplitResult {
     Newline,
     Whitespace,
     Word(char),      // 单字词
-    WordStart(char), // 单词开始, 连续的字母或数字(必须字符的type_id相同)组成的单词
+    WordStart(char), // 单词开始, 单词内部没有折行机会(如连续的字母或数字、 不能位于行首的标点及其前一个字符)
     WordNext(char),  // 单词字符继续
     WordEnd,         // 单词字符结束
 }
 
 // 劈分字符迭代器
-pub struct SplitChar<'a> {
-    iter: Chars<'a>,
-    word_split: bool,
-    merge_whitespace: bool,
-    last: Option<char>,
-    type_id: usize, // 0表示单字词, 1表示ascii字母 2及以上代表字符的type_id, MAX表示数字
+pub struct SplitChar {
+    iter: IntoIter<(usize, SplitResult)>,
 }
 
-impl<'a> Iterator for SplitChar<'a> {
+impl SplitChar {
+    // 同时返回每个劈分结果对应的源字符索引(按字符计数， WordEnd为单词最后一个字符的索引)， 富文本用其查找字符所在的片段
+    pub fn indices(self) -> IntoIter<(usize, SplitResult)> {
+        self.iter
+    }
+}
+
+impl Iterator for SplitChar {
     type Item = SplitResult;
     fn next(&mut self) -> Option<Self::Item> {
-        match self.last {
-            Some(c) if self.type_id == 0 => {
-                if c == '\n' {
-                    self.last = self.iter.next();
-                    Some(SplitResult::Newline)
-                } else if c.is_whitespace() {
-                    if self.merge_whitespace {
-                        loop {
-                            match self.iter.next() {
-                                Some(cc) if cc.is_whitespace() => continue,
-                                r => {
-                                    self.last = r;
-                                    break;
-                                }
-                            }
-                        }
-                    } else {
-                        self.last = self.iter.next();
-                    }
-                    Some(SplitResult::Whitespace)
-                } else if !self.word_split {
-                    self.last = self.iter.next();
-                    Some(SplitResult::Word(c))
-                } else {
-                    self.type_id = get_type_id(c, char::from(0));
-                    if self.type_id == 0 {
-                        self.last = self.iter.next();
-                        Some(SplitResult::Word(c))
-                    } else {
-                        // 如果是单词开始，不读取下个字符，因为需要保留当前字符做是否为单词的判断
-                        Some(SplitResult::WordStart(c))
-                    }
+        self.iter.next().map(|r| r.1)
+    }
+}
+
+/// 劈分字符串, 返回字符迭代器
+/// 按UAX #14的折行机会劈分单词， 字素簇(组合字符、 emoji序列等)总是在同一个单词中， 零宽连接符、 变体选择符等不可见字符只参与劈分， 不输出
+/// word_split为false时， 每个字素簇单独成词
+pub fn split(s: &str, word_split: bool, merge_whitespace: bool) -> SplitChar {
+    let chars: Vec<char> = s.chars().collect();
+    let mut r = Vec::with_capacity(chars.len() + 2);
+    let mut word = Vec::new();
+    let mut breaker = LineBreaker::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let end = grapheme_end(&chars, i);
+        // 字素簇之前是否可以折行， 字素簇内部的字符也需要输入折行器， 以更新其状态
+        let allowed = breaker.next(c) != Break::Prohibited;
+        for j in i + 1..end {
+            breaker.next(chars[j]);
+        }
+
+        match break_class(c) {
+            BreakClass::BK | BreakClass::CR | BreakClass::LF | BreakClass::NL => {
+                push_word(&mut word, &mut r);
+                r.push((i, SplitResult::Newline));
+            }
+            BreakClass::GL => {
+                if allowed || !word_split {
+                    push_word(&mut word, &mut r);
+                }
+                for j in i..end {
+                    word.push((j, chars[j]));
                 }
             }
-            Some(old_c) => {
-                self.last = self.iter.next();
-                match self.last {
-                    Some(c) => {
-                        let id = get_type_id(c, old_c);
-                        if id == self.type_id {
-                            Some(SplitResult::WordNext(c))
-                        } else {
-                            self.type_id = 0;
-                            Some(SplitResult::WordEnd)
-                        }
+            _ if c.is_whitespace() => {
+                push_word(&mut word, &mut r);
+                if !merge_whitespace || r.last().map(|r| &r.1) != Some(&SplitResult::Whitespace) {
+                    r.push((i, SplitResult::Whitespace));
+                }
+            }
+            _ => {
+                if allowed || !word_split {
+                    push_word(&mut word, &mut r);
+                }
+                for j in i..end {
+                    if !is_ignorable(chars[j]) {
+                        word.push((j, chars[j]));
                     }
-                    _ => Some(SplitResult::WordEnd),
                 }
             }
-            _ => None,
         }
+        i = end;
     }
+    push_word(&mut word, &mut r);
+    SplitChar { iter: r.into_iter() }
 }
 
-/// 数字或字母, 返回对应的类型
-fn get_type_id(c: char, prev: char) -> usize {
-    if c.is_ascii() {
-        if c.is_ascii_alphabetic() {
-            return 1;
-        } else if c.is_ascii_digit() {
-            return usize::max_value();
-        } else if c == '/' || c == '.' || c == '%' {
-            if prev.is_ascii_digit() {
-                return usize::max_value();
-            }
-        } else if c == '\'' {
-            if prev.is_ascii_alphabetic() {
-                return 1;
+// 输出一个单词
+fn push_word(word: &mut Vec<(usize, char)>, r: &mut Vec<(usize, SplitResult)>) {
+    match word.len() {
+        0 => return,
+        1 => r.push((word[0].0, SplitResult::Word(word[0].1))),
+        _ => {
+            r.push((word[0].0, SplitResult::WordStart(word[0].1)));
+            for c in word[1..].iter() {
+                r.push((c.0, SplitResult::WordNext(c.1)));
             }
+            r.push((word[word.len() - 1].0, SplitResult::WordEnd));
         }
-    } else if c.is_alphabetic() && !c.is_cased() {
-        return c.get_type_id();
-    }
-    0
-}
-/// 劈分字符串, 返回字符迭代器
-pub fn split<'a>(s: &'a str, word_split: bool, merge_whitespace: bool) -> SplitChar<'a> {
-    let mut i = s.chars();
-    let last = i.next();
-    SplitChar {
-        iter: i,
-        word_split: word_split,
-        merge_whitespace: merge_whitespace,
-        last: last,
-        type_id: 0,
     }
+    word.clear();
 }
 
 #[test]
@@ -626,3 +973,29 @@ fn test() {
 	println!("time==========={:?}", std::time::Instant::now() - time);
 	println!("ret==========={:?}, {}", ret, ret.len());
 }
+#[test]
+fn test_split() {
+    fn words(s: &str) -> Vec<String> {
+        let mut r = Vec::new();
+        for cr in split(s, true, true) {
+            match cr {
+                SplitResult::Word(c) => r.push(c.to_string()),
+                SplitResult::WordStart(c) => r.push(c.to_string()),
+                SplitResult::WordNext(c) => r.last_mut().unwrap().push(c),
+                SplitResult::Whitespace => r.push(" ".to_string()),
+                SplitResult::Newline => r.push("\n".to_string()),
+                SplitResult::WordEnd => (),
+            }
+        }
+        r
+    }
+    // 避头尾： 标点与前一个字符、 开括号与后一个字符不分开
+    assert_eq!(words("中文，测试。"), vec!["中", "文，", "测", "试。"]);
+    assert_eq!(words("「好」的"), vec!["「好」", "的"]);
+    assert_eq!(words("hello  world 3.14%\r\nok"), vec!["hello", " ", "world", " ", "3.14%", "\n", "ok"]);
+    // emoji序列作为整体， 零宽连接符不输出
+    assert_eq!(words("a👨\u{200D}👩b"), vec!["a", "👨👩", "b"]);
+    // 源字符索引， 被忽略的零宽连接符也占一个索引
+    let indices: Vec<usize> = split("ab c👨\u{200D}👩", true, true).indices().map(|r| r.0).collect();
+    assert_eq!(indices, vec![0, 1, 1, 2, 3, 4, 6, 6]);
+}
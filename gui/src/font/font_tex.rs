@@ -1,47 +1,104 @@
 use share::{Share};
 
 use render::res::{TextureRes};
-use hash::XHashMap;
 
 use component::user::{Point2};
 
+// 字体纹理的最大页数， 超出后只能清空所有字形重新生成
+pub const MAX_TEX_PAGE: usize = 4;
+
+/// 字体纹理， 由多页纹理组成， 每页使用货架(shelf)方式分配字符区域
+/// 字符释放后， 区域归还到所在的货架， 空货架可以被其它行高复用
 pub struct FontTex{
-    pub texture: Share<TextureRes>,
-    line_map: XHashMap<usize, (Point2, usize)>,
+    pub pages: Vec<TexPage>,
+    tex_width: f32,
+    reserve_v: f32, // 第0页顶部预留给预生成字体纹理的高度
+}
+
+pub struct TexPage {
+    pub texture: Option<Share<TextureRes>>, // 新增的页， 在字体纹理更新时创建纹理
+    shelves: Vec<Shelf>, // 按v排序
     pub last_v: f32,
 }
 
+#[derive(Debug)]
+struct Shelf {
+    v: f32,
+    height: f32,
+    free: Vec<(f32, f32)>, // 空闲区间(起始u， 宽度)， 按u排序
+    count: usize, // 已分配的字符数量
+}
+
 impl FontTex {
     pub fn new(texture: Share<TextureRes>) -> Self {
         // let tex = ctx.create_texture_2d(TEX_WIDTH as u32, INIT_TEX_HEIGHT, 0, &PixelFormat::RGBA, &DataFormat::UnsignedByte, false, &TextureData::None).unwrap();
         // texture: Share::new(TextureRes::new(Atom::from("FontTex"), TEX_WIDTH as usize, INIT_TEX_HEIGHT as usize, Opacity::Translucent, Compress::None, tex)),
+        let tex_width = texture.width as f32;
         FontTex {
-            texture: texture,
-            line_map: XHashMap::default(),
-            last_v: 0.0,
+            pages: vec![TexPage::new(Some(texture))],
+            tex_width: tex_width,
+            reserve_v: 0.0,
         }
     }
-    // 分配行
-    pub fn alloc_line(&mut self, mut line_height: usize) -> TexLine {
-        // 将奇数的行高向上变成偶数，这样单行容纳2种字号，提高利用率
-        if line_height %2 != 0 {
-            line_height += 1;
-        }
-        let v = self.last_v;
-        let mut is_new = false;
-        let line = self.line_map.entry(line_height).or_insert_with(|| {
-            is_new = true;
-            (Point2::new(0.0, v), 0)
-        });
-        // 如果是新分配的行， self.last_v + line_height
-        if is_new {
-            self.last_v += line_height as f32;
-        }
-        TexLine {
-            line: line,
-            last_v: &mut self.last_v,
-            tex_width: self.texture.width as f32,
-            line_height: line_height as f32
+
+    pub fn tex_width(&self) -> f32 {
+        self.tex_width
+    }
+
+    pub fn texture(&self, page: usize) -> Option<&Share<TextureRes>> {
+        match self.pages.get(page) {
+            Some(r) => r.texture.as_ref(),
+            None => None,
+        }
+    }
+
+    // 在指定页上分配字符区域， 纹理高度不能超过纹理宽度
+    pub fn alloc(&mut self, page: usize, char_width: f32, line_height: f32) -> Option<Point2> {
+        let max_v = self.tex_width;
+        match self.pages.get_mut(page) {
+            Some(r) => r.alloc(char_width, line_height, self.tex_width, max_v),
+            None => None,
+        }
+    }
+
+    // 释放字符区域
+    pub fn free(&mut self, page: usize, u: f32, v: f32, char_width: f32) {
+        let tex_width = self.tex_width;
+        if let Some(r) = self.pages.get_mut(page) {
+            r.free(u, v, char_width, tex_width);
+        }
+    }
+
+    // 添加一页， 返回页的索引， 超出最大页数返回None
+    pub fn add_page(&mut self) -> Option<usize> {
+        if self.pages.len() >= MAX_TEX_PAGE {
+            return None;
+        }
+        self.pages.push(TexPage::new(None));
+        Some(self.pages.len() - 1)
+    }
+
+    // 在第0页的尾部预留区域， 用于放置预生成的字体纹理， 该区域永不回收
+    pub fn reserve(&mut self, height: f32) {
+        let page = &mut self.pages[0];
+        page.last_v += height;
+        self.reserve_v = page.last_v;
+    }
+
+    // 清空一页的分配信息， 保留预留的区域
+    pub fn clear_page(&mut self, page: usize) {
+        let reserve_v = if page == 0 { self.reserve_v } else { 0.0 };
+        if let Some(r) = self.pages.get_mut(page) {
+            r.shelves.clear();
+            r.last_v = reserve_v;
+        }
+    }
+
+    // 清空所有页的分配信息， 保留预留的区域
+    pub fn clear(&mut self) {
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            page.shelves.clear();
+            page.last_v = if i == 0 { self.reserve_v } else { 0.0 };
         }
     }
 
@@ -53,31 +110,149 @@ impl FontTex {
     // }
 }
 
-#[derive(Debug)]
-pub struct TexLine<'a> {
-    line: &'a mut (Point2, usize),
-    pub last_v: &'a mut f32,
-    pub tex_width: f32,
-    line_height: f32,
+impl TexPage {
+    fn new(texture: Option<Share<TextureRes>>) -> Self {
+        TexPage {
+            texture: texture,
+            shelves: Vec::new(),
+            last_v: 0.0,
+        }
+    }
+
+    fn alloc(&mut self, char_width: f32, mut line_height: f32, tex_width: f32, max_v: f32) -> Option<Point2> {
+        // 将奇数的行高向上变成偶数，这样单行容纳2种字号，提高利用率
+        line_height = line_height.ceil();
+        if line_height as usize % 2 != 0 {
+            line_height += 1.0;
+        }
+        // 在相同行高的货架中查找
+        for shelf in self.shelves.iter_mut() {
+            if shelf.height == line_height {
+                if let Some(u) = shelf.alloc(char_width) {
+                    return Some(Point2::new(u, shelf.v));
+                }
+            }
+        }
+        // 在尾部添加货架
+        if self.last_v + line_height <= max_v {
+            let mut shelf = Shelf::new(self.last_v, line_height, tex_width);
+            self.last_v += line_height;
+            let r = shelf.alloc(char_width).map(|u| Point2::new(u, shelf.v));
+            self.shelves.push(shelf);
+            return r;
+        }
+        // 复用足够高的空货架， 剩余的高度拆分成新的空货架
+        for i in 0..self.shelves.len() {
+            if self.shelves[i].count > 0 || self.shelves[i].height < line_height {
+                continue;
+            }
+            let remain = self.shelves[i].height - line_height;
+            if remain > 0.0 {
+                let v = self.shelves[i].v + line_height;
+                self.shelves.insert(i + 1, Shelf::new(v, remain, tex_width));
+            }
+            let shelf = &mut self.shelves[i];
+            shelf.height = line_height;
+            return shelf.alloc(char_width).map(|u| Point2::new(u, shelf.v));
+        }
+        None
+    }
+
+    fn free(&mut self, u: f32, v: f32, char_width: f32, tex_width: f32) {
+        let index = match self.shelves.iter().position(|s| s.v == v) {
+            Some(r) => r,
+            None => return,
+        };
+        if !self.shelves[index].free(u, char_width) {
+            return;
+        }
+        // 货架已空， 与相邻的空货架合并
+        self.shelves[index].free = vec![(0.0, tex_width)];
+        let mut i = index;
+        if i > 0 && self.shelves[i - 1].count == 0 {
+            let h = self.shelves.remove(i).height;
+            i -= 1;
+            self.shelves[i].height += h;
+        }
+        if i + 1 < self.shelves.len() && self.shelves[i + 1].count == 0 {
+            let h = self.shelves.remove(i + 1).height;
+            self.shelves[i].height += h;
+        }
+        // 末尾的空货架直接归还
+        if i + 1 == self.shelves.len() {
+            let shelf = self.shelves.pop().unwrap();
+            self.last_v = shelf.v;
+        }
+    }
 }
-impl<'a> TexLine<'a> {
-    // 获得起始v
-    pub fn get_v(&self) -> f32 {
-        self.line.0.y
+
+impl Shelf {
+    fn new(v: f32, height: f32, tex_width: f32) -> Self {
+        Shelf {
+            v: v,
+            height: height,
+            free: vec![(0.0, tex_width)],
+            count: 0,
+        }
     }
-    // 分配字符的起始uv
-    pub fn alloc(&mut self, char_width: f32) -> Point2 {
-        if self.tex_width >= self.line.0.x + char_width {
-            let r = self.line.0.clone();
-            self.line.0.x += char_width;
-            self.line.1 += 1;
-            r
-        }else{
-            self.line.0.x = char_width;
-            self.line.0.y = *self.last_v;
-            self.line.1 = 1;
-            *self.last_v += self.line_height;
-            Point2::new(0.0, self.line.0.y)
+
+    fn alloc(&mut self, char_width: f32) -> Option<f32> {
+        for i in 0..self.free.len() {
+            let (u, w) = self.free[i];
+            if w >= char_width {
+                if w == char_width {
+                    self.free.remove(i);
+                } else {
+                    self.free[i] = (u + char_width, w - char_width);
+                }
+                self.count += 1;
+                return Some(u);
+            }
         }
+        None
     }
+
+    // 归还区间， 返回货架是否已空
+    fn free(&mut self, u: f32, char_width: f32) -> bool {
+        let i = self.free.iter().position(|r| r.0 > u).unwrap_or(self.free.len());
+        self.free.insert(i, (u, char_width));
+        // 与后一个区间合并
+        if i + 1 < self.free.len() && u + char_width == self.free[i + 1].0 {
+            self.free[i].1 += self.free[i + 1].1;
+            self.free.remove(i + 1);
+        }
+        // 与前一个区间合并
+        if i > 0 && self.free[i - 1].0 + self.free[i - 1].1 == u {
+            self.free[i - 1].1 += self.free[i].1;
+            self.free.remove(i);
+        }
+        self.count -= 1;
+        self.count == 0
+    }
+}
+
+#[test]
+fn test_font_tex_alloc() {
+    let mut page = TexPage::new(None);
+    // 奇数行高向上取偶， 同行高的字符放在同一货架
+    let a = page.alloc(10.0, 15.0, 64.0, 64.0).unwrap();
+    let b = page.alloc(20.0, 16.0, 64.0, 64.0).unwrap();
+    assert_eq!((a.x, a.y, b.x, b.y), (0.0, 0.0, 10.0, 0.0));
+    let c = page.alloc(10.0, 32.0, 64.0, 64.0).unwrap();
+    assert_eq!((c.x, c.y, page.last_v), (0.0, 16.0, 48.0));
+    // 释放后区域可以复用
+    page.free(0.0, 0.0, 10.0, 64.0);
+    let d = page.alloc(8.0, 16.0, 64.0, 64.0).unwrap();
+    assert_eq!((d.x, d.y), (0.0, 0.0));
+    // 纹理已满时， 复用空货架并拆分
+    page.free(0.0, 16.0, 10.0, 64.0);
+    assert_eq!(page.last_v, 16.0);
+    page.alloc(10.0, 40.0, 64.0, 64.0).unwrap();
+    let e = page.alloc(10.0, 8.0, 64.0, 64.0).unwrap();
+    assert_eq!((e.y, page.last_v), (56.0, 64.0));
+    assert!(page.alloc(10.0, 30.0, 64.0, 64.0).is_none());
+    page.free(0.0, 16.0, 10.0, 64.0);
+    let f = page.alloc(10.0, 20.0, 64.0, 64.0).unwrap();
+    let g = page.alloc(10.0, 18.0, 64.0, 64.0).unwrap();
+    assert_eq!((f.y, g.y), (16.0, 36.0));
 }
@@ -0,0 +1,528 @@
+/// 文字的行布局
+/// 将文字块中的字符按行排列， 支持自动折行(贪心或最优折行)、 水平对齐、 垂直对齐、 首行缩进及字间距、 词间距
+/// 空白符总是挂在行尾， 不参与折行宽度的计算， 因此软换行后的行首不会出现空白符
+/// 单词容器中字符的位置相对于容器， 只排列容器本身
+/// 存在从右向左的文字时， 按双向层级将每行的单元重排为显示顺序(单词内部的字符也会重排)
+/// 超出最大行数的行被丢弃； 最后一行被截断或超出内容区宽度时， 在其末尾显示省略号(字符集合末尾的单词容器)
+/// 富文本中字符的高度可能不同， 行高取行内最高的字符， 字符在行内底端对齐
+use std::f32;
+
+use component::calc::{CharBlock, LineBox, LineStyle};
+use component::user::{TextAlign, VerticalAlign};
+use flex_layout::CharNode;
+use font::bidi::{bidi_class, levels, mirror, reorder, BidiClass};
+
+// 宽度比较的误差
+const EPSILON: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Char, // 字符或单词
+    Space,
+    Newline,
+}
+
+// 行内的排列单元， 一个字符、 单词、 空白符或换行符
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    index: usize, // 在字符集合中的索引
+    margin: f32, // 与前一个单元的间距， 行首忽略
+    width: f32,
+    kind: Kind,
+}
+
+/// 在指定宽度下对文字块折行， 返回内容的宽高
+pub fn break_lines(block: &mut CharBlock, width: f32) -> (f32, f32) {
+    let text_len = block.text_len();
+    let all = items(&block.chars, 0, text_len, &block.style);
+    let (indent, wrap) = (block.style.indent, block.style.wrap && width < f32::INFINITY);
+    let justify = match block.style.text_align {
+        TextAlign::Justify => true,
+        _ => false,
+    };
+
+    block.lines.clear();
+    let mut max_width: f32 = 0.0;
+    let mut start = 0;
+    // 按换行符拆分段落， 每个段落单独折行
+    while start < all.len() {
+        let end = match all[start..].iter().position(|r| r.kind == Kind::Newline) {
+            Some(r) => start + r + 1,
+            None => all.len(),
+        };
+        let paragraph = &all[start..end];
+        let indent = if start == 0 { indent } else { 0.0 };
+        let starts = if !wrap {
+            vec![0]
+        } else if justify {
+            optimal_breaks(paragraph, width, indent)
+        } else {
+            greedy_breaks(paragraph, width, indent)
+        };
+
+        for i in 0..starts.len() {
+            let (s, e) = (starts[i], starts.get(i + 1).cloned().unwrap_or(paragraph.len()));
+            let (w, gaps) = measure(&paragraph[s..e], if s == 0 { indent } else { 0.0 });
+            max_width = max_width.max(w);
+            let height = paragraph[s..e]
+                .iter()
+                .fold(0.0, |r: f32, item| r.max(block.chars[item.index].size.1));
+            block.lines.push(LineBox {
+                start: paragraph[s].index,
+                end: if e < paragraph.len() {
+                    paragraph[e].index
+                } else if end < all.len() {
+                    all[end].index
+                } else {
+                    text_len
+                },
+                width: w,
+                height: if height > 0.0 { height } else { block.style.line_height },
+                gaps: gaps,
+                hard: e == paragraph.len(),
+            });
+        }
+        start = end;
+    }
+
+    // 超出最大行数， 丢弃之后的行(最后一行的结束位置不再是文字的结尾， 排列时据此截断)
+    let line_clamp = block.style.line_clamp;
+    if line_clamp > 0 && block.lines.len() > line_clamp {
+        block.lines.truncate(line_clamp);
+        max_width = block.lines.iter().fold(0.0, |r, l| r.max(l.width));
+    }
+
+    block.size = (max_width, block.lines.iter().fold(0.0, |r, l| r + l.height));
+    block.size
+}
+
+/// 计算字符的双向层级， 并将从右向左排列的镜像字符(如括号)替换为其镜像
+/// 字符必须为劈分文字得到的原始字符， 因此只能在重新劈分文字后调用一次
+pub fn resolve_bidi(block: &mut CharBlock) {
+    let text_len = block.text_len();
+    let classes: Vec<BidiClass> = block.chars[..text_len]
+        .iter()
+        .map(|c| match c.ch {
+            '\0' => BidiClass::BN,
+            _ => bidi_class(c.ch),
+        })
+        .collect();
+    // 只有从左向右的文字， 不需要重排
+    let rtl = classes.iter().any(|c| match c {
+        BidiClass::R | BidiClass::AL | BidiClass::AN => true,
+        _ => false,
+    });
+    if !block.style.rtl && !rtl {
+        block.levels.clear();
+        return;
+    }
+
+    block.levels = levels(&classes, block.style.rtl);
+    // 省略号总是位于行的结束边， 使用段落的层级
+    let base = if block.style.rtl { 1 } else { 0 };
+    block.levels.resize(block.chars.len(), base);
+    for i in 0..text_len {
+        if block.levels[i] % 2 == 1 {
+            if let Some(c) = mirror(block.chars[i].ch) {
+                block.chars[i].ch = c;
+            }
+        }
+    }
+}
+
+/// 将已折行的字符排列到内容区中， (x, y)为内容区相对于节点边框盒的位置
+pub fn position(block: &mut CharBlock, x: f32, y: f32, width: f32, height: f32) {
+    let rtl = block.style.rtl;
+    // 起始边与结束边对齐转换为左右对齐
+    let text_align = match (block.style.text_align, rtl) {
+        (TextAlign::Start, false) | (TextAlign::End, true) => TextAlign::Left,
+        (TextAlign::Start, true) | (TextAlign::End, false) => TextAlign::Right,
+        (r, _) => r,
+    };
+    let content_height = block.lines.iter().fold(0.0, |r, l| r + l.height);
+    let mut line_top = y + match block.style.vertical_align {
+        VerticalAlign::Top => 0.0,
+        VerticalAlign::Middle => (height - content_height) / 2.0,
+        VerticalAlign::Bottom => height - content_height,
+    };
+
+    let text_len = block.text_len();
+    block.visible = text_len;
+    block.truncated = false;
+    for i in 0..block.lines.len() {
+        let (start, end, mut line_width, mut hard, gaps, line_height) = {
+            let line = &block.lines[i];
+            (line.start, line.end, line.width, line.hard, line.gaps, line.height)
+        };
+        let indent = if i == 0 { block.style.indent } else { 0.0 };
+        let mut line_items = items(&block.chars, start, end, &block.style);
+        // 最后一行： 超出最大行数被截断， 或设置了省略号且超出内容区宽度
+        if i + 1 == block.lines.len() && (end < text_len || (block.ellipsis > 0 && line_width > width + EPSILON)) {
+            block.truncated = true;
+            block.visible = end;
+            if block.ellipsis > 0 {
+                line_width = truncate(block, &mut line_items, indent, width);
+            }
+            hard = true; // 被截断的行不拉伸
+        }
+        let extra = width - line_width;
+        let (mut left, gap) = match text_align {
+            TextAlign::Center => (extra / 2.0, 0.0),
+            TextAlign::Right => (extra, 0.0),
+            TextAlign::Justify => {
+                if !hard && gaps > 0 && extra > 0.0 {
+                    (0.0, extra / gaps as f32)
+                } else if rtl {
+                    (extra, 0.0)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            _ => (0.0, 0.0),
+        };
+        left += x;
+        // 从右向左时， 首行缩进位于行的右侧(行宽已包含缩进)
+        if !rtl {
+            left += indent;
+        }
+
+        let order = visual_order(block, &line_items);
+        let mut first = true;
+        for k in order.into_iter() {
+            let item = &line_items[k];
+            if !first {
+                left += item.margin + gap;
+            }
+            let c = &mut block.chars[item.index];
+            c.pos = (left, line_top + line_height - c.size.1);
+            left += item.width;
+            first = false;
+        }
+        line_top += line_height;
+    }
+}
+
+// 截断最后一行， 只保留在内容区宽度内能与省略号一起放下的字符(行尾的空白符不保留)， 并将省略号作为最后一个单元
+// 单词放不下时， 保留单词中能放下的字符， 返回截断后的行宽
+fn truncate(block: &mut CharBlock, line_items: &mut Vec<Item>, indent: f32, width: f32) -> f32 {
+    let index = block.text_len();
+    let ellipsis = {
+        let c = &block.chars[index];
+        Item {
+            index: index,
+            margin: c.margin_start,
+            width: c.size.0,
+            kind: Kind::Char,
+        }
+    };
+    let avail = width - ellipsis.margin - ellipsis.width;
+    let visible = line_items.first().map_or(block.visible, |r| r.index);
+    // (保留的单元数, 保留部分的宽度, 可见字符的数量)
+    let (mut kept, mut kept_width, mut visible) = (0, indent, visible);
+    let mut x = indent;
+    for k in 0..line_items.len() {
+        let item = line_items[k];
+        if item.kind == Kind::Newline {
+            break;
+        }
+        let margin = if k == 0 { 0.0 } else { item.margin };
+        let c = &block.chars[item.index];
+        if x + margin + item.width > avail + EPSILON {
+            if c.ch == '\0' {
+                // 单词内的字符按逻辑顺序累计宽度(字符的位置可能已被双向重排)
+                let (end, mut w) = (item.index + c.ch_id_or_count.max(1), 0.0);
+                let mut i = item.index + 1;
+                while i < end {
+                    let ch = &block.chars[i];
+                    let advance = if i == item.index + 1 { ch.size.0 } else { ch.margin_start + ch.size.0 };
+                    if x + margin + w + advance > avail + EPSILON {
+                        break;
+                    }
+                    w += advance;
+                    i += 1;
+                }
+                if i > item.index + 1 {
+                    line_items[k].width = w;
+                    kept = k + 1;
+                    kept_width = x + margin + w;
+                    visible = i;
+                }
+            }
+            break;
+        }
+        x += margin + item.width;
+        if item.kind == Kind::Char {
+            kept = k + 1;
+            kept_width = x;
+            visible = item.index + if c.ch == '\0' { c.ch_id_or_count.max(1) } else { 1 };
+        }
+    }
+
+    line_items.truncate(kept);
+    block.visible = visible;
+    let r = if kept == 0 {
+        kept_width + ellipsis.width
+    } else {
+        kept_width + ellipsis.margin + ellipsis.width
+    };
+    line_items.push(ellipsis);
+    r
+}
+
+// 一行中排列单元的显示顺序， 单词内部存在从右向左的字符时， 同时重排单词内的字符
+fn visual_order(block: &mut CharBlock, line_items: &[Item]) -> Vec<usize> {
+    if block.levels.is_empty() {
+        return (0..line_items.len()).collect();
+    }
+    let base = if block.style.rtl { 1 } else { 0 };
+    let mut item_levels = Vec::with_capacity(line_items.len());
+    for item in line_items.iter() {
+        let c = &block.chars[item.index];
+        let level = if c.ch == '\0' {
+            // 单词的层级为其字符的最低层级
+            let (s, e) = (item.index + 1, item.index + c.ch_id_or_count.max(1));
+            reorder_word(block, s, e);
+            block.levels[s..e].iter().cloned().min().unwrap_or(base)
+        } else {
+            block.levels[item.index]
+        };
+        item_levels.push(level);
+    }
+    // 行尾的空白符使用段落的层级(L1)
+    for k in (0..line_items.len()).rev() {
+        if line_items[k].kind == Kind::Char {
+            break;
+        }
+        item_levels[k] = base;
+    }
+    reorder(&item_levels)
+}
+
+// 按显示顺序重新排列单词内[start, end)的字符， 字符间距保持不变
+fn reorder_word(block: &mut CharBlock, start: usize, end: usize) {
+    if !block.levels[start..end].iter().any(|l| l % 2 == 1) {
+        return;
+    }
+    let count = end - start;
+    let total: f32 = block.chars[start..end].iter().map(|c| c.size.0).sum();
+    let width = block.chars[start - 1].size.0;
+    let gap = if count > 1 { (width - total) / (count - 1) as f32 } else { 0.0 };
+    let mut x = 0.0;
+    for i in reorder(&block.levels[start..end]).into_iter() {
+        let c = &mut block.chars[start + i];
+        c.pos.0 = x;
+        x += c.size.0 + gap;
+    }
+}
+
+// 将字符集合中[start, end)的范围转换为排列单元
+fn items(chars: &[CharNode], start: usize, end: usize, style: &LineStyle) -> Vec<Item> {
+    let mut r = Vec::with_capacity(end - start);
+    let mut i = start;
+    while i < end {
+        let c = &chars[i];
+        let (kind, len, width) = match c.ch {
+            '\0' => (Kind::Char, c.ch_id_or_count.max(1), c.size.0),
+            '\n' => (Kind::Newline, 1, 0.0),
+            ' ' => (Kind::Space, 1, c.size.0 + style.word_margin),
+            _ => (Kind::Char, 1, c.size.0),
+        };
+        r.push(Item {
+            index: i,
+            margin: c.margin_start,
+            width: width,
+            kind: kind,
+        });
+        i += len;
+    }
+    r
+}
+
+// 计算一行的宽度及间隔数， 行尾的空白符不计入
+fn measure(items: &[Item], indent: f32) -> (f32, usize) {
+    let (mut x, mut width) = (indent, indent);
+    let (mut gaps, mut width_gaps) = (0, 0);
+    let mut first = true;
+    for item in items.iter() {
+        if item.kind == Kind::Newline {
+            continue;
+        }
+        if !first {
+            x += item.margin;
+            gaps += 1;
+        }
+        x += item.width;
+        first = false;
+        if item.kind == Kind::Char {
+            width = x;
+            width_gaps = gaps;
+        }
+    }
+    (width, width_gaps)
+}
+
+// 贪心折行， 尽可能多的单元放在当前行， 返回每行的起始单元
+fn greedy_breaks(items: &[Item], width: f32, indent: f32) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut x = indent;
+    let mut first = true;
+    for i in 0..items.len() {
+        let item = &items[i];
+        let advance = if first { item.width } else { item.margin + item.width };
+        // 只在字符前折行， 空白符挂在行尾
+        if item.kind == Kind::Char && !first && x + advance > width + EPSILON {
+            starts.push(i);
+            x = item.width;
+        } else {
+            x += advance;
+        }
+        first = false;
+    }
+    starts
+}
+
+// 最优折行， 使除最后一行外各行剩余空间的平方和最小， 用于两端对齐
+fn optimal_breaks(items: &[Item], width: f32, indent: f32) -> Vec<usize> {
+    // 可折行的位置(单元索引)， 最后一个为段落的结尾
+    let mut breaks = vec![0];
+    for i in 1..items.len() {
+        if items[i].kind == Kind::Char {
+            breaks.push(i);
+        }
+    }
+    breaks.push(items.len());
+
+    let count = breaks.len();
+    let mut cost = vec![f32::INFINITY; count];
+    let mut prev = vec![0; count];
+    cost[0] = 0.0;
+    for a in 0..count - 1 {
+        if cost[a] == f32::INFINITY {
+            continue;
+        }
+        for b in a + 1..count {
+            let (w, _) = measure(&items[breaks[a]..breaks[b]], if a == 0 { indent } else { 0.0 });
+            // 超出宽度， 除非该行只有一个单元， 否则后面的折行位置都不可用
+            if w > width + EPSILON && b > a + 1 {
+                break;
+            }
+            let c = if b == count - 1 {
+                cost[a]
+            } else {
+                let slack = (width - w).max(0.0);
+                cost[a] + slack * slack
+            };
+            if c < cost[b] {
+                cost[b] = c;
+                prev[b] = a;
+            }
+        }
+    }
+
+    let mut starts = Vec::new();
+    let mut b = count - 1;
+    while b > 0 {
+        b = prev[b];
+        starts.push(breaks[b]);
+    }
+    starts.reverse();
+    starts
+}
+
+#[test]
+fn test_line_box() {
+    fn block(text: &str, text_align: TextAlign) -> CharBlock {
+        let mut block = CharBlock::default();
+        block.style.line_height = 10.0;
+        block.style.text_align = text_align;
+        block.style.wrap = true;
+        for ch in text.chars() {
+            block.chars.push(CharNode {
+                ch: ch,
+                size: (if ch == '\n' { 0.0 } else { 10.0 }, 10.0),
+                margin_start: 0.0,
+                pos: (0.0, 0.0),
+                base_width: 10.0,
+                ch_id_or_count: 0,
+            });
+        }
+        block
+    }
+
+    // 贪心折行， 行尾的空白符不计入宽度
+    let mut b = block("ab cd ef", TextAlign::Left);
+    assert_eq!(break_lines(&mut b, 50.0), (50.0, 20.0));
+    assert_eq!((b.lines[0].end, b.lines[0].width, b.lines[1].start), (6, 50.0, 6));
+    position(&mut b, 5.0, 0.0, 50.0, 20.0);
+    assert_eq!((b.chars[3].pos, b.chars[6].pos), ((35.0, 0.0), (5.0, 10.0)));
+
+    // 换行符强制换行， 右对齐
+    let mut b = block("a\nbc", TextAlign::Right);
+    assert_eq!(break_lines(&mut b, 100.0), (20.0, 20.0));
+    position(&mut b, 0.0, 0.0, 100.0, 20.0);
+    assert_eq!((b.chars[0].pos, b.chars[2].pos), ((90.0, 0.0), (80.0, 10.0)));
+
+    // 两端对齐使用最优折行， 最后一行不拉伸
+    let mut b = block("aaa bb ccc", TextAlign::Justify);
+    break_lines(&mut b, 65.0);
+    assert_eq!(b.lines.iter().map(|r| r.start).collect::<Vec<usize>>(), vec![0, 7]);
+    position(&mut b, 0.0, 0.0, 65.0, 20.0);
+    assert_eq!((b.chars[2].pos.0, b.chars[5].pos.0), (22.0, 55.0));
+    assert_eq!(b.chars[9].pos, (20.0, 10.0));
+
+    // 不折行
+    let mut b = block("aaa bb ccc", TextAlign::Center);
+    b.style.wrap = false;
+    assert_eq!(break_lines(&mut b, 60.0), (100.0, 10.0));
+
+    // 从右向左， 起始边为右边， 镜像字符替换为其镜像
+    let mut b = block("אב cd(", TextAlign::Start);
+    b.style.rtl = true;
+    resolve_bidi(&mut b);
+    assert_eq!(b.chars[5].ch, ')');
+    break_lines(&mut b, 100.0);
+    position(&mut b, 0.0, 0.0, 100.0, 10.0);
+    assert_eq!((b.chars[5].pos.0, b.chars[3].pos.0, b.chars[0].pos.0), (40.0, 50.0, 90.0));
+
+    // 在末尾加入省略号(单词容器及其字符)
+    fn ellipsis(b: &mut CharBlock) {
+        let (mut container, mut c) = (b.chars[0].clone(), b.chars[0].clone());
+        container.ch = '\0';
+        container.ch_id_or_count = 2;
+        c.ch = '…';
+        b.chars.push(container);
+        b.chars.push(c);
+        b.ellipsis = 2;
+    }
+
+    // 超出最大行数， 最后一行截断后显示省略号
+    let mut b = block("ab cd ef", TextAlign::Left);
+    b.style.line_clamp = 1;
+    ellipsis(&mut b);
+    assert_eq!(break_lines(&mut b, 50.0), (50.0, 10.0));
+    position(&mut b, 0.0, 0.0, 50.0, 10.0);
+    assert_eq!((b.truncated, b.visible, b.chars[8].pos), (true, 4, (40.0, 0.0)));
+    assert_eq!((b.is_visible(3), b.is_visible(4), b.is_visible(9)), (true, false, true));
+
+    // 不折行时超出内容区宽度
+    let mut b = block("abcdef", TextAlign::Left);
+    b.style.wrap = false;
+    ellipsis(&mut b);
+    break_lines(&mut b, 35.0);
+    position(&mut b, 0.0, 0.0, 35.0, 10.0);
+    assert_eq!((b.visible, b.chars[6].pos.0), (2, 20.0));
+
+    // 裁剪， 只丢弃超出的行
+    let mut b = block("ab cd ef", TextAlign::Left);
+    b.style.line_clamp = 1;
+    break_lines(&mut b, 50.0);
+    position(&mut b, 0.0, 0.0, 50.0, 10.0);
+    assert_eq!((b.truncated, b.visible), (true, 6));
+
+    // 富文本中字符高度不同， 行高取行内最高的字符， 字符底端对齐
+    let mut b = block("ab cd", TextAlign::Left);
+    b.chars[1].size.1 = 16.0;
+    assert_eq!(break_lines(&mut b, 30.0), (20.0, 26.0));
+    position(&mut b, 0.0, 0.0, 30.0, 26.0);
+    assert_eq!((b.chars[0].pos, b.chars[1].pos, b.chars[3].pos), ((0.0, 6.0), (10.0, 0.0), (0.0, 16.0)));
+}
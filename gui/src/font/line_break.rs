@@ -0,0 +1,411 @@
+/// 文字的分割规则
+/// 1.字素簇(UAX #29)， 组合字符、 变体选择符、 ZWJ连接的emoji序列、 国旗、 韩文音节等， 一个字素簇总是作为整体排列
+/// 2.折行机会(UAX #14)， 判断两个字符之间是否可以折行， 包括中日文的避头尾规则(句号、 逗号、 闭括号、 小假名等不能位于行首， 开括号不能位于行尾)
+/// 字符属性取自ucd， 中文的左右引号按开闭标点处理
+
+// 折行类别， UAX #14的子集(AI、 HL、 SG、 XX按AL处理， CJ按NS处理， 韩文、 EB及CB按ID处理， EM按CM处理， SA按LB1处理)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakClass {
+    BK, // 强制换行
+    CR,
+    LF,
+    NL,
+    CM, // 组合字符
+    ZWJ,
+    WJ, // 不可折行的零宽字符
+    ZW, // 零宽空格
+    GL, // 不折行的空白， 如NBSP
+    SP,
+    B2, // 破折号
+    BA, // 其后可折行
+    BB, // 其前可折行
+    HY, // 连字符
+    CL, // 闭标点
+    CP, // 闭括号
+    EX, // 感叹号、 问号
+    IN, // 省略号
+    NS, // 不能位于行首
+    OP, // 开标点
+    QU, // 引号
+    IS, // 数字中的分隔符
+    NU, // 数字
+    PO, // 数字后缀
+    PR, // 数字前缀
+    SY, // 斜杠
+    AL, // 字母
+    ID, // 表意文字
+    RI, // 区域指示符(国旗)
+}
+
+// 折行机会
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Break {
+    Mandatory, // 必须折行
+    Allowed,
+    Prohibited,
+}
+
+use self::BreakClass::*;
+use ucd::{Codepoint, GraphemeClusterBreak, LinebreakClass, UnicodeCategory};
+
+/// 字符的折行类别
+pub fn break_class(c: char) -> BreakClass {
+    match c {
+        // 中文的引号区分左右， 左引号不能位于行尾， 右引号不能位于行首
+        '\u{2018}' | '\u{201C}' => return OP,
+        '\u{2019}' | '\u{201D}' => return CL,
+        _ => (),
+    }
+    match c.linebreak_class() {
+        Some(LinebreakClass::MandatoryBreak) => BK,
+        Some(LinebreakClass::CarriageReturn) => CR,
+        Some(LinebreakClass::LineFeed) => LF,
+        Some(LinebreakClass::NextLine) => NL,
+        Some(LinebreakClass::CombiningMark) => CM,
+        Some(LinebreakClass::EmojiModifier) => CM, // emoji修饰符， 与前面的emoji组成字素簇
+        Some(LinebreakClass::ZeroWidthJoiner) => ZWJ,
+        Some(LinebreakClass::WordJoiner) => WJ,
+        Some(LinebreakClass::ZeroWidthSpace) => ZW,
+        Some(LinebreakClass::NonBreakingGlue) => GL,
+        Some(LinebreakClass::Space) => SP,
+        Some(LinebreakClass::BreakBoth) => B2,
+        Some(LinebreakClass::BreakAfter) => BA,
+        Some(LinebreakClass::BreakBefore) => BB,
+        Some(LinebreakClass::Hyphen) => HY,
+        Some(LinebreakClass::ClosePunctuation) => CL,
+        Some(LinebreakClass::CloseParenthesis) => CP,
+        Some(LinebreakClass::Exclamation) => EX,
+        Some(LinebreakClass::Inseparable) => IN,
+        Some(LinebreakClass::Nonstarter) => NS,
+        Some(LinebreakClass::ConditionalJapaneseStarter) => NS, // 小假名及长音符(严格的避头规则)
+        Some(LinebreakClass::OpenPunctuation) => OP,
+        Some(LinebreakClass::Quotation) => QU,
+        Some(LinebreakClass::InfixNumeric) => IS,
+        Some(LinebreakClass::Numeric) => NU,
+        Some(LinebreakClass::PostfixNumeric) => PO,
+        Some(LinebreakClass::PrefixNumeric) => PR,
+        Some(LinebreakClass::BreakSymbols) => SY,
+        Some(LinebreakClass::RegionalIndicator) => RI,
+        // 表意文字、 韩文音节及emoji， 两侧均可折行(CB没有上下文信息， 同样处理)
+        Some(LinebreakClass::Ideographic)
+        | Some(LinebreakClass::EmojiBase)
+        | Some(LinebreakClass::ContingentBreak)
+        | Some(LinebreakClass::HangulLVSyllable)
+        | Some(LinebreakClass::HangulLVTSyllable)
+        | Some(LinebreakClass::HangulJamoL)
+        | Some(LinebreakClass::HangulJamoV)
+        | Some(LinebreakClass::HangulJamoT) => ID,
+        // 东南亚文字没有词典， 其中的组合字符按CM处理， 其余按字母处理(LB1)
+        Some(LinebreakClass::ComplexContext) => match c.category() {
+            UnicodeCategory::NonspacingMark | UnicodeCategory::SpacingMark => CM,
+            _ => AL,
+        },
+        // AI、 HL、 SG、 XX及未收录的字符
+        _ => AL,
+    }
+}
+
+/// 折行器， 依次输入字符， 判断每个字符前是否可以折行
+pub struct LineBreaker {
+    prev: Option<BreakClass>, // 前一个字符的类别(组合字符按其基础字符处理)
+    before_space: Option<BreakClass>, // 连续空白之前的字符类别
+    ri_count: usize, // 连续的区域指示符数量
+    zwj: bool, // 前一个字符是否为ZWJ
+}
+
+impl LineBreaker {
+    pub fn new() -> Self {
+        LineBreaker {
+            prev: None,
+            before_space: None,
+            ri_count: 0,
+            zwj: false,
+        }
+    }
+
+    /// 输入下一个字符， 返回该字符之前的折行机会
+    pub fn next(&mut self, c: char) -> Break {
+        let cur = break_class(c);
+        let zwj = self.zwj;
+        self.zwj = cur == ZWJ;
+        let prev = match self.prev {
+            Some(r) => r,
+            None => {
+                // 文本开始处不折行， 开始处的组合字符按字母处理
+                self.update(if cur == CM || cur == ZWJ { AL } else { cur });
+                return Break::Prohibited;
+            }
+        };
+
+        // 组合字符与ZWJ跟随前一个字符(空白及换行后的按字母处理)
+        if cur == CM || cur == ZWJ {
+            return match prev {
+                BK | CR | LF | NL => {
+                    self.update(AL);
+                    Break::Mandatory
+                }
+                SP | ZW => {
+                    let r = rule(prev, AL, self.before_space, 0);
+                    self.update(AL);
+                    r
+                }
+                _ => Break::Prohibited,
+            };
+        }
+        // ZWJ之后不折行(LB8a)
+        if zwj {
+            self.update(cur);
+            return Break::Prohibited;
+        }
+
+        let r = rule(prev, cur, self.before_space, self.ri_count);
+        self.update(cur);
+        r
+    }
+
+    fn update(&mut self, cur: BreakClass) {
+        if cur == SP {
+            if self.prev != Some(SP) {
+                self.before_space = self.prev;
+            }
+        } else {
+            self.before_space = None;
+        }
+        self.ri_count = if cur == RI { self.ri_count + 1 } else { 0 };
+        self.prev = Some(cur);
+    }
+}
+
+// 两个字符间的折行规则， 对应UAX #14的LB4 - LB31
+fn rule(prev: BreakClass, cur: BreakClass, before_space: Option<BreakClass>, ri_count: usize) -> Break {
+    // LB4 - LB6
+    match (prev, cur) {
+        (BK, _) | (LF, _) | (NL, _) => return Break::Mandatory,
+        (CR, LF) => return Break::Prohibited,
+        (CR, _) => return Break::Mandatory,
+        (_, BK) | (_, CR) | (_, LF) | (_, NL) => return Break::Prohibited,
+        // LB7
+        (_, SP) | (_, ZW) => return Break::Prohibited,
+        // LB8
+        (ZW, _) => return Break::Allowed,
+        _ => (),
+    }
+    // 空白之后， 由空白之前的字符决定(LB8、 LB14 - LB17)
+    let space = prev == SP;
+    let base = if space { before_space.unwrap_or(SP) } else { prev };
+    if space && base == ZW {
+        return Break::Allowed;
+    }
+    // LB11 - LB13
+    match (prev, cur) {
+        (WJ, _) | (_, WJ) => return Break::Prohibited,
+        (GL, _) => return Break::Prohibited,
+        (SP, GL) | (BA, GL) | (HY, GL) => (),
+        (_, GL) => return Break::Prohibited,
+        (_, CL) | (_, CP) | (_, EX) | (_, IS) | (_, SY) => return Break::Prohibited,
+        _ => (),
+    }
+    // LB14 - LB17
+    match (base, cur) {
+        (OP, _) => return Break::Prohibited,
+        (QU, OP) => return Break::Prohibited,
+        (CL, NS) | (CP, NS) => return Break::Prohibited,
+        (B2, B2) => return Break::Prohibited,
+        _ => (),
+    }
+    // LB18
+    if space {
+        return Break::Allowed;
+    }
+    let r = match (prev, cur) {
+        // LB19
+        (_, QU) | (QU, _) => false,
+        // LB21
+        (_, BA) | (_, HY) | (_, NS) | (BB, _) => false,
+        // LB22
+        (_, IN) => false,
+        // LB23
+        (AL, NU) | (NU, AL) => false,
+        // LB23a
+        (PR, ID) | (ID, PO) => false,
+        // LB24
+        (PR, AL) | (PO, AL) | (AL, PR) | (AL, PO) => false,
+        // LB25
+        (CL, PO) | (CP, PO) | (CL, PR) | (CP, PR) | (NU, PO) | (NU, PR) | (PO, OP) | (PO, NU)
+        | (PR, OP) | (PR, NU) | (HY, NU) | (IS, NU) | (NU, NU) | (SY, NU) => false,
+        // LB28
+        (AL, AL) => false,
+        // LB29
+        (IS, AL) => false,
+        // LB30
+        (AL, OP) | (NU, OP) | (CP, AL) | (CP, NU) => false,
+        // LB30a， 区域指示符两两组合
+        (RI, RI) => ri_count % 2 == 0,
+        // LB31
+        _ => true,
+    };
+    if r {
+        Break::Allowed
+    } else {
+        Break::Prohibited
+    }
+}
+
+// 字素簇类别， UAX #29的子集(SpacingMark、 E_Modifier按Extend处理， E_Base、 E_Base_GAZ、 Glue_After_Zwj按Pictographic处理， 不支持Prepend)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Grapheme {
+    CR,
+    LF,
+    Control,
+    Extend,
+    ZWJ,
+    RI,
+    L,
+    V,
+    T,
+    LV,
+    LVT,
+    Pictographic,
+    Other,
+}
+
+fn grapheme_class(c: char) -> Grapheme {
+    match c.grapheme_cluster_break() {
+        GraphemeClusterBreak::CarriageReturn => Grapheme::CR,
+        GraphemeClusterBreak::LineFeed => Grapheme::LF,
+        GraphemeClusterBreak::Control => Grapheme::Control,
+        GraphemeClusterBreak::Extend | GraphemeClusterBreak::SpacingMark | GraphemeClusterBreak::EmojiModifier => Grapheme::Extend,
+        GraphemeClusterBreak::ZWJ => Grapheme::ZWJ,
+        GraphemeClusterBreak::RegionalIndicator => Grapheme::RI,
+        GraphemeClusterBreak::LeadingJamo => Grapheme::L,
+        GraphemeClusterBreak::VowelJamo => Grapheme::V,
+        GraphemeClusterBreak::TrailingJamo => Grapheme::T,
+        GraphemeClusterBreak::LVHangulSyllable => Grapheme::LV,
+        GraphemeClusterBreak::LVTHangulSyllable => Grapheme::LVT,
+        GraphemeClusterBreak::EmojiModifierBase
+        | GraphemeClusterBreak::EmojiModiferBaseAfterZWJ
+        | GraphemeClusterBreak::GlueAfterZWJ => Grapheme::Pictographic,
+        _ => {
+            if is_pictographic(c) {
+                Grapheme::Pictographic
+            } else {
+                Grapheme::Other
+            }
+        }
+    }
+}
+
+// ucd(Unicode 9.0)没有Extended_Pictographic属性， ZWJ序列中其它的emoji按区段判断
+fn is_pictographic(c: char) -> bool {
+    match c as u32 {
+        0xA9 | 0xAE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x2194..=0x2199 | 0x21A9..=0x21AA
+        | 0x231A..=0x231B | 0x2328 | 0x23CF | 0x23E9..=0x23F3 | 0x23F8..=0x23FA | 0x24C2
+        | 0x25AA..=0x25AB | 0x25B6 | 0x25C0 | 0x25FB..=0x25FE | 0x2600..=0x27BF
+        | 0x2934..=0x2935 | 0x2B05..=0x2B07 | 0x2B1B..=0x2B1C | 0x2B50 | 0x2B55 | 0x3030
+        | 0x303D | 0x3297 | 0x3299 | 0x1F000..=0x1FAFF | 0x1FC00..=0x1FFFD => true,
+        _ => false,
+    }
+}
+
+/// 字素簇的结束位置， 返回从start开始的字素簇之后的索引
+pub fn grapheme_end(chars: &[char], start: usize) -> usize {
+    let mut prev = grapheme_class(chars[start]);
+    let mut pictographic = prev == Grapheme::Pictographic; // emoji后跟随Extend* ZWJ
+    let mut ri_count = if prev == Grapheme::RI { 1 } else { 0 };
+    let mut i = start + 1;
+    while i < chars.len() {
+        let cur = grapheme_class(chars[i]);
+        let join = match (prev, cur) {
+            (Grapheme::CR, Grapheme::LF) => true,
+            (Grapheme::CR, _) | (Grapheme::LF, _) | (Grapheme::Control, _) => false,
+            (_, Grapheme::CR) | (_, Grapheme::LF) | (_, Grapheme::Control) => false,
+            (Grapheme::L, Grapheme::L) | (Grapheme::L, Grapheme::V) | (Grapheme::L, Grapheme::LV) | (Grapheme::L, Grapheme::LVT) => true,
+            (Grapheme::LV, Grapheme::V) | (Grapheme::V, Grapheme::V) | (Grapheme::LV, Grapheme::T) | (Grapheme::V, Grapheme::T) => true,
+            (Grapheme::LVT, Grapheme::T) | (Grapheme::T, Grapheme::T) => true,
+            (_, Grapheme::Extend) | (_, Grapheme::ZWJ) => true,
+            (Grapheme::ZWJ, Grapheme::Pictographic) => pictographic,
+            (Grapheme::RI, Grapheme::RI) => ri_count % 2 == 1,
+            _ => false,
+        };
+        if !join {
+            break;
+        }
+        match cur {
+            Grapheme::Pictographic => pictographic = true,
+            Grapheme::Extend | Grapheme::ZWJ => (),
+            _ => pictographic = false,
+        }
+        if cur == Grapheme::RI {
+            ri_count += 1;
+        }
+        prev = cur;
+        i += 1;
+    }
+    i
+}
+
+/// 字素簇的起始位置， 返回在end之前结束的字素簇的起始索引
+/// 从之前最近的必然边界(换行符、 控制字符之后)向后查找， 避免从中间开始误判区域指示符的配对
+pub fn grapheme_start(chars: &[char], end: usize) -> usize {
+    let end = end.min(chars.len());
+    if end == 0 {
+        return 0;
+    }
+    let mut start = end - 1;
+    while start > 0 {
+        match grapheme_class(chars[start - 1]) {
+            Grapheme::LF | Grapheme::Control => break,
+            _ => start -= 1,
+        }
+    }
+    loop {
+        let next = grapheme_end(chars, start);
+        if next >= end {
+            return start;
+        }
+        start = next;
+    }
+}
+
+/// 默认不显示的字符(零宽连接符、 变体选择符等)， 只参与分割， 不生成字形
+pub fn is_ignorable(c: char) -> bool {
+    c.is_default_ignorable()
+}
+
+#[test]
+fn test_line_break() {
+    fn breaks(s: &str) -> Vec<usize> {
+        let mut breaker = LineBreaker::new();
+        let mut r = Vec::new();
+        for (i, c) in s.chars().enumerate() {
+            if breaker.next(c) != Break::Prohibited {
+                r.push(i);
+            }
+        }
+        r
+    }
+    // 中文可在字间折行， 标点不能位于行首， 开括号不能位于行尾
+    assert_eq!(breaks("中文，测试。"), vec![1, 3, 4]);
+    assert_eq!(breaks("好「对」"), vec![1]);
+    assert_eq!(breaks("ちょっと"), vec![3]);
+    // 字母与数字组成的单词不折行， 空白后可折行
+    assert_eq!(breaks("hello world 3.14%"), vec![6, 12]);
+    assert_eq!(breaks("中文abc中"), vec![1, 2, 5]);
+
+    // 字素簇
+    let chars: Vec<char> = "e\u{301}👨\u{200D}👩\u{200D}👧🇨🇳🇯🇵".chars().collect();
+    assert_eq!(grapheme_end(&chars, 0), 2);
+    assert_eq!(grapheme_end(&chars, 2), 7);
+    assert_eq!(grapheme_end(&chars, 7), 9);
+    assert_eq!(grapheme_end(&chars, 9), 11);
+    assert_eq!(grapheme_start(&chars, 11), 9);
+    assert_eq!(grapheme_start(&chars, 9), 7);
+    assert_eq!(grapheme_start(&chars, 7), 2);
+    assert_eq!(grapheme_start(&chars, 2), 0);
+    assert_eq!(grapheme_start(&chars, 1), 0);
+    assert_eq!(grapheme_start(&chars, 0), 0);
+    // 换行符之后是边界
+    let chars: Vec<char> = "a\r\n\u{301}b".chars().collect();
+    assert_eq!(grapheme_start(&chars, 3), 1);
+    assert_eq!(grapheme_start(&chars, 4), 3);
+}
@@ -1,2 +1,6 @@
+pub mod bidi;
 pub mod font_sheet;
-pub mod font_tex;
\ No newline at end of file
+pub mod font_tex;
+pub mod line_box;
+pub mod line_break;
+pub mod native;
\ No newline at end of file
@@ -0,0 +1,1305 @@
+/// 原生字体， 直接解析TTF/OTF字体文件， 提供字符的宽度、字体的纵向度量， 并将字形光栅化
+/// 不依赖浏览器canvas， 渲染结果确定， 可离线测试， 也可用于非浏览器的宿主环境
+/// 支持TrueType轮廓(glyf)和CFF轮廓(包括CID字体)， 字体集合(ttc)只取第一个字体
+
+// 复合字形的最大嵌套深度
+const MAX_COMPONENT_DEPTH: usize = 8;
+// CFF子程序的最大调用深度
+const MAX_SUBR_DEPTH: usize = 10;
+// CFF参数栈的最大长度
+const MAX_ARGS: usize = 48;
+// 曲线平坦化时， 每段直线的近似长度(像素)
+const FLATTEN_STEP: f32 = 2.0;
+
+/// 字体文件中的路径命令， 坐标为字体单位， y轴向上
+#[derive(Debug, Clone, Copy)]
+pub enum PathCmd {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+pub struct NativeFont {
+    data: Vec<u8>,
+    units_per_em: f32,
+    ascender: f32,
+    descender: f32,
+    line_gap: f32,
+    underline: (f32, f32), // 下划线顶部相对基线的位置(向上为正)及粗细
+    strikeout: (f32, f32), // 删除线顶部相对基线的位置(向上为正)及粗细
+    num_glyphs: u16,
+    num_hmetrics: u16,
+    hmtx: usize,
+    cmap: (usize /*子表偏移*/, u16 /*子表格式*/),
+    outline: Outline,
+}
+
+enum Outline {
+    Glyf {
+        loca: usize,
+        glyf: usize,
+        long_loca: bool,
+    },
+    Cff(Cff),
+}
+
+impl NativeFont {
+    /// 解析字体文件
+    pub fn new(data: Vec<u8>) -> Result<NativeFont, String> {
+        let mut font_offset = 0;
+        match read_u32(&data, 0) {
+            Some(0x74746366) => {
+                // ttcf, 字体集合， 取第一个字体
+                font_offset = read_u32(&data, 12).ok_or("invalid ttc header")? as usize;
+            }
+            Some(0x00010000) | Some(0x74727565) | Some(0x4F54544F) => (),
+            _ => return Err("unknown font format".to_string()),
+        }
+        let find = |tag: &[u8; 4]| -> Option<usize> {
+            let count = read_u16(&data, font_offset + 4)? as usize;
+            for i in 0..count {
+                let record = font_offset + 12 + i * 16;
+                if data.get(record..record + 4)? == tag {
+                    return read_u32(&data, record + 8).map(|r| r as usize);
+                }
+            }
+            None
+        };
+
+        let head = find(b"head").ok_or("missing head table")?;
+        let hhea = find(b"hhea").ok_or("missing hhea table")?;
+        let maxp = find(b"maxp").ok_or("missing maxp table")?;
+        let hmtx = find(b"hmtx").ok_or("missing hmtx table")?;
+        let cmap = find(b"cmap").ok_or("missing cmap table")?;
+
+        let units_per_em = read_u16(&data, head + 18).ok_or("invalid head table")?;
+        if units_per_em == 0 {
+            return Err("invalid units_per_em".to_string());
+        }
+        let outline = match (find(b"glyf"), find(b"loca"), find(b"CFF ")) {
+            (Some(glyf), Some(loca), _) => Outline::Glyf {
+                loca,
+                glyf,
+                long_loca: read_i16(&data, head + 50).ok_or("invalid head table")? != 0,
+            },
+            (_, _, Some(cff)) => Outline::Cff(Cff::new(&data, cff).ok_or("invalid CFF table")?),
+            _ => return Err("missing glyph outlines".to_string()),
+        };
+        let cmap = find_cmap(&data, cmap).ok_or("unsupported cmap table")?;
+        // post及OS/2表是可选的， 缺少时使用常见字体的比例
+        let em = units_per_em as f32;
+        let underline = match find(b"post").and_then(|post| Some((read_i16(&data, post + 8)?, read_i16(&data, post + 10)?))) {
+            Some((p, t)) if t > 0 => (p as f32, t as f32),
+            _ => (-0.1 * em, 0.05 * em),
+        };
+        let strikeout = match find(b"OS/2").and_then(|os2| Some((read_i16(&data, os2 + 28)?, read_i16(&data, os2 + 26)?))) {
+            Some((p, t)) if t > 0 => (p as f32, t as f32),
+            _ => (0.3 * em, underline.1),
+        };
+
+        Ok(NativeFont {
+            units_per_em: units_per_em as f32,
+            ascender: read_i16(&data, hhea + 4).ok_or("invalid hhea table")? as f32,
+            descender: read_i16(&data, hhea + 6).ok_or("invalid hhea table")? as f32,
+            line_gap: read_i16(&data, hhea + 8).ok_or("invalid hhea table")? as f32,
+            underline,
+            strikeout,
+            num_hmetrics: read_u16(&data, hhea + 34).ok_or("invalid hhea table")?,
+            num_glyphs: read_u16(&data, maxp + 4).ok_or("invalid maxp table")?,
+            hmtx,
+            cmap,
+            outline,
+            data,
+        })
+    }
+
+    /// 上边界(基线以上)， 相对于字号的比例
+    pub fn ascent(&self) -> f32 {
+        self.ascender / self.units_per_em
+    }
+
+    /// 下边界(基线以下， 为正数)， 相对于字号的比例
+    pub fn descent(&self) -> f32 {
+        -self.descender / self.units_per_em
+    }
+
+    /// 行间距， 相对于字号的比例
+    pub fn line_gap(&self) -> f32 {
+        self.line_gap / self.units_per_em
+    }
+
+    /// 下划线顶部相对基线的位置(向上为正， 通常为负数)及粗细， 相对于字号的比例
+    pub fn underline(&self) -> (f32, f32) {
+        (self.underline.0 / self.units_per_em, self.underline.1 / self.units_per_em)
+    }
+
+    /// 删除线顶部相对基线的位置(向上为正)及粗细， 相对于字号的比例
+    pub fn strikeout(&self) -> (f32, f32) {
+        (self.strikeout.0 / self.units_per_em, self.strikeout.1 / self.units_per_em)
+    }
+
+    /// 字符的字形id, 字体中不存在该字符时返回0(.notdef)
+    pub fn glyph_index(&self, c: char) -> u16 {
+        let c = c as u32;
+        let (offset, format) = self.cmap;
+        let data = &self.data;
+        let r = match format {
+            4 => {
+                if c > 0xFFFF {
+                    return 0;
+                }
+                cmap_format4(data, offset, c)
+            }
+            _ => cmap_format12(data, offset, c),
+        };
+        match r {
+            Some(id) if id < self.num_glyphs => id,
+            _ => 0,
+        }
+    }
+
+    /// 字符在指定字号下的步进宽度
+    pub fn advance(&self, c: char, font_size: f32) -> f32 {
+        let id = self.glyph_index(c);
+        let i = if id < self.num_hmetrics {
+            id
+        } else {
+            self.num_hmetrics.max(1) - 1
+        };
+        let w = read_u16(&self.data, self.hmtx + i as usize * 4).unwrap_or(0);
+        w as f32 * font_size / self.units_per_em
+    }
+
+    /// 取字形的轮廓， 坐标为字体单位
+    pub fn outline(&self, id: u16) -> Vec<PathCmd> {
+        let mut path = Vec::new();
+        match &self.outline {
+            &Outline::Glyf { loca, glyf, long_loca } => {
+                glyf_outline(&self.data, loca, glyf, long_loca, id, &[1.0, 0.0, 0.0, 1.0, 0.0, 0.0], 0, &mut path);
+            }
+            &Outline::Cff(ref cff) => {
+                cff.outline(&self.data, id, &mut path);
+            }
+        }
+        path
+    }
+
+    /// 将字符光栅化到width*height的覆盖率图中， 原点在左上角， 基线位于y = baseline
+    /// 返回的每个值为该像素的覆盖率(0~1)
+    pub fn rasterize(&self, c: char, font_size: f32, x: f32, baseline: f32, width: usize, height: usize) -> Vec<f32> {
+        let mut raster = Rasterizer::new(width, height);
+        let scale = font_size / self.units_per_em;
+        let path = self.outline(self.glyph_index(c));
+        raster.draw_path(&path, scale, x, baseline);
+        raster.coverage()
+    }
+
+    /// 绘制字符， 返回width*height的RGBA图像， 颜色编码与canvas绘制的一致：
+    /// 绿色通道为填充， 红色通道为描边， 蓝色通道为背景
+    pub fn draw_char(&self, c: char, font_size: f32, stroke_width: f32, bold: bool, width: usize, height: usize) -> Vec<u8> {
+        let half = stroke_width / 2.0;
+        let mut fill = self.rasterize(c, font_size, half, half + self.ascent() * font_size, width, height);
+        if bold {
+            // 字体文件本身不是粗体， 向右加宽字形模拟粗体
+            let r = (font_size / 24.0).round().max(1.0) as usize;
+            fill = dilate(&fill, width, height, r, 0, true);
+        }
+        let stroke = if stroke_width > 0.0 {
+            let r = half.ceil() as usize;
+            Some(dilate(&fill, width, height, r, r, false))
+        } else {
+            None
+        };
+        let mut data = Vec::with_capacity(width * height * 4);
+        for i in 0..fill.len() {
+            let f = fill[i];
+            let s = match &stroke {
+                Some(s) => s[i].max(f),
+                None => f,
+            };
+            data.push(((s - f) * 255.0).round() as u8);
+            data.push((f * 255.0).round() as u8);
+            data.push(((1.0 - s) * 255.0).round() as u8);
+            data.push(255);
+        }
+        data
+    }
+
+    /// 生成字符的有向距离场(SDF)， 返回width*height的RGBA图像， 字形原点在(x, baseline)
+    /// 距离以range(像素)为范围映射到0~1, 0.5为字形边缘， 大于0.5在字形内部
+    /// rgb三个通道的值相同， 中值即为距离， 因此可以直接使用msdf的着色器
+    pub fn draw_sdf(&self, c: char, font_size: f32, range: f32, x: f32, baseline: f32, width: usize, height: usize) -> Vec<u8> {
+        let scale = font_size / self.units_per_em;
+        let path = self.outline(self.glyph_index(c));
+        let lines = flatten(&path, scale, x, baseline);
+        // 内外由覆盖率判断， 与光栅化的填充规则一致
+        let mut raster = Rasterizer::new(width, height);
+        for line in lines.iter() {
+            raster.draw_line(line.0, line.1);
+        }
+        let coverage = raster.coverage();
+
+        let mut data = Vec::with_capacity(width * height * 4);
+        for py in 0..height {
+            for px in 0..width {
+                let p = (px as f32 + 0.5, py as f32 + 0.5);
+                let mut d = range;
+                for line in lines.iter() {
+                    d = d.min(segment_distance(p, line.0, line.1));
+                }
+                if coverage[py * width + px] < 0.5 {
+                    d = -d;
+                }
+                let v = ((0.5 + d / (2.0 * range)).max(0.0).min(1.0) * 255.0).round() as u8;
+                data.extend_from_slice(&[v, v, v, 255]);
+            }
+        }
+        data
+    }
+}
+
+// 点到线段的距离
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let (apx, apy) = (p.0 - a.0, p.1 - a.1);
+    let len = abx * abx + aby * aby;
+    let t = if len > 0.0 {
+        ((apx * abx + apy * aby) / len).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+    let (dx, dy) = (apx - abx * t, apy - aby * t);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// 覆盖率图的膨胀， 取椭圆(rx, ry)范围内的最大值， right为true时只向右膨胀
+fn dilate(src: &[f32], width: usize, height: usize, rx: usize, ry: usize, right: bool) -> Vec<f32> {
+    let mut dst = src.to_vec();
+    let (rx_i, ry_i) = (rx as isize, ry as isize);
+    for dy in -ry_i..ry_i + 1 {
+        for dx in if right { 0 } else { -rx_i }..rx_i + 1 {
+            let (fx, fy) = (dx as f32 / rx.max(1) as f32, dy as f32 / ry.max(1) as f32);
+            if (dx == 0 && dy == 0) || fx * fx + fy * fy > 1.0 {
+                continue;
+            }
+            for y in 0..height as isize {
+                let sy = y - dy;
+                if sy < 0 || sy >= height as isize {
+                    continue;
+                }
+                for x in 0..width as isize {
+                    let sx = x - dx;
+                    if sx < 0 || sx >= width as isize {
+                        continue;
+                    }
+                    let v = src[sy as usize * width + sx as usize];
+                    let d = &mut dst[y as usize * width + x as usize];
+                    if v > *d {
+                        *d = v;
+                    }
+                }
+            }
+        }
+    }
+    dst
+}
+
+/// 基于有向面积累积的扫描线光栅化， 结果带抗锯齿
+pub struct Rasterizer {
+    width: usize,
+    height: usize,
+    stride: usize,
+    acc: Vec<f32>,
+}
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Rasterizer {
+        // 每行多留两个像素， 防止线段贴近右边界时写入下一行
+        let stride = width + 2;
+        Rasterizer {
+            width,
+            height,
+            stride,
+            acc: vec![0.0; stride * height + 2],
+        }
+    }
+
+    /// 绘制路径， 将字体单位的坐标转换为像素坐标(y轴向下)
+    pub fn draw_path(&mut self, path: &[PathCmd], scale: f32, ox: f32, baseline: f32) {
+        for line in flatten(path, scale, ox, baseline).iter() {
+            self.draw_line(line.0, line.1);
+        }
+    }
+
+    /// 累积线段对每个像素的有向面积
+    pub fn draw_line(&mut self, p0: (f32, f32), p1: (f32, f32)) {
+        if (p0.1 - p1.1).abs() <= std::f32::EPSILON {
+            return;
+        }
+        // 水平方向超出范围的部分压到边界上， 不影响每行的累积结果
+        let w = self.width as f32;
+        let (p0, p1) = ((p0.0.max(0.0).min(w), p0.1), (p1.0.max(0.0).min(w), p1.1));
+        let (dir, p0, p1) = if p0.1 < p1.1 { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+        let dxdy = (p1.0 - p0.0) / (p1.1 - p0.1);
+        let mut x = p0.0;
+        if p0.1 < 0.0 {
+            x -= p0.1 * dxdy;
+        }
+        let y_start = p0.1.max(0.0) as usize;
+        let y_end = (p1.1.ceil().max(0.0) as usize).min(self.height);
+        for y in y_start..y_end {
+            let line = y * self.stride;
+            let dy = ((y + 1) as f32).min(p1.1) - (y as f32).max(p0.1);
+            let x_next = x + dxdy * dy;
+            let d = dy * dir;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let x0_floor = x0.floor();
+            let x0i = x0_floor as usize;
+            let x1_ceil = x1.ceil();
+            let x1i = x1_ceil as usize;
+            if x1i <= x0i + 1 {
+                let xmf = 0.5 * (x + x_next) - x0_floor;
+                self.acc[line + x0i] += d - d * xmf;
+                self.acc[line + x0i + 1] += d * xmf;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0f = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+                let x1f = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1f * x1f;
+                self.acc[line + x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    self.acc[line + x0i + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0f);
+                    self.acc[line + x0i + 1] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.acc[line + xi] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.acc[line + x1i - 1] += d * (1.0 - a2 - am);
+                }
+                self.acc[line + x1i] += d * am;
+            }
+            x = x_next;
+        }
+    }
+
+    /// 按非零环绕规则累加， 得到每个像素的覆盖率
+    pub fn coverage(&self) -> Vec<f32> {
+        let mut r = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            let line = &self.acc[y * self.stride..y * self.stride + self.width];
+            let mut sum = 0.0;
+            for a in line.iter() {
+                sum += *a;
+                r.push(sum.abs().min(1.0));
+            }
+        }
+        r
+    }
+}
+
+/// 将路径平坦化为闭合的线段， 并将字体单位的坐标转换为像素坐标(y轴向下)
+pub fn flatten(path: &[PathCmd], scale: f32, ox: f32, baseline: f32) -> Vec<((f32, f32), (f32, f32))> {
+    let mut lines = Vec::new();
+    let tr = |x: f32, y: f32| (ox + x * scale, baseline - y * scale);
+    let mut start = (0.0, 0.0);
+    let mut last = (0.0, 0.0);
+    for cmd in path.iter() {
+        match *cmd {
+            PathCmd::MoveTo(x, y) => {
+                push_line(&mut lines, last, start);
+                start = tr(x, y);
+                last = start;
+            }
+            PathCmd::LineTo(x, y) => {
+                let p = tr(x, y);
+                push_line(&mut lines, last, p);
+                last = p;
+            }
+            PathCmd::QuadTo(x1, y1, x, y) => {
+                let (p1, p2) = (tr(x1, y1), tr(x, y));
+                let n = segments(distance(last, p1) + distance(p1, p2));
+                let mut prev = last;
+                for i in 1..n + 1 {
+                    let t = i as f32 / n as f32;
+                    let mt = 1.0 - t;
+                    let p = (
+                        mt * mt * last.0 + 2.0 * mt * t * p1.0 + t * t * p2.0,
+                        mt * mt * last.1 + 2.0 * mt * t * p1.1 + t * t * p2.1,
+                    );
+                    push_line(&mut lines, prev, p);
+                    prev = p;
+                }
+                last = p2;
+            }
+            PathCmd::CubicTo(x1, y1, x2, y2, x, y) => {
+                let (p1, p2, p3) = (tr(x1, y1), tr(x2, y2), tr(x, y));
+                let n = segments(distance(last, p1) + distance(p1, p2) + distance(p2, p3));
+                let mut prev = last;
+                for i in 1..n + 1 {
+                    let t = i as f32 / n as f32;
+                    let mt = 1.0 - t;
+                    let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                    let p = (
+                        a * last.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                        a * last.1 + b * p1.1 + c * p2.1 + d * p3.1,
+                    );
+                    push_line(&mut lines, prev, p);
+                    prev = p;
+                }
+                last = p3;
+            }
+            PathCmd::Close => {
+                push_line(&mut lines, last, start);
+                last = start;
+            }
+        }
+    }
+    push_line(&mut lines, last, start);
+    lines
+}
+
+fn push_line(lines: &mut Vec<((f32, f32), (f32, f32))>, p0: (f32, f32), p1: (f32, f32)) {
+    if p0 != p1 {
+        lines.push((p0, p1));
+    }
+}
+
+// 根据曲线的估算长度计算平坦化的段数
+fn segments(len: f32) -> usize {
+    ((len / FLATTEN_STEP).ceil() as usize).max(1).min(64)
+}
+
+fn distance(p0: (f32, f32), p1: (f32, f32)) -> f32 {
+    ((p1.0 - p0.0) * (p1.0 - p0.0) + (p1.1 - p0.1) * (p1.1 - p0.1)).sqrt()
+}
+
+#[inline]
+fn read_u8(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).cloned()
+}
+
+#[inline]
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let b = data.get(offset..offset + 2)?;
+    Some(((b[0] as u16) << 8) | b[1] as u16)
+}
+
+#[inline]
+fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+    read_u16(data, offset).map(|r| r as i16)
+}
+
+#[inline]
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let b = data.get(offset..offset + 4)?;
+    Some(((b[0] as u32) << 24) | ((b[1] as u32) << 16) | ((b[2] as u32) << 8) | b[3] as u32)
+}
+
+// 选择cmap子表， 优先使用支持全部unicode的format12， 其次是format4
+fn find_cmap(data: &[u8], cmap: usize) -> Option<(usize, u16)> {
+    let count = read_u16(data, cmap + 2)? as usize;
+    let mut r = None;
+    for i in 0..count {
+        let record = cmap + 4 + i * 8;
+        let platform = read_u16(data, record)?;
+        let encoding = read_u16(data, record + 2)?;
+        let offset = cmap + read_u32(data, record + 4)? as usize;
+        let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+        if !unicode {
+            continue;
+        }
+        match read_u16(data, offset)? {
+            12 => return Some((offset, 12)),
+            4 if r.is_none() => r = Some((offset, 4)),
+            _ => (),
+        }
+    }
+    r
+}
+
+fn cmap_format4(data: &[u8], offset: usize, c: u32) -> Option<u16> {
+    let seg_x2 = read_u16(data, offset + 6)? as usize;
+    let ends = offset + 14;
+    let starts = ends + seg_x2 + 2;
+    let deltas = starts + seg_x2;
+    let ranges = deltas + seg_x2;
+    // endCode递增， 二分查找第一个endCode >= c的段
+    let (mut lo, mut hi) = (0, seg_x2 / 2);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if (read_u16(data, ends + mid * 2)? as u32) < c {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    if lo >= seg_x2 / 2 {
+        return None;
+    }
+    let start = read_u16(data, starts + lo * 2)? as u32;
+    if start > c {
+        return None;
+    }
+    let delta = read_u16(data, deltas + lo * 2)?;
+    let range_pos = ranges + lo * 2;
+    let range = read_u16(data, range_pos)? as usize;
+    if range == 0 {
+        return Some((c as u16).wrapping_add(delta));
+    }
+    let id = read_u16(data, range_pos + range + (c - start) as usize * 2)?;
+    if id == 0 {
+        None
+    } else {
+        Some(id.wrapping_add(delta))
+    }
+}
+
+fn cmap_format12(data: &[u8], offset: usize, c: u32) -> Option<u16> {
+    let count = read_u32(data, offset + 12)? as usize;
+    let (mut lo, mut hi) = (0, count);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let group = offset + 16 + mid * 12;
+        let start = read_u32(data, group)?;
+        let end = read_u32(data, group + 4)?;
+        if c < start {
+            hi = mid;
+        } else if c > end {
+            lo = mid + 1;
+        } else {
+            return Some((read_u32(data, group + 8)? + c - start) as u16);
+        }
+    }
+    None
+}
+
+// 取TrueType字形轮廓， transform为复合字形的变换矩阵(a, b, c, d, e, f)
+fn glyf_outline(
+    data: &[u8],
+    loca: usize,
+    glyf: usize,
+    long_loca: bool,
+    id: u16,
+    transform: &[f32; 6],
+    depth: usize,
+    path: &mut Vec<PathCmd>,
+) -> Option<()> {
+    let id = id as usize;
+    let (start, end) = if long_loca {
+        (read_u32(data, loca + id * 4)? as usize, read_u32(data, loca + id * 4 + 4)? as usize)
+    } else {
+        (read_u16(data, loca + id * 2)? as usize * 2, read_u16(data, loca + id * 2 + 2)? as usize * 2)
+    };
+    if end <= start {
+        return None; // 空字形， 如空格
+    }
+    let g = glyf + start;
+    let contours = read_i16(data, g)?;
+    if contours >= 0 {
+        return glyf_simple(data, g, contours as usize, transform, path);
+    }
+    if depth >= MAX_COMPONENT_DEPTH {
+        return None;
+    }
+
+    // 复合字形
+    let mut offset = g + 10;
+    loop {
+        let flags = read_u16(data, offset)?;
+        let component = read_u16(data, offset + 2)?;
+        offset += 4;
+        let (dx, dy) = if flags & 0x0001 != 0 {
+            offset += 4;
+            (read_i16(data, offset - 4)? as f32, read_i16(data, offset - 2)? as f32)
+        } else {
+            offset += 2;
+            (read_u8(data, offset - 2)? as i8 as f32, read_u8(data, offset - 1)? as i8 as f32)
+        };
+        // 使用点匹配定位的组件不常见， 按不偏移处理
+        let (dx, dy) = if flags & 0x0002 != 0 { (dx, dy) } else { (0.0, 0.0) };
+        let f2dot14 = |offset: usize| read_i16(data, offset).map(|r| r as f32 / 16384.0);
+        let (a, b, c, d) = if flags & 0x0008 != 0 {
+            offset += 2;
+            let s = f2dot14(offset - 2)?;
+            (s, 0.0, 0.0, s)
+        } else if flags & 0x0040 != 0 {
+            offset += 4;
+            (f2dot14(offset - 4)?, 0.0, 0.0, f2dot14(offset - 2)?)
+        } else if flags & 0x0080 != 0 {
+            offset += 8;
+            (f2dot14(offset - 8)?, f2dot14(offset - 6)?, f2dot14(offset - 4)?, f2dot14(offset - 2)?)
+        } else {
+            (1.0, 0.0, 0.0, 1.0)
+        };
+        // 组件变换与父变换相乘
+        let t = transform;
+        let m = [
+            a * t[0] + b * t[2],
+            a * t[1] + b * t[3],
+            c * t[0] + d * t[2],
+            c * t[1] + d * t[3],
+            dx * t[0] + dy * t[2] + t[4],
+            dx * t[1] + dy * t[3] + t[5],
+        ];
+        glyf_outline(data, loca, glyf, long_loca, component, &m, depth + 1, path);
+        if flags & 0x0020 == 0 {
+            break;
+        }
+    }
+    Some(())
+}
+
+fn glyf_simple(data: &[u8], g: usize, contours: usize, t: &[f32; 6], path: &mut Vec<PathCmd>) -> Option<()> {
+    let mut ends = Vec::with_capacity(contours);
+    for i in 0..contours {
+        ends.push(read_u16(data, g + 10 + i * 2)? as usize);
+    }
+    let count = match ends.last() {
+        Some(r) => r + 1,
+        None => return Some(()),
+    };
+    let instructions = g + 10 + contours * 2;
+    let mut offset = instructions + 2 + read_u16(data, instructions)? as usize;
+
+    // 标志位， 可能有重复
+    let mut flags = Vec::with_capacity(count);
+    while flags.len() < count {
+        let flag = read_u8(data, offset)?;
+        offset += 1;
+        flags.push(flag);
+        if flag & 0x08 != 0 {
+            let repeat = read_u8(data, offset)?;
+            offset += 1;
+            for _ in 0..repeat {
+                flags.push(flag);
+            }
+        }
+    }
+    flags.truncate(count);
+
+    // 坐标为相对前一个点的增量
+    let mut xs = Vec::with_capacity(count);
+    let mut v = 0i32;
+    for flag in flags.iter() {
+        if flag & 0x02 != 0 {
+            let dx = read_u8(data, offset)? as i32;
+            offset += 1;
+            v += if flag & 0x10 != 0 { dx } else { -dx };
+        } else if flag & 0x10 == 0 {
+            v += read_i16(data, offset)? as i32;
+            offset += 2;
+        }
+        xs.push(v as f32);
+    }
+    let mut ys = Vec::with_capacity(count);
+    v = 0;
+    for flag in flags.iter() {
+        if flag & 0x04 != 0 {
+            let dy = read_u8(data, offset)? as i32;
+            offset += 1;
+            v += if flag & 0x20 != 0 { dy } else { -dy };
+        } else if flag & 0x20 == 0 {
+            v += read_i16(data, offset)? as i32;
+            offset += 2;
+        }
+        ys.push(v as f32);
+    }
+
+    let point = |i: usize| {
+        let (x, y) = (xs[i], ys[i]);
+        (t[0] * x + t[2] * y + t[4], t[1] * x + t[3] * y + t[5])
+    };
+    let mid = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5);
+    let mut start = 0;
+    for end in ends.iter() {
+        let end = *end;
+        if end < start || end >= count {
+            return None;
+        }
+        let len = end - start + 1;
+        let on = |i: usize| flags[start + i % len] & 0x01 != 0;
+        let p = |i: usize| point(start + i % len);
+        // 找一个在曲线上的起点， 都不在曲线上时取首尾两点的中点
+        let (first, range) = if on(0) {
+            (p(0), 1..len)
+        } else if on(len - 1) {
+            (p(len - 1), 0..len - 1)
+        } else {
+            (mid(p(len - 1), p(0)), 0..len)
+        };
+        path.push(PathCmd::MoveTo(first.0, first.1));
+        let mut control: Option<(f32, f32)> = None;
+        for i in range {
+            let cur = p(i);
+            if on(i) {
+                match control.take() {
+                    Some(c) => path.push(PathCmd::QuadTo(c.0, c.1, cur.0, cur.1)),
+                    None => path.push(PathCmd::LineTo(cur.0, cur.1)),
+                }
+            } else {
+                if let Some(c) = control {
+                    let m = mid(c, cur);
+                    path.push(PathCmd::QuadTo(c.0, c.1, m.0, m.1));
+                }
+                control = Some(cur);
+            }
+        }
+        match control {
+            Some(c) => path.push(PathCmd::QuadTo(c.0, c.1, first.0, first.1)),
+            None => path.push(PathCmd::LineTo(first.0, first.1)),
+        }
+        path.push(PathCmd::Close);
+        start = end + 1;
+    }
+    Some(())
+}
+
+// CFF表中的INDEX结构
+#[derive(Clone, Copy, Default)]
+struct CffIndex {
+    count: usize,
+    off_size: usize,
+    offsets: usize, // 偏移数组的位置
+    data: usize,    // 数据的起始位置(偏移从1开始)
+    end: usize,
+}
+
+impl CffIndex {
+    fn new(data: &[u8], offset: usize) -> Option<CffIndex> {
+        let count = read_u16(data, offset)? as usize;
+        if count == 0 {
+            return Some(CffIndex {
+                end: offset + 2,
+                ..CffIndex::default()
+            });
+        }
+        let off_size = read_u8(data, offset + 2)? as usize;
+        if off_size == 0 || off_size > 4 {
+            return None;
+        }
+        let offsets = offset + 3;
+        let data_start = offsets + (count + 1) * off_size - 1;
+        let mut index = CffIndex {
+            count,
+            off_size,
+            offsets,
+            data: data_start,
+            end: 0,
+        };
+        index.end = data_start + index.offset(data, count)?;
+        Some(index)
+    }
+
+    fn offset(&self, data: &[u8], i: usize) -> Option<usize> {
+        let p = self.offsets + i * self.off_size;
+        let b = data.get(p..p + self.off_size)?;
+        Some(b.iter().fold(0, |r, b| (r << 8) | *b as usize))
+    }
+
+    fn get<'a>(&self, data: &'a [u8], i: usize) -> Option<&'a [u8]> {
+        if i >= self.count {
+            return None;
+        }
+        let start = self.data + self.offset(data, i)?;
+        let end = self.data + self.offset(data, i + 1)?;
+        data.get(start..end)
+    }
+}
+
+// 子程序的索引偏移
+fn subr_bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+// 解析DICT, 返回(操作符, 操作数)列表， 双字节操作符表示为1200 + 第二个字节
+fn parse_dict(dict: &[u8]) -> Vec<(u16, Vec<f32>)> {
+    let mut r = Vec::new();
+    let mut operands = Vec::new();
+    let mut i = 0;
+    while i < dict.len() {
+        let b0 = dict[i];
+        i += 1;
+        match b0 {
+            0..=21 => {
+                let op = if b0 == 12 {
+                    i += 1;
+                    1200 + *dict.get(i - 1).unwrap_or(&0) as u16
+                } else {
+                    b0 as u16
+                };
+                r.push((op, std::mem::replace(&mut operands, Vec::new())));
+            }
+            28 if i + 2 <= dict.len() => {
+                operands.push((((dict[i] as u16) << 8) | dict[i + 1] as u16) as i16 as f32);
+                i += 2;
+            }
+            29 if i + 4 <= dict.len() => {
+                let v = ((dict[i] as u32) << 24) | ((dict[i + 1] as u32) << 16) | ((dict[i + 2] as u32) << 8) | dict[i + 3] as u32;
+                operands.push(v as i32 as f32);
+                i += 4;
+            }
+            30 => {
+                // 实数， 按半字节编码
+                let mut s = String::new();
+                'real: while i < dict.len() {
+                    let b = dict[i];
+                    i += 1;
+                    for nibble in [b >> 4, b & 0x0f].iter() {
+                        match *nibble {
+                            0..=9 => s.push((b'0' + *nibble) as char),
+                            0xa => s.push('.'),
+                            0xb => s.push('E'),
+                            0xc => s.push_str("E-"),
+                            0xe => s.push('-'),
+                            0xf => break 'real,
+                            _ => (),
+                        }
+                    }
+                }
+                operands.push(s.parse().unwrap_or(0.0));
+            }
+            32..=246 => operands.push(b0 as f32 - 139.0),
+            247..=250 if i < dict.len() => {
+                operands.push(((b0 as i32 - 247) * 256 + dict[i] as i32 + 108) as f32);
+                i += 1;
+            }
+            251..=254 if i < dict.len() => {
+                operands.push((-(b0 as i32 - 251) * 256 - dict[i] as i32 - 108) as f32);
+                i += 1;
+            }
+            _ => break,
+        }
+    }
+    r
+}
+
+fn dict_get(dict: &[(u16, Vec<f32>)], op: u16) -> Option<&Vec<f32>> {
+    dict.iter().find(|r| r.0 == op).map(|r| &r.1)
+}
+
+// 私有字典中的局部子程序
+fn private_subrs(data: &[u8], cff: usize, dict: &[(u16, Vec<f32>)]) -> Option<CffIndex> {
+    let private = dict_get(dict, 18)?;
+    if private.len() < 2 {
+        return None;
+    }
+    let (size, offset) = (private[0] as usize, cff + private[1] as usize);
+    let private_dict = parse_dict(data.get(offset..offset + size)?);
+    let subrs = dict_get(&private_dict, 19)?;
+    CffIndex::new(data, offset + *subrs.get(0)? as usize)
+}
+
+struct Cff {
+    char_strings: CffIndex,
+    global_subrs: CffIndex,
+    local_subrs: Option<CffIndex>,
+    // CID字体， 每个字形根据FDSelect选择不同的局部子程序
+    fd_subrs: Vec<Option<CffIndex>>,
+    fd_select: Option<usize>,
+}
+
+impl Cff {
+    fn new(data: &[u8], cff: usize) -> Option<Cff> {
+        let header_size = read_u8(data, cff + 2)? as usize;
+        let names = CffIndex::new(data, cff + header_size)?;
+        let top_dicts = CffIndex::new(data, names.end)?;
+        let strings = CffIndex::new(data, top_dicts.end)?;
+        let global_subrs = CffIndex::new(data, strings.end)?;
+        let top = parse_dict(top_dicts.get(data, 0)?);
+
+        let char_strings = CffIndex::new(data, cff + *dict_get(&top, 17)?.get(0)? as usize)?;
+        let mut fd_subrs = Vec::new();
+        let mut fd_select = None;
+        if dict_get(&top, 1230).is_some() {
+            // ROS, CID字体
+            let fd_array = CffIndex::new(data, cff + *dict_get(&top, 1236)?.get(0)? as usize)?;
+            for i in 0..fd_array.count {
+                let fd = parse_dict(fd_array.get(data, i)?);
+                fd_subrs.push(private_subrs(data, cff, &fd));
+            }
+            fd_select = Some(cff + *dict_get(&top, 1237)?.get(0)? as usize);
+        }
+        Some(Cff {
+            local_subrs: private_subrs(data, cff, &top),
+            char_strings,
+            global_subrs,
+            fd_subrs,
+            fd_select,
+        })
+    }
+
+    // CID字体中字形所属的字体字典
+    fn fd_index(&self, data: &[u8], id: u16) -> Option<usize> {
+        let select = self.fd_select?;
+        match read_u8(data, select)? {
+            0 => read_u8(data, select + 1 + id as usize).map(|r| r as usize),
+            3 => {
+                let count = read_u16(data, select + 1)? as usize;
+                for i in 0..count {
+                    let range = select + 3 + i * 3;
+                    let next = read_u16(data, range + 3)?;
+                    if id < next {
+                        return read_u8(data, range + 2).map(|r| r as usize);
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn outline(&self, data: &[u8], id: u16, path: &mut Vec<PathCmd>) -> Option<()> {
+        let char_string = self.char_strings.get(data, id as usize)?;
+        let local = if self.fd_select.is_some() {
+            let fd = self.fd_index(data, id)?;
+            self.fd_subrs.get(fd).cloned().unwrap_or(None)
+        } else {
+            self.local_subrs
+        };
+        let mut ctx = CharStringCtx {
+            data,
+            global: self.global_subrs,
+            local,
+            stack: Vec::with_capacity(MAX_ARGS),
+            stems: 0,
+            width_parsed: false,
+            open: false,
+            x: 0.0,
+            y: 0.0,
+            path,
+        };
+        ctx.run(char_string, 0)?;
+        if ctx.open {
+            ctx.path.push(PathCmd::Close);
+        }
+        Some(())
+    }
+}
+
+// Type2 CharString解释器
+struct CharStringCtx<'a> {
+    data: &'a [u8],
+    global: CffIndex,
+    local: Option<CffIndex>,
+    stack: Vec<f32>,
+    stems: usize,
+    width_parsed: bool,
+    open: bool,
+    x: f32,
+    y: f32,
+    path: &'a mut Vec<PathCmd>,
+}
+
+impl<'a> CharStringCtx<'a> {
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        if self.open {
+            self.path.push(PathCmd::Close);
+        }
+        self.x += dx;
+        self.y += dy;
+        self.open = true;
+        self.path.push(PathCmd::MoveTo(self.x, self.y));
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        self.path.push(PathCmd::LineTo(self.x, self.y));
+    }
+
+    fn curve_to(&mut self, dx1: f32, dy1: f32, dx2: f32, dy2: f32, dx3: f32, dy3: f32) {
+        let (x1, y1) = (self.x + dx1, self.y + dy1);
+        let (x2, y2) = (x1 + dx2, y1 + dy2);
+        self.x = x2 + dx3;
+        self.y = y2 + dy3;
+        self.path.push(PathCmd::CubicTo(x1, y1, x2, y2, self.x, self.y));
+    }
+
+    // 第一个清栈操作符前， 多出来的参数是字形宽度， 丢弃
+    fn parse_width(&mut self, odd: bool) {
+        if !self.width_parsed {
+            self.width_parsed = true;
+            if odd && self.stack.len() > 0 {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    // 返回true表示遇到endchar
+    fn run(&mut self, code: &[u8], depth: usize) -> Option<bool> {
+        if depth > MAX_SUBR_DEPTH {
+            return None;
+        }
+        let mut i = 0;
+        while i < code.len() {
+            let b0 = code[i];
+            i += 1;
+            match b0 {
+                // 操作数
+                28 => {
+                    let b = code.get(i..i + 2)?;
+                    self.stack.push((((b[0] as u16) << 8) | b[1] as u16) as i16 as f32);
+                    i += 2;
+                }
+                32..=246 => self.stack.push(b0 as f32 - 139.0),
+                247..=250 => {
+                    self.stack.push(((b0 as i32 - 247) * 256 + *code.get(i)? as i32 + 108) as f32);
+                    i += 1;
+                }
+                251..=254 => {
+                    self.stack.push((-(b0 as i32 - 251) * 256 - *code.get(i)? as i32 - 108) as f32);
+                    i += 1;
+                }
+                255 => {
+                    let b = code.get(i..i + 4)?;
+                    let v = ((b[0] as u32) << 24) | ((b[1] as u32) << 16) | ((b[2] as u32) << 8) | b[3] as u32;
+                    self.stack.push(v as i32 as f32 / 65536.0);
+                    i += 4;
+                }
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    let odd = self.stack.len() % 2 == 1;
+                    self.parse_width(odd);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                // hintmask, cntrmask
+                19 | 20 => {
+                    let odd = self.stack.len() % 2 == 1;
+                    self.parse_width(odd);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    i += (self.stems + 7) / 8;
+                }
+                // rmoveto
+                21 => {
+                    let odd = self.stack.len() > 2;
+                    self.parse_width(odd);
+                    let s = self.take(2)?;
+                    self.move_to(s[0], s[1]);
+                }
+                // hmoveto
+                22 => {
+                    let odd = self.stack.len() > 1;
+                    self.parse_width(odd);
+                    let s = self.take(1)?;
+                    self.move_to(s[0], 0.0);
+                }
+                // vmoveto
+                4 => {
+                    let odd = self.stack.len() > 1;
+                    self.parse_width(odd);
+                    let s = self.take(1)?;
+                    self.move_to(0.0, s[0]);
+                }
+                // rlineto
+                5 => {
+                    let s = self.take(0)?;
+                    for p in s.chunks(2) {
+                        if p.len() == 2 {
+                            self.line_to(p[0], p[1]);
+                        }
+                    }
+                }
+                // hlineto, vlineto
+                6 | 7 => {
+                    let s = self.take(0)?;
+                    let mut horizontal = b0 == 6;
+                    for d in s.iter() {
+                        if horizontal {
+                            self.line_to(*d, 0.0);
+                        } else {
+                            self.line_to(0.0, *d);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                // rrcurveto
+                8 => {
+                    let s = self.take(0)?;
+                    for c in s.chunks(6) {
+                        if c.len() == 6 {
+                            self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                        }
+                    }
+                }
+                // rcurveline
+                24 => {
+                    let s = self.take(0)?;
+                    if s.len() < 2 {
+                        return None;
+                    }
+                    let (curves, line) = s.split_at(s.len() - 2);
+                    for c in curves.chunks(6) {
+                        if c.len() == 6 {
+                            self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                        }
+                    }
+                    self.line_to(line[0], line[1]);
+                }
+                // rlinecurve
+                25 => {
+                    let s = self.take(0)?;
+                    if s.len() < 6 {
+                        return None;
+                    }
+                    let (lines, c) = s.split_at(s.len() - 6);
+                    for p in lines.chunks(2) {
+                        if p.len() == 2 {
+                            self.line_to(p[0], p[1]);
+                        }
+                    }
+                    self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                }
+                // vvcurveto
+                26 => {
+                    let s = self.take(0)?;
+                    let (mut dx1, rest) = if s.len() % 2 == 1 { (s[0], &s[1..]) } else { (0.0, &s[..]) };
+                    for c in rest.chunks(4) {
+                        if c.len() == 4 {
+                            self.curve_to(dx1, c[0], c[1], c[2], 0.0, c[3]);
+                            dx1 = 0.0;
+                        }
+                    }
+                }
+                // hhcurveto
+                27 => {
+                    let s = self.take(0)?;
+                    let (mut dy1, rest) = if s.len() % 2 == 1 { (s[0], &s[1..]) } else { (0.0, &s[..]) };
+                    for c in rest.chunks(4) {
+                        if c.len() == 4 {
+                            self.curve_to(c[0], dy1, c[1], c[2], c[3], 0.0);
+                            dy1 = 0.0;
+                        }
+                    }
+                }
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let s = self.take(0)?;
+                    let mut horizontal = b0 == 31;
+                    let mut j = 0;
+                    while j + 4 <= s.len() {
+                        // 最后一条曲线可能多一个参数
+                        let last = if s.len() - j == 5 { s[j + 4] } else { 0.0 };
+                        if horizontal {
+                            self.curve_to(s[j], 0.0, s[j + 1], s[j + 2], last, s[j + 3]);
+                        } else {
+                            self.curve_to(0.0, s[j], s[j + 1], s[j + 2], s[j + 3], last);
+                        }
+                        j += 4;
+                        horizontal = !horizontal;
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let index = if b0 == 10 { self.local? } else { self.global };
+                    let i = self.stack.pop()? as i32 + subr_bias(index.count);
+                    if i < 0 {
+                        return None;
+                    }
+                    let subr = index.get(self.data, i as usize)?;
+                    if self.run(subr, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                // return
+                11 => return Some(false),
+                // endchar
+                14 => {
+                    let odd = self.stack.len() == 1 || self.stack.len() == 5;
+                    self.parse_width(odd);
+                    self.stack.clear();
+                    return Some(true);
+                }
+                12 => {
+                    let b1 = *code.get(i)?;
+                    i += 1;
+                    let s = self.take(0)?;
+                    match b1 {
+                        // flex
+                        35 if s.len() >= 12 => {
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                            self.curve_to(s[6], s[7], s[8], s[9], s[10], s[11]);
+                        }
+                        // hflex
+                        34 if s.len() >= 7 => {
+                            self.curve_to(s[0], 0.0, s[1], s[2], s[3], 0.0);
+                            self.curve_to(s[4], 0.0, s[5], -s[2], s[6], 0.0);
+                        }
+                        // hflex1
+                        36 if s.len() >= 9 => {
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], 0.0);
+                            self.curve_to(s[5], 0.0, s[6], s[7], s[8], -(s[1] + s[3] + s[7]));
+                        }
+                        // flex1, 最后一个点只在变化较大的方向上偏移， 另一方向回到起点
+                        37 if s.len() >= 11 => {
+                            let dx = s[0] + s[2] + s[4] + s[6] + s[8];
+                            let dy = s[1] + s[3] + s[5] + s[7] + s[9];
+                            let (dx6, dy6) = if dx.abs() > dy.abs() { (s[10], -dy) } else { (-dx, s[10]) };
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                            self.curve_to(s[6], s[7], s[8], s[9], dx6, dy6);
+                        }
+                        // 算术及其它操作符在字体中极少使用， 忽略
+                        _ => (),
+                    }
+                }
+                _ => self.stack.clear(),
+            }
+        }
+        Some(false)
+    }
+
+    // 取出栈中的全部参数并清栈， 参数少于count时返回None
+    fn take(&mut self, count: usize) -> Option<Vec<f32>> {
+        if self.stack.len() < count {
+            return None;
+        }
+        let s = std::mem::replace(&mut self.stack, Vec::with_capacity(MAX_ARGS));
+        Some(s)
+    }
+}
+
+#[test]
+fn test_native_font() {
+    let data = std::fs::read("../gui_web/static/res/kaiti.TTF").unwrap();
+    let font = NativeFont::new(data).unwrap();
+    assert!(font.ascent() > 0.0 && font.descent() > 0.0);
+    // 下划线在基线以下， 删除线在基线与上边界之间
+    assert!(font.underline().0 < 0.0 && font.underline().1 > 0.0);
+    assert!(font.strikeout().0 > 0.0 && font.strikeout().0 < font.ascent());
+    // 西文半角， 中文全角
+    assert_eq!(font.advance('A', 32.0), 16.0);
+    assert_eq!(font.advance('中', 32.0), 32.0);
+
+    let (width, height) = (32, ((font.ascent() + font.descent()) * 32.0).ceil() as usize);
+    let coverage = font.rasterize('中', 32.0, 0.0, font.ascent() * 32.0, width, height);
+    assert!(coverage.iter().any(|c| *c >= 1.0));
+    assert!(font.glyph_index('中') != 0);
+
+    // 没有描边时， 红色通道为0, 绿色与蓝色通道互补
+    let data = font.draw_char('中', 32.0, 0.0, false, width, height);
+    for p in data.chunks(4) {
+        assert_eq!(p[0], 0);
+        assert_eq!(p[1] as usize + p[2] as usize, 255);
+    }
+    let data = font.draw_char('中', 32.0, 2.0, false, width + 2, height + 2);
+    assert!(data.chunks(4).any(|p| p[0] > 0));
+
+    // sdf: 四角远离字形， 值为0, 字形内部大于0.5
+    let (width, height) = (42, height + 10);
+    let data = font.draw_sdf('中', 32.0, 5.0, 5.0, 5.0 + font.ascent() * 32.0, width, height);
+    assert_eq!(data[0], 0);
+    assert!(data.chunks(4).any(|p| p[0] > 128));
+    assert!(data.chunks(4).all(|p| p[0] == p[1] && p[1] == p[2]));
+}
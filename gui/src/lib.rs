@@ -55,6 +55,10 @@ extern crate hash;
 extern crate densevec;
 extern crate idtree;
 extern crate flex_layout;
+#[cfg(test)]
+extern crate hal_null;
+#[cfg(test)]
+extern crate png;
 
 pub mod system;
 pub mod component;
@@ -43,6 +43,39 @@ pub struct TextureRes {
     pub opacity: Opacity,
     pub compress: Option<CompressedTexFormat>,
     pub bind: HalTexture,
+    pub alpha_mask: Option<AlphaMask>, // 像素的不透明度， 用于按像素的命中测试， 由宿主按需提供
+}
+
+// 纹理每个像素的alpha值， 第0行对应v=0
+#[derive(Debug, Clone)]
+pub struct AlphaMask {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl AlphaMask {
+    // 从rgba像素数据中取出alpha
+    pub fn from_rgba(width: usize, height: usize, data: &[u8]) -> Self {
+        AlphaMask {
+            width,
+            height,
+            data: data.chunks(4).map(|p| if p.len() == 4 { p[3] } else { 255 }).collect(),
+        }
+    }
+
+    // uv处的alpha值， 超出纹理范围时返回0
+    pub fn get(&self, u: f32, v: f32) -> u8 {
+        if !(u >= 0.0 && u <= 1.0 && v >= 0.0 && v <= 1.0) || self.width == 0 || self.height == 0 {
+            return 0;
+        }
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        match self.data.get(y * self.width + x) {
+            Some(r) => *r,
+            None => 0,
+        }
+    }
 }
 
 // impl<> fmt::Debug for Point {
@@ -70,9 +103,16 @@ impl TextureRes {
             opacity,
             compress,
             bind,
+            alpha_mask: None,
         }
     }
 
+    // 设置像素的不透明度， 纹理资源创建后由宿主提供
+    pub fn set_alpha_mask(&self, mask: AlphaMask) {
+        let s = unsafe { &mut *(self as *const Self as *mut Self) };
+        s.alpha_mask = Some(mask);
+    }
+
     pub fn update_size(&self, width: usize, height: usize) {
         let s = unsafe { &mut *(self as *const Self as *mut Self) };
         s.width = width;
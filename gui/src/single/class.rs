@@ -21,10 +21,30 @@ pub struct Class {
 	pub class_style_mark2: usize, // 标记class中布局的有效属性
 }
 
+// 复杂选择器对应的class在class_map中的起始id
+pub const SELECTOR_CLASS_START: usize = 3000000000;
+
 // 全局Class表
+// 简单的class选择器（.123）直接以class id为键存放在class_map中， 节点通过ClassName引用
+// 复杂选择器（后代、子代、伪类）存放在selectors中， 其class以SELECTOR_CLASS_START + 序号为键存放在class_map中， 由StyleMarkSys匹配
+// 节点同时应用简单class与复杂选择器时， 按照优先级、再按照在样式表中的顺序设置
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ClassSheet {
     pub class_map: XHashMap<usize, Class>,
+    pub selectors: Vec<(Selector, usize)>, // (选择器, class id)， 按照在样式表中的顺序
+    pub keyframes: XHashMap<Atom, KeyFrames>, // @keyframes， 以名称为键
+    pub orders: XHashMap<usize, usize>, // class在样式表中的顺序， 未记录顺序的class视为最先
+    pub order: usize, // 下一条规则的顺序
+    pub subject_index: XHashMap<usize, Vec<usize>>, // 以最右边复合选择器的第一个class为键， 值为选择器在selectors中的序号
+    pub pseudo_subjects: Vec<usize>, // 最右边复合选择器不含class（只有伪类）的选择器在selectors中的序号
+    pub ancestor_classes: XHashMap<usize, usize>, // 祖先选择器中出现的class及其出现的次数
+    pub ancestor_pseudo: usize, // 祖先选择器中出现的伪类（PseudoClassType的组合）
+}
+
+// 关键帧， 对应css的@keyframes
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct KeyFrames {
+    pub frames: Vec<(f32, Class)>, // (进度 0.0 ~ 1.0, 该帧的样式)， 按进度从小到大排列
 }
 
 impl ClassSheet {
@@ -35,6 +55,155 @@ impl ClassSheet {
             r += v.attrs2.capacity() * std::mem::size_of::<Attribute2>();
             r += v.attrs3.capacity() * std::mem::size_of::<Attribute3>();
         }
+        r += self.selectors.capacity() * std::mem::size_of::<(Selector, usize)>();
+        r += self.orders.capacity() * std::mem::size_of::<(usize, usize)>();
+        for (_, v) in self.subject_index.iter() {
+            r += v.capacity() * std::mem::size_of::<usize>();
+        }
+        r += self.pseudo_subjects.capacity() * std::mem::size_of::<usize>();
+        r += self.ancestor_classes.capacity() * std::mem::size_of::<(usize, usize)>();
+        for (_, v) in self.keyframes.iter() {
+            r += v.frames.capacity() * std::mem::size_of::<(f32, Class)>();
+        }
+        r
+    }
+
+    // 添加一条简单class选择器的规则， 同一个class重复添加时， 以最后一次的顺序为准
+    pub fn add_class(&mut self, id: usize, class: Class) {
+        self.class_map.insert(id, class);
+        self.orders.insert(id, self.order);
+        self.order += 1;
+    }
+
+    // 添加一条复杂选择器的规则， 返回其class id
+    pub fn add_selector(&mut self, selector: Selector, class: Class) -> usize {
+        let index = self.selectors.len();
+        let id = SELECTOR_CLASS_START + index;
+        self.class_map.insert(id, class);
+        self.orders.insert(id, self.order);
+        self.order += 1;
+
+        // 以最右边的复合选择器建立索引， 匹配时只需检查节点的class对应的选择器
+        match selector.subject.classes.first() {
+            Some(c) => self.subject_index.entry(*c).or_insert_with(Vec::new).push(index),
+            None => self.pseudo_subjects.push(index),
+        }
+        for (_, compound) in selector.ancestors.iter() {
+            for c in compound.classes.iter() {
+                *self.ancestor_classes.entry(*c).or_insert(0) += 1;
+            }
+            self.ancestor_pseudo |= compound.pseudo;
+        }
+        self.selectors.push((selector, id));
+        id
+    }
+
+    // class在样式表中的顺序
+    #[inline]
+    pub fn order(&self, id: usize) -> usize {
+        match self.orders.get(&id) {
+            Some(r) => *r,
+            None => 0,
+        }
+    }
+
+    // 是否存在带祖先条件的选择器
+    #[inline]
+    pub fn has_ancestor_selector(&self) -> bool {
+        self.ancestor_classes.len() > 0 || self.ancestor_pseudo > 0
+    }
+
+    // 具有这些class、处于这些伪类状态的节点， 是否可能作为祖先影响子孙节点的选择器匹配
+    pub fn is_ancestor_key(&self, class_name: Option<&ClassName>, pseudo: usize) -> bool {
+        if pseudo & self.ancestor_pseudo != 0 {
+            return true;
+        }
+        let class_name = match class_name {
+            Some(r) => r,
+            None => return false,
+        };
+        self.ancestor_classes.contains_key(&class_name.one)
+            || self.ancestor_classes.contains_key(&class_name.two)
+            || class_name.other.iter().any(|c| self.ancestor_classes.contains_key(c))
+    }
+
+    // 最右边的复合选择器可能与节点匹配的选择器（在selectors中的序号）
+    pub fn candidate_selectors(&self, class_name: Option<&ClassName>, r: &mut Vec<usize>) {
+        r.extend_from_slice(self.pseudo_subjects.as_slice());
+        if let Some(class_name) = class_name {
+            if let Some(v) = self.subject_index.get(&class_name.one) {
+                r.extend_from_slice(v.as_slice());
+            }
+            if class_name.two != class_name.one {
+                if let Some(v) = self.subject_index.get(&class_name.two) {
+                    r.extend_from_slice(v.as_slice());
+                }
+            }
+            for c in class_name.other.iter() {
+                if let Some(v) = self.subject_index.get(c) {
+                    r.extend_from_slice(v.as_slice());
+                }
+            }
+        }
+        // 节点的class重复时， 同一个选择器会被加入多次
+        r.sort();
+        r.dedup();
+    }
+}
+
+// 组合符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Combinator {
+    Descendant, // 后代， 如：.1 .2
+    Child,      // 子代， 如：.1>.2
+}
+
+// 复合选择器， 如：.1.2:hover
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompoundSelector {
+    pub classes: Vec<usize>, // 需要同时具有的class
+    pub pseudo: usize,       // 需要同时处于的伪类状态（PseudoClassType的组合）
+}
+
+impl CompoundSelector {
+    pub fn matches(&self, class_name: Option<&ClassName>, pseudo: usize) -> bool {
+        if pseudo & self.pseudo != self.pseudo {
+            return false;
+        }
+        if self.classes.len() == 0 {
+            return true;
+        }
+        let class_name = match class_name {
+            Some(r) => r,
+            None => return false,
+        };
+        for c in self.classes.iter() {
+            if class_name.one != *c && class_name.two != *c && !class_name.other.contains(c) {
+                return false;
+            }
+        }
+        true
+    }
+
+    // 优先级（class与伪类的数量）
+    pub fn specificity(&self) -> usize {
+        self.classes.len() + self.pseudo.count_ones() as usize
+    }
+}
+
+// 选择器， 从右向左匹配
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selector {
+    pub subject: CompoundSelector, // 最右边的复合选择器， 即被选中的节点需要满足的条件
+    pub ancestors: Vec<(Combinator, CompoundSelector)>, // 由近到远的祖先选择器， 组合符为该祖先与其右边的复合选择器之间的关系
+}
+
+impl Selector {
+    pub fn specificity(&self) -> usize {
+        let mut r = self.subject.specificity();
+        for (_, c) in self.ancestors.iter() {
+            r += c.specificity();
+        }
         r
     }
 }
@@ -45,6 +214,7 @@ pub enum Attribute1 {
     PositionType(PositionType),
     FlexWrap(FlexWrap),
     FlexDirection(FlexDirection),
+    Direction(Direction),
     AlignContent(AlignContent),
     AlignItems(AlignItems),
     AlignSelf(AlignSelf),
@@ -58,7 +228,7 @@ pub enum Attribute1 {
     Enable(EnableType),
     Display(Display),
     Visibility(bool),
-    Overflow(bool),
+    Overflow(OverflowType),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -70,6 +240,12 @@ pub enum Attribute2 {
     FontWeight(f32),
     FontSize(FontSize),
     FontFamily(Atom),
+    TextOverflow(TextOverflow),
+    LineClamp(usize),
+    TextDecorationLine(usize),
+    TextDecorationStyle(TextDecorationStyle),
+    TextDecorationColor(Option<CgColor>),
+    TextDecorationThickness(f32),
     ZIndex(isize),
     Opacity(Opacity),
     BorderImageRepeat(BorderImageRepeat),
@@ -119,13 +295,17 @@ pub enum Attribute3 {
     BorderImageSlice(BorderImageSlice),
 
     Color(Color),
-    TextShadow(TextShadow),
+    TextShadow(Vec<TextShadow>),
     TextStroke(Stroke),
 
     BorderRadius(BorderRadius),
+    BorderRadiusCorner(usize, CornerRadius), // 单个角的圆角(按左上、右上、右下、左下的索引)， 只修改该角， 其它角保持不变
     TransformFunc(Vec<TransformFunc>),
     TransformOrigin(TransformOrigin),
     Filter(Filter),
+    Transition(Transition),
+    Animation(Animation),
+    ClipPath(ClipPath),
 }
 
 // #[derive(Debug, Clone, EnumDefault, Serialize, Deserialize)]
@@ -0,0 +1,283 @@
+/**
+ * 事件分发
+ * 宿主（web、native、测试）输入指针事件和滚轮事件， 由八叉树命中目标节点后， 沿IdTree的祖先路径分发：
+ * 捕获阶段（根 -> 目标的父节点）， 目标阶段（目标节点）， 冒泡阶段（目标的父节点 -> 根）
+ * 任意阶段调用stop_propagation， 不再分发到后续节点； 调用stop_immediate_propagation， 当前节点上剩余的监听器也不再调用
+*/
+use share::Share;
+
+use hash::XHashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventType {
+	PointerDown = 1,
+	PointerMove = 2,
+	PointerUp = 4,
+	PointerCancel = 8,
+	Wheel = 16,
+	Click = 32, // 由同一指针的PointerDown、PointerUp合成， 目标为两次命中节点的最近公共祖先
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+	None,
+	Capture,
+	Target,
+	Bubble,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+	pub ty: EventType,
+	pub target: usize, // 命中的节点
+	pub current_target: usize, // 正在处理事件的节点
+	pub phase: EventPhase,
+	pub pointer_id: usize,
+	pub x: f32,
+	pub y: f32,
+	pub button: usize, // 改变状态的按键， 0: 主键， 1: 中键， 2: 右键
+	pub buttons: usize, // 当前按下的所有按键， 1: 主键， 2: 右键， 4: 中键， 可以组合
+	pub delta_x: f32, // 滚轮的水平滚动量
+	pub delta_y: f32, // 滚轮的垂直滚动量
+	pub time: usize, // 事件产生的时间， 由宿主提供， 单位： 毫秒
+	stop: bool,
+	stop_immediate: bool,
+	default_prevented: bool,
+}
+
+impl Event {
+	pub fn new(ty: EventType, x: f32, y: f32) -> Self {
+		Event {
+			ty: ty,
+			target: 0,
+			current_target: 0,
+			phase: EventPhase::None,
+			pointer_id: 0,
+			x: x,
+			y: y,
+			button: 0,
+			buttons: 0,
+			delta_x: 0.0,
+			delta_y: 0.0,
+			time: 0,
+			stop: false,
+			stop_immediate: false,
+			default_prevented: false,
+		}
+	}
+
+	/// 阻止事件分发到后续节点
+	pub fn stop_propagation(&mut self) {
+		self.stop = true;
+	}
+
+	/// 阻止事件分发到后续节点， 并且当前节点上剩余的监听器也不再调用
+	pub fn stop_immediate_propagation(&mut self) {
+		self.stop = true;
+		self.stop_immediate = true;
+	}
+
+	/// 取消事件的默认行为（如：阻止PointerUp合成Click）
+	pub fn prevent_default(&mut self) {
+		self.default_prevented = true;
+	}
+
+	pub fn is_propagation_stopped(&self) -> bool {
+		self.stop
+	}
+
+	pub fn default_prevented(&self) -> bool {
+		self.default_prevented
+	}
+}
+
+pub type EventListener = Share<dyn Fn(&mut Event)>;
+
+struct ListenerItem {
+	node: usize,
+	ty: EventType,
+	capture: bool, // 是否在捕获阶段调用（目标阶段总会调用）
+	listener: EventListener,
+}
+
+/// 事件监听器的注册表， 以及每个指针按下时命中的节点
+#[derive(Default)]
+pub struct EventDispatcher {
+	listeners: XHashMap<usize, ListenerItem>,
+	node_listeners: XHashMap<usize, Vec<usize>>, // 节点 -> 监听器id， 按注册顺序
+	pointers: XHashMap<usize, usize>, // 指针id -> 按下时命中的节点
+	listener_index: usize,
+}
+
+impl EventDispatcher {
+	/// 为节点注册监听器， 返回监听器id（从1开始）
+	pub fn add_listener(&mut self, node: usize, ty: EventType, capture: bool, listener: EventListener) -> usize {
+		self.listener_index += 1;
+		let id = self.listener_index;
+		self.listeners.insert(id, ListenerItem {
+			node: node,
+			ty: ty,
+			capture: capture,
+			listener: listener,
+		});
+		self.node_listeners.entry(node).or_insert_with(Vec::new).push(id);
+		id
+	}
+
+	/// 删除监听器， 返回监听器是否存在
+	pub fn remove_listener(&mut self, id: usize) -> bool {
+		let item = match self.listeners.remove(&id) {
+			Some(r) => r,
+			None => return false,
+		};
+		let empty = match self.node_listeners.get_mut(&item.node) {
+			Some(r) => {
+				r.retain(|i| *i != id);
+				r.len() == 0
+			}
+			None => false,
+		};
+		if empty {
+			self.node_listeners.remove(&item.node);
+		}
+		true
+	}
+
+	/// 删除节点上的所有监听器（节点销毁时调用）
+	pub fn remove_node(&mut self, node: usize) {
+		if let Some(ids) = self.node_listeners.remove(&node) {
+			for id in ids.into_iter() {
+				self.listeners.remove(&id);
+			}
+		}
+		self.pointers.retain(|_, target| *target != node);
+	}
+
+	/// 节点上是否存在指定类型的监听器
+	pub fn has_listener(&self, node: usize, ty: EventType) -> bool {
+		match self.node_listeners.get(&node) {
+			Some(ids) => ids.iter().any(|id| self.listeners[id].ty == ty),
+			None => false,
+		}
+	}
+
+	/// 记录指针按下时命中的节点
+	pub fn pointer_down(&mut self, pointer_id: usize, target: usize) {
+		self.pointers.insert(pointer_id, target);
+	}
+
+	/// 指针抬起或取消， 返回按下时命中的节点
+	pub fn pointer_release(&mut self, pointer_id: usize) -> Option<usize> {
+		self.pointers.remove(&pointer_id)
+	}
+
+	/// 沿路径分发事件， path为目标节点及其所有祖先（path[0]为目标， 最后一个为根）， 返回事件是否被取消默认行为
+	/// 分发前会复制每个节点上的监听器列表， 监听器中注册或删除监听器， 只影响之后的事件
+	pub fn dispatch(&self, path: &[usize], event: &mut Event) -> bool {
+		let target = match path.first() {
+			Some(r) => *r,
+			None => return event.default_prevented,
+		};
+		event.target = target;
+
+		// 捕获阶段
+		for node in path[1..].iter().rev() {
+			if self.call(*node, EventPhase::Capture, event) {
+				return event.default_prevented;
+			}
+		}
+		// 目标阶段
+		if self.call(target, EventPhase::Target, event) {
+			return event.default_prevented;
+		}
+		// 冒泡阶段
+		for node in path[1..].iter() {
+			if self.call(*node, EventPhase::Bubble, event) {
+				return event.default_prevented;
+			}
+		}
+		event.current_target = 0;
+		event.phase = EventPhase::None;
+		event.default_prevented
+	}
+
+	// 调用节点上当前阶段的监听器， 返回是否停止分发
+	fn call(&self, node: usize, phase: EventPhase, event: &mut Event) -> bool {
+		let listeners: Vec<EventListener> = match self.node_listeners.get(&node) {
+			Some(ids) => ids
+				.iter()
+				.map(|id| &self.listeners[id])
+				.filter(|r| {
+					r.ty == event.ty
+						&& match phase {
+							EventPhase::Capture => r.capture,
+							EventPhase::Bubble => !r.capture,
+							_ => true,
+						}
+				})
+				.map(|r| r.listener.clone())
+				.collect(),
+			None => return false,
+		};
+		event.current_target = node;
+		event.phase = phase;
+		for listener in listeners.iter() {
+			listener(event);
+			if event.stop_immediate {
+				break;
+			}
+		}
+		event.stop
+	}
+}
+
+#[cfg(test)]
+use std::cell::RefCell;
+#[cfg(test)]
+use std::rc::Rc;
+
+#[test]
+fn test_dispatch() {
+	let log = Rc::new(RefCell::new(Vec::new()));
+	let mut dispatcher = EventDispatcher::default();
+	let listen = |dispatcher: &mut EventDispatcher, node: usize, capture: bool, stop: bool| {
+		let log = log.clone();
+		dispatcher.add_listener(node, EventType::PointerDown, capture, Share::new(move |e: &mut Event| {
+			log.borrow_mut().push((e.current_target, e.phase));
+			if stop {
+				e.stop_propagation();
+			}
+		}))
+	};
+	// 路径： 3(目标) -> 2 -> 1(根)
+	listen(&mut dispatcher, 1, true, false);
+	listen(&mut dispatcher, 1, false, false);
+	let l2 = listen(&mut dispatcher, 2, false, false);
+	listen(&mut dispatcher, 3, false, false);
+	listen(&mut dispatcher, 3, true, false);
+	dispatcher.add_listener(3, EventType::Click, false, Share::new(|_e: &mut Event| panic!()));
+
+	let mut e = Event::new(EventType::PointerDown, 0.0, 0.0);
+	dispatcher.dispatch(&[3, 2, 1], &mut e);
+	assert_eq!(e.target, 3);
+	assert_eq!(*log.borrow(), vec![
+		(1, EventPhase::Capture),
+		(3, EventPhase::Target),
+		(3, EventPhase::Target),
+		(2, EventPhase::Bubble),
+		(1, EventPhase::Bubble),
+	]);
+
+	// 在节点2冒泡时停止， 根节点的冒泡监听器不再调用
+	log.borrow_mut().clear();
+	dispatcher.remove_listener(l2);
+	listen(&mut dispatcher, 2, false, true);
+	let mut e = Event::new(EventType::PointerDown, 0.0, 0.0);
+	dispatcher.dispatch(&[3, 2, 1], &mut e);
+	assert_eq!(log.borrow().last(), Some(&(2, EventPhase::Bubble)));
+	assert!(e.is_propagation_stopped());
+
+	dispatcher.remove_node(3);
+	assert!(!dispatcher.has_listener(3, EventType::Click));
+	assert!(dispatcher.has_listener(2, EventType::PointerDown));
+}
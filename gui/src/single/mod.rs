@@ -4,6 +4,10 @@ pub mod class;
 */
 pub mod oct;
 pub mod style_parse;
+pub mod event;
+pub mod scroll;
+pub mod virtual_list;
+pub mod text_input;
 
 use share::Share;
 use std::any::{Any, TypeId};
@@ -26,11 +30,20 @@ use render::res::*;
 
 pub use single::class::*;
 pub use single::oct::Oct;
+pub use single::event::*;
+pub use single::scroll::*;
+pub use single::virtual_list::*;
+pub use single::text_input::*;
+
+// 裁剪纹理的位数（rgba每个通道8位）， 包围盒不相交的裁剪区域可以共用一位， 因此裁剪区域的数量不受该值限制
+pub const CLIP_TEXTURE_BITS: usize = 32;
 
 pub struct OverflowClip {
     pub id_map: XHashMap<usize, usize>,
-    pub clip: Slab<Clip>, //[[Point2;4];16], //Vec<(clip_view, has_rotate, old_has_rotate)> 最多32个
-    pub clip_map: XHashMap<usize, (Aabb3, Share<dyn UniformBuffer>)>,
+    pub clip: Slab<Clip>, // 裁剪区域， 数量不受限制
+    pub clip_map: XHashMap<usize, (Aabb3, Share<dyn UniformBuffer>)>, // 每个裁剪区域与其所有祖先裁剪区域的aabb求交的结果
+    pub mode: ClipMode, // 旋转或非矩形裁剪区域的裁剪方式
+    pub stencil: bool, // 默认渲染目标是否有模板缓冲区， 没有时模板模式退回到裁剪纹理
     // pub id_vec: [usize;16],
     // pub clip: [[Point2;4];16],
 }
@@ -39,11 +52,59 @@ pub struct OverflowClip {
 pub struct Clip {
     pub view: [Point2; 4],
     pub has_rotate: bool,
-    pub old_has_rotate: bool,
+    pub old_has_rotate: bool, // 上一次是否使用裁剪纹理（旋转或非矩形）
     pub node_id: usize,
+    pub shape: Option<(Vec<f32>, Vec<u16>)>, // 非矩形（圆角、clip-path）的裁剪区域在世界坐标系下的三角形网格(positions, indices)
+    pub parent: usize, // 外层的裁剪区域， 0表示没有
+    pub texture_index: usize, // 在裁剪纹理中的位（从1开始）， 0表示不在裁剪纹理中
+    pub texture_mask: usize, // 自身及所有外层裁剪区域在裁剪纹理中的位
+}
+
+impl Clip {
+    // 是否需要用裁剪纹理裁剪（旋转或非矩形的裁剪区域无法用aabb表示）
+    #[inline]
+    pub fn use_texture(&self) -> bool {
+        self.has_rotate || self.shape.is_some()
+    }
+
+    // 裁剪区域在世界坐标系下的包围盒(min_x, min_y, max_x, max_y)
+    pub fn bound(&self) -> (f32, f32, f32, f32) {
+        let mut r = (std::f32::MAX, std::f32::MAX, std::f32::MIN, std::f32::MIN);
+        let mut add = |x: f32, y: f32| {
+            r = (r.0.min(x), r.1.min(y), r.2.max(x), r.3.max(y));
+        };
+        for p in self.view.iter() {
+            add(p.x, p.y);
+        }
+        if let Some((positions, _)) = &self.shape {
+            for p in positions.chunks_exact(2) {
+                add(p[0], p[1]);
+            }
+        }
+        r
+    }
+}
+
+// 旋转或非矩形裁剪区域的裁剪方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClipMode {
+    Texture, // 使用裁剪纹理， 受纹理分辨率影响， 边缘可能有锯齿
+    Stencil, // 使用模板缓冲区， 像素精确， 但每个裁剪区域需要额外的drawcall
 }
 
 impl OverflowClip {
+    // 裁剪区域是否需要用模板缓冲区裁剪（模板模式下的旋转或非矩形裁剪区域， 或者裁剪纹理已满时）
+    #[inline]
+    pub fn use_stencil(&self, c: &Clip) -> bool {
+        self.stencil && c.use_texture() && (self.mode == ClipMode::Stencil || c.texture_index == 0)
+    }
+
+    // 旋转或非矩形的裁剪区域是否优先使用裁剪纹理（没有模板缓冲区时， 模板模式也使用裁剪纹理）
+    #[inline]
+    pub fn prefer_texture(&self) -> bool {
+        self.mode == ClipMode::Texture || !self.stencil
+    }
+
     pub fn mem_size(&self) -> usize {
         2 * self.id_map.capacity() * std::mem::size_of::<usize>()
             + self.clip.mem_size()
@@ -51,6 +112,30 @@ impl OverflowClip {
                 * (std::mem::size_of::<usize>()
                     + std::mem::size_of::<(Aabb3, Share<dyn UniformBuffer>)>())
     }
+    // 为裁剪区域i在裁剪纹理中分配一位， 返回位的序号（从1开始）， 没有可用的位时返回0
+    // 包围盒不相交的裁剪区域可以共用一位： 节点同时受所在裁剪链的包围盒（clipBox）裁剪， 不会采样到其它裁剪区域写入的同一位
+    // 原来的位仍然可用时保留原来的位， 裁剪区域移动后与共用该位的裁剪区域相交时， 重新分配
+    pub fn alloc_texture_index(&self, i: usize) -> usize {
+        let c = &self.clip[i];
+        let bound = c.bound();
+        // 与i相交的裁剪区域占用的位
+        let mut used = 0;
+        for (j, other) in self.clip.iter() {
+            if j != i && other.texture_index > 0 && is_bound_intersect(&bound, &other.bound()) {
+                used |= 1 << (other.texture_index - 1);
+            }
+        }
+        if c.texture_index > 0 && used & (1 << (c.texture_index - 1)) == 0 {
+            return c.texture_index;
+        }
+        for index in 0..CLIP_TEXTURE_BITS {
+            if used & (1 << index) == 0 {
+                return index + 1;
+            }
+        }
+        0
+    }
+
     pub fn insert_aabb(
         &mut self,
         key: usize,
@@ -77,12 +162,20 @@ impl OverflowClip {
 }
 
 
+// 两个包围盒是否相交， 边缘相距不足一个像素也视为相交， 避免光栅化时共用边缘上的像素
+#[inline]
+fn is_bound_intersect(a: &(f32, f32, f32, f32), b: &(f32, f32, f32, f32)) -> bool {
+    a.0 < b.2 + 1.0 && b.0 < a.2 + 1.0 && a.1 < b.3 + 1.0 && b.1 < a.3 + 1.0
+}
+
 impl Default for OverflowClip {
     fn default() -> Self {
         let mut r = Self {
             id_map: XHashMap::default(),
             clip: Slab::default(),
             clip_map: XHashMap::default(),
+            mode: ClipMode::Texture,
+            stencil: true,
         };
         r.insert_aabb(
             0,
@@ -156,6 +249,7 @@ pub enum ImageType {
     ImageLocal,
     BorderImageClass,
     BorderImageLocal,
+    TextSpan, // 富文本片段的行内图片
 }
 
 #[derive(Debug)]
@@ -436,6 +530,30 @@ pub struct SystemTime {
 	pub cur_time: usize,
 }
 
+/// 本帧被关键帧动画写入的属性， 节点id -> 属性标记（1 << TransitionProperty）
+/// 过渡系统不会为这些属性产生过渡
+#[derive(Default)]
+pub struct AnimatedProperties(pub XHashMap<usize, usize>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationEventType {
+	Start,
+	Iteration,
+	End,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationEvent {
+	pub id: usize,
+	pub name: Atom,
+	pub ty: AnimationEventType,
+	pub elapsed_time: usize, // 动画已播放的时间（不含延迟）， 单位： 毫秒
+}
+
+/// 上一帧产生的动画事件， 每帧开始时清空， 外部应该在每帧运行之后处理
+#[derive(Default)]
+pub struct AnimationEvents(pub Vec<AnimationEvent>);
+
 #[derive(Deref, DerefMut, Default)]
 pub struct IdTree(idtree::IdTree<usize>);
 
@@ -0,0 +1,98 @@
+/**
+ * 滚动容器的状态
+ * overflow为scroll、auto的节点为滚动容器， 由ScrollSys维护其内容尺寸、惯性速度及回弹， 滚动偏移记录在ScrollOffset组件中
+ * 指针拖动由事件系统输入（见system::scroll）， 同一时刻每个指针最多拖动一个滚动容器
+*/
+use hash::XHashMap;
+
+use component::user::OverflowType;
+
+// 滚动容器的状态
+#[derive(Debug, Clone)]
+pub struct ScrollState {
+	pub overflow: OverflowType,
+	pub view_width: f32, // 可视区域（内边距框）的尺寸
+	pub view_height: f32,
+	pub content_width: f32, // 内容（子节点及内边距）的尺寸
+	pub content_height: f32,
+	pub velocity: (f32, f32), // 惯性滚动的速度， 单位： 像素/毫秒
+	pub time: usize, // 上次推进惯性滚动的时间
+	pub dragging: bool, // 是否正在被指针拖动
+	pub target: Option<ScrollTarget>, // 由scroll_to、scroll_into_view设置的滚动目标， 下一帧生效
+	pub bar_dirty: bool, // 滚动条是否需要重新计算
+}
+
+// 滚动目标， duration为0时直接跳到目标位置， 否则以缓出曲线平滑滚动
+#[derive(Debug, Clone)]
+pub struct ScrollTarget {
+	pub x: f32,
+	pub y: f32,
+	pub duration: usize, // 单位： 毫秒
+	pub from: Option<(f32, f32, usize)>, // 平滑滚动的起点及开始时间， 开始后设置
+}
+
+impl ScrollState {
+	pub fn new(overflow: OverflowType) -> Self {
+		ScrollState {
+			overflow: overflow,
+			view_width: 0.0,
+			view_height: 0.0,
+			content_width: 0.0,
+			content_height: 0.0,
+			velocity: (0.0, 0.0),
+			time: 0,
+			dragging: false,
+			target: None,
+			bar_dirty: true,
+		}
+	}
+
+	// 水平方向的最大滚动偏移
+	#[inline]
+	pub fn max_x(&self) -> f32 {
+		(self.content_width - self.view_width).max(0.0)
+	}
+
+	// 垂直方向的最大滚动偏移
+	#[inline]
+	pub fn max_y(&self) -> f32 {
+		(self.content_height - self.view_height).max(0.0)
+	}
+
+	// 是否可以水平滚动
+	#[inline]
+	pub fn scroll_x(&self) -> bool {
+		self.max_x() > 0.0
+	}
+
+	// 是否可以垂直滚动
+	#[inline]
+	pub fn scroll_y(&self) -> bool {
+		self.max_y() > 0.0
+	}
+}
+
+// 一个指针的拖动
+#[derive(Debug, Clone)]
+pub struct ScrollDrag {
+	pub containers: Vec<usize>, // 按下位置上的滚动容器， 由内向外
+	pub node: usize, // 被拖动的滚动容器， 0表示指针还未移动到拖动阈值之外
+	pub start: (f32, f32), // 按下的位置
+	pub last: (f32, f32), // 上一次移动的位置
+	pub last_time: usize, // 上一次移动的时间（宿主提供的事件时间）
+	pub velocity: (f32, f32), // 拖动的速度， 单位： 像素/毫秒
+}
+
+/// 所有滚动容器的状态， 以及每个指针的拖动
+#[derive(Default)]
+pub struct ScrollStates {
+	pub states: XHashMap<usize, ScrollState>, // 节点 -> 滚动状态
+	pub drags: XHashMap<usize, ScrollDrag>, // 指针id -> 拖动
+}
+
+impl ScrollStates {
+	// 节点被指针拖动时， 返回拖动它的指针
+	pub fn drag_pointer(&self, node: usize) -> Option<usize> {
+		self.drags.iter().find(|(_, d)| d.node == node).map(|(id, _)| *id)
+	}
+}
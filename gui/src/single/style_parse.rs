@@ -27,6 +27,147 @@ pub fn parse_class_map_from_string(value: &str) -> Result<XHashMap<usize, Class>
     Ok(map)
 }
 
+// 解析样式表到class_sheet中
+// 除了简单的class选择器（.123）， 还支持后代（.1 .2）、子代（.1>.2）选择器， 伪类（:hover、:active、:disabled）， 以及逗号分隔的选择器列表
+// @keyframes规则解析到class_sheet.keyframes中
+pub fn parse_class_sheet_from_string(value: &str, class_sheet: &mut ClassSheet) -> Result<(), String> {
+    let mut parser = ClassMapParser(value);
+    loop {
+        let (selectors, body) = match parser.next_rule() {
+            Ok(r) => match r {
+                Some(r) => r,
+                None => break,
+            },
+            Err(_) => continue,
+        };
+        if selectors.starts_with("@keyframes") {
+            let name = selectors["@keyframes".len()..].trim();
+            class_sheet.keyframes.insert(Atom::from(name), parse_keyframes(body)?);
+            continue;
+        }
+        let class = parse_class_from_string(body)?;
+        for selector in selectors.split(",") {
+            let selector = selector.trim();
+            // 简单的class选择器， 走class id的快速路径
+            if let Some(id) = parse_class_id(selector) {
+                class_sheet.add_class(id, class.clone());
+                continue;
+            }
+            match parse_selector(selector) {
+                Ok(r) => {
+                    class_sheet.add_selector(r, class.clone());
+                }
+                Err(r) => debug_println!("err: {}, selector: {}", r, selector),
+            };
+        }
+    }
+    Ok(())
+}
+
+// 解析@keyframes的内容， 如：from{opacity:0} 50%{opacity:0.8} to{opacity:1}
+pub fn parse_keyframes(value: &str) -> Result<KeyFrames, String> {
+    let mut parser = ClassMapParser(value);
+    let mut frames = Vec::new();
+    loop {
+        let (selectors, body) = match parser.next_rule()? {
+            Some(r) => r,
+            None => break,
+        };
+        let class = parse_class_from_string(body)?;
+        for selector in selectors.split(",") {
+            let offset = match selector.trim() {
+                "from" => 0.0,
+                "to" => 1.0,
+                r => parse_percent_to_f32(r)?,
+            };
+            // 偏移必须在0%到100%之间， NaN等非法值会导致排序失败
+            if !(offset >= 0.0 && offset <= 1.0) {
+                return Err(format!("invalid keyframe offset: {}", selector));
+            }
+            frames.push((offset, class.clone()));
+        }
+    }
+    frames.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    Ok(KeyFrames { frames })
+}
+
+// 解析选择器， 如：.1 .2>.3:hover
+pub fn parse_selector(value: &str) -> Result<Selector, String> {
+    let tokens = value.replace(">", " > ");
+    let mut compounds: Vec<(Combinator, CompoundSelector)> = Vec::new();
+    let mut combinator = None;
+    for token in tokens.split_whitespace() {
+        if token == ">" {
+            if combinator.is_some() || compounds.len() == 0 {
+                return Err(format!("invalid selector: {}", value));
+            }
+            combinator = Some(Combinator::Child);
+            continue;
+        }
+        // 组合符为该复合选择器与其左边的复合选择器之间的关系
+        compounds.push((
+            combinator.take().unwrap_or(Combinator::Descendant),
+            parse_compound_selector(token)?,
+        ));
+    }
+    if combinator.is_some() {
+        return Err(format!("invalid selector: {}", value));
+    }
+
+    let (mut combinator, subject) = match compounds.pop() {
+        Some(r) => r,
+        None => return Err(format!("selector is empty: {}", value)),
+    };
+    let mut ancestors = Vec::with_capacity(compounds.len());
+    while let Some((c, compound)) = compounds.pop() {
+        ancestors.push((combinator, compound));
+        combinator = c;
+    }
+    Ok(Selector { subject, ancestors })
+}
+
+// 解析复合选择器， 如：.1.2:hover
+fn parse_compound_selector(value: &str) -> Result<CompoundSelector, String> {
+    let mut r = CompoundSelector::default();
+    if value == "*" {
+        return Ok(r);
+    }
+    let mut rest = value;
+    while let Some(kind) = rest.chars().next() {
+        let start = kind.len_utf8();
+        let end = match rest[start..].find(|c| c == '.' || c == ':') {
+            Some(i) => i + start,
+            None => rest.len(),
+        };
+        let name = &rest[start..end];
+        match kind {
+            '.' => match usize::from_str(name) {
+                Ok(id) => r.classes.push(id),
+                Err(_) => return Err(format!("invalid class: {}", value)),
+            },
+            ':' => {
+                r.pseudo |= match name {
+                    "hover" => PseudoClassType::Hover as usize,
+                    "active" => PseudoClassType::Active as usize,
+                    "disabled" => PseudoClassType::Disabled as usize,
+                    _ => return Err(format!("unsupported pseudo class: {}", name)),
+                }
+            }
+            _ => return Err(format!("invalid selector: {}", value)),
+        }
+        rest = &rest[end..];
+    }
+    Ok(r)
+}
+
+// 简单的class选择器（.123）的class id
+fn parse_class_id(value: &str) -> Option<usize> {
+    match value.get(1..) {
+        Some(r) => usize::from_str(r).ok(),
+        None => None,
+    }
+}
+
 pub fn parse_class_from_string(value: &str) -> Result<Class, String> {
     let mut class = Class::default();
     for p in value.split(";") {
@@ -54,6 +195,17 @@ struct ClassMapParser<'a>(&'a str);
 
 impl<'a> ClassMapParser<'a> {
     fn next_class(&mut self) -> Result<Option<(usize, &'a str)>, String> {
+        match self.next_rule()? {
+            Some((selector, body)) => match parse_class_id(selector) {
+                Some(r) => Ok(Some((r, body))),
+                None => Err("".to_string()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    // 取下一条规则， 返回(选择器, 样式)
+    fn next_rule(&mut self) -> Result<Option<(&'a str, &'a str)>, String> {
         let i = match self.0.find("{") {
             Some(i) => i,
             None => return Ok(None),
@@ -63,16 +215,7 @@ impl<'a> ClassMapParser<'a> {
             None => return Ok(None),
         };
 
-        let r = (
-            match usize::from_str(&self.0[..i].trim()[1..]) {
-                Ok(r) => r,
-                Err(_) => {
-                    self.0 = &self.0[j + 1..];
-                    return Err("".to_string());
-                }
-            },
-            self.0[i + 1..j].trim(),
-        );
+        let r = (self.0[..i].trim(), self.0[i + 1..j].trim());
         self.0 = &self.0[j + 1..];
         Ok(Some(r))
     }
@@ -120,6 +263,11 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
                     parse_linear_gradient_color_string(value)?,
                 )));
                 class.class_style_mark |= StyleType::BackgroundColor as usize;
+            } else if value.starts_with("radial-gradient") {
+                class.attrs3.push(Attribute3::BGColor(BackgroundColor(
+                    parse_radial_gradient_color_string(value)?,
+                )));
+                class.class_style_mark |= StyleType::BackgroundColor as usize;
             } else {
                 println!("background err: {}", value);
                 return Ok(());
@@ -147,6 +295,11 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
                     parse_linear_gradient_color_string(value)?,
                 )));
                 class.class_style_mark |= StyleType::BackgroundColor as usize;
+            } else if value.starts_with("radial-gradient") {
+                class.attrs3.push(Attribute3::BGColor(BackgroundColor(
+                    parse_radial_gradient_color_string(value)?,
+                )));
+                class.class_style_mark |= StyleType::BackgroundColor as usize;
             } else {
 				class.attrs2.push(Attribute2::ImageUrl(parse_url(value)?.get_hash()));
 				class.class_style_mark |= StyleType::Image as usize;
@@ -199,7 +352,11 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
             class.class_style_mark |= StyleType::BackgroundColor as usize;
 		}
 		"text-gradient" => {
-			class.attrs3.push(Attribute3::Color(parse_linear_gradient_color_string(value)?));
+			if value.starts_with("radial-gradient") {
+				class.attrs3.push(Attribute3::Color(parse_radial_gradient_color_string(value)?));
+			} else {
+				class.attrs3.push(Attribute3::Color(parse_linear_gradient_color_string(value)?));
+			}
 			class.class_style_mark |= StyleType::Color as usize;
 		}
         "color" => {
@@ -236,6 +393,50 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
                 .push(Attribute3::TextShadow(parse_text_shadow(value)?));
             class.class_style_mark |= StyleType::TextShadow as usize;
         }
+        "text-overflow" => {
+            class
+                .attrs2
+                .push(Attribute2::TextOverflow(parse_text_overflow(value)?));
+            class.class_style_mark1 |= StyleType1::TextOverflow as usize;
+        }
+        "text-decoration" => {
+            let r = parse_text_decoration(value)?;
+            class.attrs2.push(Attribute2::TextDecorationLine(r.line));
+            class.attrs2.push(Attribute2::TextDecorationStyle(r.style));
+            class.attrs2.push(Attribute2::TextDecorationColor(r.color));
+            class.attrs2.push(Attribute2::TextDecorationThickness(r.thickness));
+            class.class_style_mark1 |= StyleType1::TextDecoration as usize;
+        }
+        "text-decoration-line" => {
+            class
+                .attrs2
+                .push(Attribute2::TextDecorationLine(parse_text_decoration_line(value)?));
+            class.class_style_mark1 |= StyleType1::TextDecoration as usize;
+        }
+        "text-decoration-style" => {
+            class
+                .attrs2
+                .push(Attribute2::TextDecorationStyle(parse_text_decoration_style(value)?));
+            class.class_style_mark1 |= StyleType1::TextDecoration as usize;
+        }
+        "text-decoration-color" => {
+            class
+                .attrs2
+                .push(Attribute2::TextDecorationColor(parse_text_decoration_color(value)?));
+            class.class_style_mark1 |= StyleType1::TextDecoration as usize;
+        }
+        "text-decoration-thickness" => {
+            class
+                .attrs2
+                .push(Attribute2::TextDecorationThickness(parse_text_decoration_thickness(value)?));
+            class.class_style_mark1 |= StyleType1::TextDecoration as usize;
+        }
+        "line-clamp" | "-webkit-line-clamp" => {
+            class
+                .attrs2
+                .push(Attribute2::LineClamp(parse_line_clamp(value)?));
+            class.class_style_mark1 |= StyleType1::LineClamp as usize;
+        }
         // "vertical-align" => show_attr.push(Attribute::Color( Color::RGBA(parse_color_string(value)?) )),
         "white-space" => {
             class
@@ -274,12 +475,19 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
         }
 
         "border-radius" => {
-            let v = parse_len_or_percent(value)?;
-            class.attrs3.push(Attribute3::BorderRadius(BorderRadius {
-                x: v.clone(),
-                y: v,
-            }));
-            class.class_style_mark |= StyleType::BorderRadius as usize;
+            set_border_radius(class, parse_border_radius(value)?);
+        }
+        "border-top-left-radius" => {
+            set_border_radius_corner(class, 0, parse_corner_radius(value)?);
+        }
+        "border-top-right-radius" => {
+            set_border_radius_corner(class, 1, parse_corner_radius(value)?);
+        }
+        "border-bottom-right-radius" => {
+            set_border_radius_corner(class, 2, parse_corner_radius(value)?);
+        }
+        "border-bottom-left-radius" => {
+            set_border_radius_corner(class, 3, parse_corner_radius(value)?);
         }
         "opacity" => {
             class
@@ -299,6 +507,24 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
                 .push(Attribute3::TransformOrigin(parse_transform_origin(value)?));
             class.class_style_mark1 |= StyleType1::Transform as usize;
         }
+        "transition" => {
+            class
+                .attrs3
+                .push(Attribute3::Transition(parse_transition(value)?));
+            class.class_style_mark1 |= StyleType1::Transition as usize;
+        }
+        "animation" => {
+            class
+                .attrs3
+                .push(Attribute3::Animation(parse_animation(value)?));
+            class.class_style_mark1 |= StyleType1::Animation as usize;
+        }
+        "clip-path" => {
+            class
+                .attrs3
+                .push(Attribute3::ClipPath(parse_clip_path(value)?));
+            class.class_style_mark1 |= StyleType1::ClipPath as usize;
+        }
         "z-index" => {
             class
                 .attrs2
@@ -529,6 +755,12 @@ fn match_key(key: &str, value: &str, class: &mut Class) -> Result<(), String> {
                 .push(Attribute1::FlexDirection(parse_yg_direction(value)?));
             class.class_style_mark2 |= StyleType2::FlexDirection as usize;
         }
+        "direction" => {
+            class
+                .attrs1
+                .push(Attribute1::Direction(parse_direction(value)?));
+            class.class_style_mark1 |= StyleType1::Direction as usize;
+        }
         "align-content" => {
             class
                 .attrs1
@@ -635,10 +867,12 @@ fn parse_display(value: &str) -> Result<Display, String> {
     }
 }
 
-fn parse_overflow(value: &str) -> Result<bool, String> {
+fn parse_overflow(value: &str) -> Result<OverflowType, String> {
     match value {
-        "hidden" => Ok(true),
-        _ => Ok(false), // 默认情况
+        "hidden" => Ok(OverflowType::Hidden),
+        "scroll" => Ok(OverflowType::Scroll),
+        "auto" => Ok(OverflowType::Auto),
+        _ => Ok(OverflowType::Visible), // 默认情况
     }
 }
 
@@ -684,6 +918,92 @@ fn parse_linear_gradient_color_string(value: &str) -> Result<Color, String> {
     Ok(Color::LinearGradient(color))
 }
 
+// radial-gradient([<shape> || <size>] [at <position>], <color-stop>, ...)
+fn parse_radial_gradient_color_string(value: &str) -> Result<Color, String> {
+    let value = &value[15..].trim();
+    let value = value[1..value.len() - 1].trim();
+    let mut iter = value.split(",");
+    let first = iter.nth(0);
+    let mut color = RadialGradientColor {
+        center: (0.5, 0.5),
+        shape: RadialGradientShape::Ellipse,
+        size: RadialGradientSize::Farthescorner,
+        list: Vec::new(),
+    };
+    let mut list = Vec::new();
+    let mut pre_percent = 0.0;
+    match first {
+        Some(first) => {
+            let first = first.trim();
+            if !parse_radial_gradient_config(first, &mut color)? {
+                parser_color_stop(first, &mut list, &mut color.list, &mut pre_percent)?;
+            }
+        }
+        None => return Ok(Color::RadialGradient(color)),
+    };
+
+    for value in iter {
+        let value = value.trim();
+        parser_color_stop(value, &mut list, &mut color.list, &mut pre_percent)?;
+    }
+
+    parser_color_stop_last(1.0, &mut list, &mut color.list, &mut pre_percent, None)?;
+
+    Ok(Color::RadialGradient(color))
+}
+
+// 解析径向渐变的形状、尺寸和中心， 如果value不是渐变配置（而是色标）， 返回false
+fn parse_radial_gradient_config(
+    value: &str,
+    color: &mut RadialGradientColor,
+) -> Result<bool, String> {
+    let mut is_config = false;
+    let mut iter = value.split_whitespace();
+    while let Some(v) = iter.next() {
+        match v {
+            "circle" => color.shape = RadialGradientShape::Circle,
+            "ellipse" => color.shape = RadialGradientShape::Ellipse,
+            "closest-side" => color.size = RadialGradientSize::ClosestSide,
+            "farthest-side" => color.size = RadialGradientSize::FarthesSide,
+            "closest-corner" => color.size = RadialGradientSize::ClosestCorner,
+            "farthest-corner" => color.size = RadialGradientSize::Farthescorner,
+            "at" => {
+                let x = iter.next();
+                let y = iter.next();
+                color.center = match (x, y) {
+                    // 第一个值为top、bottom时， 表示先设置纵向位置
+                    (Some(x), Some(y)) if x == "top" || x == "bottom" || y == "left" || y == "right" => {
+                        (parse_radial_gradient_pos(y)?, parse_radial_gradient_pos(x)?)
+                    }
+                    (Some(x), Some(y)) => (parse_radial_gradient_pos(x)?, parse_radial_gradient_pos(y)?),
+                    (Some("top"), None) => (0.5, 0.0),
+                    (Some("bottom"), None) => (0.5, 1.0),
+                    (Some(x), None) => (parse_radial_gradient_pos(x)?, 0.5),
+                    _ => return Err(format!("parse_radial_gradient_config error, value: {:?}", value)),
+                };
+            }
+            _ => {
+                if is_config {
+                    return Err(format!("parse_radial_gradient_config error, value: {:?}", value));
+                }
+                return Ok(false);
+            }
+        }
+        is_config = true;
+    }
+    Ok(is_config)
+}
+
+// 渐变中心的位置， 返回相对于渐变框的比例
+fn parse_radial_gradient_pos(value: &str) -> Result<f32, String> {
+    match value {
+        "left" | "top" => Ok(0.0),
+        "center" => Ok(0.5),
+        "right" | "bottom" => Ok(1.0),
+        _ => parse_percent_to_f32(value),
+    }
+}
+
 fn parser_color_stop(
     value: &str,
     list: &mut Vec<CgColor>,
@@ -871,10 +1191,99 @@ fn parse_text_align(value: &str) -> Result<TextAlign, String> {
         "right" => Ok(TextAlign::Right),
         "center" => Ok(TextAlign::Center),
         "justify" => Ok(TextAlign::Justify),
+        "start" => Ok(TextAlign::Start),
+        "end" => Ok(TextAlign::End),
         _ => Err(format!("parse_text_align error, value: {}", value)),
     }
 }
 
+// clip | ellipsis | "自定义字符串"
+fn parse_text_overflow(value: &str) -> Result<TextOverflow, String> {
+    match value {
+        "clip" => Ok(TextOverflow::Clip),
+        "ellipsis" => Ok(TextOverflow::Ellipsis),
+        _ => {
+            if value.len() >= 2
+                && ((value.starts_with("'") && value.ends_with("'"))
+                    || (value.starts_with("\"") && value.ends_with("\"")))
+            {
+                Ok(TextOverflow::Custom(value[1..value.len() - 1].to_string()))
+            } else {
+                Err(format!("parse_text_overflow error, value: {}", value))
+            }
+        }
+    }
+}
+
+// none | 正整数
+fn parse_line_clamp(value: &str) -> Result<usize, String> {
+    match value {
+        "none" => Ok(0),
+        _ => match usize::from_str(value) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(format!("parse_line_clamp error, {}: {}", e.to_string(), value)),
+        },
+    }
+}
+
+// text-decoration: <line> || <style> || <color> || <thickness>， 省略的部分为初始值
+fn parse_text_decoration(value: &str) -> Result<TextDecoration, String> {
+    let mut r = TextDecoration::default();
+    for item in split_top_level(value, char::is_whitespace).into_iter() {
+        if let Ok(line) = parse_text_decoration_line(item) {
+            r.line |= line;
+        } else if let Ok(style) = parse_text_decoration_style(item) {
+            r.style = style;
+        } else if let Ok(thickness) = parse_text_decoration_thickness(item) {
+            r.thickness = thickness;
+        } else {
+            r.color = parse_text_decoration_color(item)?;
+        }
+    }
+    Ok(r)
+}
+
+// none | [underline || overline || line-through]
+fn parse_text_decoration_line(value: &str) -> Result<usize, String> {
+    let mut r = 0;
+    for item in value.split_whitespace() {
+        r |= match item {
+            "none" => 0,
+            "underline" => TextDecorationLine::Underline as usize,
+            "overline" => TextDecorationLine::Overline as usize,
+            "line-through" => TextDecorationLine::LineThrough as usize,
+            _ => return Err(format!("parse_text_decoration_line error, value: {}", value)),
+        };
+    }
+    Ok(r)
+}
+
+fn parse_text_decoration_style(value: &str) -> Result<TextDecorationStyle, String> {
+    match value {
+        "solid" => Ok(TextDecorationStyle::Solid),
+        "dashed" => Ok(TextDecorationStyle::Dashed),
+        "dotted" => Ok(TextDecorationStyle::Dotted),
+        "wavy" => Ok(TextDecorationStyle::Wavy),
+        _ => Err(format!("parse_text_decoration_style error, value: {}", value)),
+    }
+}
+
+// currentcolor表示使用文字颜色
+fn parse_text_decoration_color(value: &str) -> Result<Option<CgColor>, String> {
+    match value {
+        "currentcolor" | "currentColor" => Ok(None),
+        _ => Ok(Some(parse_color_string(value)?)),
+    }
+}
+
+// auto | from-font | 像素， 0表示使用字体建议的粗细
+fn parse_text_decoration_thickness(value: &str) -> Result<f32, String> {
+    match value {
+        "auto" | "from-font" => Ok(0.0),
+        _ => parse_px(value),
+    }
+}
+
 fn parse_yg_align_items(value: &str) -> Result<AlignItems, String> {
     match value {
 		// "auto" => Ok(AlignItems::Auto),
@@ -936,6 +1345,15 @@ fn parse_yg_direction(value: &str) -> Result<FlexDirection, String> {
     }
 }
 
+fn parse_direction(value: &str) -> Result<Direction, String> {
+    match value {
+        "inherit" => Ok(Direction::Inherit),
+        "ltr" => Ok(Direction::LTR),
+        "rtl" => Ok(Direction::RTL),
+        _ => Err(format!("parse_direction error, value: {}", value)),
+    }
+}
+
 fn parse_yg_justify_content(value: &str) -> Result<JustifyContent, String> {
     match value {
         "flex-start" => Ok(JustifyContent::FlexStart),
@@ -982,24 +1400,47 @@ fn parse_font_size(value: &str) -> Result<FontSize, String> {
     }
 }
 
-fn parse_text_shadow(value: &str) -> Result<TextShadow, String> {
-    let mut i = 0;
+// 多个阴影以逗号分隔， 如： text-shadow: 0px 0px 4px #fff, 1px 1px 0px rgba(0,0,0,0.5)
+fn parse_text_shadow(value: &str) -> Result<Vec<TextShadow>, String> {
+    if value.trim() == "none" {
+        return Ok(Vec::new());
+    }
+    let mut shadows = Vec::new();
+    for item in split_top_level(value, |c| c == ',').into_iter() {
+        shadows.push(parse_text_shadow_item(item)?);
+    }
+    if shadows.len() == 0 {
+        return Err(format!("parse_text_shadow error: {}", value));
+    }
+    Ok(shadows)
+}
+
+// 单个阴影： <h> <v> [<blur>]， 颜色可以在长度之前或之后
+fn parse_text_shadow_item(value: &str) -> Result<TextShadow, String> {
     let mut shadow = TextShadow::default();
-    shadow.h = parse_px(iter_by_space(value, &mut i)?)?;
-    shadow.v = parse_px(iter_by_space(value, &mut i)?)?;
-    match iter_by_space(value, &mut i) {
-        Ok(r) => match parse_px(r) {
-            Ok(r) => {
-                shadow.blur = r;
-                match iter_by_space(value, &mut i) {
-                    Ok(r) => shadow.color = parse_color_string(r)?,
-                    Err(_) => (),
-                };
+    // 未指定颜色时， 使用黑色
+    shadow.color = CgColor::new(0.0, 0.0, 0.0, 1.0);
+    let mut lens = Vec::with_capacity(3);
+    let mut has_color = false;
+    for r in split_top_level(value, |c| c.is_whitespace()).into_iter() {
+        let len = if r == "0" { Ok(0.0) } else { parse_px(r) };
+        match len {
+            Ok(l) if lens.len() < 3 => lens.push(l),
+            Ok(_) => return Err(format!("parse_text_shadow error: {}", value)),
+            Err(_) if !has_color => {
+                shadow.color = parse_color_string(r)?;
+                has_color = true;
             }
-            Err(_) => shadow.color = parse_color_string(r)?,
-        },
-        _ => (),
-    };
+            Err(e) => return Err(e),
+        }
+    }
+    if lens.len() < 2 {
+        return Err(format!("parse_text_shadow error: {}", value));
+    }
+    shadow.h = lens[0];
+    shadow.v = lens[1];
+    // 模糊半径不能为负
+    shadow.blur = lens.get(2).cloned().unwrap_or(0.0).max(0.0);
     Ok(shadow)
 }
 
@@ -1548,6 +1989,502 @@ fn parse_len_or_percent_2(value: &str, split: &str) -> Result<[LengthUnit; 2], S
     Ok(r)
 }
 
+// border-radius: <h>{1,4} [/ <v>{1,4}]， 缺省的值按css规则补全
+fn parse_border_radius(value: &str) -> Result<BorderRadius, String> {
+    let mut iter = value.split("/");
+    let h = parse_radius_4(iter.next().unwrap_or(""))?;
+    let v = match iter.next() {
+        Some(r) => parse_radius_4(r)?,
+        None => h,
+    };
+    if iter.next().is_some() {
+        return Err(format!("parse_border_radius error, value: {:?}", value));
+    }
+    let corner = |i: usize| CornerRadius { x: h[i], y: v[i] };
+    Ok(BorderRadius {
+        top_left: corner(0),
+        top_right: corner(1),
+        bottom_right: corner(2),
+        bottom_left: corner(3),
+    })
+}
+
+// 解析1~4个半径， 返回左上、右上、右下、左下的值
+fn parse_radius_4(value: &str) -> Result<[LengthUnit; 4], String> {
+    let mut r = Vec::with_capacity(4);
+    for v in value.split_whitespace() {
+        r.push(parse_len_or_percent(v)?);
+    }
+    match r.len() {
+        1 => Ok([r[0], r[0], r[0], r[0]]),
+        2 => Ok([r[0], r[1], r[0], r[1]]),
+        3 => Ok([r[0], r[1], r[2], r[1]]),
+        4 => Ok([r[0], r[1], r[2], r[3]]),
+        _ => Err(format!("parse_radius_4 error, value: {:?}", value)),
+    }
+}
+
+// border-*-radius: <h> [<v>]
+fn parse_corner_radius(value: &str) -> Result<CornerRadius, String> {
+    let mut iter = value.split_whitespace();
+    let x = match iter.next() {
+        Some(r) => parse_len_or_percent(r)?,
+        None => return Err(format!("parse_corner_radius error, value: {:?}", value)),
+    };
+    let y = match iter.next() {
+        Some(r) => parse_len_or_percent(r)?,
+        None => x,
+    };
+    Ok(CornerRadius { x, y })
+}
+
+// 修改class中某个角的圆角
+// class中已声明了简写时， 直接修改简写的值； 否则记录为单个角的属性， 只覆盖该角， 其它角保留其它class或默认的值
+fn set_border_radius_corner(class: &mut Class, index: usize, value: CornerRadius) {
+    class.class_style_mark |= StyleType::BorderRadius as usize;
+    for attr in class.attrs3.iter_mut() {
+        match attr {
+            Attribute3::BorderRadius(r) => {
+                if let Some(c) = r.corner_mut(index) {
+                    *c = value;
+                }
+                return;
+            }
+            Attribute3::BorderRadiusCorner(i, r) if *i == index => {
+                *r = value;
+                return;
+            }
+            _ => (),
+        }
+    }
+    class.attrs3.push(Attribute3::BorderRadiusCorner(index, value));
+}
+
+// 设置class的圆角简写， 简写重置四个角， 之前声明的单个角被覆盖
+fn set_border_radius(class: &mut Class, value: BorderRadius) {
+    class.class_style_mark |= StyleType::BorderRadius as usize;
+    class.attrs3.retain(|attr| match attr {
+        Attribute3::BorderRadius(_) | Attribute3::BorderRadiusCorner(_, _) => false,
+        _ => true,
+    });
+    class.attrs3.push(Attribute3::BorderRadius(value));
+}
+
+// 解析clip-path， 支持circle(r at x y)、ellipse(rx ry at x y)、polygon(x y, x y, ...)
+// 省略半径时为50%， 省略中心时为布局框的中心
+pub fn parse_clip_path(value: &str) -> Result<ClipPath, String> {
+    let mut i = 0;
+    let (key, value) = iter_fun(value.trim(), &mut i)?;
+    match key.trim() {
+        "circle" | "ellipse" => {
+            let (size, center) = match value.find("at") {
+                Some(index) => (&value[..index], &value[index + 2..]),
+                None => (value, ""),
+            };
+            let mut size = size.split_whitespace();
+            let mut center = center.split_whitespace();
+            let (x, y) = match (center.next(), center.next()) {
+                // 第一个值为top、bottom时， 表示先设置纵向位置
+                (Some(x), Some(y)) if x == "top" || x == "bottom" || y == "left" || y == "right" => (Some(y), Some(x)),
+                (Some(y), None) if y == "top" || y == "bottom" => (None, Some(y)),
+                r => r,
+            };
+            let rx = parse_clip_path_len_or_default(size.next())?;
+            let (x, y) = (parse_clip_path_len_or_default(x)?, parse_clip_path_len_or_default(y)?);
+            if key.trim() == "circle" {
+                Ok(ClipPath::Circle(rx, x, y))
+            } else {
+                let ry = parse_clip_path_len_or_default(size.next())?;
+                Ok(ClipPath::Ellipse(rx, ry, x, y))
+            }
+        }
+        "polygon" => {
+            let mut points = Vec::new();
+            for p in value.split(",") {
+                let mut iter = p.split_whitespace();
+                match (iter.next(), iter.next()) {
+                    (Some(x), Some(y)) => points.push((parse_clip_path_len(x)?, parse_clip_path_len(y)?)),
+                    _ => return Err(format!("parse_clip_path error, value: {:?}", value)),
+                }
+            }
+            Ok(ClipPath::Polygon(points))
+        }
+        _ => Err(format!("parse_clip_path error, value: {:?}", value)),
+    }
+}
+
+// 省略的半径、中心坐标为50%
+fn parse_clip_path_len_or_default(value: Option<&str>) -> Result<LengthUnit, String> {
+    match value {
+        Some(r) => parse_clip_path_len(r),
+        None => Ok(LengthUnit::Percent(0.5)),
+    }
+}
+
+// clip-path中的长度， 支持像素、百分比、0以及表示位置的关键字
+fn parse_clip_path_len(value: &str) -> Result<LengthUnit, String> {
+    match value {
+        "0" => Ok(LengthUnit::Pixel(0.0)),
+        "left" | "top" => Ok(LengthUnit::Percent(0.0)),
+        "center" => Ok(LengthUnit::Percent(0.5)),
+        "right" | "bottom" => Ok(LengthUnit::Percent(1.0)),
+        _ => parse_len_or_percent(value),
+    }
+}
+
+// 解析富文本， 返回去掉标签后的文本内容及各片段的样式
+// 支持<span style="color:..;font-size:..;font-weight:..;font-family:..;text-stroke:..;text-decoration:..">、<b>、<u>、<s>、<del>、
+// <img src=图片的url(与设置图片时相同的数字) width=.. height=..>以及&lt;、&gt;、&amp;、&quot;、&nbsp;
+// 行内图片在文本中占一个'\u{FFFC}'字符
+pub fn parse_rich_text(value: &str) -> Result<(String, Vec<TextSpan>), String> {
+    let mut text = String::new();
+    let mut spans: Vec<TextSpan> = Vec::new();
+    // 标签栈， 栈顶为当前的片段样式
+    let mut stack: Vec<(String, TextSpan)> = Vec::new();
+    let mut count = 0; // 文本内容的字符数
+    let mut i = 0;
+    while i < value.len() {
+        let rest = &value[i..];
+        if rest.starts_with("<") {
+            let end = match rest.find('>') {
+                Some(r) => r,
+                None => return Err(format!("parse_rich_text error, tag is not closed: {}", rest)),
+            };
+            let tag = rest[1..end].trim();
+            i += end + 1;
+            if tag.starts_with("/") {
+                let name = tag[1..].trim();
+                match stack.iter().rposition(|s| s.0 == name) {
+                    Some(r) => stack.truncate(r),
+                    None => return Err(format!("parse_rich_text error, unexpected tag: </{}>", name)),
+                };
+                continue;
+            }
+            let tag = tag.trim_end_matches('/').trim();
+            let (name, attrs) = match tag.find(char::is_whitespace) {
+                Some(r) => (&tag[..r], parse_tag_attrs(&tag[r..])?),
+                None => (tag, Vec::new()),
+            };
+            let mut span = match stack.last() {
+                Some(r) => r.1.clone(),
+                None => TextSpan::default(),
+            };
+            match name {
+                "span" => {
+                    for (key, v) in attrs.iter() {
+                        if *key == "style" {
+                            parse_span_style(v, &mut span)?;
+                        }
+                    }
+                }
+                "b" => span.font_weight = Some(700),
+                "u" => add_span_decoration(&mut span, TextDecorationLine::Underline),
+                "s" | "del" => add_span_decoration(&mut span, TextDecorationLine::LineThrough),
+                "br" => {
+                    text.push('\n');
+                    count += 1;
+                    continue;
+                }
+                "img" => {
+                    let mut image = SpanImage::default();
+                    for (key, v) in attrs.iter() {
+                        match *key {
+                            "src" => {
+                                image.url = match usize::from_str(v) {
+                                    Ok(r) => r,
+                                    Err(e) => return Err(e.to_string()),
+                                }
+                            }
+                            "width" => image.width = parse_img_len(v)?,
+                            "height" => image.height = parse_img_len(v)?,
+                            _ => (),
+                        }
+                    }
+                    span.image = Some(image);
+                    span.start = count;
+                    span.end = count + 1;
+                    spans.push(span);
+                    text.push('\u{FFFC}');
+                    count += 1;
+                    continue;
+                }
+                _ => return Err(format!("parse_rich_text error, unsupported tag: <{}>", name)),
+            }
+            stack.push((name.to_string(), span));
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let start = count;
+            let piece = &rest[..end];
+            let mut j = 0;
+            while j < piece.len() {
+                let c = match parse_entity(&piece[j..]) {
+                    Some((c, len)) => {
+                        j += len;
+                        c
+                    }
+                    None => {
+                        let c = piece[j..].chars().next().unwrap();
+                        j += c.len_utf8();
+                        c
+                    }
+                };
+                text.push(c);
+                count += 1;
+            }
+            i += end;
+            if let Some(r) = stack.last() {
+                let mut span = r.1.clone();
+                span.start = start;
+                span.end = count;
+                spans.push(span);
+            }
+        }
+    }
+    Ok((text, spans))
+}
+
+// 富文本片段增加一种装饰线， 外层片段的装饰线保留
+fn add_span_decoration(span: &mut TextSpan, line: TextDecorationLine) {
+    let mut decoration = span.decoration.take().unwrap_or_default();
+    decoration.line |= line as usize;
+    span.decoration = Some(decoration);
+}
+
+// 解析标签属性， 如： src="1" width=20
+fn parse_tag_attrs(value: &str) -> Result<Vec<(&str, &str)>, String> {
+    let mut r = Vec::new();
+    let mut value = value.trim();
+    while value.len() > 0 {
+        let eq = match value.find('=') {
+            Some(r) => r,
+            None => return Err(format!("parse_tag_attrs error, value: {}", value)),
+        };
+        let key = value[..eq].trim();
+        let rest = value[eq + 1..].trim_start();
+        let (v, next) = match rest.chars().next() {
+            Some(q) if q == '"' || q == '\'' => match rest[1..].find(q) {
+                Some(e) => (&rest[1..e + 1], &rest[e + 2..]),
+                None => return Err(format!("parse_tag_attrs error, value: {}", value)),
+            },
+            _ => {
+                let e = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..e], &rest[e..])
+            }
+        };
+        r.push((key, v));
+        value = next.trim();
+    }
+    Ok(r)
+}
+
+// 解析span的style属性， 如："color:#ff0000;font-size:20px"
+fn parse_span_style(value: &str, span: &mut TextSpan) -> Result<(), String> {
+    for rule in value.split(';') {
+        let rule = rule.trim();
+        if rule.len() == 0 {
+            continue;
+        }
+        let (key, v) = match rule.find(':') {
+            Some(r) => (rule[..r].trim(), rule[r + 1..].trim()),
+            None => return Err(format!("parse_span_style error, rule: {}", rule)),
+        };
+        match key {
+            "color" => span.color = Some(parse_color_string(v)?),
+            "font-size" => span.font_size = Some(parse_font_size(v)?),
+            "font-weight" => span.font_weight = Some(parse_font_weight(v)? as usize),
+            "font-family" => span.font_family = Some(Atom::from(v)),
+            "text-stroke" => span.stroke = Some(parse_text_stroke(v)?),
+            "text-decoration" => span.decoration = Some(parse_text_decoration(v)?),
+            _ => return Err(format!("parse_span_style error, unsupported key: {}", key)),
+        }
+    }
+    Ok(())
+}
+
+// 行内图片的宽高， 支持像素或无单位的数字
+fn parse_img_len(value: &str) -> Result<f32, String> {
+    if value.ends_with("px") {
+        parse_px(value)
+    } else {
+        parse_f32(value)
+    }
+}
+
+// 解析字符实体， 返回字符及实体的长度
+fn parse_entity(value: &str) -> Option<(char, usize)> {
+    for (name, c) in [("&lt;", '<'), ("&gt;", '>'), ("&amp;", '&'), ("&quot;", '"'), ("&nbsp;", '\u{A0}')].iter() {
+        if value.starts_with(name) {
+            return Some((*c, name.len()));
+        }
+    }
+    None
+}
+
+// 解析transition， 如："opacity 0.3s ease-in 0.1s, transform 200ms cubic-bezier(0.1, 0.7, 1.0, 0.1)"
+// 第一个时间为持续时间， 第二个时间为延迟时间， 属性省略时为all
+pub fn parse_transition(value: &str) -> Result<Transition, String> {
+    let mut items = Vec::new();
+    for item in split_top_level(value, |c| c == ',') {
+        let (mut property, mut timing, mut duration, mut delay) = (None, None, None, None);
+        for token in split_top_level(item, |c| c.is_whitespace()) {
+            if let Ok(r) = parse_time(token) {
+                if duration.is_none() {
+                    duration = Some(r);
+                } else if delay.is_none() {
+                    delay = Some(r);
+                } else {
+                    return Err(format!("parse_transition error, value: {:?}", value));
+                }
+            } else if let Ok(r) = parse_timing_function(token) {
+                timing = Some(r);
+            } else if property.is_none() {
+                property = Some(parse_transition_property(token)?);
+            } else {
+                return Err(format!("parse_transition error, value: {:?}", value));
+            }
+        }
+        items.push(TransitionItem {
+            property: property.unwrap_or(TransitionProperty::All),
+            duration: duration.unwrap_or(0),
+            timing: timing.unwrap_or(TimingFunction::Ease),
+            delay: delay.unwrap_or(0),
+        });
+    }
+    Ok(Transition(items))
+}
+
+// 解析animation， 如："fade 1s ease-in 0.5s infinite alternate both"
+// 第一个时间为持续时间， 第二个时间为延迟时间， 其余关键字的顺序任意， 不能识别的值作为关键帧名称
+pub fn parse_animation(value: &str) -> Result<Animation, String> {
+    let mut items = Vec::new();
+    for item in split_top_level(value, |c| c == ',') {
+        let mut r = AnimationItem {
+            name: Atom::from(""),
+            duration: 0,
+            timing: TimingFunction::Ease,
+            delay: 0,
+            iteration: 1.0,
+            direction: AnimationDirection::Normal,
+            fill_mode: AnimationFillMode::None,
+            play_state: AnimationPlayState::Running,
+        };
+        let (mut name, mut time_count) = (None, 0);
+        for token in split_top_level(item, |c| c.is_whitespace()) {
+            match token {
+                "infinite" => r.iteration = std::f32::INFINITY,
+                "normal" => r.direction = AnimationDirection::Normal,
+                "reverse" => r.direction = AnimationDirection::Reverse,
+                "alternate" => r.direction = AnimationDirection::Alternate,
+                "alternate-reverse" => r.direction = AnimationDirection::AlternateReverse,
+                "none" => r.fill_mode = AnimationFillMode::None,
+                "forwards" => r.fill_mode = AnimationFillMode::Forwards,
+                "backwards" => r.fill_mode = AnimationFillMode::Backwards,
+                "both" => r.fill_mode = AnimationFillMode::Both,
+                "running" => r.play_state = AnimationPlayState::Running,
+                "paused" => r.play_state = AnimationPlayState::Paused,
+                _ => {
+                    if let Ok(t) = parse_time(token) {
+                        match time_count {
+                            0 => r.duration = t,
+                            1 => r.delay = t,
+                            _ => return Err(format!("parse_animation error, value: {:?}", value)),
+                        }
+                        time_count += 1;
+                    } else if let Ok(t) = parse_timing_function(token) {
+                        r.timing = t;
+                    } else if let Ok(n) = f32::from_str(token) {
+                        if !(n >= 0.0) {
+                            return Err(format!("parse_animation error, value: {:?}", value));
+                        }
+                        r.iteration = n;
+                    } else if name.is_none() {
+                        name = Some(token);
+                    } else {
+                        return Err(format!("parse_animation error, value: {:?}", value));
+                    }
+                }
+            }
+        }
+        match name {
+            Some(n) => r.name = Atom::from(n),
+            None => return Err(format!("parse_animation error, value: {:?}", value)),
+        };
+        items.push(r);
+    }
+    Ok(Animation(items))
+}
+
+fn parse_transition_property(value: &str) -> Result<TransitionProperty, String> {
+    match value {
+        "all" => Ok(TransitionProperty::All),
+        "opacity" => Ok(TransitionProperty::Opacity),
+        "background-color" => Ok(TransitionProperty::BackgroundColor),
+        "border-color" => Ok(TransitionProperty::BorderColor),
+        "color" => Ok(TransitionProperty::Color),
+        "transform" => Ok(TransitionProperty::Transform),
+        "width" => Ok(TransitionProperty::Width),
+        "height" => Ok(TransitionProperty::Height),
+        "filter" => Ok(TransitionProperty::Filter),
+        _ => Err(format!("parse_transition_property error, value: {:?}", value)),
+    }
+}
+
+fn parse_timing_function(value: &str) -> Result<TimingFunction, String> {
+    match value {
+        "linear" => Ok(TimingFunction::Linear),
+        "ease" => Ok(TimingFunction::Ease),
+        "ease-in" => Ok(TimingFunction::EaseIn),
+        "ease-out" => Ok(TimingFunction::EaseOut),
+        "ease-in-out" => Ok(TimingFunction::EaseInOut),
+        "step-start" => Ok(TimingFunction::StepStart),
+        "step-end" => Ok(TimingFunction::StepEnd),
+        _ => {
+            let mut i = 0;
+            let (key, v) = iter_fun(value, &mut i)?;
+            if key.trim() != "cubic-bezier" {
+                return Err(format!("parse_timing_function error, value: {:?}", value));
+            }
+            let v = parse_f32_4(v, ",")?;
+            Ok(TimingFunction::CubicBezier(v[0], v[1], v[2], v[3]))
+        }
+    }
+}
+
+// 解析时间， 单位为s或ms， 返回毫秒数
+fn parse_time(value: &str) -> Result<usize, String> {
+    let r = if value.ends_with("ms") {
+        parse_f32(&value[..value.len() - 2])?
+    } else if value.ends_with("s") {
+        parse_f32(&value[..value.len() - 1])? * 1000.0
+    } else {
+        return Err(format!("parse_time error, value: {:?}", value));
+    };
+    if r < 0.0 {
+        return Err(format!("parse_time error, value: {:?}", value));
+    }
+    Ok(r.round() as usize)
+}
+
+// 按分隔符切分字符串， 忽略括号内的分隔符， 并丢弃空项
+fn split_top_level<F: Fn(char) -> bool>(value: &str, is_split: F) -> Vec<&str> {
+    let mut r = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 && is_split(c) => {
+                r.push(value[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    r.push(value[start..].trim());
+    r.retain(|s| s.len() > 0);
+    r
+}
+
 fn parse_percent_to_f32(value: &str) -> Result<f32, String> {
 	if value.ends_with("%") {
         let v = match f32::from_str(&value[..value.len() - 1]) {
@@ -1756,7 +2693,7 @@ pub enum Attribute {
     VerticalAlign(VerticalAlign),
     WhiteSpace(WhiteSpace),
     WordSpacing(f32),
-    TextShadow(TextShadow),
+    TextShadow(Vec<TextShadow>),
     TextStroke(Stroke),
 
     FontStyle(FontStyle),
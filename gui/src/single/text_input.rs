@@ -0,0 +1,311 @@
+/**
+ * 文本输入框的编辑状态
+ * 输入框是一个文字节点， 其文字内容（TextContent）由编辑缓冲区及输入法正在组合的文字生成， 外部不应直接设置
+ * 光标、选区以字符（char）为单位索引编辑缓冲区， 只绘制获得焦点的输入框的光标和选区（见system::render::caret）
+ * 编辑命令、指针选择及输入法组合由宿主通过system::text_input中的函数输入
+*/
+use share::Share;
+
+use hash::XHashMap;
+
+use component::user::CgColor;
+use font::line_break::{grapheme_end, grapheme_start};
+
+/// 写入剪贴板， 复制、剪切时以选中的文字调用
+pub type ClipboardWriter = Share<dyn Fn(&str)>;
+/// 编辑缓冲区被用户输入改变时调用， 参数为输入框节点
+pub type InputListener = Share<dyn Fn(usize)>;
+
+/// 编辑命令， 由宿主将按键映射为命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditCommand {
+	Left,
+	Right,
+	Up,
+	Down,
+	LineStart, // 光标所在行的行首
+	LineEnd, // 光标所在行的行尾
+	WordLeft,
+	WordRight,
+	DocStart,
+	DocEnd,
+	Backspace,
+	Delete,
+	DeleteWordBackward,
+	DeleteWordForward,
+	NewLine, // 单行输入框不处理
+	SelectAll,
+	Copy,
+	Cut,
+}
+
+pub struct TextInput {
+	pub value: String, // 编辑缓冲区
+	pub caret: usize, // 光标位置
+	pub anchor: usize, // 选区的另一端， 与光标相同时没有选区
+	pub composition: String, // 输入法正在组合的文字， 显示在光标处， 不属于编辑缓冲区
+	pub multiline: bool,
+	pub caret_color: CgColor,
+	pub selection_color: CgColor,
+	pub preferred_x: Option<f32>, // 上下移动光标时保持的水平位置
+	pub blink_time: usize, // 光标开始闪烁的时间（最后一次编辑或移动光标）
+	pub caret_visible: bool, // 光标在闪烁中是否可见
+	pub dirty: bool, // 光标、选区或组合文字改变， 需要重新绘制
+}
+
+impl TextInput {
+	pub fn new(multiline: bool) -> Self {
+		TextInput {
+			value: String::new(),
+			caret: 0,
+			anchor: 0,
+			composition: String::new(),
+			multiline: multiline,
+			caret_color: CgColor::new(0.0, 0.0, 0.0, 1.0),
+			selection_color: CgColor::new(0.2, 0.56, 1.0, 0.4),
+			preferred_x: None,
+			blink_time: 0,
+			caret_visible: true,
+			dirty: true,
+		}
+	}
+
+	/// 编辑缓冲区的字符数
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.value.chars().count()
+	}
+
+	/// 选区的起点和终点， 起点不大于终点
+	#[inline]
+	pub fn selection(&self) -> (usize, usize) {
+		(self.caret.min(self.anchor), self.caret.max(self.anchor))
+	}
+
+	#[inline]
+	pub fn has_selection(&self) -> bool {
+		self.caret != self.anchor
+	}
+
+	pub fn selected_text(&self) -> String {
+		let (start, end) = self.selection();
+		self.value.chars().skip(start).take(end - start).collect()
+	}
+
+	/// 替换编辑缓冲区， 光标移到末尾， 放弃正在组合的文字
+	pub fn set_value(&mut self, value: &str) {
+		self.value = self.filter(value);
+		self.caret = self.len();
+		self.anchor = self.caret;
+		self.composition.clear();
+		self.moved();
+	}
+
+	/// 设置选区， 超出范围的位置被限制到末尾
+	pub fn select(&mut self, anchor: usize, caret: usize) {
+		let len = self.len();
+		self.anchor = anchor.min(len);
+		self.caret = caret.min(len);
+		self.moved();
+	}
+
+	/// 移动光标， extend为true时扩展选区， 否则取消选区
+	pub fn move_to(&mut self, index: usize, extend: bool) {
+		self.caret = index.min(self.len());
+		if !extend {
+			self.anchor = self.caret;
+		}
+		self.moved();
+	}
+
+	/// 在光标处插入文字（替换选区）， 返回编辑缓冲区是否改变
+	pub fn insert(&mut self, text: &str) -> bool {
+		let text = self.filter(text);
+		let deleted = self.delete_selection();
+		if text.len() == 0 {
+			return deleted;
+		}
+		let at = self.byte_index(self.caret);
+		self.value.insert_str(at, text.as_str());
+		self.caret += text.chars().count();
+		self.anchor = self.caret;
+		self.moved();
+		true
+	}
+
+	/// 删除选区， 返回是否存在选区
+	pub fn delete_selection(&mut self) -> bool {
+		let (start, end) = self.selection();
+		self.remove(start, end)
+	}
+
+	/// 删除光标前的一个字符或单词（存在选区时只删除选区）， 返回编辑缓冲区是否改变
+	pub fn delete_backward(&mut self, word: bool) -> bool {
+		if self.has_selection() {
+			return self.delete_selection();
+		}
+		let start = if word { self.word_start(self.caret) } else { self.prev_grapheme(self.caret) };
+		let end = self.caret;
+		self.remove(start, end)
+	}
+
+	/// 删除光标后的一个字符或单词（存在选区时只删除选区）， 返回编辑缓冲区是否改变
+	pub fn delete_forward(&mut self, word: bool) -> bool {
+		if self.has_selection() {
+			return self.delete_selection();
+		}
+		let end = if word { self.word_end(self.caret) } else { self.next_grapheme(self.caret) };
+		let start = self.caret;
+		self.remove(start, end)
+	}
+
+	/// index之前的字素簇的起点， 删除和移动光标以字素簇为单位， 不拆分组合字符、emoji序列
+	pub fn prev_grapheme(&self, index: usize) -> usize {
+		let chars: Vec<char> = self.value.chars().collect();
+		grapheme_start(&chars, index)
+	}
+
+	/// index之后的字素簇的终点
+	pub fn next_grapheme(&self, index: usize) -> usize {
+		let chars: Vec<char> = self.value.chars().collect();
+		if index >= chars.len() {
+			return chars.len();
+		}
+		grapheme_end(&chars, index)
+	}
+
+	/// index之前最近的单词起点（跳过空白符）
+	pub fn word_start(&self, index: usize) -> usize {
+		let chars: Vec<char> = self.value.chars().collect();
+		let mut i = index.min(chars.len());
+		while i > 0 && char_class(chars[i - 1]) == 0 {
+			i -= 1;
+		}
+		if i > 0 {
+			let class = char_class(chars[i - 1]);
+			while i > 0 && char_class(chars[i - 1]) == class {
+				i -= 1;
+			}
+		}
+		i
+	}
+
+	/// index之后最近的单词终点（跳过空白符）
+	pub fn word_end(&self, index: usize) -> usize {
+		let chars: Vec<char> = self.value.chars().collect();
+		let mut i = index.min(chars.len());
+		while i < chars.len() && char_class(chars[i]) == 0 {
+			i += 1;
+		}
+		if i < chars.len() {
+			let class = char_class(chars[i]);
+			while i < chars.len() && char_class(chars[i]) == class {
+				i += 1;
+			}
+		}
+		i
+	}
+
+	/// 设置输入法正在组合的文字， 开始组合时删除选区， 返回编辑缓冲区是否改变
+	pub fn set_composition(&mut self, text: &str) -> bool {
+		let deleted = self.delete_selection();
+		self.composition = self.filter(text);
+		self.moved();
+		deleted
+	}
+
+	/// 结束组合， 将输入法确定的文字插入光标处， 返回编辑缓冲区是否改变
+	pub fn commit_composition(&mut self, text: &str) -> bool {
+		self.composition.clear();
+		self.moved();
+		self.insert(text)
+	}
+
+	/// 显示的文字， 组合中的文字插入在光标处
+	pub fn display_text(&self) -> String {
+		if self.composition.len() == 0 {
+			return self.value.clone();
+		}
+		let at = self.byte_index(self.caret);
+		let mut r = String::with_capacity(self.value.len() + self.composition.len());
+		r.push_str(&self.value[..at]);
+		r.push_str(self.composition.as_str());
+		r.push_str(&self.value[at..]);
+		r
+	}
+
+	/// 光标在显示的文字中的位置（组合中的文字之后）
+	#[inline]
+	pub fn display_caret(&self) -> usize {
+		self.caret + self.composition.chars().count()
+	}
+
+	/// 显示的文字中的位置转换为编辑缓冲区中的位置， 组合中的文字内的位置转换为光标位置
+	pub fn to_value_index(&self, index: usize) -> usize {
+		let count = self.composition.chars().count();
+		if index <= self.caret {
+			index
+		} else if index < self.caret + count {
+			self.caret
+		} else {
+			index - count
+		}
+	}
+
+	fn remove(&mut self, start: usize, end: usize) -> bool {
+		if start >= end {
+			return false;
+		}
+		let (s, e) = (self.byte_index(start), self.byte_index(end));
+		self.value.replace_range(s..e, "");
+		self.caret = start;
+		self.anchor = start;
+		self.moved();
+		true
+	}
+
+	// 字符索引转换为字节索引
+	fn byte_index(&self, index: usize) -> usize {
+		match self.value.char_indices().nth(index) {
+			Some(r) => r.0,
+			None => self.value.len(),
+		}
+	}
+
+	// 去掉回车符， 单行输入框中的换行符替换为空格
+	fn filter(&self, text: &str) -> String {
+		text.chars()
+			.filter(|c| *c != '\r')
+			.map(|c| if c == '\n' && !self.multiline { ' ' } else { c })
+			.collect()
+	}
+
+	#[inline]
+	fn moved(&mut self) {
+		self.preferred_x = None;
+		self.caret_visible = true;
+		self.dirty = true;
+	}
+}
+
+// 字符的分类， 用于按单词移动光标： 0: 空白符， 1: 字母、数字及下划线， 2: 其它
+#[inline]
+fn char_class(c: char) -> u8 {
+	if c.is_whitespace() {
+		0
+	} else if c.is_alphanumeric() || c == '_' {
+		1
+	} else {
+		2
+	}
+}
+
+/// 所有输入框， 输入框节点 -> 编辑状态
+#[derive(Default)]
+pub struct TextInputs {
+	pub inputs: XHashMap<usize, TextInput>,
+	pub focus: usize, // 获得焦点的输入框， 0表示没有
+	pub drags: XHashMap<usize, usize>, // 正在拖动选择文字的指针， 指针id -> 输入框
+	pub clipboard: Option<ClipboardWriter>,
+	pub on_input: Option<InputListener>,
+}
@@ -0,0 +1,133 @@
+/**
+ * 虚拟列表
+ * 虚拟列表节点是一个滚动容器（overflow为scroll、auto）， 只为可视区域（及其上下的预留区域）内的数据项创建行节点，
+ * 行节点离开可视区域后被回收， 重新绑定到新进入可视区域的数据项， 因此不可见的数据项不会存在对应的实体
+ * 数据项的高度在行节点布局后测量， 未测量的数据项使用预估高度
+ * 由update_virtual_lists（见system::virtual_list）在每帧运行派发之前更新
+*/
+use share::Share;
+
+use hash::XHashMap;
+
+/// 创建行节点， 参数为列表节点， 返回新创建的行节点（不需要添加到节点树上）
+pub type RowCreator = Share<dyn Fn(usize) -> usize>;
+/// 将数据项绑定到行节点， 参数为(列表节点, 行节点, 数据项索引)
+pub type RowBinder = Share<dyn Fn(usize, usize, usize)>;
+
+// 正在使用的行
+#[derive(Debug, Clone, Copy)]
+pub struct VirtualRow {
+	pub node: usize,
+	pub index: usize,
+	pub top: f32, // 行节点当前设置的位置， 小于0表示未设置
+}
+
+pub struct VirtualList {
+	pub item_count: usize,
+	estimated_height: f32, // 未测量的数据项的预估高度
+	pub overscan: f32, // 可视区域上下额外保留行的距离， 单位： 像素
+	pub rows: Vec<VirtualRow>,
+	pub pool: Vec<usize>, // 回收的行节点（display为none）
+	pub create: RowCreator,
+	pub bind: RowBinder,
+	heights: Vec<f32>, // 测量的高度， 小于0表示未测量
+	tops: Vec<f32>, // 每个数据项的顶部位置， 最后一个为总高度， 按需计算
+	tops_dirty: bool,
+	total: f32, // 总高度
+	pub rebind: bool, // 数据改变， 需要重新绑定所有行
+}
+
+impl VirtualList {
+	pub fn new(estimated_height: f32, create: RowCreator, bind: RowBinder) -> Self {
+		VirtualList {
+			item_count: 0,
+			estimated_height: estimated_height,
+			overscan: estimated_height * 2.0,
+			rows: Vec::new(),
+			pool: Vec::new(),
+			create: create,
+			bind: bind,
+			heights: Vec::new(),
+			tops: vec![0.0],
+			tops_dirty: false,
+			total: 0.0,
+			rebind: false,
+		}
+	}
+
+	/// 设置数据项的数量， 已测量的高度保留
+	pub fn set_item_count(&mut self, count: usize) {
+		self.item_count = count;
+		self.heights.resize(count, -1.0);
+		self.total = (0..count).map(|i| self.height(i)).sum();
+		self.tops_dirty = true;
+		self.rebind = true;
+	}
+
+	/// 设置数据项的高度， 返回高度是否改变
+	pub fn set_height(&mut self, index: usize, height: f32) -> bool {
+		let old = self.height(index);
+		match self.heights.get_mut(index) {
+			Some(r) if *r != height => {
+				*r = height;
+				self.total += height - old;
+				self.tops_dirty = true;
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// 数据项的高度（未测量时为预估高度）
+	#[inline]
+	pub fn height(&self, index: usize) -> f32 {
+		match self.heights.get(index) {
+			Some(r) if *r >= 0.0 => *r,
+			_ => self.estimated_height,
+		}
+	}
+
+	/// 数据项的顶部位置
+	pub fn top(&mut self, index: usize) -> f32 {
+		self.cal_tops();
+		self.tops[index.min(self.item_count)]
+	}
+
+	/// 所有数据项的总高度
+	#[inline]
+	pub fn total_height(&self) -> f32 {
+		self.total
+	}
+
+	/// y所在的数据项索引， 超出范围时返回最近的数据项， 没有数据项时返回0
+	pub fn index_at(&mut self, y: f32) -> usize {
+		self.cal_tops();
+		if self.item_count == 0 {
+			return 0;
+		}
+		// 最后一个顶部不大于y的数据项
+		match self.tops[..self.item_count].binary_search_by(|t| t.partial_cmp(&y).unwrap()) {
+			Ok(r) => r,
+			Err(r) => r.max(1) - 1,
+		}
+	}
+
+	fn cal_tops(&mut self) {
+		if !self.tops_dirty {
+			return;
+		}
+		self.tops.clear();
+		let mut top = 0.0;
+		self.tops.push(top);
+		for i in 0..self.item_count {
+			top += self.height(i);
+			self.tops.push(top);
+		}
+		self.total = top;
+		self.tops_dirty = false;
+	}
+}
+
+/// 所有虚拟列表， 列表节点 -> 虚拟列表
+#[derive(Default)]
+pub struct VirtualLists(pub XHashMap<usize, VirtualList>);
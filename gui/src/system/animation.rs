@@ -0,0 +1,458 @@
+/**
+ * 关键帧动画
+ * AnimationSys记录设置了Animation组件的节点， 每帧根据SystemTime推进节点上的动画， 在样式表的@keyframes中取得对应的关键帧， 插值后写回对应组件（写回方式与过渡相同）
+ * 支持的属性与过渡相同： opacity、background-color、border-color、color、transform、width、height、filter
+ * 关键帧中缺少0%或100%帧时， 使用动画开始前该属性的值
+ * 动画结束后， 若fill-mode不为forwards或both， 属性恢复为动画开始前的值； 从Animation组件中移除的动画， 属性也会恢复
+ * 动画开始、每次重复、结束时， 在AnimationEvents中产生事件
+ * AnimationSys应该在TransitionSys之前运行， 本帧写入的属性记录在AnimatedProperties中， 过渡系统不会为它们产生过渡
+ */
+use ecs::{
+    CreateEvent, DeleteEvent, EntityListener, ModifyEvent, MultiCaseImpl, MultiCaseListener, Runner,
+    SingleCaseImpl, StdCell,
+};
+use atom::Atom;
+use hash::XHashMap;
+use share::Share;
+
+use component::calc::StyleMark;
+use component::user::*;
+use entity::Node;
+use single::*;
+use system::transition::{get_value, interpolate, set_value, value_eq, Styles, TransitionValue, PROPERTYS};
+
+struct AnimationState {
+    name: Atom,
+    start_time: usize,
+    pause_time: Option<usize>, // 暂停的时刻
+    started: bool,
+    ended: bool,
+    iteration: usize, // 当前所在的播放轮次
+    bases: Vec<(TransitionProperty, TransitionValue)>, // 动画开始前的属性值
+}
+
+impl AnimationState {
+    fn new(name: Atom, now: usize) -> Self {
+        AnimationState {
+            name: name,
+            start_time: now,
+            pause_time: None,
+            started: false,
+            ended: false,
+            iteration: 0,
+            bases: Vec::new(),
+        }
+    }
+}
+
+enum Phase {
+    Before,
+    Active(usize, f32), // (轮次， 本轮进度)
+    After(usize, f32),
+}
+
+#[derive(Default)]
+pub struct AnimationSys {
+    nodes: XHashMap<usize, Vec<AnimationState>>,
+    dirtys: Vec<usize>, // Animation组件发生改变的节点
+}
+
+impl<'a> Runner<'a> for AnimationSys {
+    type ReadData = (
+        &'a SingleCaseImpl<SystemTime>,
+        &'a MultiCaseImpl<Node, Animation>,
+        &'a SingleCaseImpl<Share<StdCell<ClassSheet>>>,
+    );
+    type WriteData = (
+        &'a mut MultiCaseImpl<Node, Opacity>,
+        &'a mut MultiCaseImpl<Node, BackgroundColor>,
+        &'a mut MultiCaseImpl<Node, BorderColor>,
+        &'a mut MultiCaseImpl<Node, TextStyle>,
+        &'a mut MultiCaseImpl<Node, Transform>,
+        &'a mut MultiCaseImpl<Node, RectLayoutStyle>,
+        &'a mut MultiCaseImpl<Node, Filter>,
+        &'a mut MultiCaseImpl<Node, StyleMark>,
+        &'a mut SingleCaseImpl<DirtyList>,
+        &'a mut SingleCaseImpl<AnimatedProperties>,
+        &'a mut SingleCaseImpl<AnimationEvents>,
+    );
+    fn run(&mut self, (system_time, animations, class_sheet): Self::ReadData, write: Self::WriteData) {
+        let (opacitys, background_colors, border_colors, text_styles, transforms, rect_layout_styles, filters, style_marks, dirty_list, animated, events) = write;
+        let mut styles = (opacitys, background_colors, border_colors, text_styles, transforms, rect_layout_styles, filters, style_marks, dirty_list);
+        let now = system_time.cur_time;
+        animated.0.clear();
+        events.0.clear();
+
+        // Animation组件改变， 保留同名动画的播放状态， 移除的动画恢复属性值
+        for id in self.dirtys.drain(..) {
+            let mut old = self.nodes.remove(&id).unwrap_or_default();
+            let mut states = Vec::new();
+            if let Some(animation) = animations.get(id) {
+                for item in animation.0.iter() {
+                    match old.iter().position(|r| r.name == item.name) {
+                        Some(i) => states.push(old.swap_remove(i)),
+                        None => states.push(AnimationState::new(item.name.clone(), now)),
+                    }
+                }
+            }
+            for state in old.iter_mut() {
+                restore(id, state, &mut styles, animated);
+            }
+            if states.len() > 0 {
+                self.nodes.insert(id, states);
+            }
+        }
+
+        let class_sheet = class_sheet.borrow();
+        for (id, states) in self.nodes.iter_mut() {
+            let id = *id;
+            let animation = match animations.get(id) {
+                Some(r) => r,
+                None => continue,
+            };
+            for (item, state) in animation.0.iter().zip(states.iter_mut()) {
+                match (item.play_state, state.pause_time) {
+                    (AnimationPlayState::Paused, None) => state.pause_time = Some(now),
+                    (AnimationPlayState::Running, Some(t)) => {
+                        state.start_time += now.saturating_sub(t);
+                        state.pause_time = None;
+                    }
+                    _ => (),
+                }
+                // 系统时间可能被重置到开始时间之前
+                let elapsed = state.pause_time.unwrap_or(now).saturating_sub(state.start_time);
+                let active = elapsed as f32 - item.delay as f32;
+                let total = item.duration as f32 * item.iteration;
+                let phase = if active < 0.0 {
+                    Phase::Before
+                } else if item.duration == 0 || active >= total {
+                    let (iteration, progress) = if item.iteration.is_infinite() {
+                        (0, 1.0)
+                    } else if item.iteration.fract() == 0.0 && item.iteration > 0.0 {
+                        (item.iteration as usize - 1, 1.0)
+                    } else {
+                        (item.iteration.floor() as usize, item.iteration.fract())
+                    };
+                    Phase::After(iteration, progress)
+                } else {
+                    let iteration = (active / item.duration as f32).floor();
+                    let progress = (active - iteration * item.duration as f32) / item.duration as f32;
+                    Phase::Active(iteration as usize, progress)
+                };
+
+                match phase {
+                    Phase::Before => (),
+                    _ if !state.started => {
+                        state.started = true;
+                        push_event(events, id, item, AnimationEventType::Start, 0);
+                    }
+                    _ => (),
+                }
+
+                let keyframes = match class_sheet.keyframes.get(&item.name) {
+                    Some(r) => r,
+                    None => continue,
+                };
+                match phase {
+                    Phase::Before => match item.fill_mode {
+                        AnimationFillMode::Backwards | AnimationFillMode::Both => {
+                            let progress = directed_progress(item.direction, 0, 0.0);
+                            apply(id, keyframes, progress, item.timing, state, &mut styles, animated);
+                        }
+                        _ => (),
+                    },
+                    Phase::Active(iteration, progress) => {
+                        if iteration > state.iteration {
+                            state.iteration = iteration;
+                            push_event(events, id, item, AnimationEventType::Iteration, iteration * item.duration);
+                        }
+                        let progress = directed_progress(item.direction, iteration, progress);
+                        apply(id, keyframes, progress, item.timing, state, &mut styles, animated);
+                    }
+                    Phase::After(iteration, progress) => {
+                        let fill = match item.fill_mode {
+                            AnimationFillMode::Forwards | AnimationFillMode::Both => true,
+                            _ => false,
+                        };
+                        if fill {
+                            let progress = directed_progress(item.direction, iteration, progress);
+                            apply(id, keyframes, progress, item.timing, state, &mut styles, animated);
+                        }
+                        if !state.ended {
+                            state.ended = true;
+                            push_event(events, id, item, AnimationEventType::End, active.min(total) as usize);
+                            if !fill {
+                                restore(id, state, &mut styles, animated);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Animation, CreateEvent> for AnimationSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &CreateEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.dirtys.push(event.id);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Animation, ModifyEvent> for AnimationSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &ModifyEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.dirtys.push(event.id);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Animation, DeleteEvent> for AnimationSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.dirtys.push(event.id);
+    }
+}
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for AnimationSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.nodes.remove(&event.id);
+        self.dirtys.retain(|r| *r != event.id);
+    }
+}
+
+fn push_event(events: &mut AnimationEvents, id: usize, item: &AnimationItem, ty: AnimationEventType, elapsed_time: usize) {
+    events.0.push(AnimationEvent {
+        id: id,
+        name: item.name.clone(),
+        ty: ty,
+        elapsed_time: elapsed_time,
+    });
+}
+
+// 根据播放方向， 将本轮进度转换为关键帧上的进度
+fn directed_progress(direction: AnimationDirection, iteration: usize, progress: f32) -> f32 {
+    let reverse = match direction {
+        AnimationDirection::Normal => false,
+        AnimationDirection::Reverse => true,
+        AnimationDirection::Alternate => iteration % 2 == 1,
+        AnimationDirection::AlternateReverse => iteration % 2 == 0,
+    };
+    if reverse {
+        1.0 - progress
+    } else {
+        progress
+    }
+}
+
+// 将关键帧在progress处的插值写入节点
+// 缓动函数作用于相邻两个关键帧之间， 无法插值的属性在区间的中点切换
+fn apply(
+    id: usize,
+    keyframes: &KeyFrames,
+    progress: f32,
+    timing: TimingFunction,
+    state: &mut AnimationState,
+    styles: &mut Styles,
+    animated: &mut AnimatedProperties,
+) {
+    for property in PROPERTYS.iter() {
+        let property = *property;
+        let mut frames: Vec<(f32, TransitionValue)> = keyframes
+            .frames
+            .iter()
+            .filter_map(|r| class_value(&r.1, property).map(|v| (r.0, v)))
+            .collect();
+        if frames.len() == 0 {
+            continue;
+        }
+        let base = match state.bases.iter().find(|r| r.0 == property) {
+            Some(r) => r.1.clone(),
+            None => match get_value(id, property, styles) {
+                Some(r) => {
+                    state.bases.push((property, r.clone()));
+                    r
+                }
+                None => continue,
+            },
+        };
+        if frames[0].0 > 0.0 {
+            frames.insert(0, (0.0, base.clone()));
+        }
+        if frames[frames.len() - 1].0 < 1.0 {
+            frames.push((1.0, base));
+        }
+
+        let mut i = 0;
+        while i + 2 < frames.len() && progress >= frames[i + 1].0 {
+            i += 1;
+        }
+        let (from, to) = (&frames[i], &frames[i + 1]);
+        let value = if to.0 > from.0 {
+            let t = timing.value(((progress - from.0) / (to.0 - from.0)).max(0.0).min(1.0));
+            match interpolate(&from.1, &to.1, t) {
+                Some(r) => r,
+                None => if t < 0.5 { from.1.clone() } else { to.1.clone() },
+            }
+        } else {
+            to.1.clone()
+        };
+        write_value(id, property, &value, styles, animated);
+    }
+}
+
+// 恢复动画开始前的属性值
+fn restore(id: usize, state: &mut AnimationState, styles: &mut Styles, animated: &mut AnimatedProperties) {
+    if styles.7.get(id).is_none() {
+        return;
+    }
+    for (property, value) in state.bases.drain(..) {
+        write_value(id, property, &value, styles, animated);
+    }
+}
+
+fn write_value(
+    id: usize,
+    property: TransitionProperty,
+    value: &TransitionValue,
+    styles: &mut Styles,
+    animated: &mut AnimatedProperties,
+) {
+    let changed = match get_value(id, property, styles) {
+        Some(r) => !value_eq(&r, value),
+        None => true,
+    };
+    if changed {
+        set_value(id, value, property, styles);
+    }
+    *animated.0.entry(id).or_insert(0) |= 1 << property as usize;
+}
+
+// 取得class中对应属性的值
+fn class_value(class: &Class, property: TransitionProperty) -> Option<TransitionValue> {
+    match property {
+        TransitionProperty::Opacity | TransitionProperty::Width | TransitionProperty::Height => {
+            class.attrs2.iter().rev().filter_map(|attr| match (property, attr) {
+                (TransitionProperty::Opacity, &Attribute2::Opacity(ref r)) => Some(TransitionValue::Float(r.0)),
+                (TransitionProperty::Width, &Attribute2::Width(ref r)) => Some(TransitionValue::Dimension(r.clone())),
+                (TransitionProperty::Height, &Attribute2::Height(ref r)) => Some(TransitionValue::Dimension(r.clone())),
+                _ => None,
+            }).next()
+        }
+        _ => class.attrs3.iter().rev().filter_map(|attr| match (property, attr) {
+            (TransitionProperty::BackgroundColor, &Attribute3::BGColor(ref r)) => Some(TransitionValue::Color(r.0.clone())),
+            (TransitionProperty::BorderColor, &Attribute3::BorderColor(ref r)) => Some(TransitionValue::CgColor(r.0.clone())),
+            (TransitionProperty::Color, &Attribute3::Color(ref r)) => Some(TransitionValue::Color(r.clone())),
+            (TransitionProperty::Transform, &Attribute3::TransformFunc(ref r)) => Some(TransitionValue::Transform(r.clone())),
+            (TransitionProperty::Filter, &Attribute3::Filter(ref r)) => {
+                Some(TransitionValue::Filter(r.hue_rotate, r.saturate, r.bright_ness))
+            }
+            _ => None,
+        }).next(),
+    }
+}
+
+impl_system! {
+    AnimationSys,
+    true,
+    {
+        MultiCaseListener<Node, Animation, CreateEvent>
+        MultiCaseListener<Node, Animation, ModifyEvent>
+        MultiCaseListener<Node, Animation, DeleteEvent>
+        EntityListener<Node, DeleteEvent>
+    }
+}
+
+#[cfg(test)]
+use ecs::{Lend, LendMut};
+#[cfg(test)]
+use single::style_parse::{parse_animation, parse_keyframes};
+#[cfg(test)]
+use util::snapshot::*;
+
+#[test]
+fn test_animation() {
+    let mut scene = SceneDesc::new(20, 20);
+    scene.css = "@keyframes flash{from{background-color:#ff0000} to{background-color:#0000ff}}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let node = gui.append(&NodeDesc::new("width:20px;height:20px;background-color:#00ff00"), root);
+    gui.gui.animation.lend_mut().insert(node, parse_animation("flash 100ms linear").unwrap());
+
+    let events = |gui: &SnapshotGui| -> Vec<AnimationEventType> {
+        gui.gui.animation_events.lend().0.iter().map(|r| r.ty).collect()
+    };
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(events(&gui), vec![AnimationEventType::Start]);
+
+    gui.gui.system_time.lend_mut().cur_time = 50;
+    gui.render();
+    let p = gui.pixel(0, 0);
+    assert!((p[0] as i32 - 128).abs() <= 1 && p[1] == 0 && (p[2] as i32 - 128).abs() <= 1);
+    assert_eq!(events(&gui), vec![]);
+
+    // 结束后恢复为动画开始前的颜色
+    gui.gui.system_time.lend_mut().cur_time = 100;
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [0, 255, 0, 255]);
+    assert_eq!(events(&gui), vec![AnimationEventType::End]);
+}
+
+#[test]
+fn test_animation_class() {
+    // 样式表中设置的动画， 移除class后动画被删除， 属性恢复
+    let mut scene = SceneDesc::new(20, 20);
+    scene.css = "@keyframes flash{from{background-color:#ff0000} to{background-color:#0000ff}} .1{animation:flash 100ms linear infinite}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let node = gui.append(&NodeDesc::new("width:20px;height:20px;background-color:#00ff00").with_class(&[1]), root);
+    assert!(gui.gui.animation.lend().get(node).is_some());
+
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+
+    gui.gui.system_time.lend_mut().cur_time = 50;
+    gui.render();
+    let p = gui.pixel(0, 0);
+    assert!((p[0] as i32 - 128).abs() <= 1 && p[1] == 0 && (p[2] as i32 - 128).abs() <= 1);
+
+    let inline = gui.gui.class_name.lend().get(node).unwrap().two;
+    gui.set_class(node, &[inline]);
+    assert!(gui.gui.animation.lend().get(node).is_none());
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [0, 255, 0, 255]);
+}
+
+#[test]
+fn test_animation_time_reset() {
+    // 系统时间被重置到动画开始之前， 视为动画尚未开始
+    let mut scene = SceneDesc::new(20, 20);
+    scene.css = "@keyframes flash{from{background-color:#ff0000} to{background-color:#0000ff}}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let node = gui.append(&NodeDesc::new("width:20px;height:20px;background-color:#00ff00"), root);
+    gui.gui.system_time.lend_mut().cur_time = 100;
+    gui.gui.animation.lend_mut().insert(node, parse_animation("flash 100ms linear").unwrap());
+    gui.render();
+
+    gui.gui.system_time.lend_mut().cur_time = 0;
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+}
+
+#[test]
+fn test_keyframes_offset() {
+    let frames = parse_keyframes("to{opacity:1} 50%{opacity:0.5} from{opacity:0}").unwrap();
+    let offsets: Vec<f32> = frames.frames.iter().map(|r| r.0).collect();
+    assert_eq!(offsets, vec![0.0, 0.5, 1.0]);
+
+    assert!(parse_keyframes("NaN%{opacity:0}").is_err());
+    assert!(parse_keyframes("inf%{opacity:0}").is_err());
+    assert!(parse_keyframes("150%{opacity:0}").is_err());
+    assert!(parse_animation("flash 1s NaN").is_err());
+}
@@ -0,0 +1,417 @@
+/**
+ * 事件系统
+ * 命中测试： 用八叉树查询点所在的节点， 取z最大、可响应事件（Enable）、未被裁剪、且按节点的命中测试方式（HitTest）命中的节点
+ * 事件分发： 宿主输入指针及滚轮事件， 命中目标后， 沿IdTree的祖先路径分发（见single::event）， 同一指针按下、抬起后合成Click事件
+ * 未取消默认行为的指针事件还会输入给输入框（选择文字）和滚动容器（拖动）
+ * EventSys监听节点的删除， 删除节点上的所有监听器
+ */
+use cg2d::{include_quad2, InnOuter};
+use ecs::{DeleteEvent, EntityListener, Lend, LendMut, MultiCaseImpl, SingleCaseImpl};
+use hal_core::HalContext;
+use octree::intersects;
+
+use component::calc::{ByOverflow, Enable, LayoutR, WorldMatrix, ZDepth};
+use component::user::{
+    Aabb3, BorderRadius, HitTest, HitTestType, Image, ImageClip, ObjectFit, Point2, Point3, Transform, TransformOrigin, Vector4,
+};
+use entity::Node;
+use single::*;
+use system::render::get_pos_uv;
+use system::scroll::scroll_pointer_event;
+use system::text_input::text_input_pointer_event;
+use system::util::{cal_border_radius, BorderRadiusPixel};
+use world::GuiWorld;
+use Z_MAX;
+
+#[derive(Default)]
+pub struct EventSys;
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for EventSys {
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<EventDispatcher>;
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, dispatcher: Self::WriteData) {
+        dispatcher.remove_node(event.id);
+    }
+}
+
+impl_system! {
+    EventSys,
+    false,
+    {
+        EntityListener<Node, DeleteEvent>
+    }
+}
+
+/// 分发宿主输入的事件， 返回事件是否被取消默认行为
+/// PointerCancel分发给该指针按下时命中的节点， 其它事件分发给点所在的节点；
+/// PointerUp未被取消默认行为， 且指针没有拖动滚动容器时， 在按下和抬起命中节点的最近公共祖先上合成Click事件（仅主键）
+pub fn dispatch_event<C: HalContext + 'static>(world: &GuiWorld<C>, mut event: Event) -> bool {
+    let idtree = world.idtree.lend();
+    let dispatcher = world.event_dispatcher.lend_mut();
+
+    let target = match event.ty {
+        EventType::PointerCancel => dispatcher.pointer_release(event.pointer_id).unwrap_or(0),
+        _ => query(world, event.x, event.y),
+    };
+    let path = event_path(idtree, target);
+    let prevented = if target > 0 {
+        dispatcher.dispatch(&path, &mut event)
+    } else {
+        false
+    };
+    // 输入框响应按下和拖动选择， 滚动容器响应拖动和滚轮， 取消默认行为时不响应， 但抬起总是结束拖动
+    let selecting = match event.ty {
+        EventType::PointerUp | EventType::PointerCancel => text_input_pointer_event(world, &event, &path),
+        _ if !prevented => text_input_pointer_event(world, &event, &path),
+        _ => false,
+    };
+    let dragged = match event.ty {
+        EventType::PointerUp | EventType::PointerCancel => scroll_pointer_event(world, &event, &path),
+        // 正在输入框中选择文字时不拖动滚动容器
+        EventType::PointerMove if selecting => false,
+        _ if !prevented => scroll_pointer_event(world, &event, &path),
+        _ => false,
+    };
+
+    match event.ty {
+        EventType::PointerDown if target > 0 => dispatcher.pointer_down(event.pointer_id, target),
+        EventType::PointerUp => {
+            let down = dispatcher.pointer_release(event.pointer_id).unwrap_or(0);
+            let common = common_ancestor(idtree, down, target);
+            if common > 0 && !prevented && !dragged && event.button == 0 {
+                let mut click = Event::new(EventType::Click, event.x, event.y);
+                click.pointer_id = event.pointer_id;
+                click.buttons = event.buttons;
+                click.time = event.time;
+                dispatcher.dispatch(&event_path(idtree, common), &mut click);
+            }
+        }
+        _ => (),
+    }
+    prevented
+}
+
+/// 用点命中一个节点， 返回0表示未命中
+pub fn query<C: HalContext + 'static>(world: &GuiWorld<C>, x: f32, y: f32) -> usize {
+    let octree = world.oct.lend();
+    let aabb = Aabb3::new(Point3::new(x, y, -Z_MAX), Point3::new(x, y, Z_MAX));
+    let mut args = AbQueryArgs::new(world, aabb.clone(), 0);
+    octree.query(&aabb, intersects, &mut args, ab_query_func);
+    args.result
+}
+
+/// 节点及其所有祖先， 第一个为节点自身， 最后一个为根； 节点不在根树上时， 返回空
+pub fn event_path(idtree: &IdTree, node: usize) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut id = node;
+    while id > 0 {
+        match idtree.get(id) {
+            Some(r) if r.layer() > 0 => {
+                path.push(id);
+                id = r.parent();
+            }
+            _ => return Vec::new(),
+        }
+    }
+    path
+}
+
+// 两个节点的最近公共祖先（包括节点自身）， 返回0表示不存在
+fn common_ancestor(idtree: &IdTree, a: usize, b: usize) -> usize {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let path = event_path(idtree, a);
+    for id in event_path(idtree, b).into_iter() {
+        if path.contains(&id) {
+            return id;
+        }
+    }
+    0
+}
+
+/// aabb的查询函数的参数
+pub struct AbQueryArgs<'a> {
+    enables: &'a MultiCaseImpl<Node, Enable>,
+    by_overflows: &'a MultiCaseImpl<Node, ByOverflow>,
+    z_depths: &'a MultiCaseImpl<Node, ZDepth>,
+    overflow_clip: &'a SingleCaseImpl<OverflowClip>,
+    id_tree: &'a SingleCaseImpl<IdTree>,
+    world_matrixs: &'a MultiCaseImpl<Node, WorldMatrix>,
+    transforms: &'a MultiCaseImpl<Node, Transform>,
+    layouts: &'a MultiCaseImpl<Node, LayoutR>,
+    border_radiuses: &'a MultiCaseImpl<Node, BorderRadius>,
+    images: &'a MultiCaseImpl<Node, Image>,
+    image_clips: &'a MultiCaseImpl<Node, ImageClip>,
+    object_fits: &'a MultiCaseImpl<Node, ObjectFit>,
+    hit_tests: &'a MultiCaseImpl<Node, HitTest>,
+    aabb: Aabb3,
+    ev_type: u32,
+    max_z: f32,
+    pub result: usize,
+}
+impl<'a> AbQueryArgs<'a> {
+    pub fn new<C: HalContext + 'static>(world: &'a GuiWorld<C>, aabb: Aabb3, ev_type: u32) -> AbQueryArgs<'a> {
+        AbQueryArgs {
+            enables: world.enable.lend(),
+            by_overflows: world.by_overflow.lend(),
+            z_depths: world.z_depth.lend(),
+            overflow_clip: world.overflow_clip.lend(),
+            id_tree: world.idtree.lend(),
+            world_matrixs: world.world_matrix.lend(),
+            transforms: world.transform.lend(),
+            layouts: world.layout.lend(),
+            border_radiuses: world.border_radius.lend(),
+            images: world.image.lend(),
+            image_clips: world.image_clip.lend(),
+            object_fits: world.object_fit.lend(),
+            hit_tests: world.hit_test.lend(),
+            aabb: aabb,
+            ev_type: ev_type,
+            max_z: -Z_MAX,
+            result: 0,
+        }
+    }
+}
+
+/// aabb的ab查询函数, aabb的oct查询函数应该使用intersects
+pub fn ab_query_func(arg: &mut AbQueryArgs, _id: usize, aabb: &Aabb3, bind: &usize) {
+    match arg.id_tree.get(*bind) {
+        Some(node) => {
+            if node.layer() == 0 {
+                return;
+            }
+        }
+        None => return,
+    };
+    if intersects(&arg.aabb, aabb) {
+        //如果enable false 表示不接收事件
+        if !arg.enables[*bind].0 {
+            return;
+        }
+        // 取最大z的node
+        let z_depth = arg.z_depths[*bind].0;
+        if z_depth > arg.max_z {
+            let by_overflow = arg.by_overflows[*bind].0;
+            // 检查是否有裁剪，及是否在裁剪范围内， 再检查是否命中节点的形状
+            if (by_overflow == 0
+                || in_overflow(
+                    &arg.overflow_clip,
+                    by_overflow,
+                    arg.aabb.min.x,
+                    arg.aabb.min.y,
+                ))
+                && hit_node(arg, *bind, arg.aabb.min.x, arg.aabb.min.y)
+            {
+                arg.result = *bind;
+                arg.max_z = z_depth;
+            }
+        }
+    }
+}
+
+// 按节点的命中测试方式， 检查世界坐标系下的点是否命中节点
+// 点经过世界矩阵的逆变换， 转换到布局框的局部坐标系（左上角为原点）中检查
+fn hit_node(arg: &AbQueryArgs, id: usize, x: f32, y: f32) -> bool {
+    let mode = match arg.hit_tests.get(id) {
+        Some(r) => r.0,
+        None => HitTestType::Shape,
+    };
+    if mode == HitTestType::Bounds {
+        return true;
+    }
+    let layout = match arg.layouts.get(id) {
+        Some(r) => r,
+        None => return true,
+    };
+    let width = layout.rect.end - layout.rect.start;
+    let height = layout.rect.bottom - layout.rect.top;
+    let origin = match arg.transforms.get(id) {
+        Some(r) => r.origin.to_value(width, height),
+        None => TransformOrigin::Center.to_value(width, height),
+    };
+    // 世界矩阵以变换原点为原点
+    let p = match arg.world_matrixs[id].invert() {
+        Some(m) => &m * Vector4::new(x, y, 0.0, 1.0),
+        None => return false, // 矩阵不可逆（如缩放为0）， 节点没有面积
+    };
+    let (x, y) = (p.x + origin.x, p.y + origin.y);
+    if x < 0.0 || x > width || y < 0.0 || y > height {
+        return false;
+    }
+    if !in_border_radius(&cal_border_radius(arg.border_radiuses.get(id), layout), width, height, x, y) {
+        return false;
+    }
+    if mode == HitTestType::OpaquePixels {
+        if let Some(image) = arg.images.get(id) {
+            if let Some(mask) = image.src.as_ref().and_then(|r| r.alpha_mask.as_ref()) {
+                let (pos, uv) = get_pos_uv(image, arg.image_clips.get(id), arg.object_fits.get(id), layout);
+                if x < pos.min.x || x > pos.max.x || y < pos.min.y || y > pos.max.y {
+                    return false;
+                }
+                let u = uv.min.x + (x - pos.min.x) / (pos.max.x - pos.min.x) * (uv.max.x - uv.min.x);
+                let v = uv.min.y + (y - pos.min.y) / (pos.max.y - pos.min.y) * (uv.max.y - uv.min.y);
+                return mask.get(u, v) > 0;
+            }
+        }
+    }
+    true
+}
+
+// 检查布局框内的点是否在圆角以内（每个角为椭圆）
+fn in_border_radius(radius: &BorderRadiusPixel, width: f32, height: f32, x: f32, y: f32) -> bool {
+    // 左上、右上、右下、左下角的椭圆中心
+    let centers = [
+        (radius.0[0].0, radius.0[0].1),
+        (width - radius.0[1].0, radius.0[1].1),
+        (width - radius.0[2].0, height - radius.0[2].1),
+        (radius.0[3].0, height - radius.0[3].1),
+    ];
+    for i in 0..4 {
+        let (rx, ry) = radius.0[i];
+        if rx <= 0.0 || ry <= 0.0 {
+            continue;
+        }
+        let (cx, cy) = centers[i];
+        // 点是否在该角的椭圆所在的象限内
+        let out_x = if i == 0 || i == 3 { x < cx } else { x > cx };
+        let out_y = if i < 2 { y < cy } else { y > cy };
+        if out_x && out_y {
+            let (dx, dy) = ((x - cx) / rx, (y - cy) / ry);
+            if dx * dx + dy * dy > 1.0 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// 检查坐标是否在裁剪范围内， 直接在裁剪面上检查
+fn in_overflow(
+    overflow_clip: &SingleCaseImpl<OverflowClip>,
+    by_overflow: usize,
+    x: f32,
+    y: f32,
+) -> bool {
+    let xy = Point2::new(x, y);
+    // 沿裁剪链向外检查每一个裁剪区域， 非矩形的裁剪区域检查其三角形网格
+    let mut i = by_overflow;
+    while i > 0 {
+        let c = &overflow_clip.clip[i];
+        let p = &c.view;
+        match include_quad2(&xy, &p[0], &p[1], &p[2], &p[3]) {
+            InnOuter::Inner => (),
+            _ => return false,
+        }
+        if let Some((positions, indices)) = &c.shape {
+            if !in_triangles(positions, indices, x, y) {
+                return false;
+            }
+        }
+        i = c.parent;
+    }
+    return true;
+}
+
+// 点是否在三角形网格中的某个三角形内（包括边上）
+fn in_triangles(positions: &[f32], indices: &[u16], x: f32, y: f32) -> bool {
+    let point = |i: u16| (positions[i as usize * 2], positions[i as usize * 2 + 1]);
+    let cross = |a: (f32, f32), b: (f32, f32)| (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0);
+    for t in indices.chunks(3) {
+        if t.len() < 3 {
+            break;
+        }
+        let (a, b, c) = (point(t[0]), point(t[1]), point(t[2]));
+        let (d1, d2, d3) = (cross(a, b), cross(b, c), cross(c, a));
+        // 三个叉积同号（与三角形的环绕方向无关）
+        if (d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0) || (d1 <= 0.0 && d2 <= 0.0 && d3 <= 0.0) {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+use std::cell::RefCell;
+#[cfg(test)]
+use std::rc::Rc;
+#[cfg(test)]
+use share::Share;
+#[cfg(test)]
+use util::snapshot::*;
+
+#[test]
+fn test_dispatch_event() {
+    let mut gui = SnapshotGui::new(SceneDesc::new(100, 100));
+    let root = gui.root;
+    let parent = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:100px;height:100px"), root);
+    let child = gui.append(&NodeDesc::new("position:absolute;left:10px;top:10px;width:40px;height:40px"), parent);
+    gui.render();
+    assert_eq!(query(&gui.gui, 20.0, 20.0), child);
+    assert_eq!(query(&gui.gui, 80.0, 80.0), parent);
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    {
+        let dispatcher = gui.gui.event_dispatcher.lend_mut();
+        for &(node, ty, capture) in [
+            (parent, EventType::PointerDown, true),
+            (parent, EventType::PointerDown, false),
+            (child, EventType::PointerDown, false),
+            (parent, EventType::Click, false),
+            (child, EventType::Click, false),
+        ].iter() {
+            let log = log.clone();
+            dispatcher.add_listener(node, ty, capture, Share::new(move |e: &mut Event| {
+                log.borrow_mut().push((e.ty, e.target, e.current_target, e.phase));
+            }));
+        }
+    }
+
+    let pointer = |gui: &SnapshotGui, ty: EventType, x: f32, y: f32| {
+        dispatch_event(&gui.gui, Event::new(ty, x, y));
+    };
+    pointer(&gui, EventType::PointerDown, 20.0, 20.0);
+    pointer(&gui, EventType::PointerUp, 20.0, 20.0);
+    assert_eq!(*log.borrow(), vec![
+        (EventType::PointerDown, child, parent, EventPhase::Capture),
+        (EventType::PointerDown, child, child, EventPhase::Target),
+        (EventType::PointerDown, child, parent, EventPhase::Bubble),
+        (EventType::Click, child, child, EventPhase::Target),
+        (EventType::Click, child, parent, EventPhase::Bubble),
+    ]);
+
+    // 在子节点上按下， 在父节点上抬起， Click的目标为最近公共祖先
+    log.borrow_mut().clear();
+    pointer(&gui, EventType::PointerDown, 20.0, 20.0);
+    pointer(&gui, EventType::PointerUp, 80.0, 80.0);
+    assert_eq!(log.borrow().last(), Some(&(EventType::Click, parent, parent, EventPhase::Target)));
+
+    // 取消后不再合成Click
+    log.borrow_mut().clear();
+    pointer(&gui, EventType::PointerDown, 20.0, 20.0);
+    pointer(&gui, EventType::PointerCancel, 0.0, 0.0);
+    pointer(&gui, EventType::PointerUp, 20.0, 20.0);
+    assert!(log.borrow().iter().all(|r| r.0 != EventType::Click));
+}
+
+#[test]
+fn test_hit_shape() {
+    let mut gui = SnapshotGui::new(SceneDesc::new(100, 100));
+    let root = gui.root;
+    // 旋转45度的正方形， 中心为(30, 30)
+    let rotated = gui.append(&NodeDesc::new("position:absolute;left:10px;top:10px;width:40px;height:40px;transform:rotate(45deg)"), root);
+    // 圆形， 中心为(75, 75)
+    let round = gui.append(&NodeDesc::new("position:absolute;left:60px;top:60px;width:30px;height:30px;border-radius:15px"), root);
+    gui.render();
+
+    assert_eq!(query(&gui.gui, 30.0, 30.0), rotated);
+    assert_eq!(query(&gui.gui, 30.0, 5.0), rotated);
+    // 在布局框内， 但在旋转后的形状外
+    assert_ne!(query(&gui.gui, 12.0, 12.0), rotated);
+    assert_eq!(query(&gui.gui, 75.0, 75.0), round);
+    // 在圆角外
+    assert_ne!(query(&gui.gui, 62.0, 62.0), round);
+
+    gui.gui.hit_test.lend_mut().insert(round, HitTest(HitTestType::Bounds));
+    assert_eq!(query(&gui.gui, 62.0, 62.0), round);
+}
@@ -1,17 +1,23 @@
 /// 布局系统
 /// 1.负责处理布局属性的脏，根据不同的脏，设置flex_layout节点的脏类型
 /// 负责推动flex_layout节点进行布局
+/// 2.文字节点不参与flex的字符布局， 由行布局按节点的可用宽度折行， 文字块的大小设置在文字节点唯一的子节点（测量节点）上， 由flex布局得到文字节点的大小
+///   flex_layout没有测量回调， 测量节点的大小改变时， 重新布局一次
+/// 3.flex_layout只按从左向右布局， 书写方向为从右向左的容器， 在布局后将其子节点水平镜像
 use single::{IdTree, DirtyList};
 use ecs::{
     CreateEvent, DeleteEvent, EntityListener, ModifyEvent, MultiCaseImpl,
-    SingleCaseImpl, SingleCaseListener,Runner
+    SingleCaseImpl, SingleCaseListener, MultiCaseListener, Runner
 };
 use component::user::{OtherLayoutStyle, RectLayoutStyle};
-use component::calc::{LayoutR, StyleType1, StyleMark, NodeState, StyleType2, LAYOUT_MARGIN_MARK, LAYOUT_POSITION_MARK, LAYOUT_BORDER_MARK, LAYOUT_PADDING_MARK};
+use system::util::is_rtl;
+use component::calc::{LayoutR, StyleType1, StyleMark, NodeState, StyleType2, CharBlock, LAYOUT_MARGIN_MARK, LAYOUT_POSITION_MARK, LAYOUT_BORDER_MARK, LAYOUT_PADDING_MARK};
 use flex_layout::*;
 use dirty::*;
+use hash::XHashMap;
 use map::vecmap::VecMap;
 use util::vecmap_default::VecMapWithDefault;
+use font::line_box;
 
 use entity::Node;
 
@@ -47,19 +53,28 @@ pub const DIRTY2: usize = RECT_DIRTY | NORMAL_DIRTY | SELF_DIRTY | CHILD_DIRTY;
 #[derive(Default)]
 pub struct LayoutSys{
 	dirty: LayerDirty,
+	texts: XHashMap<usize, ()>, // 存在文字块的节点
+	changed: Vec<usize>, // 本次布局结果改变的节点
+}
+
+// 布局结果的通知上下文， 同时记录布局结果改变的节点
+struct NotifyContext<'a> {
+	layouts: &'a mut MultiCaseImpl<Node, LayoutR>,
+	changed: &'a mut Vec<usize>,
 }
 
 impl<'a> Runner<'a> for LayoutSys {
 	type ReadData = ( 
-		&'a MultiCaseImpl<Node, RectLayoutStyle>,
-		&'a MultiCaseImpl<Node, OtherLayoutStyle>,
 		&'a SingleCaseImpl<IdTree>, 
 		&'a SingleCaseImpl<DirtyList>);
 	type WriteData = (
 		&'a mut MultiCaseImpl<Node, LayoutR>,
 		&'a mut MultiCaseImpl<Node, NodeState>,
-		&'a mut MultiCaseImpl<Node, StyleMark>, );
-    fn run(&mut self, (rect_layout_styles, other_layout_styles, tree, dirty_list, ): Self::ReadData, (layouts, node_states, style_marks): Self::WriteData) {
+		&'a mut MultiCaseImpl<Node, StyleMark>,
+		&'a mut MultiCaseImpl<Node, RectLayoutStyle>,
+		&'a mut MultiCaseImpl<Node, OtherLayoutStyle>,
+		&'a mut MultiCaseImpl<Node, CharBlock>, );
+    fn run(&mut self, (tree, dirty_list, ): Self::ReadData, (layouts, node_states, style_marks, rect_layout_styles, other_layout_styles, char_blocks): Self::WriteData) {
 		if dirty_list.0.len() == 0 {
             return;
 		}
@@ -78,7 +93,7 @@ impl<'a> Runner<'a> for LayoutSys {
 			let dirty1 = style_mark.dirty1;
 
             // 不存在LayoutTree关心的脏, 跳过
-            if dirty & DIRTY2 == 0 && dirty1 & StyleType1::Display as usize == 0 && dirty1 & StyleType1::FlexBasis as usize == 0 && dirty1 & StyleType1::Create as usize == 0 {
+            if dirty & DIRTY2 == 0 && dirty1 & StyleType1::Display as usize == 0 && dirty1 & StyleType1::FlexBasis as usize == 0 && dirty1 & StyleType1::Create as usize == 0 && dirty1 & StyleType1::Direction as usize == 0 {
                 continue;
 			}
 
@@ -107,10 +122,141 @@ impl<'a> Runner<'a> for LayoutSys {
 			if dirty1 & StyleType1::Display as usize != 0 {
 				set_display(*id, other_style.display, &mut self.dirty, tree, node_states, flex_rect_styles, flex_other_styles);
 			}
+
+			// 书写方向改变， 子孙节点的排列方向都可能改变， 重新布局， 并在布局后检查是否需要镜像
+			if dirty1 & StyleType1::Direction as usize != 0 {
+				set_children_style(tree, node_states, &mut self.dirty, *id, other_style);
+				for (child, _) in tree.recursive_iter(tree[*id].children().head) {
+					set_children_style(tree, node_states, &mut self.dirty, child, &flex_other_styles[child]);
+					self.changed.push(child);
+				}
+			}
 			style_mark.dirty2 &= !DIRTY2;
 			style_mark.dirty1 &= !(StyleType1::Display as usize | StyleType1::FlexBasis as usize | StyleType1::Create as usize);
 		}
-		compute(&mut self.dirty, tree, node_states, flex_rect_styles, flex_other_styles, flex_layouts, notify, layouts);
+		compute(&mut self.dirty, tree, node_states, flex_rect_styles, flex_other_styles, flex_layouts, notify, &mut NotifyContext{layouts: &mut *layouts, changed: &mut self.changed});
+
+		// 按可用宽度对文字块折行， 文字块的大小设置为测量节点的大小
+		// 可用宽度只取决于设置了宽度的祖先及兄弟节点的布局结果， 不受文字块大小的影响， 因此最多只需要重新布局一次
+		let mut change = false;
+		for (id, _) in self.texts.iter() {
+			let id = *id;
+			let block = match char_blocks.get_mut(id) {
+				Some(r) => r,
+				None => continue,
+			};
+			if block.mixed {
+				continue;
+			}
+			let width = avail_width(id, tree, layouts, rect_layout_styles, other_layout_styles, style_marks, node_states);
+			if !block.dirty && (width == block.wrap_width || (width - block.wrap_width).abs() < 0.01) {
+				continue;
+			}
+			block.dirty = false;
+			block.wrap_width = width;
+			block.content_box.2 = -1.0; // 折行后需要重新排列字符
+			let size = line_box::break_lines(block, width);
+
+			let child = tree[id].children().head;
+			if child == 0 {
+				continue;
+			}
+			let (w, h) = (Dimension::Points(size.0), Dimension::Points(size.1));
+			let style = &mut rect_layout_styles[child].size;
+			if style.width != w || style.height != h {
+				style.width = w;
+				style.height = h;
+				set_rect(tree, node_states, &mut self.dirty, child, &flex_rect_styles[child], &flex_other_styles[child], true, true);
+				change = true;
+			}
+		}
+		if change {
+			compute(&mut self.dirty, tree, node_states, flex_rect_styles, flex_other_styles, flex_layouts, notify, &mut NotifyContext{layouts: &mut *layouts, changed: &mut self.changed});
+		}
+
+		// 从右向左的容器， 镜像其子节点的水平位置
+		// flex_layout只重写重新布局的节点， 未改变的节点保留上次镜像的结果， 因此只处理布局结果改变的节点
+		self.changed.sort();
+		self.changed.dedup();
+		for id in self.changed.drain(..) {
+			if node_states[id].is_vnode() {
+				continue;
+			}
+			// 图文混排的字符节点相对于虚拟节点的父节点布局
+			let mut parent = tree[id].parent();
+			while parent > 0 && node_states[parent].is_vnode() {
+				parent = tree[parent].parent();
+			}
+			// 指定了左边位置的节点不镜像
+			if parent == 0 || other_layout_styles[id].position.start != Dimension::Undefined || !is_rtl(parent, tree, other_layout_styles) {
+				continue;
+			}
+			// 在父节点的内容区内镜像， 左右的边框和内边距可能不同
+			let (content_start, content_end) = {
+				let layout = &layouts[parent];
+				(
+					layout.border.start + layout.padding.start,
+					layout.rect.end - layout.rect.start - layout.border.end - layout.padding.end,
+				)
+			};
+			let rect = &mut layouts[id].rect;
+			let (start, end) = (rect.start, rect.end);
+			rect.start = content_start + content_end - end;
+			rect.end = content_start + content_end - start;
+			layouts.get_notify_ref().modify_event(id, "", 0);
+		}
+
+		// 在节点的内容区中排列字符
+		for (id, _) in self.texts.iter() {
+			let id = *id;
+			let block = match char_blocks.get_mut(id) {
+				Some(r) => r,
+				None => continue,
+			};
+			if block.mixed {
+				continue;
+			}
+			let content_box = {
+				let layout = &layouts[id];
+				let (w, h) = insets(layout);
+				(
+					layout.border.start + layout.padding.start,
+					layout.border.top + layout.padding.top,
+					layout.rect.end - layout.rect.start - w,
+					layout.rect.bottom - layout.rect.top - h,
+				)
+			};
+			if content_box != block.content_box {
+				block.content_box = content_box;
+				line_box::position(block, content_box.0, content_box.1, content_box.2, content_box.3);
+				layouts.get_notify_ref().modify_event(id, "", 0);
+			}
+		}
+	}
+}
+
+// 文字块创建， 记录文字节点
+impl<'a> MultiCaseListener<'a, Node, CharBlock, CreateEvent> for LayoutSys {
+	type ReadData = ();
+	type WriteData = ();
+	fn listen(&mut self, event: &CreateEvent, _: Self::ReadData, _: Self::WriteData) {
+		self.texts.insert(event.id, ());
+	}
+}
+
+impl<'a> MultiCaseListener<'a, Node, CharBlock, DeleteEvent> for LayoutSys {
+	type ReadData = ();
+	type WriteData = ();
+	fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, _: Self::WriteData) {
+		self.texts.remove(&event.id);
+	}
+}
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for LayoutSys {
+	type ReadData = ();
+	type WriteData = ();
+	fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, _: Self::WriteData) {
+		self.texts.remove(&event.id);
 	}
 }
 
@@ -172,9 +318,97 @@ impl<'a> SingleCaseListener<'a, IdTree, DeleteEvent> for LayoutSys {
     }
 }
 
-fn notify(context: &mut MultiCaseImpl<Node, LayoutR>, id: usize, _layout:&flex_layout::LayoutR) {
+fn notify(context: &mut NotifyContext, id: usize, _layout:&flex_layout::LayoutR) {
 	// println!("notify======================={}, layout:{:?}", id, layout);
-	context.get_notify_ref().modify_event(id, "", 0);
+	context.layouts.get_notify_ref().modify_event(id, "", 0);
+	context.changed.push(id);
+}
+
+// 水平和垂直方向上边框与内边距的和
+#[inline]
+fn insets(layout: &LayoutR) -> (f32, f32) {
+	(
+		layout.border.start + layout.border.end + layout.padding.start + layout.padding.end,
+		layout.border.top + layout.border.bottom + layout.padding.top + layout.padding.bottom,
+	)
+}
+
+#[inline]
+fn points(d: &Dimension) -> f32 {
+	match d {
+		&Dimension::Points(r) => r,
+		_ => 0.0,
+	}
+}
+
+// 文字块的可用宽度： 节点设置了宽度， 为节点的内容宽度； 否则为最近的设置了宽度的祖先（或根节点）的内容宽度， 减去中间各节点的外边距、 边框及内边距，
+// 以及在不折行的水平方向容器中， 与中间各节点排在同一行的兄弟节点所占的宽度（可伸展的兄弟节点占用的是剩余空间， 不计算在内）
+fn avail_width(
+	id: usize,
+	tree: &IdTree,
+	layouts: &MultiCaseImpl<Node, LayoutR>,
+	rect_layout_styles: &MultiCaseImpl<Node, RectLayoutStyle>,
+	other_layout_styles: &MultiCaseImpl<Node, OtherLayoutStyle>,
+	style_marks: &MultiCaseImpl<Node, StyleMark>,
+	node_states: &VecMap<INode>,
+) -> f32 {
+	let (mut node, mut used) = (id, 0.0);
+	loop {
+		let layout = &layouts[node];
+		let w = insets(layout).0;
+		let set_width = match style_marks.get(node) {
+			Some(r) => (r.local_style2 | r.class_style2) & StyleType2::Width as usize != 0,
+			None => false,
+		};
+		let parent = tree[node].parent();
+		if set_width || parent == 0 {
+			let width = layout.rect.end - layout.rect.start - w;
+			// 根节点尚未布局， 不限制宽度
+			if !set_width && width <= 0.0 {
+				return std::f32::INFINITY;
+			}
+			return (width - used).max(0.0);
+		}
+		let margin = &rect_layout_styles[node].margin;
+		used += w + points(&margin.start) + points(&margin.end);
+		used += siblings_width(node, parent, tree, layouts, rect_layout_styles, other_layout_styles, node_states);
+		node = parent;
+	}
+}
+
+// 在不折行的水平方向容器中， 与节点排在同一行的兄弟节点所占的宽度
+fn siblings_width(
+	id: usize,
+	parent: usize,
+	tree: &IdTree,
+	layouts: &MultiCaseImpl<Node, LayoutR>,
+	rect_layout_styles: &MultiCaseImpl<Node, RectLayoutStyle>,
+	other_layout_styles: &MultiCaseImpl<Node, OtherLayoutStyle>,
+	node_states: &VecMap<INode>,
+) -> f32 {
+	let style = &other_layout_styles[parent];
+	match (style.flex_direction, style.flex_wrap) {
+		(FlexDirection::Row, FlexWrap::NoWrap) | (FlexDirection::RowReverse, FlexWrap::NoWrap) => (),
+		_ => return 0.0,
+	}
+	let mut r = 0.0;
+	for (child, _) in tree.iter(tree[parent].children().head) {
+		if child == id || node_states[child].is_vnode() {
+			continue;
+		}
+		let style = &other_layout_styles[child];
+		match (style.position_type, style.display) {
+			(PositionType::Absolute, _) | (_, Display::None) => continue,
+			_ => (),
+		}
+		if style.flex_grow > 0.0 {
+			continue;
+		}
+		let margin = &rect_layout_styles[child].margin;
+		let rect = &layouts[child].rect;
+		r += rect.end - rect.start + points(&margin.start) + points(&margin.end);
+	}
+	r
 }
 
 
@@ -183,8 +417,76 @@ impl_system! {
     true,
     {
         EntityListener<Node, CreateEvent>
+        EntityListener<Node, DeleteEvent>
+        MultiCaseListener<Node, CharBlock, CreateEvent>
+        MultiCaseListener<Node, CharBlock, DeleteEvent>
         SingleCaseListener<IdTree, DeleteEvent>
 		SingleCaseListener<IdTree, ModifyEvent>
 		SingleCaseListener<IdTree, CreateEvent>
     }
 }
+
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+#[cfg(test)]
+use ecs::Lend;
+
+#[test]
+fn test_text_avail_width() {
+	// 水平方向的容器中， 文字的可用宽度需要减去兄弟节点（图标）的宽度， 文字块的大小不写入最小尺寸
+	let mut scene = SceneDesc::new(100, 100);
+	scene.nodes.push(
+		NodeDesc::new("position:absolute;left:0px;top:0px;width:100px;height:100px;flex-direction:row;flex-wrap:nowrap;align-items:flex-start")
+			.with_child(NodeDesc::new("width:40px;height:16px;background-color:#ff0000"))
+			.with_child(NodeDesc::new("").with_child(NodeDesc::new("font-size:16px").with_text("一二三四五六七八九十"))),
+	);
+	let mut gui = SnapshotGui::new(scene);
+	gui.render();
+
+	let idtree = gui.gui.idtree.lend();
+	let container = idtree[gui.root].children().head;
+	let wrapper = idtree[idtree[container].children().head].next();
+	let text = idtree[wrapper].children().head;
+
+	// 每个字宽8px， 可用宽度为100 - 40 = 60px， 每行7个字
+	let char_blocks = gui.gui.char_block.lend();
+	let char_block = &char_blocks[text];
+	assert!(!char_block.mixed);
+	assert_eq!(char_block.wrap_width, 60.0);
+	assert_eq!(char_block.lines.len(), 2);
+
+	let layouts = gui.gui.layout.lend();
+	let rect = &layouts[text].rect;
+	assert!(rect.end - rect.start <= 60.0);
+	assert_eq!(rect.bottom - rect.top, char_block.lines.iter().fold(0.0, |r, l| r + l.height));
+	let rect = &layouts[wrapper].rect;
+	assert_eq!(rect.start, 40.0);
+
+	let other_layout_styles = gui.gui.other_layout_style.lend();
+	assert!(other_layout_styles[text].min_size.width == Dimension::Undefined);
+	assert!(other_layout_styles[text].min_size.height == Dimension::Undefined);
+}
+
+#[test]
+fn test_rtl_padding() {
+	// 从右向左的容器， 子节点在内容区内镜像， 左右内边距不同时， 第一个子节点紧贴内容区的右边
+	let mut scene = SceneDesc::new(100, 20);
+	scene.nodes.push(
+		NodeDesc::new("position:absolute;left:0px;top:0px;width:100px;height:20px;padding-left:20px;padding-right:0px;border-width:0px 5px 0px 0px;flex-direction:row;direction:rtl")
+			.with_child(NodeDesc::new("width:10px;height:10px"))
+			.with_child(NodeDesc::new("width:30px;height:10px")),
+	);
+	let mut gui = SnapshotGui::new(scene);
+	gui.render();
+
+	let idtree = gui.gui.idtree.lend();
+	let container = idtree[gui.root].children().head;
+	let first = idtree[container].children().head;
+	let second = idtree[first].next();
+	let layouts = gui.gui.layout.lend();
+	let width = layouts[container].rect.end - layouts[container].rect.start;
+	let (start, end) = (layouts[first].rect.start, layouts[first].rect.end);
+	assert_eq!((start, end), (width - 5.0 - 10.0, width - 5.0));
+	let (start, end) = (layouts[second].rect.start, layouts[second].rect.end);
+	assert_eq!((start, end), (width - 5.0 - 40.0, width - 5.0 - 10.0));
+}
@@ -11,6 +11,12 @@ pub mod render;
 mod filter;
 mod style_mark;
 mod transform_will_change;
+mod transition;
+mod animation;
+mod event;
+mod scroll;
+mod virtual_list;
+mod text_input;
 
 pub use system::transform_will_change::*;
 pub use system::style_mark::*;
@@ -24,4 +30,10 @@ pub use system::oct::*;
 pub use system::text_layout::*;
 pub use system::filter::*;
 pub use system::render::*;
+pub use system::transition::*;
+pub use system::animation::*;
+pub use system::event::*;
+pub use system::scroll::*;
+pub use system::virtual_list::*;
+pub use system::text_input::*;
 
@@ -1,6 +1,10 @@
 //裁剪矩形系统
-// 容器设置了overflow的，就会产生一个裁剪矩形及对应的编号（编号都是2的次方），其下的所有的物件的by_overflow将会被设置为受到该id的影响
+// 容器设置了overflow的，就会产生一个裁剪矩形及对应的编号，其下的所有的物件的by_overflow将会被设置为最内层的裁剪矩形的编号
+// 裁剪矩形通过parent指向外层的裁剪矩形，形成一条链，因此裁剪矩形的数量不受限制
 // 因为很少来回变动，所以直接根据变化进行设置，不采用dirty
+// 容器有圆角或clip-path时，裁剪区域不是矩形，与旋转的裁剪区域一样，通过裁剪纹理来裁剪
+// 包围盒不相交的裁剪区域在裁剪纹理中共用一位， 相交的裁剪区域超过裁剪纹理的位数时， 改用模板缓冲区
+// 裁剪方式为模板缓冲区（或裁剪纹理中没有可用的位）时，不占用裁剪纹理，由渲染系统将裁剪区域写入模板缓冲区来裁剪
 
 use ecs::{
     component::MultiCaseImpl,
@@ -17,7 +21,8 @@ use share::Share;
 use component::{calc::*, user::*, user::Overflow, calc::LayoutR};
 use dirty::LayerDirty;
 use entity::Node;
-use single::{Clip, DefaultTable, Oct, OverflowClip, ViewMatrix};
+use single::{Clip, DefaultTable, DirtyList, Oct, OverflowClip, ViewMatrix};
+use system::util::{cal_border_radius, fan_indices, split_by_border_radius, split_by_clip_path};
 
 type Read<'a> = (
     &'a SingleCaseImpl<IdTree>,
@@ -30,6 +35,9 @@ type Read<'a> = (
 	&'a MultiCaseImpl<Node, TransformWillChangeMatrix>,
 	&'a MultiCaseImpl<Node, NodeState>,
 	&'a SingleCaseImpl<ViewMatrix>,
+	&'a MultiCaseImpl<Node, BorderRadius>,
+	&'a MultiCaseImpl<Node, ClipPath>,
+	&'a SingleCaseImpl<DirtyList>,
 );
 type Write<'a> = (
     &'a mut SingleCaseImpl<OverflowClip>,
@@ -49,38 +57,20 @@ impl OverflowImpl {
 		if node.layer() == 0 {
 			return;
 		}
-		let overflow = *read.1[id];
-		let mut by = *write.1[id] ;
+		let overflow = read.1[id].is_clip();
 		let notify = unsafe { &* (write.0.get_notify_ref() as *const NotifyImpl)} ;
-		let index = if overflow {
-			let mut i = create_clip(id, write.0);
-			if i == 0 {
-				return;
-			}
-			self.mark_dirty(
-				id,
-				StyleType1::Overflow as usize,
-				node.layer(),
-				&mut write.3,
-			);
-			i = 1 << (i - 1);
-			by = add_index(by, i);
-			i
-		} else {
-			// 删除根上的overflow的裁剪矩形
-			let mut i = remove_index(&mut *write.0, id, &notify);
-			if i == 0 {
-				return;
-			}
-			i = 1 << (i - 1);
-			by = del_index(by, i);
-			i
-		};
-		if by & index != 0 {
-			adjust(&read.0, write.1, &read.8,node.children().head, index, add_index);
-		} else {
-			adjust(&read.0, write.1, &read.8,node.children().head, index, del_index);
+		if overflow {
+			create_clip(id, write.0);
+		} else if remove_index(&mut *write.0, id, &notify) == 0 {
+			return;
 		}
+		// 重新计算子节点的by_overflow，以及内层裁剪区域的parent
+		self.mark_dirty(
+			id,
+			StyleType1::Overflow as usize,
+			node.layer(),
+			&mut write.3,
+		);
 	}
 }
 
@@ -90,6 +80,26 @@ impl<'a> Runner<'a> for OverflowImpl {
     fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
         // let time = std::time::Instant::now();
         let (overflow_clip, by_overflows, cullings, style_marks) = write;
+        // 圆角改变（包括通过class设置的圆角）， 需要重新计算裁剪区域的形状
+        for id in (read.12).0.iter() {
+            match read.1.get(*id) {
+                Some(r) if r.is_clip() => (),
+                _ => continue,
+            };
+            let dirty = match style_marks.get(*id) {
+                Some(r) => r.dirty,
+                None => continue,
+            };
+            if dirty & StyleType::BorderRadius as usize != 0 {
+                let layer = match read.0.get(*id) {
+                    Some(r) => r.layer(),
+                    None => continue,
+                };
+                if layer != 0 {
+                    self.mark_dirty(*id, StyleType1::Overflow as usize, layer, style_marks);
+                }
+            }
+        }
         for (id, layer) in self.overflow_dirty.iter() {
             let dirty_other = match style_marks.get(*id) {
                 Some(r) => r.dirty_other,
@@ -146,7 +156,7 @@ impl<'a> MultiCaseListener<'a, Node, Overflow, DeleteEvent> for OverflowImpl {
         overflows: Self::ReadData,
         overflow_clip: Self::WriteData,
     ) {
-        let overflow = overflows[event.id].0;
+        let overflow = overflows[event.id].is_clip();
         if overflow {
 			let notify = unsafe { &* (overflow_clip.get_notify_ref() as *const NotifyImpl)} ;
             remove_index(&mut *overflow_clip, event.id, &notify);
@@ -300,6 +310,43 @@ impl<'a> MultiCaseListener<'a, Node, Transform, DeleteEvent> for OverflowImpl {
     }
 }
 
+// 监听裁剪路径的改变
+impl<'a> MultiCaseListener<'a, Node, ClipPath, CreateEvent> for OverflowImpl {
+    type ReadData = (
+        &'a MultiCaseImpl<Node, ByOverflow>,
+        &'a MultiCaseImpl<Node, Overflow>,
+        &'a SingleCaseImpl<IdTree>,
+    );
+    type WriteData = &'a mut MultiCaseImpl<Node, StyleMark>;
+    fn listen(&mut self, event: &CreateEvent, read: Self::ReadData, write: Self::WriteData) {
+        self.matrix_dirty(event.id, read, write);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, ClipPath, ModifyEvent> for OverflowImpl {
+    type ReadData = (
+        &'a MultiCaseImpl<Node, ByOverflow>,
+        &'a MultiCaseImpl<Node, Overflow>,
+        &'a SingleCaseImpl<IdTree>,
+    );
+    type WriteData = &'a mut MultiCaseImpl<Node, StyleMark>;
+    fn listen(&mut self, event: &ModifyEvent, read: Self::ReadData, write: Self::WriteData) {
+        self.matrix_dirty(event.id, read, write);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, ClipPath, DeleteEvent> for OverflowImpl {
+    type ReadData = (
+        &'a MultiCaseImpl<Node, ByOverflow>,
+        &'a MultiCaseImpl<Node, Overflow>,
+        &'a SingleCaseImpl<IdTree>,
+    );
+    type WriteData = &'a mut MultiCaseImpl<Node, StyleMark>;
+    fn listen(&mut self, event: &DeleteEvent, read: Self::ReadData, write: Self::WriteData) {
+        self.matrix_dirty(event.id, read, write);
+    }
+}
+
 impl<'a> MultiCaseListener<'a, Node, LayoutR, ModifyEvent> for OverflowImpl {
     type ReadData = (
         &'a MultiCaseImpl<Node, ByOverflow>,
@@ -328,11 +375,11 @@ impl<'a> SingleCaseListener<'a, IdTree, CreateEvent> for OverflowImpl {
         let node = &read.0[event.id];
         // 获得父节点的ByOverflow
         let mut by = *write.1[node.parent()];
-        let overflow = *read.1[node.parent()];
+        let overflow = read.1[node.parent()].is_clip();
         if overflow {
             let i = get_index(write.0, node.parent());
             if i > 0 {
-                by = add_index(by, 1 << (i - 1));
+                by = i;
             }
         }
         self.set_overflow(event.id, by, &read, &mut write);
@@ -353,7 +400,7 @@ impl<'a> SingleCaseListener<'a, IdTree, DeleteEvent> for OverflowImpl {
 		let node = &read.0[event.id];
 		let notify = unsafe { &* (write.0.get_notify_ref() as *const NotifyImpl)} ;
         if match read.1.get(event.id) {
-            Some(r) => **r,
+            Some(r) => r.is_clip(),
             _ => false,
         } {
             remove_index(&mut *write.0, event.id, &notify);
@@ -361,7 +408,7 @@ impl<'a> SingleCaseListener<'a, IdTree, DeleteEvent> for OverflowImpl {
 		unsafe {write.1.get_unchecked_write(event.id)}.set_0(0);
         // 递归调用，检查是否有overflow， 撤销设置OverflowClip
         for (id, _n) in read.0.recursive_iter(node.children().head) {
-            if *read.1[id] {
+            if read.1[id].is_clip() {
                 remove_index(&mut *write.0, id, &notify);
             }
             match write.1.get_write(event.id) {
@@ -383,7 +430,7 @@ impl<'a> EntityListener<'a, Node, ModifyEvent> for OverflowImpl {
     );
 
     fn listen(&mut self, event: &ModifyEvent, read: Self::ReadData, write: Self::WriteData) {
-        let overflow = *read.1[event.id];
+        let overflow = read.1[event.id].is_clip();
         if overflow {
 			let notify = unsafe { &* (write.0.get_notify_ref() as *const NotifyImpl)} ;
             remove_index(&mut *write.0, event.id, &notify);
@@ -427,7 +474,7 @@ impl OverflowImpl {
         if node.layer() == 0
             || (read.0[id].0 == 0
                 && !match read.1.get(id) {
-                    Some(r) => **r,
+                    Some(r) => r.is_clip(),
                     _ => false,
                 })
         {
@@ -461,7 +508,7 @@ impl OverflowImpl {
 		if !read.8[id].0.is_rnode() {
 			return;
 		}
-		let overflow = *read.1[id];
+		let overflow = read.1[id].is_clip();
         if by > 0 {
             unsafe {write.1.get_unchecked_write(id)}.set_0(by);
         }
@@ -475,9 +522,7 @@ impl OverflowImpl {
 				read.0[id].layer(),
                 &mut write.3,
             );
-            if i > 0 {
-                by = add_index(by, 1 << (i - 1));
-            }
+            by = i;
         }
 
         let node = &read.0[id];
@@ -517,6 +562,12 @@ fn calc_clip<'a>(
     cullings: &'a mut MultiCaseImpl<Node, Culling>,
     style_marks: &'a mut MultiCaseImpl<Node, StyleMark>,
 ) {
+    if by_overflows[id].0 != by {
+        unsafe { by_overflows.get_unchecked_write(id) }.set_0(by);
+    } else if by > 0 {
+		// 通知by_overflow改变，以修改clipBox
+        by_overflows.get_notify_ref().modify_event(id, "", 0);
+    }
     if by > 0 {
         // 裁剪剔除
         if let Some(item) = by_clip_aabb {
@@ -542,29 +593,25 @@ fn calc_clip<'a>(
                 )),
             }
         }
-		// 通知by_overflow改变，以修改clipBox
-        by_overflows.get_notify_ref().modify_event(id, "", 0);
     }
 
     if let Some(r) = read.7.get(id) {
         transform_will_change_matrix = Some(r);
     }
 
-    let overflow = *read.1[id];
+    let overflow = read.1[id].is_clip();
     if overflow {
         let i = get_index(overflow_clip, id);
         if i > 0 {
             // 计算裁剪平面
-            set_clip(id, i, &read, overflow_clip, transform_will_change_matrix);
-			let by1 = add_index(by, 1 << (i - 1));
+            set_clip(id, i, by, &read, overflow_clip, transform_will_change_matrix);
             by_clip_aabb = modify_intersect_clip(
                 by,
-                by1,
                 i,
                 unsafe { &mut *(overflow_clip as *mut SingleCaseImpl<OverflowClip>) },
                 &(read.9).0,
             );
-            by = by1;
+            by = i;
             // by_clip_aabb
             overflow_clip.get_notify_ref().modify_event(i, "", id);
         }
@@ -592,10 +639,11 @@ fn calc_clip<'a>(
 }
 
 //================================ 内部静态方法
-// 设置裁剪区域，需要 world_matrix LayoutR
+// 设置裁剪区域，需要 world_matrix LayoutR， parent为外层的裁剪区域
 fn set_clip(
     id: usize,
     i: usize,
+    parent: usize,
     read: &Read,
     clip: &mut SingleCaseImpl<OverflowClip>,
     transform_will_change: Option<&TransformWillChangeMatrix>,
@@ -613,22 +661,44 @@ fn set_clip(
         None => TransformOrigin::Center,
 	};
     let origin = origin.to_value(layout.rect.end - layout.rect.start, layout.rect.bottom - layout.rect.top);
-    let c = &mut clip.clip[i];
-    *c = Clip {
+    let shape = calc_shape(layout, read.10.get(id), read.11.get(id), world_matrix, &origin);
+    let use_texture = world_matrix.1 || shape.is_some();
+    let old_texture_index = clip.clip[i].texture_index;
+    clip.clip[i] = Clip {
         view: calc_point(layout, world_matrix, &origin),
         has_rotate: world_matrix.1,
-        old_has_rotate: c.has_rotate,
+        old_has_rotate: old_texture_index > 0,
         node_id: id,
+        shape: shape,
+        parent: parent,
+        texture_index: old_texture_index,
+        texture_mask: 0,
+    };
+
+    // 纹理模式（或没有模板缓冲区）下， 旋转或非矩形的裁剪区域，需要在裁剪纹理中占用一位（包围盒不相交的裁剪区域共用一位）
+    // 相交的裁剪区域太多， 没有可用的位时改用模板缓冲区裁剪
+    // 既没有可用的位， 也没有模板缓冲区时， 只能按包围盒裁剪
+    let texture_index = if use_texture && clip.prefer_texture() {
+        clip.alloc_texture_index(i)
+    } else {
+        0
+    };
+    let mut texture_mask = match parent {
+        0 => 0,
+        _ => clip.clip[parent].texture_mask,
     };
+    if texture_index > 0 {
+        texture_mask |= 1 << (texture_index - 1);
+    }
+
+    let c = &mut clip.clip[i];
+    c.texture_index = texture_index;
+    c.texture_mask = texture_mask;
 }
 
 //================================ 内部静态方法
 // 创建裁剪区域，需要 world_matrix LayoutR
 fn create_clip(id: usize, clip: &mut SingleCaseImpl<OverflowClip>) -> usize {
-	if clip.clip.len() >= 32 {
-        println!("clip overflow!!!!!!!");
-        return 0;
-    }
 	if let Some(r) = clip.id_map.get(&id) {
 		return *r;
 	}
@@ -643,6 +713,10 @@ fn create_clip(id: usize, clip: &mut SingleCaseImpl<OverflowClip>) -> usize {
         has_rotate: false,
         old_has_rotate: false,
         node_id: id,
+        shape: None,
+        parent: 0,
+        texture_index: 0,
+        texture_mask: 0,
     });
     clip.id_map.insert(id, i);
     i
@@ -662,38 +736,13 @@ fn remove_index(overflow: &mut OverflowClip, node_id: usize, notify: &NotifyImpl
     if let Some(r) = overflow.id_map.remove(&node_id) {
         notify.modify_event(r, "", node_id);
         overflow.clip.remove(r);
+        overflow.clip_map.remove(&r);
         r
     } else {
         0
     }
 }
 
-#[inline]
-fn add_index(by: usize, index: usize) -> usize {
-    by | index
-}
-#[inline]
-fn del_index(by: usize, index: usize) -> usize {
-    by & !index
-}
-// 整理方法。设置或取消所有子节点的by_overflow上的index。
-#[inline]
-fn adjust(
-    idtree: &SingleCaseImpl<IdTree>,
-	by_overflow: &mut MultiCaseImpl<Node, ByOverflow>,
-	node_states: &MultiCaseImpl<Node, NodeState>,
-    child: usize,
-    index: usize,
-    ops: fn(a: usize, b: usize) -> usize,
-) {
-    for (id, _n) in idtree.recursive_iter(child) {
-		if !node_states[id].is_rnode() {
-			continue;
-		}
-        let by = *by_overflow[id];
-		unsafe { by_overflow.get_unchecked_write(id).set_0(ops(by, index)) };
-    }
-}
 // 计算内容区域矩形的4个点
 fn calc_point(layout: &LayoutR, m: &Matrix4, origin: &Point2) -> [Point2; 4] {
 	let width = layout.rect.end 
@@ -736,6 +785,47 @@ fn calc_point(layout: &LayoutR, m: &Matrix4, origin: &Point2) -> [Point2; 4] {
     [lt, lb, rb, rt]
 }
 
+// 计算非矩形裁剪区域在世界坐标系下的三角形网格， 有clip-path时使用clip-path， 否则使用内容区域的圆角， 矩形返回None
+fn calc_shape(
+    layout: &LayoutR,
+    border_radius: Option<&BorderRadius>,
+    clip_path: Option<&ClipPath>,
+    m: &Matrix4,
+    origin: &Point2,
+) -> Option<(Vec<f32>, Vec<u16>)> {
+    let (mut positions, indices) = match clip_path {
+        Some(clip_path) => split_by_clip_path(
+            clip_path,
+            layout.rect.end - layout.rect.start,
+            layout.rect.bottom - layout.rect.top,
+        ),
+        None => {
+            let radius = cal_border_radius(border_radius, layout).content(layout);
+            if radius.is_zero() {
+                return None;
+            }
+            let (b, p) = (&layout.border, &layout.padding);
+            let (positions, polygon) = split_by_border_radius(
+                b.start + p.start,
+                b.top + p.top,
+                layout.rect.end - layout.rect.start - b.start - b.end - p.start - p.end,
+                layout.rect.bottom - layout.rect.top - b.top - b.bottom - p.top - p.bottom,
+                &radius,
+                None,
+            );
+            // 圆角矩形是凸多边形
+            (positions, fan_indices(polygon.as_slice()))
+        }
+    };
+    for i in 0..positions.len() / 2 {
+        let p = m * Vector4::new(positions[i * 2] - origin.x, positions[i * 2 + 1] - origin.y, 0.0, 1.0);
+        positions[i * 2] = p.x;
+        positions[i * 2 + 1] = p.y;
+    }
+    // 空的形状（如半径为0的圆）， 什么都不显示
+    Some((positions, indices))
+}
+
 // 计算aabb
 fn matrix_mul_aabb(m: &WorldMatrix, aabb: &Aabb3) -> Aabb3 {
     let min = m * Vector4::new(aabb.min.x, aabb.min.y, 0.0, 1.0);
@@ -746,56 +836,42 @@ fn matrix_mul_aabb(m: &WorldMatrix, aabb: &Aabb3) -> Aabb3 {
     )
 }
 
-// 将clip求交结果存储在clip_map中
-fn add_intersect_clip(
-    parent_by: usize,
-    by: usize,
-    i: usize,
-    overflow: &mut OverflowClip,
-    view_matrix: &WorldMatrix,
-) {
-    let r = &overflow.clip[i];
-    if r.has_rotate {
-        return;
-    }
-    if let Some(p) = overflow.clip_map.get(&parent_by) {
-        let aabb = intersect(
-            &Aabb3::new(
-                Point3::new(r.view[0].x, r.view[0].y, 0.0),
-                Point3::new(r.view[2].x, r.view[2].y, 0.0),
-            ),
-            &p.0,
-        );
-        overflow.insert_aabb(by, aabb, view_matrix);
-        // overflow.clip_map.insert(by, intersect(&Aabb3::new(Point3::new(r.view[0].x, r.view[0].y, 0.0),  Point3::new(r.view[2].x, r.view[2].y, 0.0)), p));
-    }
-}
-
-// 将clip求交结果存储在clip_map中
+// 将clip与外层裁剪区域求交，结果存储在clip_map中
+// 旋转或非矩形的裁剪区域使用其包围盒求交，精确的裁剪由裁剪纹理完成
 fn modify_intersect_clip<'a, 'b>(
-    parent_by: usize,
-    by: usize,
+    parent: usize,
     i: usize,
     overflow: &'a mut OverflowClip,
     view_matrix: &'b WorldMatrix,
 ) -> Option<&'a (Aabb3, Share<dyn UniformBuffer>)> {
     let r = &overflow.clip[i];
-    if r.has_rotate {
-        overflow.clip_map.remove(&by);
-        return None;
-    }
-    if let Some(p) = overflow.clip_map.get(&parent_by) {
-        let aabb = intersect(
-            &Aabb3::new(
-                Point3::new(r.view[0].x, r.view[0].y, 0.0),
-                Point3::new(r.view[2].x, r.view[2].y, 0.0),
-            ),
-            &p.0,
-        );
-        Some(overflow.insert_aabb(by, aabb, view_matrix))
-    } else {
-        None
+    let mut aabb = Aabb3::new(
+        Point3::new(std::f32::MAX, std::f32::MAX, 0.0),
+        Point3::new(std::f32::MIN, std::f32::MIN, 0.0),
+    );
+    let mut extend = |x: f32, y: f32| {
+        aabb.min.x = aabb.min.x.min(x);
+        aabb.min.y = aabb.min.y.min(y);
+        aabb.max.x = aabb.max.x.max(x);
+        aabb.max.y = aabb.max.y.max(y);
+    };
+    match &r.shape {
+        Some((positions, _)) => {
+            for i in 0..positions.len() / 2 {
+                extend(positions[i * 2], positions[i * 2 + 1]);
+            }
+        }
+        None => {
+            for p in r.view.iter() {
+                extend(p.x, p.y);
+            }
+        }
     }
+    let aabb = match overflow.clip_map.get(&parent) {
+        Some(p) => intersect(&aabb, &p.0),
+        None => aabb,
+    };
+    Some(overflow.insert_aabb(i, aabb, view_matrix))
 }
 
 #[inline]
@@ -861,6 +937,9 @@ impl_system! {
         MultiCaseListener<Node, Transform, CreateEvent>
         MultiCaseListener<Node, Transform, DeleteEvent>
         MultiCaseListener<Node, LayoutR, ModifyEvent>
+        MultiCaseListener<Node, ClipPath, CreateEvent>
+        MultiCaseListener<Node, ClipPath, ModifyEvent>
+        MultiCaseListener<Node, ClipPath, DeleteEvent>
         SingleCaseListener<IdTree, CreateEvent>
         SingleCaseListener<IdTree, DeleteEvent>
         MultiCaseListener<Node, TransformWillChangeMatrix, DeleteEvent>
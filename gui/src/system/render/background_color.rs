@@ -29,6 +29,7 @@ use system::util::*;
 
 lazy_static! {
     static ref GRADUAL: Atom = Atom::from("GRADUAL");
+    static ref RADIAL_GRADUAL: Atom = Atom::from("RADIAL_GRADUAL");
 }
 
 const DIRTY_TYPE: usize = StyleType::BackgroundColor as usize
@@ -181,15 +182,11 @@ impl<'a, C: HalContext + 'static> Runner<'a> for BackgroundColorSys<C> {
                 let depth = z_depths[*id].0;
                 let is_unit_geo = match &color.0 {
                     Color::RGBA(_) => {
-                        let radius = cal_border_radius(border_radius, layout);
-                        let g_b = geo_box(layout);
-                        if radius.x <= g_b.min.x {
-                            true
-                        } else {
-                            false
-                        }
+                        cal_border_radius(border_radius, layout)
+                            .inner(layout)
+                            .is_zero()
                     }
-                    Color::LinearGradient(_) => false,
+                    Color::LinearGradient(_) | Color::RadialGradient(_) => false,
                 };
                 if is_unit_geo {
                     modify_matrix(
@@ -275,27 +272,29 @@ fn create_rgba_geo<C: HalContext + 'static>(
     unit_quad: &Share<GeometryRes>,
     engine: &mut Engine<C>,
 ) -> Option<Share<GeometryRes>> {
-    let radius = cal_border_radius(border_radius, layout);
+    let radius = cal_border_radius(border_radius, layout).inner(layout);
     let g_b = geo_box(layout);
     if g_b.min.x - g_b.max.x == 0.0 || g_b.min.y - g_b.max.y == 0.0 {
         return None;
     }
 
-    if radius.x <= g_b.min.x {
+    if radius.is_zero() {
         return Some(unit_quad.clone());
     } else {
         let mut hasher = DefaultHasher::default();
-        radius_quad_hash(&mut hasher, radius.x, layout.rect.end - layout.rect.start - layout.border.start - layout.border.end, layout.rect.bottom - layout.rect.top-layout.border.bottom - layout.border.top);
+        NotNan::new(g_b.min.x).unwrap().hash(&mut hasher);
+        NotNan::new(g_b.min.y).unwrap().hash(&mut hasher);
+        radius_quad_hash(&mut hasher, &radius, g_b.max.x - g_b.min.x, g_b.max.y - g_b.min.y);
         let hash = hasher.finish();
         match engine.geometry_res_map.get(&hash) {
             Some(r) => Some(r.clone()),
             None => {
-                let r = split_by_radius(
+                let r = split_by_border_radius(
                     g_b.min.x,
                     g_b.min.y,
                     g_b.max.x - g_b.min.x,
                     g_b.max.y - g_b.min.y,
-                    radius.x - g_b.min.x,
+                    &radius,
                     None,
                 );
                 if r.0.len() == 0 {
@@ -325,7 +324,7 @@ fn create_linear_gradient_geo<C: HalContext + 'static>(
     layout: &LayoutR,
     engine: &mut Engine<C>,
 ) -> Option<Share<GeometryRes>> {
-    let radius = cal_border_radius(border_radius, layout);
+    let radius = cal_border_radius(border_radius, layout).inner(layout);
     let g_b = geo_box(layout);
     if g_b.min.x - g_b.max.x == 0.0 || g_b.min.y - g_b.max.y == 0.0 {
         return None;
@@ -345,7 +344,7 @@ fn create_linear_gradient_geo<C: HalContext + 'static>(
     NotNan::new(color.direction).unwrap().hash(&mut hasher);
     radius_quad_hash(
         &mut hasher,
-        radius.x - g_b.min.x,
+        &radius,
         g_b.max.x - g_b.min.x,
         g_b.max.y - g_b.min.y,
     );
@@ -354,7 +353,7 @@ fn create_linear_gradient_geo<C: HalContext + 'static>(
     match engine.geometry_res_map.get(&hash) {
         Some(r) => Some(r.clone()),
         None => {
-            let (positions, indices) = if radius.x <= g_b.min.x {
+            let (positions, indices) = if radius.is_zero() {
                 (
                     vec![
                         g_b.min.x, g_b.min.y, // left_top
@@ -365,12 +364,12 @@ fn create_linear_gradient_geo<C: HalContext + 'static>(
                     vec![0, 1, 2, 3],
                 )
             } else {
-                split_by_radius(
+                split_by_border_radius(
                     g_b.min.x,
                     g_b.min.y,
                     g_b.max.x - g_b.min.x,
                     g_b.max.y - g_b.min.y,
-                    radius.x - g_b.min.x,
+                    &radius,
                     None,
                 )
             };
@@ -432,6 +431,78 @@ fn create_linear_gradient_geo<C: HalContext + 'static>(
     }
 }
 
+// 创建一个径向渐变背景色的geo
+#[inline]
+fn create_radial_gradient_geo<C: HalContext + 'static>(
+    color: &RadialGradientColor,
+    border_radius: Option<&BorderRadius>,
+    layout: &LayoutR,
+    engine: &mut Engine<C>,
+) -> Option<Share<GeometryRes>> {
+    let radius = cal_border_radius(border_radius, layout).inner(layout);
+    let g_b = geo_box(layout);
+    if g_b.min.x - g_b.max.x == 0.0 || g_b.min.y - g_b.max.y == 0.0 {
+        return None;
+    }
+
+    // 圆角 + 渐变hash
+    let mut hasher = DefaultHasher::default();
+    RADIAL_GRADUAL.hash(&mut hasher);
+    color.hash(&mut hasher);
+    NotNan::new(g_b.min.x).unwrap().hash(&mut hasher);
+    NotNan::new(g_b.min.y).unwrap().hash(&mut hasher);
+    radius_quad_hash(
+        &mut hasher,
+        &radius,
+        g_b.max.x - g_b.min.x,
+        g_b.max.y - g_b.min.y,
+    );
+    let hash = hasher.finish();
+
+    match engine.geometry_res_map.get(&hash) {
+        Some(r) => Some(r.clone()),
+        None => {
+            // 渐变的轮廓（多边形顶点）
+            let outline = if radius.is_zero() {
+                vec![
+                    g_b.min.x, g_b.min.y, // left_top
+                    g_b.min.x, g_b.max.y, // left_bootom
+                    g_b.max.x, g_b.max.y, // right_bootom
+                    g_b.max.x, g_b.min.y, // right_top
+                ]
+            } else {
+                let (positions, indices) = split_by_border_radius(
+                    g_b.min.x,
+                    g_b.min.y,
+                    g_b.max.x - g_b.min.x,
+                    g_b.max.y - g_b.min.y,
+                    &radius,
+                    None,
+                );
+                let mut outline = Vec::with_capacity(indices.len() * 2);
+                for i in indices.iter() {
+                    let i = *i as usize;
+                    outline.extend_from_slice(&positions[i * 2..i * 2 + 2]);
+                }
+                outline
+            };
+
+            let (center, r) = cal_radial_gradient(&g_b, color.center, color.shape, color.size);
+            let (positions, colors, indices) =
+                split_by_radial_gradient(outline.as_slice(), &center, r, color.list.as_slice());
+            // 创建geo， 设置attribut
+            Some(engine.create_geo_res(
+                hash,
+                indices.as_slice(),
+                &[
+                    AttributeDecs::new(AttributeName::Position, positions.as_slice(), 2),
+                    AttributeDecs::new(AttributeName::Color, colors.as_slice(), 4),
+                ],
+            ))
+        }
+    }
+}
+
 #[inline]
 fn background_is_opacity(opacity: f32, background_color: &BackgroundColor) -> bool {
     if opacity < 1.0 {
@@ -489,6 +560,22 @@ fn modify_color<C: HalContext + 'static>(
                 render_obj.geometry = create_linear_gradient_geo(c, border_radius, layout, engine);
             }
         }
+        Color::RadialGradient(c) => {
+            if dirty & StyleType::BackgroundColor as usize != 0 {
+                change = to_vex_color_defines(
+                    render_obj.vs_defines.as_mut(),
+                    render_obj.fs_defines.as_mut(),
+                );
+            }
+
+            // 径向渐变的颜色在顶点上， 颜色改变也需要重新创建geometry
+            if dirty & StyleType::BackgroundColor as usize != 0
+                || dirty & StyleType::BorderRadius as usize != 0
+                || dirty & StyleType::Layout as usize != 0
+            {
+                render_obj.geometry = create_radial_gradient_geo(c, border_radius, layout, engine);
+            }
+        }
     };
     change
 }
@@ -500,3 +587,157 @@ impl_system! {
         MultiCaseListener<Node, BackgroundColor, DeleteEvent>
     }
 }
+
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+
+#[test]
+fn test_snapshot() {
+    let mut scene = SceneDesc::new(20, 20);
+    scene.css = ".1{position:absolute;left:5px;top:5px;width:10px;height:10px}".to_string();
+    scene
+        .nodes
+        .push(NodeDesc::new("background-color:#ff0000").with_class(&[1]));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    let (w, h, pixels) = gui.read_pixels();
+    assert_eq!((w, h), (20, 20));
+    assert_eq!(pixels.len(), 20 * 20 * 4);
+    assert_eq!(gui.pixel(5, 5), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(14, 14), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(4, 10), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(15, 10), [255, 255, 255, 255]);
+
+    gui.assert_snapshot("background_color", 0);
+}
+
+
+#[test]
+fn test_radial_gradient_snapshot() {
+    let mut scene = SceneDesc::new(20, 20);
+    scene.nodes.push(NodeDesc::new(
+        "position:absolute;left:0px;top:0px;width:20px;height:20px;background:radial-gradient(circle closest-side, #ff0000, #0000ff)",
+    ));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    // 中心接近第一个色标， 半径之外为最后一个色标
+    let center = gui.pixel(10, 10);
+    assert!(center[0] > 200 && center[2] < 55);
+    assert_eq!(gui.pixel(0, 0), [0, 0, 255, 255]);
+    assert_eq!(gui.pixel(19, 19), [0, 0, 255, 255]);
+
+    gui.assert_snapshot("background_radial_gradient", 2);
+}
+
+#[test]
+fn test_radial_gradient_center_outside() {
+    // 渐变中心在节点之外， 节点仍然被完整填充
+    let mut scene = SceneDesc::new(20, 20);
+    scene.nodes.push(NodeDesc::new(
+        "position:absolute;left:0px;top:0px;width:20px;height:20px;background:radial-gradient(circle farthest-side at 150% 50%, #ff0000, #0000ff)",
+    ));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    let (w, h, _) = gui.read_pixels();
+    for y in 0..h {
+        for x in 0..w {
+            assert_ne!(gui.pixel(x, y), [255, 255, 255, 255]);
+        }
+    }
+    // 中心(30, 10)， 半径30
+    let near = |p: [u8; 4], r: [u8; 4]| (0..4).all(|i| (p[i] as i32 - r[i] as i32).abs() <= 3);
+    assert!(near(gui.pixel(19, 10), [166, 0, 89, 255]));
+    assert!(near(gui.pixel(0, 10), [4, 0, 251, 255]));
+}
+
+#[test]
+fn test_radial_gradient_hard_stop() {
+    // 位置相同的两个色标， 两侧的颜色不混合
+    let mut scene = SceneDesc::new(20, 20);
+    scene.nodes.push(NodeDesc::new(
+        "position:absolute;left:0px;top:0px;width:20px;height:20px;background:radial-gradient(circle farthest-side, #ff0000 50%, #0000ff 50%)",
+    ));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    assert_eq!(gui.pixel(10, 10), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(10, 7), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(10, 2), [0, 0, 255, 255]);
+    assert_eq!(gui.pixel(0, 0), [0, 0, 255, 255]);
+}
+
+#[test]
+fn test_border_radius_snapshot() {
+    let mut scene = SceneDesc::new(20, 20);
+    // 只有上方两个角有圆角
+    scene.nodes.push(NodeDesc::new(
+        "position:absolute;left:0px;top:0px;width:20px;height:20px;background-color:#ff0000;border-radius:8px 8px 0px 0px",
+    ));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(19, 0), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(0, 19), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(19, 19), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(10, 10), [255, 0, 0, 255]);
+
+    gui.assert_snapshot("background_border_radius", 2);
+}
+
+#[test]
+fn test_border_radius_order() {
+    // 圆角的简写与单个角的声明按声明顺序覆盖
+    let mut scene = SceneDesc::new(60, 20);
+    let base = "position:absolute;top:0px;width:20px;height:20px;background-color:#ff0000";
+    // 简写在后， 四个角都是圆角
+    scene.nodes.push(NodeDesc::new(&format!(
+        "{};left:0px;border-top-left-radius:0px;border-radius:8px",
+        base
+    )));
+    // 单个角在后， 只有左上角是直角
+    scene.nodes.push(NodeDesc::new(&format!(
+        "{};left:20px;border-radius:8px;border-top-left-radius:0px",
+        base
+    )));
+    // 第二个简写重置之前的所有角， 只有右下角是圆角
+    scene.nodes.push(NodeDesc::new(&format!(
+        "{};left:40px;border-radius:8px;border-top-left-radius:0px;border-radius:0px;border-bottom-right-radius:8px",
+        base
+    )));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    let (white, red) = ([255, 255, 255, 255], [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(0, 0), white);
+    assert_eq!(gui.pixel(19, 19), white);
+
+    assert_eq!(gui.pixel(20, 0), red);
+    assert_eq!(gui.pixel(39, 0), white);
+    assert_eq!(gui.pixel(39, 19), white);
+
+    assert_eq!(gui.pixel(40, 0), red);
+    assert_eq!(gui.pixel(59, 0), red);
+    assert_eq!(gui.pixel(40, 19), red);
+    assert_eq!(gui.pixel(59, 19), white);
+}
+
+#[test]
+fn test_border_radius_longhand_class() {
+    // 只声明了单个角的class， 只覆盖该角， 其它角保留之前的class设置的圆角
+    let mut scene = SceneDesc::new(20, 20);
+    scene.css = ".1{position:absolute;left:0px;top:0px;width:20px;height:20px;background-color:#ff0000;border-radius:10px}.2{border-top-left-radius:0px}".to_string();
+    scene.nodes.push(NodeDesc::new("").with_class(&[1, 2]));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    let (white, red) = ([255, 255, 255, 255], [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(0, 0), red);
+    assert_eq!(gui.pixel(19, 0), white);
+    assert_eq!(gui.pixel(19, 19), white);
+    assert_eq!(gui.pixel(0, 19), white);
+    assert_eq!(gui.pixel(10, 10), red);
+}
@@ -9,7 +9,6 @@ use ecs::monitor::NotifyImpl;
 use hal_core::*;
 use map::vecmap::VecMap;
 use map::Map;
-use polygon::*;
 
 use component::calc::{Opacity, LayoutR};
 use component::calc::*;
@@ -276,7 +275,7 @@ fn get_geo_flow(radiu: Option<&BorderRadius>, layout: &LayoutR) -> (Vec<f32>, Ve
 
 	let width = layout.rect.end - layout.rect.start;
 	let height = layout.rect.bottom - layout.rect.top;
-    if radius.x == 0.0 {
+    if radius.is_zero() {
         let border_start_x = layout.border.start;
         let border_start_y = layout.border.top;
         let border_end_x = width - layout.border.end;
@@ -306,15 +305,7 @@ fn get_geo_flow(radiu: Option<&BorderRadius>, layout: &LayoutR) -> (Vec<f32>, Ve
             ],
         )
     } else {
-        split_by_radius_border(
-            0.0,
-            0.0,
-            width,
-            height,
-            radius.x,
-            layout.border.start,
-            None,
-        )
+        split_by_border_radius_border(width, height, &radius, layout)
     }
 }
 
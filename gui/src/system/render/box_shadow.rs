@@ -8,7 +8,6 @@ use entity::Node;
 use hal_core::*;
 use map::vecmap::VecMap;
 use map::Map;
-use polygon::*;
 use render::engine::{AttributeDecs, Engine, ShareEngine};
 use render::res::GeometryRes;
 /**
@@ -269,7 +268,7 @@ fn create_shadow_geo<C: HalContext + 'static>(
     let y = g_b.min.y;
     let w = g_b.max.x - g_b.min.x;
     let h = g_b.max.y - g_b.min.y;
-    let bg = split_by_radius(x, y, w, h, radius.x, Some(16));
+    let bg = split_by_border_radius(x, y, w, h, &radius, Some(16));
     if bg.0.len() == 0 {
         return None;
     }
@@ -278,7 +277,7 @@ fn create_shadow_geo<C: HalContext + 'static>(
     let y = g_b.min.y + shadow.v - shadow.spread - shadow.blur;
     let w = g_b.max.x - g_b.min.x + 2.0 * shadow.spread + 2.0 * shadow.blur;
     let h = g_b.max.y - g_b.min.y + 2.0 * shadow.spread + 2.0 * shadow.blur;
-    let shadow_pts = split_by_radius(x, y, w, h, radius.x, Some(16));
+    let shadow_pts = split_by_border_radius(x, y, w, h, &radius, Some(16));
     if shadow_pts.0.len() == 0 {
         return None;
     }
@@ -367,3 +366,30 @@ impl_system! {
         MultiCaseListener<Node, BoxShadow, DeleteEvent>
     }
 }
+
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+
+#[test]
+fn test_snapshot() {
+    let mut scene = SceneDesc::new(40, 40);
+    scene.nodes.push(NodeDesc::new(
+        "position:absolute;left:10px;top:10px;width:20px;height:20px;background-color:#ffffff;box-shadow:2px 2px 4px #000000",
+    ));
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    // 节点本身盖住阴影， 远离节点处没有阴影
+    assert_eq!(gui.pixel(20, 20), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(2, 2), [255, 255, 255, 255]);
+    // 阴影向右下偏移， 只露出右边和下边
+    assert!(gui.pixel(31, 20)[0] < 200);
+    assert!(gui.pixel(20, 31)[0] < 200);
+    assert_eq!(gui.pixel(6, 20), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(20, 6), [255, 255, 255, 255]);
+    // 模糊： 越远离阴影的边缘越淡
+    assert!(gui.pixel(31, 20)[0] < gui.pixel(33, 20)[0]);
+    assert!(gui.pixel(33, 20)[0] < gui.pixel(35, 20)[0]);
+
+    gui.assert_snapshot("box_shadow", 2);
+}
@@ -0,0 +1,224 @@
+/**
+ * 输入框光标及选区的渲染对象的构建及其属性设置
+ * 只绘制获得焦点的输入框： 选区为每行一个矩形， 绘制在文字之下； 光标为竖线， 与输入法组合中文字的下划线一起绘制在文字之上
+ * 位置取自文字布局的结果（CharBlock）， 见system::text_input
+	*/
+use component::calc::*;
+use component::user::*;
+use ecs::{MultiCaseImpl, Runner, SingleCaseImpl};
+use ecs::monitor::NotifyImpl;
+use entity::Node;
+use hal_core::*;
+use map::vecmap::VecMap;
+use map::Map;
+use render::engine::{AttributeDecs, Engine, ShareEngine};
+use render::res::GeometryRes;
+use share::Share;
+use single::*;
+use std::marker::PhantomData;
+use system::render::shaders::color::{COLOR_FS_SHADER_NAME, COLOR_VS_SHADER_NAME};
+use system::text_input::TextLayout;
+use system::util::*;
+
+const CARET_WIDTH: f32 = 1.0; // 光标的宽度
+const UNDERLINE_WIDTH: f32 = 1.0; // 组合中文字下划线的粗细
+const SELECTION_DEPTH: f32 = 0.01; // 选区在文字（0.1）及其阴影之下
+const CARET_DEPTH: f32 = 0.2; // 光标在文字及富文本的额外渲染对象之上
+
+pub struct CaretSys<C: HalContext + 'static> {
+    selection_map: VecMap<usize>,
+    caret_map: VecMap<usize>,
+    node: usize, // 当前绘制光标的输入框， 0表示没有
+    default_paramter: ColorParamter,
+    marker: PhantomData<C>,
+}
+
+impl<C: HalContext + 'static> Default for CaretSys<C> {
+    fn default() -> Self {
+        Self {
+            selection_map: VecMap::default(),
+            caret_map: VecMap::default(),
+            node: 0,
+            default_paramter: ColorParamter::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static> Runner<'a> for CaretSys<C> {
+    type ReadData = (
+        &'a MultiCaseImpl<Node, CharBlock>,
+        &'a MultiCaseImpl<Node, TextStyle>,
+        &'a MultiCaseImpl<Node, WorldMatrix>,
+        &'a MultiCaseImpl<Node, LayoutR>,
+        &'a MultiCaseImpl<Node, ZDepth>,
+        &'a MultiCaseImpl<Node, Transform>,
+        &'a MultiCaseImpl<Node, StyleMark>,
+        &'a SingleCaseImpl<DefaultTable>,
+        &'a SingleCaseImpl<DefaultState>,
+    );
+
+    type WriteData = (
+        &'a mut SingleCaseImpl<TextInputs>,
+        &'a mut SingleCaseImpl<RenderObjs>,
+        &'a mut SingleCaseImpl<ShareEngine<C>>,
+    );
+
+    fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
+        let (
+            char_blocks,
+            text_styles,
+            world_matrixs,
+            layouts,
+            z_depths,
+            transforms,
+            style_marks,
+            default_table,
+            default_state,
+        ) = read;
+        let (text_inputs, render_objs, engine) = write;
+        let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
+        let focus = text_inputs.focus;
+
+        // 输入框失去焦点或被删除， 删除渲染对象
+        if self.node > 0 && (self.node != focus || style_marks.get(self.node).is_none()) {
+            let node = self.node;
+            self.remove_render_obj(node, render_objs);
+        }
+        let input = match text_inputs.inputs.get_mut(&focus) {
+            Some(r) => r,
+            None => return,
+        };
+        let dirty = match style_marks.get(focus) {
+            Some(r) => r.dirty,
+            None => return,
+        };
+        if self.node == focus && !input.dirty && dirty == 0 {
+            return;
+        }
+        // 文字还未布局
+        let (char_block, layout) = match (char_blocks.get(focus), layouts.get(focus)) {
+            (Some(r), Some(r1)) => (r, r1),
+            _ => return,
+        };
+        input.dirty = false;
+        if self.node != focus {
+            self.create_render_obj(focus, render_objs, default_state);
+        }
+
+        let text_layout = TextLayout::new(char_block, input.display_text().as_str(), text_styles.get(focus));
+        let composition = input.composition.chars().count();
+        let caret = input.display_caret();
+        let selection = if input.has_selection() {
+            // 选区中光标之后的位置在组合中的文字之后
+            let (start, end) = input.selection();
+            let to_display = |i: usize| if i > input.caret { i + composition } else { i };
+            text_layout.selection(to_display(start), to_display(end))
+        } else {
+            Vec::new()
+        };
+        let mut carets: Vec<(f32, f32, f32, f32)> = text_layout
+            .selection(caret - composition, caret)
+            .into_iter()
+            .map(|(x, y, w, h)| (x, y + h - UNDERLINE_WIDTH, w, UNDERLINE_WIDTH))
+            .collect();
+        if input.caret_visible && !input.has_selection() {
+            let (x, top, height) = text_layout.caret(caret);
+            carets.push((x - CARET_WIDTH / 2.0, top, CARET_WIDTH, height));
+        }
+
+        let depth = z_depths[focus].0;
+        let world_matrix = &world_matrixs[focus];
+        let transform = match transforms.get(focus) {
+            Some(r) => r,
+            None => default_table.get::<Transform>().unwrap(),
+        };
+        for &(index, rects, color) in [
+            (self.selection_map[focus], &selection, &input.selection_color),
+            (self.caret_map[focus], &carets, &input.caret_color),
+        ].iter() {
+            let render_obj = &mut render_objs[index];
+            render_obj
+                .paramter
+                .as_ref()
+                .set_value("uColor", engine.create_u_color_ubo(color));
+            render_obj.geometry = create_rect_geo(engine, rects);
+            render_obj.depth = depth + render_obj.depth_diff;
+            let arr = create_let_top_offset_matrix(layout, world_matrix, transform, 0.0, 0.0, render_obj.depth);
+            render_obj.paramter.set_value(
+                "worldMatrix",
+                Share::new(WorldMatrixUbo::new(UniformValue::MatrixV4(arr))),
+            );
+            notify.modify_event(index, "", 0);
+        }
+    }
+}
+
+impl<C: HalContext + 'static> CaretSys<C> {
+    fn remove_render_obj(&mut self, id: usize, render_objs: &mut SingleCaseImpl<RenderObjs>) {
+        let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
+        for index in self.selection_map.remove(id).into_iter().chain(self.caret_map.remove(id).into_iter()) {
+            render_objs.remove(index, Some(notify));
+        }
+        self.node = 0;
+    }
+
+    fn create_render_obj(
+        &mut self,
+        id: usize,
+        render_objs: &mut SingleCaseImpl<RenderObjs>,
+        default_state: &DefaultState,
+    ) {
+        for &(depth_diff, is_caret) in [(SELECTION_DEPTH, false), (CARET_DEPTH, true)].iter() {
+            let render_map = if is_caret { &mut self.caret_map } else { &mut self.selection_map };
+            let index = create_render_obj(
+                id,
+                depth_diff,
+                false,
+                COLOR_VS_SHADER_NAME.clone(),
+                COLOR_FS_SHADER_NAME.clone(),
+                Share::new(self.default_paramter.clone()),
+                default_state,
+                render_objs,
+                render_map,
+            );
+            let render_obj = &mut render_objs[index];
+            render_obj.fs_defines.add("UCOLOR");
+            render_obj.state.ds = default_state.tarns_ds.clone();
+        }
+        self.node = id;
+    }
+}
+
+// 矩形(x, y, 宽, 高)的几何体， 坐标相对于节点的布局框左上角
+fn create_rect_geo<C: HalContext + 'static>(
+    engine: &mut Engine<C>,
+    rects: &[(f32, f32, f32, f32)],
+) -> Option<Share<GeometryRes>> {
+    if rects.len() == 0 {
+        return None;
+    }
+    let mut positions: Vec<f32> = Vec::with_capacity(rects.len() * 8);
+    let mut indices: Vec<u16> = Vec::with_capacity(rects.len() * 6);
+    for &(x, y, w, h) in rects.iter() {
+        let start = (positions.len() / 2) as u16;
+        positions.extend_from_slice(&[x, y, x, y + h, x + w, y + h, x + w, y]);
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+    Some(engine.create_geo_res(
+        0,
+        indices.as_slice(),
+        &[AttributeDecs::new(
+            AttributeName::Position,
+            positions.as_slice(),
+            2,
+        )],
+    ))
+}
+
+impl_system! {
+    CaretSys<C> where [C: HalContext + 'static],
+    true,
+    {
+    }
+}
@@ -14,7 +14,6 @@ use ecs::{
 	SingleCaseListener,
 	StdCell,
 };
-use idtree::NodeList;
 use hal_core::*;
 use map::vecmap::VecMap;
 use map::Map;
@@ -32,6 +31,7 @@ use single::*;
 use system::render::shaders::canvas_text::{
     CANVAS_TEXT_FS_SHADER_NAME, CANVAS_TEXT_VS_SHADER_NAME,
 };
+use system::render::shaders::image::{IMAGE_FS_SHADER_NAME, IMAGE_VS_SHADER_NAME};
 use system::render::shaders::text::{TEXT_FS_SHADER_NAME, TEXT_VS_SHADER_NAME};
 use system::util::*;
 
@@ -74,7 +74,10 @@ const FONT_DIRTY: usize = StyleType::FontStyle as usize
 #[derive(Default, Clone, Debug)]
 struct I {
     text: usize,
-    shadow: usize,
+    shadows: Vec<usize>, // 每个可见阴影一个渲染对象， 与TextStyle::shadows中可见的阴影一一对应
+    page: usize, // 字形所在的字体纹理页
+    strokes: Vec<usize>, // 富文本中描边与节点不同的片段， 每种描边一个渲染对象， 与RichText::strokes一一对应
+    images: Vec<usize>, // 富文本中每个行内图片的渲染对象
 }
 
 struct RenderCatch {
@@ -96,7 +99,7 @@ pub struct CharBlockSys<C: HalContext + 'static> {
     default_ubos: RenderCatch,
     index_buffer: Share<BufferRes>, // 索引 buffer， 长度： 600
     index_len: usize,
-    texture_size_ubo: Share<TextTextureSize>,
+    texture_size_ubos: Vec<Share<TextTextureSize>>, // 每个字体纹理页的尺寸
 	
 	old_texture_tex_version: usize,
 
@@ -105,6 +108,7 @@ pub struct CharBlockSys<C: HalContext + 'static> {
 
     msdf_default_paramter: MsdfParamter,
     canvas_default_paramter: CanvasTextParamter,
+    image_default_paramter: ImageParamter,
     mark: PhantomData<(C)>,
 }
 
@@ -122,6 +126,8 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
         &'a SingleCaseImpl<DefaultState>,
 		&'a SingleCaseImpl<DirtyList>,
 		&'a SingleCaseImpl<IdTree>,
+		&'a MultiCaseImpl<Node, CharBlock>,
+		&'a MultiCaseImpl<Node, TextSpans>,
     );
     type WriteData = (
         &'a mut SingleCaseImpl<RenderObjs>,
@@ -140,10 +146,11 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
             default_table,
             default_state,
 			dirty_list,
-			idtree
+			_idtree,
+			char_blocks,
+			text_spans,
 		) = read;
 		let font_sheet = &font_sheet.borrow();
-		let t = font_sheet.get_font_tex();
 		let mut texture_change = false;
 		if font_sheet.tex_version != self.old_texture_tex_version {
 			texture_change = true;
@@ -155,22 +162,40 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
         let (render_objs, engine, node_states) = write;
         let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
         let default_transform = default_table.get::<Transform>().unwrap();
+		let empty_block = CharBlock::default();
 
         if texture_change == true {
-            self.texture_size_ubo = Share::new(TextTextureSize::new(UniformValue::Float2(
-                t.width as f32,
-                t.height as f32,
-            )));
+            let page_count = font_sheet.font_tex.pages.len();
+            self.texture_size_ubos = (0..page_count)
+                .map(|page| {
+                    let t = match font_sheet.get_page_tex(page) {
+                        Some(r) => r,
+                        None => font_sheet.get_font_tex(),
+                    };
+                    Share::new(TextTextureSize::new(UniformValue::Float2(
+                        t.width as f32,
+                        t.height as f32,
+                    )))
+                })
+                .collect();
             for i in self.render_map.iter() {
                 match i {
                     Some(i) => {
+                        let ubo = self.texture_size_ubo(i.page);
                         render_objs[i.text]
                             .paramter
-                            .set_value("textureSize", self.texture_size_ubo.clone());
-                        if i.shadow > 0 {
-                            render_objs[i.shadow]
+                            .set_value("textureSize", ubo.clone());
+                        for index in i.shadows.iter() {
+                            render_objs[*index]
                                 .paramter
-                                .set_value("textureSize", self.texture_size_ubo.clone());
+                                .set_value("textureSize", ubo.clone());
+                            notify.modify_event(*index, "ubo", 0);
+                        }
+                        for index in i.strokes.iter() {
+                            render_objs[*index]
+                                .paramter
+                                .set_value("textureSize", ubo.clone());
+                            notify.modify_event(*index, "ubo", 0);
                         }
                         notify.modify_event(i.text, "ubo", 0);
                     }
@@ -189,6 +214,15 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
 			};
 
             let mut dirty = style_mark.dirty;
+            // 装饰线改变， 需要重新生成几何体
+            if style_mark.dirty1 & StyleType1::TextDecoration as usize != 0 {
+                dirty |= StyleType::Text as usize;
+            }
+			// 字体不存在时， 文字还未布局， 不存在文字块
+			let char_block = match char_blocks.get(*id) {
+				Some(r) => r,
+				None => &empty_block,
+			};
 
             // 不存在Chablock关心的脏, 跳过
             if dirty & TEXT_STYLE_DIRTY == 0 {
@@ -196,30 +230,34 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
 			}
 
             // 如果Text脏， 并且不存在Text组件， 尝试删除渲染对象
-            let (index, children, text_style) = if dirty & StyleType::FontFamily as usize != 0 {
+            let (index, text_style) = if dirty & StyleType::FontFamily as usize != 0 {
                 self.remove_render_obj(*id, render_objs); // 可能存在旧的render_obj， 先尝试删除（FontFamily修改， 整renderobj中大部分值都会修改， 因此直接重新创建）
                 let text_style = &text_styles[*id];
-                let children = idtree[*id].children();
-                let have_shadow = text_style.shadow.color != CgColor::new(0.0, 0.0, 0.0, 0.0);
                 let r = self.create_render_obj(
                     *id,
                     render_objs,
                     default_state,
-                    have_shadow,
-                    true,//charblock.is_pixel,
+                    font_type(font_sheet, text_style, 1.0).0,
                 );
                 dirty = dirty | TEXT_STYLE_DIRTY;
-                (r, children, text_style)
+                (r, text_style)
             } else {
                 match self.render_map.get(*id) {
-                    Some(r) => (
-                        r.clone(),
-                        idtree[*id].children(),
-                        &text_styles[*id],
-                    ),
+                    Some(r) => (r.clone(), &text_styles[*id]),
                     None => continue,
                 }
             };
+			let (is_pixel, glyph_scale) = font_type(font_sheet, text_style, node_states[*id].0.scale);
+			let rich = RichText::new(text_spans.get(*id), char_block, text_style, font_sheet);
+			// 字形被移到了其它纹理页， 需要重新绑定纹理， 并重新计算uv
+			let page = node_page(char_block, font_sheet);
+			let page_change = page != index.page;
+			if page_change {
+				if let Some(r) = self.render_map.get_mut(*id) {
+					r.page = page;
+				}
+			}
+			let texture_size = self.texture_size_ubo(page);
 			let world_matrix = &world_matrixs[*id];
 			let layout = &layouts[*id];
             let transform = match transforms.get(*id) {
@@ -260,32 +298,46 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
                         &text_style.text.stroke,
                         render_obj,
                         &notify,
-                        true,//charblock.is_pixel,
+                        is_pixel,
                         &class_ubo,
                         &mut *self.canvas_stroke_ubo_map,
                         &mut *self.msdf_stroke_ubo_map,
                     );
             }
             // 尝试修改字体， 如果字体类型修改（dyn_type）， 需要修改pipeline， （字体类型修改应该重新创建paramter， TODO）
-            if dirty & FONT_DIRTY != 0 {
+            if dirty & FONT_DIRTY != 0 || page_change {
                 modify_font(
                     index.text,
                     render_obj,
-                    true,//charblock.is_pixel,
+                    is_pixel,
                     &font_sheet,
+                    page,
+                    &texture_size,
                     &notify,
                     &self.default_sampler,
                     &self.point_sampler,
                 );
-                program_change = true;
+                program_change = program_change | (dirty & FONT_DIRTY != 0);
             }
 
             // 文字内容脏， 这是顶点流脏
-            if dirty & TEXT_LAYOUT_DIRTY != 0 {
+            if dirty & TEXT_LAYOUT_DIRTY != 0 || page_change {
                 geometry_change = true;
                 shadow_geometry_change = true;
             }
 
+            // 富文本片段设置了颜色时， 纯色文字也使用顶点颜色
+            if geometry_change {
+                if let Color::RGBA(_) = &text_style.text.color {
+                    let exchange = if rich.colored {
+                        to_vex_color_defines(render_obj.vs_defines.as_mut(), render_obj.fs_defines.as_mut())
+                    } else {
+                        to_ucolor_defines(render_obj.vs_defines.as_mut(), render_obj.fs_defines.as_mut())
+                    };
+                    program_change = program_change | exchange;
+                }
+            }
+
             // // 如果渲染管线脏， 重新创建渲染管线
             if program_change {
                 notify.modify_event(index.text, "program_dirty", 0);
@@ -296,9 +348,9 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
                 let l = &mut self.index_len;
                 render_obj.geometry = create_geo(
                     dirty,
-					children,
-					idtree,
-					&node_states[*id],
+					char_block,
+					&rich,
+					Some(0),
 					layout,
                     &text_style.text.color,
                     text,
@@ -308,14 +360,14 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
                     &self.index_buffer,
                     l,
 					engine,
-					node_states[*id].0.scale
+					glyph_scale
                 );
                 render_objs
                     .get_notify_ref()
                     .modify_event(index.text, "geometry", 0);
 			}
 			let (mut h, mut v) = (0.0, 0.0);
-			if node_states[*id].0.is_vnode() {
+			if char_block.mixed {
 				h = -layout.rect.start;
 				v = -layout.rect.top;
 			}
@@ -338,56 +390,85 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
                 );
             }
             notify.modify_event(index.text, "", 0);
-            // 阴影存在
-            if index.shadow > 0 {
-                let shadow_render_obj = &mut render_objs[index.shadow];
 
-                if dirty & (StyleType::TextShadow as usize) != 0 {
-                    // 阴影颜色脏，或描边脏， 修改ubo
+            // 阴影列表改变（或渲染对象被重新创建）， 重新创建阴影的渲染对象
+            let shadows_change = dirty & (StyleType::TextShadow as usize | StyleType::FontFamily as usize) != 0;
+            let shadow_indexs = if shadows_change {
+                self.create_shadow_objs(*id, text_style, is_pixel, render_objs, default_state, notify)
+            } else {
+                index.shadows.clone()
+            };
+            // 创建渲染对象后， render_obj的引用可能已失效， 重新取文字的geo
+            let text_geometry = render_objs[index.text].geometry.clone();
+            let visible_shadows = text_style.shadows.iter().filter(|r| is_visible_shadow(r));
+            for (shadow, shadow_index) in visible_shadows.zip(shadow_indexs.into_iter()) {
+                let shadow_render_obj = &mut render_objs[shadow_index];
+
+                if dirty & (StyleType::TextShadow as usize | StyleType::Stroke as usize | FONT_DIRTY) != 0 {
+                    // 阴影颜色脏，或描边脏， 修改ubo（字体改变时， 模糊范围也会改变）
                     modify_shadow_color(
-                        index.shadow,
-                        style_mark.local_style,
+                        shadow_index,
+                        shadow,
                         text_style,
                         &notify,
                         shadow_render_obj,
                         engine,
-                        true, // charblock.is_pixel,
-                        &class_ubo,
+                        is_pixel,
                         &mut *self.canvas_stroke_ubo_map,
                     );
-                    // 设置ubo TODO
+                    // 模糊半径改变， 可能需要修改宏
+                    if modify_shadow_blur(shadow, shadow_render_obj, is_pixel, glyph_scale) {
+                        notify.modify_event(shadow_index, "program_dirty", 0);
+                    }
                 }
 
                 // 尝试修改字体， 如果字体类型修改（dyn_type）， 需要修改pipeline， （字体类型修改应该重新创建paramter， TODO）
-                if dirty & FONT_DIRTY != 0 {
+                if dirty & FONT_DIRTY != 0 || page_change || shadows_change {
                     modify_font(
-                        index.text,
+                        shadow_index,
                         shadow_render_obj,
-                        true, //charblock.is_pixel,
+                        is_pixel,
                         &font_sheet,
+                        page,
+                        &texture_size,
                         &notify,
                         &self.default_sampler,
                         &self.point_sampler,
                     );
                 }
 
-                if program_change {
-                    notify.modify_event(index.shadow, "program_dirty", 0);
+                if program_change || shadows_change {
+                    notify.modify_event(shadow_index, "program_dirty", 0);
                 }
 
-                // 修改阴影的顶点流
-                if shadow_geometry_change {
+                // 修改阴影的顶点流（模糊半径改变时， canvas字体阴影的顶点也会改变）
+                if shadow_geometry_change || dirty & (StyleType::TextShadow as usize) != 0 {
+                    let blur_geo = is_pixel && shadow.blur > 0.0;
                     // 如果填充色是纯色， 阴影的geo和文字的geo一样， 否则重新创建阴影的geo
+                    // 富文本的片段颜色及描边分组不影响阴影， 阴影包含所有分组的字符
                     match &text_style.text.color {
-                        Color::RGBA(_) => shadow_render_obj.geometry = render_obj.geometry.clone(),
-                        Color::LinearGradient(_) => {
-                            let color = text_style.shadow.color.clone();
+                        Color::RGBA(_) if !rich.colored && rich.strokes.len() == 0 && !blur_geo => shadow_render_obj.geometry = text_geometry.clone(),
+                        _ if blur_geo => {
+                            shadow_render_obj.geometry = create_blur_shadow_geo(
+                                char_block,
+                                &rich,
+                                layout,
+                                shadow,
+                                font_sheet,
+                                &self.index_buffer,
+                                &mut self.index_len,
+                                engine,
+                                glyph_scale,
+                            )
+                        }
+                        _ => {
+                            let color = shadow.color.clone();
                             let l = &mut self.index_len;
                             shadow_render_obj.geometry = create_geo(
 								dirty,
-								children,
-								idtree,
-								&node_states[*id],
+								char_block,
+								&rich,
+								None,
 								layout,
                                 &Color::RGBA(color),
                                 text,
@@ -397,32 +478,61 @@ impl<'a, C: HalContext + 'static> Runner<'a> for CharBlockSys<C> {
                                 &self.index_buffer,
                                 l,
 								engine,
-								node_states[*id].0.scale,
+								glyph_scale,
                             )
                         }
                     }
-                    notify.modify_event(index.shadow, "geometry", 0);
+                    notify.modify_event(shadow_index, "geometry", 0);
                 }
 
                 if dirty & (StyleType::Matrix as usize) != 0
                     || dirty & (StyleType::TextShadow as usize) != 0
                 {
                     modify_matrix(
-                        index.shadow,
+                        shadow_index,
                         create_let_top_offset_matrix(
                             layout,
                             world_matrix,
                             transform,
-                            text_style.shadow.h + h,
-                            text_style.shadow.v + v,
+                            shadow.h + h,
+                            shadow.v + v,
                             shadow_render_obj.depth,
                         ),
                         shadow_render_obj,
                         &notify,
                     );
                 }
-                notify.modify_event(index.shadow, "", 0);
+                notify.modify_event(shadow_index, "", 0);
 			}
+
+            // 富文本中描边与节点不同的片段及行内图片， 使用额外的渲染对象绘制
+            if geometry_change || program_change || dirty & (StyleType::Color as usize | StyleType::Stroke as usize | FONT_DIRTY) != 0 {
+                self.create_rich_objs(
+                    *id,
+                    &rich,
+                    char_block,
+                    text_style,
+                    layout,
+                    (world_matrix, transform, h, v),
+                    (is_pixel, glyph_scale, page),
+                    font_sheet,
+                    render_objs,
+                    default_state,
+                    engine,
+                    notify,
+                );
+            } else if dirty & (StyleType::Matrix as usize) != 0 {
+                for i in index.strokes.iter().chain(index.images.iter()) {
+                    let render_obj = &mut render_objs[*i];
+                    modify_matrix(
+                        *i,
+                        create_let_top_offset_matrix(layout, world_matrix, transform, h, v, render_obj.depth),
+                        render_obj,
+                        &notify,
+                    );
+                    notify.modify_event(*i, "", 0);
+                }
+            }
         }
     }
 }
@@ -508,16 +618,17 @@ impl<C: HalContext + 'static> CharBlockSys<C> {
                 true,
             ))),
             index_len: 100,
-            texture_size_ubo: Share::new(TextTextureSize::new(UniformValue::Float2(
+            texture_size_ubos: vec![Share::new(TextTextureSize::new(UniformValue::Float2(
                 texture_size.0 as f32,
                 texture_size.1 as f32,
-            ))),
+            )))],
 			old_texture_tex_version: 0,
 
             msdf_stroke_ubo_map,
             canvas_stroke_ubo_map,
             msdf_default_paramter: MsdfParamter::default(),
             canvas_default_paramter: CanvasTextParamter::default(),
+            image_default_paramter: ImageParamter::default(),
             mark: PhantomData,
         }
     }
@@ -528,14 +639,8 @@ impl<C: HalContext + 'static> CharBlockSys<C> {
         id: usize,
         render_objs: &mut SingleCaseImpl<RenderObjs>,
         default_state: &DefaultState,
-        have_shadow: bool,
         is_pixel: bool,
     ) -> I {
-        let shadow_index = if !have_shadow {
-            0
-        } else {
-            self.create_render_obj1(id, render_objs, default_state, is_pixel, 0.0)
-        };
         let index = self.create_render_obj1(id, render_objs, default_state, is_pixel, 0.1);
 
         // 创建RenderObj与Node实体的索引关系， 并设脏
@@ -543,7 +648,10 @@ impl<C: HalContext + 'static> CharBlockSys<C> {
             id,
             I {
                 text: index,
-                shadow: shadow_index,
+                shadows: Vec::new(),
+                page: 0,
+                strokes: Vec::new(),
+                images: Vec::new(),
             },
         );
         self.render_map[id].clone()
@@ -561,7 +669,7 @@ impl<C: HalContext + 'static> CharBlockSys<C> {
             let paramter: Share<dyn ProgramParamter> =
                 Share::new(self.canvas_default_paramter.clone());
             paramter.set_value("strokeColor", self.canvas_default_stroke_color.clone());
-            paramter.set_value("textureSize", self.texture_size_ubo.clone());
+            paramter.set_value("textureSize", self.texture_size_ubos[0].clone());
             (
                 CANVAS_TEXT_VS_SHADER_NAME.clone(),
                 CANVAS_TEXT_FS_SHADER_NAME.clone(),
@@ -587,22 +695,195 @@ impl<C: HalContext + 'static> CharBlockSys<C> {
         let render_obj = new_render_obj(id, depth_diff, false, vs_name, fs_name, paramter, state);
         render_obj
             .paramter
-            .set_value("textureSize", self.texture_size_ubo.clone());
+            .set_value("textureSize", self.texture_size_ubos[0].clone());
 			let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
         render_objs.insert(render_obj, Some(notify))
     }
 
+    // 取字体纹理页的尺寸
+    #[inline]
+    fn texture_size_ubo(&self, page: usize) -> Share<TextTextureSize> {
+        match self.texture_size_ubos.get(page) {
+            Some(r) => r.clone(),
+            None => self.texture_size_ubos[0].clone(),
+        }
+    }
+
     #[inline]
     fn remove_render_obj(&mut self, id: usize, render_objs: &mut SingleCaseImpl<RenderObjs>) {
         match self.render_map.remove(id) {
             Some(index) => {
                 let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
                 render_objs.remove(index.text, Some(notify));
-                render_objs.remove(index.shadow, Some(notify));
+                for i in index.shadows.into_iter().chain(index.strokes.into_iter()).chain(index.images.into_iter()) {
+                    render_objs.remove(i, Some(notify));
+                }
             }
             None => (),
         };
     }
+
+    // 重新创建阴影的渲染对象， 返回可见阴影的渲染对象索引
+    // 阴影在文字之下， 按css的规则， 先声明的阴影绘制在上层
+    fn create_shadow_objs(
+        &mut self,
+        id: usize,
+        text_style: &TextStyle,
+        is_pixel: bool,
+        render_objs: &mut SingleCaseImpl<RenderObjs>,
+        default_state: &DefaultState,
+        notify: &NotifyImpl,
+    ) -> Vec<usize> {
+        let old = match self.render_map.get_mut(id) {
+            Some(r) => std::mem::replace(&mut r.shadows, Vec::new()),
+            None => return Vec::new(),
+        };
+        for i in old.into_iter() {
+            render_objs.remove(i, Some(notify));
+        }
+
+        let count = text_style.shadows.iter().filter(|r| is_visible_shadow(r)).count();
+        let shadows: Vec<usize> = (0..count)
+            .map(|k| {
+                let depth_diff = 0.1 * (count - k) as f32 / (count + 1) as f32;
+                self.create_render_obj1(id, render_objs, default_state, is_pixel, depth_diff)
+            })
+            .collect();
+        if let Some(r) = self.render_map.get_mut(id) {
+            r.shadows = shadows.clone();
+        }
+        shadows
+    }
+
+    // 重新创建富文本的描边分组及行内图片的渲染对象
+    fn create_rich_objs(
+        &mut self,
+        id: usize,
+        rich: &RichText,
+        char_block: &CharBlock,
+        text_style: &TextStyle,
+        layout: &LayoutR,
+        (world_matrix, transform, h, v): (&WorldMatrix, &Transform, f32, f32),
+        (is_pixel, glyph_scale, page): (bool, f32, usize),
+        font_sheet: &FontSheet,
+        render_objs: &mut SingleCaseImpl<RenderObjs>,
+        default_state: &DefaultState,
+        engine: &mut Engine<C>,
+        notify: &NotifyImpl,
+    ) {
+        let old = match self.render_map.get_mut(id) {
+            Some(r) => (std::mem::replace(&mut r.strokes, Vec::new()), std::mem::replace(&mut r.images, Vec::new())),
+            None => return,
+        };
+        for i in old.0.into_iter().chain(old.1.into_iter()) {
+            render_objs.remove(i, Some(notify));
+        }
+
+        let texture_size = self.texture_size_ubo(page);
+        let mut strokes = Vec::with_capacity(rich.strokes.len());
+        for (k, stroke) in rich.strokes.iter().enumerate() {
+            let index = self.create_render_obj1(id, render_objs, default_state, is_pixel, 0.1);
+            let render_obj = &mut render_objs[index];
+            modify_color(index, 0, &text_style.text.color, engine, notify, render_obj, &self.default_ubos);
+            if let Color::RGBA(_) = &text_style.text.color {
+                if rich.colored {
+                    to_vex_color_defines(render_obj.vs_defines.as_mut(), render_obj.fs_defines.as_mut());
+                }
+            }
+            modify_stroke(
+                index,
+                StyleType::Stroke as usize,
+                stroke,
+                render_obj,
+                notify,
+                is_pixel,
+                &self.default_ubos,
+                &mut *self.canvas_stroke_ubo_map,
+                &mut *self.msdf_stroke_ubo_map,
+            );
+            modify_font(
+                index,
+                render_obj,
+                is_pixel,
+                font_sheet,
+                page,
+                &texture_size,
+                notify,
+                &self.default_sampler,
+                &self.point_sampler,
+            );
+            render_obj.geometry = get_geo_flow(
+                char_block,
+                &text_quads(char_block, rich, font_sheet, glyph_scale, Some(k + 1)),
+                layout,
+                &text_style.text.color,
+                rich.colored,
+                engine,
+                None,
+                &self.index_buffer,
+                &mut self.index_len,
+                None,
+            );
+            let matrix = create_let_top_offset_matrix(layout, world_matrix, transform, h, v, render_obj.depth);
+            modify_matrix(index, matrix, render_obj, notify);
+            notify.modify_event(index, "geometry", 0);
+            strokes.push(index);
+        }
+
+        let mut images = Vec::new();
+        for (index, x, y) in char_positions(char_block).into_iter() {
+            let src = match rich.span(index).and_then(|r| r.image.as_ref()).and_then(|r| r.src.as_ref()) {
+                Some(r) => r,
+                None => continue,
+            };
+            let c = &char_block.chars[index];
+            let paramter: Share<dyn ProgramParamter> = Share::new(self.image_default_paramter.clone());
+            paramter.set_texture("texture", (&src.bind, &self.default_sampler));
+            let state = State {
+                bs: default_state.df_bs.clone(),
+                rs: default_state.df_rs.clone(),
+                ss: default_state.df_ss.clone(),
+                ds: default_state.df_ds.clone(),
+            };
+            let render_obj = new_render_obj(id, 0.1, false, IMAGE_VS_SHADER_NAME.clone(), IMAGE_FS_SHADER_NAME.clone(), paramter, state);
+            let i = render_objs.insert(render_obj, Some(notify));
+            let render_obj = &mut render_objs[i];
+            render_obj.geometry = create_quad_geo(engine, &self.index_buffer, (x, y, x + c.size.0, y + c.size.1));
+            let matrix = create_let_top_offset_matrix(layout, world_matrix, transform, h, v, render_obj.depth);
+            modify_matrix(i, matrix, render_obj, notify);
+            notify.modify_event(i, "geometry", 0);
+            images.push(i);
+        }
+
+        if let Some(r) = self.render_map.get_mut(id) {
+            r.strokes = strokes;
+            r.images = images;
+        }
+    }
+}
+
+// 文字节点的字形所在的纹理页， 一个节点的字形总在同一页上
+#[inline]
+fn node_page(char_block: &CharBlock, font_sheet: &FontSheet) -> usize {
+    for c in char_block.chars.iter() {
+        // 行内图片没有字形
+        if c.ch > ' ' && c.ch_id_or_count > 0 {
+            return font_sheet.glyph_page(c.ch_id_or_count);
+        }
+    }
+    0
+}
+
+// 字体是否为像素字体， 以及字形纹理到布局的缩放(像素字体的字形按节点的缩放生成， sdf字形按SDF_FONT_SIZE生成)
+// 预生成的sdf纹理字体保持原来的渲染方式， 见FontSheet::is_sdf
+#[inline]
+fn font_type(font_sheet: &FontSheet, text_style: &TextStyle, scale: f32) -> (bool, f32) {
+    match font_sheet.get_font_info(&text_style.font.family) {
+        Some((font, size)) if font_sheet.is_sdf(font) => {
+            (false, SDF_FONT_SIZE / get_size(size, &text_style.font.size) as f32)
+        }
+        _ => (true, scale),
+    }
 }
 
 #[inline]
@@ -683,8 +964,8 @@ fn modify_color<C: HalContext + 'static>(
                 render_obj.fs_defines.as_mut(),
             )
         }
-        Color::LinearGradient(_) => {
-            // 如果未找到VERTEX_COLOR宏， 表示修改之前的颜色不为LinearGradient， 应该删除UCOLOR宏， 添加VERTEX_COLOR宏，并尝试设顶点脏， 否则， 不需要做任何处理
+        Color::LinearGradient(_) | Color::RadialGradient(_) => {
+            // 如果未找到VERTEX_COLOR宏， 表示修改之前的颜色不为渐变色， 应该删除UCOLOR宏， 添加VERTEX_COLOR宏，并尝试设顶点脏， 否则， 不需要做任何处理
             to_vex_color_defines(
                 render_obj.vs_defines.as_mut(),
                 render_obj.fs_defines.as_mut(),
@@ -700,6 +981,8 @@ fn modify_font(
     render_obj: &mut RenderObj,
     is_pixel: bool,
     font_sheet: &FontSheet,
+    page: usize,
+    texture_size: &Share<TextTextureSize>,
     notify: &NotifyImpl,
     default_sampler: &Share<SamplerRes>,
     point_sampler: &Share<SamplerRes>, // 点采样sampler
@@ -711,24 +994,30 @@ fn modify_font(
     } else {
         default_sampler
     };
+    let texture = match font_sheet.get_page_tex(page) {
+        Some(r) => r,
+        None => font_sheet.get_font_tex(),
+    };
     render_obj
         .paramter
-        .set_texture("texture", (&font_sheet.get_font_tex().bind, &sampler));
+        .set_texture("texture", (&texture.bind, &sampler));
+    render_obj
+        .paramter
+        .set_value("textureSize", texture_size.clone());
 }
 
 #[inline]
 fn modify_shadow_color<C: HalContext + 'static>(
     index: usize,
-    _local_style: usize,
+    shadow: &TextShadow,
     text_style: &TextStyle,
     notify: &NotifyImpl,
     render_obj: &mut RenderObj,
     engine: &mut Engine<C>,
     is_pixel: bool,
-    _class_ubo: &RenderCatch,
     canvas_stroke_ubo_map: &mut ResMap<CanvasTextStrokeColorUbo>,
 ) {
-    let c = &text_style.shadow.color;
+    let c = &shadow.color;
     if text_style.text.stroke.width > 0.0 && is_pixel {
         let ubo = create_hash_res(
             CanvasTextStrokeColorUbo::new(UniformValue::Float4(c.r, c.g, c.b, c.a)),
@@ -750,6 +1039,34 @@ fn modify_shadow_color<C: HalContext + 'static>(
     notify.modify_event(index, "ubo", 0);
 }
 
+// 设置阴影的模糊范围， 返回宏是否改变
+// msdf字体在距离场上做平滑过渡， 模糊范围受sdf纹理的距离范围（SDF_RANGE）限制；
+// canvas字体在着色器中对字形纹理做高斯采样， 模糊半径以纹素为单位
+#[inline]
+fn modify_shadow_blur(shadow: &TextShadow, render_obj: &mut RenderObj, is_pixel: bool, glyph_scale: f32) -> bool {
+    if shadow.blur <= 0.0 {
+        return render_obj.vs_defines.remove("TEXT_SHADOW_BLUR").is_some()
+            | render_obj.fs_defines.remove("TEXT_SHADOW_BLUR").is_some();
+    }
+    let blur = if is_pixel {
+        shadow.blur * glyph_scale
+    } else {
+        // 距离场中， 0.1的距离对应SDF_FONT_SIZE下的1像素
+        shadow.blur * glyph_scale / 10.0
+    };
+    render_obj
+        .paramter
+        .as_ref()
+        .set_single_uniform("blur", UniformValue::Float1(blur));
+    render_obj.vs_defines.add("TEXT_SHADOW_BLUR").is_none() | render_obj.fs_defines.add("TEXT_SHADOW_BLUR").is_none()
+}
+
+// 颜色完全透明的阴影不需要渲染
+#[inline]
+fn is_visible_shadow(shadow: &TextShadow) -> bool {
+    shadow.color.a > 0.0
+}
+
 #[inline]
 fn set_canvas_default_stroke(
     render_obj: &RenderObj,
@@ -767,13 +1084,13 @@ fn set_canvas_default_stroke(
 
 // 返回position， uv， color， index
 #[inline]
+// group为富文本的描边分组， None表示所有分组(阴影)
 fn create_geo<C: HalContext + 'static>(
 	dirty: usize,
-	children: &NodeList,
-	idtree: &SingleCaseImpl<IdTree>,
-	node_state: &NodeState,
+	char_block: &CharBlock,
+	rich: &RichText,
+	group: Option<usize>,
 	layout: &LayoutR,
-    // char_block: &CharBlock<L>,
     color: &Color,
     text: &TextContent,
     text_style: &TextStyle,
@@ -784,8 +1101,10 @@ fn create_geo<C: HalContext + 'static>(
 	engine: &mut Engine<C>,
 	scale: f32,
 ) -> Option<Share<GeometryRes>> {
-    // 是共享文字
-    if text.0 == String::new() {
+    // 阴影使用自身的颜色， 不使用片段的颜色
+    let vertex_color = rich.colored && group.is_some();
+    // 是共享文字(富文本不共享)
+    if text.0 == String::new() && rich.spans.len() == 0 {
         let mut hasher = DefaultHasher::default();
         text.1.hash(&mut hasher);
         // 对于布局信息， 如果没有在style中设置， 可以直接使用class中的布局hash
@@ -797,9 +1116,23 @@ fn create_geo<C: HalContext + 'static>(
             layout_hash.hash(&mut hasher);
         }
 
+        // 字形可能被回收后重新分配到其它位置， 字形的位置也要参与hash； 字符的位置由节点的宽度决定， 也要参与hash
+        // 截断位置同样由节点的宽度决定
+        (char_block.visible, char_block.truncated).hash(&mut hasher);
+        for c in char_block.chars.iter() {
+            (c.pos.0 as i32, c.pos.1 as i32).hash(&mut hasher);
+            if c.ch > ' ' {
+                if let Some(glyph) = font_sheet.get_glyph(c.ch_id_or_count) {
+                    (glyph.1.x as u32, glyph.1.y as u32, glyph.1.page).hash(&mut hasher);
+                }
+            }
+        }
+
         // 如果是渐变色， 计算渐变色的hash
-        if let Color::LinearGradient(ref c) = color {
-            c.hash(&mut hasher);
+        match color {
+            Color::LinearGradient(ref c) => c.hash(&mut hasher),
+            Color::RadialGradient(ref c) => c.hash(&mut hasher),
+            Color::RGBA(_) => (),
         }
 
         let hash = hasher.finish();
@@ -810,36 +1143,89 @@ fn create_geo<C: HalContext + 'static>(
 
         // 缓存中不存在 对应的geo， 创建geo并缓存
         get_geo_flow(
-			children,
-			idtree,
-			node_state,
+			char_block,
+			&text_quads(char_block, rich, font_sheet, scale, group),
 			layout,
             color,
-            font_sheet,
+            vertex_color,
             engine,
             Some(hash),
             share_index_buffer,
 			index_buffer_max_len,
-			scale,
+			None,
         )
     } else {
         // 如果文字不共享， 重新创建geo， 并且不缓存geo
         get_geo_flow(
-			children,
-			idtree,
-			node_state,
+			char_block,
+			&text_quads(char_block, rich, font_sheet, scale, group),
 			layout,
             color,
-            font_sheet,
+            vertex_color,
             engine,
             None,
             share_index_buffer,
 			index_buffer_max_len,
-			scale,
+			None,
         )
     }
 }
 
+// canvas字体模糊阴影的顶点流： 字形四边向外扩展模糊半径， 并记录字形在纹理中的范围， 着色器采样时不超出该范围
+// 装饰线的纹理坐标是纯色字形上的一个点， 不扩展， 其阴影不模糊
+fn create_blur_shadow_geo<C: HalContext + 'static>(
+	char_block: &CharBlock,
+	rich: &RichText,
+	layout: &LayoutR,
+	shadow: &TextShadow,
+	font_sheet: &FontSheet,
+	index_buffer: &Share<BufferRes>,
+	index_buffer_max_len: &mut usize,
+	engine: &mut Engine<C>,
+	scale: f32,
+) -> Option<Share<GeometryRes>> {
+	let mut quads = text_quads(char_block, rich, font_sheet, scale, None);
+	let (mins, maxs) = expand_blur_quads(&mut quads, shadow.blur);
+	get_geo_flow(
+		char_block,
+		&quads,
+		layout,
+		&Color::RGBA(shadow.color.clone()),
+		false,
+		engine,
+		None,
+		index_buffer,
+		index_buffer_max_len,
+		Some((mins.as_slice(), maxs.as_slice())),
+	)
+}
+
+// 字形四边（位置与纹理坐标）向外扩展模糊半径blur， 返回每个顶点对应的字形原始纹理范围(左上, 右下)
+// 纹理范围为空的四边形（装饰线）不扩展
+fn expand_blur_quads(quads: &mut [Quad], blur: f32) -> (Vec<f32>, Vec<f32>) {
+	let mut mins = Vec::with_capacity(8 * quads.len());
+	let mut maxs = Vec::with_capacity(8 * quads.len());
+	for q in quads.iter_mut() {
+		let (min, max) = ((q.uv[0], q.uv[1]), (q.uv[4], q.uv[5]));
+		let (w, h) = (q.ps[4] - q.ps[0], q.ps[5] - q.ps[1]);
+		if max.0 > min.0 && w > 0.0 && h > 0.0 {
+			let (du, dv) = (blur * (max.0 - min.0) / w, blur * (max.1 - min.1) / h);
+			let (sx, sy) = ([-1.0, -1.0, 1.0, 1.0], [-1.0, 1.0, 1.0, -1.0]);
+			for i in 0..4 {
+				q.ps[2 * i] += sx[i] * blur;
+				q.ps[2 * i + 1] += sy[i] * blur;
+				q.uv[2 * i] += sx[i] * du;
+				q.uv[2 * i + 1] += sy[i] * dv;
+			}
+		}
+		for _ in 0..4 {
+			mins.extend_from_slice(&[min.0, min.1]);
+			maxs.extend_from_slice(&[max.0, max.1]);
+		}
+	}
+	(mins, maxs)
+}
+
 fn text_layout_hash(text_style: &Text, font: &Font) -> u64 {
     let mut hasher = DefaultHasher::default();
     let hasher = &mut hasher;
@@ -865,6 +1251,8 @@ fn text_layout_hash(text_style: &Text, font: &Font) -> u64 {
     text_style.text_align.hash(hasher);
     text_style.white_space.hash(hasher);
     text_style.vertical_align.hash(hasher);
+    text_style.text_overflow.hash(hasher);
+    text_style.line_clamp.hash(hasher);
     font.weight.hash(hasher);
     match font.size {
         FontSize::None => 0.hash(hasher),
@@ -885,20 +1273,19 @@ fn text_layout_hash(text_style: &Text, font: &Font) -> u64 {
 // 返回position， uv， color， index
 #[inline]
 fn get_geo_flow<C: HalContext + 'static>(
-	children: &NodeList,
-	_idtree: &SingleCaseImpl<IdTree>,
-	node_state: &NodeState,
+	char_block: &CharBlock,
+	quads: &[Quad],
 	layout: &LayoutR,
     color: &Color,
-    font_sheet: &FontSheet,
+    vertex_color: bool, // 纯色文字是否使用顶点颜色(富文本片段的颜色)
     engine: &mut Engine<C>,
     hash: Option<u64>,
     index_buffer: &Share<BufferRes>,
 	index_buffer_max_len: &mut usize,
-	scale: f32,
+	uv_rects: Option<(&[f32], &[f32])>, // 字形在纹理中的范围（左上， 右下）， 模糊阴影使用
 ) -> Option<Share<GeometryRes>> {
-    let mut positions: Vec<f32> = Vec::with_capacity(8 * children.len);
-    let mut uvs: Vec<f32> = Vec::with_capacity(8 * children.len);
+    let mut positions: Vec<f32> = Vec::with_capacity(8 * quads.len());
+    let mut uvs: Vec<f32> = Vec::with_capacity(8 * quads.len());
     // let font_height = char_block.font_height;
     let mut i = 0;
     let mut size = 0;
@@ -910,49 +1297,17 @@ fn get_geo_flow<C: HalContext + 'static>(
 	};
 	
 	let rect = &layout.rect;
-	let mut word_pos = (0.0, 0.0);
-	let mut count = 0;
     match color {
-		Color::RGBA(_) => {
-			for c in node_state.0.text.iter(){
-				if c.ch == char::from(0) {
-					if c.ch_id_or_count > 0 {
-						word_pos = c.pos;
-						count = c.ch_id_or_count - 1;
+		Color::RGBA(c) => {
+			let mut colors = Vec::with_capacity(if vertex_color { 16 * quads.len() } else { 0 });
+			for q in quads.iter() {
+				positions.extend_from_slice(&q.ps);
+				uvs.extend_from_slice(&q.uv);
+				if vertex_color {
+					let c = q.color.as_ref().unwrap_or(c);
+					for _ in 0..4 {
+						colors.extend_from_slice(&[c.r, c.g, c.b, c.a]);
 					}
-					continue;
-				}
-				if c.ch <= ' ' {
-					continue;
-				}
-
-				let glyph = match font_sheet.get_glyph(c.ch_id_or_count) {
-					Some(r) => r.1.clone(),
-					None => continue,
-				};
-				if count > 0 {
-					count -= 1;
-					push_pos_uv(
-						&mut positions,
-						&mut uvs,
-						word_pos.0 + c.pos.0,
-						word_pos.1 + c.pos.1,
-						&glyph,
-						c.size.0,
-						c.size.1,
-						scale,
-					);
-				} else {
-					push_pos_uv(
-						&mut positions,
-						&mut uvs,
-						c.pos.0,
-						c.pos.1,
-						&glyph,
-						c.size.0,
-						c.size.1,
-						scale,
-					);
 				}
 			}
 			// 更新buffer
@@ -973,10 +1328,24 @@ fn get_geo_flow<C: HalContext + 'static>(
 					positions.len() / 8 * 6,
 				)
 				.unwrap();
+			if vertex_color {
+				let color_buffer = engine.create_buffer(
+					BufferType::Attribute,
+					colors.len(),
+					Some(BufferData::Float(&colors)),
+					false,
+				);
+				engine
+					.gl
+					.geometry_set_attribute(&geo_res.geo, &AttributeName::Color, &color_buffer, 4)
+					.unwrap();
+				geo_res.buffers.push(Share::new(BufferRes(color_buffer)));
+				size += buffer_size(colors.len(), BufferType::Attribute);
+			}
 		}
 		Color::LinearGradient(color) => {
 			let mut colors = vec![Vec::new()];
-			let mut indices = Vec::with_capacity(6 * children.len);
+			let mut indices = Vec::with_capacity(6 * quads.len());
 			// let (start, end) = cal_all_size(children, idtree, node_state, layouts, font_sheet); // 渐变范围
 																	 //渐变端点
 			let endp = find_lg_endp(
@@ -1004,47 +1373,9 @@ fn get_geo_flow<C: HalContext + 'static>(
 				data: lg_color,
 			}];
 			
-			for c in node_state.0.text.iter(){
-				if c.ch == char::from(0) {
-					if c.ch_id_or_count > 0 {
-						word_pos = c.pos;
-						count = c.ch_id_or_count - 1;
-					}
-					continue;
-				}
-				if c.ch <= ' ' {
-					continue;
-				}
-
-				let glyph = match font_sheet.get_glyph(c.ch_id_or_count) {
-					Some(r) => r.1.clone(),
-					None => continue,
-				};
-
-				if count > 0 {
-					count -= 1;
-					push_pos_uv(
-						&mut positions,
-						&mut uvs,
-						word_pos.0 + c.pos.0,
-						word_pos.1 + c.pos.1,
-						&glyph,
-						c.size.0,
-						c.size.1,
-						scale,
-					);
-				} else {
-					push_pos_uv(
-						&mut positions,
-						&mut uvs,
-						c.pos.0,
-						c.pos.1,
-						&glyph,
-						c.size.0,
-						c.size.1,
-						scale,
-					);
-				}
+			for q in quads.iter() {
+				positions.extend_from_slice(&q.ps);
+				uvs.extend_from_slice(&q.uv);
 
 				let (ps, indices_arr) = split_by_lg(
 					positions,
@@ -1099,6 +1430,66 @@ fn get_geo_flow<C: HalContext + 'static>(
 			size += buffer_size(indices.len(), BufferType::Indices);
 			size += buffer_size(colors.len(), BufferType::Attribute);
 		}
+		Color::RadialGradient(color) => {
+			let grid = (RADIAL_GLYPH_GRID + 1) * (RADIAL_GLYPH_GRID + 1);
+			let mut colors = Vec::with_capacity(4 * grid * quads.len());
+			let mut indices = Vec::with_capacity(6 * RADIAL_GLYPH_GRID * RADIAL_GLYPH_GRID * quads.len());
+			// 渐变框， 虚拟节点的字符位置相对于父节点， 否则相对于节点自身
+			let (x, y) = if char_block.mixed {
+				(rect.start, rect.top)
+			} else {
+				(0.0, 0.0)
+			};
+			let (center, radius) = cal_radial_gradient(
+				&Aabb2::new(
+					Point2::new(x, y),
+					Point2::new(x + rect.end - rect.start, y + rect.bottom - rect.top),
+				),
+				color.center,
+				color.shape,
+				color.size,
+			);
+
+			for q in quads.iter() {
+				positions.extend_from_slice(&q.ps);
+				uvs.extend_from_slice(&q.uv);
+
+				split_glyph_by_radial(
+					&mut positions,
+					&mut uvs,
+					&mut colors,
+					&mut indices,
+					&center,
+					radius,
+					color.list.as_slice(),
+				);
+			}
+
+			let color_buffer = engine.create_buffer(
+				BufferType::Attribute,
+				colors.len(),
+				Some(BufferData::Float(&colors)),
+				false,
+			);
+			let i_buffer = engine.create_buffer(
+				BufferType::Indices,
+				indices.len(),
+				Some(BufferData::Short(&indices)),
+				false,
+			);
+			engine
+				.gl
+				.geometry_set_attribute(&geo_res.geo, &AttributeName::Color, &color_buffer, 4)
+				.unwrap();
+			engine
+				.gl
+				.geometry_set_indices_short(&geo_res.geo, &i_buffer)
+				.unwrap();
+			geo_res.buffers.push(Share::new(BufferRes(i_buffer)));
+			geo_res.buffers.push(Share::new(BufferRes(color_buffer)));
+			size += buffer_size(indices.len(), BufferType::Indices);
+			size += buffer_size(colors.len(), BufferType::Attribute);
+		}
 	}
 
 	let position_buffer = engine.create_buffer(
@@ -1126,6 +1517,23 @@ fn get_geo_flow<C: HalContext + 'static>(
 	size += buffer_size(positions.len(), BufferType::Attribute);
 	size += buffer_size(uvs.len(), BufferType::Attribute);
 
+	if let Some((mins, maxs)) = uv_rects {
+		for (name, data) in [(AttributeName::UV1, mins), (AttributeName::UV2, maxs)].iter() {
+			let buffer = engine.create_buffer(
+				BufferType::Attribute,
+				data.len(),
+				Some(BufferData::Float(data)),
+				false,
+			);
+			engine
+				.gl
+				.geometry_set_attribute(&geo_res.geo, name, &buffer, 2)
+				.unwrap();
+			geo_res.buffers.push(Share::new(BufferRes(buffer)));
+			size += buffer_size(data.len(), BufferType::Attribute);
+		}
+	}
+
 	Some(match hash {
 		Some(hash) => engine.geometry_res_map.create(hash, geo_res, size, 0),
 		None => Share::new(geo_res),
@@ -1226,16 +1634,330 @@ fn fill_uv(positions: &mut Vec<f32>, uvs: &mut Vec<f32>, i: usize) {
     }
 }
 
-fn push_pos_uv(
-    positions: &mut Vec<f32>,
-    uvs: &mut Vec<f32>,
+// 富文本的绘制信息
+struct RichText<'a> {
+	spans: &'a [TextSpan],
+	runs: &'a [usize], // 每个字符所在的片段， 见CharBlock::runs
+	font_sizes: Vec<f32>, // 节点及每个片段的字号
+	font_heights: Vec<f32>, // 节点及每个片段的字体框高度， 字形在字符的高度内居中
+	decorations: Vec<TextDecoration>, // 节点及每个片段的装饰线
+	metrics: Vec<DecorationMetrics>, // 节点及每个片段的装饰线度量
+	strokes: Vec<Stroke>, // 与节点描边不同的片段描边， 每种描边使用一个额外的渲染对象绘制
+	groups: Vec<usize>, // 每个片段的描边分组， 0表示与节点一起绘制， k表示使用strokes[k - 1]
+	colored: bool, // 是否存在设置了颜色的片段或装饰线
+	is_pixel: bool, // 节点的字体是否为像素字体， 片段的字体必须与节点的字体类型相同
+}
+
+impl<'a> RichText<'a> {
+	fn new(text_spans: Option<&'a TextSpans>, char_block: &'a CharBlock, text_style: &TextStyle, font_sheet: &FontSheet) -> Self {
+		let spans: &[TextSpan] = match text_spans {
+			Some(r) => &r.0,
+			None => &[],
+		};
+		let (font, text) = (&text_style.font, &text_style.text);
+		let (tex_font, size) = match font_sheet.get_font_info(&font.family) {
+			Some((r, size)) => (Some(r), size),
+			None => (None, 0),
+		};
+		let is_pixel = tex_font.map_or(true, |r| !font_sheet.is_sdf(r));
+		let (mut font_sizes, mut font_heights, mut metrics) = (Vec::new(), Vec::new(), Vec::new());
+		// 片段的字号、字体框高度及装饰线度量
+		let mut push_font = |tex_font: Option<&TexFont>, size: usize, font_size: &FontSize, sw: f32| {
+			let font_size = get_size(size, font_size) as f32;
+			font_sizes.push(font_size);
+			match tex_font {
+				Some(r) => {
+					font_heights.push(r.get_font_height(font_size as usize, sw));
+					metrics.push(font_sheet.decoration_metrics(r, font_size, sw));
+				}
+				None => {
+					font_heights.push(font_size);
+					metrics.push(DecorationMetrics::default());
+				}
+			}
+		};
+		push_font(tex_font, size, &font.size, text.stroke.width);
+		let (mut decorations, mut strokes, mut groups) = (vec![text.decoration.clone()], Vec::new(), Vec::with_capacity(spans.len()));
+		for span in spans.iter() {
+			let (tex_font, size) = match span.font_family.as_ref().and_then(|r| font_sheet.get_font_info(r)) {
+				Some((r, size)) => (Some(r), size),
+				None => (tex_font, size),
+			};
+			push_font(
+				tex_font,
+				size,
+				span.font_size.as_ref().unwrap_or(&font.size),
+				span.stroke.as_ref().map_or(text.stroke.width, |r| r.width),
+			);
+			decorations.push(span.decoration.as_ref().unwrap_or(&text.decoration).clone());
+			let group = match &span.stroke {
+				Some(s) if !same_stroke(s, &text_style.text.stroke) => match strokes.iter().position(|r| same_stroke(r, s)) {
+					Some(i) => i + 1,
+					None => {
+						strokes.push(s.clone());
+						strokes.len()
+					}
+				},
+				_ => 0,
+			};
+			groups.push(group);
+		}
+		RichText {
+			spans: spans,
+			runs: &char_block.runs,
+			font_sizes: font_sizes,
+			font_heights: font_heights,
+			colored: spans.iter().any(|r| r.color.is_some()) || decorations.iter().any(|r| r.line != 0 && r.color.is_some()),
+			decorations: decorations,
+			metrics: metrics,
+			strokes: strokes,
+			groups: groups,
+			is_pixel: is_pixel,
+		}
+	}
+
+	// 字符所在的片段
+	fn span(&self, index: usize) -> Option<&'a TextSpan> {
+		match self.runs.get(index) {
+			Some(r) if *r > 0 => self.spans.get(*r - 1),
+			_ => None,
+		}
+	}
+
+	// 字符所在的描边分组
+	fn group(&self, index: usize) -> usize {
+		match self.runs.get(index) {
+			Some(r) if *r > 0 => self.groups[*r - 1],
+			_ => 0,
+		}
+	}
+
+	// 片段的字形纹理到布局的缩放， sdf字形按字号缩放， 像素字形已按字号生成
+	fn glyph_scale(&self, run: usize, scale: f32) -> f32 {
+		if self.is_pixel || self.font_sizes[run] == 0.0 {
+			scale
+		} else {
+			scale * self.font_sizes[0] / self.font_sizes[run]
+		}
+	}
+}
+
+fn same_stroke(a: &Stroke, b: &Stroke) -> bool {
+	a.width == b.width && a.color == b.color
+}
+
+// 需要绘制的四边形(左上、左下、右下、右上)， 字形或下划线
+struct Quad {
+	ps: [f32; 8],
+	uv: [f32; 8],
+	color: Option<CgColor>, // 所在富文本片段的颜色
+}
+
+// 文字块中可见字符(不包括单词容器)的索引及其相对于节点的位置
+pub(crate) fn char_positions(char_block: &CharBlock) -> Vec<(usize, f32, f32)> {
+	let mut r = Vec::with_capacity(char_block.chars.len());
+	let (mut word_pos, mut count) = ((0.0, 0.0), 0);
+	for (index, c) in char_block.chars.iter().enumerate() {
+		// 文字被截断时， 截断位置之后的字符不显示
+		if !char_block.is_visible(index) {
+			continue;
+		}
+		if c.ch == char::from(0) {
+			if c.ch_id_or_count > 0 {
+				word_pos = c.pos;
+				count = c.ch_id_or_count - 1;
+			}
+			continue;
+		}
+		// 单词内字符的位置相对于单词容器
+		if count > 0 {
+			count -= 1;
+			r.push((index, word_pos.0 + c.pos.0, word_pos.1 + c.pos.1));
+		} else {
+			r.push((index, c.pos.0, c.pos.1));
+		}
+	}
+	r
+}
+
+// 装饰线的一段， 同一片段在同一行上相邻字符的同种装饰线合并为一段
+struct DecorationSegment {
+	left: f32,
+	right: f32,
+	top: f32,
+	thickness: f32,
+	run: usize,
+	line: usize, // TextDecorationLine
+}
+
+// 收集需要绘制的字形及装饰线， group为富文本的描边分组， None表示所有分组(阴影)
+// 装饰线使用实心字形中心的纹理绘制， 位置取自字体的度量， 因此换行后每行各自绘制
+fn text_quads(char_block: &CharBlock, rich: &RichText, font_sheet: &FontSheet, scale: f32, group: Option<usize>) -> Vec<Quad> {
+	let mut quads = Vec::with_capacity(char_block.chars.len());
+	let solid = font_sheet
+		.get_glyph(char_block.solid_glyph)
+		.map(|r| (r.1.x + r.1.width / 2.0, r.1.y + r.1.height / 2.0));
+	let mut segments: Vec<DecorationSegment> = Vec::new();
+	for (index, x, y) in char_positions(char_block).into_iter() {
+		if group.map_or(false, |g| g != rich.group(index)) {
+			continue;
+		}
+		let c = &char_block.chars[index];
+		let span = rich.span(index);
+		let run = char_block.run(index);
+		let decoration = &rich.decorations[run];
+		if solid.is_some() && decoration.line != 0 && c.ch != '\n' && span.map_or(true, |r| r.image.is_none()) {
+			let (m, font_size) = (&rich.metrics[run], rich.font_sizes[run]);
+			// 字体框在字符的高度内居中
+			let font_top = y + (c.size.1 - rich.font_heights[run]) / 2.0;
+			let thickness = if decoration.thickness > 0.0 { decoration.thickness } else { m.thickness };
+			let lines = [
+				(TextDecorationLine::Underline as usize, m.underline),
+				(TextDecorationLine::Overline as usize, m.overline),
+				(TextDecorationLine::LineThrough as usize, m.line_through - thickness / 2.0),
+			];
+			for &(line, offset) in lines.iter() {
+				if decoration.line & line == 0 {
+					continue;
+				}
+				let top = font_top + offset;
+				let merged = segments.iter_mut().rev().take(3).find(|s| {
+					s.run == run && s.line == line && (s.top - top).abs() < 0.5 && x <= s.right + font_size && x + c.size.0 >= s.left - font_size
+				});
+				match merged {
+					Some(s) => {
+						s.left = s.left.min(x);
+						s.right = s.right.max(x + c.size.0);
+					}
+					None => segments.push(DecorationSegment {
+						left: x,
+						right: x + c.size.0,
+						top: top,
+						thickness: thickness,
+						run: run,
+						line: line,
+					}),
+				}
+			}
+		}
+		// 行内图片由单独的渲染对象绘制
+		if c.ch <= ' ' || span.map_or(false, |r| r.image.is_some()) {
+			continue;
+		}
+
+		let glyph = match font_sheet.get_glyph(c.ch_id_or_count) {
+			Some(r) => r.1.clone(),
+			None => continue,
+		};
+		let (ps, uv) = glyph_quad(x, y, &glyph, c.size.0, c.size.1, rich.glyph_scale(char_block.run(index), scale));
+		quads.push(Quad {
+			ps: ps,
+			uv: uv,
+			color: span.and_then(|r| r.color.clone()),
+		});
+	}
+	if let Some(uv) = solid {
+		for s in segments.into_iter() {
+			let decoration = &rich.decorations[s.run];
+			// 装饰线未设置颜色时使用所在片段的文字颜色
+			let color = match &decoration.color {
+				Some(r) => Some(r.clone()),
+				None if s.run > 0 => rich.spans[s.run - 1].color.clone(),
+				None => None,
+			};
+			decoration_quads(&mut quads, &s, decoration.style, uv, color);
+		}
+	}
+	quads
+}
+
+// 按装饰线的样式生成四边形， 虚线、点线、波浪线的尺寸与粗细成比例
+fn decoration_quads(quads: &mut Vec<Quad>, s: &DecorationSegment, style: TextDecorationStyle, (u, v): (f32, f32), color: Option<CgColor>) {
+	let t = s.thickness;
+	let uv = [u, v, u, v, u, v, u, v];
+	let mut push = |ps: [f32; 8]| quads.push(Quad { ps: ps, uv: uv, color: color.clone() });
+	match style {
+		TextDecorationStyle::Solid => {
+			let b = s.top + t;
+			push([s.left, s.top, s.left, b, s.right, b, s.right, s.top]);
+		}
+		TextDecorationStyle::Dashed | TextDecorationStyle::Dotted => {
+			// 虚线长3倍粗细， 点线为正方形， 间隔均与线段等长
+			let len = if style == TextDecorationStyle::Dashed { 3.0 * t } else { t };
+			let b = s.top + t;
+			let mut l = s.left;
+			while l < s.right {
+				let r = (l + len).min(s.right);
+				push([l, s.top, l, b, r, b, r, s.top]);
+				l += 2.0 * len;
+			}
+		}
+		TextDecorationStyle::Wavy => {
+			// 折线近似的波浪， 半个周期宽2倍粗细， 振幅为粗细， 中线与实线的中线重合
+			let (half, center) = (2.0 * t, s.top + t / 2.0);
+			let (mut l, mut up) = (s.left, true);
+			while l < s.right {
+				let r = (l + half).min(s.right);
+				// 中线在center ± t之间往复， 线段的上边缘比中线高t/2
+				let (from, to) = if up { (center + t, center - t) } else { (center - t, center + t) };
+				let y0 = from - t / 2.0;
+				let y1 = y0 + (to - from) * (r - l) / half;
+				push([l, y0, l, y0 + t, r, y1 + t, r, y1]);
+				l = r;
+				up = !up;
+			}
+		}
+	}
+}
+
+// 行内图片的四边形， 纹理坐标为整张图片
+fn create_quad_geo<C: HalContext + 'static>(
+	engine: &mut Engine<C>,
+	index_buffer: &Share<BufferRes>,
+	(l, t, r, b): (f32, f32, f32, f32),
+) -> Option<Share<GeometryRes>> {
+	let positions = [l, t, l, b, r, b, r, t];
+	let uvs = [0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0];
+	let geo = engine.create_geometry();
+	let position_buffer = engine.create_buffer(
+		BufferType::Attribute,
+		positions.len(),
+		Some(BufferData::Float(&positions)),
+		false,
+	);
+	let uv_buffer = engine.create_buffer(
+		BufferType::Attribute,
+		uvs.len(),
+		Some(BufferData::Float(&uvs)),
+		false,
+	);
+	engine
+		.gl
+		.geometry_set_attribute(&geo, &AttributeName::Position, &position_buffer, 2)
+		.unwrap();
+	engine
+		.gl
+		.geometry_set_attribute(&geo, &AttributeName::UV0, &uv_buffer, 2)
+		.unwrap();
+	engine
+		.gl
+		.geometry_set_indices_short_with_offset(&geo, index_buffer, 0, 6)
+		.unwrap();
+	Some(Share::new(GeometryRes {
+		geo: geo,
+		buffers: vec![Share::new(BufferRes(uv_buffer)), Share::new(BufferRes(position_buffer))],
+	}))
+}
+
+// 字形的四边形(左上、左下、右下、右上)的顶点及uv
+fn glyph_quad(
 	x: f32,
 	mut y: f32,
     glyph: &Glyph,
 	width: f32,
 	height: f32,
 	scale: f32,
-) {
+) -> ([f32; 8], [f32; 8]) {
 	let ratio = 1.0/scale;
 	let w = glyph.width.ceil();
 	let h = glyph.height.ceil();
@@ -1270,8 +1992,54 @@ fn push_pos_uv(
         glyph.x + w,
         glyph.y,
 	];
-    uvs.extend_from_slice(&uv);
-    positions.extend_from_slice(&ps[..]);
+    (ps, uv)
+}
+
+// 径向渐变的文字， 每个字符的四边形被划分为RADIAL_GLYPH_GRID * RADIAL_GLYPH_GRID的网格， 在网格顶点上计算颜色
+const RADIAL_GLYPH_GRID: usize = 4;
+
+// 将最后压入的四边形（左上、左下、右下、右上）替换为网格， 并为网格顶点计算径向渐变颜色
+#[inline]
+fn split_glyph_by_radial(
+	positions: &mut Vec<f32>,
+	uvs: &mut Vec<f32>,
+	colors: &mut Vec<f32>,
+	indices: &mut Vec<u16>,
+	center: &Point2,
+	radius: (f32, f32),
+	list: &[ColorAndPosition],
+) {
+	let i = positions.len() - 8;
+	let (l, t, r, b) = (positions[i], positions[i + 1], positions[i + 4], positions[i + 5]);
+	let (ul, ut, ur, ub) = (uvs[i], uvs[i + 1], uvs[i + 4], uvs[i + 5]);
+	positions.truncate(i);
+	uvs.truncate(i);
+
+	let start = (i / 2) as u16;
+	let n = RADIAL_GLYPH_GRID;
+	for row in 0..n + 1 {
+		let ratio_y = row as f32 / n as f32;
+		let (y, v) = (t + (b - t) * ratio_y, ut + (ub - ut) * ratio_y);
+		for col in 0..n + 1 {
+			let ratio_x = col as f32 / n as f32;
+			let x = l + (r - l) * ratio_x;
+			positions.extend_from_slice(&[x, y]);
+			uvs.extend_from_slice(&[ul + (ur - ul) * ratio_x, v]);
+			colors.extend_from_slice(&radial_gradient_color(
+				list,
+				radial_gradient_pos(x, y, center, radius),
+			));
+		}
+	}
+
+	let n = n as u16;
+	for row in 0..n {
+		for col in 0..n {
+			let lt = start + row * (n + 1) + col;
+			let lb = lt + n + 1;
+			indices.extend_from_slice(&[lt, lb, lb + 1, lt, lb + 1, lt + 1]);
+		}
+	}
 }
 
 fn create_index_buffer(count: usize) -> Vec<u16> {
@@ -1291,3 +2059,217 @@ impl_system! {
         MultiCaseListener<Node, TextStyle, DeleteEvent>
     }
 }
+
+#[cfg(test)]
+use atom::Atom;
+#[cfg(test)]
+use ecs::{Lend, LendMut};
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+
+#[test]
+fn test_text_shadow_list() {
+	// 多个阴影按声明顺序保存， 颜色可以在长度之前或之后， 模糊半径可省略
+	let mut gui = SnapshotGui::new(SceneDesc::new(20, 20));
+	let root = gui.root;
+	let node = gui.append(
+		&NodeDesc::new("text-shadow:1px 2px #ff0000, #00ff00 -3px 4px 5px, 0 0 6px").with_text("a"),
+		root,
+	);
+	let text_styles = gui.gui.text_style.lend();
+	let shadows = &text_styles[node].shadows;
+	assert_eq!(shadows.len(), 3);
+	let r: Vec<(f32, f32, f32)> = shadows.iter().map(|s| (s.h, s.v, s.blur)).collect();
+	assert_eq!(r, vec![(1.0, 2.0, 0.0), (-3.0, 4.0, 5.0), (0.0, 0.0, 6.0)]);
+	assert_eq!((shadows[0].color.r, shadows[0].color.g), (1.0, 0.0));
+	assert_eq!((shadows[1].color.r, shadows[1].color.g), (0.0, 1.0));
+	// 未指定颜色时为黑色
+	assert_eq!((shadows[2].color.r, shadows[2].color.a), (0.0, 1.0));
+}
+
+#[test]
+fn test_blur_shadow_quads() {
+	// 字形(10, 10)~(20, 30)， 纹理范围(100, 200)~(105, 220)， 纹理与位置的比例为(0.5, 1)
+	let mut quads = vec![
+		Quad {
+			ps: [10.0, 10.0, 10.0, 30.0, 20.0, 30.0, 20.0, 10.0],
+			uv: [100.0, 200.0, 100.0, 220.0, 105.0, 220.0, 105.0, 200.0],
+			color: None,
+		},
+		// 装饰线， 纹理坐标为纯色字形上的一个点
+		Quad {
+			ps: [0.0, 40.0, 0.0, 41.0, 30.0, 41.0, 30.0, 40.0],
+			uv: [50.0, 50.0, 50.0, 50.0, 50.0, 50.0, 50.0, 50.0],
+			color: None,
+		},
+	];
+	let (mins, maxs) = expand_blur_quads(&mut quads, 2.0);
+	assert_eq!(quads[0].ps, [8.0, 8.0, 8.0, 32.0, 22.0, 32.0, 22.0, 8.0]);
+	assert_eq!(quads[0].uv, [99.0, 198.0, 99.0, 222.0, 106.0, 222.0, 106.0, 198.0]);
+	assert_eq!(quads[1].ps, [0.0, 40.0, 0.0, 41.0, 30.0, 41.0, 30.0, 40.0]);
+	assert_eq!(quads[1].uv, [50.0; 8]);
+	// 每个顶点记录字形原始的纹理范围
+	assert_eq!(mins.len(), 16);
+	assert_eq!(&mins[0..8], &[100.0, 200.0, 100.0, 200.0, 100.0, 200.0, 100.0, 200.0]);
+	assert_eq!(&maxs[0..8], &[105.0, 220.0, 105.0, 220.0, 105.0, 220.0, 105.0, 220.0]);
+	assert_eq!(&mins[8..10], &[50.0, 50.0]);
+}
+
+#[test]
+fn test_decoration_wrap() {
+	// 换行的文字， 每行生成一条装饰线， 范围为该行的第一个字符到最后一个字符
+	let text: String = (0..8u32).map(|i| std::char::from_u32(0x4e00 + i).unwrap()).collect();
+	let mut scene = SceneDesc::new(100, 100);
+	scene.nodes.push(
+		NodeDesc::new("position:absolute;left:0px;top:0px;width:40px;font-size:16px;text-decoration:underline")
+			.with_text(text.as_str()),
+	);
+	let mut gui = SnapshotGui::new(scene);
+	gui.render();
+	let node = {
+		let idtree = gui.gui.idtree.lend();
+		idtree[gui.root].children().head
+	};
+
+	let char_blocks = gui.gui.char_block.lend();
+	let text_styles = gui.gui.text_style.lend();
+	let font_sheet = gui.gui.font_sheet.lend();
+	let font_sheet = font_sheet.borrow();
+	let char_block = &char_blocks[node];
+	let rich = RichText::new(None, char_block, &text_styles[node], &font_sheet);
+
+	// 每行的范围(top, left, right)
+	let mut lines: Vec<(f32, f32, f32)> = Vec::new();
+	for (index, x, y) in char_positions(char_block).into_iter() {
+		let w = char_block.chars[index].size.0;
+		match lines.iter_mut().find(|r| (r.0 - y).abs() < 0.5) {
+			Some(r) => {
+				r.1 = r.1.min(x);
+				r.2 = r.2.max(x + w);
+			}
+			None => lines.push((y, x, x + w)),
+		}
+	}
+	assert!(lines.len() >= 2);
+
+	// 装饰线的纹理坐标为一个点
+	let quads = text_quads(char_block, &rich, &font_sheet, 1.0, None);
+	let decorations: Vec<&Quad> = quads.iter().filter(|q| q.uv[0] == q.uv[4] && q.uv[1] == q.uv[5]).collect();
+	assert_eq!(decorations.len(), lines.len());
+	for (q, line) in decorations.iter().zip(lines.iter()) {
+		assert_eq!((q.ps[0], q.ps[4]), (line.1, line.2));
+		assert!(q.ps[1] >= line.0);
+		assert!(line.1 == 0.0 && line.2 <= 40.0);
+	}
+}
+
+#[cfg(test)]
+fn span(start: usize, end: usize, color: Option<CgColor>, font_size: Option<f32>) -> TextSpan {
+	TextSpan {
+		start: start,
+		end: end,
+		color: color,
+		font_size: font_size.map(|r| FontSize::Length(r)),
+		..Default::default()
+	}
+}
+
+#[test]
+fn test_text_span() {
+	// 片段的边界在单词内部， 片段内的字符使用片段的字号和颜色， 其余字符使用节点的样式； 空片段不影响任何字符
+	let mut gui = SnapshotGui::new(SceneDesc::new(100, 40));
+	let root = gui.root;
+	let node = gui.append(
+		&NodeDesc::new("position:absolute;left:0px;top:0px;font-size:16px;white-space:nowrap").with_text("aaaa"),
+		root,
+	);
+	let red = CgColor::new(1.0, 0.0, 0.0, 1.0);
+	gui.gui.text_spans.lend_mut().insert(
+		node,
+		TextSpans(vec![
+			span(1, 3, Some(red.clone()), Some(32.0)),
+			span(3, 3, Some(CgColor::new(0.0, 1.0, 0.0, 1.0)), Some(8.0)),
+		]),
+	);
+	gui.render();
+
+	let char_blocks = gui.gui.char_block.lend();
+	let text_styles = gui.gui.text_style.lend();
+	let text_spans = gui.gui.text_spans.lend();
+	let font_sheet = gui.gui.font_sheet.lend();
+	let font_sheet = font_sheet.borrow();
+	let char_block = &char_blocks[node];
+
+	// 一个单词容器及4个字符
+	let chars: Vec<(usize, f32, f32)> = char_positions(char_block).into_iter().filter(|r| char_block.chars[r.0].ch == 'a').collect();
+	assert_eq!(chars.len(), 4);
+	let runs: Vec<usize> = chars.iter().map(|r| char_block.run(r.0)).collect();
+	assert_eq!(runs, vec![0, 1, 1, 0]);
+	let sizes: Vec<f32> = chars.iter().map(|r| char_block.chars[r.0].size.0).collect();
+	assert_eq!((sizes[1], sizes[2], sizes[3]), (2.0 * sizes[0], 2.0 * sizes[0], sizes[0]));
+	// 同一单词内的字符依次排列， 不重叠
+	for i in 1..4 {
+		assert!(chars[i].1 >= chars[i - 1].1 + sizes[i - 1] - 0.001);
+	}
+
+	let rich = RichText::new(text_spans.get(node), char_block, &text_styles[node], &font_sheet);
+	assert_eq!(&rich.font_sizes[..], &[16.0, 32.0, 8.0]);
+	let quads = text_quads(char_block, &rich, &font_sheet, 1.0, None);
+	assert_eq!(quads.len(), 4);
+	let colors: Vec<Option<f32>> = quads.iter().map(|q| q.color.as_ref().map(|c| c.r)).collect();
+	assert_eq!(colors, vec![None, Some(1.0), Some(1.0), None]);
+}
+
+#[test]
+fn test_font_type() {
+	// 原生字体按需生成SDF_FONT_SIZE大小的sdf字形， 预生成的sdf纹理字体与像素字体一样， 按节点的缩放渲染
+	let gui = SnapshotGui::new(SceneDesc::new(20, 20));
+	let font_sheet = gui.gui.font_sheet.lend_mut();
+	let mut font_sheet = font_sheet.borrow_mut();
+	let data = std::fs::read("../gui_web/static/res/kaiti.TTF").unwrap();
+	font_sheet.add_native_font(Atom::from("native"), data, false).unwrap();
+	font_sheet.set_src(Atom::from("prebaked"), false, 0.0, 0.0);
+	font_sheet.set_face(Atom::from("native_face"), 0.0, 16, 500, "native".to_string());
+	font_sheet.set_face(Atom::from("prebaked_face"), 0.0, 16, 500, "prebaked".to_string());
+
+	let mut text_style = TextStyle::default();
+	text_style.font.size = FontSize::Length(16.0);
+	text_style.font.family = Atom::from("native_face");
+	assert_eq!(font_type(&font_sheet, &text_style, 2.0), (false, SDF_FONT_SIZE / 16.0));
+	text_style.font.family = Atom::from("prebaked_face");
+	assert_eq!(font_type(&font_sheet, &text_style, 2.0), (true, 2.0));
+
+	// 富文本与节点使用相同的判断
+	let char_block = CharBlock::default();
+	assert!(RichText::new(None, &char_block, &text_style, &font_sheet).is_pixel);
+	text_style.font.family = Atom::from("native_face");
+	assert!(!RichText::new(None, &char_block, &text_style, &font_sheet).is_pixel);
+}
+
+#[test]
+fn test_native_text_snapshot() {
+	// 使用原生字体时， 字形被光栅化到字体纹理并绘制， 颜色为文字颜色， 字形之外不绘制
+	let mut scene = SceneDesc::new(40, 20);
+	scene.native_font = Some(std::fs::read("../gui_web/static/res/kaiti.TTF").unwrap());
+	scene.nodes.push(
+		NodeDesc::new("position:absolute;left:0px;top:0px;font-size:16px;color:#ff0000").with_text("中"),
+	);
+	let mut gui = SnapshotGui::new(scene);
+	gui.render();
+
+	let (w, h, pixels) = gui.read_pixels();
+	let mut inked = 0;
+	for y in 0..h {
+		for x in 0..w {
+			let i = ((y * w + x) * 4) as usize;
+			if pixels[i + 1] < 128 {
+				// 字形在第一个字符的范围内， 且为文字颜色
+				assert!(x < 16);
+				assert!(pixels[i] > 200 && pixels[i + 2] < 128);
+				inked += 1;
+			}
+		}
+	}
+	assert!(inked > 10);
+	assert_eq!(gui.pixel(30, 10), [255, 255, 255, 255]);
+}
@@ -2,6 +2,9 @@
  * 裁剪
  * 非旋转矩形裁剪区域采用向shader传入裁剪区域的的aabb， 在shader中通过aabb来判断是否裁剪
  * 旋转矩形裁剪区域采用在纹理中填入每像素的裁剪位标记，shader通过采样该纹理来确定是否渲染该像素
+ * 圆角、clip-path等非矩形裁剪区域与旋转矩形相同，使用裁剪纹理
+ * 每个节点都会传入所在裁剪链上所有裁剪区域（包围盒）求交后的aabb，裁剪链上有使用裁剪纹理的裁剪区域时，同时使用裁剪纹理
+ * 使用模板缓冲区裁剪的裁剪区域不在裁剪纹理中， 由渲染系统（RenderSys）处理
  */
 use std::marker::PhantomData;
 
@@ -26,8 +29,6 @@ pub struct ClipSys<C> {
     no_rotate_dirtys: VecMap<bool>,
     render_obj: Option<ClipTextureRender>,
 	marker: PhantomData<C>,
-	
-	rotate_index: usize,
 }
 
 struct ClipTextureRender {
@@ -146,64 +147,60 @@ impl<C: HalContext + 'static> ClipSys<C> {
             no_rotate_dirtys: VecMap::default(),
             render_obj: None,
 			marker: PhantomData,
-			rotate_index: 0,
         }
     }
 
     fn set_clip_uniform(
         &self,
         id: usize,
-        by_overflow: usize,
         aabb: Option<&(Aabb3, Share<dyn UniformBuffer>)>,
+        texture_mask: usize,
         notify: &NotifyImpl,
         render_obj: &mut RenderObj,
         engine: &mut Engine<C>,
     ) {
-        match aabb {
-            Some(item) => {
-                // if render_obj.visibility {
-                render_obj.paramter.set_value("clipBox", item.1.clone());
-                if let None = render_obj.fs_defines.add("CLIP_BOX") {
-                    render_obj.vs_defines.add("CLIP_BOX");
-                    render_obj.fs_defines.remove("CLIP");
-                    notify.modify_event(id, "program_dirty", 0);
-                }
-                // }
+        if let Some(item) = aabb {
+            render_obj.paramter.set_value("clipBox", item.1.clone());
+            if let None = render_obj.fs_defines.add("CLIP_BOX") {
+                render_obj.vs_defines.add("CLIP_BOX");
+                notify.modify_event(id, "program_dirty", 0);
             }
-            None => {
-				let by_overflow = by_overflow & self.rotate_index;
-                render_obj
-                    .paramter
-					.set_single_uniform("clipIndices1", UniformValue::Float1(by_overflow as f32));
-				render_obj
-                    .paramter
-                    .set_single_uniform("clipIndices2", UniformValue::Float1((by_overflow >> 24) as f32));
-                let clip_render = match self.render_obj.as_ref(){
-					Some(r) => r,
-					None => panic!("xxxxxxxxxxxxxxxxxxx, by_overflow: {}", by_overflow)
-				};
-                // 插入裁剪ubo 插入裁剪宏
-                if let None = render_obj.fs_defines.add("CLIP") {
-                    render_obj.vs_defines.remove("CLIP_BOX");
-                    render_obj.fs_defines.remove("CLIP_BOX");
-                    render_obj.paramter.set_texture(
-                        "clipTexture",
-                        (
-                            match engine
-                                .gl
-                                .rt_get_color_texture(&clip_render.render_target, 0){
-									Some(r) => r,
-									None => panic!("yyyyyyyyyyyyyyyyyyy")
-								},
-                            &clip_render.sampler,
-                        ),
-                    );
-                    render_obj
-                        .paramter
-                        .set_value("clipTextureSize", clip_render.clip_size_ubo.clone());
-                    notify.modify_event(id, "program_dirty", 0);
-                }
+        }
+
+        if texture_mask == 0 {
+            if let Some(_) = render_obj.fs_defines.remove("CLIP") {
+                notify.modify_event(id, "program_dirty", 0);
             }
+            return;
+        }
+        render_obj
+            .paramter
+            .set_single_uniform("clipIndices1", UniformValue::Float1((texture_mask & 0xffffff) as f32));
+        render_obj
+            .paramter
+            .set_single_uniform("clipIndices2", UniformValue::Float1((texture_mask >> 24) as f32));
+        let clip_render = match self.render_obj.as_ref(){
+            Some(r) => r,
+            None => panic!("xxxxxxxxxxxxxxxxxxx, texture_mask: {}", texture_mask)
+        };
+        // 插入裁剪ubo 插入裁剪宏
+        if let None = render_obj.fs_defines.add("CLIP") {
+            render_obj.paramter.set_texture(
+                "clipTexture",
+                (
+                    match engine
+                        .gl
+                        .rt_get_color_texture(&clip_render.render_target, 0){
+                        Some(r) => r,
+                        None => panic!("yyyyyyyyyyyyyyyyyyy")
+                    },
+                    &clip_render.sampler,
+                ),
+            );
+            render_obj
+                .paramter
+                .set_value("clipTextureSize", clip_render.clip_size_ubo.clone());
+            notify.modify_event(id, "program_dirty", 0);
         }
     }
 }
@@ -248,42 +245,28 @@ impl<'a, C: HalContext + 'static> Runner<'a> for ClipSys<C> {
 			let mut positions: Vec<f32> = Vec::new();
 			let mut mumbers: Vec<f32> = Vec::new();
 
-			self.rotate_index = 0;
-            for (i, c) in overflow.clip.iter() {
-                if c.has_rotate {
-                    let p = &c.view;
-                    positions.push(p[0].x);
-                    positions.push(p[0].y);
-                    positions.push(p[1].x);
-                    positions.push(p[1].y);
-                    positions.push(p[2].x);
-                    positions.push(p[2].y);
-                    positions.push(p[3].x);
-					positions.push(p[3].y);
-					let m = i as f32 -1.0;
-					mumbers.extend_from_slice(&[m,m,m,m]);
-					self.rotate_index |= 1 << m as usize;
+			let mut indices: Vec<u16> = Vec::new();
+
+			let mut count: usize = 0;
+			for (_i, c) in overflow.clip.iter() {
+				if c.texture_index > 0 {
+					let start = (positions.len() / 2) as u16;
+					match &c.shape {
+						Some((p, tri)) => {
+							positions.extend_from_slice(p.as_slice());
+							indices.extend(tri.iter().map(|v| start + *v));
+						}
+						None => {
+							let p = &c.view;
+							positions.extend_from_slice(&[p[0].x, p[0].y, p[1].x, p[1].y, p[2].x, p[2].y, p[3].x, p[3].y]);
+							indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+						}
+					}
+					let m = c.texture_index as f32 -1.0;
+					mumbers.resize(positions.len() / 2, m);
+					count = count.max(c.texture_index);
 				}
-            }
-
-            let mut indices: Vec<u16> = Vec::new();
-
-			// clip数量超出16个，设置clip只有16个
-            let mut count = positions.len() / 8;
-            if count > 32 {
-                count = 32;
-                unsafe { positions.set_len(256) };
-            }
-            for i in 0..count as u16 {
-                indices.extend_from_slice(&[
-                    4 * i + 0,
-                    4 * i + 1,
-                    4 * i + 2,
-                    4 * i + 0,
-                    4 * i + 2,
-                    4 * i + 3,
-                ]);
-            }
+			}
 
             let p_buffer = engine.create_buffer(BufferType::Attribute, positions.len(), Some(BufferData::Float(positions.as_slice())), false);
             let m_buffer = engine.create_buffer(
@@ -345,6 +328,7 @@ impl<'a, C: HalContext + 'static> Runner<'a> for ClipSys<C> {
         let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
         let mut pre_by_overflow = 0;
         let mut aabb = None;
+        let mut texture_mask = 0;
         for id in dirty_list.0.iter() {
             let style_mark = match style_marks.get(*id) {
                 Some(r) => r,
@@ -361,11 +345,12 @@ impl<'a, C: HalContext + 'static> Runner<'a> for ClipSys<C> {
                 if by_overflow != pre_by_overflow {
                     pre_by_overflow = by_overflow;
                     aabb = overflow_clip.clip_map.get(&by_overflow);
+                    texture_mask = overflow_clip.clip[by_overflow].texture_mask;
                 }
                 for id in obj_ids.iter() {
                     let render_obj = &mut render_objs[*id];
                    
-                    self.set_clip_uniform(*id, by_overflow, aabb, &notify, render_obj, engine);
+                    self.set_clip_uniform(*id, aabb, texture_mask, &notify, render_obj, engine);
                 }
             } else if style_mark.dirty & StyleType::ByOverflow as usize != 0 && by_overflow == 0 {
                 // 裁剪剔除
@@ -432,7 +417,7 @@ impl<'a, C: HalContext + 'static> SingleCaseListener<'a, OverflowClip, ModifyEve
     type WriteData = &'a mut SingleCaseImpl<OverflowClip>;
     fn listen(&mut self, event: &ModifyEvent, _read: Self::ReadData, write: Self::WriteData) {
 		let c = &write.clip[event.id];
-        if c.has_rotate || c.old_has_rotate {
+        if c.texture_index > 0 || c.old_has_rotate {
             self.dirty = true;
         }
     }
@@ -478,3 +463,179 @@ impl_system! {
 		SingleCaseListener<RenderBegin, ModifyEvent>
     }
 }
+
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+#[cfg(test)]
+use ecs::LendMut;
+
+#[test]
+fn test_border_radius_clip_snapshot() {
+    let mut scene = SceneDesc::new(20, 20);
+    // 圆形的裁剪容器， 子节点铺满容器
+    scene.nodes.push(
+        NodeDesc::new("position:absolute;left:0px;top:0px;width:20px;height:20px;overflow:hidden;border-radius:50%")
+            .with_child(NodeDesc::new("width:20px;height:20px;background-color:#ff0000")),
+    );
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(19, 19), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(10, 10), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(10, 1), [255, 0, 0, 255]);
+
+    gui.assert_snapshot("clip_border_radius", 0);
+}
+
+#[test]
+fn test_many_clip() {
+    let mut scene = SceneDesc::new(40, 10);
+    // 超过32个同时存在的裁剪区域
+    for i in 0..40 {
+        scene.nodes.push(
+            NodeDesc::new(&format!("position:absolute;left:{}px;top:0px;width:1px;height:5px;overflow:hidden", i))
+                .with_child(NodeDesc::new("width:10px;height:10px;background-color:#ff0000")),
+        );
+    }
+    let mut gui = SnapshotGui::new(scene);
+
+    gui.render();
+    for x in 0..40 {
+        assert_eq!(gui.pixel(x, 2), [255, 0, 0, 255]);
+        assert_eq!(gui.pixel(x, 7), [255, 255, 255, 255]);
+    }
+}
+
+// 旋转45度的裁剪区域， 8x8的正方形， 中心在(x, y)， 子节点铺满裁剪区域的包围盒
+#[cfg(test)]
+fn rotated_clip_node(x: u32, y: u32) -> NodeDesc {
+    NodeDesc::new(&format!(
+        "position:absolute;left:{}px;top:{}px;width:8px;height:8px;overflow:hidden;transform:rotate(45deg)",
+        x - 4,
+        y - 4
+    ))
+    .with_child(NodeDesc::new(
+        "position:absolute;left:-6px;top:-6px;width:20px;height:20px;background-color:#ff0000",
+    ))
+}
+
+#[test]
+fn test_many_rotated_clip() {
+    // 超过裁剪纹理位数的旋转裁剪区域， 有无模板缓冲区都按旋转后的形状裁剪， 而不是按包围盒
+    for stencil in [true, false].iter() {
+        // 互不相交的裁剪区域， 在裁剪纹理中共用位
+        let mut scene = SceneDesc::new(200, 80);
+        scene.stencil = *stencil;
+        for i in 0..40 {
+            scene.nodes.push(rotated_clip_node(i % 10 * 20 + 10, i / 10 * 20 + 10));
+        }
+        let mut gui = SnapshotGui::new(scene);
+        gui.render();
+        {
+            let overflow_clip = gui.gui.overflow_clip.lend_mut();
+            assert_eq!(overflow_clip.clip.iter().filter(|(_, c)| c.texture_index > 0).count(), 40);
+        }
+        for i in 0..40 {
+            let (x, y) = (i % 10 * 20 + 10, i / 10 * 20 + 10);
+            assert_eq!(gui.pixel(x, y), [255, 0, 0, 255]);
+            // 在旋转前的正方形及旋转后的包围盒内， 但在旋转后的形状外
+            assert_eq!(gui.pixel(x - 4, y - 4), [255, 255, 255, 255]);
+            assert_eq!(gui.pixel(x + 3, y + 3), [255, 255, 255, 255]);
+        }
+    }
+
+    // 相互重叠的裁剪区域超过裁剪纹理的位数， 多出的裁剪区域使用模板缓冲区
+    let mut scene = SceneDesc::new(20, 20);
+    for _ in 0..40 {
+        scene.nodes.push(rotated_clip_node(10, 10));
+    }
+    let mut gui = SnapshotGui::new(scene);
+    gui.render();
+    {
+        let overflow_clip = gui.gui.overflow_clip.lend_mut();
+        let count = overflow_clip.clip.iter().filter(|(_, c)| overflow_clip.use_stencil(c)).count();
+        assert_eq!(count, 40 - CLIP_TEXTURE_BITS);
+    }
+    assert_eq!(gui.pixel(10, 10), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(6, 6), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(13, 13), [255, 255, 255, 255]);
+}
+
+#[test]
+fn test_stencil_clip() {
+    let mut scene = SceneDesc::new(20, 20);
+    // 圆形的裁剪容器中嵌套一个矩形裁剪容器
+    scene.nodes.push(
+        NodeDesc::new("position:absolute;left:0px;top:0px;width:20px;height:20px;overflow:hidden;border-radius:50%")
+            .with_child(
+                NodeDesc::new("width:20px;height:10px;overflow:hidden")
+                    .with_child(NodeDesc::new("width:20px;height:20px;background-color:#ff0000")),
+            ),
+    );
+    let mut gui = SnapshotGui::new(scene);
+    gui.gui.overflow_clip.lend_mut().mode = ClipMode::Stencil;
+
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(10, 1), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(10, 8), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(10, 15), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(19, 19), [255, 255, 255, 255]);
+}
+
+#[test]
+fn test_stencil_clip_fallback() {
+    // 没有模板缓冲区时， 模板模式退回到裁剪纹理， 仍然按圆形裁剪
+    let mut scene = SceneDesc::new(20, 20);
+    scene.stencil = false;
+    scene.nodes.push(
+        NodeDesc::new("position:absolute;left:0px;top:0px;width:20px;height:20px;overflow:hidden;border-radius:50%")
+            .with_child(NodeDesc::new("width:20px;height:20px;background-color:#ff0000")),
+    );
+    let mut gui = SnapshotGui::new(scene);
+    {
+        let overflow_clip = gui.gui.overflow_clip.lend_mut();
+        assert!(!overflow_clip.stencil);
+        overflow_clip.mode = ClipMode::Stencil;
+    }
+
+    gui.render();
+    {
+        let overflow_clip = gui.gui.overflow_clip.lend_mut();
+        let (_, c) = overflow_clip.clip.iter().next().unwrap();
+        assert!(c.texture_index > 0);
+        assert!(!overflow_clip.use_stencil(c));
+    }
+    assert_eq!(gui.pixel(0, 0), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(19, 19), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(10, 10), [255, 0, 0, 255]);
+}
+
+#[test]
+fn test_clip_path_class() {
+    // 样式表中设置的clip-path， 凹多边形（L形）， 移除class后不再裁剪
+    let mut scene = SceneDesc::new(20, 20);
+    scene.css = ".1{clip-path:polygon(0 0, 100% 0, 100% 50%, 50% 50%, 50% 100%, 0 100%)}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let node = gui.append(
+        &NodeDesc::new("position:absolute;left:0px;top:0px;width:20px;height:20px;overflow:hidden")
+            .with_class(&[1])
+            .with_child(NodeDesc::new("width:20px;height:20px;background-color:#ff0000")),
+        root,
+    );
+    assert!(gui.gui.clip_path.lend_mut().get(node).is_some());
+
+    gui.render();
+    assert_eq!(gui.pixel(5, 5), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(15, 5), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(5, 15), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(15, 15), [255, 255, 255, 255]);
+
+    let inline = gui.gui.class_name.lend_mut().get(node).unwrap().two;
+    gui.set_class(node, &[inline]);
+    assert!(gui.gui.clip_path.lend_mut().get(node).is_none());
+    gui.render();
+    assert_eq!(gui.pixel(15, 15), [255, 0, 0, 255]);
+}
@@ -194,13 +194,9 @@ impl<'a, C: HalContext + 'static> Runner<'a> for ImageSys<C> {
             // 世界矩阵脏， 设置世界矩阵ubo
             if dirty & StyleType::Matrix as usize != 0 {
                 let (pos, _uv) = get_pos_uv(image, image_clip, object_fit, layout);
-                let radius = cal_border_radius(border_radius, layout);
-                let mut has_radius = false;
+                let radius = cal_border_radius(border_radius, layout).inner(layout);
                 let g_b = geo_box(layout);
-
-                if radius.x > g_b.min.x && pos.min.x < radius.x && pos.min.y < radius.x {
-                    has_radius = true;
-				}
+                let has_radius = is_overlap_radius(&pos, &g_b, &radius);
                 modify_matrix(
                     render_obj,
                     layout,
@@ -332,14 +328,14 @@ fn update_geo<C: HalContext + 'static>(
     unit_geo: &Share<GeometryRes>,
 ) -> (bool, Aabb2) {
     let (pos, uv) = get_pos_uv(image, image_clip, object_fit, layout);
-    let radius = cal_border_radius(border_radius, layout);
+    let radius = cal_border_radius(border_radius, layout).inner(layout);
     let g_b = geo_box(layout);
     let flip_y = match image.width {
         Some(_) => true,
         None => false,
     };
     //flip_y为true时，暂时不支持圆角
-    if !flip_y && radius.x > g_b.min.x && pos.min.x < radius.x && pos.min.y < radius.x {
+    if !flip_y && is_overlap_radius(&pos, &g_b, &radius) {
         use_layout_pos(render_obj, uv, layout, &radius, engine); // 有圆角
         (true, pos)
     } else {
@@ -348,6 +344,16 @@ fn update_geo<C: HalContext + 'static>(
     }
 }
 
+// 图片区域是否与某个圆角的区域相交（相交时需要按圆角裁剪图片）
+#[inline]
+fn is_overlap_radius(pos: &Aabb2, g_b: &Aabb2, radius: &BorderRadiusPixel) -> bool {
+    let [tl, tr, br, bl] = radius.0;
+    (tl.0 > 0.0 && tl.1 > 0.0 && pos.min.x < g_b.min.x + tl.0 && pos.min.y < g_b.min.y + tl.1)
+        || (tr.0 > 0.0 && tr.1 > 0.0 && pos.max.x > g_b.max.x - tr.0 && pos.min.y < g_b.min.y + tr.1)
+        || (br.0 > 0.0 && br.1 > 0.0 && pos.max.x > g_b.max.x - br.0 && pos.max.y > g_b.max.y - br.1)
+        || (bl.0 > 0.0 && bl.1 > 0.0 && pos.min.x < g_b.min.x + bl.0 && pos.max.y > g_b.max.y - bl.1)
+}
+
 fn update_geo_quad<C: HalContext + 'static>(
     render_obj: &mut RenderObj,
     uv: &Aabb2,
@@ -496,7 +502,7 @@ fn use_layout_pos<C: HalContext + 'static>(
     render_obj: &mut RenderObj,
     uv: Aabb2,
     layout: &LayoutR,
-    radius: &Point2,
+    radius: &BorderRadiusPixel,
     engine: &mut Engine<C>,
 ) {
 	let width = layout.rect.end - layout.rect.start;
@@ -505,7 +511,7 @@ fn use_layout_pos<C: HalContext + 'static>(
     let start_y = layout.border.top;
     let end_x = width - layout.border.end;
     let end_y = height - layout.border.bottom;
-    let (positions, indices) = if radius.x == 0.0 || width == 0.0 || height == 0.0 {
+    let (positions, indices) = if radius.is_zero() || width == 0.0 || height == 0.0 {
         (
             vec![
                 start_x, start_y, start_x, end_y, end_x, end_y, end_x, start_y,
@@ -513,12 +519,12 @@ fn use_layout_pos<C: HalContext + 'static>(
             vec![0, 1, 2, 3],
         )
     } else {
-        split_by_radius(
+        split_by_border_radius(
             start_x,
             start_y,
             end_x - start_x,
             end_y - start_y,
-            radius.x - start_x,
+            radius,
             None,
         )
     };
@@ -583,7 +589,7 @@ fn use_layout_pos<C: HalContext + 'static>(
 }
 
 // 获得图片的4个点(逆时针)的坐标和uv的Aabb
-fn get_pos_uv(
+pub fn get_pos_uv(
     img: &Image,
     clip: Option<&ImageClip>,
     fit: Option<&ObjectFit>,
@@ -8,6 +8,9 @@ mod render;
 mod image;
 mod charblock;
 mod clip;
+mod native_text;
+mod scroll_bar;
+mod caret;
 pub mod res_release;
 
 
@@ -20,4 +23,7 @@ pub use system::render::image::*;
 pub use system::render::node_attr::*;
 pub use system::render::render::*;
 pub use system::render::box_shadow::*;
-pub use system::render::res_release::*;
\ No newline at end of file
+pub use system::render::res_release::*;
+pub use system::render::native_text::*;
+pub use system::render::scroll_bar::*;
+pub use system::render::caret::*;
\ No newline at end of file
@@ -0,0 +1,124 @@
+/**
+ *  原生字体绘制， 将等待队列中使用原生字体的字符直接光栅化， 并更新到字体纹理中， 不依赖canvas
+ *  同时为新增的字体纹理页创建纹理
+ */
+use std::marker::PhantomData;
+
+use atom::Atom;
+use ecs::{Runner, SingleCaseImpl, StdCell};
+use share::Share;
+
+use font::font_sheet::FontSheet;
+use hal_core::*;
+use render::engine::{Engine, ShareEngine};
+use render::res::{Opacity, TextureRes};
+
+// 新增纹理页的初始高度
+const INIT_PAGE_HEIGHT: u32 = 32;
+
+pub struct NativeTextSys<C: HalContext + 'static> {
+    marker: PhantomData<C>,
+}
+
+impl<C: HalContext + 'static> NativeTextSys<C> {
+    pub fn new() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static> Runner<'a> for NativeTextSys<C> {
+    type ReadData = ();
+    type WriteData = (
+        &'a mut SingleCaseImpl<Share<StdCell<FontSheet>>>,
+        &'a mut SingleCaseImpl<ShareEngine<C>>,
+    );
+    fn run(&mut self, _: Self::ReadData, write: Self::WriteData) {
+        let (single_font_sheet, engine) = write;
+        let font_sheet = &mut single_font_sheet.borrow_mut();
+        if create_page_texture(font_sheet, engine) {
+            font_sheet.tex_version += 1;
+        }
+        let images = font_sheet.draw_native_text();
+        if images.len() == 0 {
+            return;
+        }
+        font_sheet.tex_version += 1;
+
+        for page in 0..font_sheet.font_tex.pages.len() {
+            let texture = match font_sheet.get_page_tex(page) {
+                Some(r) => r,
+                None => continue,
+            };
+            // 扩展纹理
+            let end_v = images
+                .iter()
+                .filter(|image| image.page == page)
+                .fold(0, |r, image| r.max(image.y + image.height));
+            if end_v > texture.height as u32 {
+                let end_v = end_v.next_power_of_two();
+                engine
+                    .gl
+                    .texture_extend(&texture.bind, texture.width as u32, end_v);
+                texture.update_size(texture.width, end_v as usize);
+                single_font_sheet.get_notify_ref().modify_event(0, "", 0);
+            }
+
+            for image in images.iter().filter(|image| image.page == page) {
+                engine.gl.texture_update(
+                    &texture.bind,
+                    0,
+                    &TextureData::U8(image.x, image.y, image.width, image.height, &image.data),
+                );
+            }
+        }
+    }
+}
+
+// 为新增的字体纹理页创建纹理， 宽度与第0页相同， 返回是否创建了纹理
+fn create_page_texture<C: HalContext + 'static>(font_sheet: &mut FontSheet, engine: &mut Engine<C>) -> bool {
+    let width = font_sheet.font_tex.tex_width() as u32;
+    let mut r = false;
+    for page in 1..font_sheet.font_tex.pages.len() {
+        if font_sheet.font_tex.pages[page].texture.is_some() {
+            continue;
+        }
+        let texture = engine
+            .gl
+            .texture_create_2d(
+                0,
+                width,
+                INIT_PAGE_HEIGHT,
+                PixelFormat::RGBA,
+                DataFormat::UnsignedByte,
+                false,
+                None,
+            )
+            .unwrap();
+        let res = engine.create_texture_res(
+            Atom::from(format!("__$text{}", page)).get_hash(),
+            TextureRes::new(
+                width as usize,
+                INIT_PAGE_HEIGHT as usize,
+                PixelFormat::RGBA,
+                DataFormat::UnsignedByte,
+                Opacity::Translucent,
+                None,
+                texture,
+            ),
+            0,
+        );
+        font_sheet.font_tex.pages[page].texture = Some(res);
+        r = true;
+    }
+    r
+}
+
+impl_system! {
+    NativeTextSys<C> where [C: HalContext + 'static],
+    true,
+    {
+
+    }
+}
@@ -1,5 +1,6 @@
 /**
  *  渲染， 将渲染对象按照透明与不透明分类， 先渲染不透明物体， 再渲染透明物体， 不透明物体按照渲染管线的顺序渲染， 透明物体按照物体的深度顺序渲染
+ *  渲染对象所在的裁剪链上有使用模板缓冲区裁剪的裁剪区域时， 渲染该对象前先将这些裁剪区域写入模板缓冲区， 再开启模板测试渲染该对象
  */
 use std::cmp::Ordering;
 use std::default::Default;
@@ -9,12 +10,16 @@ use ecs::{
     CreateEvent, DeleteEvent, ModifyEvent, Runner, SingleCaseImpl, SingleCaseListener, MultiCaseImpl,
 };
 use hal_core::*;
+use hash::XHashMap;
+use share::Share;
 use component::user::{Vector4, Aabb3, Point3};
-use component::calc::WorldMatrix;
+use component::calc::{WorldMatrix, ByOverflow, ClipParamter, ViewMatrixUbo, ProjectMatrixUbo};
 use entity::Node;
 
-use render::engine::ShareEngine;
-use single::{RenderBegin, RenderObj, RenderObjs, Statistics, ProjectionMatrix, DirtyViewRect, Oct};
+use render::engine::{Engine, ShareEngine};
+use render::res::*;
+use single::{RenderBegin, RenderObj, RenderObjs, Statistics, ProjectionMatrix, ViewMatrix, DirtyViewRect, Oct, OverflowClip};
+use system::render::shaders::clip::*;
 
 pub struct RenderSys<C: HalContext + 'static> {
     program_dirtys: Vec<usize>,
@@ -23,9 +28,238 @@ pub struct RenderSys<C: HalContext + 'static> {
     pub dirty: bool,
     opacity_list: Vec<usize>,
     transparent_list: Vec<usize>,
+    stencil: StencilClip,
     marker: PhantomData<C>,
 }
 
+// 模板裁剪
+// 将渲染对象所在裁剪链上使用模板缓冲区裁剪的裁剪区域由外到内依次写入模板缓冲区（第n个区域只在模板值为n的像素上加1），
+// 然后以模板值等于区域数量为条件渲染该对象， 嵌套及旋转的裁剪区域都是像素精确的
+// 相邻渲染对象的裁剪链相同时不需要重新写入， 裁剪链改变时， 先将上一条裁剪链的区域清零
+struct StencilClip {
+    enable: bool,
+    pre_by_overflow: usize,
+    chain: Vec<usize>, // 当前写入模板缓冲区的裁剪区域（由外到内）
+    temp: Vec<usize>,
+    geos: XHashMap<usize, Share<GeometryRes>>, // 裁剪区域的几何体
+    states: Vec<(Share<StencilStateRes>, Share<StencilStateRes>)>, // 每一层的(写入状态, 测试状态)
+    render: Option<StencilClipRender>,
+}
+
+struct StencilClipRender {
+    program: Share<HalProgram>,
+    paramter: Share<dyn ProgramParamter>,
+    rs: Share<RasterStateRes>,
+    bs: Share<BlendStateRes>,
+    ds: Share<DepthStateRes>,
+    erase_ss: Share<StencilStateRes>,
+}
+
+impl StencilClip {
+    fn new() -> Self {
+        Self {
+            enable: false,
+            pre_by_overflow: 0,
+            chain: Vec::new(),
+            temp: Vec::new(),
+            geos: XHashMap::default(),
+            states: Vec::new(),
+            render: None,
+        }
+    }
+
+    fn init_render<C: HalContext + 'static>(
+        &mut self,
+        engine: &mut Engine<C>,
+        view_matrix: &ViewMatrix,
+        projection_matrix: &ProjectionMatrix,
+    ) {
+        let (rs, mut bs, mut ss, mut ds) = (
+            RasterStateDesc::default(),
+            BlendStateDesc::default(),
+            StencilStateDesc::default(),
+            DepthStateDesc::default(),
+        );
+        // 只写模板， 不改变颜色和深度
+        bs.set_rgb_factor(BlendFactor::Zero, BlendFactor::One);
+        bs.set_alpha_factor(BlendFactor::Zero, BlendFactor::One);
+        ds.set_test_enable(false);
+        ds.set_write_enable(false);
+        ss.set_enable(true);
+        ss.set_func(CompareFunc::Always, 0, 0xff);
+        ss.set_op(StencilOp::Zero, StencilOp::Zero, StencilOp::Zero);
+
+        let paramter = ClipParamter::default();
+        paramter.set_single_uniform("meshNum", UniformValue::Float1(1.0));
+        let slice: &[f32; 16] = view_matrix.0.as_ref();
+        paramter.set_value("viewMatrix", Share::new(ViewMatrixUbo::new(UniformValue::MatrixV4(Vec::from(&slice[..]))))); // VIEW_MATRIX
+        let slice: &[f32; 16] = projection_matrix.0.as_ref();
+        paramter.set_value("projectMatrix", Share::new(ProjectMatrixUbo::new(UniformValue::MatrixV4(Vec::from(&slice[..]))))); // PROJECT_MATRIX
+
+        let program = engine.create_program(
+            CLIP_VS_SHADER_NAME.get_hash() as u64,
+            CLIP_FS_SHADER_NAME.get_hash() as u64,
+            CLIP_VS_SHADER_NAME.as_ref(),
+            &VsDefines::default(),
+            CLIP_FS_SHADER_NAME.as_ref(),
+            &FsDefines::default(),
+            &paramter,
+        );
+
+        self.render = Some(StencilClipRender {
+            program: program,
+            paramter: Share::new(paramter),
+            rs: engine.create_rs_res(rs),
+            bs: engine.create_bs_res(bs),
+            ds: engine.create_ds_res(ds),
+            erase_ss: engine.create_ss_res(ss),
+        });
+    }
+
+    // 准备本帧需要的模板状态和裁剪区域的几何体
+    fn prepare<C: HalContext + 'static>(
+        &mut self,
+        engine: &mut Engine<C>,
+        overflow_clip: &OverflowClip,
+        view_matrix: &ViewMatrix,
+        projection_matrix: &ProjectionMatrix,
+    ) {
+        self.pre_by_overflow = 0;
+        self.chain.clear();
+
+        let mut count = 0;
+        for (i, c) in overflow_clip.clip.iter() {
+            if !overflow_clip.use_stencil(c) {
+                continue;
+            }
+            count += 1;
+            if self.geos.contains_key(&i) {
+                continue;
+            }
+            let (positions, indices) = match &c.shape {
+                Some((p, tri)) => (p.clone(), tri.clone()),
+                None => {
+                    let p = &c.view;
+                    (vec![p[0].x, p[0].y, p[1].x, p[1].y, p[2].x, p[2].y, p[3].x, p[3].y], vec![0, 1, 2, 0, 2, 3])
+                }
+            };
+            let skins = vec![0.0; positions.len() / 2];
+            let geo = engine.create_geo_res(
+                0,
+                indices.as_slice(),
+                &[
+                    AttributeDecs::new(AttributeName::Position, positions.as_slice(), 2),
+                    AttributeDecs::new(AttributeName::SkinIndex, skins.as_slice(), 1),
+                ],
+            );
+            self.geos.insert(i, geo);
+        }
+
+        self.enable = count > 0;
+        if !self.enable {
+            return;
+        }
+        if self.render.is_none() {
+            self.init_render(engine, view_matrix, projection_matrix);
+        }
+        // 模板值只有8位
+        let count = count.min(255);
+        while self.states.len() < count {
+            let level = self.states.len() as i32;
+            let mut write = StencilStateDesc::default();
+            write.set_enable(true);
+            write.set_func(CompareFunc::Equal, level, 0xff);
+            write.set_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Incr);
+            let mut test = StencilStateDesc::default();
+            test.set_enable(true);
+            test.set_func(CompareFunc::Equal, level + 1, 0xff);
+            test.set_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+            self.states.push((engine.create_ss_res(write), engine.create_ss_res(test)));
+        }
+    }
+
+    fn render<C: HalContext + 'static>(
+        &mut self,
+        gl: &C,
+        obj: &RenderObj,
+        statistics: &mut Statistics,
+        by_overflows: &MultiCaseImpl<Node, ByOverflow>,
+        overflow_clip: &OverflowClip,
+    ) {
+        if !self.enable {
+            return render(gl, obj, statistics);
+        }
+        let geometry = match &obj.geometry {
+            None => return,
+            Some(g) => g,
+        };
+
+        let by_overflow = by_overflows[obj.context].0;
+        if by_overflow != self.pre_by_overflow {
+            self.pre_by_overflow = by_overflow;
+            self.temp.clear();
+            let mut i = by_overflow;
+            while i > 0 {
+                let c = &overflow_clip.clip[i];
+                if overflow_clip.use_stencil(c) && self.geos.contains_key(&i) {
+                    self.temp.push(i);
+                }
+                i = c.parent;
+            }
+            self.temp.reverse();
+            self.temp.truncate(self.states.len());
+            if self.temp != self.chain {
+                self.erase(gl, statistics);
+                std::mem::swap(&mut self.temp, &mut self.chain);
+                self.write(gl, statistics);
+            }
+        }
+
+        if self.chain.len() == 0 {
+            return render(gl, obj, statistics);
+        }
+        statistics.drawcall_times += 1;
+        gl.render_set_program(obj.program.as_ref().unwrap());
+        gl.render_set_state(&obj.state.bs, &obj.state.ds, &obj.state.rs, &self.states[self.chain.len() - 1].1);
+        gl.render_draw(&geometry.geo, &obj.paramter);
+    }
+
+    // 将当前裁剪链写入模板缓冲区
+    fn write<C: HalContext + 'static>(&self, gl: &C, statistics: &mut Statistics) {
+        let r = match &self.render {
+            Some(r) => r,
+            None => return,
+        };
+        for (level, id) in self.chain.iter().enumerate() {
+            statistics.drawcall_times += 1;
+            gl.render_set_program(&r.program);
+            gl.render_set_state(&r.bs, &r.ds, &r.rs, &self.states[level].0);
+            gl.render_draw(&self.geos[id].geo, &r.paramter);
+        }
+    }
+
+    // 将当前裁剪链覆盖的区域的模板值清零
+    fn erase<C: HalContext + 'static>(&self, gl: &C, statistics: &mut Statistics) {
+        let r = match &self.render {
+            Some(r) => r,
+            None => return,
+        };
+        for id in self.chain.iter() {
+            statistics.drawcall_times += 1;
+            gl.render_set_program(&r.program);
+            gl.render_set_state(&r.bs, &r.ds, &r.rs, &r.erase_ss);
+            gl.render_draw(&self.geos[id].geo, &r.paramter);
+        }
+    }
+
+    // 一帧结束时清除模板缓冲区， 保证下一帧从0开始
+    fn end<C: HalContext + 'static>(&mut self, gl: &C, statistics: &mut Statistics) {
+        self.erase(gl, statistics);
+        self.chain.clear();
+        self.pre_by_overflow = 0;
+    }
+}
+
 impl<C: HalContext + 'static> Default for RenderSys<C> {
     fn default() -> Self {
         Self {
@@ -35,6 +269,7 @@ impl<C: HalContext + 'static> Default for RenderSys<C> {
             dirty: false,
             opacity_list: Vec::new(),
             transparent_list: Vec::new(),
+            stencil: StencilClip::new(),
             // transparent_list: BTreeMap::new(),
             marker: PhantomData,
         }
@@ -55,6 +290,9 @@ impl<'a, C: HalContext + 'static> Runner<'a> for RenderSys<C> {
 		&'a SingleCaseImpl<ProjectionMatrix>,
 		&'a SingleCaseImpl<Oct>,
 		&'a MultiCaseImpl<Node, WorldMatrix>,
+		&'a MultiCaseImpl<Node, ByOverflow>,
+		&'a SingleCaseImpl<OverflowClip>,
+		&'a SingleCaseImpl<ViewMatrix>,
 	);
     type WriteData = (
 		&'a mut SingleCaseImpl<RenderObjs>,
@@ -65,7 +303,7 @@ impl<'a, C: HalContext + 'static> Runner<'a> for RenderSys<C> {
 	);
 	
     fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
-		let (projection_matrix, octree, world_matrixs) = read;
+		let (projection_matrix, octree, world_matrixs, by_overflows, overflow_clip, view_matrix) = read;
         let (render_objs, engine, statistics, dirty_view_rect, render_begin) = write;
 
         for id in self.program_dirtys.iter() {
@@ -191,6 +429,8 @@ impl<'a, C: HalContext + 'static> Runner<'a> for RenderSys<C> {
         // js! {
         //     __time = performance.now();
         // }
+        self.stencil.prepare(engine, overflow_clip, view_matrix, projection_matrix);
+
         let target = match &render_begin.1 {
             Some(r) => Some(&**r),
             None => None,
@@ -208,11 +448,11 @@ impl<'a, C: HalContext + 'static> Runner<'a> for RenderSys<C> {
 			gl.render_begin(target, &render_begin_desc);
 			for id in self.opacity_list.iter() {
 				let obj = &render_objs[*id];
-				render(gl, obj, statistics);
+				self.stencil.render(gl, obj, statistics, by_overflows, overflow_clip);
 			}
 			for id in self.transparent_list.iter() {
 				let obj = &render_objs[*id];
-				render(gl, obj, statistics);
+				self.stencil.render(gl, obj, statistics, by_overflows, overflow_clip);
 			}
 		} else {
 			// let root_matrix = &world_matrixs[1];
@@ -257,17 +497,18 @@ impl<'a, C: HalContext + 'static> Runner<'a> for RenderSys<C> {
 				let obj = &render_objs[*id];
 				// 如果相交才渲染
 				if is_intersect(&viewPortAabb, &unsafe { octree.get_unchecked(obj.context) }.0) {
-					render(gl, obj, statistics);
+					self.stencil.render(gl, obj, statistics, by_overflows, overflow_clip);
 				}
 			}
 			for id in self.transparent_list.iter() {
 				let obj = &render_objs[*id];
 				if is_intersect(&viewPortAabb, &unsafe { octree.get_unchecked(obj.context) }.0) {
-					render(gl, obj, statistics);
+					self.stencil.render(gl, obj, statistics, by_overflows, overflow_clip);
 				}
 			}
 		}
 
+		self.stencil.end(gl, statistics);
 		gl.render_end();
 		
 		dirty_view_rect.0 = viewport.2 as f32;
@@ -340,6 +581,28 @@ impl<'a, C: HalContext + 'static> SingleCaseListener<'a, RenderObjs, ModifyEvent
     }
 }
 
+impl<'a, C: HalContext + 'static> SingleCaseListener<'a, OverflowClip, ModifyEvent> for RenderSys<C> {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &ModifyEvent, _: Self::ReadData, _: Self::WriteData) {
+        // 裁剪区域改变， 重新创建其模板几何体
+        self.stencil.geos.remove(&event.id);
+        self.dirty = true;
+    }
+}
+
+impl<'a, C: HalContext + 'static> SingleCaseListener<'a, ProjectionMatrix, ModifyEvent> for RenderSys<C> {
+    type ReadData = &'a SingleCaseImpl<ProjectionMatrix>;
+    type WriteData = ();
+    fn listen(&mut self, _event: &ModifyEvent, projection_matrix: Self::ReadData, _: Self::WriteData) {
+        if let Some(r) = &self.stencil.render {
+            let slice: &[f32; 16] = projection_matrix.0.as_ref();
+            let project_matrix_ubo = ProjectMatrixUbo::new(UniformValue::MatrixV4(Vec::from(&slice[..])));
+            r.paramter.set_value("projectMatrix", Share::new(project_matrix_ubo)); // PROJECT_MATRIX
+        }
+    }
+}
+
 impl<'a, C: HalContext + 'static> SingleCaseListener<'a, RenderObjs, DeleteEvent> for RenderSys<C> {
     type ReadData = &'a SingleCaseImpl<RenderObjs>;
     type WriteData = ();
@@ -431,5 +694,7 @@ impl_system! {
         SingleCaseListener<RenderObjs, CreateEvent>
         SingleCaseListener<RenderObjs, ModifyEvent>
         SingleCaseListener<RenderObjs, DeleteEvent>
+        SingleCaseListener<OverflowClip, ModifyEvent>
+        SingleCaseListener<ProjectionMatrix, ModifyEvent>
     }
 }
@@ -0,0 +1,304 @@
+/**
+ * 滚动条渲染对象的构建及其属性设置
+ * 每个设置了ScrollBar的滚动容器一个渲染对象， 包含垂直和水平两个滑块（圆角矩形）， 绘制在容器所有子孙节点之上
+	*/
+use component::calc::*;
+use component::user::*;
+use ecs::{DeleteEvent, ModifyEvent, CreateEvent, MultiCaseImpl, MultiCaseListener, Runner, SingleCaseImpl};
+use ecs::monitor::NotifyImpl;
+use entity::Node;
+use hal_core::*;
+use map::vecmap::VecMap;
+use map::Map;
+use render::engine::{AttributeDecs, Engine, ShareEngine};
+use render::res::GeometryRes;
+use share::Share;
+use single::*;
+use std::marker::PhantomData;
+use system::render::shaders::color::{COLOR_FS_SHADER_NAME, COLOR_VS_SHADER_NAME};
+use system::util::*;
+
+const DITY_TYPE: usize = StyleType::Matrix as usize | StyleType::Layout as usize;
+const MARGIN: f32 = 2.0; // 滑块与容器边缘的距离
+const MIN_LENGTH: f32 = 16.0; // 滑块的最小长度
+
+pub struct ScrollBarSys<C: HalContext + 'static> {
+    render_map: VecMap<usize>,
+    default_paramter: ColorParamter,
+    marker: std::marker::PhantomData<C>,
+}
+
+impl<C: HalContext + 'static> ScrollBarSys<C> {
+	pub fn with_capacity(capacity: usize) -> Self {
+		ScrollBarSys {
+			render_map: VecMap::with_capacity(capacity),
+			default_paramter: ColorParamter::default(),
+			marker: std::marker::PhantomData,
+		}
+	}
+}
+
+impl<C: HalContext + 'static> Default for ScrollBarSys<C> {
+    fn default() -> Self {
+        Self {
+            render_map: VecMap::default(),
+            default_paramter: ColorParamter::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static> Runner<'a> for ScrollBarSys<C> {
+    type ReadData = (
+        &'a MultiCaseImpl<Node, ScrollBar>,
+        &'a MultiCaseImpl<Node, ScrollOffset>,
+        &'a MultiCaseImpl<Node, WorldMatrix>,
+        &'a MultiCaseImpl<Node, LayoutR>,
+        &'a MultiCaseImpl<Node, ZDepth>,
+        &'a MultiCaseImpl<Node, Transform>,
+        &'a MultiCaseImpl<Node, StyleMark>,
+        &'a SingleCaseImpl<IdTree>,
+        &'a SingleCaseImpl<DefaultTable>,
+        &'a SingleCaseImpl<DirtyList>,
+        &'a SingleCaseImpl<DefaultState>,
+    );
+
+    type WriteData = (
+        &'a mut SingleCaseImpl<ScrollStates>,
+        &'a mut SingleCaseImpl<RenderObjs>,
+        &'a mut SingleCaseImpl<ShareEngine<C>>,
+    );
+
+    fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
+        let (
+            scroll_bars,
+            offsets,
+            world_matrixs,
+            layouts,
+            z_depths,
+            transforms,
+            style_marks,
+            idtree,
+            default_table,
+            dirty_list,
+            default_state,
+        ) = read;
+		let (scroll_states, render_objs, engine) = write;
+		let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
+        let default_transform = default_table.get::<Transform>().unwrap();
+
+        // 节点被删除， 或不再是滚动容器， 删除渲染对象
+        for id in dirty_list.0.iter() {
+            if self.render_map.get(*id).is_some()
+                && (style_marks.get(*id).is_none() || !scroll_states.states.contains_key(id))
+            {
+                self.remove_render_obj(*id, render_objs);
+            }
+        }
+
+        for (id, state) in scroll_states.states.iter_mut() {
+            let id = *id;
+            let bar_dirty = state.bar_dirty;
+            state.bar_dirty = false;
+            let bar = match scroll_bars.get(id) {
+                Some(r) => r,
+                None => continue,
+            };
+            let dirty = match style_marks.get(id) {
+                Some(r) => r.dirty,
+                None => continue,
+            };
+            if !bar_dirty && dirty & DITY_TYPE == 0 && self.render_map.get(id).is_some() {
+                continue;
+            }
+
+            let render_index = match self.render_map.get(id) {
+                Some(r) => *r,
+                None => self.create_render_obj(id, render_objs, default_state),
+            };
+            let render_obj = &mut render_objs[render_index];
+            let layout = &layouts[id];
+            let offset = offsets.get(id).cloned().unwrap_or_default();
+
+            render_obj
+                .paramter
+                .as_ref()
+                .set_value("uColor", engine.create_u_color_ubo(&bar.color));
+            render_obj.geometry = create_bar_geo(engine, layout, state, &offset, bar);
+
+            // 绘制在所有子孙节点之上
+            let depth = z_depths[id].0;
+            let mut max_depth = depth;
+            if let Some(node) = idtree.get(id) {
+                for (child, _) in idtree.recursive_iter(node.children().head) {
+                    if let Some(r) = z_depths.get(child) {
+                        max_depth = max_depth.max(r.0);
+                    }
+                }
+            }
+            render_obj.depth_diff = max_depth - depth + 0.1;
+            render_obj.depth = depth + render_obj.depth_diff;
+
+            if dirty & DITY_TYPE != 0 || bar_dirty {
+                let world_matrix = &world_matrixs[id];
+                let transform = match transforms.get(id) {
+                    Some(r) => r,
+                    None => default_transform,
+                };
+                let arr = create_let_top_offset_matrix(layout, world_matrix, transform, 0.0, 0.0, depth);
+                render_obj.paramter.set_value(
+                    "worldMatrix",
+                    Share::new(WorldMatrixUbo::new(UniformValue::MatrixV4(arr))),
+                );
+            }
+            notify.modify_event(render_index, "", 0);
+        }
+    }
+}
+
+// 滚动条改变， 重新计算滑块
+impl<'a, C: HalContext + 'static> MultiCaseListener<'a, Node, ScrollBar, CreateEvent>
+    for ScrollBarSys<C>
+{
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<ScrollStates>;
+    fn listen(&mut self, event: &CreateEvent, _: Self::ReadData, scroll_states: Self::WriteData) {
+        if let Some(r) = scroll_states.states.get_mut(&event.id) {
+            r.bar_dirty = true;
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static> MultiCaseListener<'a, Node, ScrollBar, ModifyEvent>
+    for ScrollBarSys<C>
+{
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<ScrollStates>;
+    fn listen(&mut self, event: &ModifyEvent, _: Self::ReadData, scroll_states: Self::WriteData) {
+        if let Some(r) = scroll_states.states.get_mut(&event.id) {
+            r.bar_dirty = true;
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static> MultiCaseListener<'a, Node, ScrollBar, DeleteEvent>
+    for ScrollBarSys<C>
+{
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<RenderObjs>;
+    fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, render_objs: Self::WriteData) {
+        self.remove_render_obj(event.id, render_objs)
+    }
+}
+
+impl<C: HalContext + 'static> ScrollBarSys<C> {
+    #[inline]
+    fn remove_render_obj(&mut self, id: usize, render_objs: &mut SingleCaseImpl<RenderObjs>) {
+        match self.render_map.remove(id) {
+            Some(index) => {
+                let notify = unsafe { &*(render_objs.get_notify_ref() as * const NotifyImpl) };
+                render_objs.remove(index, Some(notify));
+            }
+            None => (),
+        };
+    }
+
+    #[inline]
+    fn create_render_obj(
+        &mut self,
+        id: usize,
+        render_objs: &mut SingleCaseImpl<RenderObjs>,
+        default_state: &DefaultState,
+    ) -> usize {
+        let index = create_render_obj(
+            id,
+            0.1,
+            false,
+            COLOR_VS_SHADER_NAME.clone(),
+            COLOR_FS_SHADER_NAME.clone(),
+            Share::new(self.default_paramter.clone()),
+            default_state,
+            render_objs,
+            &mut self.render_map,
+        );
+        let render_obj = &mut render_objs[index];
+        render_obj.fs_defines.add("UCOLOR");
+        render_obj.state.ds = default_state.tarns_ds.clone();
+        index
+    }
+}
+
+// 滑块的位置和长度， 返回(起点, 长度)
+// 超出滚动范围（回弹）时， 滑块按超出量缩短
+#[inline]
+fn thumb(track: f32, view: f32, content: f32, offset: f32) -> (f32, f32) {
+    let max = (content - view).max(0.0);
+    if max <= 0.0 {
+        return (0.0, track);
+    }
+    let over = if offset < 0.0 { -offset } else { (offset - max).max(0.0) };
+    let min = MIN_LENGTH.min(track);
+    let len = (track * view / content - over).max(min);
+    let ratio = (offset / max).max(0.0).min(1.0);
+    ((track - len) * ratio, len)
+}
+
+// 滑块的几何体， 坐标相对于容器的布局框左上角
+fn create_bar_geo<C: HalContext + 'static>(
+    engine: &mut Engine<C>,
+    layout: &LayoutR,
+    state: &ScrollState,
+    offset: &ScrollOffset,
+    bar: &ScrollBar,
+) -> Option<Share<GeometryRes>> {
+    // overflow: scroll总是显示滚动条， auto只在内容超出时显示
+    let always = state.overflow == OverflowType::Scroll;
+    let show_x = always || state.scroll_x();
+    let show_y = always || state.scroll_y();
+    let (left, top) = (layout.border.start, layout.border.top);
+    let (view_w, view_h) = (state.view_width, state.view_height);
+    let radius = BorderRadiusPixel([(bar.width / 2.0, bar.width / 2.0); 4]);
+
+    let mut positions: Vec<f32> = Vec::new();
+    let mut indices: Vec<u16> = Vec::new();
+    let mut push = |x: f32, y: f32, w: f32, h: f32| {
+        let (pts, polygon) = split_by_border_radius(x, y, w, h, &radius, Some(8));
+        let start = (positions.len() / 2) as u16;
+        positions.extend_from_slice(pts.as_slice());
+        indices.extend(fan_indices(&polygon).into_iter().map(|i| i + start));
+    };
+    // 两个滚动条都显示时， 留出右下角
+    let corner = bar.width + MARGIN;
+    if show_y {
+        let track = view_h - 2.0 * MARGIN - if show_x { corner } else { 0.0 };
+        let (pos, len) = thumb(track, view_h, state.content_height, offset.y);
+        push(left + view_w - corner, top + MARGIN + pos, bar.width, len);
+    }
+    if show_x {
+        let track = view_w - 2.0 * MARGIN - if show_y { corner } else { 0.0 };
+        let (pos, len) = thumb(track, view_w, state.content_width, offset.x);
+        push(left + MARGIN + pos, top + view_h - corner, len, bar.width);
+    }
+    if indices.len() == 0 {
+        return None;
+    }
+    Some(engine.create_geo_res(
+        0,
+        indices.as_slice(),
+        &[AttributeDecs::new(
+            AttributeName::Position,
+            positions.as_slice(),
+            2,
+        )],
+    ))
+}
+
+impl_system! {
+    ScrollBarSys<C> where [C: HalContext + 'static],
+    true,
+    {
+        MultiCaseListener<Node, ScrollBar, CreateEvent>
+        MultiCaseListener<Node, ScrollBar, ModifyEvent>
+        MultiCaseListener<Node, ScrollBar, DeleteEvent>
+    }
+}
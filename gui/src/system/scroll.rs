@@ -0,0 +1,518 @@
+/**
+ * 滚动容器
+ * ScrollSys监听Overflow， 为overflow为scroll、auto的节点创建滚动状态（见single::scroll）；
+ * 每帧测量滚动容器的可视区域和内容尺寸， 推进平滑滚动、惯性滚动及超出滚动范围后的回弹， 结果写入ScrollOffset组件
+ * 指针拖动和滚轮由dispatch_event调用scroll_pointer_event输入
+ */
+use ecs::{CreateEvent, DeleteEvent, EntityListener, Lend, LendMut, ModifyEvent, MultiCaseImpl, MultiCaseListener, Runner, SingleCaseImpl};
+use hal_core::HalContext;
+
+use component::calc::LayoutR;
+use component::user::{Overflow, ScrollOffset};
+use entity::Node;
+use single::*;
+use world::GuiWorld;
+
+const DRAG_THRESHOLD: f32 = 5.0; // 指针移动超过该距离后开始拖动， 单位： 像素
+const SMOOTH_DURATION: usize = 300; // 平滑滚动的时长， 单位： 毫秒
+const DECELERATION: f32 = 0.998; // 滚动范围内， 惯性速度每毫秒的衰减
+const OVERSHOOT_DECELERATION: f32 = 0.95; // 超出滚动范围后， 惯性速度每毫秒的衰减
+const BOUNCE: f32 = 0.985; // 回弹时， 超出量每毫秒的衰减
+const RESISTANCE: f32 = 0.5; // 超出滚动范围后拖动的阻力
+const MIN_VELOCITY: f32 = 0.02; // 低于该速度时停止惯性滚动， 单位： 像素/毫秒
+
+#[derive(Default)]
+pub struct ScrollSys;
+
+impl<'a> Runner<'a> for ScrollSys {
+    type ReadData = (
+        &'a SingleCaseImpl<SystemTime>,
+        &'a SingleCaseImpl<IdTree>,
+        &'a MultiCaseImpl<Node, LayoutR>,
+        &'a SingleCaseImpl<VirtualLists>,
+    );
+    type WriteData = (
+        &'a mut SingleCaseImpl<ScrollStates>,
+        &'a mut MultiCaseImpl<Node, ScrollOffset>,
+    );
+    fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
+        let (system_time, idtree, layouts, virtual_lists) = read;
+        let (scroll_states, offsets) = write;
+        let now = system_time.cur_time;
+        for (id, state) in scroll_states.states.iter_mut() {
+            let id = *id;
+            let layout = match layouts.get(id) {
+                Some(r) => r,
+                None => continue,
+            };
+            let node = match idtree.get(id) {
+                Some(r) if r.layer() > 0 => r,
+                _ => continue,
+            };
+            // 可视区域为内边距框， 内容为所有子节点的外包围盒加上内边距
+            let view_width = layout.rect.end - layout.rect.start - layout.border.start - layout.border.end;
+            let view_height = layout.rect.bottom - layout.rect.top - layout.border.top - layout.border.bottom;
+            let (mut content_width, mut content_height) = (0.0f32, 0.0f32);
+            for (child, _) in idtree.iter(node.children().head) {
+                if let Some(r) = layouts.get(child) {
+                    content_width = content_width.max(layout.padding.start + r.rect.end + layout.padding.end);
+                    content_height = content_height.max(layout.padding.top + r.rect.bottom + layout.padding.bottom);
+                }
+            }
+            // 虚拟列表的内容高度为所有数据项的总高度（回收的行不参与）
+            if let Some(list) = virtual_lists.0.get(&id) {
+                content_height = layout.padding.top + list.total_height() + layout.padding.bottom;
+            }
+            if view_width != state.view_width
+                || view_height != state.view_height
+                || content_width != state.content_width
+                || content_height != state.content_height
+            {
+                state.view_width = view_width;
+                state.view_height = view_height;
+                state.content_width = content_width;
+                state.content_height = content_height;
+                state.bar_dirty = true;
+            }
+
+            // 长时间未刷新时， 限制单帧推进的时间， 避免惯性滚动跳跃过大
+            let dt = if state.time == 0 { 0 } else { now.saturating_sub(state.time).min(100) };
+            state.time = now;
+
+            let old = offsets.get(id).cloned().unwrap_or_default();
+            let (mut x, mut y) = (old.x, old.y);
+            let (max_x, max_y) = (state.max_x(), state.max_y());
+            let mut finish = false;
+            if let Some(target) = state.target.as_mut() {
+                let (tx, ty) = (target.x.max(0.0).min(max_x), target.y.max(0.0).min(max_y));
+                if target.duration == 0 {
+                    x = tx;
+                    y = ty;
+                    finish = true;
+                } else {
+                    let from = *target.from.get_or_insert((x, y, now));
+                    let t = (now.saturating_sub(from.2) as f32 / target.duration as f32).min(1.0);
+                    let e = 1.0 - (1.0 - t).powi(3); // 缓出
+                    x = from.0 + (tx - from.0) * e;
+                    y = from.1 + (ty - from.1) * e;
+                    finish = t >= 1.0;
+                }
+                state.velocity = (0.0, 0.0);
+            } else if !state.dragging {
+                x = step_axis(x, &mut state.velocity.0, max_x, dt);
+                y = step_axis(y, &mut state.velocity.1, max_y, dt);
+            }
+            if finish {
+                state.target = None;
+            }
+
+            if x != old.x || y != old.y {
+                set_offset(offsets, id, x, y);
+                state.bar_dirty = true;
+            }
+        }
+    }
+}
+
+// 推进一个方向上的惯性滚动， 超出滚动范围时减速并回弹， 返回新的偏移
+fn step_axis(pos: f32, velocity: &mut f32, max: f32, dt: usize) -> f32 {
+    if dt == 0 {
+        return pos;
+    }
+    let dt = dt as f32;
+    if pos < 0.0 || pos > max {
+        let edge = pos.max(0.0).min(max);
+        let over = pos - edge;
+        if *velocity != 0.0 && velocity.signum() == over.signum() {
+            // 继续向外滚动， 快速减速
+            let pos = pos + *velocity * dt;
+            *velocity *= OVERSHOOT_DECELERATION.powf(dt);
+            if velocity.abs() < MIN_VELOCITY {
+                *velocity = 0.0;
+            }
+            return pos;
+        }
+        *velocity = 0.0;
+        let over = over * BOUNCE.powf(dt);
+        return if over.abs() < 0.5 { edge } else { edge + over };
+    }
+    if *velocity == 0.0 {
+        return pos;
+    }
+    let pos = pos + *velocity * dt;
+    *velocity *= DECELERATION.powf(dt);
+    if velocity.abs() < MIN_VELOCITY {
+        *velocity = 0.0;
+    }
+    pos
+}
+
+// 设置节点的滚动偏移， 不存在时创建
+pub(crate) fn set_offset(offsets: &mut MultiCaseImpl<Node, ScrollOffset>, id: usize, x: f32, y: f32) {
+    match offsets.get_write(id) {
+        Some(mut r) => {
+            r.modify(|offset: &mut ScrollOffset| {
+                if offset.x == x && offset.y == y {
+                    return false;
+                }
+                offset.x = x;
+                offset.y = y;
+                true
+            });
+        }
+        None => {
+            offsets.insert(id, ScrollOffset { x: x, y: y });
+        }
+    };
+}
+
+// overflow改变， 创建或删除滚动状态
+impl<'a> MultiCaseListener<'a, Node, Overflow, CreateEvent> for ScrollSys {
+    type ReadData = &'a MultiCaseImpl<Node, Overflow>;
+    type WriteData = (
+        &'a mut SingleCaseImpl<ScrollStates>,
+        &'a mut MultiCaseImpl<Node, ScrollOffset>,
+    );
+    fn listen(&mut self, event: &CreateEvent, overflows: Self::ReadData, write: Self::WriteData) {
+        modify_overflow(event.id, overflows, write.0, write.1);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Overflow, ModifyEvent> for ScrollSys {
+    type ReadData = &'a MultiCaseImpl<Node, Overflow>;
+    type WriteData = (
+        &'a mut SingleCaseImpl<ScrollStates>,
+        &'a mut MultiCaseImpl<Node, ScrollOffset>,
+    );
+    fn listen(&mut self, event: &ModifyEvent, overflows: Self::ReadData, write: Self::WriteData) {
+        modify_overflow(event.id, overflows, write.0, write.1);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Overflow, DeleteEvent> for ScrollSys {
+    type ReadData = ();
+    type WriteData = (
+        &'a mut SingleCaseImpl<ScrollStates>,
+        &'a mut MultiCaseImpl<Node, ScrollOffset>,
+    );
+    fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, write: Self::WriteData) {
+        remove_state(event.id, write.0, write.1);
+    }
+}
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for ScrollSys {
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<ScrollStates>;
+    fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, scroll_states: Self::WriteData) {
+        scroll_states.states.remove(&event.id);
+        scroll_states.drags.retain(|_, d| d.node != event.id);
+        for drag in scroll_states.drags.values_mut() {
+            drag.containers.retain(|id| *id != event.id);
+        }
+    }
+}
+
+fn modify_overflow(
+    id: usize,
+    overflows: &MultiCaseImpl<Node, Overflow>,
+    scroll_states: &mut SingleCaseImpl<ScrollStates>,
+    offsets: &mut MultiCaseImpl<Node, ScrollOffset>,
+) {
+    let overflow = &overflows[id];
+    if overflow.is_scroll() {
+        let state = scroll_states.states.entry(id).or_insert_with(|| ScrollState::new(overflow.0));
+        state.overflow = overflow.0;
+        state.bar_dirty = true;
+    } else {
+        remove_state(id, scroll_states, offsets);
+    }
+}
+
+fn remove_state(id: usize, scroll_states: &mut SingleCaseImpl<ScrollStates>, offsets: &mut MultiCaseImpl<Node, ScrollOffset>) {
+    if scroll_states.states.remove(&id).is_some() {
+        scroll_states.drags.retain(|_, d| d.node != id);
+        if offsets.get(id).is_some() {
+            offsets.delete(id);
+        }
+    }
+}
+
+impl_system! {
+    ScrollSys,
+    true,
+    {
+        MultiCaseListener<Node, Overflow, CreateEvent>
+        MultiCaseListener<Node, Overflow, ModifyEvent>
+        MultiCaseListener<Node, Overflow, DeleteEvent>
+        EntityListener<Node, DeleteEvent>
+    }
+}
+
+/// 输入指针或滚轮事件， path为命中节点的祖先路径（见event_path）， 返回指针是否拖动了滚动容器（拖动后不再合成Click）
+/// PointerDown停止路径上滚动容器的惯性滚动； PointerMove超过拖动阈值后， 按主要移动方向选择一个滚动容器跟随指针；
+/// PointerUp、PointerCancel结束拖动， 以拖动速度开始惯性滚动； Wheel滚动路径上第一个能在该方向滚动的容器
+pub fn scroll_pointer_event<C: HalContext + 'static>(world: &GuiWorld<C>, event: &Event, path: &[usize]) -> bool {
+    let scroll_states = &mut *world.scroll_states.lend_mut();
+    let offsets = &mut *world.scroll_offset.lend_mut();
+    match event.ty {
+        EventType::PointerDown => {
+            let containers: Vec<usize> = path.iter().filter(|id| scroll_states.states.contains_key(*id)).cloned().collect();
+            if containers.len() == 0 {
+                return false;
+            }
+            for id in containers.iter() {
+                let state = scroll_states.states.get_mut(id).unwrap();
+                state.velocity = (0.0, 0.0);
+                state.target = None;
+            }
+            scroll_states.drags.insert(
+                event.pointer_id,
+                ScrollDrag {
+                    containers: containers,
+                    node: 0,
+                    start: (event.x, event.y),
+                    last: (event.x, event.y),
+                    last_time: event.time,
+                    velocity: (0.0, 0.0),
+                },
+            );
+            false
+        }
+        EventType::PointerMove => {
+            let ScrollStates { states, drags } = &mut **scroll_states;
+            let drag = match drags.get_mut(&event.pointer_id) {
+                Some(r) => r,
+                None => return false,
+            };
+            if drag.node == 0 {
+                let (dx, dy) = (event.x - drag.start.0, event.y - drag.start.1);
+                if dx * dx + dy * dy < DRAG_THRESHOLD * DRAG_THRESHOLD {
+                    return false;
+                }
+                let vertical = dy.abs() >= dx.abs();
+                let node = drag.containers.iter().find(|id| match states.get(*id) {
+                    Some(r) => if vertical { r.scroll_y() } else { r.scroll_x() },
+                    None => false,
+                });
+                drag.node = match node {
+                    Some(r) => *r,
+                    None => return false,
+                };
+                states.get_mut(&drag.node).unwrap().dragging = true;
+            }
+            let state = match states.get(&drag.node) {
+                Some(r) => r,
+                None => return false,
+            };
+            let (dx, dy) = (event.x - drag.last.0, event.y - drag.last.1);
+            let old = offsets.get(drag.node).cloned().unwrap_or_default();
+            let x = if state.scroll_x() { drag_axis(old.x, dx, state.max_x()) } else { old.x };
+            let y = if state.scroll_y() { drag_axis(old.y, dy, state.max_y()) } else { old.y };
+            set_offset(offsets, drag.node, x, y);
+
+            let dt = event.time.saturating_sub(drag.last_time);
+            if dt > 0 {
+                let (vx, vy) = (-dx / dt as f32, -dy / dt as f32);
+                drag.velocity = (0.8 * vx + 0.2 * drag.velocity.0, 0.8 * vy + 0.2 * drag.velocity.1);
+            }
+            drag.last = (event.x, event.y);
+            drag.last_time = event.time;
+            true
+        }
+        EventType::PointerUp | EventType::PointerCancel => {
+            let drag = match scroll_states.drags.remove(&event.pointer_id) {
+                Some(r) => r,
+                None => return false,
+            };
+            if drag.node == 0 {
+                return false;
+            }
+            if let Some(state) = scroll_states.states.get_mut(&drag.node) {
+                state.dragging = false;
+                // 抬起前停顿过久， 不产生惯性
+                state.velocity = if event.time.saturating_sub(drag.last_time) < 100 {
+                    (
+                        if state.scroll_x() { drag.velocity.0 } else { 0.0 },
+                        if state.scroll_y() { drag.velocity.1 } else { 0.0 },
+                    )
+                } else {
+                    (0.0, 0.0)
+                };
+            }
+            true
+        }
+        EventType::Wheel => {
+            for id in path.iter() {
+                let state = match scroll_states.states.get_mut(id) {
+                    Some(r) => r,
+                    None => continue,
+                };
+                let old = offsets.get(*id).cloned().unwrap_or_default();
+                let (max_x, max_y) = (state.max_x(), state.max_y());
+                let can_x = (event.delta_x > 0.0 && old.x < max_x) || (event.delta_x < 0.0 && old.x > 0.0);
+                let can_y = (event.delta_y > 0.0 && old.y < max_y) || (event.delta_y < 0.0 && old.y > 0.0);
+                if !can_x && !can_y {
+                    continue;
+                }
+                state.velocity = (0.0, 0.0);
+                state.target = None;
+                let x = if can_x { (old.x + event.delta_x).max(0.0).min(max_x) } else { old.x };
+                let y = if can_y { (old.y + event.delta_y).max(0.0).min(max_y) } else { old.y };
+                set_offset(offsets, *id, x, y);
+                state.bar_dirty = true;
+                break;
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+// 拖动一个方向， 超出滚动范围时增加阻力
+#[inline]
+fn drag_axis(pos: f32, delta: f32, max: f32) -> f32 {
+    if pos < 0.0 || pos > max {
+        pos - delta * RESISTANCE
+    } else {
+        pos - delta
+    }
+}
+
+/// 滚动到指定偏移（下一帧生效， 超出滚动范围时被限制）， smooth为true时平滑滚动， 返回节点是否为滚动容器
+pub fn scroll_to<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, x: f32, y: f32, smooth: bool) -> bool {
+    match world.scroll_states.lend_mut().states.get_mut(&node) {
+        Some(state) => {
+            set_target(state, x, y, smooth);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 滚动所有祖先滚动容器， 使节点可见（每个容器以最小的滚动量）， 下一帧生效
+pub fn scroll_into_view<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, smooth: bool) {
+    let idtree = world.idtree.lend();
+    let layouts = world.layout.lend();
+    let offsets = world.scroll_offset.lend();
+    let scroll_states = &mut *world.scroll_states.lend_mut();
+
+    let layout = match layouts.get(node) {
+        Some(r) => r,
+        None => return,
+    };
+    // 节点在父节点内容框中的位置
+    let (mut left, mut top) = (layout.rect.start, layout.rect.top);
+    let (width, height) = (layout.rect.end - layout.rect.start, layout.rect.bottom - layout.rect.top);
+    let mut id = node;
+    loop {
+        let parent = match idtree.get(id) {
+            Some(r) if r.parent() > 0 => r.parent(),
+            _ => return,
+        };
+        let layout = match layouts.get(parent) {
+            Some(r) => r,
+            None => return,
+        };
+        // 在父节点内边距框中（未滚动时）的位置
+        let (mut x, mut y) = (layout.padding.start + left, layout.padding.top + top);
+        if let Some(state) = scroll_states.states.get_mut(&parent) {
+            let (cur_x, cur_y) = match state.target.as_ref() {
+                Some(r) => (r.x, r.y),
+                None => offsets.get(parent).map(|r| (r.x, r.y)).unwrap_or((0.0, 0.0)),
+            };
+            let new_x = into_view(cur_x, x, width, state.view_width);
+            let new_y = into_view(cur_y, y, height, state.view_height);
+            if new_x != cur_x || new_y != cur_y {
+                set_target(state, new_x, new_y, smooth);
+            }
+            x -= new_x;
+            y -= new_y;
+        }
+        left = layout.rect.start + layout.border.start + x;
+        top = layout.rect.top + layout.border.top + y;
+        id = parent;
+    }
+}
+
+// 使[pos, pos + size]在可视区域内的最小滚动
+#[inline]
+fn into_view(cur: f32, pos: f32, size: f32, view: f32) -> f32 {
+    if pos < cur || size > view {
+        pos
+    } else if pos + size > cur + view {
+        pos + size - view
+    } else {
+        cur
+    }
+}
+
+fn set_target(state: &mut ScrollState, x: f32, y: f32, smooth: bool) {
+    state.velocity = (0.0, 0.0);
+    state.target = Some(ScrollTarget {
+        x: x,
+        y: y,
+        duration: if smooth { SMOOTH_DURATION } else { 0 },
+        from: None,
+    });
+}
+
+#[cfg(test)]
+use system::event::{dispatch_event, query};
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+
+#[test]
+fn test_scroll() {
+    let mut gui = SnapshotGui::new(SceneDesc::new(100, 100));
+    let root = gui.root;
+    let container = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:50px;height:50px;overflow:auto"), root);
+    let item = gui.append(&NodeDesc::new("position:absolute;left:0px;top:100px;width:20px;height:20px"), container);
+    gui.gui.system_time.lend_mut().cur_time = 1;
+    gui.render();
+    {
+        let states = gui.gui.scroll_states.lend();
+        let state = &states.states[&container];
+        assert_eq!((state.view_height, state.content_height), (50.0, 120.0));
+    }
+
+    // 滚轮滚动， 被限制在滚动范围内
+    let mut wheel = Event::new(EventType::Wheel, 10.0, 10.0);
+    wheel.delta_y = 100.0;
+    dispatch_event(&gui.gui, wheel);
+    assert_eq!(gui.gui.scroll_offset.lend()[container].y, 70.0);
+    gui.render();
+    assert_eq!(gui.gui.world_matrix.lend()[item].0.w.y, 100.0 - 70.0 + 10.0);
+    assert_eq!(query(&gui.gui, 10.0, 35.0), item);
+
+    // 滚动到顶部
+    assert!(scroll_to(&gui.gui, container, 0.0, 0.0, false));
+    gui.render();
+    assert_eq!(gui.gui.scroll_offset.lend()[container].y, 0.0);
+    assert_eq!(query(&gui.gui, 10.0, 35.0), container);
+
+    // 滚动到节点可见
+    scroll_into_view(&gui.gui, item, false);
+    gui.render();
+    assert_eq!(gui.gui.scroll_offset.lend()[container].y, 70.0);
+
+    // 拖动后不合成Click， 抬起后以拖动速度惯性滚动， 超出范围后回弹
+    let mut down = Event::new(EventType::PointerDown, 10.0, 10.0);
+    down.time = 100;
+    dispatch_event(&gui.gui, down);
+    let mut move_ = Event::new(EventType::PointerMove, 10.0, 30.0);
+    move_.time = 110;
+    dispatch_event(&gui.gui, move_);
+    assert_eq!(gui.gui.scroll_offset.lend()[container].y, 50.0);
+    let mut up = Event::new(EventType::PointerUp, 10.0, 30.0);
+    up.time = 120;
+    dispatch_event(&gui.gui, up);
+    assert!(gui.gui.scroll_states.lend().states[&container].velocity.1 < 0.0);
+    gui.gui.system_time.lend_mut().cur_time = 17;
+    gui.render();
+    assert!(gui.gui.scroll_offset.lend()[container].y < 50.0);
+    for i in 2..200 {
+        gui.gui.system_time.lend_mut().cur_time = 1 + i * 16;
+        gui.render();
+    }
+    assert_eq!(gui.gui.scroll_offset.lend()[container].y, 0.0);
+}
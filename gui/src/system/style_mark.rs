@@ -5,6 +5,7 @@
 * StyleMarkSys系统会监听ClassName的修改， 遍历class中的属性， 如果该属性没有设置本地样式，将覆盖该属性对应的组件，并标记样式脏
 * class中的图片， 是一个url， 在设置class时， 该图片资源可能还未加载， StyleMarkSys会将不存在的图片url放入ImageWaitSheet中， 由外部处理ImageWaitSheet中的等待列表，图片加载完成， 应该将图片放入完成列表中， 并通知ImageWaitSheet修改， 由StyleMarkSys来处理ImageWaitSheet中的完成列表
 * StyleMarkSys系统监听ImageWaitSheet单例的修改， 将完成加载的图片设置在对应的Node组件上， 并标记样式脏
+* 样式表中存在复杂选择器（后代、子代、伪类）时， StyleMarkSys在节点的ClassName、PseudoClass改变或节点被添加到树上时， 重新匹配该节点及其子孙节点， 匹配结果改变的节点将重新设置class属性
 */
 use std::marker::PhantomData;
 // use std::mem::transmute;
@@ -17,6 +18,7 @@ use ecs::{
 use single::IdTree;
 use hal_core::*;
 use flex_layout::*;
+use hash::XHashMap;
 use share::Share;
 
 use component::calc::{Opacity as COpacity, LayoutR};
@@ -93,6 +95,7 @@ pub struct StyleMarkSys<C> {
     text_style: TextStyle,
     default_text: TextStyle,
     show: Show,
+    selector_classes: XHashMap<usize, Vec<usize>>, // 节点匹配的复杂选择器对应的class
     mark: PhantomData<(C)>,
 }
 
@@ -102,9 +105,70 @@ impl<'a, C: HalContext + 'static> StyleMarkSys<C> {
             text_style: TextStyle::default(),
             default_text: TextStyle::default(),
             show: Show::default(),
+            selector_classes: XHashMap::default(),
             mark: PhantomData,
         }
     }
+
+    // 重新匹配节点的复杂选择器， 匹配结果改变时， 重新设置节点的class属性
+    fn resolve_selector(&mut self, id: usize, read: ReadData, write: &mut WriteData<C>) {
+        let matched = match_selectors(id, &read.1.borrow(), read.0, read.2, &*write.22);
+        let empty: &[usize] = &[];
+        if self.selector_classes.get(&id).map_or(empty, |r| r.as_slice()) == matched.as_slice() {
+            return;
+        }
+        reset_class(id, &matched, &mut self.text_style, &self.default_text, read, write);
+        if matched.len() == 0 {
+            self.selector_classes.remove(&id);
+        } else {
+            self.selector_classes.insert(id, matched);
+        }
+    }
+
+    // 重新匹配子孙节点的复杂选择器（只有存在带祖先条件的选择器时才需要）
+    // 只重新匹配最右边的复合选择器可能与其匹配、且带祖先条件的子孙节点
+    fn resolve_children_selector(&mut self, id: usize, read: ReadData, write: &mut WriteData<C>) {
+        let ids: Vec<usize> = {
+            let class_sheet = read.1.borrow();
+            if !class_sheet.has_ancestor_selector() {
+                return;
+            }
+            let idtree = &*write.22;
+            let head = match idtree.get(id) {
+                Some(node) => node.children().head,
+                None => return,
+            };
+            let mut candidates = Vec::new();
+            idtree
+                .recursive_iter(head)
+                .map(|r| r.0)
+                .filter(|id| {
+                    candidates.clear();
+                    class_sheet.candidate_selectors(read.0.get(*id), &mut candidates);
+                    candidates.iter().any(|i| class_sheet.selectors[*i].0.ancestors.len() > 0)
+                })
+                .collect()
+        };
+        for id in ids.into_iter() {
+            self.resolve_selector(id, read, write);
+        }
+    }
+
+    // 节点从树上移除， 不再有祖先， 清除其及子孙节点匹配的复杂选择器（重新添加到树上时重新匹配）
+    fn clear_selector(&mut self, id: usize, read: ReadData, write: &mut WriteData<C>) {
+        let ids: Vec<usize> = {
+            let idtree = &*write.22;
+            let node = &idtree[id];
+            let mut ids = vec![id];
+            ids.extend(idtree.recursive_iter(node.children().head).map(|r| r.0));
+            ids
+        };
+        for id in ids.into_iter() {
+            if self.selector_classes.remove(&id).is_some() {
+                reset_class(id, &[], &mut self.text_style, &self.default_text, read, write);
+            }
+        }
+    }
 }
 
 #[inline]
@@ -144,7 +208,7 @@ fn set_local_dirty2(
 }
 
 #[inline]
-fn set_dirty(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut StyleMark) {
+pub(crate) fn set_dirty(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut StyleMark) {
     if style_mark.dirty == 0 && style_mark.dirty1 == 0 && style_mark.dirty2 == 0{
         dirty_list.0.push(id);
 	}
@@ -152,7 +216,7 @@ fn set_dirty(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut
 }
 
 #[inline]
-fn set_dirty1(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut StyleMark) {
+pub(crate) fn set_dirty1(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut StyleMark) {
     if style_mark.dirty == 0 && style_mark.dirty1 == 0 && style_mark.dirty2 == 0 {
         dirty_list.0.push(id);
     }
@@ -160,7 +224,7 @@ fn set_dirty1(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut
 }
 
 #[inline]
-fn set_dirty2(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut StyleMark) {
+pub(crate) fn set_dirty2(dirty_list: &mut DirtyList, id: usize, ty: usize, style_mark: &mut StyleMark) {
     if style_mark.dirty == 0 && style_mark.dirty1 == 0 && style_mark.dirty2 == 0{
         dirty_list.0.push(id);
 	}
@@ -265,7 +329,6 @@ impl<'a, C: HalContext + 'static>
 		
         let r = match event.field {
 			"position_type" => StyleType2::PositionType as usize,
-			"direction" => StyleType2::FlexDirection as usize,
 
 			"flex_direction" => StyleType2::FlexDirection as usize,
 			"flex_wrap" => StyleType2::FlexWrap as usize,
@@ -310,6 +373,7 @@ impl<'a, C: HalContext + 'static>
 			"display" => StyleType1::Display as usize,
 			"overflow" =>  StyleType1::Overflow as usize,
 			"flex_basis" => StyleType1::FlexBasis as usize,
+			"direction" => StyleType1::Direction as usize,
 			_ => return,
 		};
 		set_local_dirty1(dirty_list, event.id, r as usize, style_marks);
@@ -327,6 +391,7 @@ impl<'a, C: HalContext + 'static>
     );
 
     fn listen(&mut self, event: &ModifyEvent, _read: Self::ReadData, write: Self::WriteData) {
+        let (style_marks, dirty_list) = write;
         let r = match event.field {
             "letter_spacing" => StyleType::LetterSpacing,
 			"word_spacing" => StyleType::WordSpacing,
@@ -342,9 +407,20 @@ impl<'a, C: HalContext + 'static>
             "font_weight" => StyleType::FontWeight,
             "font_size" => StyleType::FontSize,
             "font_family" => StyleType::FontFamily,
+            "text_overflow" => {
+                set_local_dirty1(dirty_list, event.id, StyleType1::TextOverflow as usize, style_marks);
+                return;
+            }
+            "line_clamp" => {
+                set_local_dirty1(dirty_list, event.id, StyleType1::LineClamp as usize, style_marks);
+                return;
+            }
+            "text_decoration" => {
+                set_local_dirty1(dirty_list, event.id, StyleType1::TextDecoration as usize, style_marks);
+                return;
+            }
             _ => return,
 		};
-        let (style_marks, dirty_list) = write;
         set_local_dirty(dirty_list, event.id, r as usize, style_marks);
     }
 }
@@ -389,6 +465,74 @@ impl<'a, C: HalContext + 'static>
     }
 }
 
+// 监听富文本的改变， 加载片段中的行内图片， 并标记文字脏
+// 行内图片在设置富文本时加载(不等待节点添加到树上)， 删除富文本时释放
+impl<'a, C: HalContext + 'static>
+    MultiCaseListener<'a, Node, TextSpans, CreateEvent> for StyleMarkSys<C>
+{
+    type ReadData = ();
+    type WriteData = TextSpansWrite<'a, C>;
+    fn listen(&mut self, event: &CreateEvent, _read: Self::ReadData, write: Self::WriteData) {
+        set_text_spans(event.id, write);
+    }
+}
+
+impl<'a, C: HalContext + 'static>
+    MultiCaseListener<'a, Node, TextSpans, ModifyEvent> for StyleMarkSys<C>
+{
+    type ReadData = ();
+    type WriteData = TextSpansWrite<'a, C>;
+    fn listen(&mut self, event: &ModifyEvent, _read: Self::ReadData, write: Self::WriteData) {
+        set_text_spans(event.id, write);
+    }
+}
+
+impl<'a, C: HalContext + 'static>
+    MultiCaseListener<'a, Node, TextSpans, DeleteEvent> for StyleMarkSys<C>
+{
+    type ReadData = ();
+    type WriteData = (
+        &'a mut MultiCaseImpl<Node, StyleMark>,
+        &'a mut SingleCaseImpl<DirtyList>,
+    );
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, write: Self::WriteData) {
+        let (style_marks, dirty_list) = write;
+        // 节点删除时， 富文本随之删除， 不需要标记
+        if let Some(style_mark) = style_marks.get_mut(event.id) {
+            set_dirty(dirty_list, event.id, StyleType::Text as usize, style_mark);
+        }
+    }
+}
+
+type TextSpansWrite<'a, C> = (
+    &'a mut MultiCaseImpl<Node, TextSpans>,
+    &'a mut MultiCaseImpl<Node, StyleMark>,
+    &'a mut SingleCaseImpl<DirtyList>,
+    &'a mut SingleCaseImpl<ShareEngine<C>>,
+    &'a mut SingleCaseImpl<ImageWaitSheet>,
+);
+
+fn set_text_spans<'a, C: HalContext>(id: usize, write: TextSpansWrite<'a, C>) {
+    let (text_spans, style_marks, dirty_list, engine, image_wait_sheet) = write;
+    for span in text_spans[id].0.iter_mut() {
+        if let Some(image) = &mut span.image {
+            if image.src.is_none() {
+                match engine.texture_res_map.get(&image.url) {
+                    Some(r) => image.src = Some(r),
+                    None => image_wait_sheet.add(
+                        image.url,
+                        ImageWait {
+                            id: id,
+                            ty: ImageType::TextSpan,
+                        },
+                    ),
+                }
+            }
+        }
+    }
+    set_dirty(dirty_list, id, StyleType::Text as usize, &mut style_marks[id]);
+}
+
 impl<'a, C: HalContext + 'static>
     MultiCaseListener<'a, Node, Image, CreateEvent> for StyleMarkSys<C>
 {
@@ -984,9 +1128,27 @@ impl<'a, C: HalContext + 'static>
 impl<'a, C: HalContext + 'static> SingleCaseListener<'a, IdTree, CreateEvent>
     for StyleMarkSys<C>
 {
-    type ReadData = &'a SingleCaseImpl<IdTree>;
-    type WriteData = ImageTextureWrite<'a, C>;
-    fn listen(&mut self, event: &CreateEvent, idtree: Self::ReadData, mut write: Self::WriteData) {
+    type ReadData = ReadData<'a>;
+    type WriteData = WriteData<'a, C>;
+    fn listen(&mut self, event: &CreateEvent, read: Self::ReadData, mut write: Self::WriteData) {
+        // 节点被添加到树上， 祖先改变， 需要重新匹配复杂选择器
+        if read.1.borrow().selectors.len() > 0 {
+            self.resolve_selector(event.id, read, &mut write);
+            self.resolve_children_selector(event.id, read, &mut write);
+        }
+
+        let idtree = &*write.22;
+        let mut write = (
+            &mut *write.1,
+            &mut *write.4,
+            &mut *write.19,
+            &mut *write.25,
+            &mut *write.23,
+            &mut *write.24,
+            &mut *write.20,
+            &mut *write.2,
+            &mut *write.5,
+        );
         load_image(event.id, &mut write);
 
         let node = &idtree[event.id];
@@ -999,9 +1161,25 @@ impl<'a, C: HalContext + 'static> SingleCaseListener<'a, IdTree, CreateEvent>
 impl<'a, C: HalContext + 'static> SingleCaseListener<'a, IdTree, DeleteEvent>
     for StyleMarkSys<C>
 {
-    type ReadData = &'a SingleCaseImpl<IdTree>;
-    type WriteData = ImageTextureWrite<'a, C>;
-    fn listen(&mut self, event: &DeleteEvent, idtree: Self::ReadData, mut write: Self::WriteData) {
+    type ReadData = ReadData<'a>;
+    type WriteData = WriteData<'a, C>;
+    fn listen(&mut self, event: &DeleteEvent, read: Self::ReadData, mut write: Self::WriteData) {
+        if self.selector_classes.len() > 0 {
+            self.clear_selector(event.id, read, &mut write);
+        }
+
+        let idtree = &*write.22;
+        let mut write = (
+            &mut *write.1,
+            &mut *write.4,
+            &mut *write.19,
+            &mut *write.25,
+            &mut *write.23,
+            &mut *write.24,
+            &mut *write.20,
+            &mut *write.2,
+            &mut *write.5,
+        );
         release_image(event.id, &mut write);
 
         let node = &idtree[event.id];
@@ -1014,6 +1192,7 @@ impl<'a, C: HalContext + 'static> SingleCaseListener<'a, IdTree, DeleteEvent>
 type ReadData<'a> = (
     &'a MultiCaseImpl<Node, ClassName>,
     &'a SingleCaseImpl<Share<StdCell<ClassSheet>>>,
+    &'a MultiCaseImpl<Node, PseudoClass>,
 );
 type WriteData<'a, C> = (
     &'a mut MultiCaseImpl<Node, TextStyle>,
@@ -1027,7 +1206,11 @@ type WriteData<'a, C> = (
     &'a mut MultiCaseImpl<Node, BorderColor>,
     &'a mut MultiCaseImpl<Node, BackgroundColor>,
     &'a mut MultiCaseImpl<Node, BoxShadow>,
-    &'a mut MultiCaseImpl<Node, WorldMatrix>,
+    (
+        &'a mut MultiCaseImpl<Node, Transition>,
+        &'a mut MultiCaseImpl<Node, Animation>,
+        &'a mut MultiCaseImpl<Node, ClipPath>,
+    ),
     &'a mut MultiCaseImpl<Node, Opacity>,
     &'a mut MultiCaseImpl<Node, Transform>,
     &'a mut MultiCaseImpl<Node, BorderRadius>,
@@ -1050,12 +1233,33 @@ impl<'a, C: HalContext + 'static>
     type ReadData = ReadData<'a>;
     type WriteData = WriteData<'a, C>;
     fn listen(&mut self, event: &ModifyEvent, read: Self::ReadData, mut write: Self::WriteData) {
-		let (class_names, class_sheet) = read;
+		let (class_names, class_sheet, pseudo_classes) = read;
 		let class_name = &class_names[event.id];
 		let class_sheet = &class_sheet.borrow();
 
 		//event.index是接的className的指针
         let oldr = unsafe { &* Box::from_raw(event.index as *mut Option<ClassName>) };
+
+        // 样式表中存在复杂选择器时， 需要重新匹配该节点， 新旧class出现在祖先选择器中时， 还需要重新匹配子孙节点
+        let has_selector = class_sheet.selectors.len() > 0;
+        let is_ancestor_key = has_selector
+            && (class_sheet.is_ancestor_key(Some(class_name), 0) || class_sheet.is_ancestor_key(oldr.as_ref(), 0));
+        if has_selector {
+            let matched = match_selectors(event.id, class_sheet, class_names, pseudo_classes, &*write.22);
+            if matched.len() > 0 || self.selector_classes.contains_key(&event.id) {
+                // 节点匹配了复杂选择器， 不能只设置改变的class， 重新设置所有class属性
+                reset_class(event.id, &matched, &mut self.text_style, &self.default_text, read, &mut write);
+                if matched.len() == 0 {
+                    self.selector_classes.remove(&event.id);
+                } else {
+                    self.selector_classes.insert(event.id, matched);
+                }
+                if is_ancestor_key {
+                    self.resolve_children_selector(event.id, read, &mut write);
+                }
+                return;
+            }
+        }
 		let mark = &mut write.19[event.id];
         let (old_style, old_style1, old_style2) = (mark.class_style, mark.class_style1, mark.class_style2);
         mark.class_style = 0;
@@ -1163,6 +1367,39 @@ impl<'a, C: HalContext + 'static>
                 &self.default_text,
             );
 		}
+
+        if is_ancestor_key {
+            self.resolve_children_selector(event.id, read, &mut write);
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static>
+    MultiCaseListener<'a, Node, PseudoClass, CreateEvent> for StyleMarkSys<C>
+{
+    type ReadData = ReadData<'a>;
+    type WriteData = WriteData<'a, C>;
+    fn listen(&mut self, event: &CreateEvent, read: Self::ReadData, mut write: Self::WriteData) {
+        if read.1.borrow().selectors.len() > 0 {
+            self.resolve_selector(event.id, read, &mut write);
+            self.resolve_children_selector(event.id, read, &mut write);
+        }
+    }
+}
+
+impl<'a, C: HalContext + 'static>
+    MultiCaseListener<'a, Node, PseudoClass, ModifyEvent> for StyleMarkSys<C>
+{
+    type ReadData = ReadData<'a>;
+    type WriteData = WriteData<'a, C>;
+    fn listen(&mut self, event: &ModifyEvent, read: Self::ReadData, mut write: Self::WriteData) {
+        if read.1.borrow().selectors.len() > 0 {
+            self.resolve_selector(event.id, read, &mut write);
+            // 祖先选择器中有伪类时， 伪类状态的改变才会影响子孙节点
+            if read.1.borrow().ancestor_pseudo > 0 {
+                self.resolve_children_selector(event.id, read, &mut write);
+            }
+        }
     }
 }
 
@@ -1192,6 +1429,7 @@ impl<'a, C: HalContext + 'static>
         &'a mut MultiCaseImpl<Node, StyleMark>,
         &'a mut SingleCaseImpl<ImageWaitSheet>,
         &'a mut SingleCaseImpl<DirtyList>,
+        &'a mut MultiCaseImpl<Node, TextSpans>,
     );
     fn listen(&mut self, _event: &ModifyEvent, idtree: Self::ReadData, write: Self::WriteData) {
         let (
@@ -1204,6 +1442,7 @@ impl<'a, C: HalContext + 'static>
             style_marks,
             image_wait_sheet,
             dirty_list,
+            text_spans,
         ) = write;
 
         for wait in image_wait_sheet.finish.iter() {
@@ -1212,9 +1451,12 @@ impl<'a, C: HalContext + 'static>
                 if !entitys.is_exist(image_wait.id) {
                     continue;
                 }
-                // 节点不可见， 跳过
-                if idtree.get(image_wait.id).is_none() {
-                    continue;
+                // 节点不可见， 跳过(富文本的行内图片不依赖节点是否在树上)
+                match image_wait.ty {
+                    ImageType::TextSpan => (),
+                    _ => if idtree.get(image_wait.id).is_none() {
+                        continue;
+                    },
                 }
                 // 判断等待类型， 设置对应的组件
                 match image_wait.ty {
@@ -1295,6 +1537,27 @@ impl<'a, C: HalContext + 'static>
                             }
                         }
                     }
+                    ImageType::TextSpan => {
+                        if let Some(spans) = text_spans.get_mut(image_wait.id) {
+                            let mut change = false;
+                            for span in spans.0.iter_mut() {
+                                if let Some(image) = &mut span.image {
+                                    if image.url == wait.0 {
+                                        image.src = Some(wait.1.clone());
+                                        change = true;
+                                    }
+                                }
+                            }
+                            if change {
+                                set_dirty(
+                                    dirty_list,
+                                    image_wait.id,
+                                    StyleType::Text as usize,
+                                    &mut style_marks[image_wait.id],
+                                );
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -1346,7 +1609,7 @@ fn reset_attr<C: HalContext>(
 	old_style2: usize,
     defualt_text: &TextStyle,
 ) {
-    let (_class_names, _class_sheet) = read;
+    let (_class_names, _class_sheet, _) = read;
     let (
         text_styles,
         images,
@@ -1359,7 +1622,7 @@ fn reset_attr<C: HalContext>(
         border_colors,
         background_colors,
         box_shadows,
-        _world_matrixs,
+        (transitions, animations, clip_paths),
         opacitys,
         transforms,
         border_radiuss,
@@ -1442,7 +1705,7 @@ fn reset_attr<C: HalContext>(
                 }
 
                 if old_style & StyleType::TextShadow as usize != 0 {
-                    text_style.shadow = defualt_text.shadow.clone();
+                    text_style.shadows = defualt_text.shadows.clone();
                     set_dirty(dirty_list, id, StyleType::TextShadow as usize, style_mark);
                 }
 
@@ -1587,6 +1850,43 @@ fn reset_attr<C: HalContext>(
 		if old_style1 & StyleType1::FlexBasis as usize != 0 {
 			other_layout_style.flex_basis = Dimension::Undefined;
 		}
+		if old_style1 & StyleType1::Direction as usize != 0 {
+			other_layout_style.direction = Direction::Inherit;
+			set_dirty1(dirty_list, id, StyleType1::Direction as usize, style_mark);
+		}
+		if old_style1 & (StyleType1::TextOverflow as usize | StyleType1::LineClamp as usize) != 0 {
+			if let Some(text_style) = text_styles.get_mut(id) {
+				if old_style1 & StyleType1::TextOverflow as usize != 0 {
+					text_style.text.text_overflow = defualt_text.text.text_overflow.clone();
+					set_dirty1(dirty_list, id, StyleType1::TextOverflow as usize, style_mark);
+				}
+				if old_style1 & StyleType1::LineClamp as usize != 0 {
+					text_style.text.line_clamp = defualt_text.text.line_clamp;
+					set_dirty1(dirty_list, id, StyleType1::LineClamp as usize, style_mark);
+				}
+			}
+		}
+		if old_style1 & StyleType1::TextDecoration as usize != 0 {
+			if let Some(text_style) = text_styles.get_mut(id) {
+				text_style.text.decoration = defualt_text.text.decoration.clone();
+				set_dirty1(dirty_list, id, StyleType1::TextDecoration as usize, style_mark);
+			}
+		}
+		if old_style1 & StyleType1::Transition as usize != 0 {
+			if transitions.get(id).is_some() {
+				transitions.delete(id);
+			}
+		}
+		if old_style1 & StyleType1::Animation as usize != 0 {
+			if animations.get(id).is_some() {
+				animations.delete(id);
+			}
+		}
+		if old_style1 & StyleType1::ClipPath as usize != 0 {
+			if clip_paths.get(id).is_some() {
+				clip_paths.delete(id);
+			}
+		}
 	}
 	
 	if old_style2 != 0 {
@@ -1780,7 +2080,7 @@ fn set_attr<C: HalContext>(
     if class_name == 0 {
         return;
     }
-    let (_class_names, class_sheet) = read;
+    let (_class_names, class_sheet, _) = read;
     let (
         text_styles,
         images,
@@ -1793,7 +2093,7 @@ fn set_attr<C: HalContext>(
         border_colors,
         background_colors,
         box_shadows,
-        _world_matrixs,
+        (transitions, animations, clip_paths),
         opacitys,
         transforms,
         border_radiuss,
@@ -1874,9 +2174,131 @@ fn set_attr<C: HalContext>(
         filters,
         transforms,
         rect_layout_styles,
+        transitions,
+        animations,
+        clip_paths,
     );
 }
 
+// 重新设置节点的class属性（自身的class及匹配的复杂选择器对应的class）
+fn reset_class<C: HalContext>(
+    id: usize,
+    matched: &[usize],
+    text_style: &mut TextStyle,
+    default_text: &TextStyle,
+    read: ReadData,
+    write: &mut WriteData<C>,
+) {
+    let (old_style, old_style1, old_style2) = {
+        let mark = &mut write.19[id];
+        let r = (mark.class_style, mark.class_style1, mark.class_style2);
+        mark.class_style = 0;
+        mark.class_style1 = 0;
+        mark.class_style2 = 0;
+        r
+    };
+    // 按照优先级、再按照在样式表中的顺序设置（后设置的覆盖先设置的）， 简单class的优先级为1
+    let mut classes = Vec::new();
+    {
+        let class_sheet = read.1.borrow();
+        if let Some(class_name) = read.0.get(id) {
+            let simple = [class_name.one, class_name.two];
+            for c in simple.iter().chain(class_name.other.iter()) {
+                if *c > 0 {
+                    classes.push((1, class_sheet.order(*c), *c));
+                }
+            }
+        }
+        for c in matched.iter() {
+            let specificity = class_sheet.selectors[*c - SELECTOR_CLASS_START].0.specificity();
+            classes.push((specificity, class_sheet.order(*c), *c));
+        }
+    }
+    // 稳定排序， 优先级与顺序都相同时保持class属性中的顺序
+    classes.sort_by_key(|r| (r.0, r.1));
+    for (_, _, c) in classes.into_iter() {
+        set_attr(id, c, text_style, read, write);
+    }
+    if old_style > 0 || old_style1 > 0 || old_style2 > 0 {
+        reset_attr(id, read, write, old_style, old_style1, old_style2, default_text);
+    }
+}
+
+// 匹配节点的复杂选择器， 返回匹配的class
+// 优先级高的排在后面（后设置的覆盖先设置的）， 优先级相同时， 保持在样式表中的顺序
+fn match_selectors(
+    id: usize,
+    class_sheet: &ClassSheet,
+    class_names: &MultiCaseImpl<Node, ClassName>,
+    pseudo_classes: &MultiCaseImpl<Node, PseudoClass>,
+    idtree: &IdTree,
+) -> Vec<usize> {
+    // 只检查最右边的复合选择器可能与节点匹配的选择器
+    let mut candidates = Vec::new();
+    class_sheet.candidate_selectors(class_names.get(id), &mut candidates);
+    let parent = match idtree.get(id) {
+        Some(node) => node.parent(),
+        None => 0,
+    };
+    let mut r = Vec::new();
+    for i in candidates.into_iter() {
+        let (selector, class) = &class_sheet.selectors[i];
+        if !match_compound(&selector.subject, id, class_names, pseudo_classes) {
+            continue;
+        }
+        if match_ancestors(&selector.ancestors, parent, class_names, pseudo_classes, idtree) {
+            r.push((selector.specificity(), i, *class));
+        }
+    }
+    r.sort_by_key(|v| (v.0, v.1));
+    r.into_iter().map(|v| v.2).collect()
+}
+
+#[inline]
+fn match_compound(
+    compound: &CompoundSelector,
+    id: usize,
+    class_names: &MultiCaseImpl<Node, ClassName>,
+    pseudo_classes: &MultiCaseImpl<Node, PseudoClass>,
+) -> bool {
+    let pseudo = match pseudo_classes.get(id) {
+        Some(r) => r.0,
+        None => 0,
+    };
+    compound.matches(class_names.get(id), pseudo)
+}
+
+// 从id开始向上匹配祖先选择器
+fn match_ancestors(
+    ancestors: &[(Combinator, CompoundSelector)],
+    mut id: usize,
+    class_names: &MultiCaseImpl<Node, ClassName>,
+    pseudo_classes: &MultiCaseImpl<Node, PseudoClass>,
+    idtree: &IdTree,
+) -> bool {
+    let (combinator, compound) = match ancestors.first() {
+        Some(r) => r,
+        None => return true,
+    };
+    while id > 0 {
+        let parent = match idtree.get(id) {
+            Some(node) => node.parent(),
+            None => 0,
+        };
+        if match_compound(compound, id, class_names, pseudo_classes)
+            && match_ancestors(&ancestors[1..], parent, class_names, pseudo_classes, idtree)
+        {
+            return true;
+        }
+        // 子代选择器只能匹配直接父节点
+        if *combinator == Combinator::Child {
+            return false;
+        }
+        id = parent;
+    }
+    false
+}
+
 #[inline]
 fn set_mark(class_sheet: &ClassSheet, name: usize, mark: &mut StyleMark) {
     match class_sheet.class_map.get(&name) {
@@ -1937,6 +2359,12 @@ pub fn set_attr1(
 					other_style.position_type = *r;
                 }
             }
+            Attribute1::Direction(r) => {
+                if StyleType1::Direction as usize & style_mark.local_style1 == 0 {
+					other_style.direction = *r;
+					set_dirty1(dirty_list, id, StyleType1::Direction as usize, style_mark);
+                }
+            }
 
             Attribute1::ObjectFit(r) => {
                 if style_mark.local_style == 0 & StyleType::ObjectFit as usize {
@@ -2081,6 +2509,43 @@ pub fn set_attr2<C: HalContext>(
                     set_dirty(dirty_list, id, StyleType::FontFamily as usize, style_mark);
                 }
             }
+            Attribute2::TextOverflow(r) => {
+                if style_mark.local_style1 & StyleType1::TextOverflow as usize == 0 {
+                    text_style.text.text_overflow = r.clone();
+                    set_dirty1(dirty_list, id, StyleType1::TextOverflow as usize, style_mark);
+                }
+            }
+            Attribute2::LineClamp(r) => {
+                if style_mark.local_style1 & StyleType1::LineClamp as usize == 0 {
+                    text_style.text.line_clamp = *r;
+                    set_dirty1(dirty_list, id, StyleType1::LineClamp as usize, style_mark);
+                }
+            }
+            // 装饰线的各个属性共用一个样式标记， 本地设置了任意一个属性时， class中的装饰线都不生效
+            Attribute2::TextDecorationLine(r) => {
+                if style_mark.local_style1 & StyleType1::TextDecoration as usize == 0 {
+                    text_style.text.decoration.line = *r;
+                    set_dirty1(dirty_list, id, StyleType1::TextDecoration as usize, style_mark);
+                }
+            }
+            Attribute2::TextDecorationStyle(r) => {
+                if style_mark.local_style1 & StyleType1::TextDecoration as usize == 0 {
+                    text_style.text.decoration.style = *r;
+                    set_dirty1(dirty_list, id, StyleType1::TextDecoration as usize, style_mark);
+                }
+            }
+            Attribute2::TextDecorationColor(r) => {
+                if style_mark.local_style1 & StyleType1::TextDecoration as usize == 0 {
+                    text_style.text.decoration.color = r.clone();
+                    set_dirty1(dirty_list, id, StyleType1::TextDecoration as usize, style_mark);
+                }
+            }
+            Attribute2::TextDecorationThickness(r) => {
+                if style_mark.local_style1 & StyleType1::TextDecoration as usize == 0 {
+                    text_style.text.decoration.thickness = *r;
+                    set_dirty1(dirty_list, id, StyleType1::TextDecoration as usize, style_mark);
+                }
+            }
             Attribute2::ZIndex(r) => {
                 if style_mark.local_style1 & StyleType1::ZIndex as usize == 0 {
                     zindexs.insert(id, ZIndex(*r));
@@ -2393,6 +2858,9 @@ pub fn set_attr3(
     filters: &mut MultiCaseImpl<Node, Filter>,
     transforms: &mut MultiCaseImpl<Node, Transform>,
     rect_layout_styles: &mut MultiCaseImpl<Node, RectLayoutStyle>,
+    transitions: &mut MultiCaseImpl<Node, Transition>,
+    animations: &mut MultiCaseImpl<Node, Animation>,
+    clip_paths: &mut MultiCaseImpl<Node, ClipPath>,
 ) {
     for attr in attrs.iter() {
         match attr {
@@ -2469,7 +2937,7 @@ pub fn set_attr3(
             }
             Attribute3::TextShadow(r) => {
                 if style_mark.local_style & StyleType::TextShadow as usize == 0 {
-                    text_style.shadow = r.clone();
+                    text_style.shadows = r.clone();
                     set_dirty(dirty_list, id, StyleType::TextShadow as usize, style_mark);
                 }
             }
@@ -2486,6 +2954,20 @@ pub fn set_attr3(
                     set_dirty(dirty_list, id, StyleType::BorderRadius as usize, style_mark);
                 }
             }
+            Attribute3::BorderRadiusCorner(index, r) => {
+                if style_mark.local_style & StyleType::BorderRadius as usize == 0 {
+                    // 在当前的圆角上修改一个角， 其它角保留之前的class设置的值
+                    let mut radius = match border_radiuss.get(id) {
+                        Some(r) => r.clone(),
+                        None => BorderRadius::default(),
+                    };
+                    if let Some(c) = radius.corner_mut(*index) {
+                        *c = *r;
+                    }
+                    border_radiuss.insert_no_notify(id, radius);
+                    set_dirty(dirty_list, id, StyleType::BorderRadius as usize, style_mark);
+                }
+            }
             Attribute3::TransformFunc(r) => {
                 if style_mark.local_style1 & StyleType1::Transform as usize == 0 {
                     match transforms.get_mut(id) {
@@ -2524,6 +3006,22 @@ pub fn set_attr3(
                     set_dirty(dirty_list, id, StyleType::Filter as usize, style_mark);
                 }
             }
+            // 过渡由TransitionSys处理， 不需要设置脏
+            Attribute3::Transition(r) => {
+                if style_mark.local_style1 & StyleType1::Transition as usize == 0 {
+                    transitions.insert(id, r.clone());
+                }
+            }
+            Attribute3::Animation(r) => {
+                if style_mark.local_style1 & StyleType1::Animation as usize == 0 {
+                    animations.insert(id, r.clone());
+                }
+            }
+            Attribute3::ClipPath(r) => {
+                if style_mark.local_style1 & StyleType1::ClipPath as usize == 0 {
+                    clip_paths.insert(id, r.clone());
+                }
+            }
         }
     }
 }
@@ -2733,6 +3231,9 @@ impl_system! {
 		EntityListener<Node, ModifyEvent>
         MultiCaseListener<Node, TextContent, CreateEvent>
         MultiCaseListener<Node, TextContent, ModifyEvent>
+        MultiCaseListener<Node, TextSpans, CreateEvent>
+        MultiCaseListener<Node, TextSpans, ModifyEvent>
+        MultiCaseListener<Node, TextSpans, DeleteEvent>
 		MultiCaseListener<Node, TextStyle, ModifyEvent>
 		MultiCaseListener<Node, RectLayoutStyle, ModifyEvent>
 		MultiCaseListener<Node, OtherLayoutStyle, ModifyEvent>
@@ -2774,6 +3275,8 @@ impl_system! {
 		MultiCaseListener<Node, COpacity, ModifyEvent>
 
         MultiCaseListener<Node, ClassName, ModifyEvent>
+        MultiCaseListener<Node, PseudoClass, CreateEvent>
+        MultiCaseListener<Node, PseudoClass, ModifyEvent>
         SingleCaseListener<ImageWaitSheet, ModifyEvent>
         SingleCaseListener<RenderObjs, CreateEvent>
         SingleCaseListener<DefaultTable, ModifyEvent>
@@ -2781,3 +3284,88 @@ impl_system! {
         SingleCaseListener<IdTree, DeleteEvent>
     }
 }
+
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+#[cfg(test)]
+use ecs::LendMut;
+#[cfg(test)]
+use ecs::monitor::NotifyImpl;
+
+#[test]
+fn test_selector() {
+    let mut scene = SceneDesc::new(30, 10);
+    scene.css = ".1 .2{background-color:#ff0000} .1>.3:hover{background-color:#00ff00}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let parent = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:20px;height:10px").with_class(&[1]), root);
+    gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:10px;height:10px").with_class(&[2]), parent);
+    let hover = gui.append(&NodeDesc::new("position:absolute;left:10px;top:0px;width:10px;height:10px").with_class(&[3]), parent);
+    // 不在.1之下， 不匹配
+    gui.append(&NodeDesc::new("position:absolute;left:20px;top:0px;width:10px;height:10px").with_class(&[2]), root);
+
+    gui.render();
+    assert_eq!(gui.pixel(5, 5), [255, 0, 0, 255]);
+    assert_eq!(gui.pixel(15, 5), [255, 255, 255, 255]);
+    assert_eq!(gui.pixel(25, 5), [255, 255, 255, 255]);
+
+    gui.gui.pseudo_class.lend_mut().insert(hover, PseudoClass(PseudoClassType::Hover as usize));
+    gui.render();
+    assert_eq!(gui.pixel(15, 5), [0, 255, 0, 255]);
+}
+
+#[test]
+fn test_selector_order() {
+    // 优先级相同时， 样式表中靠后的规则覆盖靠前的规则， 不论是简单class还是复杂选择器
+    // 优先级高的复杂选择器覆盖简单class
+    let mut scene = SceneDesc::new(30, 10);
+    scene.css = ":hover{background-color:#ff0000} .2{background-color:#00ff00} .3{background-color:#00ff00} .4:hover{background-color:#0000ff} .1 .5{background-color:#ff0000} .5{background-color:#00ff00}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let first = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:10px;height:10px").with_class(&[2]), root);
+    let second = gui.append(&NodeDesc::new("position:absolute;left:10px;top:0px;width:10px;height:10px").with_class(&[3, 4]), root);
+    let parent = gui.append(&NodeDesc::new("position:absolute;left:20px;top:0px;width:10px;height:10px").with_class(&[1]), root);
+    gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:10px;height:10px").with_class(&[5]), parent);
+    {
+        let pseudo_classes = gui.gui.pseudo_class.lend_mut();
+        pseudo_classes.insert(first, PseudoClass(PseudoClassType::Hover as usize));
+        pseudo_classes.insert(second, PseudoClass(PseudoClassType::Hover as usize));
+    }
+
+    gui.render();
+    assert_eq!(gui.pixel(5, 5), [0, 255, 0, 255]);
+    assert_eq!(gui.pixel(15, 5), [0, 0, 255, 255]);
+    assert_eq!(gui.pixel(25, 5), [255, 0, 0, 255]);
+}
+
+#[test]
+fn test_selector_remove() {
+    // 节点从树上移除后， 清除其匹配的复杂选择器， 重新添加到其它位置时重新匹配
+    let mut scene = SceneDesc::new(10, 10);
+    scene.css = ".1 .2{background-color:#ff0000}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let parent = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:10px;height:10px").with_class(&[1]), root);
+    let child = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:10px;height:10px").with_class(&[2]), parent);
+    gui.render();
+
+    assert_eq!(gui.pixel(5, 5), [255, 0, 0, 255]);
+
+    {
+        let idtree = gui.gui.idtree.lend_mut();
+        let notify = unsafe { &*(idtree.get_notify_ref() as *const NotifyImpl) };
+        idtree.remove_with_notify(child, notify);
+        idtree.insert_child_with_notify(child, root, 0, notify);
+    }
+    gui.render();
+    assert_eq!(gui.pixel(5, 5), [255, 255, 255, 255]);
+
+    {
+        let idtree = gui.gui.idtree.lend_mut();
+        let notify = unsafe { &*(idtree.get_notify_ref() as *const NotifyImpl) };
+        idtree.remove_with_notify(child, notify);
+        idtree.insert_child_with_notify(child, parent, 0, notify);
+    }
+    gui.render();
+    assert_eq!(gui.pixel(5, 5), [255, 0, 0, 255]);
+}
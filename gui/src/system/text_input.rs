@@ -0,0 +1,701 @@
+/**
+ * 文本输入框
+ * create_text_input将文字节点设置为输入框（编辑状态见single::text_input）， 编辑缓冲区或组合中的文字改变后， 重新设置节点的TextContent；
+ * 光标位置与指针位置的相互转换使用文字布局的结果（CharBlock）， 因此输入框需要使用简单布局（设置了宽度或高度， 或是父节点唯一的子节点）
+ * TextInputSys推进获得焦点的输入框的光标闪烁， 指针选择由dispatch_event调用text_input_pointer_event输入
+ */
+use atom::Atom;
+use ecs::{DeleteEvent, EntityListener, Lend, LendMut, Runner, SingleCaseImpl};
+use hal_core::HalContext;
+
+use component::calc::{CharBlock, WorldMatrix};
+use component::user::{CgColor, TextAlign, TextContent, TextStyle, TransformOrigin, Vector4, VerticalAlign, WhiteSpace};
+use entity::Node;
+use font::font_sheet::{split, SplitResult};
+use single::*;
+use system::render::char_positions;
+use world::GuiWorld;
+
+const BLINK_INTERVAL: usize = 530; // 光标闪烁的间隔， 单位： 毫秒
+
+#[derive(Default)]
+pub struct TextInputSys;
+
+impl<'a> Runner<'a> for TextInputSys {
+    type ReadData = &'a SingleCaseImpl<SystemTime>;
+    type WriteData = &'a mut SingleCaseImpl<TextInputs>;
+    fn run(&mut self, system_time: Self::ReadData, text_inputs: Self::WriteData) {
+        let focus = text_inputs.focus;
+        if let Some(input) = text_inputs.inputs.get_mut(&focus) {
+            let elapsed = system_time.cur_time.saturating_sub(input.blink_time);
+            let visible = (elapsed / BLINK_INTERVAL) % 2 == 0;
+            if visible != input.caret_visible {
+                input.caret_visible = visible;
+                input.dirty = true;
+            }
+        }
+    }
+}
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for TextInputSys {
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<TextInputs>;
+    fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, text_inputs: Self::WriteData) {
+        remove_input(event.id, text_inputs);
+    }
+}
+
+impl_system! {
+    TextInputSys,
+    true,
+    {
+        EntityListener<Node, DeleteEvent>
+    }
+}
+
+fn remove_input(id: usize, text_inputs: &mut TextInputs) -> bool {
+    if text_inputs.focus == id {
+        text_inputs.focus = 0;
+    }
+    text_inputs.drags.retain(|_, node| *node != id);
+    text_inputs.inputs.remove(&id).is_some()
+}
+
+// 布局后的一个字符
+#[derive(Debug, Clone, Copy)]
+struct Glyph {
+    source: usize, // 在显示的文字中的索引
+    x: f32, // 相对于节点的边框盒
+    width: f32,
+    line: usize,
+    newline: bool,
+    rtl: bool,
+}
+
+/// 输入框的文字布局， 坐标相对于节点的边框盒， 文字索引为显示的文字（见TextInput::display_text）中的字符索引
+pub(crate) struct TextLayout {
+    glyphs: Vec<Glyph>, // 可见的字符， 按逻辑顺序
+    lines: Vec<Option<(f32, f32)>>, // 每行的顶部和底部， 行内没有可见字符时为None
+    empty: (f32, f32, f32), // 空行中光标的水平位置、顶部及高度
+}
+
+impl TextLayout {
+    pub(crate) fn new(char_block: &CharBlock, text: &str, text_style: Option<&TextStyle>) -> Self {
+        let merge_whitespace = text_style.map_or(false, |r| r.text.white_space.preserve_spaces());
+        let sources = char_sources(text, merge_whitespace);
+        let mut lines = vec![None; char_block.lines.len()];
+        let mut glyphs = Vec::with_capacity(sources.len());
+        let mut line = 0;
+        // 图文混排的字符由flex布局排列， 不在行中
+        for (index, x, y) in char_positions(char_block).into_iter() {
+            let source = match sources.get(index) {
+                Some(Some(r)) => *r,
+                _ => continue,
+            };
+            while line < char_block.lines.len() && index >= char_block.lines[line].end {
+                line += 1;
+            }
+            if line >= char_block.lines.len() {
+                break;
+            }
+            let c = &char_block.chars[index];
+            // 字符在行内底端对齐
+            if lines[line].is_none() {
+                let bottom = y + c.size.1;
+                lines[line] = Some((bottom - char_block.lines[line].height, bottom));
+            }
+            glyphs.push(Glyph {
+                source: source,
+                x: x,
+                width: c.size.0,
+                line: line,
+                newline: c.ch == '\n',
+                rtl: char_block.levels.get(index).map_or(false, |l| l % 2 == 1),
+            });
+        }
+
+        let (x, y, width, height) = char_block.content_box;
+        let line_height = char_block.style.line_height;
+        let rtl = char_block.style.rtl;
+        let left = match (char_block.style.text_align, rtl) {
+            (TextAlign::Center, _) => x + width / 2.0,
+            (TextAlign::Right, _) | (TextAlign::End, false) | (TextAlign::Start, true) => x + width,
+            _ => x,
+        };
+        let top = match char_block.style.vertical_align {
+            VerticalAlign::Top => y,
+            VerticalAlign::Middle => y + (height - line_height) / 2.0,
+            VerticalAlign::Bottom => y + height - line_height,
+        };
+        TextLayout {
+            glyphs: glyphs,
+            lines: lines,
+            empty: (left, top, line_height),
+        }
+    }
+
+    /// 光标的位置， 返回(水平位置, 顶部, 高度)
+    pub(crate) fn caret(&self, index: usize) -> (f32, f32, f32) {
+        if let Some(g) = self.glyphs.iter().find(|g| g.source >= index) {
+            let (top, bottom) = self.line_range(g.line);
+            let x = if g.rtl { g.x + g.width } else { g.x };
+            return (x, top, bottom - top);
+        }
+        match self.glyphs.last() {
+            // 以换行符结尾时， 光标在新的空行上
+            Some(g) if g.newline => {
+                let bottom = self.line_range(g.line).1;
+                (self.empty.0, bottom, self.empty.2)
+            }
+            Some(g) => {
+                let (top, bottom) = self.line_range(g.line);
+                let x = if g.rtl { g.x } else { g.x + g.width };
+                (x, top, bottom - top)
+            }
+            None => self.empty,
+        }
+    }
+
+    /// 离点最近的光标位置
+    pub(crate) fn index_at(&self, x: f32, y: f32) -> usize {
+        let last = match self.glyphs.last() {
+            Some(r) => *r,
+            None => return 0,
+        };
+        let mut line = last.line;
+        for (i, r) in self.lines.iter().enumerate() {
+            if let Some((_, bottom)) = r {
+                if y < *bottom {
+                    line = i;
+                    break;
+                }
+            }
+        }
+        // 点在以换行符结尾的文字之下， 光标在最后的空行上
+        if last.newline && line == last.line && y >= self.line_range(line).1 {
+            return last.source + 1;
+        }
+
+        let (mut index, mut distance) = (last.source + 1, std::f32::INFINITY);
+        for g in self.glyphs.iter().filter(|g| g.line == line) {
+            let (before, after) = if g.rtl { (g.x + g.width, g.x) } else { (g.x, g.x + g.width) };
+            if (x - before).abs() < distance {
+                index = g.source;
+                distance = (x - before).abs();
+            }
+            // 光标不能在换行符之后（那是下一行的行首）
+            if !g.newline && (x - after).abs() < distance {
+                index = g.source + 1;
+                distance = (x - after).abs();
+            }
+        }
+        index
+    }
+
+    /// 选中[start, end)范围的文字时， 每行需要高亮的矩形(x, y, 宽, 高)
+    pub(crate) fn selection(&self, start: usize, end: usize) -> Vec<(f32, f32, f32, f32)> {
+        let mut rects: Vec<(usize, f32, f32)> = Vec::new();
+        for g in self.glyphs.iter().filter(|g| g.source >= start && g.source < end) {
+            // 换行符没有宽度， 选中时显示为较窄的矩形
+            let width = if g.newline { self.empty.2 / 4.0 } else { g.width };
+            match rects.last_mut() {
+                Some(r) if r.0 == g.line => {
+                    r.1 = r.1.min(g.x);
+                    r.2 = r.2.max(g.x + width);
+                }
+                _ => rects.push((g.line, g.x, g.x + width)),
+            }
+        }
+        rects
+            .into_iter()
+            .map(|(line, left, right)| {
+                let (top, bottom) = self.line_range(line);
+                (left, top, right - left, bottom - top)
+            })
+            .collect()
+    }
+
+    /// 第一行的顶部和最后一行的底部
+    pub(crate) fn bounds(&self) -> (f32, f32) {
+        let mut iter = self.lines.iter().filter_map(|r| *r);
+        match iter.next() {
+            Some(first) => (first.0, iter.last().unwrap_or(first).1),
+            None => (self.empty.1, self.empty.1 + self.empty.2),
+        }
+    }
+
+    #[inline]
+    fn line_range(&self, line: usize) -> (f32, f32) {
+        self.lines[line].unwrap_or((self.empty.1, self.empty.1 + self.empty.2))
+    }
+}
+
+// 文字块中每个字符对应的源文字字符索引， 单词容器及省略号为None
+// 与文字布局劈分文字的方式一致（见TextLayoutSys的简单布局）
+fn char_sources(text: &str, merge_whitespace: bool) -> Vec<Option<usize>> {
+    let mut r = Vec::with_capacity(text.len());
+    for (index, cr) in split(text, true, merge_whitespace).indices() {
+        match cr {
+            SplitResult::WordStart(_) => {
+                r.push(None);
+                r.push(Some(index));
+            }
+            SplitResult::WordEnd => (),
+            _ => r.push(Some(index)),
+        }
+    }
+    r
+}
+
+// 节点的文字布局， 节点不是输入框或还未布局时返回None
+fn text_layout<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, input: &TextInput) -> Option<TextLayout> {
+    let char_blocks = world.char_block.lend();
+    let char_block = char_blocks.get(node)?;
+    Some(TextLayout::new(char_block, input.display_text().as_str(), world.text_style.lend().get(node)))
+}
+
+// 世界坐标系下的点转换到节点布局框的局部坐标系（左上角为原点）
+fn to_local<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, x: f32, y: f32) -> Option<(f32, f32)> {
+    let layouts = world.layout.lend();
+    let layout = layouts.get(node)?;
+    let (width, height) = (layout.rect.end - layout.rect.start, layout.rect.bottom - layout.rect.top);
+    let origin = match world.transform.lend().get(node) {
+        Some(r) => r.origin.to_value(width, height),
+        None => TransformOrigin::Center.to_value(width, height),
+    };
+    let m = world.world_matrix.lend().get(node)?.invert()?;
+    let p = &m * Vector4::new(x, y, 0.0, 1.0);
+    Some((p.x + origin.x, p.y + origin.y))
+}
+
+// 编辑缓冲区或组合中的文字改变， 重新设置节点的文字内容
+fn update_text<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, input: &TextInput) {
+    let text_spans = world.text_spans.lend_mut();
+    if text_spans.get(node).is_some() {
+        text_spans.delete(node);
+    }
+    world
+        .text_content
+        .lend_mut()
+        .insert(node, TextContent(input.display_text(), Atom::from("")));
+}
+
+// 编辑完成， 重新开始闪烁光标； 编辑缓冲区改变时更新文字内容， user为true时通知监听器
+fn finish_edit<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, text_changed: bool, user: bool) {
+    let listener = {
+        let text_inputs = world.text_inputs.lend_mut();
+        let input = match text_inputs.inputs.get_mut(&node) {
+            Some(r) => r,
+            None => return,
+        };
+        input.blink_time = world.system_time.lend().cur_time;
+        input.caret_visible = true;
+        input.dirty = true;
+        if !text_changed {
+            return;
+        }
+        update_text(world, node, input);
+        if user {
+            text_inputs.on_input.clone()
+        } else {
+            None
+        }
+    };
+    // 监听器中可能再次修改输入框， 调用时不能持有借用
+    if let Some(listener) = listener {
+        listener(node);
+    }
+}
+
+/// 将文字节点设置为输入框， 输入框保留空白符， 单行输入框不折行
+pub fn create_text_input<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, multiline: bool) {
+    let text_styles = world.text_style.lend_mut();
+    if let Some(r) = text_styles.get_mut(node) {
+        r.text.white_space = if multiline { WhiteSpace::PreWrap } else { WhiteSpace::Pre };
+    }
+    text_styles.get_notify_ref().modify_event(node, "white_space", 0);
+
+    let mut input = TextInput::new(multiline);
+    if let Some(r) = world.text_content.lend().get(node) {
+        input.set_value(r.0.as_str());
+    }
+    world.text_inputs.lend_mut().inputs.insert(node, input);
+    finish_edit(world, node, true, false);
+}
+
+/// 取消节点的输入框， 保留文字内容， 返回节点是否为输入框
+pub fn remove_text_input<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> bool {
+    remove_input(node, world.text_inputs.lend_mut())
+}
+
+/// 设置编辑缓冲区（不通知监听器）， 光标移到末尾， 返回节点是否为输入框
+pub fn set_text_input_value<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, value: &str) -> bool {
+    match world.text_inputs.lend_mut().inputs.get_mut(&node) {
+        Some(r) => r.set_value(value),
+        None => return false,
+    }
+    finish_edit(world, node, true, false);
+    true
+}
+
+/// 编辑缓冲区的内容
+pub fn text_input_value<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> Option<String> {
+    world.text_inputs.lend().inputs.get(&node).map(|r| r.value.clone())
+}
+
+/// 设置选区， anchor为选区的固定端， caret为光标所在端， 返回节点是否为输入框
+pub fn set_text_input_selection<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, anchor: usize, caret: usize) -> bool {
+    match world.text_inputs.lend_mut().inputs.get_mut(&node) {
+        Some(r) => r.select(anchor, caret),
+        None => return false,
+    }
+    finish_edit(world, node, false, false);
+    true
+}
+
+/// 选区的起点和终点
+pub fn text_input_selection<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> Option<(usize, usize)> {
+    world.text_inputs.lend().inputs.get(&node).map(|r| r.selection())
+}
+
+/// 设置光标和选区的颜色
+pub fn set_text_input_colors<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, caret: CgColor, selection: CgColor) -> bool {
+    match world.text_inputs.lend_mut().inputs.get_mut(&node) {
+        Some(r) => {
+            r.caret_color = caret;
+            r.selection_color = selection;
+            r.dirty = true;
+            true
+        }
+        None => false,
+    }
+}
+
+/// 使输入框获得焦点， node为0时当前输入框失去焦点， 返回节点是否为输入框
+/// 只有获得焦点的输入框显示光标和选区
+pub fn focus_text_input<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> bool {
+    let text_inputs = world.text_inputs.lend_mut();
+    if node > 0 && !text_inputs.inputs.contains_key(&node) {
+        return false;
+    }
+    let old = text_inputs.focus;
+    text_inputs.focus = node;
+    if let Some(r) = text_inputs.inputs.get_mut(&old) {
+        r.dirty = true;
+    }
+    if node > 0 {
+        finish_edit(world, node, false, false);
+    }
+    true
+}
+
+/// 获得焦点的输入框， 0表示没有
+pub fn focused_text_input<C: HalContext + 'static>(world: &GuiWorld<C>) -> usize {
+    world.text_inputs.lend().focus
+}
+
+/// 在光标处插入文字（替换选区）， 用于键入字符及粘贴， 返回节点是否为输入框
+pub fn text_input_insert<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, text: &str) -> bool {
+    let changed = match world.text_inputs.lend_mut().inputs.get_mut(&node) {
+        Some(r) => r.insert(text),
+        None => return false,
+    };
+    finish_edit(world, node, changed, true);
+    true
+}
+
+/// 设置输入法正在组合的文字（显示在光标处， 不属于编辑缓冲区）， 返回节点是否为输入框
+pub fn set_text_input_composition<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, text: &str) -> bool {
+    let deleted = match world.text_inputs.lend_mut().inputs.get_mut(&node) {
+        Some(r) => r.set_composition(text),
+        None => return false,
+    };
+    // 组合中的文字改变也需要更新文字内容
+    finish_edit(world, node, true, deleted);
+    true
+}
+
+/// 结束输入法组合， 将确定的文字插入光标处（text为空表示取消组合）， 返回节点是否为输入框
+pub fn commit_text_input_composition<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, text: &str) -> bool {
+    let changed = match world.text_inputs.lend_mut().inputs.get_mut(&node) {
+        Some(r) => r.commit_composition(text),
+        None => return false,
+    };
+    finish_edit(world, node, true, changed);
+    true
+}
+
+/// 执行编辑命令， extend为true时移动光标并扩展选区， 返回命令是否被处理（单行输入框不处理NewLine， 宿主可据此提交输入）
+pub fn text_input_command<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, command: EditCommand, extend: bool) -> bool {
+    // 上下移动及行首行尾需要文字布局
+    let layout = match command {
+        EditCommand::Up | EditCommand::Down | EditCommand::LineStart | EditCommand::LineEnd => {
+            match world.text_inputs.lend().inputs.get(&node) {
+                Some(input) => text_layout(world, node, input),
+                None => return false,
+            }
+        }
+        _ => None,
+    };
+
+    let (changed, copy, clipboard) = {
+        let text_inputs = world.text_inputs.lend_mut();
+        let clipboard = text_inputs.clipboard.clone();
+        let input = match text_inputs.inputs.get_mut(&node) {
+            Some(r) => r,
+            None => return false,
+        };
+        let len = input.len();
+        let mut copy = None;
+        let changed = match command {
+            EditCommand::Left => {
+                let index = if input.has_selection() && !extend { input.selection().0 } else { input.prev_grapheme(input.caret) };
+                input.move_to(index, extend);
+                false
+            }
+            EditCommand::Right => {
+                let index = if input.has_selection() && !extend { input.selection().1 } else { input.next_grapheme(input.caret) };
+                input.move_to(index, extend);
+                false
+            }
+            EditCommand::WordLeft => {
+                let index = input.word_start(input.caret);
+                input.move_to(index, extend);
+                false
+            }
+            EditCommand::WordRight => {
+                let index = input.word_end(input.caret);
+                input.move_to(index, extend);
+                false
+            }
+            EditCommand::DocStart => {
+                input.move_to(0, extend);
+                false
+            }
+            EditCommand::DocEnd => {
+                input.move_to(len, extend);
+                false
+            }
+            EditCommand::Up | EditCommand::Down | EditCommand::LineStart | EditCommand::LineEnd => {
+                if let Some(layout) = layout {
+                    let (x, top, height) = layout.caret(input.display_caret());
+                    let (first, last) = layout.bounds();
+                    let preferred_x = input.preferred_x.unwrap_or(x);
+                    let index = match command {
+                        // 在第一行向上、最后一行向下时， 移到文字的开头或结尾
+                        EditCommand::Up if top <= first => 0,
+                        EditCommand::Up => layout.index_at(preferred_x, top - 0.5),
+                        EditCommand::Down if top + height >= last => len + input.composition.chars().count(),
+                        EditCommand::Down => layout.index_at(preferred_x, top + height + 0.5),
+                        EditCommand::LineStart => layout.index_at(std::f32::MIN, top + height / 2.0),
+                        _ => layout.index_at(std::f32::MAX, top + height / 2.0),
+                    };
+                    let index = input.to_value_index(index);
+                    input.move_to(index, extend);
+                    if command == EditCommand::Up || command == EditCommand::Down {
+                        input.preferred_x = Some(preferred_x);
+                    }
+                }
+                false
+            }
+            EditCommand::Backspace => input.delete_backward(false),
+            EditCommand::DeleteWordBackward => input.delete_backward(true),
+            EditCommand::Delete => input.delete_forward(false),
+            EditCommand::DeleteWordForward => input.delete_forward(true),
+            EditCommand::NewLine => {
+                if !input.multiline {
+                    return false;
+                }
+                input.insert("\n")
+            }
+            EditCommand::SelectAll => {
+                input.select(0, len);
+                false
+            }
+            EditCommand::Copy => {
+                if input.has_selection() {
+                    copy = Some(input.selected_text());
+                }
+                false
+            }
+            EditCommand::Cut => {
+                if input.has_selection() {
+                    copy = Some(input.selected_text());
+                }
+                input.delete_selection()
+            }
+        };
+        (changed, copy, clipboard)
+    };
+
+    if let (Some(text), Some(clipboard)) = (copy, clipboard) {
+        clipboard(text.as_str());
+    }
+    finish_edit(world, node, changed, true);
+    true
+}
+
+/// 世界坐标系下的点对应的光标位置（编辑缓冲区中的字符索引）， 节点不是输入框或还未布局时返回None
+pub fn text_input_index_at<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, x: f32, y: f32) -> Option<usize> {
+    let (x, y) = to_local(world, node, x, y)?;
+    let text_inputs = world.text_inputs.lend();
+    let input = text_inputs.inputs.get(&node)?;
+    let layout = text_layout(world, node, input)?;
+    Some(input.to_value_index(layout.index_at(x, y)))
+}
+
+/// 光标在世界坐标系下的位置， 返回(x, 顶部y, 底部y)， 宿主可据此放置输入法的候选窗口
+pub fn text_input_caret_position<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> Option<(f32, f32, f32)> {
+    let (x, top, height) = {
+        let text_inputs = world.text_inputs.lend();
+        let input = text_inputs.inputs.get(&node)?;
+        text_layout(world, node, input)?.caret(input.display_caret())
+    };
+    let layouts = world.layout.lend();
+    let layout = layouts.get(node)?;
+    let (width, h) = (layout.rect.end - layout.rect.start, layout.rect.bottom - layout.rect.top);
+    let origin = match world.transform.lend().get(node) {
+        Some(r) => r.origin.to_value(width, h),
+        None => TransformOrigin::Center.to_value(width, h),
+    };
+    let world_matrixs = world.world_matrix.lend();
+    let m: &WorldMatrix = world_matrixs.get(node)?;
+    let p0 = m * Vector4::new(x - origin.x, top - origin.y, 0.0, 1.0);
+    let p1 = m * Vector4::new(x - origin.x, top + height - origin.y, 0.0, 1.0);
+    Some((p0.x, p0.y, p1.y))
+}
+
+/// 输入指针事件， path为命中节点的祖先路径（见event_path）， 返回指针是否在输入框中选择文字（选择时不拖动滚动容器）
+/// PointerDown使路径上第一个输入框获得焦点并移动光标（未命中输入框时， 当前输入框失去焦点）， 之后的PointerMove扩展选区
+pub fn text_input_pointer_event<C: HalContext + 'static>(world: &GuiWorld<C>, event: &Event, path: &[usize]) -> bool {
+    match event.ty {
+        EventType::PointerDown => {
+            if event.button != 0 {
+                return false;
+            }
+            let node = {
+                let text_inputs = world.text_inputs.lend();
+                path.iter().find(|id| text_inputs.inputs.contains_key(*id)).cloned().unwrap_or(0)
+            };
+            focus_text_input(world, node);
+            if node == 0 {
+                return false;
+            }
+            let index = text_input_index_at(world, node, event.x, event.y);
+            let text_inputs = world.text_inputs.lend_mut();
+            text_inputs.drags.insert(event.pointer_id, node);
+            if let (Some(index), Some(input)) = (index, text_inputs.inputs.get_mut(&node)) {
+                input.move_to(index, false);
+            }
+            finish_edit(world, node, false, false);
+            true
+        }
+        EventType::PointerMove => {
+            let node = match world.text_inputs.lend().drags.get(&event.pointer_id) {
+                Some(r) => *r,
+                None => return false,
+            };
+            let index = text_input_index_at(world, node, event.x, event.y);
+            let moved = match (index, world.text_inputs.lend_mut().inputs.get_mut(&node)) {
+                (Some(index), Some(input)) if index != input.caret => {
+                    input.move_to(index, true);
+                    true
+                }
+                _ => false,
+            };
+            if moved {
+                finish_edit(world, node, false, false);
+            }
+            true
+        }
+        EventType::PointerUp | EventType::PointerCancel => world.text_inputs.lend_mut().drags.remove(&event.pointer_id).is_some(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+use component::calc::LineBox;
+#[cfg(test)]
+use flex_layout::CharNode;
+
+#[test]
+fn test_text_input() {
+    // 编辑缓冲区
+    let mut input = TextInput::new(false);
+    input.set_value("hello world");
+    assert_eq!((input.caret, input.word_start(11), input.word_start(5)), (11, 6, 0));
+    input.move_to(6, false);
+    input.move_to(11, true);
+    assert_eq!(input.selected_text(), "world");
+    assert!(input.insert("a\r\nb"));
+    assert_eq!((input.value.as_str(), input.caret), ("hello a b", 9));
+    assert!(input.delete_backward(true));
+    assert_eq!(input.value, "hello a ");
+    // 输入法组合的文字显示在光标处， 不属于编辑缓冲区
+    input.move_to(6, false);
+    input.set_composition("中文");
+    assert_eq!((input.display_text().as_str(), input.display_caret()), ("hello 中文a ", 8));
+    assert_eq!((input.to_value_index(7), input.to_value_index(9)), (6, 7));
+    assert!(input.commit_composition("中"));
+    assert_eq!((input.value.as_str(), input.caret, input.composition.len()), ("hello 中a ", 7, 0));
+
+    // 删除和移动光标以字素簇为单位， 组合字符和emoji序列不被拆分
+    input.set_value("ae\u{301}b👨\u{200D}👩\u{200D}👧c");
+    assert_eq!(input.len(), 10);
+    input.move_to(3, false);
+    assert_eq!((input.prev_grapheme(3), input.next_grapheme(1)), (1, 3));
+    assert!(input.delete_backward(false));
+    assert_eq!((input.value.as_str(), input.caret), ("ab👨\u{200D}👩\u{200D}👧c", 1));
+    input.move_to(2, false);
+    assert_eq!(input.next_grapheme(2), 7);
+    assert!(input.delete_forward(false));
+    assert_eq!((input.value.as_str(), input.caret), ("abc", 2));
+    input.set_value("a👨\u{200D}👩\u{200D}👧");
+    assert_eq!(input.prev_grapheme(input.caret), 1);
+    assert!(input.delete_backward(false));
+    assert_eq!(input.value, "a");
+
+    // 文字布局： 两行， 第一行"ab cd"（"ab"、"cd"为单词）， 第二行"ef"， 字符宽10， 行高20
+    let text = "ab cd\nef";
+    let sources = char_sources(text, false);
+    assert_eq!(sources, vec![None, Some(0), Some(1), Some(2), None, Some(3), Some(4), Some(5), None, Some(6), Some(7)]);
+    let mut block = CharBlock::default();
+    block.style.line_height = 20.0;
+    let mut push = |ch: char, count: usize, pos: (f32, f32)| {
+        block.chars.push(CharNode {
+            ch: ch,
+            size: (if ch == '\0' { 20.0 } else if ch == '\n' { 0.0 } else { 10.0 }, 20.0),
+            margin_start: 0.0,
+            pos: pos,
+            base_width: 10.0,
+            ch_id_or_count: count,
+        });
+    };
+    push('\0', 3, (0.0, 0.0));
+    push('a', 0, (0.0, 0.0));
+    push('b', 0, (10.0, 0.0));
+    push(' ', 0, (20.0, 0.0));
+    push('\0', 3, (30.0, 0.0));
+    push('c', 0, (0.0, 0.0));
+    push('d', 0, (10.0, 0.0));
+    push('\n', 0, (50.0, 0.0));
+    push('\0', 3, (0.0, 20.0));
+    push('e', 0, (0.0, 0.0));
+    push('f', 0, (10.0, 0.0));
+    block.visible = block.chars.len();
+    for &(start, end) in [(0, 8), (8, 11)].iter() {
+        block.lines.push(LineBox { start: start, end: end, width: 0.0, height: 20.0, gaps: 0, hard: true });
+    }
+    let layout = TextLayout::new(&block, text, None);
+    assert_eq!(layout.caret(0), (0.0, 0.0, 20.0));
+    assert_eq!(layout.caret(4), (40.0, 0.0, 20.0));
+    assert_eq!(layout.caret(6), (0.0, 20.0, 20.0));
+    assert_eq!(layout.caret(8), (20.0, 20.0, 20.0));
+    // 指针位置转换为最近的光标位置， 行尾之后为换行符之前
+    assert_eq!(layout.index_at(14.0, 5.0), 1);
+    assert_eq!(layout.index_at(16.0, 25.0), 8);
+    assert_eq!(layout.index_at(100.0, 5.0), 5);
+    assert_eq!(layout.index_at(-5.0, 100.0), 6);
+    // 跨行的选区每行一个矩形
+    assert_eq!(layout.selection(1, 7), vec![(10.0, 0.0, 45.0, 20.0), (0.0, 20.0, 10.0, 20.0)]);
+}
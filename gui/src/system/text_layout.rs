@@ -1,7 +1,9 @@
 // 文字布局及布局系统
-// 文本节点的布局算法： 文本节点本身所对应的yoga节点总是一个0大小的节点。文本节点的父节点才是进行文本布局的节点，称为P节点。P节点如果没有设置布局，则默认用flex布局模拟文档流布局。会将文本拆成每个字（英文为单词）的yoga节点加入P节点上。这样可以支持图文混排。P节点如果有flex布局，则遵循该布局。
+// 文本节点的布局算法： 文字被拆分为字符（英文为单词）， 存放在节点的CharBlock中。
+// 如果文字节点是父节点唯一的子节点， 或自身设置了宽高， 使用行布局（font::line_box）排列字符， flex布局只关心文字块的大小（见LayoutSys）。
+// 否则为图文混排， 文本节点为虚拟节点， 每个字（英文为单词）创建一个flex节点加入父节点， 与其它兄弟节点一起布局。
 // 字节点，根据字符是否为单字决定是需要字符容器还是单字。
-// 文字根据样式，会处理：缩进，是否合并空白符，是否自动换行，是否允许换行符。来设置相应的flex布局。 换行符采用高度为0, 宽度100%的yoga节点来模拟。
+use std::collections::VecDeque;
 use std::result::Result;
 use cgmath::InnerSpace;
 
@@ -9,10 +11,11 @@ use ecs::StdCell;
 use ecs::{
 	component::MultiCaseImpl,
 	entity::EntityImpl,
-    monitor::{CreateEvent, ModifyEvent},
+    monitor::{CreateEvent, DeleteEvent, ModifyEvent},
     single::SingleCaseImpl,
-	system::{MultiCaseListener, Runner},
+	system::{EntityListener, MultiCaseListener, Runner},
 };
+use hash::XHashMap;
 
 use share::Share;
 
@@ -21,6 +24,8 @@ use flex_layout::{Dimension, INodeStateType};
 use component::{calc::*, user::*, calc::LayoutR};
 use entity::Node;
 use font::font_sheet::{get_line_height, get_size, split, FontSheet, SplitResult, TexFont};
+use font::line_box;
+use system::util::is_rtl;
 use single::class::*;
 use single::*;
 
@@ -39,12 +44,18 @@ const MARK_LAYOUT: usize = StyleType::LetterSpacing as usize
 
 const MARK: usize = MARK_LAYOUT | StyleType::Text as usize;
 
+// 文字溢出处理方式及最大行数改变， 需要重新计算字符(省略号)
+const MARK_LAYOUT1: usize = StyleType1::TextOverflow as usize | StyleType1::LineClamp as usize;
+// 装饰线改变， 不需要重新布局， 但可能需要计算绘制装饰线的实心字形
+const MARK1: usize = StyleType1::TextDecoration as usize;
+
 type Read<'a> = (
     &'a MultiCaseImpl<Node, TextContent>,
     &'a MultiCaseImpl<Node, ClassName>,
     &'a MultiCaseImpl<Node, WorldMatrix>,
     &'a MultiCaseImpl<Node, StyleMark>,
     &'a SingleCaseImpl<DirtyList>,
+    &'a MultiCaseImpl<Node, TextSpans>,
 );
 type Write<'a> = (
     &'a mut MultiCaseImpl<Node, NodeState>, // TODO
@@ -55,6 +66,7 @@ type Write<'a> = (
 	&'a mut SingleCaseImpl<Share<StdCell<FontSheet>>>,
 	&'a mut SingleCaseImpl<IdTree>,
 	&'a mut EntityImpl<Node>,
+	&'a mut MultiCaseImpl<Node, CharBlock>,
 );
 
 pub struct LayoutImpl {
@@ -62,53 +74,111 @@ pub struct LayoutImpl {
     write: usize,
 }
 
-pub struct TextGlphySys;
+// 字形系统， 记录每个文字节点引用的字形， 字形改变或节点删除时释放引用， 不再被引用的字形可以被回收
+// 所有纹理页都已满时， 回收最久未使用的一页， 只有引用了该页上字形的节点需要重新计算字形
+#[derive(Default)]
+pub struct TextGlphySys {
+	glyphs: XHashMap<usize, Vec<usize>>, // 节点引用的字形
+	retry: Vec<usize>, // 本帧回收的页数已达上限， 未能计算字形的节点， 下一帧重新计算
+}
+
+impl TextGlphySys {
+	fn release(&mut self, id: usize, font_sheet: &SingleCaseImpl<Share<StdCell<FontSheet>>>) {
+		if let Some(glyphs) = self.glyphs.remove(&id) {
+			let font_sheet = &mut font_sheet.borrow_mut();
+			for glyph in glyphs.into_iter() {
+				font_sheet.release_glyph(glyph);
+			}
+		}
+	}
+
+	// 回收最久未使用的纹理页， 返回引用了该页上字形的节点， 这些节点的字形已被释放
+	fn evict_page(&mut self, font_sheet: &mut FontSheet, char_blocks: &mut MultiCaseImpl<Node, CharBlock>) -> Vec<usize> {
+		let page = match font_sheet.lru_page() {
+			Some(r) => r,
+			None => return Vec::new(),
+		};
+		let evicted = font_sheet.clear_page(page);
+		let nodes: Vec<usize> = self
+			.glyphs
+			.iter()
+			.filter(|(_, glyphs)| glyphs.iter().any(|glyph| evicted.contains(glyph)))
+			.map(|(id, _)| *id)
+			.collect();
+		// 在分配新的字形之前释放， 避免释放到复用了相同id的新字形
+		for id in nodes.iter() {
+			for glyph in self.glyphs.remove(id).unwrap().into_iter() {
+				font_sheet.release_glyph(glyph);
+			}
+			// 被回收的字形不能再绘制
+			if let Some(char_block) = char_blocks.get_mut(*id) {
+				for char_node in char_block.chars.iter_mut() {
+					if char_node.ch > ' ' {
+						char_node.ch_id_or_count = 0;
+					}
+				}
+				char_block.solid_glyph = 0;
+			}
+		}
+		nodes
+	}
+}
 
 impl<'a> Runner<'a> for TextGlphySys {
     type ReadData = Read<'a>;
     type WriteData = Write<'a>;
 
     fn run(&mut self, read: Self::ReadData, mut write: Self::WriteData) {
-		let mut flag = true;
-		let mut count = 0;
-		while flag {
-			flag = false;
-			count += 1;
-			if count > 2 { // 迭代了两次以上，则可能进入了死循环，报错
-				panic!("TextGlphySys 死循环");
+		let notify = read.0.get_notify_ref();
+		let mut queue: VecDeque<usize> = dirty_texts(&read, &write.6).into_iter().map(|r| r.0).collect();
+		// 上一帧未能计算字形的节点， 发送修改事件， 使其重新绘制
+		for id in std::mem::replace(&mut self.retry, Vec::new()).into_iter() {
+			if read.0.get(id).is_some() && !queue.contains(&id) {
+				notify.modify_event(id, "", 0);
+				queue.push_back(id);
 			}
-			for id in (read.4).0.iter() {
-				let r = match read.3.get(*id) {
-					Some(r) => r,
-					None => continue,
-				};
-	
-				if (r.dirty & MARK == 0) || read.0.get(*id).is_none(){
-					continue;
+		}
+		// 每帧最多回收的页数， 超过时说明本帧的文字无法同时放入所有纹理页， 剩余的节点下一帧再计算
+		let max_evict = write.5.borrow().font_tex.pages.len();
+		let mut evict_count = 0;
+		while let Some(id) = queue.pop_front() {
+			if set_gylph(id, &read, &mut write, &mut self.glyphs).is_ok() {
+				continue;
+			}
+			if evict_count >= max_evict {
+				self.retry.push(id);
+				continue;
+			}
+			evict_count += 1;
+			let nodes = self.evict_page(&mut write.5.borrow_mut(), &mut write.8);
+			for node in nodes.into_iter() {
+				if node != id && !queue.contains(&node) {
+					notify.modify_event(node, "", 0);
+					queue.push_back(node);
 				}
-	
-				match set_gylph(*id, &read, &mut write) {
-					Result::Err(_) => {
-						write.5.borrow_mut().clear_gylph();
-						// 对界面上的文字全部重新计算字形
-						let idtree = &write.6;
-						let root = &idtree[0];
-						let notify = read.0.get_notify_ref();
-						for (id, _node) in idtree.recursive_iter(root.children().head) {
-							if read.0.get(id).is_some() { // 文字节点，发送修改事件
-								notify.modify_event(id, "", 0)
-							}
-						}
-						flag = true; // 重新迭代
-						break;
-					},
-					_ => ()
-				};
 			}
+			queue.push_front(id);
 		}
     }
 }
 
+impl<'a> MultiCaseListener<'a, Node, TextContent, DeleteEvent> for TextGlphySys {
+	type ReadData = ();
+	type WriteData = (&'a mut SingleCaseImpl<Share<StdCell<FontSheet>>>, &'a mut MultiCaseImpl<Node, CharBlock>);
+	fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, (font_sheet, char_blocks): Self::WriteData) {
+		self.release(event.id, font_sheet);
+		char_blocks.delete(event.id);
+	}
+}
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for TextGlphySys {
+	type ReadData = ();
+	type WriteData = &'a mut SingleCaseImpl<Share<StdCell<FontSheet>>>;
+	fn listen(&mut self, event: &DeleteEvent, _: Self::ReadData, font_sheet: Self::WriteData) {
+		self.release(event.id, font_sheet);
+	}
+}
+
 impl<'a> LayoutImpl {
     pub fn new() -> Self {
         LayoutImpl {
@@ -138,23 +208,40 @@ impl<'a> Runner<'a> for LayoutImpl {
 	
     fn run(&mut self, read: Self::ReadData, mut write: Self::WriteData) {
 		
-		// 暂时拷贝， TODO
-		let dirty_list = (read.4).0.clone();
 		// let time = std::time::Instant::now();
-        for id in dirty_list.iter() {
-            let r = match read.3.get(*id) {
-                Some(r) => r,
-                None => continue,
-			};
+        for (id, layout_dirty) in dirty_texts(&read, &write.6).into_iter() {
+			// println!("text dirty===================id:{}, layout_dirty:{}", id, layout_dirty);
+            calc(id, &read, &mut write, layout_dirty);
+		}
+	}
+}
 
-			
-            if (r.dirty & MARK == 0) || read.0.get(*id).is_none(){
-                continue;
+// 需要重新计算的文字节点及其布局脏
+// 节点的书写方向改变时， 其自身及子孙中的文字节点都需要重新计算(双向重排及镜像字符)
+fn dirty_texts(read: &Read, idtree: &IdTree) -> Vec<(usize, usize)> {
+	let (text_contents, style_marks, dirty_list) = (read.0, read.3, read.4);
+	let mut r = Vec::new();
+	for id in dirty_list.0.iter() {
+		let mark = match style_marks.get(*id) {
+			Some(r) => r,
+			None => continue,
+		};
+		if mark.dirty1 & StyleType1::Direction as usize != 0 {
+			if text_contents.get(*id).is_some() {
+				r.push((*id, MARK_LAYOUT));
+			}
+			for (child, _) in idtree.recursive_iter(idtree[*id].children().head) {
+				if text_contents.get(child).is_some() {
+					r.push((child, MARK_LAYOUT));
+				}
 			}
-			// println!("text dirty===================textContent dirty{:?}, layout_dirty:{}, dirty:{}, id:{}", r.dirty & StyleType::Text as usize, r.dirty & MARK_LAYOUT, r.dirty, id);
-            calc(*id, &read, &mut write, r.dirty & MARK_LAYOUT);
+		} else if mark.dirty1 & MARK_LAYOUT1 != 0 && text_contents.get(*id).is_some() {
+			r.push((*id, MARK_LAYOUT));
+		} else if (mark.dirty & MARK != 0 || mark.dirty1 & MARK1 != 0) && text_contents.get(*id).is_some() {
+			r.push((*id, mark.dirty & MARK_LAYOUT));
 		}
 	}
+	r
 }
 
 #[derive(Default)]
@@ -165,11 +252,12 @@ impl<'a> Runner<'a> for TextLayoutUpdateSys {
     type WriteData = (
 		&'a mut MultiCaseImpl<Node, NodeState>,
 		&'a mut MultiCaseImpl<Node, LayoutR>,
-		&'a mut SingleCaseImpl<IdTree>);
+		&'a mut SingleCaseImpl<IdTree>,
+		&'a mut MultiCaseImpl<Node, CharBlock>);
 
     fn run(&mut self, _resd: Self::ReadData, write: Self::WriteData) {
         for id in self.0.iter() {
-			update_layout(*id, write.0, write.1,write.2);
+			update_layout(*id, write.0, write.1,write.2, write.3);
 		}
 		self.0.clear();
     }
@@ -180,10 +268,11 @@ impl<'a> MultiCaseListener<'a, Node, LayoutR, ModifyEvent> for TextLayoutUpdateS
     type WriteData = (
 		&'a mut MultiCaseImpl<Node, NodeState>,
 		&'a mut MultiCaseImpl<Node, LayoutR>,
-		&'a mut SingleCaseImpl<IdTree>);
+		&'a mut SingleCaseImpl<IdTree>,
+		&'a mut MultiCaseImpl<Node, CharBlock>);
     fn listen(&mut self, event: &ModifyEvent, text_contents: Self::ReadData, write: Self::WriteData) {
 		let id = event.id;
-		// 如果是虚拟节点，并且是文字节点，需要将字符的布局结果拷贝到INode中
+		// 如果是虚拟节点，并且是文字节点，需要将字符的布局结果拷贝到CharBlock中
 		if write.0[id].0.is_vnode() && text_contents.get(id).is_some() {
 			self.0.push(id);
 		}
@@ -196,10 +285,13 @@ fn update_layout(
 	node_states: &mut MultiCaseImpl<Node, NodeState>,
 	layout_rs: &mut MultiCaseImpl<Node, LayoutR>,
 	idtree: &SingleCaseImpl<IdTree>,
+	char_blocks: &mut MultiCaseImpl<Node, CharBlock>,
 ) {
 	let n = &idtree[id];
-	let node_state = unsafe{&mut *((&node_states[id]) as *const NodeState as *mut NodeState)};
-	let chars = &mut node_state.0.text;
+	let chars = match char_blocks.get_mut(id) {
+		Some(r) => &mut r.chars,
+		None => return,
+	};
 	let mut rect = Rect{
 		start: std::f32::MAX,
 		end: 0.0,
@@ -232,52 +324,181 @@ fn update_layout(
 	}
 }
 
+// 富文本片段的字体， 索引0为节点自身的文字样式， 其后依次为每个片段
+struct RunFont {
+	tex_font: TexFont,
+	font_size: f32,
+	font_height: f32,
+	weight: usize,
+	sw: f32,
+	line_height: f32,
+	image: Option<(f32, f32)>, // 行内图片的尺寸
+}
+
+// 计算节点及每个富文本片段的字体， 节点的字体不存在时返回None
+// 片段的字体不存在时， 使用节点的字体
+fn run_fonts(id: usize, text_style: &TextStyle, text_spans: Option<&TextSpans>, font_sheet: &FontSheet) -> Option<Vec<RunFont>> {
+	let (tex_font, default_size) = match font_sheet.get_font_info(&text_style.font.family) {
+		Some(r) => (r.0.clone(), r.1),
+		None => {
+			println!(
+				"font is not exist, face_name: {:?}, id: {:?}",
+				text_style.font.family.as_ref(),
+				id
+			);
+			return None;
+		}
+	};
+	let run_font = |tex_font: TexFont, default_size: usize, size: &FontSize, weight: usize, sw: f32| {
+		let font_size = get_size(default_size, size) as f32;
+		let font_height = tex_font.get_font_height(font_size as usize, sw);
+		RunFont {
+			tex_font,
+			font_size,
+			font_height,
+			weight,
+			sw,
+			line_height: get_line_height(font_height as usize, &text_style.text.line_height),
+			image: None,
+		}
+	};
+	let (font, sw) = (&text_style.font, text_style.text.stroke.width);
+	let mut r = vec![run_font(tex_font.clone(), default_size, &font.size, font.weight, sw)];
+	if let Some(spans) = text_spans {
+		for span in spans.0.iter() {
+			let (tex_font, default_size) = match span.font_family.as_ref().and_then(|f| font_sheet.get_font_info(f)) {
+				Some(f) => (f.0.clone(), f.1),
+				None => (tex_font.clone(), default_size),
+			};
+			let mut run = run_font(
+				tex_font,
+				default_size,
+				span.font_size.as_ref().unwrap_or(&font.size),
+				span.font_weight.unwrap_or(font.weight),
+				span.stroke.as_ref().map_or(sw, |s| s.width),
+			);
+			if let Some(image) = &span.image {
+				let w = if image.width > 0.0 { image.width } else { run.line_height };
+				let h = if image.height > 0.0 { image.height } else { run.line_height };
+				run.image = Some((w, h));
+			}
+			r.push(run);
+		}
+	}
+	Some(r)
+}
+
 // 设置字形的id
 fn set_gylph<'a>(
 	id: usize, 
-	(_text_contents, _class_names, world_matrixs, _style_marks, _dirty_list): &Read, 
-	(node_states, _layout_rs, _rect_layout_styles, _other_layout_styles, text_styles, font_sheet, _idtree, _nodes): &mut Write) -> Result<(), ()> {
+	(_text_contents, _class_names, world_matrixs, _style_marks, _dirty_list, text_spans): &Read, 
+	(node_states, _layout_rs, _rect_layout_styles, _other_layout_styles, text_styles, font_sheet, _idtree, _nodes, char_blocks): &mut Write,
+	glyphs: &mut XHashMap<usize, Vec<usize>>) -> Result<(), ()> {
     let scale = world_matrixs[id].y.magnitude();
 	let text_style = &text_styles[id];
 	let font_sheet = &mut font_sheet.borrow_mut();
-	
-    let (tex_font, font_size) = match font_sheet.get_font_info(&text_style.font.family) {
-        Some(r) => (r.0.clone(), get_size(r.1, &text_style.font.size) as f32),
-        None => {
-            println!(
-                "font is not exist, face_name: {:?}, id: {:?}",
-                text_style.font.family.as_ref(),
-                id
-            );
-            return Ok(());
-        }
+	let text_spans = text_spans.get(id);
+	let fonts = match run_fonts(id, text_style, text_spans, font_sheet) {
+		Some(r) => r,
+		None => return Ok(()),
 	};
-
-	let weight = text_style.font.weight;
-	let sw = text_style.text.stroke.width;
+	// 存在装饰线时， 需要实心字形
+	let decorated = text_style.text.decoration.line != 0
+		|| text_spans.map_or(false, |r| r.0.iter().any(|s| s.decoration.as_ref().map_or(false, |d| d.line != 0)));
 
 	node_states[id].0.scale = scale;
-	let chars = &mut node_states[id].0.text;
-	let mut id;
-	// clear_gylph
-    for char_node in chars.iter_mut(){
-        if char_node.ch > ' ' {
-            id = font_sheet.calc_gylph(
-                &tex_font,
-                font_size as usize,
-                sw as usize,
-                weight,
-                scale,
-                char_node.base_width,
-                char_node.ch,
-            );
-			// 异常，无法计算字形
-			if id == 0 {
-				return Result::Err(());
+	let char_block = match char_blocks.get_mut(id) {
+		Some(r) => r,
+		None => return Ok(()),
+	};
+	// 一个节点的字形必须在同一个纹理页上， 先在任意页上计算， 如果分布在多个页上， 则在最大的页上重新计算
+	let mut page = None;
+	let mut ids = Vec::new();
+	loop {
+		let mut fail = false;
+		let (mut min_page, mut max_page) = (std::usize::MAX, 0);
+		let runs = &char_block.runs;
+		for (index, char_node) in char_block.chars.iter_mut().enumerate() {
+			if char_node.ch > ' ' {
+				let font = &fonts[runs.get(index).cloned().unwrap_or(0)];
+				// 行内图片不需要字形
+				if font.image.is_some() {
+					continue;
+				}
+				let glyph = font_sheet.calc_gylph(
+					&font.tex_font,
+					font.font_size as usize,
+					font.sw as usize,
+					font.weight,
+					scale,
+					char_node.base_width,
+					char_node.ch,
+					page,
+				);
+				// 异常，无法计算字形
+				if glyph == 0 {
+					fail = true;
+					break;
+				}
+				font_sheet.retain_glyph(glyph);
+				ids.push(glyph);
+				let p = font_sheet.glyph_page(glyph);
+				min_page = min_page.min(p);
+				max_page = max_page.max(p);
+				char_node.ch_id_or_count = glyph;
 			}
-			char_node.ch_id_or_count = id;
-        }
-    }
+		}
+		char_block.solid_glyph = 0;
+		if !fail && decorated {
+			let font = &fonts[0];
+			let (_, base_width) = font_sheet.measure(&font.tex_font, font.font_size as usize, font.sw as usize, font.weight, '█');
+			let glyph = font_sheet.calc_gylph(&font.tex_font, font.font_size as usize, font.sw as usize, font.weight, scale, base_width, '█', page);
+			if glyph == 0 {
+				fail = true;
+			} else {
+				font_sheet.retain_glyph(glyph);
+				ids.push(glyph);
+				let p = font_sheet.glyph_page(glyph);
+				min_page = min_page.min(p);
+				max_page = max_page.max(p);
+				char_block.solid_glyph = glyph;
+			}
+		}
+		if !fail && (min_page == std::usize::MAX || min_page == max_page) {
+			if min_page != std::usize::MAX {
+				font_sheet.touch_page(min_page);
+			}
+			break;
+		}
+		// 释放本次计算的字形， 在其它页上重新计算
+		for glyph in ids.drain(..) {
+			font_sheet.release_glyph(glyph);
+		}
+		page = if fail {
+			// 指定页上空间不足， 在新的页上计算， 已达最大页数， 由TextGlphySys回收最久未使用的页
+			match font_sheet.font_tex.add_page() {
+				Some(r) => Some(r),
+				None => {
+					// 已释放的字形可能被回收， 不能再绘制
+					for char_node in char_block.chars.iter_mut() {
+						if char_node.ch > ' ' {
+							char_node.ch_id_or_count = 0;
+						}
+					}
+					char_block.solid_glyph = 0;
+					return Result::Err(());
+				}
+			}
+		} else {
+			Some(max_page)
+		};
+	}
+	// 先引用新的字形， 再释放旧的字形， 使两者共同的字形不会被回收
+	if let Some(old) = glyphs.insert(id, ids) {
+		for glyph in old.into_iter() {
+			font_sheet.release_glyph(glyph);
+		}
+	}
 	return Ok(())
 }
 
@@ -294,28 +515,33 @@ struct Calc<'a> {
 
 	text: &'a str,
 	style_mark: &'a StyleMark,
-	tex_font: (TexFont, usize),
 	font_size: f32,
 	font_height: f32,
 	line_height: f32,
-	sw: f32,
 	char_margin: f32,
 	word_margin: f32,
 	text_style: &'a TextStyle,
 	parent: usize,
+
+	text_spans: Option<&'a TextSpans>,
+	fonts: Vec<RunFont>, // 节点及每个富文本片段的字体
+	run: usize, // 当前字符所在的富文本片段
+	runs: Vec<usize>, // 每个字符所在的富文本片段
 }
 
 impl<'a> Calc<'a> {
-	// 将文字样式用flex布局属性替换, 可以考虑不支持文字的布局属性？
+	// 图文混排时， 将文字样式用flex布局属性替换
 	fn fit_text_style(&mut self) {
+		let rtl = is_rtl(self.id, self.idtree, self.other_layout_styles);
 		let (local_style, class_style, local_style2, class_style2, text, other_layout_styles, id) = (self.style_mark.local_style, self.style_mark.class_style, self.style_mark.local_style2, self.style_mark.class_style2, &self.text_style.text, &mut self.other_layout_styles, self.id);
 		// 兼容目前使用父节点的对齐属性来对齐文本， 如果项目将其修改正确， 应该去掉该段TODO
 		if local_style & StyleType::TextAlign as usize > 0 || class_style & StyleType::TextAlign as usize > 0 {
-			other_layout_styles[id].justify_content = match text.text_align {
-				TextAlign::Center => JustifyContent::Center,
-				TextAlign::Right => JustifyContent::FlexEnd,
-				TextAlign::Left => JustifyContent::FlexStart,
-				TextAlign::Justify => JustifyContent::SpaceBetween,
+			// 图文混排的字符不做双向重排， 起始边与结束边按书写方向转换
+			other_layout_styles[id].justify_content = match (text.text_align, rtl) {
+				(TextAlign::Center, _) => JustifyContent::Center,
+				(TextAlign::Right, _) | (TextAlign::Start, true) | (TextAlign::End, false) => JustifyContent::FlexEnd,
+				(TextAlign::Left, _) | (TextAlign::Start, false) | (TextAlign::End, true) => JustifyContent::FlexStart,
+				(TextAlign::Justify, _) => JustifyContent::SpaceBetween,
 			};
 		}
 		
@@ -353,21 +579,18 @@ impl<'a> Calc<'a> {
 		other_layout_styles.get_notify().modify_event(id, "justify_content", 0);
 	}
 
-	// 简单布局， 将文字劈分，单词节点的内部字符使用绝对布局，其余节点使用相对布局
+	// 简单布局， 将文字劈分为字符及单词， 由行布局排列（单词内部字符的位置相对于单词容器）
 	// 与图文混排的布局方式不同，该布局不需要为每个字符节点创建实体
-	fn cacl_simple(&mut self, node_states: &mut MultiCaseImpl<Node, NodeState>) {
+	// 设置了省略号时， 省略号作为一个单词放在字符集合的末尾， 返回其字符数
+	fn cacl_simple(&mut self, node_states: &mut MultiCaseImpl<Node, NodeState>, chars: &mut Vec<CharNode>) -> usize {
 		let (id, text_style) = (self.id, self.text_style);
 		node_states[id].0.set_vnode(false);
 		
-		let chars = &mut node_states[id].0.text;
 		let (mut word_index, mut p_x, mut word_margin_start, mut char_index) = (0, 0.0, 0.0, 0);
 
-		if text_style.text.indent > 0.0 {
-			self.create_or_get_indice(chars, text_style.text.indent, char_index);
-			char_index += 1;
-		}
 		// 根据每个字符, 创建charNode
-		for cr in split(self.text, true, text_style.text.white_space.preserve_spaces()) {
+		for (src_index, cr) in split(self.text, true, text_style.text.white_space.preserve_spaces()).indices() {
+			self.run = self.run_of(src_index);
 			// println!("cacl_simple, cr: {:?}, char_index:{}, word_index: {}, word_margin_start: {}, p_x:{}", cr, char_index, word_index, word_margin_start, p_x);
 			// 如果是单词的结束字符，释放掉当前节点后面的所有兄弟节点， 并将当前节点索引重置为当前节点的父节点的下一个兄弟节点
 			match cr {
@@ -398,7 +621,7 @@ impl<'a> Calc<'a> {
 					char_index += 1;
 				}
 				SplitResult::WordEnd => {
-					chars[word_index].size = (p_x - self.char_margin, self.line_height);
+					self.end_word(chars, word_index, char_index, p_x - self.char_margin);
 				},
 				SplitResult::Whitespace => {
 					let cn = self.create_or_get(' ', chars, char_index, p_x);
@@ -414,18 +637,42 @@ impl<'a> Calc<'a> {
 			}
 		}
 
-		let cur_child = self.idtree[id].children().head;
+		// 保留一个子节点作为测量节点， 其大小由布局系统在折行后设置为文字块的大小
+		let measure = self.create_measure_node(node_states);
+		let cur_child = self.idtree[measure].next();
 		if cur_child > 0 {
 			free_childs(cur_child, self.idtree, self.nodes);
 		}
 
+		let ellipsis = match &text_style.text.text_overflow {
+			TextOverflow::Clip => "",
+			TextOverflow::Ellipsis => "…",
+			TextOverflow::Custom(r) => r.as_str(),
+		};
+		let start = char_index;
+		self.run = 0;
+		if ellipsis.len() > 0 {
+			word_index = char_index;
+			self.create_or_get_container(chars, char_index, self.char_margin);
+			char_index += 1;
+			p_x = 0.0;
+			for c in ellipsis.chars() {
+				let cn = self.create_or_get(c, chars, char_index, p_x);
+				p_x += cn.size.0 + self.char_margin;
+				chars[word_index].ch_id_or_count += 1;
+				char_index += 1;
+			}
+			chars[word_index].size = (p_x - self.char_margin, self.line_height);
+		}
+
 		while char_index < chars.len() {
 			chars.pop();
 		}
+		char_index - start
 	}
 
 	// 图文混排布局，由于每个字符需要与文字节点的其它兄弟节点在同一层进行布局，因此，每个字符将被当成一个实体进行布局
-	fn calc_mixed(&mut self, node_states: &mut MultiCaseImpl<Node, NodeState>) {
+	fn calc_mixed(&mut self, node_states: &mut MultiCaseImpl<Node, NodeState>, chars: &mut Vec<CharNode>) {
 		let (id, text_style) = (self.id, self.text_style);
 		let node_state = &mut node_states[id];
 		node_state.set_vnode(true);
@@ -436,27 +683,26 @@ impl<'a> Calc<'a> {
 		let (mut word_index, mut p_x, mut word_margin_start, mut word_id, mut char_index) = (0, 0.0, 0.0, 0, 0);
 		
 		if text_style.text.indent > 0.0 {
-			let chars = &mut node_states[id].0.text;
-			let cn = self.create_or_get_indice(chars, text_style.text.indent, char_index);
-			cur_child = self.create_entity(cur_child, parent, &cn.clone(), word_margin_start, char_index, node_states);
+			let cn = self.create_or_get_indice(chars, text_style.text.indent, char_index).clone();
+			cur_child = self.create_entity(cur_child, parent, &cn, word_margin_start, char_index, node_states);
 			char_index += 1;
 			cur_child = self.idtree[cur_child].next();
 		}
 
 		// 根据每个字符, 创建charNode
-		for cr in split(self.text, true, text_style.text.white_space.preserve_spaces()) {
+		for (src_index, cr) in split(self.text, true, text_style.text.white_space.preserve_spaces()).indices() {
+			self.run = self.run_of(src_index);
 			// 如果是单词的结束字符，释放掉当前节点后面的所有兄弟节点， 并将当前节点索引重置为当前节点的父节点的下一个兄弟节点
 			match cr {
 				SplitResult::Word(c) => {
-					let chars = &mut node_states[id].0.text;
 					let cn = self.create_or_get(c, chars, char_index, 0.0);
 					cn.margin_start = word_margin_start;
 					word_margin_start = self.char_margin;
-					cur_child = self.create_entity(cur_child, parent, &cn.clone(), word_margin_start, char_index, node_states);
+					let cn = cn.clone();
+					cur_child = self.create_entity(cur_child, parent, &cn, word_margin_start, char_index, node_states);
 					char_index += 1;
 				}
 				SplitResult::WordNext(c) => {
-					let chars = &mut node_states[id].0.text;
 					let cn = self.create_or_get(c, chars, char_index, p_x);
 					p_x += cn.size.0 + self.char_margin; // 下一个字符的位置
 					chars[word_index].ch_id_or_count += 1;
@@ -466,8 +712,8 @@ impl<'a> Calc<'a> {
 				// 存在WordStart， 表示开始一个多字符单词
 				SplitResult::WordStart(c) => {
 					// 容器节点
-					let cn = self.create_or_get_container(&mut node_states[id].0.text, char_index, word_margin_start);
-					cur_child = self.create_entity(cur_child, parent, &cn.clone(), word_margin_start, char_index, node_states);
+					let cn = self.create_or_get_container(chars, char_index, word_margin_start).clone();
+					cur_child = self.create_entity(cur_child, parent, &cn, word_margin_start, char_index, node_states);
 
 					word_id = cur_child;
 					word_index = char_index;
@@ -475,27 +721,25 @@ impl<'a> Calc<'a> {
 					word_margin_start = self.char_margin;
 					char_index += 1;
 
-					let chars = &mut node_states[id].0.text;
 					let cn = self.create_or_get(c, chars, char_index, 0.0);
 					p_x += cn.size.0 + self.char_margin; // 下一个字符的位置
 					chars[word_index].ch_id_or_count += 1;
 					char_index += 1;
 				}
 				SplitResult::WordEnd => {
-					let chars = &mut node_states[id].0.text;
+					let (width, height) = self.end_word(chars, word_index, char_index, p_x - self.char_margin);
 					self.rect_layout_styles[word_id].size = Size{
-						width: Dimension::Points(p_x - self.char_margin),
-						height: Dimension::Points(self.line_height),
+						width: Dimension::Points(width),
+						height: Dimension::Points(height),
 					};
-					chars[word_index].size = (p_x - self.char_margin, self.line_height);
 					continue;
 				},
 				SplitResult::Whitespace => {
-					let chars = &mut node_states[id].0.text;
 					let cn = self.create_or_get(' ', chars, char_index, 0.0);
 					cn.margin_start = word_margin_start;
 					word_margin_start = self.char_margin;
-					cur_child = self.create_entity(cur_child, parent, &cn.clone(), word_margin_start, char_index, node_states);
+					let cn = cn.clone();
+					cur_child = self.create_entity(cur_child, parent, &cn, word_margin_start, char_index, node_states);
 					char_index += 1;
 
 					// 如果用magine-start来表示空格，会导致行首的空格无效
@@ -503,9 +747,8 @@ impl<'a> Calc<'a> {
 					// continue;
 				}
 				SplitResult::Newline => {
-					let chars = &mut node_states[id].0.text;
-					let cn = self.create_or_get_breakline(chars, char_index);
-					cur_child = self.create_entity(cur_child, parent, &cn.clone(), 0.0, char_index, node_states);
+					let cn = self.create_or_get_breakline(chars, char_index).clone();
+					cur_child = self.create_entity(cur_child, parent, &cn, 0.0, char_index, node_states);
 					char_index += 1;
 				}
 			};
@@ -516,12 +759,29 @@ impl<'a> Calc<'a> {
 			free_childs(cur_child, self.idtree, self.nodes);
 		}
 
-		let chars = &mut node_states[id].0.text;
 		while char_index < chars.len() {
 			chars.pop();
 		}
 	}
 
+	// 简单布局的测量节点， 复用第一个子节点（图文混排时的字符节点）， 不存在时创建
+	fn create_measure_node(&mut self, node_states: &mut MultiCaseImpl<Node, NodeState>) -> usize {
+		let (parent, child) = (self.id, self.idtree[self.id].children().head);
+		if child > 0 {
+			self.rect_layout_styles[child].margin.start = Dimension::Points(0.0);
+			return child;
+		}
+		let cn = CharNode {
+			ch: char::from(0),
+			size: (0.0, 0.0),
+			margin_start: 0.0,
+			pos: (0.0, 0.0),
+			base_width: 0.0,
+			ch_id_or_count: 0,
+		};
+		self.create_entity(0, parent, &cn, 0.0, 0, node_states)
+	}
+
 	fn create_entity(&mut self, mut id: usize, parent: usize, cn: &CharNode, margin: f32, index: usize, node_states: &mut MultiCaseImpl<Node, NodeState>) -> usize {
 		if id == 0 {
 			id = self.nodes.create_but_no_notify();
@@ -554,18 +814,52 @@ impl<'a> Calc<'a> {
 		id
 	}
 
+	// 字符所在的富文本片段， 0表示不在任何片段中， 否则为片段的索引加1
+	fn run_of(&self, index: usize) -> usize {
+		if let Some(spans) = self.text_spans {
+			for (i, span) in spans.0.iter().enumerate() {
+				if index >= span.start && index < span.end {
+					return i + 1;
+				}
+			}
+		}
+		0
+	}
+
+	// 单词结束， 设置单词容器的宽高， 高度为单词内最高字符的高度， 字符在容器内底端对齐
+	fn end_word(&self, chars: &mut Vec<CharNode>, word_index: usize, end: usize, width: f32) -> (f32, f32) {
+		let height = chars[word_index + 1..end].iter().fold(0.0, |r: f32, c| r.max(c.size.1));
+		for c in chars[word_index + 1..end].iter_mut() {
+			c.pos.1 = height - c.size.1;
+		}
+		chars[word_index].size = (width, height);
+		(width, height)
+	}
+
 	fn create_char_node(&mut self, ch: char, p_x: f32) -> CharNode {
+		let font = &self.fonts[self.run];
+		// 行内图片， 使用图片的尺寸
+		if let Some(size) = font.image {
+			return CharNode {
+				ch,
+				size: size,
+				margin_start: self.char_margin,
+				pos: (p_x, 0.0),
+				base_width: 0.0,
+				ch_id_or_count: 0,
+			};
+		}
 		let r = self.font_sheet.measure(
-			&self.tex_font.0,
-			self.font_size as usize,
-			self.sw as usize,
-			self.text_style.font.weight,
+			&font.tex_font,
+			font.font_size as usize,
+			font.sw as usize,
+			font.weight,
 			ch,
 		);
 
 		CharNode {
 			ch,
-			size: (r.0, self.line_height),
+			size: (r.0, font.line_height),
 			margin_start: self.char_margin,
 			pos: (p_x, 0.0),
 			base_width: r.1,
@@ -582,6 +876,12 @@ impl<'a> Calc<'a> {
 				chars[index] = self.create_char_node(ch, p_x);
 			}
 		}
+		if self.text_spans.is_some() {
+			if self.runs.len() <= index {
+				self.runs.resize(index + 1, 0);
+			}
+			self.runs[index] = self.run;
+		}
 		let cn = &mut chars[index];
 		cn.pos.0 = p_x;
 		cn
@@ -661,27 +961,18 @@ impl<'a> Calc<'a> {
 
 fn calc<'a>(
 	id: usize,
-	(text_content, _class_names, _world_matrixs, style_marks, _dirty_list): &Read,
-	(node_states, layout_rs, rect_layout_styles, other_layout_styles, text_styles, font_sheet, idtree, nodes):&mut Write,
+	(text_content, _class_names, _world_matrixs, style_marks, _dirty_list, text_spans): &Read,
+	(node_states, layout_rs, rect_layout_styles, other_layout_styles, text_styles, font_sheet, idtree, nodes, char_blocks):&mut Write,
 	layout_dirty: usize,) {
 	let font_sheet = &mut font_sheet.borrow_mut();
 
 	let text_style = &text_styles[id];
-	let tex_font = match font_sheet.get_font_info(&text_style.font.family) {
-		Some(r) => (r.0.clone(), r.1),
-		None => {
-			println!(
-				"font is not exist, face_name: {:?}, id: {:?}",
-				text_style.font.family.as_ref(),
-				id
-			);
-			return ;//true;
-		}
+	let text_spans = text_spans.get(id);
+	let fonts = match run_fonts(id, text_style, text_spans, font_sheet) {
+		Some(r) => r,
+		None => return,
 	};
-	let font_size = get_size(tex_font.1, &text_style.font.size) as f32;
-	let font_height = tex_font
-		.0
-		.get_font_height(font_size as usize, text_style.text.stroke.width);
+	let (font_size, font_height) = (fonts[0].font_size, fonts[0].font_height);
 	let sw = text_style.text.stroke.width;
 	let parent = idtree[id].parent();
 	let mut calc = Calc {
@@ -690,16 +981,19 @@ fn calc<'a>(
 			_ => "",
 		},
 		style_mark: &style_marks[id],
-		tex_font,
 		font_size,
 		font_height,
-		line_height: get_line_height(font_height as usize, &text_style.text.line_height),
-		sw: text_style.text.stroke.width,
+		line_height: fonts[0].line_height,
 		char_margin: text_style.text.letter_spacing - sw,
 		word_margin: text_style.text.word_spacing - sw,
 		text_style: &mut text_styles[id],
 		parent: parent,
 
+		text_spans,
+		fonts,
+		run: 0,
+		runs: Vec::new(),
+
 		id,
 		text_content,
 		style_marks,
@@ -712,27 +1006,50 @@ fn calc<'a>(
 	};
 
 
-	calc.fit_text_style();
-	if layout_dirty > 0 {
-		// 如果布局属性修改，清除CharNode
-		node_states[id].0.text.clear();
+	if char_blocks.get(id).is_none() {
+		char_blocks.insert(id, CharBlock::default());
 	}
-	
-	let size = &mut calc.rect_layout_styles[id].size;
-	// 如果父节点没有其它子节点，或者，自身定义了宽度或高度，则可使用简单布局
-	if calc.idtree[parent].children().len == 1 {
-		// if size.width == Dimension::Undefined {
-		// 	size.width = Dimension::Percent(1.0);
-		// }
-		// if size.height == Dimension::Undefined {
-		// 	size.height = Dimension::Percent(1.0);
-		// }
-		calc.cacl_simple(node_states);
-	} else if size.width != Dimension::Undefined || size.height != Dimension::Undefined {
-		calc.cacl_simple(node_states);
-	}else {
-		calc.calc_mixed(node_states);
+	let char_block = &mut char_blocks[id];
+	if layout_dirty > 0 || text_spans.is_some() || char_block.runs.len() > 0 {
+		// 如果布局属性修改，清除CharNode； 富文本中相同的字符可能属于不同的片段， 也需要清除
+		char_block.chars.clear();
+	}
+	let rtl = is_rtl(id, calc.idtree, calc.other_layout_styles);
+	let text = &calc.text_style.text;
+	char_block.style = LineStyle {
+		line_height: calc.line_height,
+		word_margin: text.word_spacing,
+		indent: text.indent,
+		text_align: text.text_align,
+		vertical_align: text.vertical_align,
+		wrap: text.white_space.allow_wrap(),
+		rtl: rtl,
+		line_clamp: text.line_clamp,
+	};
+	char_block.dirty = true;
+
+	let size = &calc.rect_layout_styles[id].size;
+	// 如果父节点没有其它子节点，或者，自身定义了宽度或高度，则可使用简单布局， 否则为图文混排
+	let mixed = calc.idtree[parent].children().len != 1 && size.width == Dimension::Undefined && size.height == Dimension::Undefined;
+	if mixed {
+		calc.fit_text_style();
+		calc.calc_mixed(node_states, &mut char_block.chars);
+		// 图文混排的字符由flex布局排列， 不做双向重排， 也不截断
+		char_block.levels.clear();
+		char_block.ellipsis = 0;
+		char_block.visible = char_block.chars.len();
+		char_block.truncated = false;
+	} else {
+		char_block.ellipsis = calc.cacl_simple(node_states, &mut char_block.chars);
+	}
+	if text_spans.is_some() {
+		calc.runs.resize(char_block.chars.len(), 0);
 	}
+	char_block.runs = calc.runs;
+	if !mixed {
+		line_box::resolve_bidi(char_block);
+	}
+	char_block.mixed = mixed;
 }
 
 fn free_childs(mut start: usize, idtree: &mut SingleCaseImpl<IdTree>, nodes: &mut EntityImpl<Node>) {
@@ -766,5 +1083,104 @@ impl_system! {
     TextGlphySys,
     true,
     {
+		MultiCaseListener<Node, TextContent, DeleteEvent>
+		EntityListener<Node, DeleteEvent>
     }
 }
+
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+#[cfg(test)]
+use ecs::Lend;
+#[cfg(test)]
+use font::font_tex::MAX_TEX_PAGE;
+
+#[test]
+fn test_glyph_page_evict() {
+	// 64x64的字体纹理页， 每页可以容纳32个16号字的字形， 每个节点的30个字形占满一页
+	// 5个节点超过了所有纹理页的容量， 回收最久未使用的页， 不会panic
+	let mut scene = SceneDesc::new(300, 100);
+	scene.font_texture_width = 64;
+	for k in 0..5u32 {
+		let text: String = (0..30u32).map(|i| std::char::from_u32(0x4e00 + k * 30 + i).unwrap()).collect();
+		scene.nodes.push(
+			NodeDesc::new(&format!("position:absolute;left:0px;top:{}px;width:300px;height:20px;font-size:16px", k * 20))
+				.with_text(text.as_str()),
+		);
+	}
+	let mut gui = SnapshotGui::new(scene);
+	let nodes: Vec<usize> = {
+		let idtree = gui.gui.idtree.lend();
+		idtree.iter(idtree[gui.root].children().head).map(|r| r.0).collect()
+	};
+	assert_eq!(nodes.len(), 5);
+
+	for _ in 0..3 {
+		gui.render();
+		let char_blocks = gui.gui.char_block.lend();
+		let font_sheet = gui.gui.font_sheet.lend();
+		let font_sheet = font_sheet.borrow();
+		assert_eq!(font_sheet.font_tex.pages.len(), MAX_TEX_PAGE);
+		// 同一时刻只有4个节点能得到字形， 每个节点的字形都在同一页上
+		let mut count = 0;
+		for id in nodes.iter() {
+			let char_block = &char_blocks[*id];
+			let pages: Vec<usize> = char_block
+				.chars
+				.iter()
+				.filter(|c| c.ch > ' ')
+				.filter_map(|c| font_sheet.get_glyph(c.ch_id_or_count).map(|r| (r.1).page))
+				.collect();
+			if pages.len() == 30 {
+				assert!(pages.iter().all(|p| *p == pages[0]));
+				count += 1;
+			} else {
+				// 未能计算字形的节点不引用任何字形
+				assert!(char_block.chars.iter().filter(|c| c.ch > ' ').all(|c| c.ch_id_or_count == 0));
+			}
+		}
+		assert_eq!(count, MAX_TEX_PAGE);
+	}
+}
+
+#[test]
+fn test_text_overflow() {
+	// 不折行的文字超出宽度， 截断并在末尾显示省略号； 超出最大行数的行被丢弃
+	let cjk: String = (0..30u32).map(|i| std::char::from_u32(0x4e00 + i).unwrap()).collect();
+	let mut scene = SceneDesc::new(100, 100);
+	scene.nodes.push(
+		NodeDesc::new("position:absolute;left:0px;top:0px;width:40px;font-size:16px;white-space:nowrap;text-overflow:ellipsis")
+			.with_text("abcdefghijklmn"),
+	);
+	scene.nodes.push(
+		NodeDesc::new("position:absolute;left:0px;top:20px;width:40px;font-size:16px;line-clamp:2")
+			.with_text(cjk.as_str()),
+	);
+	let mut gui = SnapshotGui::new(scene);
+	gui.render();
+	let nodes: Vec<usize> = {
+		let idtree = gui.gui.idtree.lend();
+		idtree.iter(idtree[gui.root].children().head).map(|r| r.0).collect()
+	};
+	let char_blocks = gui.gui.char_block.lend();
+
+	let b = &char_blocks[nodes[0]];
+	let text_len = b.text_len();
+	assert_eq!(text_len + b.ellipsis, b.chars.len());
+	assert!(b.truncated && b.ellipsis > 0);
+	assert!(b.visible > 0 && b.visible < text_len);
+	assert!(!b.is_visible(text_len - 1) && b.is_visible(text_len));
+	// 省略号在内容区之内， 紧跟最后一个可见字符
+	let ellipsis = &b.chars[text_len];
+	assert!(ellipsis.pos.0 + ellipsis.size.0 <= 40.0);
+	let last = &b.chars[b.visible - 1];
+	assert!(ellipsis.pos.0 >= last.pos.0 + last.size.0 - 0.001);
+
+	let b = &char_blocks[nodes[1]];
+	assert_eq!(b.ellipsis, 0);
+	assert_eq!(b.lines.len(), 2);
+	assert!(b.truncated);
+	assert_eq!(b.size.1, b.lines[0].height + b.lines[1].height);
+	assert_eq!(b.visible, b.lines[1].end);
+	assert!(b.chars.iter().enumerate().filter(|r| r.0 < b.visible).all(|r| (r.1).pos.1 < b.size.1));
+}
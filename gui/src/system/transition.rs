@@ -0,0 +1,489 @@
+/**
+ * 过渡
+ * TransitionSys记录设置了Transition组件的节点， 每帧比较这些节点上可过渡属性的当前值与上一帧显示的值
+ * 值发生改变时（无论是本地样式、class还是伪类引起的改变）， 以上一帧显示的值为起点、新值为终点开始过渡， 由SystemTime推进， 插值结果写回对应组件
+ * 写回的方式与class设置属性的方式一致， 不会将属性标记为本地样式
+ * 渐变色、函数列表不一致的变换、非像素或百分比的尺寸无法插值， 直接使用新值
+ * 本帧被关键帧动画写入的属性（AnimatedProperties）不产生过渡
+ * TransitionSys应该在其它系统之前运行， 以便本帧的插值结果在本帧生效
+ */
+use std::mem::discriminant;
+
+use ecs::{CreateEvent, DeleteEvent, EntityListener, MultiCaseImpl, MultiCaseListener, Runner, SingleCaseImpl};
+use flex_layout::Dimension;
+use hash::XHashMap;
+
+use component::calc::{StyleMark, StyleType, StyleType1, StyleType2};
+use component::user::*;
+use entity::Node;
+use single::{AnimatedProperties, DirtyList, SystemTime};
+use system::style_mark::{set_dirty, set_dirty2};
+
+pub(crate) const PROPERTYS: [TransitionProperty; 8] = [
+    TransitionProperty::Opacity,
+    TransitionProperty::BackgroundColor,
+    TransitionProperty::BorderColor,
+    TransitionProperty::Color,
+    TransitionProperty::Transform,
+    TransitionProperty::Width,
+    TransitionProperty::Height,
+    TransitionProperty::Filter,
+];
+
+pub(crate) type Styles<'a> = (
+    &'a mut MultiCaseImpl<Node, Opacity>,
+    &'a mut MultiCaseImpl<Node, BackgroundColor>,
+    &'a mut MultiCaseImpl<Node, BorderColor>,
+    &'a mut MultiCaseImpl<Node, TextStyle>,
+    &'a mut MultiCaseImpl<Node, Transform>,
+    &'a mut MultiCaseImpl<Node, RectLayoutStyle>,
+    &'a mut MultiCaseImpl<Node, Filter>,
+    &'a mut MultiCaseImpl<Node, StyleMark>,
+    &'a mut SingleCaseImpl<DirtyList>,
+);
+
+#[derive(Clone, Debug)]
+pub(crate) enum TransitionValue {
+    Float(f32),
+    Color(Color),
+    CgColor(CgColor),
+    Transform(Vec<TransformFunc>),
+    Dimension(Dimension),
+    Filter(f32, f32, f32),
+}
+
+struct Running {
+    property: TransitionProperty,
+    from: TransitionValue,
+    to: TransitionValue,
+    start_time: usize,
+    duration: usize,
+    timing: TimingFunction,
+}
+
+struct NodeTransition {
+    init: bool, // 刚设置Transition时， 仅记录当前值， 不产生过渡
+    values: Vec<(TransitionProperty, TransitionValue)>, // 上一帧显示的值
+    animations: Vec<Running>,
+}
+
+impl NodeTransition {
+    fn new() -> Self {
+        NodeTransition {
+            init: true,
+            values: Vec::new(),
+            animations: Vec::new(),
+        }
+    }
+
+    fn value(&self, property: TransitionProperty) -> Option<&TransitionValue> {
+        self.values.iter().find(|r| r.0 == property).map(|r| &r.1)
+    }
+
+    fn set_value(&mut self, property: TransitionProperty, value: TransitionValue) {
+        match self.values.iter_mut().find(|r| r.0 == property) {
+            Some(r) => r.1 = value,
+            None => self.values.push((property, value)),
+        }
+    }
+
+    fn remove(&mut self, property: TransitionProperty) {
+        self.values.retain(|r| r.0 != property);
+        self.animations.retain(|r| r.property != property);
+    }
+}
+
+#[derive(Default)]
+pub struct TransitionSys {
+    nodes: XHashMap<usize, NodeTransition>,
+}
+
+impl<'a> Runner<'a> for TransitionSys {
+    type ReadData = (
+        &'a SingleCaseImpl<SystemTime>,
+        &'a MultiCaseImpl<Node, Transition>,
+        &'a SingleCaseImpl<AnimatedProperties>,
+    );
+    type WriteData = Styles<'a>;
+    fn run(&mut self, (system_time, transitions, animated): Self::ReadData, mut styles: Self::WriteData) {
+        let now = system_time.cur_time;
+        for (id, node) in self.nodes.iter_mut() {
+            let id = *id;
+            let transition = match transitions.get(id) {
+                Some(r) => r,
+                None => continue,
+            };
+            // 节点刚创建时， 其样式为初始样式， 不产生过渡
+            let init = node.init
+                || match styles.7.get(id) {
+                    Some(r) => r.dirty1 & StyleType1::Create as usize != 0,
+                    None => true,
+                };
+            node.init = false;
+            let animated = animated.0.get(&id).cloned().unwrap_or(0);
+
+            for property in PROPERTYS.iter() {
+                let property = *property;
+                let item = match transition.get(property) {
+                    Some(r) => r,
+                    None => {
+                        node.remove(property);
+                        continue;
+                    }
+                };
+                let cur = match get_value(id, property, &styles) {
+                    Some(r) => r,
+                    None => continue,
+                };
+                // 关键帧动画控制的属性， 只记录当前值
+                if animated & (1 << property as usize) != 0 {
+                    node.animations.retain(|r| r.property != property);
+                    node.set_value(property, cur);
+                    continue;
+                }
+                let from = match node.value(property) {
+                    Some(last) if !value_eq(last, &cur) => last.clone(),
+                    Some(_) => continue,
+                    None => {
+                        node.set_value(property, cur);
+                        continue;
+                    }
+                };
+                // 值被外部修改， 以当前显示的值为起点重新开始过渡
+                node.animations.retain(|r| r.property != property);
+                if init || item.duration == 0 || interpolate(&from, &cur, 0.0).is_none() {
+                    node.set_value(property, cur);
+                    continue;
+                }
+                node.animations.push(Running {
+                    property: property,
+                    from: from,
+                    to: cur,
+                    start_time: now + item.delay,
+                    duration: item.duration,
+                    timing: item.timing,
+                });
+            }
+
+            let mut i = 0;
+            while i < node.animations.len() {
+                let (property, value, end) = {
+                    let animation = &node.animations[i];
+                    let progress = if now < animation.start_time {
+                        0.0
+                    } else {
+                        ((now - animation.start_time) as f32 / animation.duration as f32).min(1.0)
+                    };
+                    let value = if progress >= 1.0 {
+                        animation.to.clone()
+                    } else {
+                        interpolate(&animation.from, &animation.to, animation.timing.value(progress)).unwrap()
+                    };
+                    (animation.property, value, progress >= 1.0)
+                };
+                let changed = match get_value(id, property, &styles) {
+                    Some(r) => !value_eq(&r, &value),
+                    None => true,
+                };
+                if changed {
+                    set_value(id, &value, property, &mut styles);
+                }
+                node.set_value(property, value);
+                if end {
+                    node.animations.swap_remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Transition, CreateEvent> for TransitionSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &CreateEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.nodes.insert(event.id, NodeTransition::new());
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, Transition, DeleteEvent> for TransitionSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.nodes.remove(&event.id);
+    }
+}
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for TransitionSys {
+    type ReadData = ();
+    type WriteData = ();
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, _write: Self::WriteData) {
+        self.nodes.remove(&event.id);
+    }
+}
+
+pub(crate) fn get_value(id: usize, property: TransitionProperty, styles: &Styles) -> Option<TransitionValue> {
+    match property {
+        TransitionProperty::Opacity => Some(TransitionValue::Float(match styles.0.get(id) {
+            Some(r) => r.0,
+            None => 1.0,
+        })),
+        TransitionProperty::BackgroundColor => styles.1.get(id).map(|r| TransitionValue::Color(r.0.clone())),
+        TransitionProperty::BorderColor => styles.2.get(id).map(|r| TransitionValue::CgColor(r.0.clone())),
+        TransitionProperty::Color => styles.3.get(id).map(|r| TransitionValue::Color(r.text.color.clone())),
+        TransitionProperty::Transform => Some(TransitionValue::Transform(match styles.4.get(id) {
+            Some(r) => r.funcs.clone(),
+            None => Vec::new(),
+        })),
+        TransitionProperty::Width => styles.5.get(id).map(|r| TransitionValue::Dimension(r.size.width.clone())),
+        TransitionProperty::Height => styles.5.get(id).map(|r| TransitionValue::Dimension(r.size.height.clone())),
+        TransitionProperty::Filter => styles.6.get(id).map(|r| TransitionValue::Filter(r.hue_rotate, r.saturate, r.bright_ness)),
+        TransitionProperty::All => None,
+    }
+}
+
+// 写回插值结果， 不改变属性的本地样式标记
+pub(crate) fn set_value(id: usize, value: &TransitionValue, property: TransitionProperty, styles: &mut Styles) {
+    match (property, value) {
+        (TransitionProperty::Opacity, &TransitionValue::Float(r)) => {
+            let local = styles.7[id].local_style & StyleType::Opacity as usize;
+            styles.0.insert(id, Opacity(r));
+            styles.7[id].local_style = styles.7[id].local_style & !(StyleType::Opacity as usize) | local;
+        }
+        (TransitionProperty::BackgroundColor, &TransitionValue::Color(ref r)) => {
+            styles.1.insert_no_notify(id, BackgroundColor(r.clone()));
+            set_dirty(&mut styles.8, id, StyleType::BackgroundColor as usize, &mut styles.7[id]);
+        }
+        (TransitionProperty::BorderColor, &TransitionValue::CgColor(ref r)) => {
+            styles.2.insert_no_notify(id, BorderColor(r.clone()));
+            set_dirty(&mut styles.8, id, StyleType::BorderColor as usize, &mut styles.7[id]);
+        }
+        (TransitionProperty::Color, &TransitionValue::Color(ref r)) => {
+            styles.3[id].text.color = r.clone();
+            set_dirty(&mut styles.8, id, StyleType::Color as usize, &mut styles.7[id]);
+        }
+        (TransitionProperty::Transform, &TransitionValue::Transform(ref r)) => match styles.4.get_write(id) {
+            Some(mut write) => write.modify(|transform: &mut Transform| {
+                transform.funcs = r.clone();
+                true
+            }),
+            None => {
+                let mut transform = Transform::default();
+                transform.funcs = r.clone();
+                styles.4.insert(id, transform);
+            }
+        },
+        (TransitionProperty::Width, &TransitionValue::Dimension(ref r)) => {
+            styles.5[id].size.width = r.clone();
+            set_dirty2(&mut styles.8, id, StyleType2::Width as usize, &mut styles.7[id]);
+        }
+        (TransitionProperty::Height, &TransitionValue::Dimension(ref r)) => {
+            styles.5[id].size.height = r.clone();
+            set_dirty2(&mut styles.8, id, StyleType2::Height as usize, &mut styles.7[id]);
+        }
+        (TransitionProperty::Filter, &TransitionValue::Filter(h, s, v)) => {
+            let local = styles.7[id].local_style & StyleType::Filter as usize;
+            styles.6.insert(id, Filter { hue_rotate: h, saturate: s, bright_ness: v });
+            styles.7[id].local_style = styles.7[id].local_style & !(StyleType::Filter as usize) | local;
+        }
+        _ => (),
+    }
+}
+
+// 渐变色不参与过渡， 两个渐变色视为相等
+pub(crate) fn value_eq(a: &TransitionValue, b: &TransitionValue) -> bool {
+    match (a, b) {
+        (&TransitionValue::Float(a), &TransitionValue::Float(b)) => a == b,
+        (&TransitionValue::Color(Color::RGBA(ref a)), &TransitionValue::Color(Color::RGBA(ref b)))
+        | (&TransitionValue::CgColor(ref a), &TransitionValue::CgColor(ref b)) => {
+            a.r == b.r && a.g == b.g && a.b == b.b && a.a == b.a
+        }
+        (&TransitionValue::Color(Color::RGBA(_)), _) | (_, &TransitionValue::Color(Color::RGBA(_))) => false,
+        (&TransitionValue::Color(_), &TransitionValue::Color(_)) => true,
+        (&TransitionValue::Transform(ref a), &TransitionValue::Transform(ref b)) => {
+            a.len() == b.len()
+                && a.iter().zip(b.iter()).all(|(a, b)| {
+                    let (a, b) = (transform_func_params(a), transform_func_params(b));
+                    a.0 == b.0 && a.1 == b.1
+                })
+        }
+        (&TransitionValue::Dimension(ref a), &TransitionValue::Dimension(ref b)) => match (a, b) {
+            (&Dimension::Points(a), &Dimension::Points(b)) | (&Dimension::Percent(a), &Dimension::Percent(b)) => a == b,
+            (a, b) => discriminant(a) == discriminant(b),
+        },
+        (&TransitionValue::Filter(h1, s1, v1), &TransitionValue::Filter(h2, s2, v2)) => {
+            h1 == h2 && s1 == s2 && v1 == v2
+        }
+        _ => false,
+    }
+}
+
+// 按进度t插值， 无法插值时返回None
+pub(crate) fn interpolate(from: &TransitionValue, to: &TransitionValue, t: f32) -> Option<TransitionValue> {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    let lerp_color = |a: &CgColor, b: &CgColor| {
+        CgColor::new(lerp(a.r, b.r), lerp(a.g, b.g), lerp(a.b, b.b), lerp(a.a, b.a))
+    };
+    match (from, to) {
+        (&TransitionValue::Float(a), &TransitionValue::Float(b)) => Some(TransitionValue::Float(lerp(a, b))),
+        (&TransitionValue::Color(Color::RGBA(ref a)), &TransitionValue::Color(Color::RGBA(ref b))) => {
+            Some(TransitionValue::Color(Color::RGBA(lerp_color(a, b))))
+        }
+        (&TransitionValue::CgColor(ref a), &TransitionValue::CgColor(ref b)) => {
+            Some(TransitionValue::CgColor(lerp_color(a, b)))
+        }
+        (&TransitionValue::Transform(ref a), &TransitionValue::Transform(ref b)) => {
+            // 一方为空时， 以另一方各函数的单位值作为起点或终点
+            let (a, b): (Vec<_>, Vec<_>) = if a.len() == 0 {
+                (b.iter().map(|r| transform_func_identity(r)).collect(), b.iter().map(transform_func_params).collect())
+            } else if b.len() == 0 {
+                (a.iter().map(transform_func_params).collect(), a.iter().map(|r| transform_func_identity(r)).collect())
+            } else if a.len() == b.len() {
+                (a.iter().map(transform_func_params).collect(), b.iter().map(transform_func_params).collect())
+            } else {
+                return None;
+            };
+            let mut funcs = Vec::with_capacity(a.len());
+            for (a, b) in a.iter().zip(b.iter()) {
+                if a.0 != b.0 {
+                    return None;
+                }
+                funcs.push(transform_func_from_params(a.0, [lerp(a.1[0], b.1[0]), lerp(a.1[1], b.1[1])]));
+            }
+            Some(TransitionValue::Transform(funcs))
+        }
+        (&TransitionValue::Dimension(ref a), &TransitionValue::Dimension(ref b)) => match (a, b) {
+            (&Dimension::Points(a), &Dimension::Points(b)) => Some(TransitionValue::Dimension(Dimension::Points(lerp(a, b)))),
+            (&Dimension::Percent(a), &Dimension::Percent(b)) => Some(TransitionValue::Dimension(Dimension::Percent(lerp(a, b)))),
+            _ => None,
+        },
+        (&TransitionValue::Filter(h1, s1, v1), &TransitionValue::Filter(h2, s2, v2)) => {
+            Some(TransitionValue::Filter(lerp(h1, h2), lerp(s1, s2), lerp(v1, v2)))
+        }
+        _ => None,
+    }
+}
+
+// 将变换函数表示为（类型， 参数）
+fn transform_func_params(func: &TransformFunc) -> (u8, [f32; 2]) {
+    match *func {
+        TransformFunc::TranslateX(x) => (0, [x, 0.0]),
+        TransformFunc::TranslateY(y) => (1, [y, 0.0]),
+        TransformFunc::Translate(x, y) => (2, [x, y]),
+        TransformFunc::TranslateXPercent(x) => (3, [x, 0.0]),
+        TransformFunc::TranslateYPercent(y) => (4, [y, 0.0]),
+        TransformFunc::TranslatePercent(x, y) => (5, [x, y]),
+        TransformFunc::ScaleX(x) => (6, [x, 0.0]),
+        TransformFunc::ScaleY(y) => (7, [y, 0.0]),
+        TransformFunc::Scale(x, y) => (8, [x, y]),
+        TransformFunc::RotateZ(z) => (9, [z, 0.0]),
+    }
+}
+
+// 与func同类型、不产生变换的参数
+fn transform_func_identity(func: &TransformFunc) -> (u8, [f32; 2]) {
+    match transform_func_params(func).0 {
+        6 => (6, [1.0, 0.0]),
+        7 => (7, [1.0, 0.0]),
+        8 => (8, [1.0, 1.0]),
+        ty => (ty, [0.0, 0.0]),
+    }
+}
+
+fn transform_func_from_params(ty: u8, p: [f32; 2]) -> TransformFunc {
+    match ty {
+        0 => TransformFunc::TranslateX(p[0]),
+        1 => TransformFunc::TranslateY(p[0]),
+        2 => TransformFunc::Translate(p[0], p[1]),
+        3 => TransformFunc::TranslateXPercent(p[0]),
+        4 => TransformFunc::TranslateYPercent(p[0]),
+        5 => TransformFunc::TranslatePercent(p[0], p[1]),
+        6 => TransformFunc::ScaleX(p[0]),
+        7 => TransformFunc::ScaleY(p[0]),
+        8 => TransformFunc::Scale(p[0], p[1]),
+        _ => TransformFunc::RotateZ(p[0]),
+    }
+}
+
+impl_system! {
+    TransitionSys,
+    true,
+    {
+        MultiCaseListener<Node, Transition, CreateEvent>
+        MultiCaseListener<Node, Transition, DeleteEvent>
+        EntityListener<Node, DeleteEvent>
+    }
+}
+
+#[cfg(test)]
+use ecs::LendMut;
+#[cfg(test)]
+use single::style_parse::parse_transition;
+#[cfg(test)]
+use util::snapshot::*;
+
+#[test]
+fn test_transition() {
+    let mut gui = SnapshotGui::new(SceneDesc::new(20, 20));
+    let root = gui.root;
+    let node = gui.append(&NodeDesc::new("width:20px;height:20px;background-color:#ff0000"), root);
+    gui.gui.transition.lend_mut().insert(node, parse_transition("background-color 100ms linear").unwrap());
+
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+
+    gui.gui.background_color.lend_mut().insert(node, BackgroundColor(Color::RGBA(CgColor::new(0.0, 0.0, 1.0, 1.0))));
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+
+    gui.gui.system_time.lend_mut().cur_time = 50;
+    gui.render();
+    let p = gui.pixel(0, 0);
+    assert!((p[0] as i32 - 128).abs() <= 1 && p[1] == 0 && (p[2] as i32 - 128).abs() <= 1);
+
+    gui.gui.system_time.lend_mut().cur_time = 100;
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [0, 0, 255, 255]);
+}
+
+#[test]
+fn test_transition_class_reverse() {
+    // 样式表中设置的过渡， 由class变化触发； 过渡中途反向时， 从当前显示的值开始反向过渡
+    let mut scene = SceneDesc::new(10, 10);
+    scene.css = ".1{transition:opacity 100ms linear} .2{opacity:0} .3{position:absolute;left:0px;top:0px;width:10px;height:10px;background-color:#ff0000}".to_string();
+    let mut gui = SnapshotGui::new(scene);
+    let root = gui.root;
+    let node = gui.append(&NodeDesc::new("").with_class(&[3, 1]), root);
+    assert!(gui.gui.transition.lend_mut().get(node).is_some());
+
+    let near = |p: [u8; 4], r: [u8; 3]| (0..3).all(|i| (p[i] as i32 - r[i] as i32).abs() <= 2);
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+
+    gui.set_class(node, &[3, 1, 2]);
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+
+    // 不透明度0.5
+    gui.gui.system_time.lend_mut().cur_time = 50;
+    gui.render();
+    assert!(near(gui.pixel(0, 0), [255, 128, 128]));
+
+    // 反向， 从0.5开始过渡到1
+    gui.set_class(node, &[3, 1]);
+    gui.render();
+    assert!(near(gui.pixel(0, 0), [255, 128, 128]));
+
+    gui.gui.system_time.lend_mut().cur_time = 100;
+    gui.render();
+    assert!(near(gui.pixel(0, 0), [255, 64, 64]));
+
+    gui.gui.system_time.lend_mut().cur_time = 150;
+    gui.render();
+    assert_eq!(gui.pixel(0, 0), [255, 0, 0, 255]);
+
+    // 移除设置过渡的class， 过渡组件被删除
+    gui.set_class(node, &[3]);
+    assert!(gui.gui.transition.lend_mut().get(node).is_none());
+}
@@ -7,8 +7,10 @@ use hash::DefaultHasher;
 use ordered_float::NotNan;
 
 use atom::Atom;
+use cg2d::{Point2 as Point2d, Polygon as Polygon2d};
 use ecs::monitor::NotifyImpl;
 use ecs::{MultiCaseImpl, SingleCaseImpl};
+use flex_layout::Direction;
 use hal_core::*;
 use map::vecmap::VecMap;
 
@@ -57,25 +59,107 @@ pub trait DefinesClip {
     fn get_clip(&self) -> bool;
 }
 
-pub fn cal_border_radius(border_radius: Option<&BorderRadius>, layout: &LayoutR) -> Point2 {
+// 计算后的圆角（像素）， 四个角依次为左上、右上、右下、左下， 每个角为(水平半径, 垂直半径)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BorderRadiusPixel(pub [(f32, f32); 4]);
+
+impl BorderRadiusPixel {
+    // 所有角都没有圆角
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|r| r.0 <= 0.0 || r.1 <= 0.0)
+    }
+
+    // 内边框的圆角（外圆角减去对应边的边框宽度）
+    pub fn inner(&self, layout: &LayoutR) -> BorderRadiusPixel {
+        let b = &layout.border;
+        let [tl, tr, br, bl] = self.0;
+        BorderRadiusPixel([
+            ((tl.0 - b.start).max(0.0), (tl.1 - b.top).max(0.0)),
+            ((tr.0 - b.end).max(0.0), (tr.1 - b.top).max(0.0)),
+            ((br.0 - b.end).max(0.0), (br.1 - b.bottom).max(0.0)),
+            ((bl.0 - b.start).max(0.0), (bl.1 - b.bottom).max(0.0)),
+        ])
+    }
+
+    // 内容区域的圆角（内边框的圆角再减去对应边的padding）
+    pub fn content(&self, layout: &LayoutR) -> BorderRadiusPixel {
+        let p = &layout.padding;
+        let [tl, tr, br, bl] = self.inner(layout).0;
+        BorderRadiusPixel([
+            ((tl.0 - p.start).max(0.0), (tl.1 - p.top).max(0.0)),
+            ((tr.0 - p.end).max(0.0), (tr.1 - p.top).max(0.0)),
+            ((br.0 - p.end).max(0.0), (br.1 - p.bottom).max(0.0)),
+            ((bl.0 - p.start).max(0.0), (bl.1 - p.bottom).max(0.0)),
+        ])
+    }
+
+    // 按css规则， 同一条边上相邻两个圆角之和超过边长时， 等比缩小所有圆角
+    pub fn fit(&self, width: f32, height: f32) -> BorderRadiusPixel {
+        let [tl, tr, br, bl] = self.0;
+        let mut f: f32 = 1.0;
+        for (sum, len) in [
+            (tl.0 + tr.0, width),
+            (bl.0 + br.0, width),
+            (tl.1 + bl.1, height),
+            (tr.1 + br.1, height),
+        ]
+        .iter()
+        {
+            if *sum > 0.0 && *len / *sum < f {
+                f = (*len / *sum).max(0.0);
+            }
+        }
+        if f >= 1.0 {
+            return *self;
+        }
+        let mut r = *self;
+        for c in r.0.iter_mut() {
+            c.0 *= f;
+            c.1 *= f;
+        }
+        r
+    }
+}
+
+// 计算节点的圆角（相对于布局框的外边缘）， 百分比分别相对于布局的宽、高
+pub fn cal_border_radius(border_radius: Option<&BorderRadius>, layout: &LayoutR) -> BorderRadiusPixel {
     match border_radius {
-        Some(border_radius) => Point2 {
-            x: match border_radius.x {
-                LengthUnit::Pixel(r) => r,
-                LengthUnit::Percent(r) => r * (layout.rect.end - layout.rect.start),
-            },
-            y: match border_radius.y {
-                LengthUnit::Pixel(r) => r,
-                LengthUnit::Percent(r) => r * (layout.rect.bottom - layout.rect.top),
-            },
-        },
-        None => Point2::new(0.0, 0.0),
+        Some(border_radius) => {
+            let width = layout.rect.end - layout.rect.start;
+            let height = layout.rect.bottom - layout.rect.top;
+            let to_pixel = |c: &CornerRadius| {
+                (
+                    match c.x {
+                        LengthUnit::Pixel(r) => r,
+                        LengthUnit::Percent(r) => r * width,
+                    }
+                    .max(0.0),
+                    match c.y {
+                        LengthUnit::Pixel(r) => r,
+                        LengthUnit::Percent(r) => r * height,
+                    }
+                    .max(0.0),
+                )
+            };
+            BorderRadiusPixel([
+                to_pixel(&border_radius.top_left),
+                to_pixel(&border_radius.top_right),
+                to_pixel(&border_radius.bottom_right),
+                to_pixel(&border_radius.bottom_left),
+            ])
+            .fit(width, height)
+        }
+        None => BorderRadiusPixel::default(),
     }
 }
 
-pub fn radius_quad_hash(hasher: &mut DefaultHasher, radius: f32, width: f32, height: f32) {
+pub fn radius_quad_hash(hasher: &mut DefaultHasher, radius: &BorderRadiusPixel, width: f32, height: f32) {
     RADIUS_QUAD_POSITION_INDEX.hash(hasher);
-    NotNan::new(radius).unwrap().hash(hasher);
+    for r in radius.0.iter() {
+        NotNan::new(r.0).unwrap().hash(hasher);
+        NotNan::new(r.1).unwrap().hash(hasher);
+    }
     NotNan::new(width).unwrap().hash(hasher);
     NotNan::new(height).unwrap().hash(hasher);
 }
@@ -272,6 +356,370 @@ pub fn to_vex_color_defines(vs_defines: &mut dyn Defines, fs_defines: &mut dyn D
     }
 }
 
+// 圆角每个角的分段数， 根据半径计算
+#[inline]
+fn radius_segment(radius: &BorderRadiusPixel) -> usize {
+    let mut max: f32 = 0.0;
+    for r in radius.0.iter() {
+        max = max.max(r.0).max(r.1);
+    }
+    ((max.sqrt() * 2.0).ceil() as usize).max(2).min(16)
+}
+
+// 沿左上、左下、右下、右上的方向， 为每个角压入segment + 1个点（没有圆角的角， 所有点都在角上）
+fn push_radius_points(
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    radius: &BorderRadiusPixel,
+    segment: usize,
+    positions: &mut Vec<f32>,
+) {
+    let [tl, tr, br, bl] = radius.0;
+    let half_pi = std::f32::consts::FRAC_PI_2;
+    // (圆角的中心, 半径, 起始角度)， y轴向下， 每个角逆时针扫过π/2
+    let corners = [
+        ((x + tl.0, y + tl.1), tl, 3.0 * half_pi),
+        ((x + bl.0, y + h - bl.1), bl, 2.0 * half_pi),
+        ((x + w - br.0, y + h - br.1), br, half_pi),
+        ((x + w - tr.0, y + tr.1), tr, 0.0),
+    ];
+    for (c, r, start) in corners.iter() {
+        for i in 0..segment + 1 {
+            let angle = start - half_pi * i as f32 / segment as f32;
+            positions.push(c.0 + r.0 * angle.cos());
+            positions.push(c.1 + r.1 * angle.sin());
+        }
+    }
+}
+
+// 将圆角矩形划分为多边形， 返回(positions, indices)， indices为多边形顶点的顺序（左上、左下、右下、右上方向）
+// segment为每个圆角的分段数， None时根据半径计算
+pub fn split_by_border_radius(
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    radius: &BorderRadiusPixel,
+    segment: Option<usize>,
+) -> (Vec<f32>, Vec<u16>) {
+    if w <= 0.0 || h <= 0.0 {
+        return (Vec::new(), Vec::new());
+    }
+    let radius = radius.fit(w, h);
+    let segment = match segment {
+        Some(r) => r.max(1),
+        None => radius_segment(&radius),
+    };
+    let mut points = Vec::with_capacity((segment + 1) * 8);
+    push_radius_points(x, y, w, h, &radius, segment, &mut points);
+
+    // 去掉重合的点（没有圆角的角， 以及圆角占满整条边时相邻的角）
+    let mut positions: Vec<f32> = Vec::with_capacity(points.len());
+    for i in 0..points.len() / 2 {
+        let (px, py) = (points[i * 2], points[i * 2 + 1]);
+        let len = positions.len();
+        if len > 0 && (positions[len - 2] - px).abs() < 0.001 && (positions[len - 1] - py).abs() < 0.001 {
+            continue;
+        }
+        positions.push(px);
+        positions.push(py);
+    }
+    let len = positions.len();
+    if len > 2 && (positions[0] - positions[len - 2]).abs() < 0.001 && (positions[1] - positions[len - 1]).abs() < 0.001 {
+        positions.truncate(len - 2);
+    }
+    let indices = (0..(positions.len() / 2) as u16).collect();
+    (positions, indices)
+}
+
+// 圆角边框的几何体， 外轮廓为(0, 0, width, height)， 内轮廓为去掉边框宽度后的区域， 返回(positions, 三角形indices)
+pub fn split_by_border_radius_border(
+    width: f32,
+    height: f32,
+    radius: &BorderRadiusPixel,
+    layout: &LayoutR,
+) -> (Vec<f32>, Vec<u16>) {
+    let radius = radius.fit(width, height);
+    let segment = radius_segment(&radius);
+    let b = &layout.border;
+    let (inner_w, inner_h) = (
+        (width - b.start - b.end).max(0.0),
+        (height - b.top - b.bottom).max(0.0),
+    );
+    let inner = radius.inner(layout).fit(inner_w, inner_h);
+
+    // 内外轮廓的点数相同， 一一对应
+    let mut positions = Vec::with_capacity((segment + 1) * 16);
+    push_radius_points(0.0, 0.0, width, height, &radius, segment, &mut positions);
+    push_radius_points(b.start, b.top, inner_w, inner_h, &inner, segment, &mut positions);
+
+    let count = ((segment + 1) * 4) as u16;
+    let mut indices = Vec::with_capacity(count as usize * 6);
+    for i in 0..count {
+        let j = (i + 1) % count;
+        indices.extend_from_slice(&[i, j, count + j, i, count + j, count + i]);
+    }
+    (positions, indices)
+}
+
+// 以多边形的第一个顶点为中心， 将凸多边形划分为三角形
+pub fn fan_indices(polygon: &[u16]) -> Vec<u16> {
+    let mut indices = Vec::with_capacity(polygon.len().max(2) * 3 - 6);
+    for i in 1..polygon.len().max(1) - 1 {
+        indices.extend_from_slice(&[polygon[0], polygon[i], polygon[i + 1]]);
+    }
+    indices
+}
+
+// 将裁剪路径划分为三角形， width、height为布局框的宽高， 返回(positions, 三角形indices)
+pub fn split_by_clip_path(clip_path: &ClipPath, width: f32, height: f32) -> (Vec<f32>, Vec<u16>) {
+    let to_pixel = |v: &LengthUnit, size: f32| match v {
+        LengthUnit::Pixel(r) => *r,
+        LengthUnit::Percent(r) => *r * size,
+    };
+    let (cx, cy, rx, ry) = match clip_path {
+        ClipPath::Circle(r, x, y) => {
+            let r = to_pixel(r, (width * width + height * height).sqrt() / std::f32::consts::SQRT_2);
+            (to_pixel(x, width), to_pixel(y, height), r, r)
+        }
+        ClipPath::Ellipse(rx, ry, x, y) => (
+            to_pixel(x, width),
+            to_pixel(y, height),
+            to_pixel(rx, width),
+            to_pixel(ry, height),
+        ),
+        ClipPath::Polygon(points) => {
+            if points.len() < 3 {
+                return (Vec::new(), Vec::new());
+            }
+            let mut positions = Vec::with_capacity(points.len() * 2);
+            for (x, y) in points.iter() {
+                positions.push(to_pixel(x, width));
+                positions.push(to_pixel(y, height));
+            }
+            // 多边形可能是凹的， 需要三角剖分
+            let points: Vec<Point2d<f32>> = positions.chunks_exact(2).map(|p| Point2d::new(p[0], p[1])).collect();
+            let polygon = Polygon2d::new(points.as_slice());
+            let indices = polygon.triangulation().iter().map(|&v| v as u16).collect();
+            return (positions, indices);
+        }
+    };
+    if rx <= 0.0 || ry <= 0.0 {
+        return (Vec::new(), Vec::new());
+    }
+    // 与圆角一样， y轴向下， 逆时针方向
+    let segment = ((rx.max(ry).sqrt() * 8.0).ceil() as usize).max(16).min(64);
+    let mut positions = Vec::with_capacity(segment * 2);
+    for i in 0..segment {
+        let angle = -2.0 * std::f32::consts::PI * i as f32 / segment as f32;
+        positions.push(cx + rx * angle.cos());
+        positions.push(cy + ry * angle.sin());
+    }
+    let polygon: Vec<u16> = (0..segment as u16).collect();
+    (positions, fan_indices(polygon.as_slice()))
+}
+
+// 径向渐变的环形剖分中， 一周划分的份数
+const RADIAL_SEGMENT: f32 = 64.0;
+
+// 计算径向渐变的中心和半径（椭圆的x、y半径）， rect为渐变框， center为渐变中心相对于渐变框的比例（0~1）
+pub fn cal_radial_gradient(
+    rect: &Aabb2,
+    center: (f32, f32),
+    shape: RadialGradientShape,
+    size: RadialGradientSize,
+) -> (Point2, (f32, f32)) {
+    let c = Point2::new(
+        rect.min.x + (rect.max.x - rect.min.x) * center.0,
+        rect.min.y + (rect.max.y - rect.min.y) * center.1,
+    );
+    // 中心到左右、上下边的距离
+    let (l, r) = ((c.x - rect.min.x).abs(), (rect.max.x - c.x).abs());
+    let (t, b) = ((c.y - rect.min.y).abs(), (rect.max.y - c.y).abs());
+    let (min_x, max_x, min_y, max_y) = (l.min(r), l.max(r), t.min(b), t.max(b));
+    let radius = match (shape, size) {
+        (RadialGradientShape::Circle, RadialGradientSize::ClosestSide) => {
+            let r = min_x.min(min_y);
+            (r, r)
+        }
+        (RadialGradientShape::Circle, RadialGradientSize::FarthesSide) => {
+            let r = max_x.max(max_y);
+            (r, r)
+        }
+        (RadialGradientShape::Circle, RadialGradientSize::ClosestCorner) => {
+            let r = (min_x * min_x + min_y * min_y).sqrt();
+            (r, r)
+        }
+        (RadialGradientShape::Circle, RadialGradientSize::Farthescorner) => {
+            let r = (max_x * max_x + max_y * max_y).sqrt();
+            (r, r)
+        }
+        (RadialGradientShape::Ellipse, RadialGradientSize::ClosestSide) => (min_x, min_y),
+        (RadialGradientShape::Ellipse, RadialGradientSize::FarthesSide) => (max_x, max_y),
+        // 椭圆的corner尺寸， 保持side尺寸的宽高比， 并经过对应的角， 即side尺寸放大根号2倍
+        (RadialGradientShape::Ellipse, RadialGradientSize::ClosestCorner) => {
+            (min_x * std::f32::consts::SQRT_2, min_y * std::f32::consts::SQRT_2)
+        }
+        (RadialGradientShape::Ellipse, RadialGradientSize::Farthescorner) => {
+            (max_x * std::f32::consts::SQRT_2, max_y * std::f32::consts::SQRT_2)
+        }
+    };
+    // 半径为0时， 渐变退化为最后一个色标的颜色， 这里用一个极小的半径代替
+    (c, (radius.0.max(0.0001), radius.1.max(0.0001)))
+}
+
+// 点在径向渐变上的位置（0为中心， 1为渐变椭圆的边）
+#[inline]
+pub fn radial_gradient_pos(x: f32, y: f32, center: &Point2, radius: (f32, f32)) -> f32 {
+    let (dx, dy) = ((x - center.x) / radius.0, (y - center.y) / radius.1);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// 根据渐变位置， 在色标列表中插值出颜色， 超出色标范围的部分取端点颜色
+pub fn radial_gradient_color(list: &[ColorAndPosition], pos: f32) -> [f32; 4] {
+    let first = match list.first() {
+        Some(r) => r,
+        None => return [0.0, 0.0, 0.0, 0.0],
+    };
+    if pos <= first.position {
+        return [first.rgba.r, first.rgba.g, first.rgba.b, first.rgba.a];
+    }
+    for i in 1..list.len() {
+        let (s, e) = (&list[i - 1], &list[i]);
+        if pos <= e.position {
+            let d = e.position - s.position;
+            let ratio = if d <= 0.0 { 1.0 } else { (pos - s.position) / d };
+            return [
+                s.rgba.r + (e.rgba.r - s.rgba.r) * ratio,
+                s.rgba.g + (e.rgba.g - s.rgba.g) * ratio,
+                s.rgba.b + (e.rgba.b - s.rgba.b) * ratio,
+                s.rgba.a + (e.rgba.a - s.rgba.a) * ratio,
+            ];
+        }
+    }
+    let last = &list[list.len() - 1];
+    [last.rgba.r, last.rgba.g, last.rgba.b, last.rgba.a]
+}
+
+// 以渐变中心为圆心， 按色标位置将渐变划分为同心的椭圆环， 每个环再按角度划分为小块（最内层为三角形， 其余为四边形），
+// 各小块被凸多边形（outline为多边形的顶点）裁剪后剖分为三角形， 中心可以在多边形的外部， 返回(positions, colors, indices)
+pub fn split_by_radial_gradient(
+    outline: &[f32],
+    center: &Point2,
+    radius: (f32, f32),
+    list: &[ColorAndPosition],
+) -> (Vec<f32>, Vec<f32>, Vec<u16>) {
+    let (mut positions, mut colors, mut indices) = (Vec::new(), Vec::new(), Vec::new());
+    let len = outline.len() / 2;
+    if len < 3 {
+        return (positions, colors, indices);
+    }
+    // 多边形的环绕方向， 用于判断点在边的哪一侧
+    let mut area = 0.0;
+    for i in 0..len {
+        let j = (i + 1) % len;
+        area += outline[i * 2] * outline[j * 2 + 1] - outline[j * 2] * outline[i * 2 + 1];
+    }
+    let sign = if area < 0.0 { -1.0 } else { 1.0 };
+
+    // 最外层的环需要包含整个多边形， 环的边是椭圆的弦， 弦在椭圆内侧， 需要再放大
+    let step = 2.0 * std::f32::consts::PI / RADIAL_SEGMENT;
+    let mut max_pos: f32 = 0.0;
+    for i in 0..len {
+        max_pos = max_pos.max(radial_gradient_pos(outline[i * 2], outline[i * 2 + 1], center, radius));
+    }
+    let max_pos = (max_pos / (step / 2.0).cos() * 1.001).max(0.0001);
+    // 环的边界： 中心、落在多边形范围内的各色标， 以及最外层
+    let mut rings = vec![0.0];
+    for c in list.iter() {
+        if c.position > rings[rings.len() - 1] && c.position < max_pos {
+            rings.push(c.position);
+        }
+    }
+    rings.push(max_pos);
+
+    let segment = RADIAL_SEGMENT as usize;
+    let point = |pos: f32, i: usize| {
+        let angle = step * (i % segment) as f32;
+        (center.x + radius.0 * pos * angle.cos(), center.y + radius.1 * pos * angle.sin())
+    };
+    for r in 0..rings.len() - 1 {
+        let (inner, outer) = (rings[r], rings[r + 1]);
+        for i in 0..segment {
+            let mut piece = Vec::with_capacity(4);
+            if inner <= 0.0 {
+                piece.push((center.x, center.y));
+            } else {
+                piece.push(point(inner, i));
+                piece.push(point(inner, i + 1));
+            }
+            piece.push(point(outer, i + 1));
+            piece.push(point(outer, i));
+            let piece = clip_by_convex(piece, outline, sign);
+            if piece.len() < 3 {
+                continue;
+            }
+            let start = (positions.len() / 2) as u16;
+            for (x, y) in piece.iter() {
+                positions.extend_from_slice(&[*x, *y]);
+                let pos = radial_gradient_pos(*x, *y, center, radius);
+                colors.extend_from_slice(&ring_color(list, pos, (inner + outer) / 2.0));
+            }
+            for k in 1..piece.len() as u16 - 1 {
+                indices.extend_from_slice(&[start, start + k, start + k + 1]);
+            }
+        }
+    }
+    (positions, colors, indices)
+}
+
+// 用凸多边形outline裁剪多边形（Sutherland-Hodgman）， sign为outline的环绕方向
+fn clip_by_convex(mut polygon: Vec<(f32, f32)>, outline: &[f32], sign: f32) -> Vec<(f32, f32)> {
+    let len = outline.len() / 2;
+    for i in 0..len {
+        if polygon.len() == 0 {
+            break;
+        }
+        let j = (i + 1) % len;
+        let (ax, ay, bx, by) = (outline[i * 2], outline[i * 2 + 1], outline[j * 2], outline[j * 2 + 1]);
+        let side = |p: &(f32, f32)| ((bx - ax) * (p.1 - ay) - (by - ay) * (p.0 - ax)) * sign;
+        let input = std::mem::replace(&mut polygon, Vec::new());
+        for k in 0..input.len() {
+            let (p, q) = (&input[k], &input[(k + 1) % input.len()]);
+            let (sp, sq) = (side(p), side(q));
+            if sp >= 0.0 {
+                polygon.push(*p);
+            }
+            if (sp >= 0.0) != (sq >= 0.0) {
+                let t = sp / (sp - sq);
+                polygon.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            }
+        }
+    }
+    polygon
+}
+
+// 环内的颜色， mid为环的中间位置， 用于确定所在的两个色标， 使位置相同的两个色标（硬边界）两侧的颜色不被混合
+fn ring_color(list: &[ColorAndPosition], pos: f32, mid: f32) -> [f32; 4] {
+    // 环在第一个色标之前或最后一个色标之后， 为端点颜色
+    let i = match list.iter().position(|c| c.position >= mid) {
+        Some(0) => return radial_gradient_color(list, std::f32::MIN),
+        None => return radial_gradient_color(list, std::f32::MAX),
+        Some(r) => r,
+    };
+    let (s, e) = (&list[i - 1], &list[i]);
+    let d = e.position - s.position;
+    let ratio = if d <= 0.0 { 1.0 } else { ((pos - s.position) / d).max(0.0).min(1.0) };
+    [
+        s.rgba.r + (e.rgba.r - s.rgba.r) * ratio,
+        s.rgba.g + (e.rgba.g - s.rgba.g) * ratio,
+        s.rgba.b + (e.rgba.b - s.rgba.b) * ratio,
+        s.rgba.a + (e.rgba.a - s.rgba.a) * ratio,
+    ]
+}
+
 pub fn modify_opacity<C: HalContext + 'static>(
     _engine: &mut Engine<C>,
     render_obj: &mut RenderObj,
@@ -385,3 +833,19 @@ pub fn create_render_obj(
     render_map.insert(context, render_index);
     render_index
 }
+
+// 节点的书写方向是否为从右向左， direction为Inherit时使用父节点的方向， 根节点默认从左向右
+pub fn is_rtl(
+    mut id: usize,
+    idtree: &IdTree,
+    other_layout_styles: &MultiCaseImpl<Node, OtherLayoutStyle>,
+) -> bool {
+    while id > 0 {
+        match other_layout_styles[id].direction {
+            Direction::LTR => return false,
+            Direction::RTL => return true,
+            _ => id = idtree[id].parent(),
+        }
+    }
+    false
+}
@@ -0,0 +1,363 @@
+/**
+ * 虚拟列表的更新（见single::virtual_list）
+ * update_virtual_lists由宿主在每帧运行派发之前调用： 测量上一帧布局后的行高， 计算可视范围， 回收、创建并绑定行节点， 设置行的位置
+ * 创建和绑定行节点的回调会修改节点树和组件， 因此不能在系统中调用， 调用回调时不持有任何组件的借用
+ * VirtualListSys监听节点的删除， 删除虚拟列表及其中的行
+ */
+use std::usize::MAX as UMAX;
+
+use ecs::{DeleteEvent, EntityListener, Lend, LendMut, SingleCaseImpl};
+use ecs::monitor::NotifyImpl;
+use flex_layout::{Dimension, Display, PositionType};
+use hal_core::HalContext;
+
+use component::calc::{StyleType1, StyleType2};
+use component::user::Show;
+use entity::Node;
+use single::*;
+use system::scroll::{scroll_to, set_offset};
+use world::GuiWorld;
+
+#[derive(Default)]
+pub struct VirtualListSys;
+
+impl<'a> EntityListener<'a, Node, DeleteEvent> for VirtualListSys {
+    type ReadData = ();
+    type WriteData = &'a mut SingleCaseImpl<VirtualLists>;
+    fn listen(&mut self, event: &DeleteEvent, _read: Self::ReadData, virtual_lists: Self::WriteData) {
+        if virtual_lists.0.remove(&event.id).is_some() {
+            return;
+        }
+        for list in virtual_lists.0.values_mut() {
+            list.rows.retain(|r| r.node != event.id);
+            list.pool.retain(|r| *r != event.id);
+        }
+    }
+}
+
+impl_system! {
+    VirtualListSys,
+    false,
+    {
+        EntityListener<Node, DeleteEvent>
+    }
+}
+
+/// 将节点设置为虚拟列表， 节点需要是滚动容器（overflow为scroll、auto）
+/// estimated_height为未测量的数据项的预估高度， create创建行节点， bind将数据项绑定到行节点
+pub fn create_virtual_list<C: HalContext + 'static>(
+    world: &GuiWorld<C>,
+    node: usize,
+    estimated_height: f32,
+    create: RowCreator,
+    bind: RowBinder,
+) {
+    world
+        .virtual_lists
+        .lend_mut()
+        .0
+        .insert(node, VirtualList::new(estimated_height, create, bind));
+}
+
+/// 取消节点的虚拟列表， 已创建的行节点保留为普通子节点， 返回节点是否为虚拟列表
+pub fn remove_virtual_list<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> bool {
+    world.virtual_lists.lend_mut().0.remove(&node).is_some()
+}
+
+/// 设置数据项的数量， 所有行在下一帧重新绑定
+pub fn set_virtual_list_count<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, count: usize) -> bool {
+    match world.virtual_lists.lend_mut().0.get_mut(&node) {
+        Some(r) => {
+            r.set_item_count(count);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 数据改变， 所有行在下一帧重新绑定
+pub fn refresh_virtual_list<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize) -> bool {
+    match world.virtual_lists.lend_mut().0.get_mut(&node) {
+        Some(r) => {
+            r.rebind = true;
+            true
+        }
+        None => false,
+    }
+}
+
+/// 滚动到数据项（数据项的顶部对齐可视区域的顶部）， 下一帧生效
+pub fn virtual_list_scroll_to_index<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, index: usize, smooth: bool) -> bool {
+    let top = match world.virtual_lists.lend_mut().0.get_mut(&node) {
+        Some(r) => r.top(index),
+        None => return false,
+    };
+    let x = world.scroll_offset.lend().get(node).map(|r| r.x).unwrap_or(0.0);
+    scroll_to(world, node, x, top, smooth)
+}
+
+/// 更新所有虚拟列表， 在每帧运行派发之前调用
+pub fn update_virtual_lists<C: HalContext + 'static>(world: &GuiWorld<C>) {
+    let ids: Vec<usize> = world.virtual_lists.lend().0.keys().cloned().collect();
+    for id in ids.into_iter() {
+        update_virtual_list(world, id);
+    }
+}
+
+fn update_virtual_list<C: HalContext + 'static>(world: &GuiWorld<C>, id: usize) {
+    let mut binds: Vec<(usize, usize)> = Vec::new(); // (行节点, 数据项索引)
+    let mut missing: Vec<usize> = Vec::new(); // 需要创建行节点的数据项
+    let mut shows: Vec<usize> = Vec::new(); // 从回收池中取出的行节点
+    let mut hides: Vec<usize> = Vec::new(); // 放入回收池的行节点
+    let (create, bind) = {
+        let virtual_lists = world.virtual_lists.lend_mut();
+        let list = match virtual_lists.0.get_mut(&id) {
+            Some(r) => r,
+            None => return,
+        };
+        let layouts = world.layout.lend();
+        let layout = match layouts.get(id) {
+            Some(r) => r,
+            None => return,
+        };
+        let offsets = world.scroll_offset.lend_mut();
+        let scroll_states = world.scroll_states.lend();
+
+        // 测量上一帧布局后的行高， 可视区域之上的数据项高度改变时， 调整滚动偏移， 使可见的内容保持不动
+        let mut offset_y = offsets.get(id).map(|r| r.y).unwrap_or(0.0);
+        let scroll_top = offset_y - layout.padding.top;
+        // 可视区域顶部所在的数据项， 之前的数据项高度改变会使其移动
+        let anchor_index = list.index_at(scroll_top);
+        let mut anchor = 0.0;
+        let measures: Vec<(usize, f32)> = list
+            .rows
+            .iter()
+            .filter(|r| r.top >= 0.0)
+            .filter_map(|r| layouts.get(r.node).map(|l| (r.index, l.rect.bottom - l.rect.top)))
+            .collect();
+        for (index, height) in measures.into_iter() {
+            let old = list.height(index);
+            if list.set_height(index, height) && index < anchor_index {
+                anchor += height - old;
+            }
+        }
+        let dragging = scroll_states.states.get(&id).map(|r| r.dragging).unwrap_or(false);
+        if anchor != 0.0 && !dragging {
+            offset_y += anchor;
+            let x = offsets.get(id).map(|r| r.x).unwrap_or(0.0);
+            set_offset(offsets, id, x, offset_y);
+        }
+
+        // 可视范围（内容框坐标）
+        let view_height = match scroll_states.states.get(&id) {
+            Some(r) if r.view_height > 0.0 => r.view_height,
+            _ => layout.rect.bottom - layout.rect.top - layout.border.top - layout.border.bottom,
+        };
+        let scroll_top = offset_y - layout.padding.top;
+        let (first, last) = if list.item_count == 0 {
+            (1, 0)
+        } else {
+            (
+                list.index_at(scroll_top - list.overscan),
+                list.index_at(scroll_top + view_height + list.overscan),
+            )
+        };
+
+        // 回收可视范围之外的行
+        let rebind = list.rebind;
+        list.rebind = false;
+        let mut free = Vec::new();
+        let mut covered = vec![false; (last + 1).saturating_sub(first)];
+        list.rows.retain(|r| {
+            if r.index >= first && r.index <= last {
+                covered[r.index - first] = true;
+                if rebind {
+                    binds.push((r.node, r.index));
+                }
+                true
+            } else {
+                free.push(r.node);
+                false
+            }
+        });
+        for (i, c) in covered.iter().enumerate() {
+            if *c {
+                continue;
+            }
+            let index = first + i;
+            let node = match free.pop() {
+                Some(r) => r,
+                None => match list.pool.pop() {
+                    Some(r) => {
+                        shows.push(r);
+                        r
+                    }
+                    None => {
+                        missing.push(index);
+                        continue;
+                    }
+                },
+            };
+            list.rows.push(VirtualRow { node: node, index: index, top: -1.0 });
+            binds.push((node, index));
+        }
+        for node in free.into_iter() {
+            list.pool.push(node);
+            hides.push(node);
+        }
+        (list.create.clone(), list.bind.clone())
+    };
+
+    for node in hides.into_iter() {
+        set_row_display(world, node, Display::None);
+    }
+    for node in shows.into_iter() {
+        set_row_display(world, node, Display::Flex);
+    }
+    // 创建行节点， 并添加到列表上
+    for index in missing.into_iter() {
+        let node = create(id);
+        {
+            let idtree = world.idtree.lend_mut();
+            let notify = unsafe { &*(idtree.get_notify_ref() as *const NotifyImpl) };
+            idtree.insert_child_with_notify(node, id, UMAX, notify);
+        }
+        if let Some(list) = world.virtual_lists.lend_mut().0.get_mut(&id) {
+            list.rows.push(VirtualRow { node: node, index: index, top: -1.0 });
+        }
+        binds.push((node, index));
+    }
+    // 绑定数据
+    for (node, index) in binds.into_iter() {
+        bind(id, node, index);
+    }
+
+    // 设置行的位置
+    let virtual_lists = world.virtual_lists.lend_mut();
+    let list = match virtual_lists.0.get_mut(&id) {
+        Some(r) => r,
+        None => return,
+    };
+    for i in 0..list.rows.len() {
+        let top = list.top(list.rows[i].index);
+        let row = &mut list.rows[i];
+        if row.top != top {
+            row.top = top;
+            set_row_top(world, row.node, top);
+        }
+    }
+}
+
+// 行节点绝对定位， 左右撑满列表的内容框
+fn set_row_top<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, top: f32) {
+    let other_layout_styles = world.other_layout_style.lend_mut();
+    let style_marks = world.style_mark.lend_mut();
+    if style_marks.get(node).is_none() {
+        return;
+    }
+    let style = &mut other_layout_styles[node];
+    style.position_type = PositionType::Absolute;
+    style.position.start = Dimension::Points(0.0);
+    style.position.end = Dimension::Points(0.0);
+    style.position.top = Dimension::Points(top);
+    style_marks[node].local_style2 |= StyleType2::PositionType as usize
+        | StyleType2::PositionLeft as usize
+        | StyleType2::PositionRight as usize
+        | StyleType2::PositionTop as usize;
+    let notify = other_layout_styles.get_notify_ref();
+    notify.modify_event(node, "position_type", 0);
+    notify.modify_event(node, "left", 0);
+    notify.modify_event(node, "right", 0);
+    notify.modify_event(node, "top", 0);
+}
+
+// 回收的行节点display为none， 不参与布局和渲染
+fn set_row_display<C: HalContext + 'static>(world: &GuiWorld<C>, node: usize, display: Display) {
+    let other_layout_styles = world.other_layout_style.lend_mut();
+    let style_marks = world.style_mark.lend_mut();
+    if style_marks.get(node).is_none() {
+        return;
+    }
+    other_layout_styles[node].display = display;
+    style_marks[node].local_style1 |= StyleType1::Display as usize;
+    other_layout_styles.get_notify_ref().modify_event(node, "display", 0);
+    unsafe { world.show.lend_mut().get_unchecked_write(node) }.modify(|s: &mut Show| {
+        let old = s.clone();
+        s.set_display(display);
+        old != *s
+    });
+}
+
+#[cfg(test)]
+use std::cell::Cell;
+#[cfg(test)]
+use share::Share;
+#[cfg(test)]
+use hal_null::NullHalContext;
+#[cfg(test)]
+use component::user::{BorderRadius, LengthUnit};
+#[cfg(test)]
+use util::snapshot::{NodeDesc, SceneDesc, SnapshotGui};
+
+#[test]
+fn test_virtual_list() {
+    let mut gui = SnapshotGui::new(SceneDesc::new(100, 100));
+    let root = gui.root;
+    let list = gui.append(&NodeDesc::new("position:absolute;left:0px;top:0px;width:100px;height:100px;overflow:auto"), root);
+    gui.gui.system_time.lend_mut().cur_time = 1;
+    gui.render();
+
+    // 偶数项高20， 奇数项高40
+    let gui_ptr = &gui.gui as *const GuiWorld<NullHalContext> as usize;
+    let created = Share::new(Cell::new(0));
+    let created1 = created.clone();
+    create_virtual_list(
+        &gui.gui,
+        list,
+        20.0,
+        Share::new(move |_list: usize| {
+            let gui = unsafe { &*(gui_ptr as *const GuiWorld<NullHalContext>) };
+            let node = gui.node.lend_mut().create();
+            gui.border_radius.lend_mut().insert(node, BorderRadius::new(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)));
+            gui.idtree.lend_mut().create(node);
+            created1.set(created1.get() + 1);
+            node
+        }),
+        Share::new(move |_list: usize, row: usize, index: usize| {
+            let gui = unsafe { &*(gui_ptr as *const GuiWorld<NullHalContext>) };
+            let styles = gui.rect_layout_style.lend_mut();
+            styles[row].size.height = Dimension::Points(if index % 2 == 0 { 20.0 } else { 40.0 });
+            gui.style_mark.lend_mut()[row].local_style2 |= StyleType2::Height as usize;
+            styles.get_notify_ref().modify_event(row, "height", 0);
+        }),
+    );
+    set_virtual_list_count(&gui.gui, list, 1000);
+
+    // 按预估高度创建可视范围内的行
+    gui.render();
+    assert_eq!(created.get(), 8);
+
+    // 测量后按实际高度排列， 多余的行被回收
+    gui.render();
+    gui.render();
+    {
+        let virtual_lists = gui.gui.virtual_lists.lend();
+        let l = &virtual_lists.0[&list];
+        assert_eq!(l.rows.len(), 6);
+        assert_eq!(l.pool.len(), 2);
+        let row = l.rows.iter().find(|r| r.index == 2).unwrap();
+        assert_eq!(gui.gui.layout.lend()[row.node].rect.top, 60.0);
+    }
+
+    // 滚动到底部， 行节点被复用， 不会为不可见的数据项创建节点
+    assert!(virtual_list_scroll_to_index(&gui.gui, list, 999, false));
+    for i in 2..20 {
+        gui.gui.system_time.lend_mut().cur_time = i * 16;
+        gui.render();
+    }
+    assert_eq!(created.get(), 8);
+    let virtual_lists = gui.gui.virtual_lists.lend();
+    let l = &virtual_lists.0[&list];
+    assert!(l.rows.len() > 0);
+    assert!(l.rows.iter().all(|r| r.index > 980));
+}
@@ -49,6 +49,16 @@ impl WorldMatrixSys {
         };
     }
 
+    fn marked_children_dirty(&mut self, id: usize, id_tree: &SingleCaseImpl<IdTree>) {
+        let first = match id_tree.get(id) {
+            Some(r) => r.children().head,
+            None => return,
+        };
+        for (child, _) in id_tree.iter(first) {
+            self.marked_dirty(child, id_tree);
+        }
+    }
+
     fn cal_matrix(
         &mut self,
         idtree: &SingleCaseImpl<IdTree>,
@@ -57,6 +67,7 @@ impl WorldMatrixSys {
         world_matrix: &mut MultiCaseImpl<Node, WorldMatrix>,
 		default_table: &SingleCaseImpl<DefaultTable>,
 		node_states: &MultiCaseImpl<Node, NodeState>,
+		scroll_offsets: &MultiCaseImpl<Node, ScrollOffset>,
     ) {
         let mut count = 0;
 		// let time = std::time::Instant::now();
@@ -107,7 +118,8 @@ impl WorldMatrixSys {
                 world_matrix,
                 default_transform,
 				&mut count,
-				node_states
+				node_states,
+				scroll_offsets,
             );
         }
         self.dirty.clear();
@@ -121,10 +133,11 @@ impl<'a> Runner<'a> for WorldMatrixSys {
         &'a MultiCaseImpl<Node, LayoutR>,
 		&'a SingleCaseImpl<DefaultTable>,
 		&'a MultiCaseImpl<Node, NodeState>,
+		&'a MultiCaseImpl<Node, ScrollOffset>,
     );
     type WriteData = &'a mut MultiCaseImpl<Node, WorldMatrix>;
     fn run(&mut self, read: Self::ReadData, write: Self::WriteData) {
-        self.cal_matrix(read.0, read.1, read.2, write, read.3, read.4);
+        self.cal_matrix(read.0, read.1, read.2, write, read.3, read.4, read.5);
     }
 }
 
@@ -181,6 +194,31 @@ impl<'a> MultiCaseListener<'a, Node, LayoutR, ModifyEvent> for WorldMatrixSys {
     }
 }
 
+// 滚动偏移改变， 子节点的世界矩阵脏（滚动容器自身的世界矩阵不变）
+impl<'a> MultiCaseListener<'a, Node, ScrollOffset, ModifyEvent> for WorldMatrixSys {
+    type ReadData = &'a SingleCaseImpl<IdTree>;
+    type WriteData = ();
+    fn listen(&mut self, event: &ModifyEvent, read: Self::ReadData, _write: Self::WriteData) {
+        self.marked_children_dirty(event.id, read);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, ScrollOffset, CreateEvent> for WorldMatrixSys {
+    type ReadData = &'a SingleCaseImpl<IdTree>;
+    type WriteData = ();
+    fn listen(&mut self, event: &CreateEvent, read: Self::ReadData, _write: Self::WriteData) {
+        self.marked_children_dirty(event.id, read);
+    }
+}
+
+impl<'a> MultiCaseListener<'a, Node, ScrollOffset, DeleteEvent> for WorldMatrixSys {
+    type ReadData = &'a SingleCaseImpl<IdTree>;
+    type WriteData = ();
+    fn listen(&mut self, event: &DeleteEvent, read: Self::ReadData, _write: Self::WriteData) {
+        self.marked_children_dirty(event.id, read);
+    }
+}
+
 impl<'a> SingleCaseListener<'a, IdTree, CreateEvent> for WorldMatrixSys {
     type ReadData = &'a SingleCaseImpl<IdTree>;
     type WriteData = ();
@@ -219,6 +257,7 @@ fn recursive_cal_matrix(
     default_transform: &Transform,
 	count: &mut usize,
 	node_states: &MultiCaseImpl<Node, NodeState>,
+	scroll_offsets: &MultiCaseImpl<Node, ScrollOffset>,
 ) {
     // *count = 1 + *count;
     // match dirty_mark_list.get_mut(id) {
@@ -252,7 +291,12 @@ fn recursive_cal_matrix(
         let parent_transform_origin = parent_transform
             .origin
             .to_value(parent_layout.rect.end - parent_layout.rect.start, parent_layout.rect.bottom - parent_layout.rect.top);
-        let offset = get_lefttop_offset(&layout, &parent_transform_origin, &parent_layout);
+        let mut offset = get_lefttop_offset(&layout, &parent_transform_origin, &parent_layout);
+        // 滚动容器的子节点整体偏移
+        if let Some(scroll) = scroll_offsets.get(parent) {
+            offset.x -= scroll.x;
+            offset.y -= scroll.y;
+        }
         parent_world_matrix
             * transform_value.matrix(width, height, &offset)
 	};
@@ -276,7 +320,8 @@ fn recursive_cal_matrix(
             world_matrix,
             default_transform,
 			count,
-			node_states
+			node_states,
+			scroll_offsets,
         );
     }
 }
@@ -290,6 +335,9 @@ impl_system! {
         MultiCaseListener<Node, Transform, CreateEvent>
         MultiCaseListener<Node, Transform, DeleteEvent>
         MultiCaseListener<Node, LayoutR, ModifyEvent>
+        MultiCaseListener<Node, ScrollOffset, ModifyEvent>
+        MultiCaseListener<Node, ScrollOffset, CreateEvent>
+        MultiCaseListener<Node, ScrollOffset, DeleteEvent>
 		SingleCaseListener<IdTree, CreateEvent>
 		EntityListener<Node, DeleteEvent>
     }
@@ -1,2 +1,4 @@
 pub mod vecmap_default;
-pub mod hashmap_default;
\ No newline at end of file
+pub mod hashmap_default;
+#[cfg(test)]
+pub mod snapshot;
\ No newline at end of file
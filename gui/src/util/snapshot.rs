@@ -0,0 +1,527 @@
+/**
+ * 渲染快照测试工具
+ * 用hal_null创建一个无头的gui，按声明的样式表和节点树构建界面，运行渲染派发，读回默认渲染目标的像素，与png基准图比较
+ * 基准图放在 gui/tests/snapshot/ 目录下：
+ *   设置了环境变量 GUI_SNAPSHOT_UPDATE 时，将本次的渲染结果写为基准图；未设置时，基准图不存在视为失败
+ *   比较失败时，在基准图旁写出 {name}.actual.png（本次结果）和 {name}.diff.png（差异图，不同的像素标红）
+ * 注：默认的测试字体没有字形，文字节点只参与布局；设置了SceneDesc::native_font时，字形由原生字体光栅化到字体纹理，与文字一起绘制
+ */
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::mem::transmute;
+use std::path::PathBuf;
+use std::usize::MAX as UMAX;
+
+use atom::Atom;
+use ecs::{Lend, LendMut};
+use ecs::monitor::NotifyImpl;
+use flex_layout::*;
+use hal_core::*;
+use hal_null::NullHalContext;
+use png;
+use share::Share;
+
+use component::calc::Visibility;
+use component::user::*;
+use render::engine::{Engine, UnsafeMut};
+use render::res::Opacity as ROpacity;
+use render::res::{AlphaMask, TextureRes};
+use single::style_parse::{parse_class_from_string, parse_class_sheet_from_string};
+use single::RenderBegin;
+use system::update_virtual_lists;
+use world::{create_res_mgr, create_world, GuiWorld, RENDER_DISPATCH};
+
+// 行内样式转换成的class，从该值开始分配class_id，避免与样式表中的class冲突
+const INLINE_CLASS_START: usize = 1000000000;
+
+// 测试用的字体名称， 所有文字节点都使用该字体
+const SNAPSHOT_FONT: &'static str = "__$snapshot";
+
+/// 节点描述
+#[derive(Default)]
+pub struct NodeDesc {
+    pub class: Vec<usize>,    // 样式表中的class_id
+    pub style: String,        // 行内样式，格式同class的内容，如："width:10px;height:10px;background-color:#ff0000"
+    pub text: Option<String>, // 存在时，创建为文字节点
+    pub children: Vec<NodeDesc>,
+}
+
+impl NodeDesc {
+    pub fn new(style: &str) -> Self {
+        NodeDesc {
+            style: style.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_class(mut self, class: &[usize]) -> Self {
+        self.class.extend_from_slice(class);
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    pub fn with_child(mut self, child: NodeDesc) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+/// 图片描述，url与样式中的url(...)对应，data为从上到下的rgba数据
+pub struct ImageDesc {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl ImageDesc {
+    /// 纯色图片
+    pub fn fill(url: &str, width: u32, height: u32, rgba: [u8; 4]) -> Self {
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for _ in 0..width * height {
+            data.extend_from_slice(&rgba);
+        }
+        ImageDesc {
+            url: url.to_string(),
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+/// 场景描述
+pub struct SceneDesc {
+    pub width: u32,
+    pub height: u32,
+    pub clear_color: (f32, f32, f32, f32),
+    pub stencil: bool, // 默认渲染目标是否有模板缓冲区
+    pub font_texture_width: u32, // 字体纹理的宽度（每页的最大高度与宽度相同）
+    pub native_font: Option<Vec<u8>>, // 原生字体文件（TTF/OTF）的数据，设置时文字节点使用该字体测量和绘制字形
+    pub css: String, // 样式表，格式同parse_class_sheet_from_string，如：".1{width:10px}"、".1 .2:hover{width:10px}"
+    pub images: Vec<ImageDesc>,
+    pub nodes: Vec<NodeDesc>, // 根节点的子节点
+}
+
+impl SceneDesc {
+    pub fn new(width: u32, height: u32) -> Self {
+        SceneDesc {
+            width,
+            height,
+            clear_color: (1.0, 1.0, 1.0, 1.0),
+            stencil: true,
+            font_texture_width: 4096,
+            native_font: None,
+            css: "".to_string(),
+            images: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+}
+
+pub struct SnapshotGui {
+    pub gui: GuiWorld<NullHalContext>,
+    pub root: usize,
+    images: Vec<ImageDesc>,
+    inline_class: usize,
+}
+
+impl SnapshotGui {
+    pub fn new(scene: SceneDesc) -> Self {
+        let gl = if scene.stencil {
+            NullHalContext::new(scene.width, scene.height)
+        } else {
+            NullHalContext::new_without_stencil(scene.width, scene.height)
+        };
+        let mut engine = UnsafeMut::new(Share::new(Engine::new(gl, create_res_mgr(0))));
+
+        let max_texture_size = engine.gl.render_get_caps().max_texture_size.min(scene.font_texture_width);
+        let texture = engine
+            .gl
+            .texture_create_2d(
+                0,
+                max_texture_size,
+                32,
+                PixelFormat::RGBA,
+                DataFormat::UnsignedByte,
+                false,
+                None,
+            )
+            .unwrap();
+        let res = engine.create_texture_res(
+            Atom::from("__$text".to_string()).get_hash(),
+            TextureRes::new(
+                max_texture_size as usize,
+                32,
+                PixelFormat::RGBA,
+                DataFormat::UnsignedByte,
+                unsafe { transmute(1 as u8) },
+                None,
+                texture,
+            ),
+            0,
+        );
+        // 未设置原生字体时，字符宽度固定为字号的一半，保证布局结果与平台字体无关
+        let measure = Box::new(|_name: &Atom, font_size: usize, _ch: char| -> f32 {
+            font_size as f32 / 2.0
+        });
+
+        let world = create_world::<NullHalContext>(
+            engine,
+            scene.width as f32,
+            scene.height as f32,
+            measure,
+            res,
+            0,
+            None,
+            None,
+        );
+        let gui = GuiWorld::<NullHalContext>::new(world);
+
+        {
+            let font_sheet = gui.font_sheet.lend_mut();
+            let mut font_sheet = font_sheet.borrow_mut();
+            match &scene.native_font {
+                Some(data) => {
+                    if let Err(e) = font_sheet.add_native_font(Atom::from(SNAPSHOT_FONT), data.clone(), true) {
+                        panic!("load native font fail, {:?}", e);
+                    }
+                }
+                None => font_sheet.set_src(Atom::from(SNAPSHOT_FONT), true, 0.0, 0.0),
+            }
+            font_sheet.set_face(Atom::from("__$common"), 0.0, 16, 500, SNAPSHOT_FONT.to_string());
+
+            let class_sheet = gui.class_sheet.lend_mut();
+            if let Err(e) = parse_class_sheet_from_string(scene.css.as_str(), &mut class_sheet.borrow_mut()) {
+                panic!("parse css fail, {:?}", e);
+            }
+
+            let render_begin = gui.world.fetch_single::<RenderBegin>().unwrap();
+            let render_begin = render_begin.lend_mut();
+            let (r, g, b, a) = scene.clear_color;
+            render_begin.0.set_clear_color(true, r, g, b, a);
+        }
+
+        let root = create_root(&gui, scene.width as f32, scene.height as f32);
+        let mut snapshot = SnapshotGui {
+            gui,
+            root,
+            images: scene.images,
+            inline_class: INLINE_CLASS_START,
+        };
+        for node in scene.nodes.iter() {
+            snapshot.append(node, root);
+        }
+        snapshot
+    }
+
+    /// 按描述创建节点，并添加到parent上，返回节点id
+    pub fn append(&mut self, desc: &NodeDesc, parent: usize) -> usize {
+        let gui = &self.gui;
+        let node = gui.node.lend_mut().create();
+        gui.border_radius.lend_mut().insert(
+            node,
+            BorderRadius::new(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)),
+        );
+        gui.idtree.lend_mut().create(node);
+
+        if let Some(text) = &desc.text {
+            gui.text_content
+                .lend_mut()
+                .insert(node, TextContent(text.clone(), Atom::from("")));
+        }
+
+        let mut class = desc.class.clone();
+        if desc.style.trim() != "" {
+            match parse_class_from_string(desc.style.as_str()) {
+                Ok(r) => {
+                    self.inline_class += 1;
+                    let class_sheet = gui.class_sheet.lend_mut();
+                    class_sheet.borrow_mut().class_map.insert(self.inline_class, r);
+                    class.push(self.inline_class);
+                }
+                Err(e) => panic!("parse style fail, {:?}", e),
+            };
+        }
+        if class.len() > 0 {
+            self.set_class(node, &class);
+        }
+
+        let idtree = gui.idtree.lend_mut();
+        let notify = unsafe { &*(idtree.get_notify_ref() as *const NotifyImpl) };
+        idtree.insert_child_with_notify(node, parent, UMAX, notify);
+
+        for child in desc.children.iter() {
+            self.append(child, node);
+        }
+        node
+    }
+
+    /// 替换节点的class（样式表中的class id）， 并发出修改事件
+    pub fn set_class(&self, node: usize, class: &[usize]) {
+        let mut class_name = ClassName::default();
+        for (i, c) in class.iter().enumerate() {
+            match i {
+                0 => class_name.one = *c,
+                1 => class_name.two = *c,
+                _ => class_name.other.push(*c),
+            }
+        }
+        let class_names = self.gui.class_name.lend_mut();
+        let old = class_names.insert_no_notify(node, class_name);
+        class_names
+            .get_notify_ref()
+            .modify_event(node, "", Box::into_raw(Box::new(old)) as usize);
+    }
+
+    /// 运行一帧渲染
+    pub fn render(&mut self) {
+        self.load_image();
+        update_virtual_lists(&self.gui);
+        self.gui.world.run(&RENDER_DISPATCH);
+        // 原生字体的字形已在渲染派发中绘制，剩下的是默认测试字体的文字，没有字形，直接丢弃， wait_draw_map索引wait_draw_list， 必须一起清空
+        let font_sheet = self.gui.font_sheet.lend_mut();
+        let mut font_sheet = font_sheet.borrow_mut();
+        font_sheet.wait_draw_list.clear();
+        font_sheet.wait_draw_map.clear();
+    }
+
+    /// 读回默认渲染目标的像素，返回 (宽, 高, 从上到下的rgba数据)
+    pub fn read_pixels(&self) -> (u32, u32, Vec<u8>) {
+        let render_begin = self.gui.world.fetch_single::<RenderBegin>().unwrap();
+        let render_begin = render_begin.lend();
+        let engine = self.gui.engine.lend();
+        engine
+            .gl
+            .rt_read_pixels(render_begin.1.as_ref().map(|r| &**r))
+    }
+
+    /// 读回(x, y)处像素的rgba
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let (w, _h, pixels) = self.read_pixels();
+        let i = ((y * w + x) * 4) as usize;
+        [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+    }
+
+    /// 渲染一帧，并与基准图比较
+    /// tolerance: 每个通道允许的误差
+    pub fn assert_snapshot(&mut self, name: &str, tolerance: u8) {
+        self.render();
+        let (width, height, pixels) = self.read_pixels();
+        if let Err(e) = compare_snapshot(name, width, height, &pixels, tolerance) {
+            panic!("snapshot {} fail, {}", name, e);
+        }
+    }
+
+    // 将样式中引用到的图片，从场景描述中加载
+    fn load_image(&mut self) {
+        let gui = &self.gui;
+        let image_wait_sheet = gui.image_wait_sheet.lend_mut();
+        if image_wait_sheet.loads.len() == 0 {
+            return;
+        }
+        let engine = gui.engine.lend_mut();
+        for name in image_wait_sheet.loads.iter() {
+            let desc = match self
+                .images
+                .iter()
+                .find(|image| Atom::from(image.url.as_str()).get_hash() == *name)
+            {
+                Some(r) => r,
+                None => continue,
+            };
+            let res = match engine.texture_res_map.get(name) {
+                Some(r) => r,
+                None => {
+                    // 纹理的第0行是v=0，和gl上传图片时一致，从图片的第一行开始
+                    let texture = engine
+                        .gl
+                        .texture_create_2d(
+                            0,
+                            desc.width,
+                            desc.height,
+                            PixelFormat::RGBA,
+                            DataFormat::UnsignedByte,
+                            false,
+                            Some(TextureData::U8(0, 0, desc.width, desc.height, &desc.data)),
+                        )
+                        .unwrap();
+                    let res = engine.create_texture_res(
+                        *name,
+                        TextureRes::new(
+                            desc.width as usize,
+                            desc.height as usize,
+                            PixelFormat::RGBA,
+                            DataFormat::UnsignedByte,
+                            ROpacity::Translucent,
+                            None,
+                            texture,
+                        ),
+                        0,
+                    );
+                    // 提供像素的不透明度， 以便测试按像素的命中
+                    res.set_alpha_mask(AlphaMask::from_rgba(desc.width as usize, desc.height as usize, &desc.data));
+                    res
+                }
+            };
+            if let Some(r) = image_wait_sheet.wait.remove(name) {
+                image_wait_sheet.finish.push((*name, res, r));
+            }
+        }
+        image_wait_sheet.loads.clear();
+        if image_wait_sheet.finish.len() > 0 {
+            image_wait_sheet.get_notify_ref().modify_event(0, "", 0);
+        }
+    }
+}
+
+// 创建根节点，和gui_web的create_gui一致
+fn create_root(gui: &GuiWorld<NullHalContext>, width: f32, height: f32) -> usize {
+    let idtree = gui.idtree.lend_mut();
+    let node = gui.node.lend_mut().create();
+    idtree.create(node);
+
+    gui.border_radius.lend_mut().insert(
+        node,
+        BorderRadius::new(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)),
+    );
+    gui.visibility.lend_mut().insert(node, Visibility(true));
+
+    let rect_layout_styles = gui.rect_layout_style.lend_mut();
+    let other_layout_styles = gui.other_layout_style.lend_mut();
+    let rect_layout_style = &mut rect_layout_styles[node];
+    let other_layout_style = &mut other_layout_styles[node];
+    rect_layout_style.size = Size {
+        width: Dimension::Points(width),
+        height: Dimension::Points(height),
+    };
+    other_layout_style.position_type = PositionType::Absolute;
+    other_layout_style.position = Rect::default();
+    rect_layout_styles.get_notify_ref().modify_event(node, "width", 0);
+    other_layout_styles
+        .get_notify_ref()
+        .modify_event(node, "position_type", 0);
+
+    idtree.insert_child(node, 0, 0);
+    node
+}
+
+fn snapshot_path(name: &str, suffix: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("snapshot");
+    path.push(format!("{}{}.png", name, suffix));
+    path
+}
+
+/**
+ * 将像素与名为name的基准图比较
+ * 设置了GUI_SNAPSHOT_UPDATE时，写入基准图并返回成功
+ * 基准图不存在时返回失败，并写出 {name}.actual.png， 确认无误后用GUI_SNAPSHOT_UPDATE生成基准图
+ */
+pub fn compare_snapshot(
+    name: &str,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    tolerance: u8,
+) -> Result<(), String> {
+    let path = snapshot_path(name, "");
+    if env::var("GUI_SNAPSHOT_UPDATE").is_ok() {
+        return write_png(&path, width, height, pixels);
+    }
+
+    let actual_path = snapshot_path(name, ".actual");
+    let diff_path = snapshot_path(name, ".diff");
+    if !path.exists() {
+        write_png(&actual_path, width, height, pixels)?;
+        return Err(format!(
+            "snapshot {:?} isn't exist, see {:?}, set GUI_SNAPSHOT_UPDATE to write it",
+            path, actual_path
+        ));
+    }
+
+    let (w, h, expect) = read_png(&path)?;
+    if w != width || h != height {
+        write_png(&actual_path, width, height, pixels)?;
+        return Err(format!(
+            "size isn't match, expect: {:?}, actual: {:?}",
+            (w, h),
+            (width, height)
+        ));
+    }
+
+    let mut count = 0;
+    let mut diff = Vec::with_capacity(pixels.len());
+    for i in 0..(width * height) as usize {
+        let (a, b) = (&pixels[i * 4..i * 4 + 4], &expect[i * 4..i * 4 + 4]);
+        let is_same = (0..4).all(|c| (a[c] as i32 - b[c] as i32).abs() <= tolerance as i32);
+        if is_same {
+            // 相同的像素，用变淡的灰度表示
+            let gray = (b[0] as u32 * 299 + b[1] as u32 * 587 + b[2] as u32 * 114) / 1000;
+            let gray = (255 - (255 - gray) / 4) as u8;
+            diff.extend_from_slice(&[gray, gray, gray, 255]);
+        } else {
+            count += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        }
+    }
+    if count == 0 {
+        // 比较成功，清除上次失败时留下的图片
+        let _ = fs::remove_file(&actual_path);
+        let _ = fs::remove_file(&diff_path);
+        return Ok(());
+    }
+
+    write_png(&actual_path, width, height, pixels)?;
+    write_png(&diff_path, width, height, &diff)?;
+    Err(format!(
+        "{} pixels are different, tolerance: {}, see {:?}",
+        count, tolerance, diff_path
+    ))
+}
+
+fn write_png(path: &PathBuf, width: u32, height: u32, pixels: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(pixels).map_err(|e| e.to_string())
+}
+
+fn read_png(path: &PathBuf) -> Result<(u32, u32, Vec<u8>), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let (info, mut reader) = png::Decoder::new(file)
+        .read_info()
+        .map_err(|e| e.to_string())?;
+    if info.color_type != png::ColorType::RGBA || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!(
+            "snapshot must be rgba8, color_type: {:?}, bit_depth: {:?}",
+            info.color_type, info.bit_depth
+        ));
+    }
+    let mut buf = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    Ok((info.width, info.height, buf))
+}
+
+#[test]
+fn test_missing_snapshot() {
+    // 没有基准图时比较失败， 而不是写入基准图
+    if env::var("GUI_SNAPSHOT_UPDATE").is_ok() {
+        return;
+    }
+    let pixels = vec![255; 4 * 4 * 4];
+    assert!(compare_snapshot("__missing", 4, 4, &pixels, 0).is_err());
+    assert!(!snapshot_path("__missing", "").exists());
+    let _ = fs::remove_file(snapshot_path("__missing", ".actual"));
+}
@@ -47,6 +47,7 @@ lazy_static! {
     pub static ref IMAGE_N: Atom = Atom::from("image_sys");
     pub static ref CHAR_BLOCK_N: Atom = Atom::from("charblock_sys");
     pub static ref TEXT_GLPHY_N: Atom = Atom::from("text_glphy_sys");
+    pub static ref NATIVE_TEXT_N: Atom = Atom::from("native_text_sys");
     pub static ref CHAR_BLOCK_SHADOW_N: Atom = Atom::from("charblock_shadow_sys");
     pub static ref NODE_ATTR_N: Atom = Atom::from("node_attr_sys");
     pub static ref FILTER_N: Atom = Atom::from("filter_sys");
@@ -54,6 +55,14 @@ lazy_static! {
     pub static ref RES_RELEASE_N: Atom = Atom::from("res_release");
     pub static ref STYLE_MARK_N: Atom = Atom::from("style_mark_sys");
     pub static ref TRANSFORM_WILL_CHANGE_N: Atom = Atom::from("transform_will_change_sys");
+    pub static ref TRANSITION_N: Atom = Atom::from("transition_sys");
+    pub static ref ANIMATION_N: Atom = Atom::from("animation_sys");
+    pub static ref EVENT_N: Atom = Atom::from("event_sys");
+    pub static ref SCROLL_N: Atom = Atom::from("scroll_sys");
+    pub static ref SCROLL_BAR_N: Atom = Atom::from("scroll_bar_sys");
+    pub static ref VIRTUAL_LIST_N: Atom = Atom::from("virtual_list_sys");
+    pub static ref TEXT_INPUT_N: Atom = Atom::from("text_input_sys");
+    pub static ref CARET_N: Atom = Atom::from("caret_sys");
 }
 
 pub fn create_res_mgr(total_capacity: usize) -> ResMgr {
@@ -202,13 +211,21 @@ pub fn create_world<C: HalContext + 'static>(
     // world.register_multi::<Node, CharBlock<L>>();
     world.register_multi::<Node, TextStyle>();
     world.register_multi::<Node, TextContent>();
+    world.register_multi::<Node, TextSpans>();
     world.register_multi::<Node, Font>();
     world.register_multi::<Node, BorderRadius>();
+    world.register_multi::<Node, ClipPath>();
+    world.register_multi::<Node, HitTest>();
+    world.register_multi::<Node, ScrollOffset>();
+    world.register_multi::<Node, ScrollBar>();
     world.register_multi::<Node, Image>();
     world.register_multi::<Node, ImageClip>();
     world.register_multi::<Node, ObjectFit>();
     world.register_multi::<Node, Filter>();
     world.register_multi::<Node, ClassName>();
+    world.register_multi::<Node, PseudoClass>();
+    world.register_multi::<Node, Transition>();
+    world.register_multi::<Node, Animation>();
     world.register_multi::<Node, StyleMark>();
 	world.register_multi::<Node, TransformWillChange>();
 	world.register_multi::<Node, RectLayoutStyle>();
@@ -226,6 +243,7 @@ pub fn create_world<C: HalContext + 'static>(
     world.register_multi::<Node, HSV>();
     world.register_multi::<Node, Culling>();
 	world.register_multi::<Node, TransformWillChangeMatrix>();
+	world.register_multi::<Node, CharBlock>();
 
 	let mut idtree = IdTree::with_capacity(capacity);
 	idtree.set_statistics_count(true);
@@ -233,7 +251,10 @@ pub fn create_world<C: HalContext + 'static>(
     world.register_single::<Statistics>(Statistics::default());
     world.register_single::<IdTree>(idtree);
     world.register_single::<Oct>(Oct::with_capacity(capacity));
-    world.register_single::<OverflowClip>(OverflowClip::default());
+    let mut overflow_clip = OverflowClip::default();
+    // 没有模板缓冲区时（如webgl上下文创建时没有指定stencil）， 只能使用裁剪纹理
+    overflow_clip.stencil = engine.gl.render_get_caps().stencil_bits > 0;
+    world.register_single::<OverflowClip>(overflow_clip);
     world.register_single::<RenderObjs>(RenderObjs::with_capacity(capacity));
 	world.register_single::<ShareEngine<C>>(engine);
 
@@ -268,6 +289,12 @@ pub fn create_world<C: HalContext + 'static>(
     world.register_single::<ImageWaitSheet>(ImageWaitSheet::default());
 	world.register_single::<DirtyList>(DirtyList::with_capacity(capacity));
 	world.register_single::<SystemTime>(sys_time);
+	world.register_single::<AnimatedProperties>(AnimatedProperties::default());
+	world.register_single::<AnimationEvents>(AnimationEvents::default());
+	world.register_single::<EventDispatcher>(EventDispatcher::default());
+	world.register_single::<ScrollStates>(ScrollStates::default());
+	world.register_single::<VirtualLists>(VirtualLists::default());
+	world.register_single::<TextInputs>(TextInputs::default());
 
     world.register_system(ZINDEX_N.clone(), CellZIndexImpl::new(ZIndexImpl::with_capacity(capacity)));
     world.register_system(SHOW_N.clone(), CellShowSys::new(ShowSys::default()));
@@ -291,12 +318,28 @@ pub fn create_world<C: HalContext + 'static>(
     world.register_system(CHAR_BLOCK_N.clone(), charblock_sys);
     world.register_system(
         TEXT_GLPHY_N.clone(),
-        CellTextGlphySys::new(TextGlphySys),
+        CellTextGlphySys::new(TextGlphySys::default()),
+    );
+    world.register_system(
+        NATIVE_TEXT_N.clone(),
+        CellNativeTextSys::<C>::new(NativeTextSys::new()),
     );
     world.register_system(
         TRANSFORM_WILL_CHANGE_N.clone(),
         CellTransformWillChangeSys::new(TransformWillChangeSys::default()),
     );
+    world.register_system(
+        TRANSITION_N.clone(),
+        CellTransitionSys::new(TransitionSys::default()),
+    );
+    world.register_system(
+        ANIMATION_N.clone(),
+        CellAnimationSys::new(AnimationSys::default()),
+    );
+    world.register_system(EVENT_N.clone(), CellEventSys::new(EventSys::default()));
+    world.register_system(SCROLL_N.clone(), CellScrollSys::new(ScrollSys::default()));
+    world.register_system(VIRTUAL_LIST_N.clone(), CellVirtualListSys::new(VirtualListSys::default()));
+    world.register_system(TEXT_INPUT_N.clone(), CellTextInputSys::new(TextInputSys::default()));
 
     // world.register_system(CHAR_BLOCK_SHADOW_N.clone(), CellCharBlockShadowSys::<L>::new(CharBlockShadowSys::new()));
     world.register_system(
@@ -315,6 +358,11 @@ pub fn create_world<C: HalContext + 'static>(
         BOX_SHADOW_N.clone(),
         CellBoxShadowSys::<C>::new(BoxShadowSys::with_capacity(capacity)),
     );
+    world.register_system(
+        SCROLL_BAR_N.clone(),
+        CellScrollBarSys::<C>::new(ScrollBarSys::with_capacity(capacity)),
+    );
+    world.register_system(CARET_N.clone(), CellCaretSys::<C>::new(CaretSys::default()));
     world.register_system(NODE_ATTR_N.clone(), node_attr_sys);
     world.register_system(
         RENDER_N.clone(),
@@ -337,7 +385,7 @@ pub fn create_world<C: HalContext + 'static>(
     );
 
     let mut dispatch = SeqDispatcher::default();
-    dispatch.build("z_index_sys, show_sys, filter_sys, opacity_sys, text_layout_sys, layout_sys, text_layout_update_sys, world_matrix_sys, text_glphy_sys, transform_will_change_sys, oct_sys, overflow_sys, background_color_sys, box_shadow_sys, border_color_sys, image_sys, border_image_sys, charblock_sys, clip_sys, node_attr_sys, render_sys, res_release, style_mark_sys".to_string(), &world);
+    dispatch.build("animation_sys, transition_sys, z_index_sys, show_sys, filter_sys, opacity_sys, text_layout_sys, layout_sys, text_layout_update_sys, scroll_sys, text_input_sys, world_matrix_sys, text_glphy_sys, native_text_sys, transform_will_change_sys, oct_sys, overflow_sys, background_color_sys, box_shadow_sys, scroll_bar_sys, border_color_sys, image_sys, border_image_sys, charblock_sys, caret_sys, clip_sys, node_attr_sys, render_sys, res_release, style_mark_sys, event_sys, virtual_list_sys".to_string(), &world);
     world.add_dispatcher(RENDER_DISPATCH.clone(), dispatch);
 
     // let mut dispatch = SeqDispatcher::default();
@@ -352,7 +400,7 @@ pub fn create_world<C: HalContext + 'static>(
 	world.add_dispatcher(LAYOUT_DISPATCH.clone(), dispatch);
 	
 	let mut dispatch = SeqDispatcher::default();
-    dispatch.build("z_index_sys, show_sys, filter_sys, opacity_sys, text_layout_sys, layout_sys, text_layout_update_sys, world_matrix_sys, text_glphy_sys, transform_will_change_sys, oct_sys, overflow_sys, background_color_sys, box_shadow_sys, border_color_sys, image_sys, border_image_sys, charblock_sys, clip_sys, node_attr_sys, res_release, style_mark_sys".to_string(), &world);
+    dispatch.build("animation_sys, transition_sys, z_index_sys, show_sys, filter_sys, opacity_sys, text_layout_sys, layout_sys, text_layout_update_sys, scroll_sys, text_input_sys, world_matrix_sys, text_glphy_sys, native_text_sys, transform_will_change_sys, oct_sys, overflow_sys, background_color_sys, box_shadow_sys, scroll_bar_sys, border_color_sys, image_sys, border_image_sys, charblock_sys, caret_sys, clip_sys, node_attr_sys, res_release, style_mark_sys, event_sys, virtual_list_sys".to_string(), &world);
     world.add_dispatcher(CALC_DISPATCH.clone(), dispatch);
 
     world
@@ -374,8 +422,13 @@ pub struct GuiWorld<C: HalContext + 'static> {
     pub border_image_repeat: Arc<CellMultiCase<Node, BorderImageRepeat>>,
     pub text_style: Arc<CellMultiCase<Node, TextStyle>>,
     pub text_content: Arc<CellMultiCase<Node, TextContent>>,
+    pub text_spans: Arc<CellMultiCase<Node, TextSpans>>,
     pub font: Arc<CellMultiCase<Node, Font>>,
     pub border_radius: Arc<CellMultiCase<Node, BorderRadius>>,
+    pub clip_path: Arc<CellMultiCase<Node, ClipPath>>,
+    pub hit_test: Arc<CellMultiCase<Node, HitTest>>,
+    pub scroll_offset: Arc<CellMultiCase<Node, ScrollOffset>>,
+    pub scroll_bar: Arc<CellMultiCase<Node, ScrollBar>>,
     pub image: Arc<CellMultiCase<Node, Image>>,
     pub image_clip: Arc<CellMultiCase<Node, ImageClip>>,
     pub object_fit: Arc<CellMultiCase<Node, ObjectFit>>,
@@ -384,6 +437,9 @@ pub struct GuiWorld<C: HalContext + 'static> {
 	pub other_layout_style: Arc<CellMultiCase<Node, OtherLayoutStyle>>,
 	pub node_state: Arc<CellMultiCase<Node, NodeState>>,
     pub class_name: Arc<CellMultiCase<Node, ClassName>>,
+    pub pseudo_class: Arc<CellMultiCase<Node, PseudoClass>>,
+    pub transition: Arc<CellMultiCase<Node, Transition>>,
+    pub animation: Arc<CellMultiCase<Node, Animation>>,
     pub style_mark: Arc<CellMultiCase<Node, StyleMark>>,
     pub transform_will_change: Arc<CellMultiCase<Node, TransformWillChange>>,
 
@@ -395,6 +451,7 @@ pub struct GuiWorld<C: HalContext + 'static> {
     pub by_overflow: Arc<CellMultiCase<Node, ByOverflow>>,
     pub copacity: Arc<CellMultiCase<Node, calc::Opacity>>,
     pub layout: Arc<CellMultiCase<Node, LayoutR>>,
+    pub char_block: Arc<CellMultiCase<Node, CharBlock>>,
     pub hsv: Arc<CellMultiCase<Node, HSV>>,
     pub culling: Arc<CellMultiCase<Node, Culling>>,
 
@@ -410,6 +467,11 @@ pub struct GuiWorld<C: HalContext + 'static> {
     pub image_wait_sheet: Arc<CellSingleCase<ImageWaitSheet>>,
 	pub dirty_list: Arc<CellSingleCase<DirtyList>>,
 	pub system_time: Arc<CellSingleCase<SystemTime>>,
+	pub animation_events: Arc<CellSingleCase<AnimationEvents>>,
+	pub event_dispatcher: Arc<CellSingleCase<EventDispatcher>>,
+	pub scroll_states: Arc<CellSingleCase<ScrollStates>>,
+	pub virtual_lists: Arc<CellSingleCase<VirtualLists>>,
+	pub text_inputs: Arc<CellSingleCase<TextInputs>>,
 	pub dirty_view_rect: Arc<CellSingleCase<DirtyViewRect>>,
 
 	pub renderSys: Arc<CellRenderSys<C>>,
@@ -434,9 +496,14 @@ impl<C: HalContext + 'static> GuiWorld<C> {
             border_image_slice: world.fetch_multi::<Node, BorderImageSlice>().unwrap(),
             border_image_repeat: world.fetch_multi::<Node, BorderImageRepeat>().unwrap(),
             text_content: world.fetch_multi::<Node, TextContent>().unwrap(),
+            text_spans: world.fetch_multi::<Node, TextSpans>().unwrap(),
             text_style: world.fetch_multi::<Node, TextStyle>().unwrap(),
             font: world.fetch_multi::<Node, Font>().unwrap(),
             border_radius: world.fetch_multi::<Node, BorderRadius>().unwrap(),
+            clip_path: world.fetch_multi::<Node, ClipPath>().unwrap(),
+            hit_test: world.fetch_multi::<Node, HitTest>().unwrap(),
+            scroll_offset: world.fetch_multi::<Node, ScrollOffset>().unwrap(),
+            scroll_bar: world.fetch_multi::<Node, ScrollBar>().unwrap(),
             image: world.fetch_multi::<Node, Image>().unwrap(),
             image_clip: world.fetch_multi::<Node, ImageClip>().unwrap(),
             object_fit: world.fetch_multi::<Node, ObjectFit>().unwrap(),
@@ -445,6 +512,9 @@ impl<C: HalContext + 'static> GuiWorld<C> {
 			other_layout_style: world.fetch_multi::<Node, OtherLayoutStyle>().unwrap(),
 			node_state: world.fetch_multi::<Node, NodeState>().unwrap(),
             class_name: world.fetch_multi::<Node, ClassName>().unwrap(),
+            pseudo_class: world.fetch_multi::<Node, PseudoClass>().unwrap(),
+            transition: world.fetch_multi::<Node, Transition>().unwrap(),
+            animation: world.fetch_multi::<Node, Animation>().unwrap(),
             style_mark: world.fetch_multi::<Node, StyleMark>().unwrap(),
             transform_will_change: world.fetch_multi::<Node, TransformWillChange>().unwrap(),
             culling: world.fetch_multi::<Node, Culling>().unwrap(),
@@ -457,6 +527,7 @@ impl<C: HalContext + 'static> GuiWorld<C> {
             by_overflow: world.fetch_multi::<Node, ByOverflow>().unwrap(),
             copacity: world.fetch_multi::<Node, calc::Opacity>().unwrap(),
             layout: world.fetch_multi::<Node, LayoutR>().unwrap(),
+            char_block: world.fetch_multi::<Node, CharBlock>().unwrap(),
             hsv: world.fetch_multi::<Node, HSV>().unwrap(),
 
             //single
@@ -471,6 +542,11 @@ impl<C: HalContext + 'static> GuiWorld<C> {
             image_wait_sheet: world.fetch_single::<ImageWaitSheet>().unwrap(),
 			dirty_list: world.fetch_single::<DirtyList>().unwrap(),
 			system_time: world.fetch_single::<SystemTime>().unwrap(),
+			animation_events: world.fetch_single::<AnimationEvents>().unwrap(),
+			event_dispatcher: world.fetch_single::<EventDispatcher>().unwrap(),
+			scroll_states: world.fetch_single::<ScrollStates>().unwrap(),
+			virtual_lists: world.fetch_single::<VirtualLists>().unwrap(),
+			text_inputs: world.fetch_single::<TextInputs>().unwrap(),
 			dirty_view_rect: world.fetch_single::<DirtyViewRect>().unwrap(),
 
 			renderSys: world.fetch_sys::<CellRenderSys<C>>(&RENDER_N).unwrap(),
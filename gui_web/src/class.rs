@@ -6,7 +6,7 @@ use bincode;
 use debug_info::debug_println;
 use ecs::LendMut;
 #[cfg(feature = "create_class_by_str")]
-use gui::single::style_parse::parse_class_from_string;
+use gui::single::style_parse::{parse_class_from_string, parse_class_sheet_from_string};
 use gui::single::Class;
 use GuiWorld;
 
@@ -29,7 +29,23 @@ pub fn create_class(world: u32, class_id: u32) {
     };
 
     let class_sheet = world.gui.class_sheet.lend_mut();
-    class_sheet.borrow_mut().class_map.insert(class_id as usize, r);
+    class_sheet.borrow_mut().add_class(class_id as usize, r);
+}
+
+/// 添加文本格式的css表， 支持后代、子代选择器及伪类（:hover、:active、:disabled）
+///__jsObj: css表的文本描述， 如：.1 .2{color:#ff0000} .3:hover{opacity:0.5}
+#[cfg(feature = "create_class_by_str")]
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn create_class_sheet(world: u32) {
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+
+    let class_sheet = world.gui.class_sheet.lend_mut();
+    if let Err(e) = parse_class_sheet_from_string(value.as_str(), &mut class_sheet.borrow_mut()) {
+        debug_println!("{:?}", e);
+    }
 }
 
 /// 添加二进制格式的css表
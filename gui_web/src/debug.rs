@@ -305,6 +305,11 @@ fn to_css_str(attr: Attr) -> String {
                     "flex-direction:rowreverse".to_string()
                 }
             },
+            Attribute1::Direction(r) => match r {
+                Direction::Inherit => "direction:inherit".to_string(),
+                Direction::LTR => "direction:ltr".to_string(),
+                Direction::RTL => "direction:rtl".to_string(),
+            },
             Attribute1::AlignContent(r) => match r {
                 // AlignContent::Auto => "align-content:auto".to_string(),
                 AlignContent::FlexStart => "align-content:flex-start".to_string(),
@@ -359,6 +364,8 @@ fn to_css_str(attr: Attr) -> String {
                 TextAlign::Right => "text-align:right".to_string(),
                 TextAlign::Center => "text-align:center".to_string(),
                 TextAlign::Justify => "text-align:justify".to_string(),
+                TextAlign::Start => "text-align:start".to_string(),
+                TextAlign::End => "text-align:end".to_string(),
             },
             Attribute1::VerticalAlign(r) => match r {
                 VerticalAlign::Top => "vertical-align:top".to_string(),
@@ -391,8 +398,10 @@ fn to_css_str(attr: Attr) -> String {
                 false => "visibility:hidden".to_string(),
             },
             Attribute1::Overflow(r) => match r {
-                true => "overflow:hidden".to_string(),
-                false => "overflow:visible".to_string(),
+                OverflowType::Visible => "overflow:visible".to_string(),
+                OverflowType::Hidden => "overflow:hidden".to_string(),
+                OverflowType::Scroll => "overflow:scroll".to_string(),
+                OverflowType::Auto => "overflow:auto".to_string(),
             },
         },
         Attr::Attr2(attr) => match attr {
@@ -408,6 +417,49 @@ fn to_css_str(attr: Attr) -> String {
             Attribute2::FontWeight(r) => "font-weight:".to_string() + r.to_string().as_str(),
             Attribute2::FontSize(_r) => "".to_string(), // TODO
             Attribute2::FontFamily(r) => "font-family:".to_string() + r.to_string().as_str(),
+            Attribute2::TextOverflow(r) => match r {
+                TextOverflow::Clip => "text-overflow:clip".to_string(),
+                TextOverflow::Ellipsis => "text-overflow:ellipsis".to_string(),
+                TextOverflow::Custom(r) => "text-overflow:\"".to_string() + r.as_str() + "\"",
+            },
+            Attribute2::LineClamp(r) => "line-clamp:".to_string() + r.to_string().as_str(),
+            Attribute2::TextDecorationLine(r) => {
+                let mut s = "text-decoration-line:".to_string();
+                if r == 0 {
+                    s += "none";
+                }
+                if r & TextDecorationLine::Underline as usize != 0 {
+                    s += " underline";
+                }
+                if r & TextDecorationLine::Overline as usize != 0 {
+                    s += " overline";
+                }
+                if r & TextDecorationLine::LineThrough as usize != 0 {
+                    s += " line-through";
+                }
+                s
+            }
+            Attribute2::TextDecorationStyle(r) => match r {
+                TextDecorationStyle::Solid => "text-decoration-style:solid".to_string(),
+                TextDecorationStyle::Dashed => "text-decoration-style:dashed".to_string(),
+                TextDecorationStyle::Dotted => "text-decoration-style:dotted".to_string(),
+                TextDecorationStyle::Wavy => "text-decoration-style:wavy".to_string(),
+            },
+            Attribute2::TextDecorationColor(r) => match r {
+                Some(r) => {
+                    "text-decoration-color:rgba(".to_string()
+                        + r.r.to_string().as_str()
+                        + ","
+                        + r.g.to_string().as_str()
+                        + ","
+                        + r.b.to_string().as_str()
+                        + ","
+                        + r.a.to_string().as_str()
+                        + ")"
+                }
+                None => "text-decoration-color:currentcolor".to_string(),
+            },
+            Attribute2::TextDecorationThickness(r) => "text-decoration-thickness:".to_string() + r.to_string().as_str() + "px",
             Attribute2::ZIndex(r) => "z-index:".to_string() + r.to_string().as_str(),
             Attribute2::Opacity(r) => "opacity:".to_string() + r.0.to_string().as_str(),
             // Attribute2::BorderImageRepeat(BorderImageRepeat)(x, y) => "border-image-repeat:" + r.to_string().as_str() + " " +,
@@ -600,6 +652,7 @@ fn to_css_str(attr: Attr) -> String {
                         + ")"
                 }
                 Color::LinearGradient(_r) => "background-color:linear-gradient".to_string(),
+                Color::RadialGradient(_r) => "background-color:radial-gradient".to_string(),
             },
             Attribute3::BorderColor(r) => {
                 let r = r.0;
@@ -691,23 +744,32 @@ fn to_css_str(attr: Attr) -> String {
                         + ")"
                 }
                 Color::LinearGradient(_r) => "background-color:linear-gradient".to_string(),
+                Color::RadialGradient(_r) => "color:radial-gradient".to_string(),
             },
             Attribute3::TextShadow(r) => {
-                "text-shadow:".to_string()
-                    + r.h.to_string().as_str()
-                    + " "
-                    + r.v.to_string().as_str()
-                    + " "
-                    + r.blur.to_string().as_str()
-                    + " rgba("
-                    + r.color.r.to_string().as_str()
-                    + ","
-                    + r.color.g.to_string().as_str()
-                    + ","
-                    + r.color.b.to_string().as_str()
-                    + ","
-                    + r.color.a.to_string().as_str()
-                    + ")"
+                if r.len() == 0 {
+                    return "text-shadow:none".to_string();
+                }
+                let list: Vec<String> = r
+                    .iter()
+                    .map(|r| {
+                        r.h.to_string()
+                            + "px "
+                            + r.v.to_string().as_str()
+                            + "px "
+                            + r.blur.to_string().as_str()
+                            + "px rgba("
+                            + r.color.r.to_string().as_str()
+                            + ","
+                            + r.color.g.to_string().as_str()
+                            + ","
+                            + r.color.b.to_string().as_str()
+                            + ","
+                            + r.color.a.to_string().as_str()
+                            + ")"
+                    })
+                    .collect();
+                "text-shadow:".to_string() + list.join(",").as_str()
             }
             Attribute3::TextStroke(r) => {
                 "text-stroke:".to_string()
@@ -723,9 +785,13 @@ fn to_css_str(attr: Attr) -> String {
             }
 
             Attribute3::BorderRadius(_r) => "".to_string(), // TODO
+            Attribute3::BorderRadiusCorner(_i, _r) => "".to_string(), // TODO
             Attribute3::TransformFunc(_r) => "".to_string(), // TODO
             Attribute3::TransformOrigin(_r) => "".to_string(), // TODO
             Attribute3::Filter(_r) => "".to_string(),       // TODO
+            Attribute3::Transition(_r) => "".to_string(),   // TODO
+            Attribute3::Animation(_r) => "".to_string(),    // TODO
+            Attribute3::ClipPath(_r) => "".to_string(),     // TODO
         },
     }
 }
@@ -982,7 +1048,7 @@ pub fn node_info(world: u32, node: u32) {
 
     let info = Info {
         // char_block: char_block,
-        overflow: world.overflow.lend()[node].0,
+        overflow: world.overflow.lend()[node].is_clip(),
         by_overflow: by_overflow,
         visibility: visibility,
         enable: enable,
@@ -0,0 +1,119 @@
+/// 将事件分发接口导出到js
+/// 事件类型： 1: pointerdown, 2: pointermove, 4: pointerup, 8: pointercancel, 16: wheel, 32: click
+use stdweb::unstable::TryInto;
+
+use ecs::LendMut;
+use gui::single::{Event, EventPhase, EventType};
+use gui::system::{dispatch_event, scroll_into_view as scroll_node_into_view, scroll_to as scroll_node_to};
+use share::Share;
+
+use GuiWorld;
+
+fn event_type(ty: u8) -> Option<EventType> {
+    match ty {
+        1 => Some(EventType::PointerDown),
+        2 => Some(EventType::PointerMove),
+        4 => Some(EventType::PointerUp),
+        8 => Some(EventType::PointerCancel),
+        16 => Some(EventType::Wheel),
+        32 => Some(EventType::Click),
+        _ => None,
+    }
+}
+
+/// 为节点注册监听器， 返回监听器id， 返回0表示事件类型错误
+/// 事件到达时调用window.__gui_event(world, callback, ty, target, current_target, phase, pointer_id, x, y, button, buttons, delta_x, delta_y)
+/// phase: 1: 捕获， 2: 目标， 3: 冒泡
+/// __gui_event的返回值： 1: stopPropagation, 2: stopImmediatePropagation, 4: preventDefault， 可以组合
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn add_event_listener(world_id: u32, node: u32, ty: u8, capture: bool, callback: u32) -> u32 {
+    let ty = match event_type(ty) {
+        Some(r) => r,
+        None => return 0,
+    };
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let dispatcher = world.gui.event_dispatcher.lend_mut();
+    dispatcher.add_listener(node as usize, ty, capture, Share::new(move |e: &mut Event| {
+        let phase = match e.phase {
+            EventPhase::Capture => 1,
+            EventPhase::Target => 2,
+            EventPhase::Bubble => 3,
+            EventPhase::None => 0,
+        };
+        let flags: u32 = js! {
+            if (window.__gui_event) {
+                return window.__gui_event(@{world_id}, @{callback}, @{e.ty as u32}, @{e.target as u32}, @{e.current_target as u32}, @{phase}, @{e.pointer_id as u32}, @{e.x}, @{e.y}, @{e.button as u32}, @{e.buttons as u32}, @{e.delta_x}, @{e.delta_y}) || 0;
+            }
+            return 0;
+        }.try_into().unwrap_or(0);
+        if flags & 2 != 0 {
+            e.stop_immediate_propagation();
+        } else if flags & 1 != 0 {
+            e.stop_propagation();
+        }
+        if flags & 4 != 0 {
+            e.prevent_default();
+        }
+    })) as u32
+}
+
+/// 删除监听器
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn remove_event_listener(world_id: u32, listener: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    world.gui.event_dispatcher.lend_mut().remove_listener(listener as usize)
+}
+
+/// 输入指针事件（pointerdown、pointermove、pointerup、pointercancel）， 返回事件是否被取消默认行为
+/// button: 改变状态的按键， 0: 主键， 1: 中键， 2: 右键； buttons: 当前按下的所有按键
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn dispatch_pointer_event(world_id: u32, ty: u8, pointer_id: u32, x: f32, y: f32, button: u32, buttons: u32, time: u32) -> bool {
+    let ty = match event_type(ty) {
+        Some(EventType::Wheel) | Some(EventType::Click) | None => return false,
+        Some(r) => r,
+    };
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let mut event = Event::new(ty, x, y);
+    event.pointer_id = pointer_id as usize;
+    event.button = button as usize;
+    event.buttons = buttons as usize;
+    event.time = time as usize;
+    dispatch_event(&world.gui, event)
+}
+
+/// 输入滚轮事件， 返回事件是否被取消默认行为
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn dispatch_wheel_event(world_id: u32, x: f32, y: f32, delta_x: f32, delta_y: f32, time: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let mut event = Event::new(EventType::Wheel, x, y);
+    event.delta_x = delta_x;
+    event.delta_y = delta_y;
+    event.time = time as usize;
+    dispatch_event(&world.gui, event)
+}
+
+/// 滚动容器滚动到指定偏移（下一帧生效）， smooth为true时平滑滚动， 返回节点是否为滚动容器
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn scroll_to(world_id: u32, node: u32, x: f32, y: f32, smooth: bool) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    scroll_node_to(&world.gui, node as usize, x, y, smooth)
+}
+
+/// 滚动所有祖先滚动容器， 使节点可见（下一帧生效）
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn scroll_into_view(world_id: u32, node: u32, smooth: bool) {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    scroll_node_into_view(&world.gui, node as usize, smooth)
+}
@@ -3,7 +3,7 @@ use std::mem::transmute;
 
 use ecs::LendMut;
 
-use gui::component::calc::{StyleType2};
+use gui::component::calc::{StyleType1, StyleType2};
 use gui::component::user::{OtherLayoutStyleWrite};
 use flex_layout::style::*;
 use flex_layout::Rect;
@@ -154,6 +154,19 @@ func_enum!(set_flex_wrap, FlexWrap);
 func_enum!(set_align_self, AlignSelf);
 func_enum!(set_position_type, PositionType);
 
+/// 设置书写方向， 0: inherit, 1: ltr, 2: rtl
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_direction(world: u32, node_id: u32, value: u8) {
+    let value = unsafe { transmute(value) };
+    let node_id = node_id as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let world = &mut world.gui;
+    world.style_mark.lend_mut()[node_id].local_style1 |= StyleType1::Direction as usize;
+    unsafe { world.other_layout_style.lend_mut().get_unchecked_write(node_id) }.set_direction(value);
+}
+
 
 func_value!(set_flex_grow, FlexGrow);
 func_value!(set_flex_shrink, FlexShrink);
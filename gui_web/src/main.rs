@@ -46,6 +46,7 @@ use ordered_float::OrderedFloat;
 // use res::ResMgr;
 use stdweb::unstable::TryInto;
 use stdweb::Object;
+use stdweb::web::TypedArray;
 use webgl_rendering_context::WebGLRenderingContext;
 use flex_layout::{Size, Dimension, PositionType, Rect};
 
@@ -56,9 +57,10 @@ use gui::font::font_sheet::FontSheet;
 use gui::component::user::*;
 use gui::render::engine::{Engine, ShareEngine, UnsafeMut};
 use gui::render::res::Opacity as ROpacity;
-use gui::render::res::TextureRes;
+use gui::render::res::{AlphaMask, TextureRes};
 use gui::single::Class;
-use gui::single::{RenderBegin, ClassSheet};
+use gui::single::{RenderBegin, ClassSheet, ClipMode, AnimationEventType};
+use gui::system::update_virtual_lists;
 use gui::world::GuiWorld as GuiWorld1;
 use gui::Z_MAX;
 use gui::world::{create_res_mgr, create_world, LAYOUT_DISPATCH, RENDER_DISPATCH, CALC_DISPATCH};
@@ -70,6 +72,7 @@ use share::Share;
 pub mod class;
 #[cfg(not(feature = "no_debug"))]
 pub mod debug;
+pub mod event;
 pub mod layout;
 pub mod node;
 // // pub mod reset_style;
@@ -77,7 +80,9 @@ pub mod node;
 pub mod rs_call_js;
 pub mod style;
 pub mod text;
+pub mod text_input;
 pub mod transform;
+pub mod virtual_list;
 pub mod world;
 pub mod yoga;
 
@@ -280,10 +285,7 @@ pub fn create_gui(engine: u32, width: f32, height: f32, class_sheet: u32, font_s
     let border_radius = world.border_radius.lend_mut();
     border_radius.insert(
         node,
-        BorderRadius {
-            x: LengthUnit::Pixel(0.0),
-            y: LengthUnit::Pixel(0.0),
-        },
+        BorderRadius::new(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)),
     );
 
     let visibilitys = world.visibility.lend_mut();
@@ -363,6 +365,31 @@ pub fn nullify_clear_color(world: u32) {
 	render_begin.0.clear_color = None;
 }
 
+/// 设置旋转或非矩形裁剪区域的裁剪方式， 0: 裁剪纹理， 1: 模板缓冲区（像素精确）
+/// webgl上下文没有模板缓冲区（创建时未指定stencil: true）时， 模板缓冲区方式退回到裁剪纹理
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_clip_mode(world: u32, mode: u8) {
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let world = &mut world.gui;
+    let mode = match mode {
+        1 => ClipMode::Stencil,
+        _ => ClipMode::Texture,
+    };
+    let overflow_clip = world.overflow_clip.lend_mut();
+    if overflow_clip.mode == mode {
+        return;
+    }
+    overflow_clip.mode = mode;
+    // 重新计算所有裁剪区域
+    let nodes: Vec<usize> = overflow_clip.clip.iter().map(|(_, c)| c.node_id).collect();
+    let notify = world.overflow.lend().get_notify_ref();
+    for id in nodes.into_iter() {
+        notify.modify_event(id, "", 0);
+    }
+}
+
 /// 设置视口
 #[allow(unused_attributes)]
 #[no_mangle]
@@ -512,7 +539,9 @@ pub fn render(world_id: u32) {
 	sys_time.cur_time = cur_time as usize;
     // #[cfg(feature = "debug")]
     // let time = std::time::Instant::now();
+    update_virtual_lists(world);
     world.world.run(&RENDER_DISPATCH);
+    dispatch_animation_event(world_id);
     // #[cfg(feature = "debug")]
     // let run_all_time = std::time::Instant::now() - time;
 
@@ -568,7 +597,9 @@ js_serializable!(RunTime);
 pub fn calc(world_id: u32) {
     let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
     let world = &mut world.gui;
+    update_virtual_lists(world);
     world.world.run(&CALC_DISPATCH);
+    dispatch_animation_event(world_id);
 }
 
 /// 强制计算一次布局
@@ -652,6 +683,27 @@ pub fn create_texture_res(
     Share::into_raw(create_texture(world_id, opacity, compress, r_type).0) as u32
 }
 
+/// 为纹理资源设置像素的不透明度， 用于opaque-pixels方式的命中测试， 返回纹理资源是否存在
+/// __jsObj: 每个像素的alpha值(Uint8Array, 长度为width * height， 第一行对应图片的第一行)， __jsObj1: image_name
+#[no_mangle]
+#[js_export]
+pub fn set_texture_alpha_mask(world_id: u32, width: u32, height: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let name: usize = js! {return __jsObj1}.try_into().unwrap();
+    let data: TypedArray<u8> = js! {return __jsObj}.try_into().unwrap();
+    match world.gui.engine.lend_mut().texture_res_map.get(&name) {
+        Some(r) => {
+            r.set_alpha_mask(AlphaMask {
+                width: width as usize,
+                height: height as usize,
+                data: data.to_vec(),
+            });
+            true
+        }
+        None => false,
+    }
+}
+
 pub fn create_texture(
     world_id: u32,
     opacity: u8,
@@ -755,6 +807,25 @@ fn load_image(world_id: u32) {
     image_wait_sheet.loads.clear();
 }
 
+/// 通知js本帧产生的动画事件
+/// ty: 0表示动画开始， 1表示动画重复， 2表示动画结束
+fn dispatch_animation_event(world_id: u32) {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let events = world.gui.animation_events.lend();
+    for e in events.0.iter() {
+        let ty = match e.ty {
+            AnimationEventType::Start => 0,
+            AnimationEventType::Iteration => 1,
+            AnimationEventType::End => 2,
+        };
+        js! {
+            if (window.__animation_event) {
+                window.__animation_event(@{world_id}, @{e.id as u32}, @{e.name.as_ref().to_string()}, @{ty}, @{e.elapsed_time as u32});
+            }
+        }
+    }
+}
+
 /// 调试使用， 设置渲染脏， 使渲染系统在下一帧进行渲染
 #[allow(unused_attributes)]
 #[no_mangle]
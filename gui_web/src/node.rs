@@ -4,12 +4,10 @@ use std::{f32::INFINITY as FMAX, usize::MAX as UMAX};
 use stdweb::unstable::TryInto;
 
 use atom::Atom;
-use cg2d::{include_quad2, InnOuter};
 use gui::single::{IdTree};
 use idtree::InsertType;
-use ecs::{Lend, LendMut, MultiCaseImpl, SingleCaseImpl};
+use ecs::{Lend, LendMut};
 use ecs::monitor::NotifyImpl;
-use octree::intersects;
 
 // use share::Share;
 use gui::component::calc::*;
@@ -17,6 +15,7 @@ use gui::component::user::*;
 use gui::entity::Node;
 use gui::render::res::Opacity as ROpacity;
 use gui::single::*;
+use gui::system::{ab_query_func, AbQueryArgs};
 use hal_core::*;
 // use gui::
 // use gui::system::set_layout_style;
@@ -32,10 +31,7 @@ fn create(world: &GuiWorld) -> usize {
     let border_radius = gui.border_radius.lend_mut();
     border_radius.insert(
         node,
-        BorderRadius {
-            x: LengthUnit::Pixel(0.0),
-            y: LengthUnit::Pixel(0.0),
-        },
+        BorderRadius::new(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)),
     );
 	idtree.create(node);
     // set_layout_style(&world.default_attr, unsafe {gui.yoga.lend_mut().get_unchecked(node)}, &mut StyleMark::default());
@@ -621,27 +617,7 @@ pub fn content_box(world: u32, node: u32) {
 #[js_export]
 pub fn query(world: u32, x: f32, y: f32) -> u32 {
     let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
-    let world = &mut world.gui;
-
-    let octree = world.oct.lend();
-    let enables = world.enable.lend();
-    let overflow_clip = world.overflow_clip.lend();
-    let by_overflows = world.by_overflow.lend();
-    let z_depths = world.z_depth.lend();
-    let idtree = world.idtree.lend();
-
-    let aabb = Aabb3::new(Point3::new(x, y, -Z_MAX), Point3::new(x, y, Z_MAX));
-    let mut args = AbQueryArgs::new(
-        enables,
-        by_overflows,
-        z_depths,
-        overflow_clip,
-        idtree,
-        aabb.clone(),
-        0,
-    );
-    octree.query(&aabb, intersects, &mut args, ab_query_func);
-    args.result as u32
+    gui::system::query(&world.gui, x, y) as u32
 }
 
 /// 判断点是否能命中点
@@ -687,22 +663,9 @@ pub fn iter_query(world: u32, x: f32, y: f32) -> u32 {
 
     let entitys = world.node.lend();
     let octree = world.oct.lend();
-    let enables = world.enable.lend();
-    let overflow_clip = world.overflow_clip.lend();
-    let by_overflows = world.by_overflow.lend();
-    let z_depths = world.z_depth.lend();
-    let idtree = world.idtree.lend();
 
     let aabb = Aabb3::new(Point3::new(x, y, -Z_MAX), Point3::new(x, y, Z_MAX));
-    let mut args = AbQueryArgs::new(
-        enables,
-        by_overflows,
-        z_depths,
-        overflow_clip,
-        idtree,
-        aabb.clone(),
-        0,
-    );
+    let mut args = AbQueryArgs::new(world, aabb.clone(), 0);
 
     for e in entitys.iter() {
         let oct = match octree.get(e) {
@@ -782,6 +745,20 @@ pub fn add_class_start(world: u32, node_id: u32) -> u32 {
     )) as u32
 }
 
+/// 设置节点的伪类状态， 用于匹配样式表中的伪类选择器
+/// value: 1: hover, 2: active, 4: disabled， 可以组合
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_pseudo_class(world: u32, node_id: u32, value: u32) {
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    world
+        .gui
+        .pseudo_class
+        .lend_mut()
+        .insert(node_id as usize, PseudoClass(value as usize));
+}
+
 pub fn define_set_class() {
     js! {
         Module._set_class = function(world, node, class_arr){
@@ -794,126 +771,6 @@ pub fn define_set_class() {
     }
 }
 
-/// aabb的查询函数的参数
-struct AbQueryArgs<'a> {
-    enables: &'a MultiCaseImpl<Node, Enable>,
-    by_overflows: &'a MultiCaseImpl<Node, ByOverflow>,
-    z_depths: &'a MultiCaseImpl<Node, ZDepth>,
-    overflow_clip: &'a SingleCaseImpl<OverflowClip>,
-    id_tree: &'a SingleCaseImpl<IdTree>,
-    aabb: Aabb3,
-    ev_type: u32,
-    max_z: f32,
-    result: usize,
-}
-impl<'a> AbQueryArgs<'a> {
-    pub fn new(
-        enables: &'a MultiCaseImpl<Node, Enable>,
-        by_overflows: &'a MultiCaseImpl<Node, ByOverflow>,
-        z_depths: &'a MultiCaseImpl<Node, ZDepth>,
-        overflow_clip: &'a SingleCaseImpl<OverflowClip>,
-        id_tree: &'a SingleCaseImpl<IdTree>,
-        aabb: Aabb3,
-        ev_type: u32,
-    ) -> AbQueryArgs<'a> {
-        AbQueryArgs {
-            enables,
-            by_overflows,
-            z_depths,
-            overflow_clip,
-            id_tree,
-            aabb: aabb,
-            ev_type: ev_type,
-            max_z: -Z_MAX,
-            result: 0,
-        }
-    }
-}
-/// aabb的ab查询函数, aabb的oct查询函数应该使用intersects
-fn ab_query_func(arg: &mut AbQueryArgs, _id: usize, aabb: &Aabb3, bind: &usize) {
-    match arg.id_tree.get(*bind) {
-        Some(node) => {
-            if node.layer() == 0 {
-                return;
-            }
-        }
-        None => return,
-    };
-    // println!("ab_query_func----------------------------bind: {}, aabb: {:?}, arg: {:?}", *bind, aabb, arg.aabb);
-    if intersects(&arg.aabb, aabb) {
-        // debug_println!("bind----------------------------{}", *bind);
-		let enable = arg.enables[*bind].0;
-		let z_depth = arg.z_depths[*bind].0;
-        // println!("enable----------------------------{}, bind:{}", enable, bind);
-        // println!("enable----------id: {}, enable: {}, z_depth: {}, max_z: {}", bind, enable, z_depth,  arg.max_z);
-        //如果enable true 表示不接收事件
-        match enable {
-            true => (),
-            false => return,
-        };
-
-        
-        // println!("z_depth----------id: {}, z_depth: {}, arg.max_z:{}", bind, z_depth, arg.max_z);
-        // debug_println!("----------------------------z_depth: {}, arg.max_z: {}", z_depth, arg.max_z);
-        // 取最大z的node
-        if z_depth > arg.max_z {
-            let by_overflow = arg.by_overflows[*bind].0;
-			//   println!("by_overflow1---------------------------bind: {},  by: {}, clip: {:?}, id_vec: {:?}, x: {}, y: {}", bind, by_overflow, &arg.overflow_clip.clip, &arg.overflow_clip.id_vec, arg.aabb.min.x, arg.aabb.min.y);
-			// println!("in_overflow1------------------by: {}, bind: {}, ", by_overflow, bind);
-            // 检查是否有裁剪，及是否在裁剪范围内
-            if by_overflow == 0
-                || in_overflow(
-                    &arg.overflow_clip,
-                    by_overflow,
-                    arg.aabb.min.x,
-                    arg.aabb.min.y,
-                )
-            {
-                // println!("in_overflow------------------by: {}, bind: {}, ", by_overflow, bind);
-                // println!("result----------id: {}", bind);
-                arg.result = *bind;
-                arg.max_z = z_depth;
-            }
-        }
-        // // 判断类型是否有相交
-        // if (node.event_type as u32) & arg.ev_type != 0 {
-        //     // 取最大z的node
-        //     if node.z_depth > arg.max_z {
-        //       // 检查是否有裁剪，及是否在裁剪范围内
-        //       if node.by_overflow == 0 || in_overflow(&arg.mgr, node.by_overflow, aabb.min.x, aabb.min.y) {
-        //         arg.result = *bind;
-        //         arg.max_z = node.z_depth;
-        //        }
-        //     }
-        // }
-    }
-}
-
-/// 检查坐标是否在裁剪范围内， 直接在裁剪面上检查
-fn in_overflow(
-    overflow_clip: &SingleCaseImpl<OverflowClip>,
-    by_overflow: usize,
-    x: f32,
-    y: f32,
-) -> bool {
-    let xy = Point2::new(x, y);
-    for (i, c) in overflow_clip.clip.iter() {
-        // debug_println!("i + 1---------------------------{}",i + 1);
-        // debug_println!("overflow_clip.id_vec[i]---------------------------{}",overflow_clip.id_vec[i]);
-        if (by_overflow & (1 << (i - 1))) != 0 {
-            let p = &c.view;
-            match include_quad2(&xy, &p[0], &p[1], &p[2], &p[3]) {
-                InnOuter::Inner => (),
-                _ => {
-                    // println!("overflow----------clip: {:?},x: {}, y: {}", p[0], x, y);
-                    return false;
-                }
-            }
-        }
-    }
-    return true;
-}
-
 // /// 检查坐标是否在裁剪范围内， 直接在裁剪面上检查
 // fn in_overflow(overflow_clip: &SingleCaseImpl<OverflowClip>, by_overflow: usize, x: f32, y: f32) -> bool{
 //   let xy = Point2::new(x, y);
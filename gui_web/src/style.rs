@@ -7,11 +7,13 @@ use atom::Atom;
 use ecs::LendMut;
 use hash::XHashMap;
 
+use gui::component::calc::StyleType1;
 use gui::component::user::*;
 use gui::single::*;
 use gui::util::vecmap_default::VecMapWithDefault;
 #[cfg(feature = "create_class_by_str")]
 use gui::single::style_parse::{parse_class_from_string};
+use gui::single::style_parse::{parse_animation, parse_clip_path, parse_transition};
 use flex_layout::Rect;
 use GuiWorld;
 
@@ -61,15 +63,30 @@ pub fn set_background_rgba_color(world: u32, node: u32, r: f32, g: f32, b: f32,
     );
 }
 
-// // 设置一个径向渐变的背景颜色
-// #[allow(unused_attributes)]
-// #[no_mangle]
-// #[js_export]
-// pub fn set_background_radial_gradient_color(world: u32, node: u32, center_x: f32, center_y: f32, shape: u8, size: u8 ){
-//     let color_and_positions: TypedArray<f32> = js!(return __jsObj;).try_into().unwrap();
-//     let value = Color::RadialGradient(to_radial_gradient_color(color_and_positions, center_x, center_y, shape, size));
-//     insert_value!(world, node, BackgroundColor, value);
-// }
+/// 设置一个径向渐变的背景颜色
+/// __jsObj: color_and_positions: [r, g, b, a, pos,   r, g, b, a, pos], center_x: 0~1, center_y: 0~1
+/// shape: 0(ellipse), 1(circle); size: 0(closest-side), 1(farthest-side), 2(closest-corner), 3(farthest-corner)
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_background_radial_gradient_color(
+    world: u32,
+    node: u32,
+    center_x: f32,
+    center_y: f32,
+    shape: u8,
+    size: u8,
+) {
+    let color_and_positions: TypedArray<f32> = js!(return __jsObj;).try_into().unwrap();
+    let value = Color::RadialGradient(to_radial_gradient_color(
+        color_and_positions.to_vec(),
+        center_x,
+        center_y,
+        shape,
+        size,
+    ));
+    insert_value!(world, node, BackgroundColor, value, background_color);
+}
 
 /// 设置一个线性渐变的背景颜色
 #[allow(unused_attributes)]
@@ -107,10 +124,7 @@ pub fn set_border_radius(world: u32, node: u32, x: f32, y: f32) {
         world,
         node,
         BorderRadius,
-        BorderRadius {
-            x: LengthUnit::Pixel(x),
-            y: LengthUnit::Pixel(y)
-        },
+        BorderRadius::new(LengthUnit::Pixel(x), LengthUnit::Pixel(y)),
         border_radius
     );
 }
@@ -124,14 +138,143 @@ pub fn set_border_radius_percent(world: u32, node: u32, x: f32, y: f32) {
         world,
         node,
         BorderRadius,
-        BorderRadius {
+        BorderRadius::new(LengthUnit::Percent(x), LengthUnit::Percent(y)),
+        border_radius
+    );
+}
+
+/// 设置单个角的圆角, corner: 0(左上), 1(右上), 2(右下), 3(左下)
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_border_radius_corner(world: u32, node: u32, corner: u8, x: f32, y: f32) {
+    set_border_radius_corner_value(
+        world,
+        node,
+        corner,
+        CornerRadius {
+            x: LengthUnit::Pixel(x),
+            y: LengthUnit::Pixel(y),
+        },
+    );
+}
+
+/// 设置单个角的圆角（百分比）, corner: 0(左上), 1(右上), 2(右下), 3(左下)
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_border_radius_corner_percent(world: u32, node: u32, corner: u8, x: f32, y: f32) {
+    set_border_radius_corner_value(
+        world,
+        node,
+        corner,
+        CornerRadius {
             x: LengthUnit::Percent(x),
-            y: LengthUnit::Percent(y)
+            y: LengthUnit::Percent(y),
         },
-        border_radius
     );
 }
 
+fn set_border_radius_corner_value(world: u32, node: u32, corner: u8, value: CornerRadius) {
+    let node = node as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let border_radius = world.gui.border_radius.lend_mut();
+    let mut r = match border_radius.get(node) {
+        Some(r) => r.clone(),
+        None => BorderRadius::default(),
+    };
+    match r.corner_mut(corner as usize) {
+        Some(c) => *c = value,
+        None => return,
+    };
+    border_radius.insert(node, r);
+}
+
+/// 设置裁剪路径， 节点设置了overflow时， 子节点将被裁剪到该路径内
+/// __jsObj: clip-path的文本描述， 如：circle(50% at 50% 50%)、ellipse(20px 10px)、polygon(0 0, 100% 0, 50% 100%)， 空字符串或none表示删除裁剪路径
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_clip_path(world: u32, node: u32) {
+    let node = node as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    let clip_paths = world.gui.clip_path.lend_mut();
+    let style_mark = &mut world.gui.style_mark.lend_mut()[node];
+    let value = value.trim();
+    if value == "" || value == "none" {
+        style_mark.local_style1 &= !(StyleType1::ClipPath as usize);
+        if clip_paths.get(node).is_some() {
+            clip_paths.delete(node);
+        }
+        return;
+    }
+    match parse_clip_path(value) {
+        Ok(r) => {
+            style_mark.local_style1 |= StyleType1::ClipPath as usize;
+            clip_paths.insert(node, r);
+        }
+        Err(e) => debug_println!("set_clip_path error, {:?}", e),
+    };
+}
+
+/// 设置过渡， 属性值改变时， 将在指定时间内由旧值过渡到新值
+/// __jsObj: transition的文本描述， 如：opacity 0.3s ease-in 0.1s, transform 200ms， 空字符串或none表示删除过渡
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_transition(world: u32, node: u32) {
+    let node = node as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    let transitions = world.gui.transition.lend_mut();
+    let style_mark = &mut world.gui.style_mark.lend_mut()[node];
+    let value = value.trim();
+    if value == "" || value == "none" {
+        // 删除本地过渡， class中的过渡在下次class变化时重新生效
+        style_mark.local_style1 &= !(StyleType1::Transition as usize);
+        if transitions.get(node).is_some() {
+            transitions.delete(node);
+        }
+        return;
+    }
+    match parse_transition(value) {
+        Ok(r) => {
+            style_mark.local_style1 |= StyleType1::Transition as usize;
+            transitions.insert(node, r);
+        }
+        Err(e) => debug_println!("set_transition error, {:?}", e),
+    };
+}
+
+/// 设置关键帧动画， 关键帧在样式表的@keyframes中定义
+/// __jsObj: animation的文本描述， 如：fade 1s ease-in 0.5s infinite alternate both， 空字符串或none表示删除动画
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_animation(world: u32, node: u32) {
+    let node = node as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    let animations = world.gui.animation.lend_mut();
+    let style_mark = &mut world.gui.style_mark.lend_mut()[node];
+    let value = value.trim();
+    if value == "" || value == "none" {
+        style_mark.local_style1 &= !(StyleType1::Animation as usize);
+        if animations.get(node).is_some() {
+            animations.delete(node);
+        }
+        return;
+    }
+    match parse_animation(value) {
+        Ok(r) => {
+            style_mark.local_style1 |= StyleType1::Animation as usize;
+            animations.insert(node, r);
+        }
+        Err(e) => debug_println!("set_animation error, {:?}", e),
+    };
+}
+
 // // 设置阴影颜色
 // #[allow(unused_attributes)]
 // #[no_mangle]
@@ -296,14 +439,45 @@ pub fn set_border_image_repeat(world: u32, node: u32, vertical: u8, horizontal:
     );
 }
 
-/// 设置overflow
+/// 设置overflow， 0: visible, 1: hidden, 2: scroll, 3: auto（兼容bool， true表示hidden）
 #[allow(unused_attributes)]
 #[no_mangle]
 #[js_export]
-pub fn set_overflow(world: u32, node: u32, value: bool) {
+pub fn set_overflow(world: u32, node: u32, value: u8) {
+    let value = match value {
+        1 => OverflowType::Hidden,
+        2 => OverflowType::Scroll,
+        3 => OverflowType::Auto,
+        _ => OverflowType::Visible,
+    };
     insert_value!(world, node, Overflow, value, overflow);
 }
 
+/// 设置滚动容器的滚动条， width: 滑块的宽度， r, g, b, a: 滑块的颜色
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_scroll_bar(world: u32, node: u32, width: f32, r: f32, g: f32, b: f32, a: f32) {
+    let value = ScrollBar {
+        width: width,
+        color: CgColor::new(r, g, b, a),
+    };
+    insert_attr!(world, node, ScrollBar, value, scroll_bar);
+}
+
+/// 删除滚动容器的滚动条
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn remove_scroll_bar(world: u32, node: u32) {
+    let node = node as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let scroll_bars = world.gui.scroll_bar.lend_mut();
+    if scroll_bars.get(node).is_some() {
+        scroll_bars.delete(node);
+    }
+}
+
 /// 设置不透明度
 #[allow(unused_attributes)]
 #[no_mangle]
@@ -351,6 +525,14 @@ pub fn set_enable(world: u32, node: u32, value: u32) {
     set_show!(world, node, set_enable, unsafe { transmute(value as u8) });
 }
 
+/// 设置命中测试的方式， 0: shape（默认， 按变换后的布局框及圆角）， 1: bounds（按包围盒）， 2: opaque-pixels（按图片的不透明像素）
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_hit_test(world: u32, node: u32, value: u8) {
+    insert_value!(world, node, HitTest, unsafe { transmute(value) }, hit_test);
+}
+
 /// 这只z_index
 #[allow(unused_attributes)]
 #[no_mangle]
@@ -492,6 +674,7 @@ fn set_default_style1(world: &mut GuiWorld, r: Class) {
 			Attribute1::PositionType(r) => other_layout_style.position_type = r,
 			Attribute1::FlexWrap(r) => other_layout_style.flex_wrap = r,
 			Attribute1::FlexDirection(r) => other_layout_style.flex_direction = r,
+			Attribute1::Direction(r) => other_layout_style.direction = r,
 			Attribute1::AlignContent(r) => other_layout_style.align_content = r,
 			Attribute1::AlignItems(r) => other_layout_style.align_items = r,
 			Attribute1::AlignSelf(r) => other_layout_style.align_self = r,
@@ -514,6 +697,12 @@ fn set_default_style1(world: &mut GuiWorld, r: Class) {
 			Attribute2::FontFamily(r) => {
 				text_style.font.family = r;
 			},
+			Attribute2::TextOverflow(r) => text_style.text.text_overflow = r,
+			Attribute2::LineClamp(r) => text_style.text.line_clamp = r,
+			Attribute2::TextDecorationLine(r) => text_style.text.decoration.line = r,
+			Attribute2::TextDecorationStyle(r) => text_style.text.decoration.style = r,
+			Attribute2::TextDecorationColor(r) => text_style.text.decoration.color = r,
+			Attribute2::TextDecorationThickness(r) => text_style.text.decoration.thickness = r,
 			
 			Attribute2::Width(r) => rect_layout_style.size.width = r,
 			Attribute2::Height(r) => rect_layout_style.size.height = r,
@@ -568,7 +757,7 @@ fn set_default_style1(world: &mut GuiWorld, r: Class) {
     for attr in r.attrs3.into_iter() {
         match attr {
             Attribute3::Color(r) => text_style.text.color = r,
-            Attribute3::TextShadow(r) => text_style.shadow = r,
+            Attribute3::TextShadow(r) => text_style.shadows = r,
             Attribute3::TextStroke(r) => text_style.text.stroke = r,
             // Attribute::BorderColor(r) => border_color = r,
             _ => debug_println!("set_class error"),
@@ -1,4 +1,3 @@
-use std::collections::hash_map::Entry;
 /// 将设置文本属性的接口导出到js
 use std::mem::transmute;
 
@@ -11,6 +10,7 @@ use data_view::GetView;
 use ecs::LendMut;
 use gui::component::user::*;
 use gui::font::font_sheet::{FontSheet, Glyph, TexFont, TextInfo as TextInfo1};
+use gui::single::style_parse::parse_rich_text;
 use hal_core::*;
 use hash::XHashMap;
 use set_render_dirty;
@@ -94,6 +94,31 @@ pub fn set_text_linear_gradient_color(world: u32, node_id: u32, direction: f32)
     set_attr!(world, node_id, text, color, "color", value, text_style);
 }
 
+/// 设置文字径向渐变颜色
+/// __jsObj: color_and_positions: [r, g, b, a, pos,   r, g, b, a, pos], center_x: 0~1, center_y: 0~1
+/// shape: 0(ellipse), 1(circle); size: 0(closest-side), 1(farthest-side), 2(closest-corner), 3(farthest-corner)
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_radial_gradient_color(
+    world: u32,
+    node_id: u32,
+    center_x: f32,
+    center_y: f32,
+    shape: u8,
+    size: u8,
+) {
+    let color_and_positions: TypedArray<f32> = js!(return __jsObj;).try_into().unwrap();
+    let value = Color::RadialGradient(to_radial_gradient_color(
+        color_and_positions.to_vec(),
+        center_x,
+        center_y,
+        shape,
+        size,
+    ));
+    set_attr!(world, node_id, text, color, "color", value, text_style);
+}
+
 /// 设置行高为normal
 #[allow(unused_attributes)]
 #[no_mangle]
@@ -209,7 +234,120 @@ pub fn set_white_space(world: u32, node_id: u32, value: u8) {
     );
 }
 
-/// 设置文字阴影
+/// 设置文字溢出时的处理方式， 0: 裁剪， 1: 省略号
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_overflow(world: u32, node_id: u32, value: u8) {
+    set_attr!(
+        world,
+        node_id,
+        text,
+        text_overflow,
+        "text_overflow",
+        if value == 1 { TextOverflow::Ellipsis } else { TextOverflow::Clip },
+        text_style
+    );
+}
+
+/// 设置文字溢出时， 在最后一个可见行末尾显示的字符串
+/// __jsObj: 字符串
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_overflow_custom(world: u32, node_id: u32) {
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    set_attr!(
+        world,
+        node_id,
+        text,
+        text_overflow,
+        "text_overflow",
+        TextOverflow::Custom(value),
+        text_style
+    );
+}
+
+/// 设置文字的最大行数， 0表示不限制
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_line_clamp(world: u32, node_id: u32, value: u32) {
+    set_attr!(
+        world,
+        node_id,
+        text,
+        line_clamp,
+        "line_clamp",
+        value as usize,
+        text_style
+    );
+}
+
+// 修改文字装饰线的某个属性
+fn set_text_decoration<F: FnOnce(&mut TextDecoration)>(world: u32, node_id: u32, f: F) {
+    let node_id = node_id as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let attr = world.gui.text_style.lend_mut();
+    f(&mut unsafe { attr.get_unchecked_mut(node_id) }.text.decoration);
+    attr.get_notify_ref().modify_event(node_id, "text_decoration", 0);
+    debug_println!("set_text_decoration");
+}
+
+/// 设置文字装饰线的种类， 1: 下划线， 2: 上划线， 4: 删除线， 可以组合， 0表示没有装饰线
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_decoration_line(world: u32, node_id: u32, value: u8) {
+    set_text_decoration(world, node_id, |r| r.line = value as usize);
+}
+
+/// 设置文字装饰线的样式， 0: 实线， 1: 虚线， 2: 点线， 3: 波浪线
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_decoration_style(world: u32, node_id: u32, value: u8) {
+    set_text_decoration(world, node_id, |r| r.style = unsafe { transmute(value) });
+}
+
+/// 设置文字装饰线的颜色， a小于0时使用文字颜色
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_decoration_color(world: u32, node_id: u32, r: f32, g: f32, b: f32, a: f32) {
+    set_text_decoration(world, node_id, |d| {
+        d.color = if a < 0.0 { None } else { Some(CgColor::new(r, g, b, a)) }
+    });
+}
+
+/// 设置文字装饰线的粗细， 0表示使用字体建议的粗细
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_text_decoration_thickness(world: u32, node_id: u32, value: f32) {
+    set_text_decoration(world, node_id, |r| r.thickness = value);
+}
+
+// 修改文字阴影列表
+fn modify_text_shadows<F: FnOnce(&mut Vec<TextShadow>)>(world: u32, node_id: u32, f: F) {
+    let node_id = node_id as usize;
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let world = &mut world.gui;
+    let text_styles = world.text_style.lend_mut();
+    let r = match text_styles.get_mut(node_id) {
+        Some(r) => r,
+        None => {
+            text_styles.insert_no_notify(node_id, TextStyle::default());
+            unsafe { text_styles.get_unchecked_mut(node_id) }
+        }
+    };
+    f(&mut r.shadows);
+    text_styles
+        .get_notify_ref()
+        .modify_event(node_id, "text_shadow", 0);
+}
+
+/// 设置文字阴影（替换节点已有的所有阴影）
 #[allow(unused_attributes)]
 #[no_mangle]
 #[js_export]
@@ -227,27 +365,47 @@ pub fn set_text_shadow(
     let value = TextShadow {
         h: h,
         v: v,
-        blur: blur,
+        blur: blur.max(0.0),
         color: CgColor::new(r, g, b, a),
     };
-    let node_id = node_id as usize;
-    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
-    let world = &mut world.gui;
-    let text_styles = world.text_style.lend_mut();
-    let r = match text_styles.get_mut(node_id) {
-        Some(r) => r,
-        None => {
-            text_styles.insert_no_notify(node_id, TextStyle::default());
-            unsafe { text_styles.get_unchecked_mut(node_id) }
-        }
-    };
-    r.shadow = value;
-    text_styles
-        .get_notify_ref()
-        .modify_event(node_id, "text_shadow", 0);
+    modify_text_shadows(world, node_id, |r| *r = vec![value]);
     debug_println!("set_text_shadow");
 }
 
+/// 追加一个文字阴影， 先添加的阴影绘制在上层（与css的text-shadow列表一致）
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn add_text_shadow(
+    world: u32,
+    node_id: u32,
+    h: f32,
+    v: f32,
+    blur: f32,
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+) {
+    let value = TextShadow {
+        h: h,
+        v: v,
+        blur: blur.max(0.0),
+        color: CgColor::new(r, g, b, a),
+    };
+    modify_text_shadows(world, node_id, |r| r.push(value));
+    debug_println!("add_text_shadow");
+}
+
+/// 删除节点的所有文字阴影
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn clear_text_shadow(world: u32, node_id: u32) {
+    modify_text_shadows(world, node_id, |r| r.clear());
+    debug_println!("clear_text_shadow");
+}
+
 /// 设置字体风格
 #[allow(unused_attributes)]
 #[no_mangle]
@@ -369,7 +527,7 @@ pub fn add_msdf_font_res(world_id: u32) {
     update_text_texture(world_id, 0, 0, height);
 
     parse_msdf_font_res(cfg.as_slice(), font_sheet).unwrap();
-    font_sheet.font_tex.last_v += height as f32;
+    font_sheet.font_tex.reserve(height as f32);
 }
 
 /// 设置文本内容
@@ -382,6 +540,10 @@ pub fn set_text_content(world_id: u32, node: u32) {
     let node = node as usize;
     let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
     let world = &mut world.gui;
+    let text_spans = world.text_spans.lend_mut();
+    if text_spans.get(node).is_some() {
+        text_spans.delete(node);
+    }
     world
         .text_content
         .lend_mut()
@@ -389,6 +551,31 @@ pub fn set_text_content(world_id: u32, node: u32) {
     debug_println!("set_text_content");
 }
 
+/// 设置富文本， 各片段可以设置不同的颜色、字号、字重、字体、描边、下划线， 或显示为行内图片
+/// __jsObj 富文本字符串， 如：普通<span style="color:#ff0000;font-size:20px">红色</span><b>粗体</b><img src="图片url的hash" width=20 height=20>
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_rich_text(world_id: u32, node: u32) {
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    let node = node as usize;
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let world = &mut world.gui;
+    let (text, spans) = match parse_rich_text(value.as_str()) {
+        Ok(r) => r,
+        Err(e) => {
+            debug_println!("set_rich_text error, {:?}", e);
+            return;
+        }
+    };
+    world.text_spans.lend_mut().insert(node, TextSpans(spans));
+    world
+        .text_content
+        .lend_mut()
+        .insert(node, TextContent(text, Atom::from("")));
+    debug_println!("set_rich_text");
+}
+
 /// 添加一个canvas字体
 /// __jsObj1: name(String)
 #[allow(unused_attributes)]
@@ -402,6 +589,23 @@ pub fn add_canvas_font(world: u32, factor_t: f32, factor_b: f32) {
     font_sheet.borrow_mut().set_src(Atom::from(name), true, factor_t, factor_b);
 }
 
+/// 添加一个原生字体， 由gui直接解析字体文件， 测量和绘制字符， 不需要canvas及修正系数
+/// is_pixel为0时， 字符按需生成sdf， 使用msdf着色器渲染
+/// __jsObj: name(String), __jsObj1: 字体文件(TTF/OTF, Uint8Array)
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn add_native_font(world: u32, is_pixel: u32) {
+    let world = unsafe { &mut *(world as usize as *mut GuiWorld) };
+    let world = &mut world.gui;
+    let name: String = js!(return __jsObj;).try_into().unwrap();
+    let data: TypedArray<u8> = js!(return __jsObj1;).try_into().unwrap();
+    let font_sheet = world.font_sheet.lend_mut();
+    if let Err(e) = font_sheet.borrow_mut().add_native_font(Atom::from(name), data.to_vec(), is_pixel != 0) {
+        debug_println!("add_native_font fail, {}", e);
+    }
+}
+
 /// 添加font-face
 ///          字体族名称                        字体名称（逗号分隔）     
 /// __jsObj: family_name(String), __jsObj1: src_name(String, 逗号分隔),
@@ -471,126 +675,145 @@ pub fn draw_canvas_text(world_id: u32, data: u32) {
 	let font_sheet = &mut single_font_sheet.borrow_mut();
 	font_sheet.tex_version += 1;
     let engine = world.engine.lend_mut();
-    let texture = font_sheet.get_font_tex();
 
-    // 将在绘制在同一行的文字归类在一起， 以便一起绘制，一起更新
-    let mut end_v = 0;
-    let mut map: XHashMap<u32, (Vec<usize>, Vector2)> = XHashMap::default();
+    // 将在绘制在同一页同一行的文字归类在一起， 以便一起绘制，一起更新
+    let mut end_vs: XHashMap<usize, u32> = XHashMap::default();
+    let mut map: XHashMap<(usize, u32), Vec<usize>> = XHashMap::default();
     for i in 0..text_info_list.len() {
         let text_info = &text_info_list[i];
         let first = &text_info.chars[0];
         let h = first.y + text_info.size.y as u32;
-        if h > end_v {
-            end_v = h;
+        let end_v = end_vs.entry(text_info.page).or_insert(0);
+        if h > *end_v {
+            *end_v = h;
         }
-        match map.entry(first.y) {
-            Entry::Occupied(mut e) => {
-                let r = e.get_mut();
-                r.0.push(i);
-                r.1.x += text_info.size.x;
-                if text_info.size.y > r.1.y {
-                    r.1.y = text_info.size.y;
-                }
-            }
-            Entry::Vacant(r) => {
-                r.insert((vec![i], text_info.size.clone()));
-            }
-        };
+        map.entry((text_info.page, first.y)).or_insert_with(Vec::new).push(i);
     }
 
     // 扩展纹理
-    if end_v > texture.height as u32 {
-        end_v = next_power_of_two(end_v);
-        if end_v > world1.max_texture_size {
-            debug_println!("update_canvas_text fail, height overflow");
+    for (page, end_v) in end_vs.iter() {
+        let texture = match font_sheet.get_page_tex(*page) {
+            Some(r) => r,
+            None => continue,
+        };
+        let mut end_v = *end_v;
+        if end_v > texture.height as u32 {
+            end_v = next_power_of_two(end_v);
+            if end_v > world1.max_texture_size {
+                debug_println!("update_canvas_text fail, height overflow");
+            }
+            engine
+                .gl
+                .texture_extend(&texture.bind, texture.width as u32, end_v);
+            texture.update_size(texture.width, end_v as usize);
+            single_font_sheet.get_notify_ref().modify_event(0, "", 0);
         }
-        engine
-            .gl
-            .texture_extend(&texture.bind, texture.width as u32, end_v);
-        texture.update_size(texture.width, end_v as usize);
-        single_font_sheet.get_notify_ref().modify_event(0, "", 0);
     }
 
-    for indexs in map.iter() {
+    for ((page, _), indexs) in map.iter_mut() {
+        let texture = match font_sheet.get_page_tex(*page) {
+            Some(r) => r,
+            None => continue,
+        };
+        // 字形回收后， 同一行的空闲区域不一定连续， 只有在纹理中相邻的文字才一起绘制， 避免背景覆盖其它字形
+        indexs.sort_by_key(|i| text_info_list[*i].chars[0].x);
+        let mut start = 0;
+        while start < indexs.len() {
+            let mut size = text_info_list[indexs[start]].size.clone();
+            let mut end = start + 1;
+            while end < indexs.len() {
+                let prev = &text_info_list[indexs[end - 1]];
+                let next = &text_info_list[indexs[end]];
+                if prev.chars[0].x + prev.size.x as u32 != next.chars[0].x {
+                    break;
+                }
+                size.x += next.size.x;
+                if next.size.y > size.y {
+                    size.y = next.size.y;
+                }
+                end += 1;
+            }
+            draw_text_infos(canvas, &text_info_list, &indexs[start..end], &size);
+            // // 在华为Mate 20上，将canvas更新到纹理存在bug，因此这里将canvas的数据取到，然后跟新到纹理
+            // // 如果在后续迭代的过程中，所有手机都不存在该bug，应该删除该句，以节省性能（getImageData会拷贝数据）
+            // js!{
+            // 	@{canvas}.wrap = @{canvas}.ctx.getImageData(0, 0, @{canvas}.canvas.width, @{canvas}.canvas.height);
+            // }
+            let first = &text_info_list[indexs[start]].chars[0];
+            engine
+                .gl
+                .texture_update_webgl(&texture.bind, 0, first.x, first.y, &canvas);
+            start = end;
+        }
+    }
+
+    // println!("time: {:?}", std::time::Instant::now() - t);
+	// println!("set_render_dirty11============={}", world_id);
+    set_render_dirty(world_id);
+}
+
+// 将纹理中相邻的文字绘制到canvas上
+fn draw_text_infos(canvas: &Object, text_info_list: &Vec<TextInfo1>, indexs: &[usize], size: &Vector2) {
+    js! {
+
+        var c = @{canvas};
+        var canvas = c.canvas;
+        canvas.width = @{size.x as u32 };
+        canvas.height = @{size.y as u32 };
+        var ctx = c.ctx;
+        ctx.fillStyle = "#00f";
+        ctx.fillRect(0, 0, canvas.width, canvas.height);
+    }
+    let start = text_info_list[indexs[0]].chars[0].x;
+    for i in indexs.iter() {
+        let text_info = &text_info_list[*i];
+        let hal_stroke_width = text_info.stroke_width / 2;
         js! {
 
             var c = @{canvas};
-            var canvas = c.canvas;
-            canvas.width = @{(indexs.1).1.x as u32 };
-            canvas.height = @{(indexs.1).1.y as u32 };
             var ctx = c.ctx;
-            ctx.fillStyle = "#00f";
-            ctx.fillRect(0, 0, canvas.width, canvas.height);
-        }
-        let mut start: (i32, i32) = (-1, -1);
-        for i in (indexs.1).0.iter() {
-            let text_info = &text_info_list[*i];
-            let first = &text_info.chars[0];
-            if start.0 == -1 {
-                start.0 = first.x as i32;
-                start.1 = first.y as i32;
+            var weight;
+            if (@{text_info.weight as u32} <= 300 ) {
+                weight = "lighter";
+            } else if (@{text_info.weight as u32} < 700 ) {
+                weight = "normal";
+            } else if (@{text_info.weight as u32} < 900 ) {
+                weight = "bold";
+            } else {
+                weight = "bolder";
             }
-            let hal_stroke_width = text_info.stroke_width / 2;
-            let bottom = text_info.size.y as u32 - hal_stroke_width as u32;
+            ctx.font = weight + " " + @{text_info.font_size as u32} + "px " + @{text_info.font.as_ref()};
+            ctx.fillStyle = "#0f0";
+            ctx.textBaseline = "top";
+        }
+        if text_info.stroke_width > 0 {
             js! {
-
                 var c = @{canvas};
-                var ctx = c.ctx;
-                var weight;
-                if (@{text_info.weight as u32} <= 300 ) {
-                    weight = "lighter";
-                } else if (@{text_info.weight as u32} < 700 ) {
-                    weight = "normal";
-                } else if (@{text_info.weight as u32} < 900 ) {
-                    weight = "bold";
-                } else {
-                    weight = "bolder";
-                }
-                ctx.font = weight + " " + @{text_info.font_size as u32} + "px " + @{text_info.font.as_ref()};
-                ctx.fillStyle = "#0f0";
-                ctx.textBaseline = "top";
+                c.ctx.lineWidth = @{text_info.stroke_width as u8};
+                c.ctx.strokeStyle = "#f00";
             }
-            if text_info.stroke_width > 0 {
+            for char_info in text_info.chars.iter() {
+                let ch_code: u32 = unsafe { transmute(char_info.ch) };
+                let x = char_info.x + hal_stroke_width as u32 - start;
                 js! {
                     var c = @{canvas};
-                    c.ctx.lineWidth = @{text_info.stroke_width as u8};
-                    c.ctx.strokeStyle = "#f00";
-                }
-                for char_info in text_info.chars.iter() {
-                    let ch_code: u32 = unsafe { transmute(char_info.ch) };
-                    let x = char_info.x + hal_stroke_width as u32 - start.0 as u32;
-                    js! {
-                        var c = @{canvas};
-                        var ch = String.fromCharCode(@{ch_code});
-                        //fillText 和 strokeText 的顺序对最终效果会有影响， 为了与css text-stroke保持一致， 应该fillText在前
-                        c.ctx.strokeText(ch, @{x}, @{text_info.top as u32});
-                        c.ctx.fillText(ch, @{x}, @{text_info.top as u32});
-                    }
+                    var ch = String.fromCharCode(@{ch_code});
+                    //fillText 和 strokeText 的顺序对最终效果会有影响， 为了与css text-stroke保持一致， 应该fillText在前
+                    c.ctx.strokeText(ch, @{x}, @{text_info.top as u32});
+                    c.ctx.fillText(ch, @{x}, @{text_info.top as u32});
                 }
-            } else {
-                for char_info in text_info.chars.iter() {
-                    let ch_code: u32 = unsafe { transmute(char_info.ch) };
-                    let x = char_info.x - start.0 as u32;
-                    js! {
-                        var ch = String.fromCharCode(@{ch_code});
-                        @{canvas}.ctx.fillText(ch, @{x}, @{text_info.top as u32});
-                    }
+            }
+        } else {
+            for char_info in text_info.chars.iter() {
+                let ch_code: u32 = unsafe { transmute(char_info.ch) };
+                let x = char_info.x - start;
+                js! {
+                    var ch = String.fromCharCode(@{ch_code});
+                    @{canvas}.ctx.fillText(ch, @{x}, @{text_info.top as u32});
                 }
             }
-		}
-		// // 在华为Mate 20上，将canvas更新到纹理存在bug，因此这里将canvas的数据取到，然后跟新到纹理
-		// // 如果在后续迭代的过程中，所有手机都不存在该bug，应该删除该句，以节省性能（getImageData会拷贝数据）
-		// js!{
-		// 	@{canvas}.wrap = @{canvas}.ctx.getImageData(0, 0, @{canvas}.canvas.width, @{canvas}.canvas.height);
-		// }
-        engine
-            .gl
-            .texture_update_webgl(&texture.bind, 0, start.0 as u32, start.1 as u32, &canvas);
+        }
     }
-
-    // println!("time: {:?}", std::time::Instant::now() - t);
-	// println!("set_render_dirty11============={}", world_id);
-    set_render_dirty(world_id);
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,186 @@
+/// 将文本输入框接口导出到js
+/// 复制、剪切时调用window.__text_input_copy(world, text)写入剪贴板， 用户输入改变编辑缓冲区后调用window.__text_input_change(world, node)
+/// 编辑命令： 0: 左， 1: 右， 2: 上， 3: 下， 4: 行首， 5: 行尾， 6: 上一个单词， 7: 下一个单词， 8: 开头， 9: 结尾，
+/// 10: 向前删除， 11: 向后删除， 12: 向前删除单词， 13: 向后删除单词， 14: 换行， 15: 全选， 16: 复制， 17: 剪切
+use stdweb::unstable::TryInto;
+
+use ecs::LendMut;
+use gui::single::EditCommand;
+use gui::system::{
+    commit_text_input_composition, create_text_input, focus_text_input, set_text_input_composition, set_text_input_selection,
+    set_text_input_value, text_input_caret_position, text_input_command, text_input_insert, text_input_selection, text_input_value,
+};
+use share::Share;
+
+use node::create_text_node;
+use GuiWorld;
+
+fn edit_command(command: u8) -> Option<EditCommand> {
+    Some(match command {
+        0 => EditCommand::Left,
+        1 => EditCommand::Right,
+        2 => EditCommand::Up,
+        3 => EditCommand::Down,
+        4 => EditCommand::LineStart,
+        5 => EditCommand::LineEnd,
+        6 => EditCommand::WordLeft,
+        7 => EditCommand::WordRight,
+        8 => EditCommand::DocStart,
+        9 => EditCommand::DocEnd,
+        10 => EditCommand::Backspace,
+        11 => EditCommand::Delete,
+        12 => EditCommand::DeleteWordBackward,
+        13 => EditCommand::DeleteWordForward,
+        14 => EditCommand::NewLine,
+        15 => EditCommand::SelectAll,
+        16 => EditCommand::Copy,
+        17 => EditCommand::Cut,
+        _ => return None,
+    })
+}
+
+/// 创建输入框节点， multiline为true时为多行输入框
+/// 输入框是文字节点， 需要设置宽度或高度（或为父节点唯一的子节点）
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn create_input_node(world_id: u32, multiline: bool) -> u32 {
+    let node = create_text_node(world_id);
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    let gui = &world.gui;
+    {
+        let text_inputs = gui.text_inputs.lend_mut();
+        if text_inputs.clipboard.is_none() {
+            text_inputs.clipboard = Some(Share::new(move |text: &str| {
+                js! {
+                    window.__text_input_copy(@{world_id}, @{text});
+                }
+            }));
+            text_inputs.on_input = Some(Share::new(move |node: usize| {
+                js! {
+                    window.__text_input_change(@{world_id}, @{node as u32});
+                }
+            }));
+        }
+    }
+    create_text_input(gui, node as usize, multiline);
+    node
+}
+
+/// 设置输入框的内容， 光标移到末尾
+/// __jsObj 文字字符串
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_input_value(world_id: u32, node: u32) -> bool {
+    let value: String = js!(return __jsObj;).try_into().unwrap();
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    set_text_input_value(&world.gui, node as usize, value.as_str())
+}
+
+/// 取输入框的内容， 节点不是输入框时返回空字符串
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn get_input_value(world_id: u32, node: u32) -> String {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    text_input_value(&world.gui, node as usize).unwrap_or_default()
+}
+
+/// 设置选区， anchor为选区的固定端， caret为光标所在端（按字符计数）
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_input_selection(world_id: u32, node: u32, anchor: u32, caret: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    set_text_input_selection(&world.gui, node as usize, anchor as usize, caret as usize)
+}
+
+/// 取选区， 结果为[起点, 终点]， 设置在__jsObj中， 节点不是输入框时返回false
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn get_input_selection(world_id: u32, node: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    match text_input_selection(&world.gui, node as usize) {
+        Some((start, end)) => {
+            js! {
+                window.__jsObj = [@{start as u32}, @{end as u32}];
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// 使输入框获得焦点， node为0时当前输入框失去焦点
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn focus_input(world_id: u32, node: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    focus_text_input(&world.gui, node as usize)
+}
+
+/// 在光标处插入文字（键入字符或粘贴）
+/// __jsObj 文字字符串
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn input_insert_text(world_id: u32, node: u32) -> bool {
+    let text: String = js!(return __jsObj;).try_into().unwrap();
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    text_input_insert(&world.gui, node as usize, text.as_str())
+}
+
+/// 执行编辑命令， extend为true时扩展选区（如按住shift）， 返回命令是否被处理
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn input_command(world_id: u32, node: u32, command: u8, extend: bool) -> bool {
+    let command = match edit_command(command) {
+        Some(r) => r,
+        None => return false,
+    };
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    text_input_command(&world.gui, node as usize, command, extend)
+}
+
+/// 设置输入法正在组合的文字（compositionupdate）
+/// __jsObj 文字字符串
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_input_composition(world_id: u32, node: u32) -> bool {
+    let text: String = js!(return __jsObj;).try_into().unwrap();
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    set_text_input_composition(&world.gui, node as usize, text.as_str())
+}
+
+/// 结束输入法组合（compositionend）， 插入确定的文字， 空字符串表示取消组合
+/// __jsObj 文字字符串
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn commit_input_composition(world_id: u32, node: u32) -> bool {
+    let text: String = js!(return __jsObj;).try_into().unwrap();
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    commit_text_input_composition(&world.gui, node as usize, text.as_str())
+}
+
+/// 取光标在屏幕上的位置， 结果为[x, 顶部y, 底部y]， 设置在__jsObj中， 用于放置输入法的候选窗口
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn get_input_caret(world_id: u32, node: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    match text_input_caret_position(&world.gui, node as usize) {
+        Some((x, top, bottom)) => {
+            js! {
+                window.__jsObj = [@{x}, @{top}, @{bottom}];
+            }
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,74 @@
+/// 将虚拟列表接口导出到js
+/// 行节点的创建和数据绑定由js完成：
+/// 创建行节点时调用window.__virtual_list_create(world, callback, list)， 返回新创建的行节点
+/// 绑定数据时调用window.__virtual_list_bind(world, callback, list, row, index)
+use stdweb::unstable::TryInto;
+
+use gui::system::{
+    create_virtual_list as create_list, refresh_virtual_list as refresh_list, remove_virtual_list as remove_list,
+    set_virtual_list_count as set_list_count, virtual_list_scroll_to_index as scroll_to_index,
+};
+use share::Share;
+
+use GuiWorld;
+
+/// 将节点设置为虚拟列表， 节点需要设置overflow为scroll或auto
+/// estimated_height: 未测量的数据项的预估高度， callback: 传给js回调的参数， 用于区分不同的列表
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn create_virtual_list(world_id: u32, node: u32, estimated_height: f32, callback: u32) {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    create_list(
+        &world.gui,
+        node as usize,
+        estimated_height,
+        Share::new(move |list: usize| {
+            let row: u32 = js! {
+                return window.__virtual_list_create(@{world_id}, @{callback}, @{list as u32});
+            }.try_into().unwrap();
+            row as usize
+        }),
+        Share::new(move |list: usize, row: usize, index: usize| {
+            js! {
+                window.__virtual_list_bind(@{world_id}, @{callback}, @{list as u32}, @{row as u32}, @{index as u32});
+            }
+        }),
+    );
+}
+
+/// 取消节点的虚拟列表， 已创建的行节点保留为普通子节点
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn remove_virtual_list(world_id: u32, node: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    remove_list(&world.gui, node as usize)
+}
+
+/// 设置数据项的数量， 所有行在下一帧重新绑定
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn set_virtual_list_count(world_id: u32, node: u32, count: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    set_list_count(&world.gui, node as usize, count as usize)
+}
+
+/// 数据改变， 所有行在下一帧重新绑定
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn refresh_virtual_list(world_id: u32, node: u32) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    refresh_list(&world.gui, node as usize)
+}
+
+/// 滚动到数据项， smooth为true时平滑滚动
+#[allow(unused_attributes)]
+#[no_mangle]
+#[js_export]
+pub fn virtual_list_scroll_to_index(world_id: u32, node: u32, index: u32, smooth: bool) -> bool {
+    let world = unsafe { &mut *(world_id as usize as *mut GuiWorld) };
+    scroll_to_index(&world.gui, node as usize, index as usize, smooth)
+}
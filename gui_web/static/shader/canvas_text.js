@@ -10,6 +10,10 @@ let canvas_text_vs_code = `
     #ifdef VERTEX_COLOR
     attribute vec4 color;
     #endif
+    #ifdef TEXT_SHADOW_BLUR
+    attribute vec2 uv1; // 字形在纹理中的范围（左上）
+    attribute vec2 uv2; // 字形在纹理中的范围（右下）
+    #endif
 
     // Uniforms
     uniform mat4 worldMatrix;
@@ -30,6 +34,11 @@ let canvas_text_vs_code = `
         varying vec2 vClipBox;
     #endif
 
+    #ifdef TEXT_SHADOW_BLUR
+        varying vec2 vTexel;
+        varying vec4 vRect;
+    #endif
+
     varying vec2 vUV;
 
     void main() {
@@ -47,6 +56,10 @@ let canvas_text_vs_code = `
     #endif
         // vUV = vec2(uv0.x/1024.0, uv0.y/1024.0);
         vUV = vec2(uv0.x/textureSize.x, uv0.y/textureSize.y);
+    #ifdef TEXT_SHADOW_BLUR
+        vTexel = uv0;
+        vRect = vec4(uv1, uv2);
+    #endif
 
         #ifdef CLIP_BOX
             vClipBox = vec2((p1.x - clipBox.x)/clipBox.z, (p1.y - clipBox.y)/clipBox.w);
@@ -99,6 +112,12 @@ let canvas_text_fs_code = `
     #ifdef UCOLOR
     uniform vec4 uColor;
     #endif
+    #ifdef TEXT_SHADOW_BLUR
+    uniform float blur; // 模糊半径（纹素）
+    uniform vec2 textureSize;
+    varying vec2 vTexel;
+    varying vec4 vRect;
+    #endif
 
     #ifdef HSV
         vec3 rgb2hsv(vec3 c)
@@ -202,10 +221,28 @@ let canvas_text_fs_code = `
         c = c * uColor;
     #endif
     
+    #ifdef TEXT_SHADOW_BLUR
+        // 模糊阴影： 在字形所在的纹理范围内做7x7的高斯采样（半径为2倍标准差）， 不会采样到相邻的字形
+        float cover = 0.0;
+        float total = 0.0;
+        vec4 rect = vec4(vRect.xy + 0.5, vRect.zw - 0.5);
+        for (int i = -3; i <= 3; i++) {
+            for (int j = -3; j <= 3; j++) {
+                float w = exp(-float(i * i + j * j) * 2.0 / 9.0);
+                vec2 uv = clamp(vTexel + vec2(float(i), float(j)) * blur / 3.0, rect.xy, rect.zw);
+                vec4 s = texture2D(texture, uv / textureSize);
+                cover += w * clamp(s.a - s.b, 0.0, 1.0);
+                total += w;
+            }
+        }
+        cover = cover / total;
+        c = vec4(alpha * c.rgb * c.a * cover, c.a * cover);
+    #else
     vec4 sample = texture2D(texture, vUV);
     c = alpha * (sample.r * strokeColor + sample.g * c);
     // c.a = 1.0 - sample.b;
     c.a = clamp(sample.a - sample.b, 0.0, 1.0); // 应该 c.a = 1.0 - sample.b, 由于纹理坐标误差， 导致采样到纹理的空白处（rgba都为0）， 会看到一条黑线
+    #endif
     
     #ifdef HSV
         vec3 hsv = rgb2hsv(c.rgb);
@@ -1,4 +1,5 @@
-var gl = document.getElementById("canvas").getContext('webgl');
+// 旋转、圆角等非矩形的裁剪区域可以使用模板缓冲区裁剪， 需要请求模板缓冲区
+var gl = document.getElementById("canvas").getContext('webgl', { stencil: true });
 window.__gl = gl;
 window.__fbo = undefined;
 
@@ -100,6 +100,9 @@ let text_fs_code = `
     #ifdef UCOLOR
     uniform vec4 uColor;
     #endif
+    #ifdef TEXT_SHADOW_BLUR
+    uniform float blur; // 模糊范围（sdf距离）， 受sdf纹理的距离范围限制
+    #endif
 
     #ifdef HSV
         vec3 rgb2hsv(vec3 c)
@@ -215,7 +218,13 @@ let text_fs_code = `
         // float a = clamp( (dist - 0.5) / fwidth(dist - 0.5) + 0.5, 0.0, 1.0);
 
         float d = fwidth(dist);
+    #ifdef TEXT_SHADOW_BLUR
+        // 模糊阴影： 在字形边缘两侧的距离场上做平滑过渡
+        float k = clamp(blur, d, 0.5);
+        float a = smoothstep(-k, k, dist - 0.5);
+    #else
         float a = smoothstep(-d, d, dist - 0.5);
+    #endif
     #ifdef STROKE
         c = mix(strokeColor, c, a);
         a = smoothstep(-d, d, dist - (0.5 - strokeSize));
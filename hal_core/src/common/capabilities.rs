@@ -52,6 +52,8 @@ pub struct Capabilities {
     pub vertex_array_object: bool,
     // 是否支持实例化
     pub instanced_arrays: bool,
+    // 默认渲染目标的模板缓冲区位数， 0表示没有模板缓冲区
+    pub stencil_bits: u32,
 }
 
 impl Default for Capabilities {
@@ -87,6 +89,7 @@ impl Capabilities {
             depth_texture_extension: false,
             vertex_array_object: false,
             instanced_arrays: false,
+            stencil_bits: 0,
         }
     }
 }
@@ -154,6 +157,7 @@ impl fmt::Debug for Capabilities {
         )?;
         writeln!(f, "    vertex_array_object: {}", self.vertex_array_object)?;
         writeln!(f, "    instanced_arrays: {}", self.instanced_arrays)?;
+        writeln!(f, "    stencil_bits: {}", self.stencil_bits)?;
         writeln!(f, "]")
     }
 }
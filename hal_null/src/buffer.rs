@@ -0,0 +1,88 @@
+use hal_core::*;
+
+pub enum NullBufferData {
+    Float(Vec<f32>),
+    Short(Vec<u16>),
+}
+
+pub struct NullBufferImpl {
+    pub count: usize,
+    pub data: NullBufferData,
+}
+
+impl NullBufferImpl {
+    pub fn new(
+        btype: BufferType,
+        count: usize,
+        data: Option<BufferData>,
+        _is_updatable: bool,
+    ) -> Result<Self, String> {
+        let mut data_impl = match btype {
+            BufferType::Attribute => NullBufferData::Float(vec![0.0; count]),
+            BufferType::Indices => NullBufferData::Short(vec![0; count]),
+        };
+
+        if let Some(data) = data {
+            match (&mut data_impl, data) {
+                (NullBufferData::Float(dst), BufferData::Float(src)) => {
+                    // 和webgl一样，初始数据决定buffer的大小
+                    dst.clear();
+                    dst.extend_from_slice(src);
+                }
+                (NullBufferData::Short(dst), BufferData::Short(src)) => {
+                    dst.clear();
+                    dst.extend_from_slice(src);
+                }
+                _ => return Err("NullBufferImpl new failed, data type isn't match".to_string()),
+            }
+        }
+
+        let count = match &data_impl {
+            NullBufferData::Float(v) => v.len(),
+            NullBufferData::Short(v) => v.len(),
+        };
+
+        Ok(NullBufferImpl {
+            count,
+            data: data_impl,
+        })
+    }
+
+    pub fn update(&mut self, offset: usize, data: BufferData) {
+        match (&mut self.data, data) {
+            (NullBufferData::Float(dst), BufferData::Float(src)) => {
+                if offset + src.len() > dst.len() {
+                    dst.resize(offset + src.len(), 0.0);
+                }
+                dst[offset..offset + src.len()].copy_from_slice(src);
+            }
+            (NullBufferData::Short(dst), BufferData::Short(src)) => {
+                if offset + src.len() > dst.len() {
+                    dst.resize(offset + src.len(), 0);
+                }
+                dst[offset..offset + src.len()].copy_from_slice(src);
+            }
+            _ => panic!("NullBufferImpl update failed, data type isn't match"),
+        }
+        self.count = match &self.data {
+            NullBufferData::Float(v) => v.len(),
+            NullBufferData::Short(v) => v.len(),
+        };
+    }
+
+    #[inline]
+    pub fn get_float(&self, index: usize) -> f32 {
+        match &self.data {
+            NullBufferData::Float(v) => v.get(index).cloned().unwrap_or(0.0),
+            NullBufferData::Short(v) => v.get(index).cloned().unwrap_or(0) as f32,
+        }
+    }
+
+    #[inline]
+    pub fn get_short(&self, index: usize) -> u16 {
+        match &self.data {
+            NullBufferData::Short(v) => v.get(index).cloned().unwrap_or(0),
+            NullBufferData::Float(v) => v.get(index).cloned().unwrap_or(0.0) as u16,
+        }
+    }
+}
@@ -0,0 +1,1200 @@
+use atom::Atom;
+use hash::XHashMap;
+use share::Share;
+use slab::Slab;
+
+use hal_core::*;
+
+use buffer::NullBufferImpl;
+use geometry::NullGeometryImpl;
+use program::NullProgramImpl;
+use raster::{self, DrawState, ShadedVertex, Target};
+use render_target::{NullRenderBufferImpl, NullRenderTargetImpl};
+use shader::*;
+use texture::NullTextureImpl;
+use util::*;
+
+/**
+ * 当前的渲染状态，相当于gl的状态机
+ */
+pub struct NullState {
+    pub target: (u32, u32),
+    pub viewport: (i32, i32, i32, i32),
+    pub scissor: (i32, i32, i32, i32),
+    pub program: (u32, u32),
+    pub bs: BlendStateDesc,
+    pub ds: DepthStateDesc,
+    pub rs: RasterStateDesc,
+    pub ss: StencilStateDesc,
+}
+
+pub struct NullHalContextImpl {
+    // 用于给每个context
+    pub stat: RenderStat,
+
+    pub caps: Capabilities,
+    pub shader_creators: XHashMap<(Atom, Atom), ShaderCreator>,
+    pub state: NullState,
+
+    // u32代表该槽分配的次数
+    pub buffer_slab: Slab<(NullBufferImpl, u32)>,
+    pub geometry_slab: Slab<(NullGeometryImpl, u32)>,
+    pub texture_slab: Slab<(NullTextureImpl, u32)>,
+    pub sampler_slab: Slab<(SamplerDesc, u32)>,
+    pub rt_slab: Slab<(NullRenderTargetImpl, u32)>,
+    pub rb_slab: Slab<(NullRenderBufferImpl, u32)>,
+    pub bs_slab: Slab<(BlendStateDesc, u32)>,
+    pub ds_slab: Slab<(DepthStateDesc, u32)>,
+    pub rs_slab: Slab<(RasterStateDesc, u32)>,
+    pub ss_slab: Slab<(StencilStateDesc, u32)>,
+    pub program_slab: Slab<(NullProgramImpl, u32)>,
+}
+
+pub struct NullHalContext(Share<NullHalContextImpl>, HalRenderTarget);
+
+impl HalContext for NullHalContext {
+    // ==================== HalBuffer
+
+    fn buffer_create(
+        &self,
+        btype: BufferType,
+        count: usize,
+        data: Option<BufferData>,
+        is_updatable: bool,
+    ) -> Result<HalBuffer, String> {
+        NullBufferImpl::new(btype, count, data, is_updatable).map(|buffer| {
+            let context = convert_to_mut(self.0.as_ref());
+            let (index, use_count) = create_new_slot(&mut context.buffer_slab, buffer);
+            context.stat.buffer_count += 1;
+
+            let context_impl = self.0.clone();
+            HalBuffer {
+                item: HalItem { index, use_count },
+                destroy_func: Share::new(move |index: u32, use_count: u32| {
+                    context_impl.buffer_destroy(index, use_count);
+                }),
+            }
+        })
+    }
+
+    fn buffer_update(&self, buffer: &HalBuffer, offset: usize, data: BufferData) {
+        let context = convert_to_mut(self.0.as_ref());
+        if let Some(buffer) = get_mut_ref(
+            &mut context.buffer_slab,
+            buffer.item.index,
+            buffer.item.use_count,
+        ) {
+            buffer.update(offset, data);
+        }
+    }
+
+    // ==================== HalGeometry
+
+    fn geometry_create(&self) -> Result<HalGeometry, String> {
+        NullGeometryImpl::new().map(|geometry| {
+            let context = convert_to_mut(self.0.as_ref());
+            let (index, use_count) = create_new_slot(&mut context.geometry_slab, geometry);
+            context.stat.geometry_count += 1;
+
+            let context_impl = self.0.clone();
+            HalGeometry {
+                item: HalItem { index, use_count },
+                destroy_func: Share::new(move |index: u32, use_count: u32| {
+                    context_impl.geometry_destroy(index, use_count);
+                }),
+            }
+        })
+    }
+
+    fn geometry_get_vertex_count(&self, geometry: &HalGeometry) -> u32 {
+        get_ref(
+            &self.0.geometry_slab,
+            geometry.item.index,
+            geometry.item.use_count,
+        )
+        .map_or(0, |geometry| geometry.get_vertex_count())
+    }
+
+    fn geometry_set_vertex_count(&self, geometry: &HalGeometry, count: u32) {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        if let Some(geometry) = get_mut_ref(slab, geometry.item.index, geometry.item.use_count) {
+            geometry.set_vertex_count(count);
+        }
+    }
+
+    fn geometry_set_attribute(
+        &self,
+        geometry: &HalGeometry,
+        name: &AttributeName,
+        buffer: &HalBuffer,
+        item_count: usize,
+    ) -> Result<(), String> {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        let g = get_mut_ref(slab, geometry.item.index, geometry.item.use_count)
+            .ok_or("geometry isn't found")?;
+
+        let b = get_ref(&self.0.buffer_slab, buffer.item.index, buffer.item.use_count)
+            .ok_or("buffer isn't found")?;
+
+        g.set_attribute(name, b, buffer, item_count)
+    }
+
+    fn geometry_set_attribute_with_offset(
+        &self,
+        geometry: &HalGeometry,
+        name: &AttributeName,
+        buffer: &HalBuffer,
+        item_count: usize,
+        offset: usize,
+        count: usize,
+        stride: usize,
+    ) -> Result<(), String> {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        let g = get_mut_ref(slab, geometry.item.index, geometry.item.use_count)
+            .ok_or("geometry isn't found")?;
+
+        let b = get_ref(&self.0.buffer_slab, buffer.item.index, buffer.item.use_count)
+            .ok_or("buffer isn't found")?;
+
+        g.set_attribute_with_offset(name, b, buffer, item_count, offset, count, stride)
+    }
+
+    fn geometry_remove_attribute(&self, geometry: &HalGeometry, name: &AttributeName) {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        if let Some(g) = get_mut_ref(slab, geometry.item.index, geometry.item.use_count) {
+            g.remove_attribute(name);
+        }
+    }
+
+    fn geometry_set_indices_short(
+        &self,
+        geometry: &HalGeometry,
+        buffer: &HalBuffer,
+    ) -> Result<(), String> {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        let g = get_mut_ref(slab, geometry.item.index, geometry.item.use_count)
+            .ok_or("geometry isn't found")?;
+
+        let b = get_ref(&self.0.buffer_slab, buffer.item.index, buffer.item.use_count)
+            .ok_or("buffer isn't found")?;
+
+        g.set_indices_short(b, buffer)
+    }
+
+    fn geometry_set_indices_short_with_offset(
+        &self,
+        geometry: &HalGeometry,
+        buffer: &HalBuffer,
+        offset: usize,
+        count: usize,
+    ) -> Result<(), String> {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        let g = get_mut_ref(slab, geometry.item.index, geometry.item.use_count)
+            .ok_or("geometry isn't found")?;
+
+        let b = get_ref(&self.0.buffer_slab, buffer.item.index, buffer.item.use_count)
+            .ok_or("buffer isn't found")?;
+
+        g.set_indices_short_with_offset(b, buffer, offset, count)
+    }
+
+    fn geometry_remove_indices(&self, geometry: &HalGeometry) {
+        let slab = convert_to_mut(&self.0.geometry_slab);
+        if let Some(g) = get_mut_ref(slab, geometry.item.index, geometry.item.use_count) {
+            g.remove_indices();
+        }
+    }
+
+    // ==================== HalProgram
+
+    fn program_create_with_vs_fs(
+        &self,
+        _vs_id: u64,
+        _fs_id: u64,
+        vs_name: &str,
+        vs_defines: &[Option<&str>],
+        fs_name: &str,
+        fs_defines: &[Option<&str>],
+        _uniform_layout: &UniformLayout,
+    ) -> Result<HalProgram, String> {
+        let vs_name = Atom::from(vs_name);
+        let fs_name = Atom::from(fs_name);
+
+        NullProgramImpl::new_with_vs_fs(
+            &self.0.shader_creators,
+            &vs_name,
+            vs_defines,
+            &fs_name,
+            fs_defines,
+        )
+        .map(|program| {
+            let context = convert_to_mut(self.0.as_ref());
+            let (index, use_count) = create_new_slot(&mut context.program_slab, program);
+            context.stat.program_count += 1;
+
+            let context_impl = self.0.clone();
+            HalProgram {
+                item: HalItem { index, use_count },
+                destroy_func: Share::new(move |index: u32, use_count: u32| {
+                    context_impl.program_destroy(index, use_count);
+                }),
+            }
+        })
+    }
+
+    // ==================== HalRenderTarget
+
+    fn rt_create(
+        &self,
+        tex: Option<&HalTexture>,
+        w: u32,
+        h: u32,
+        pformat: PixelFormat,
+        dformat: DataFormat,
+        has_depth: bool,
+    ) -> Result<HalRenderTarget, String> {
+        let texture_wrap = match tex {
+            None => self.texture_create_2d(0, w, h, pformat, dformat, false, None)?,
+            Some(r) => HalTexture {
+                item: HalItem {
+                    index: r.item.index,
+                    use_count: r.item.use_count,
+                },
+                destroy_func: Share::new(move |_index: u32, _use_count: u32| {}),
+            },
+        };
+
+        let rb_wrap = if has_depth {
+            Some(self.rb_create(w, h, PixelFormat::DEPTH16)?)
+        } else {
+            None
+        };
+
+        NullRenderTargetImpl::new(w, h, texture_wrap, rb_wrap, tex.is_none()).map(|rt| {
+            let context = convert_to_mut(self.0.as_ref());
+            let (index, use_count) = create_new_slot(&mut context.rt_slab, rt);
+            context.stat.rt_count += 1;
+
+            let context_impl = self.0.clone();
+            HalRenderTarget {
+                item: HalItem { index, use_count },
+                destroy_func: Share::new(move |index: u32, use_count: u32| {
+                    context_impl.rt_destroy(index, use_count);
+                }),
+            }
+        })
+    }
+
+    fn rt_get_size(&self, rt: &HalRenderTarget) -> (u32, u32) {
+        get_ref(&self.0.rt_slab, rt.item.index, rt.item.use_count)
+            .map(|rt| rt.get_size())
+            .unwrap()
+    }
+
+    fn rt_get_color_texture(&self, rt: &HalRenderTarget, _index: u32) -> Option<&HalTexture> {
+        get_ref(&self.0.rt_slab, rt.item.index, rt.item.use_count)
+            .and_then(|rt| rt.get_color_texture())
+    }
+
+    // ==================== HalRenderBuffer
+
+    fn rb_create(&self, w: u32, h: u32, pformat: PixelFormat) -> Result<HalRenderBuffer, String> {
+        NullRenderBufferImpl::new(w, h, pformat).map(|rb| {
+            let slab = convert_to_mut(&self.0.rb_slab);
+            let (index, use_count) = create_new_slot(slab, rb);
+
+            let context_impl = self.0.clone();
+            HalRenderBuffer {
+                item: HalItem { index, use_count },
+                destroy_func: Share::new(move |index: u32, use_count: u32| {
+                    context_impl.rb_destroy(index, use_count);
+                }),
+            }
+        })
+    }
+
+    fn rb_get_size(&self, rb: &HalRenderBuffer) -> (u32, u32) {
+        get_ref(&self.0.rb_slab, rb.item.index, rb.item.use_count)
+            .map(|rb| rb.get_size())
+            .unwrap()
+    }
+
+    // ==================== HalTexture
+
+    fn texture_create_2d(
+        &self,
+        mipmap_level: u32,
+        width: u32,
+        height: u32,
+        pformat: PixelFormat,
+        dformat: DataFormat,
+        is_gen_mipmap: bool,
+        data: Option<TextureData>,
+    ) -> Result<HalTexture, String> {
+        NullTextureImpl::new_2d(
+            mipmap_level,
+            width,
+            height,
+            pformat,
+            dformat,
+            is_gen_mipmap,
+            data,
+        )
+        .map(|texture| {
+            let context = convert_to_mut(self.0.as_ref());
+            let (index, use_count) = create_new_slot(&mut context.texture_slab, texture);
+            context.stat.texture_count += 1;
+
+            let context_impl = self.0.clone();
+            HalTexture {
+                item: HalItem { index, use_count },
+                destroy_func: Share::new(move |index: u32, use_count: u32| {
+                    context_impl.texture_destroy(index, use_count);
+                }),
+            }
+        })
+    }
+
+    /**
+     * 软件实现不解码压缩纹理，创建一张同样大小的空纹理
+     */
+    fn compressed_texture_create_2d(
+        &self,
+        mipmap_level: u32,
+        width: u32,
+        height: u32,
+        internalformat: CompressedTexFormat,
+        is_gen_mipmap: bool,
+        _data: Option<TextureData>,
+    ) -> Result<HalTexture, String> {
+        if internalformat.0 == -1 {
+            return Err(format!("不支持压缩纹理格式：{:?}", internalformat));
+        }
+        self.texture_create_2d(
+            mipmap_level,
+            width,
+            height,
+            PixelFormat::RGBA,
+            DataFormat::UnsignedByte,
+            is_gen_mipmap,
+            None,
+        )
+    }
+
+    fn texture_get_size(&self, texture: &HalTexture) -> (u32, u32) {
+        get_ref(
+            &self.0.texture_slab,
+            texture.item.index,
+            texture.item.use_count,
+        )
+        .map(|tex| tex.get_size())
+        .unwrap()
+    }
+
+    fn texture_get_render_format(&self, texture: &HalTexture) -> PixelFormat {
+        get_ref(
+            &self.0.texture_slab,
+            texture.item.index,
+            texture.item.use_count,
+        )
+        .map(|tex| tex.get_render_format())
+        .unwrap()
+    }
+
+    fn texture_is_gen_mipmap(&self, texture: &HalTexture) -> bool {
+        get_ref(
+            &self.0.texture_slab,
+            texture.item.index,
+            texture.item.use_count,
+        )
+        .is_some_and(|tex| tex.is_gen_mipmap())
+    }
+
+    fn texture_resize(&self, texture: &HalTexture, mipmap_level: u32, width: u32, height: u32) {
+        let slab = convert_to_mut(&self.0.texture_slab);
+        if let Some(t) = get_mut_ref(slab, texture.item.index, texture.item.use_count) {
+            t.resize(mipmap_level, width, height);
+        }
+    }
+
+    fn texture_update(&self, texture: &HalTexture, mipmap_level: u32, data: &TextureData) {
+        let slab = convert_to_mut(&self.0.texture_slab);
+        if let Some(t) = get_mut_ref(slab, texture.item.index, texture.item.use_count) {
+            t.update(mipmap_level, data);
+        }
+    }
+
+    fn texture_copy(
+        &self,
+        dst: &HalTexture,
+        src: &HalTexture,
+        _src_mipmap_level: u32,
+        src_x: u32,
+        src_y: u32,
+        dst_x: u32,
+        dst_y: u32,
+        width: u32,
+        height: u32,
+    ) {
+        let pixels = match get_ref(&self.0.texture_slab, src.item.index, src.item.use_count) {
+            Some(src) => src.read_region(src_x, src_y, width, height),
+            None => return,
+        };
+        let slab = convert_to_mut(&self.0.texture_slab);
+        if let Some(dst) = get_mut_ref(slab, dst.item.index, dst.item.use_count) {
+            dst.write_region(dst_x, dst_y, width, height, &pixels);
+        }
+    }
+
+    fn texture_extend(&self, texture: &HalTexture, width: u32, height: u32) -> bool {
+        let slab = convert_to_mut(&self.0.texture_slab);
+        if let Some(old_tex) = get_mut_ref(slab, texture.item.index, texture.item.use_count) {
+            old_tex.extend(width, height);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn texture_pixel_storei(&self, texture: &HalTexture, value: PixelStore) {
+        let slab = convert_to_mut(&self.0.texture_slab);
+        if let Some(t) = get_mut_ref(slab, texture.item.index, texture.item.use_count) {
+            t.pixel_storei(value);
+        }
+    }
+
+    // ==================== HalSampler
+
+    fn sampler_create(&self, desc: SamplerDesc) -> Result<HalSampler, String> {
+        let slab = convert_to_mut(&self.0.sampler_slab);
+        let (index, use_count) = create_new_slot(slab, desc);
+
+        let context_impl = self.0.clone();
+        Ok(HalSampler {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |index: u32, use_count: u32| {
+                context_impl.sampler_destroy(index, use_count);
+            }),
+        })
+    }
+
+    fn sampler_get_desc(&self, sampler: &HalSampler) -> &SamplerDesc {
+        get_ref(
+            &self.0.sampler_slab,
+            sampler.item.index,
+            sampler.item.use_count,
+        )
+        .unwrap()
+    }
+
+    // ==================== HalRasterState
+
+    fn rs_create(&self, desc: RasterStateDesc) -> Result<HalRasterState, String> {
+        let slab = convert_to_mut(&self.0.rs_slab);
+        let (index, use_count) = create_new_slot(slab, desc);
+
+        let context_impl = self.0.clone();
+        Ok(HalRasterState {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |index: u32, use_count: u32| {
+                context_impl.rs_destroy(index, use_count);
+            }),
+        })
+    }
+
+    fn rs_get_desc(&self, state: &HalRasterState) -> &RasterStateDesc {
+        get_ref(&self.0.rs_slab, state.item.index, state.item.use_count).unwrap()
+    }
+
+    // ==================== HalDepthState
+
+    fn ds_create(&self, desc: DepthStateDesc) -> Result<HalDepthState, String> {
+        let slab = convert_to_mut(&self.0.ds_slab);
+        let (index, use_count) = create_new_slot(slab, desc);
+
+        let context_impl = self.0.clone();
+        Ok(HalDepthState {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |index: u32, use_count: u32| {
+                context_impl.ds_destroy(index, use_count);
+            }),
+        })
+    }
+
+    fn ds_get_desc(&self, state: &HalDepthState) -> &DepthStateDesc {
+        get_ref(&self.0.ds_slab, state.item.index, state.item.use_count).unwrap()
+    }
+
+    // ==================== HalStencilState
+
+    fn ss_create(&self, desc: StencilStateDesc) -> Result<HalStencilState, String> {
+        let slab = convert_to_mut(&self.0.ss_slab);
+        let (index, use_count) = create_new_slot(slab, desc);
+
+        let context_impl = self.0.clone();
+        Ok(HalStencilState {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |index: u32, use_count: u32| {
+                context_impl.ss_destroy(index, use_count);
+            }),
+        })
+    }
+
+    fn ss_get_desc(&self, state: &HalStencilState) -> &StencilStateDesc {
+        get_ref(&self.0.ss_slab, state.item.index, state.item.use_count).unwrap()
+    }
+
+    // ==================== HalBlendState
+
+    fn bs_create(&self, desc: BlendStateDesc) -> Result<HalBlendState, String> {
+        let slab = convert_to_mut(&self.0.bs_slab);
+        let (index, use_count) = create_new_slot(slab, desc);
+
+        let context_impl = self.0.clone();
+        Ok(HalBlendState {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |index: u32, use_count: u32| {
+                context_impl.bs_destroy(index, use_count);
+            }),
+        })
+    }
+
+    fn bs_get_desc(&self, state: &HalBlendState) -> &BlendStateDesc {
+        get_ref(&self.0.bs_slab, state.item.index, state.item.use_count).unwrap()
+    }
+
+    // ==================== 上下文相关
+
+    fn render_get_caps(&self) -> &Capabilities {
+        &self.0.caps
+    }
+
+    /**
+     * 软件实现不编译glsl，着色器由register_shader注册
+     */
+    fn render_set_shader_code(&self, _name: &str, _code: &str) {}
+
+    fn restore_state(&self) {
+        let context = convert_to_mut(self.0.as_ref());
+        let state = &mut context.state;
+        state.program = (0, 0);
+        state.bs = BlendStateDesc::default();
+        state.ds = DepthStateDesc::default();
+        state.rs = RasterStateDesc::default();
+        state.ss = StencilStateDesc::default();
+    }
+
+    fn restore_active_uniform(&self, _program: &HalProgram, _index: usize) {
+        // 软件实现没有uniform缓存，每次draw都会取最新的值
+    }
+
+    fn render_begin(&self, render_target: Option<&HalRenderTarget>, data: &RenderBeginDesc) {
+        self.restore_state();
+
+        let context = convert_to_mut(self.0.as_ref());
+        context.stat.reset_frame();
+
+        let render_target = if render_target.is_none() {
+            &self.1
+        } else {
+            render_target.unwrap()
+        };
+        let target = (render_target.item.index, render_target.item.use_count);
+        if context.state.target != target {
+            context.state.target = target;
+            context.stat.add_rt_change();
+        }
+        context.state.viewport = data.viewport;
+        context.state.scissor = data.scissor;
+
+        let color = data
+            .clear_color
+            .as_ref()
+            .map(|c| [*c.0, *c.1, *c.2, *c.3]);
+        let depth = data.clear_depth.as_ref().map(|d| **d);
+        if let Some(mut t) = context.get_target() {
+            raster::clear(&mut t, &data.scissor, color, depth, data.clear_stencil);
+        }
+    }
+
+    fn render_end(&self) {}
+
+    fn render_get_stat(&self) -> &RenderStat {
+        let mut r = 0;
+        r += self.0.buffer_slab.mem_size();
+        r += self.0.geometry_slab.mem_size();
+        r += self.0.texture_slab.mem_size();
+        r += self.0.sampler_slab.mem_size();
+        r += self.0.rt_slab.mem_size();
+        r += self.0.rb_slab.mem_size();
+        r += self.0.bs_slab.mem_size();
+        r += self.0.ds_slab.mem_size();
+        r += self.0.rs_slab.mem_size();
+        r += self.0.ss_slab.mem_size();
+        r += self.0.program_slab.mem_size();
+
+        let context = convert_to_mut(self.0.as_ref());
+        context.stat.slab_mem_size = r;
+
+        &self.0.stat
+    }
+
+    fn render_set_program(&self, program: &HalProgram) {
+        let context = convert_to_mut(self.0.as_ref());
+        let p = (program.item.index, program.item.use_count);
+        if context.state.program != p {
+            context.state.program = p;
+            context.stat.add_program_change();
+        }
+    }
+
+    fn render_set_state(
+        &self,
+        bs: &HalBlendState,
+        ds: &HalDepthState,
+        rs: &HalRasterState,
+        ss: &HalStencilState,
+    ) {
+        let context = convert_to_mut(self.0.as_ref());
+        let bsdesc = get_ref(&context.bs_slab, bs.item.index, bs.item.use_count)
+            .expect("bs param not found");
+        let dsdesc = get_ref(&context.ds_slab, ds.item.index, ds.item.use_count)
+            .expect("ds param not found");
+        let ssdesc = get_ref(&context.ss_slab, ss.item.index, ss.item.use_count)
+            .expect("ss param not found");
+        let rsdesc = get_ref(&context.rs_slab, rs.item.index, rs.item.use_count)
+            .expect("rs param not found");
+        context.state.bs = bsdesc.clone();
+        context.state.ds = dsdesc.clone();
+        context.state.ss = ssdesc.clone();
+        context.state.rs = rsdesc.clone();
+    }
+
+    fn render_draw(&self, geometry: &HalGeometry, pp: &Share<dyn ProgramParamter>) {
+        let context = convert_to_mut(self.0.as_ref());
+
+        let program = context.state.program;
+        let pimpl = get_ref(&context.program_slab, program.0, program.1)
+            .expect("curr program not found");
+        let gimpl = get_ref(
+            &context.geometry_slab,
+            geometry.item.index,
+            geometry.item.use_count,
+        )
+        .expect("geometry not found");
+
+        let indices: Vec<usize> = match &gimpl.indices {
+            Some(indices) => {
+                match get_ref(&context.buffer_slab, indices.handle.0, indices.handle.1) {
+                    Some(buffer) => (0..indices.count)
+                        .map(|i| buffer.get_short(indices.offset / 2 + i) as usize)
+                        .collect(),
+                    None => return,
+                }
+            }
+            None => (0..gimpl.vertex_count as usize).collect(),
+        };
+        let vertex_count = match indices.iter().max() {
+            Some(max) => max + 1,
+            None => return,
+        };
+
+        let uniforms = Uniforms::new(pp.as_ref(), &context.texture_slab, &context.sampler_slab);
+
+        // 顶点着色，只处理用到的顶点
+        let mut attributes = Vec::with_capacity(gimpl.attributes.len());
+        for (name, attribute) in gimpl.attributes.iter() {
+            if let Some(buffer) =
+                get_ref(&context.buffer_slab, attribute.handle.0, attribute.handle.1)
+            {
+                attributes.push((name, attribute, buffer));
+            }
+        }
+        let mut values: Vec<(AttributeName, [f32; 4])> = attributes
+            .iter()
+            .map(|(name, _, _)| ((*name).clone(), [0.0, 0.0, 0.0, 1.0]))
+            .collect();
+        let mut vertices: Vec<Option<ShadedVertex>> = (0..vertex_count).map(|_| None).collect();
+        for i in indices.iter() {
+            if vertices[*i].is_some() {
+                continue;
+            }
+            for (j, (_, attribute, buffer)) in attributes.iter().enumerate() {
+                let stride = if attribute.stride == 0 {
+                    attribute.item_count
+                } else {
+                    attribute.stride / 4
+                };
+                let start = attribute.offset / 4 + i * stride;
+                let end = attribute.offset / 4 + attribute.count;
+                let mut value = [0.0, 0.0, 0.0, 1.0];
+                for k in 0..attribute.item_count {
+                    if start + k < end {
+                        value[k] = buffer.get_float(start + k);
+                    }
+                }
+                values[j].1 = value;
+            }
+            let mut varyings = vec![0.0; pimpl.shader.varying_count()];
+            let position = pimpl.shader.vertex(
+                &VertexInput {
+                    attributes: &values,
+                },
+                &uniforms,
+                &mut varyings,
+            );
+            vertices[*i] = Some(ShadedVertex { position, varyings });
+        }
+
+        let state = DrawState {
+            viewport: context.state.viewport,
+            scissor: context.state.scissor,
+            bs: &context.state.bs,
+            ds: &context.state.ds,
+            rs: &context.state.rs,
+            ss: &context.state.ss,
+        };
+        if let Some(mut target) = context.get_target() {
+            raster::draw_triangles(
+                &mut target,
+                &state,
+                pimpl.shader.as_ref(),
+                &uniforms,
+                &vertices,
+                &indices,
+            );
+        }
+
+        context.stat.add_draw_call();
+    }
+}
+
+impl NullHalContextImpl {
+    /**
+     * 当前渲染目标的颜色和深度模板缓冲区
+     */
+    fn get_target(&self) -> Option<Target<'_>> {
+        let rt = get_ref(&self.rt_slab, self.state.target.0, self.state.target.1)?;
+        let color = rt.color.as_ref()?;
+        let color = get_mut_ref(
+            convert_to_mut(&self.texture_slab),
+            color.item.index,
+            color.item.use_count,
+        )?;
+        let depth = match &rt.depth {
+            Some(rb) => get_mut_ref(
+                convert_to_mut(&self.rb_slab),
+                rb.item.index,
+                rb.item.use_count,
+            ),
+            None => None,
+        };
+        Some(Target { color, depth })
+    }
+
+    fn buffer_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.buffer_slab, index, use_count).is_some() {
+            let context = convert_to_mut(self);
+            context.buffer_slab.remove(index as usize);
+            context.stat.buffer_count -= 1;
+        }
+    }
+
+    fn geometry_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.geometry_slab, index, use_count).is_some() {
+            let context = convert_to_mut(self);
+            context.geometry_slab.remove(index as usize);
+            context.stat.geometry_count -= 1;
+        }
+    }
+
+    fn program_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.program_slab, index, use_count).is_some() {
+            let context = convert_to_mut(self);
+            context.program_slab.remove(index as usize);
+            context.stat.program_count -= 1;
+        }
+    }
+
+    fn rt_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.rt_slab, index, use_count).is_some() {
+            let context = convert_to_mut(self);
+            let rimpl = context.rt_slab.remove(index as usize).0;
+            if rimpl.is_default {
+                // 默认渲染目标的纹理和缓冲区没有计入统计
+                if let Some(t) = &rimpl.color {
+                    context.texture_slab.remove(t.item.index as usize);
+                }
+                if let Some(rb) = &rimpl.depth {
+                    context.rb_slab.remove(rb.item.index as usize);
+                }
+                return;
+            }
+
+            context.stat.rt_count -= 1;
+            if let Some(t) = &rimpl.color {
+                if rimpl.is_tex_destroy {
+                    self.texture_destroy(t.item.index, t.item.use_count);
+                }
+            }
+            if let Some(rb) = &rimpl.depth {
+                self.rb_destroy(rb.item.index, rb.item.use_count);
+            }
+        }
+    }
+
+    fn rb_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.rb_slab, index, use_count).is_some() {
+            let slab = convert_to_mut(&self.rb_slab);
+            slab.remove(index as usize);
+        }
+    }
+
+    fn texture_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.texture_slab, index, use_count).is_some() {
+            let context = convert_to_mut(self);
+            context.texture_slab.remove(index as usize);
+            context.stat.texture_count -= 1;
+        }
+    }
+
+    fn sampler_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.sampler_slab, index, use_count).is_some() {
+            let slab = convert_to_mut(&self.sampler_slab);
+            slab.remove(index as usize);
+        }
+    }
+
+    fn rs_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.rs_slab, index, use_count).is_some() {
+            let slab = convert_to_mut(&self.rs_slab);
+            slab.remove(index as usize);
+        }
+    }
+
+    fn ds_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.ds_slab, index, use_count).is_some() {
+            let slab = convert_to_mut(&self.ds_slab);
+            slab.remove(index as usize);
+        }
+    }
+
+    fn ss_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.ss_slab, index, use_count).is_some() {
+            let slab = convert_to_mut(&self.ss_slab);
+            slab.remove(index as usize);
+        }
+    }
+
+    fn bs_destroy(&self, index: u32, use_count: u32) {
+        if get_ref(&self.bs_slab, index, use_count).is_some() {
+            let slab = convert_to_mut(&self.bs_slab);
+            slab.remove(index as usize);
+        }
+    }
+}
+
+impl NullHalContext {
+    pub fn new(width: u32, height: u32) -> NullHalContext {
+        Self::create(width, height, true)
+    }
+
+    /**
+     * 默认渲染目标没有模板缓冲区的上下文，相当于创建webgl上下文时没有指定stencil: true
+     */
+    pub fn new_without_stencil(width: u32, height: u32) -> NullHalContext {
+        Self::create(width, height, false)
+    }
+
+    fn create(width: u32, height: u32, stencil: bool) -> NullHalContext {
+        let buffer_slab = Slab::new();
+        let geometry_slab = Slab::new();
+        let mut texture_slab = Slab::new();
+        let sampler_slab = Slab::new();
+        let mut rt_slab = Slab::new();
+        let mut rb_slab = Slab::new();
+        let bs_slab = Slab::new();
+        let ds_slab = Slab::new();
+        let rs_slab = Slab::new();
+        let ss_slab = Slab::new();
+        let program_slab = Slab::new();
+
+        // 默认渲染目标：相当于带深度和模板的canvas
+        let texture = NullTextureImpl::new_2d(
+            0,
+            width,
+            height,
+            PixelFormat::RGBA,
+            DataFormat::UnsignedByte,
+            false,
+            None,
+        )
+        .unwrap();
+        let (index, use_count) = create_new_slot(&mut texture_slab, texture);
+        let texture_wrap = HalTexture {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |_index: u32, _use_count: u32| {}),
+        };
+        let rb = if stencil {
+            NullRenderBufferImpl::new(width, height, PixelFormat::DEPTH16).unwrap()
+        } else {
+            NullRenderBufferImpl::without_stencil(width, height, PixelFormat::DEPTH16)
+        };
+        let (index, use_count) = create_new_slot(&mut rb_slab, rb);
+        let rb_wrap = HalRenderBuffer {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |_index: u32, _use_count: u32| {}),
+        };
+        let rt = NullRenderTargetImpl::new_default(width, height, texture_wrap, rb_wrap);
+        let (index, use_count) = create_new_slot(&mut rt_slab, rt);
+
+        let mut caps = Capabilities::new();
+        caps.max_textures_image_units = 16;
+        caps.max_vertex_texture_image_units = 16;
+        caps.max_combined_textures_image_units = 32;
+        caps.max_texture_size = 4096;
+        caps.max_render_texture_size = 4096;
+        caps.max_vertex_attribs = 16;
+        caps.max_varying_vectors = 16;
+        caps.max_vertex_uniform_vectors = 1024;
+        caps.max_fragment_uniform_vectors = 1024;
+        caps.standard_derivatives = true;
+        caps.texture_float = true;
+        caps.texture_float_linear_filtering = true;
+        caps.color_buffer_float = true;
+        caps.stencil_bits = if stencil { 8 } else { 0 };
+
+        let mut shader_creators: XHashMap<(Atom, Atom), ShaderCreator> = XHashMap::default();
+        shader_creators.insert(
+            (Atom::from("color_vs"), Atom::from("color_fs")),
+            ColorShader::create,
+        );
+        shader_creators.insert(
+            (Atom::from("image_vs"), Atom::from("image_fs")),
+            ImageShader::create,
+        );
+        shader_creators.insert(
+            (Atom::from("text_vs"), Atom::from("text_fs")),
+            TextShader::create,
+        );
+        shader_creators.insert(
+            (Atom::from("canvas_text_vs"), Atom::from("canvas_text_fs")),
+            CanvasTextShader::create,
+        );
+        shader_creators.insert(
+            (Atom::from("clip_vs"), Atom::from("clip_fs")),
+            ClipShader::create,
+        );
+
+        let context_impl = Share::new(NullHalContextImpl {
+            stat: RenderStat::new(),
+            caps,
+            shader_creators,
+            state: NullState {
+                target: (index, use_count),
+                viewport: (0, 0, width as i32, height as i32),
+                scissor: (0, 0, width as i32, height as i32),
+                program: (0, 0),
+                bs: BlendStateDesc::default(),
+                ds: DepthStateDesc::default(),
+                rs: RasterStateDesc::default(),
+                ss: StencilStateDesc::default(),
+            },
+            buffer_slab,
+            geometry_slab,
+            texture_slab,
+            sampler_slab,
+            rt_slab,
+            rb_slab,
+            bs_slab,
+            ds_slab,
+            rs_slab,
+            ss_slab,
+            program_slab,
+        });
+
+        let context_clone = context_impl.clone();
+        let default_rt = HalRenderTarget {
+            item: HalItem { index, use_count },
+            destroy_func: Share::new(move |index: u32, use_count: u32| {
+                context_clone.rt_destroy(index, use_count)
+            }),
+        };
+
+        NullHalContext(context_impl, default_rt)
+    }
+
+    /**
+     * 注册软件着色器，vs_name，fs_name和program_create_with_vs_fs的参数对应
+     */
+    pub fn register_shader(&self, vs_name: &str, fs_name: &str, creator: ShaderCreator) {
+        let context = convert_to_mut(self.0.as_ref());
+        context
+            .shader_creators
+            .insert((Atom::from(vs_name), Atom::from(fs_name)), creator);
+    }
+
+    /**
+     * 读回渲染目标的像素
+     * render_target传None，读默认渲染目标
+     * 返回 (宽, 高, rgba数据)，行从上到下排列（和png一致，和gl的readPixels相反）
+     */
+    pub fn rt_read_pixels(&self, render_target: Option<&HalRenderTarget>) -> (u32, u32, Vec<u8>) {
+        let render_target = render_target.unwrap_or(&self.1);
+        let rt = get_ref(
+            &self.0.rt_slab,
+            render_target.item.index,
+            render_target.item.use_count,
+        )
+        .expect("rt param not found");
+        let color = rt.color.as_ref().expect("rt color not found");
+        self.read_pixels(color)
+    }
+
+    /**
+     * 读回纹理的像素，返回格式同rt_read_pixels
+     */
+    pub fn texture_read_pixels(&self, texture: &HalTexture) -> (u32, u32, Vec<u8>) {
+        self.read_pixels(texture)
+    }
+
+    fn read_pixels(&self, texture: &HalTexture) -> (u32, u32, Vec<u8>) {
+        let t = get_ref(
+            &self.0.texture_slab,
+            texture.item.index,
+            texture.item.use_count,
+        )
+        .expect("texture not found");
+        let (w, h) = t.get_size();
+        let mut data = Vec::with_capacity((w * h * 4) as usize);
+        for y in (0..h).rev() {
+            for x in 0..w {
+                let p = t.read_pixel(x, y);
+                for c in p.iter() {
+                    data.push((c.max(0.0).min(1.0) * 255.0).round() as u8);
+                }
+            }
+        }
+        (w, h, data)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod test {
+    use hal_core::*;
+    use share::Share;
+
+    use super::NullHalContext;
+
+    uniform_buffer! {
+        struct TestColorUbo {
+            uColor: UniformValue,
+        }
+    }
+
+    uniform_buffer! {
+        struct TestProjectMatrixUbo {
+            projectMatrix: UniformValue,
+        }
+    }
+
+    program_paramter! {
+        struct TestColorParamter {
+            uColor: TestColorUbo,
+            projectMatrix: TestProjectMatrixUbo,
+            alpha: UniformValue,
+            blur: UniformValue,
+        }
+    }
+
+    #[test]
+    fn test_draw_color() {
+        let gl = NullHalContext::new(4, 4);
+
+        let program = gl
+            .program_create_with_vs_fs(
+                0,
+                0,
+                "color_vs",
+                &[],
+                "color_fs",
+                &[Some("UCOLOR")],
+                &UniformLayout {
+                    ubos: &[],
+                    uniforms: &[],
+                    textures: &[],
+                    single_uniforms: &[],
+                },
+            )
+            .unwrap();
+
+        // 左上角为原点的正交投影，和gui一致
+        let project = vec![
+            0.5, 0.0, 0.0, 0.0, 0.0, -0.5, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 1.0, 0.0, 1.0,
+        ];
+        let paramter = TestColorParamter::default();
+        paramter.set_value(
+            "uColor",
+            Share::new(TestColorUbo::new(UniformValue::Float4(0.0, 1.0, 0.0, 1.0))),
+        );
+        paramter.set_value(
+            "projectMatrix",
+            Share::new(TestProjectMatrixUbo::new(UniformValue::MatrixV4(project))),
+        );
+        paramter.set_single_uniform("alpha", UniformValue::Float1(1.0));
+        paramter.set_single_uniform("blur", UniformValue::Float1(1.0));
+        let paramter: Share<dyn ProgramParamter> = Share::new(paramter);
+
+        // 覆盖左半边的矩形
+        let position: &[f32] = &[0.0, 0.0, 0.0, 4.0, 2.0, 4.0, 2.0, 0.0];
+        let indices: &[u16] = &[0, 1, 2, 0, 2, 3];
+        let position = gl
+            .buffer_create(
+                BufferType::Attribute,
+                position.len(),
+                Some(BufferData::Float(position)),
+                false,
+            )
+            .unwrap();
+        let indices = gl
+            .buffer_create(
+                BufferType::Indices,
+                indices.len(),
+                Some(BufferData::Short(indices)),
+                false,
+            )
+            .unwrap();
+        let geometry = gl.geometry_create().unwrap();
+        gl.geometry_set_vertex_count(&geometry, 4);
+        gl.geometry_set_attribute(&geometry, &AttributeName::Position, &position, 2)
+            .unwrap();
+        gl.geometry_set_indices_short(&geometry, &indices).unwrap();
+
+        let mut bs = BlendStateDesc::new();
+        bs.set_rgb_factor(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha);
+        let bs = gl.bs_create(bs).unwrap();
+        let ds = gl.ds_create(DepthStateDesc::new()).unwrap();
+        let rs = gl.rs_create(RasterStateDesc::new()).unwrap();
+        let ss = gl.ss_create(StencilStateDesc::new()).unwrap();
+
+        let mut desc = RenderBeginDesc::new(0, 0, 4, 4);
+        desc.set_clear_color(true, 1.0, 0.0, 0.0, 1.0);
+        gl.render_begin(None, &desc);
+        gl.render_set_program(&program);
+        gl.render_set_state(&bs, &ds, &rs, &ss);
+        gl.render_draw(&geometry, &paramter);
+        gl.render_end();
+
+        let (w, h, pixels) = gl.rt_read_pixels(None);
+        assert_eq!((w, h), (4, 4));
+        for y in 0..4 {
+            for x in 0..4 {
+                let i = (y * 4 + x) * 4;
+                let expect = if x < 2 { [0, 255, 0, 255] } else { [255, 0, 0, 255] };
+                assert_eq!(&pixels[i..i + 4], &expect[..]);
+            }
+        }
+        assert_eq!(gl.render_get_stat().draw_call_count, 1);
+    }
+}
@@ -0,0 +1,110 @@
+use buffer::NullBufferImpl;
+use hal_core::{AttributeName, HalBuffer};
+
+pub struct Attribute {
+    pub offset: usize,      // 字节偏移，和webgl的vertexAttribPointer一致
+    pub count: usize,       // 元素的个数
+    pub item_count: usize,  // 每个元素的个数
+    pub stride: usize,      // 字节跨度，0代表紧密排列
+    pub handle: (u32, u32), // HalBuffer的index, use_count
+}
+
+pub struct Indices {
+    pub offset: usize, // 字节偏移，和webgl的drawElements一致
+    pub count: usize,
+    pub handle: (u32, u32), // HalBuffer的index, use_count
+}
+
+pub struct NullGeometryImpl {
+    pub vertex_count: u32,
+    pub attributes: Vec<(AttributeName, Attribute)>,
+    pub indices: Option<Indices>,
+}
+
+impl NullGeometryImpl {
+    pub fn new() -> Result<NullGeometryImpl, String> {
+        Ok(Self {
+            vertex_count: 0,
+            attributes: Vec::new(),
+            indices: None,
+        })
+    }
+
+    pub fn get_vertex_count(&self) -> u32 {
+        self.vertex_count
+    }
+
+    pub fn set_vertex_count(&mut self, count: u32) {
+        self.vertex_count = count;
+    }
+
+    pub fn set_attribute(
+        &mut self,
+        name: &AttributeName,
+        buffer: &NullBufferImpl,
+        wrap: &HalBuffer,
+        item_count: usize,
+    ) -> Result<(), String> {
+        let count = buffer.count;
+        self.set_attribute_with_offset(name, buffer, wrap, item_count, 0, count, 0)
+    }
+
+    pub fn set_attribute_with_offset(
+        &mut self,
+        name: &AttributeName,
+        _buffer: &NullBufferImpl,
+        wrap: &HalBuffer,
+        item_count: usize,
+        offset: usize,
+        count: usize,
+        stride: usize,
+    ) -> Result<(), String> {
+        if item_count == 0 || item_count > 4 {
+            return Err(format!("set_attribute failed, item_count = {}", item_count));
+        }
+        let attribute = Attribute {
+            offset,
+            count,
+            item_count,
+            stride,
+            handle: (wrap.item.index, wrap.item.use_count),
+        };
+        match self.attributes.iter_mut().find(|v| &v.0 == name) {
+            Some(v) => v.1 = attribute,
+            None => self.attributes.push((name.clone(), attribute)),
+        }
+        Ok(())
+    }
+
+    pub fn remove_attribute(&mut self, name: &AttributeName) {
+        self.attributes.retain(|v| &v.0 != name);
+    }
+
+    pub fn set_indices_short(
+        &mut self,
+        buffer: &NullBufferImpl,
+        wrap: &HalBuffer,
+    ) -> Result<(), String> {
+        let count = buffer.count;
+        self.set_indices_short_with_offset(buffer, wrap, 0, count)
+    }
+
+    pub fn set_indices_short_with_offset(
+        &mut self,
+        _buffer: &NullBufferImpl,
+        wrap: &HalBuffer,
+        offset: usize,
+        count: usize,
+    ) -> Result<(), String> {
+        self.indices = Some(Indices {
+            offset,
+            count,
+            handle: (wrap.item.index, wrap.item.use_count),
+        });
+        Ok(())
+    }
+
+    pub fn remove_indices(&mut self) {
+        self.indices = None;
+    }
+}
@@ -0,0 +1,38 @@
+/**
+ * 抽象硬件层HAL的软件实现
+ * 不依赖任何图形api，所有的光栅化、混合、深度、模板都在CPU上完成，
+ * 用于在没有gl环境的地方（如：cargo test）运行gui的渲染流程，并能读回渲染目标的像素
+ */
+
+extern crate atom;
+extern crate hash;
+extern crate ordered_float;
+extern crate share;
+extern crate slab;
+extern crate hal_core;
+
+#[cfg(test)]
+#[macro_use]
+extern crate hal_derive;
+
+mod context;
+
+mod buffer;
+mod geometry;
+mod program;
+mod render_target;
+mod texture;
+
+mod raster;
+mod shader;
+mod util;
+
+pub use context::NullHalContext;
+pub use shader::{FragmentInput, ShaderCreator, ShaderDefines, SoftShader, Uniforms, VertexInput};
+
+/**
+ * width, height: 默认渲染目标的宽高
+ */
+pub fn create_hal_null(width: u32, height: u32) -> NullHalContext {
+    NullHalContext::new(width, height)
+}
@@ -0,0 +1,29 @@
+use atom::Atom;
+use hash::XHashMap;
+
+use shader::{ShaderCreator, ShaderDefines, SoftShader};
+
+pub struct NullProgramImpl {
+    pub shader: Box<dyn SoftShader>,
+}
+
+impl NullProgramImpl {
+    pub fn new_with_vs_fs(
+        creators: &XHashMap<(Atom, Atom), ShaderCreator>,
+        vs_name: &Atom,
+        vs_defines: &[Option<&str>],
+        fs_name: &Atom,
+        fs_defines: &[Option<&str>],
+    ) -> Result<Self, String> {
+        match creators.get(&(vs_name.clone(), fs_name.clone())) {
+            Some(creator) => Ok(NullProgramImpl {
+                shader: creator(&ShaderDefines::new(vs_defines, fs_defines)),
+            }),
+            None => Err(format!(
+                "soft shader isn't found, vs: {:?}, fs: {:?}",
+                vs_name.as_ref() as &str,
+                fs_name.as_ref() as &str
+            )),
+        }
+    }
+}
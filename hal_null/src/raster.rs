@@ -0,0 +1,418 @@
+/**
+ * 三角形光栅化，以及逐片段的 裁剪（scissor）、模板、深度、混合
+ * 坐标约定和gl一致：窗口坐标左下角为原点，像素中心在0.5处
+ */
+use hal_core::*;
+
+use render_target::NullRenderBufferImpl;
+use shader::{FragmentInput, SoftShader, Uniforms};
+use texture::NullTextureImpl;
+
+/**
+ * 经过顶点着色的顶点
+ */
+pub struct ShadedVertex {
+    pub position: [f32; 4],
+    pub varyings: Vec<f32>,
+}
+
+/**
+ * 渲染目标
+ */
+pub struct Target<'a> {
+    pub color: &'a mut NullTextureImpl,
+    pub depth: Option<&'a mut NullRenderBufferImpl>,
+}
+
+/**
+ * 一次draw用到的状态
+ */
+pub struct DrawState<'a> {
+    pub viewport: (i32, i32, i32, i32),
+    pub scissor: (i32, i32, i32, i32),
+    pub bs: &'a BlendStateDesc,
+    pub ds: &'a DepthStateDesc,
+    pub rs: &'a RasterStateDesc,
+    pub ss: &'a StencilStateDesc,
+}
+
+/**
+ * 清空渲染目标，和gl一样，只清空scissor范围内的像素
+ */
+pub fn clear(
+    target: &mut Target,
+    scissor: &(i32, i32, i32, i32),
+    color: Option<[f32; 4]>,
+    depth: Option<f32>,
+    stencil: Option<u8>,
+) {
+    let (w, h) = target.color.get_size();
+    let (x0, y0, x1, y1) = intersect_rect(scissor, w, h);
+    for y in y0..y1 {
+        for x in x0..x1 {
+            if let Some(c) = &color {
+                target.color.write_pixel(x as u32, y as u32, c);
+            }
+            if let Some(rb) = &mut target.depth {
+                let (rw, rh) = rb.get_size();
+                if x as u32 >= rw || y as u32 >= rh {
+                    continue;
+                }
+                let i = (y as u32 * rw + x as u32) as usize;
+                if let Some(d) = depth {
+                    rb.depth[i] = d;
+                }
+                if let Some(s) = stencil {
+                    if rb.has_stencil() {
+                        rb.stencil[i] = s;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/**
+ * 光栅化三角形列表，indices每3个组成一个三角形
+ */
+pub fn draw_triangles(
+    target: &mut Target,
+    state: &DrawState,
+    shader: &dyn SoftShader,
+    uniforms: &Uniforms,
+    vertices: &[Option<ShadedVertex>],
+    indices: &[usize],
+) {
+    let varying_count = shader.varying_count();
+    let mut varyings = vec![0.0; varying_count];
+    let mut varyings_x = vec![0.0; varying_count];
+    let mut varyings_y = vec![0.0; varying_count];
+    let mut ddx = vec![0.0; varying_count];
+    let mut ddy = vec![0.0; varying_count];
+
+    let (tw, th) = target.color.get_size();
+    let (sx0, sy0, sx1, sy1) = intersect_rect(&state.scissor, tw, th);
+    let vp = state.viewport;
+
+    for tri in indices.chunks(3) {
+        if tri.len() < 3 {
+            break;
+        }
+        let v = match (
+            vertices.get(tri[0]).and_then(|v| v.as_ref()),
+            vertices.get(tri[1]).and_then(|v| v.as_ref()),
+            vertices.get(tri[2]).and_then(|v| v.as_ref()),
+        ) {
+            (Some(a), Some(b), Some(c)) => [a, b, c],
+            _ => continue,
+        };
+
+        // 软件实现不做近平面裁剪，w<=0的三角形直接丢弃
+        if v.iter().any(|v| v.position[3] <= 0.0) {
+            continue;
+        }
+
+        // 视口变换
+        let mut win = [[0.0f32; 3]; 3];
+        let mut inv_w = [0.0f32; 3];
+        for i in 0..3 {
+            let p = &v[i].position;
+            inv_w[i] = 1.0 / p[3];
+            let ndc = [p[0] * inv_w[i], p[1] * inv_w[i], p[2] * inv_w[i]];
+            win[i] = [
+                vp.0 as f32 + (ndc[0] + 1.0) * 0.5 * vp.2 as f32,
+                vp.1 as f32 + (ndc[1] + 1.0) * 0.5 * vp.3 as f32,
+                ndc[2],
+            ];
+        }
+
+        let area = edge(&win[0], &win[1], &win[2][0], &win[2][1]);
+        if area == 0.0 || !area.is_finite() {
+            continue;
+        }
+
+        // 背面剔除
+        let is_ccw = area > 0.0;
+        let is_front = is_ccw == state.rs.is_front_face_ccw;
+        match state.rs.cull_mode {
+            Some(CullMode::Back) if !is_front => continue,
+            Some(CullMode::Front) if is_front => continue,
+            _ => (),
+        }
+        // 统一成逆时针
+        let (win, inv_w, v, area) = if is_ccw {
+            (win, inv_w, v, area)
+        } else {
+            (
+                [win[0], win[2], win[1]],
+                [inv_w[0], inv_w[2], inv_w[1]],
+                [v[0], v[2], v[1]],
+                -area,
+            )
+        };
+
+        // 包围盒，和视口、scissor、渲染目标求交
+        let min_x = win.iter().fold(f32::MAX, |r, p| r.min(p[0])).floor() as i32;
+        let max_x = win.iter().fold(f32::MIN, |r, p| r.max(p[0])).ceil() as i32;
+        let min_y = win.iter().fold(f32::MAX, |r, p| r.min(p[1])).floor() as i32;
+        let max_y = win.iter().fold(f32::MIN, |r, p| r.max(p[1])).ceil() as i32;
+        let x0 = min_x.max(sx0).max(vp.0);
+        let x1 = max_x.min(sx1).min(vp.0 + vp.2);
+        let y0 = min_y.max(sy0).max(vp.1);
+        let y1 = max_y.min(sy1).min(vp.1 + vp.3);
+
+        let top_left = [
+            is_top_left(&win[1], &win[2]),
+            is_top_left(&win[2], &win[0]),
+            is_top_left(&win[0], &win[1]),
+        ];
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let (fx, fy) = (px as f32 + 0.5, py as f32 + 0.5);
+                let b = barycentric(&win, area, fx, fy);
+                let inside = (0..3).all(|i| b[i] > 0.0 || (b[i] == 0.0 && top_left[i]));
+                if !inside {
+                    continue;
+                }
+
+                // 深度裁剪
+                let z = b[0] * win[0][2] + b[1] * win[1][2] + b[2] * win[2][2];
+                if !(-1.0..=1.0).contains(&z) {
+                    continue;
+                }
+                let depth = z * 0.5 + 0.5;
+
+                interpolate(&v, &inv_w, &b, &mut varyings);
+                interpolate(
+                    &v,
+                    &inv_w,
+                    &barycentric(&win, area, fx + 1.0, fy),
+                    &mut varyings_x,
+                );
+                interpolate(
+                    &v,
+                    &inv_w,
+                    &barycentric(&win, area, fx, fy + 1.0),
+                    &mut varyings_y,
+                );
+                for i in 0..varying_count {
+                    ddx[i] = varyings_x[i] - varyings[i];
+                    ddy[i] = varyings_y[i] - varyings[i];
+                }
+
+                let w = b[0] * inv_w[0] + b[1] * inv_w[1] + b[2] * inv_w[2];
+                let input = FragmentInput {
+                    frag_coord: [fx, fy, depth, w],
+                    varyings: &varyings,
+                    ddx: &ddx,
+                    ddy: &ddy,
+                };
+                let color = match shader.fragment(&input, uniforms) {
+                    Some(c) => c,
+                    None => continue,
+                };
+
+                if !depth_stencil_test(target, state, px as u32, py as u32, depth) {
+                    continue;
+                }
+
+                let dst = target.color.read_pixel(px as u32, py as u32);
+                let c = blend(state.bs, &color, &dst);
+                target.color.write_pixel(px as u32, py as u32, &c);
+            }
+        }
+    }
+}
+
+// 返回 [x0, y0, x1, y1)
+#[inline]
+fn intersect_rect(rect: &(i32, i32, i32, i32), w: u32, h: u32) -> (i32, i32, i32, i32) {
+    (
+        rect.0.max(0),
+        rect.1.max(0),
+        (rect.0 + rect.2).min(w as i32),
+        (rect.1 + rect.3).min(h as i32),
+    )
+}
+
+#[inline]
+fn edge(a: &[f32; 3], b: &[f32; 3], x: &f32, y: &f32) -> f32 {
+    (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0])
+}
+
+/**
+ * 逆时针三角形（y轴向上）的上边和左边
+ */
+#[inline]
+fn is_top_left(a: &[f32; 3], b: &[f32; 3]) -> bool {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    (dy == 0.0 && dx < 0.0) || dy < 0.0
+}
+
+#[inline]
+fn barycentric(win: &[[f32; 3]; 3], area: f32, x: f32, y: f32) -> [f32; 3] {
+    [
+        edge(&win[1], &win[2], &x, &y) / area,
+        edge(&win[2], &win[0], &x, &y) / area,
+        edge(&win[0], &win[1], &x, &y) / area,
+    ]
+}
+
+/**
+ * 透视校正的插值
+ */
+#[inline]
+fn interpolate(v: &[&ShadedVertex; 3], inv_w: &[f32; 3], b: &[f32; 3], out: &mut [f32]) {
+    let w = [b[0] * inv_w[0], b[1] * inv_w[1], b[2] * inv_w[2]];
+    let sum = w[0] + w[1] + w[2];
+    for i in 0..out.len() {
+        out[i] = (w[0] * v[0].varyings[i] + w[1] * v[1].varyings[i] + w[2] * v[2].varyings[i])
+            / sum;
+    }
+}
+
+#[inline]
+fn compare<T: PartialOrd>(func: CompareFunc, src: T, dst: T) -> bool {
+    match func {
+        CompareFunc::Never => false,
+        CompareFunc::Always => true,
+        CompareFunc::Less => src < dst,
+        CompareFunc::Equal => src == dst,
+        CompareFunc::LEqual => src <= dst,
+        CompareFunc::Greater => src > dst,
+        CompareFunc::GEqual => src >= dst,
+        CompareFunc::NotEqual => src != dst,
+    }
+}
+
+#[inline]
+fn stencil_op(op: StencilOp, value: u8, sref: u8) -> u8 {
+    match op {
+        StencilOp::Keep => value,
+        StencilOp::Zero => 0,
+        StencilOp::Replace => sref,
+        StencilOp::Incr => value.saturating_add(1),
+        StencilOp::Decr => value.saturating_sub(1),
+        StencilOp::Invert => !value,
+        StencilOp::IncrWrap => value.wrapping_add(1),
+        StencilOp::DecrWrap => value.wrapping_sub(1),
+    }
+}
+
+/**
+ * 模板测试和深度测试，返回true表示通过
+ * 没有深度模板缓冲区的渲染目标，和gl一样视为测试通过
+ * 注：软件实现正反面用同一套模板状态
+ */
+fn depth_stencil_test(
+    target: &mut Target,
+    state: &DrawState,
+    x: u32,
+    y: u32,
+    depth: f32,
+) -> bool {
+    let rb = match &mut target.depth {
+        Some(rb) => rb,
+        None => return true,
+    };
+    let (w, h) = rb.get_size();
+    if x >= w || y >= h {
+        return true;
+    }
+    let i = (y * w + x) as usize;
+    let ss = state.ss;
+    let ds = state.ds;
+    let sref = (ss.stencil_ref.max(0).min(255)) as u8;
+    let mask = (ss.stencil_mask & 0xff) as u8;
+    // 没有模板缓冲区时， 和gl一样不做模板测试
+    let stencil_enable = ss.is_stencil_test_enable && rb.has_stencil();
+
+    if stencil_enable {
+        let s = rb.stencil[i];
+        if !compare(ss.stencil_test_func, sref & mask, s & mask) {
+            rb.stencil[i] = stencil_op(ss.stencil_fail_op, s, sref);
+            return false;
+        }
+    }
+
+    if ds.is_depth_test_enable {
+        if !compare(ds.depth_test_func, depth, rb.depth[i]) {
+            if stencil_enable {
+                rb.stencil[i] = stencil_op(ss.stencil_zfail_op, rb.stencil[i], sref);
+            }
+            return false;
+        }
+        if ds.is_depth_write_enable {
+            rb.depth[i] = depth;
+        }
+    }
+
+    if stencil_enable {
+        rb.stencil[i] = stencil_op(ss.stencil_zpass_op, rb.stencil[i], sref);
+    }
+    true
+}
+
+fn blend_factor(factor: BlendFactor, src: &[f32; 4], dst: &[f32; 4], constant: &[f32; 4]) -> [f32; 4] {
+    match factor {
+        BlendFactor::Zero => [0.0; 4],
+        BlendFactor::One => [1.0; 4],
+        BlendFactor::SrcColor => *src,
+        BlendFactor::OneMinusSrcColor => [1.0 - src[0], 1.0 - src[1], 1.0 - src[2], 1.0 - src[3]],
+        BlendFactor::DstColor => *dst,
+        BlendFactor::OneMinusDstColor => [1.0 - dst[0], 1.0 - dst[1], 1.0 - dst[2], 1.0 - dst[3]],
+        BlendFactor::SrcAlpha => [src[3]; 4],
+        BlendFactor::OneMinusSrcAlpha => [1.0 - src[3]; 4],
+        BlendFactor::DstAlpha => [dst[3]; 4],
+        BlendFactor::OneMinusDstAlpha => [1.0 - dst[3]; 4],
+        BlendFactor::ConstantColor => *constant,
+        BlendFactor::OneMinusConstantColor => [
+            1.0 - constant[0],
+            1.0 - constant[1],
+            1.0 - constant[2],
+            1.0 - constant[3],
+        ],
+        BlendFactor::ConstantAlpha => [constant[3]; 4],
+        BlendFactor::OneMinusConstantAlpha => [1.0 - constant[3]; 4],
+    }
+}
+
+#[inline]
+fn blend_equation(func: BlendFunc, s: f32, d: f32) -> f32 {
+    match func {
+        BlendFunc::Add => s + d,
+        BlendFunc::Sub => s - d,
+        BlendFunc::ReverseSub => d - s,
+    }
+}
+
+/**
+ * 混合，和webgl一样，混合总是开启的
+ */
+fn blend(bs: &BlendStateDesc, src: &[f32; 4], dst: &[f32; 4]) -> [f32; 4] {
+    // 定点数的颜色缓冲区，片段颜色先截断到[0, 1]
+    let src = [
+        src[0].max(0.0).min(1.0),
+        src[1].max(0.0).min(1.0),
+        src[2].max(0.0).min(1.0),
+        src[3].max(0.0).min(1.0),
+    ];
+    let constant = [
+        *bs.const_rgba.0,
+        *bs.const_rgba.1,
+        *bs.const_rgba.2,
+        *bs.const_rgba.3,
+    ];
+    let src_rgb = blend_factor(bs.src_rgb_factor, &src, dst, &constant);
+    let dst_rgb = blend_factor(bs.dst_rgb_factor, &src, dst, &constant);
+    let src_a = blend_factor(bs.src_alpha_factor, &src, dst, &constant);
+    let dst_a = blend_factor(bs.dst_alpha_factor, &src, dst, &constant);
+
+    let mut r = [0.0; 4];
+    for i in 0..3 {
+        r[i] = blend_equation(bs.rgb_equation, src[i] * src_rgb[i], dst[i] * dst_rgb[i]);
+    }
+    r[3] = blend_equation(bs.alpha_equation, src[3] * src_a[3], dst[3] * dst_a[3]);
+    r
+}
@@ -0,0 +1,103 @@
+use hal_core::{HalRenderBuffer, HalTexture, PixelFormat};
+
+/**
+ * 软件渲染缓冲区
+ * 深度缓冲区同时带有8位模板，相当于gl的DEPTH_STENCIL
+ * 没有模板缓冲区时stencil为空，模板测试总是通过，和gl一样
+ */
+pub struct NullRenderBufferImpl {
+    width: u32,
+    height: u32,
+    _format: PixelFormat,
+    pub depth: Vec<f32>,
+    pub stencil: Vec<u8>,
+}
+
+impl NullRenderBufferImpl {
+    pub fn new(w: u32, h: u32, pformat: PixelFormat) -> Result<Self, String> {
+        Ok(NullRenderBufferImpl {
+            width: w,
+            height: h,
+            _format: pformat,
+            depth: vec![1.0; (w * h) as usize],
+            stencil: vec![0; (w * h) as usize],
+        })
+    }
+
+    /**
+     * 只有深度，没有模板的缓冲区，用于模拟创建时没有请求模板缓冲区的上下文
+     */
+    pub fn without_stencil(w: u32, h: u32, pformat: PixelFormat) -> Self {
+        NullRenderBufferImpl {
+            width: w,
+            height: h,
+            _format: pformat,
+            depth: vec![1.0; (w * h) as usize],
+            stencil: Vec::new(),
+        }
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[inline]
+    pub fn has_stencil(&self) -> bool {
+        self.stencil.len() > 0
+    }
+}
+
+pub struct NullRenderTargetImpl {
+    pub is_default: bool, // 注：不能从默认的渲染目标上取color depth
+    width: u32,
+    height: u32,
+    pub is_tex_destroy: bool,
+    pub color: Option<HalTexture>,
+    pub depth: Option<HalRenderBuffer>,
+}
+
+impl NullRenderTargetImpl {
+    pub fn new(
+        w: u32,
+        h: u32,
+        texture_wrap: HalTexture,
+        rb_wrap: Option<HalRenderBuffer>,
+        is_tex_destroy: bool,
+    ) -> Result<Self, String> {
+        Ok(NullRenderTargetImpl {
+            is_default: false,
+            width: w,
+            height: h,
+            is_tex_destroy,
+            color: Some(texture_wrap),
+            depth: rb_wrap,
+        })
+    }
+
+    /**
+     * 默认渲染目标，相当于canvas
+     * 软件实现中，它的颜色和深度也放在纹理和渲染缓冲区中，但不对外暴露
+     */
+    pub fn new_default(w: u32, h: u32, texture_wrap: HalTexture, rb_wrap: HalRenderBuffer) -> Self {
+        NullRenderTargetImpl {
+            is_default: true,
+            width: w,
+            height: h,
+            is_tex_destroy: true,
+            color: Some(texture_wrap),
+            depth: Some(rb_wrap),
+        }
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn get_color_texture(&self) -> Option<&HalTexture> {
+        if self.is_default {
+            None
+        } else {
+            self.color.as_ref()
+        }
+    }
+}
@@ -0,0 +1,869 @@
+/**
+ * 软件着色器
+ * 软件实现不编译glsl，每对（vs名，fs名）对应一个用rust实现的着色器，
+ * 内置了gui用到的 color，image，text，canvas_text，clip，行为和gui_web/static/shader中的glsl一致
+ * 其他着色器可以通过 NullHalContext::register_shader 注册
+ */
+use std::f32;
+
+use share::Share;
+use slab::Slab;
+
+use hal_core::*;
+
+use texture::NullTextureImpl;
+use util::*;
+
+/**
+ * 创建Program时的宏定义
+ */
+pub struct ShaderDefines {
+    pub vs: Vec<String>,
+    pub fs: Vec<String>,
+}
+
+impl ShaderDefines {
+    pub fn new(vs: &[Option<&str>], fs: &[Option<&str>]) -> Self {
+        ShaderDefines {
+            vs: vs.iter().filter_map(|v| v.map(|v| v.to_string())).collect(),
+            fs: fs.iter().filter_map(|v| v.map(|v| v.to_string())).collect(),
+        }
+    }
+
+    pub fn has_vs(&self, name: &str) -> bool {
+        self.vs.iter().any(|v| v == name)
+    }
+
+    pub fn has_fs(&self, name: &str) -> bool {
+        self.fs.iter().any(|v| v == name)
+    }
+}
+
+/**
+ * 根据宏定义创建着色器
+ */
+pub type ShaderCreator = fn(&ShaderDefines) -> Box<dyn SoftShader>;
+
+/**
+ * 顶点着色器的输入：一个顶点的所有attribute
+ */
+pub struct VertexInput<'a> {
+    pub attributes: &'a [(AttributeName, [f32; 4])],
+}
+
+impl<'a> VertexInput<'a> {
+    /**
+     * 取attribute，缺少的分量和gl一样用(0, 0, 0, 1)补齐
+     */
+    pub fn get(&self, name: &AttributeName) -> [f32; 4] {
+        self.attributes
+            .iter()
+            .find(|v| &v.0 == name)
+            .map_or([0.0, 0.0, 0.0, 1.0], |v| v.1)
+    }
+}
+
+/**
+ * 片段着色器的输入
+ * frag_coord: 同gl_FragCoord，左下角为原点，像素中心是0.5
+ * ddx, ddy: varyings在屏幕x，y方向上相邻像素的差，用于模拟dFdx，dFdy，fwidth
+ */
+pub struct FragmentInput<'a> {
+    pub frag_coord: [f32; 4],
+    pub varyings: &'a [f32],
+    pub ddx: &'a [f32],
+    pub ddy: &'a [f32],
+}
+
+pub trait SoftShader {
+    /**
+     * varying的float个数
+     */
+    fn varying_count(&self) -> usize;
+
+    /**
+     * 返回gl_Position，并写varyings
+     */
+    fn vertex(&self, input: &VertexInput, uniforms: &Uniforms, varyings: &mut [f32]) -> [f32; 4];
+
+    /**
+     * 返回gl_FragColor，None表示discard
+     */
+    fn fragment(&self, input: &FragmentInput, uniforms: &Uniforms) -> Option<[f32; 4]>;
+}
+
+/**
+ * 一次draw的所有uniform，按名字取值
+ */
+pub struct Uniforms<'a> {
+    ubos: &'a [Share<dyn UniformBuffer>],
+    single_layout: &'a [&'a str],
+    singles: &'a [UniformValue],
+    textures: Vec<(&'a str, Option<(&'a NullTextureImpl, &'a SamplerDesc)>)>,
+}
+
+impl<'a> Uniforms<'a> {
+    pub fn new(
+        pp: &'a dyn ProgramParamter,
+        texture_slab: &'a Slab<(NullTextureImpl, u32)>,
+        sampler_slab: &'a Slab<(SamplerDesc, u32)>,
+    ) -> Self {
+        let textures = pp
+            .get_texture_layout()
+            .iter()
+            .zip(pp.get_textures().iter())
+            .map(|(name, (t, s))| {
+                let t = get_ref(texture_slab, t.index, t.use_count);
+                let s = get_ref(sampler_slab, s.index, s.use_count);
+                match (t, s) {
+                    (Some(t), Some(s)) => (*name, Some((t, s))),
+                    _ => (*name, None),
+                }
+            })
+            .collect();
+        Uniforms {
+            ubos: pp.get_values(),
+            single_layout: pp.get_single_uniform_layout(),
+            singles: pp.get_single_uniforms(),
+            textures,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&UniformValue> {
+        for ubo in self.ubos.iter() {
+            for (n, v) in ubo.get_layout().iter().zip(ubo.get_values().iter()) {
+                if *n == name {
+                    return Some(v);
+                }
+            }
+        }
+        self.single_layout
+            .iter()
+            .zip(self.singles.iter())
+            .find(|v| *v.0 == name)
+            .map(|v| v.1)
+    }
+
+    pub fn float1(&self, name: &str) -> f32 {
+        match self.get(name) {
+            Some(UniformValue::Float1(v)) => *v,
+            Some(UniformValue::Int1(v)) => *v as f32,
+            Some(UniformValue::FloatV1(v)) if !v.is_empty() => v[0],
+            _ => 0.0,
+        }
+    }
+
+    pub fn float2(&self, name: &str) -> [f32; 2] {
+        match self.get(name) {
+            Some(UniformValue::Float2(x, y)) => [*x, *y],
+            Some(UniformValue::FloatV2(v)) if v.len() > 1 => [v[0], v[1]],
+            _ => [0.0, 0.0],
+        }
+    }
+
+    pub fn float3(&self, name: &str) -> [f32; 3] {
+        match self.get(name) {
+            Some(UniformValue::Float3(x, y, z)) => [*x, *y, *z],
+            Some(UniformValue::FloatV3(v)) if v.len() > 2 => [v[0], v[1], v[2]],
+            _ => [0.0, 0.0, 0.0],
+        }
+    }
+
+    pub fn float4(&self, name: &str) -> [f32; 4] {
+        match self.get(name) {
+            Some(UniformValue::Float4(x, y, z, w)) => [*x, *y, *z, *w],
+            Some(UniformValue::FloatV4(v)) if v.len() > 3 => [v[0], v[1], v[2], v[3]],
+            _ => [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+    /**
+     * 列主序的4x4矩阵，没有设置时返回单位矩阵
+     */
+    pub fn mat4(&self, name: &str) -> [f32; 16] {
+        let mut r = [
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ];
+        if let Some(UniformValue::MatrixV4(v)) = self.get(name) {
+            if v.len() >= 16 {
+                r.copy_from_slice(&v[0..16]);
+            }
+        }
+        r
+    }
+
+    /**
+     * 同glsl的texture2D，没有设置纹理时返回(0, 0, 0, 1)
+     */
+    pub fn texture2d(&self, name: &str, u: f32, v: f32) -> [f32; 4] {
+        match self.textures.iter().find(|t| t.0 == name) {
+            Some((_, Some((t, s)))) => t.sample(s, u, v),
+            _ => [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+// ============================== 工具函数
+
+/**
+ * 列主序矩阵乘向量
+ */
+#[inline]
+pub fn mul_mat4(m: &[f32; 16], v: [f32; 4]) -> [f32; 4] {
+    let mut r = [0.0; 4];
+    for i in 0..4 {
+        r[i] = m[i] * v[0] + m[4 + i] * v[1] + m[8 + i] * v[2] + m[12 + i] * v[3];
+    }
+    r
+}
+
+#[inline]
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+#[inline]
+fn step(edge: f32, x: f32) -> f32 {
+    if x < edge {
+        0.0
+    } else {
+        1.0
+    }
+}
+
+#[inline]
+fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = ((x - e0) / (e1 - e0)).max(0.0).min(1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[inline]
+fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+#[inline]
+fn median(r: f32, g: f32, b: f32) -> f32 {
+    r.min(g).max(r.max(g).min(b))
+}
+
+fn rgb2hsv(c: [f32; 3]) -> [f32; 3] {
+    let k = [0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0];
+    let s = step(c[2], c[1]);
+    let p = [
+        mix(c[2], c[1], s),
+        mix(c[1], c[2], s),
+        mix(k[3], k[0], s),
+        mix(k[2], k[1], s),
+    ];
+    let s = step(p[0], c[0]);
+    let q = [
+        mix(p[0], c[0], s),
+        mix(p[1], p[1], s),
+        mix(p[3], p[2], s),
+        mix(c[0], p[0], s),
+    ];
+    let d = q[0] - q[3].min(q[1]);
+    let e = 1.0e-10;
+    [
+        (q[2] + (q[3] - q[1]) / (6.0 * d + e)).abs(),
+        d / (q[0] + e),
+        q[0],
+    ]
+}
+
+fn hsv2rgb(c: [f32; 3]) -> [f32; 3] {
+    let k = [1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0];
+    let mut r = [0.0; 3];
+    for i in 0..3 {
+        let p = (fract(c[0] + k[i]) * 6.0 - k[3]).abs();
+        r[i] = c[2] * mix(k[0], (p - k[0]).max(0.0).min(1.0), c[1]);
+    }
+    r
+}
+
+/**
+ * 同glsl中的HSV宏
+ */
+fn apply_hsv(c: &mut [f32; 4], hsv_value: [f32; 3]) {
+    let mut hsv = rgb2hsv([c[0], c[1], c[2]]);
+    hsv[0] += hsv_value[0];
+    let rgb = hsv2rgb(hsv);
+    c[0] = rgb[0];
+    c[1] = rgb[1];
+    c[2] = rgb[2];
+
+    let gray = c[0].max(c[1].max(c[2])) + c[0].min(c[1].min(c[2]));
+    for i in 0..3 {
+        c[i] = mix(c[i], 0.5 * gray, -hsv_value[1]);
+    }
+
+    if hsv_value[2] >= 0.0 {
+        for i in 0..3 {
+            c[i] = mix(c[i], 1.0, hsv_value[2]);
+        }
+    } else {
+        for i in 0..3 {
+            c[i] *= 1.0 + hsv_value[2];
+        }
+    }
+}
+
+fn apply_gray(c: &mut [f32; 4], weight: [f32; 3]) {
+    let g = c[0] * weight[0] + c[1] * weight[1] + c[2] * weight[2];
+    c[0] = g;
+    c[1] = g;
+    c[2] = g;
+}
+
+/**
+ * 裁剪纹理的测试，返回true表示通过
+ * clipIndices1是低24位，clipIndices2是高8位；裁剪纹理的rgba每个通道存8位
+ */
+fn clip_test(input: &FragmentInput, uniforms: &Uniforms) -> bool {
+    let indices = (uniforms.float1("clipIndices1") as u32 & 0xffffff)
+        | ((uniforms.float1("clipIndices2") as u32 & 0xff) << 24);
+    let size = uniforms.float2("clipTextureSize");
+    if size[0] == 0.0 || size[1] == 0.0 {
+        return true;
+    }
+    let c = uniforms.texture2d(
+        "clipTexture",
+        input.frag_coord[0] / size[0],
+        input.frag_coord[1] / size[1],
+    );
+    let mut mask = 0u32;
+    for i in 0..4 {
+        mask |= ((c[i] * 255.0).round() as u32 & 0xff) << (i * 8);
+    }
+    indices & !mask == 0
+}
+
+/**
+ * 裁剪矩形的测试，返回true表示通过
+ */
+#[inline]
+fn clip_box_test(v: &[f32]) -> bool {
+    let factor = (1.0 - v[0].abs()).min(1.0 - v[1].abs());
+    step(0.0, factor) > 0.0
+}
+
+/**
+ * 计算vClipBox
+ */
+#[inline]
+fn clip_box_varying(uniforms: &Uniforms, p1: &[f32; 4], out: &mut [f32]) {
+    let clip_box = uniforms.float4("clipBox");
+    out[0] = (p1[0] - clip_box[0]) / clip_box[2];
+    out[1] = (p1[1] - clip_box[1]) / clip_box[3];
+}
+
+/**
+ * 误差函数的近似，见color_fs
+ */
+fn erf(x: f32) -> f32 {
+    let negative = x < 0.0;
+    let x = x.abs();
+    let x2 = x * x;
+    let x3 = x2 * x;
+    let x4 = x2 * x2;
+    let denom = 1.0 + 0.278393 * x + 0.230389 * x2 + 0.000972 * x3 + 0.078108 * x4;
+    let result = 1.0 - 1.0 / (denom * denom * denom * denom);
+    if negative {
+        -result
+    } else {
+        result
+    }
+}
+
+#[inline]
+fn erf_sigma(x: f32, sigma: f32) -> f32 {
+    erf(x / (sigma * 1.414_213_5))
+}
+
+fn shadow_alpha(pos: [f32; 2], pt_min: [f32; 2], pt_max: [f32; 2], sigma: f32) -> f32 {
+    let d_min = [pos[0] - pt_min[0], pos[1] - pt_min[1]];
+    let d_max = [pos[0] - pt_max[0], pos[1] - pt_max[1]];
+    (erf_sigma(d_max[0], sigma) - erf_sigma(d_min[0], sigma))
+        * (erf_sigma(d_max[1], sigma) - erf_sigma(d_min[1], sigma))
+        / 4.0
+}
+
+/**
+ * viewMatrix * worldMatrix * vec4(x, y, 1.0, 1.0)， 以及最终的gl_Position
+ */
+#[inline]
+fn transform(uniforms: &Uniforms, x: f32, y: f32) -> ([f32; 4], [f32; 4]) {
+    let world = uniforms.mat4("worldMatrix");
+    let view = uniforms.mat4("viewMatrix");
+    let project = uniforms.mat4("projectMatrix");
+    let p1 = mul_mat4(&view, mul_mat4(&world, [x, y, 1.0, 1.0]));
+    let p = mul_mat4(&project, p1);
+    (p1, [p[0], p[1], world[14], 1.0])
+}
+
+// ============================== color
+
+pub struct ColorShader {
+    vs_vertex_color: bool,
+    vs_clip_box: bool,
+    vs_box_shadow_blur: bool,
+
+    fs_ucolor: bool,
+    fs_vertex_color: bool,
+    fs_box_shadow_blur: bool,
+    fs_clip: bool,
+    fs_clip_box: bool,
+    fs_hsv: bool,
+    fs_gray: bool,
+}
+
+impl ColorShader {
+    pub fn create(defines: &ShaderDefines) -> Box<dyn SoftShader> {
+        Box::new(ColorShader {
+            vs_vertex_color: defines.has_vs("VERTEX_COLOR"),
+            vs_clip_box: defines.has_vs("CLIP_BOX"),
+            vs_box_shadow_blur: defines.has_vs("BOX_SHADOW_BLUR"),
+            fs_ucolor: defines.has_fs("UCOLOR"),
+            fs_vertex_color: defines.has_fs("VERTEX_COLOR"),
+            fs_box_shadow_blur: defines.has_fs("BOX_SHADOW_BLUR"),
+            fs_clip: defines.has_fs("CLIP"),
+            fs_clip_box: defines.has_fs("CLIP_BOX"),
+            fs_hsv: defines.has_fs("HSV"),
+            fs_gray: defines.has_fs("GRAY"),
+        })
+    }
+}
+
+// varyings: vColor(0..4), vPosition(4..6), vClipBox(6..8)
+impl SoftShader for ColorShader {
+    fn varying_count(&self) -> usize {
+        8
+    }
+
+    fn vertex(&self, input: &VertexInput, uniforms: &Uniforms, varyings: &mut [f32]) -> [f32; 4] {
+        let position = input.get(&AttributeName::Position);
+        let (p1, p) = transform(uniforms, position[0], position[1]);
+        if self.vs_vertex_color {
+            varyings[0..4].copy_from_slice(&input.get(&AttributeName::Color));
+        }
+        if self.vs_box_shadow_blur {
+            varyings[4] = position[0];
+            varyings[5] = position[1];
+        }
+        if self.vs_clip_box {
+            clip_box_varying(uniforms, &p1, &mut varyings[6..8]);
+        }
+        p
+    }
+
+    fn fragment(&self, input: &FragmentInput, uniforms: &Uniforms) -> Option<[f32; 4]> {
+        if self.fs_clip && !clip_test(input, uniforms) {
+            return None;
+        }
+        let v = input.varyings;
+        let mut c = [1.0f32; 4];
+        if self.fs_vertex_color {
+            for i in 0..4 {
+                c[i] *= v[i];
+            }
+        }
+        if self.fs_ucolor {
+            let u = uniforms.float4("uColor");
+            for i in 0..4 {
+                c[i] *= u[i];
+            }
+        }
+        if self.fs_hsv {
+            apply_hsv(&mut c, uniforms.float3("hsvValue"));
+        }
+        if self.fs_gray {
+            apply_gray(&mut c, [0.299, 0.587, 0.114]);
+        }
+
+        let blur = uniforms.float1("blur");
+        let mut blur_size = blur;
+        if self.fs_box_shadow_blur {
+            blur_size = 1.0;
+            let rect = uniforms.float4("uRect");
+            c[3] *= shadow_alpha(
+                [v[4], v[5]],
+                [rect[0], rect[1]],
+                [rect[2], rect[3]],
+                blur / 2.0,
+            );
+        }
+        c[3] *= uniforms.float1("alpha") * blur_size;
+
+        if self.fs_clip_box && !clip_box_test(&v[6..8]) {
+            c[3] = 0.0;
+        }
+        if c[3] == 0.0 {
+            return None;
+        }
+        Some(c)
+    }
+}
+
+// ============================== image
+
+pub struct ImageShader {
+    vs_clip_box: bool,
+
+    fs_clip: bool,
+    fs_clip_box: bool,
+    fs_hsv: bool,
+    fs_gray: bool,
+}
+
+impl ImageShader {
+    pub fn create(defines: &ShaderDefines) -> Box<dyn SoftShader> {
+        Box::new(ImageShader {
+            vs_clip_box: defines.has_vs("CLIP_BOX"),
+            fs_clip: defines.has_fs("CLIP"),
+            fs_clip_box: defines.has_fs("CLIP_BOX"),
+            fs_hsv: defines.has_fs("HSV"),
+            fs_gray: defines.has_fs("GRAY"),
+        })
+    }
+}
+
+// varyings: vuv(0..2), vClipBox(2..4)
+impl SoftShader for ImageShader {
+    fn varying_count(&self) -> usize {
+        4
+    }
+
+    fn vertex(&self, input: &VertexInput, uniforms: &Uniforms, varyings: &mut [f32]) -> [f32; 4] {
+        let position = input.get(&AttributeName::Position);
+        let uv = input.get(&AttributeName::UV0);
+        let (p1, p) = transform(uniforms, position[0], position[1]);
+        varyings[0] = uv[0];
+        varyings[1] = uv[1];
+        if self.vs_clip_box {
+            clip_box_varying(uniforms, &p1, &mut varyings[2..4]);
+        }
+        p
+    }
+
+    fn fragment(&self, input: &FragmentInput, uniforms: &Uniforms) -> Option<[f32; 4]> {
+        if self.fs_clip && !clip_test(input, uniforms) {
+            return None;
+        }
+        let v = input.varyings;
+        let mut c = uniforms.texture2d("texture", v[0], v[1]);
+        if self.fs_hsv {
+            apply_hsv(&mut c, uniforms.float3("hsvValue"));
+        }
+        if self.fs_gray {
+            apply_gray(&mut c, [0.21, 0.71, 0.07]);
+        }
+        if self.fs_clip_box && !clip_box_test(&v[2..4]) {
+            c[3] = 0.0;
+        }
+        c[3] *= uniforms.float1("alpha");
+        if c[3] == 0.0 {
+            return None;
+        }
+        Some(c)
+    }
+}
+
+// ============================== text（msdf）
+
+pub struct TextShader {
+    vs_vertex_color: bool,
+    vs_clip_box: bool,
+
+    fs_ucolor: bool,
+    fs_vertex_color: bool,
+    fs_clip: bool,
+    fs_clip_box: bool,
+    fs_hsv: bool,
+    fs_gray: bool,
+    fs_stroke: bool,
+    fs_text_shadow_blur: bool,
+}
+
+impl TextShader {
+    pub fn create(defines: &ShaderDefines) -> Box<dyn SoftShader> {
+        Box::new(TextShader {
+            vs_vertex_color: defines.has_vs("VERTEX_COLOR"),
+            vs_clip_box: defines.has_vs("CLIP_BOX"),
+            fs_ucolor: defines.has_fs("UCOLOR"),
+            fs_vertex_color: defines.has_fs("VERTEX_COLOR"),
+            fs_clip: defines.has_fs("CLIP"),
+            fs_clip_box: defines.has_fs("CLIP_BOX"),
+            fs_hsv: defines.has_fs("HSV"),
+            fs_gray: defines.has_fs("GRAY"),
+            fs_stroke: defines.has_fs("STROKE"),
+            fs_text_shadow_blur: defines.has_fs("TEXT_SHADOW_BLUR"),
+        })
+    }
+
+    fn dist(uniforms: &Uniforms, u: f32, v: f32) -> f32 {
+        let s = uniforms.texture2d("texture", u, v);
+        median(s[0], s[1], s[2])
+    }
+}
+
+// varyings: vColor(0..4), vUV(4..6), vClipBox(6..8)
+impl SoftShader for TextShader {
+    fn varying_count(&self) -> usize {
+        8
+    }
+
+    fn vertex(&self, input: &VertexInput, uniforms: &Uniforms, varyings: &mut [f32]) -> [f32; 4] {
+        let position = input.get(&AttributeName::Position);
+        let uv = input.get(&AttributeName::UV0);
+        let (p1, p) = transform(uniforms, position[0], position[1]);
+        if self.vs_vertex_color {
+            varyings[0..4].copy_from_slice(&input.get(&AttributeName::Color));
+        }
+        let size = uniforms.float2("textureSize");
+        varyings[4] = uv[0] / size[0];
+        varyings[5] = uv[1] / size[1];
+        if self.vs_clip_box {
+            clip_box_varying(uniforms, &p1, &mut varyings[6..8]);
+        }
+        p
+    }
+
+    fn fragment(&self, input: &FragmentInput, uniforms: &Uniforms) -> Option<[f32; 4]> {
+        if self.fs_clip && !clip_test(input, uniforms) {
+            return None;
+        }
+        let v = input.varyings;
+        let mut c = [1.0f32; 4];
+        if self.fs_vertex_color {
+            for i in 0..4 {
+                c[i] *= v[i];
+            }
+        }
+        if self.fs_ucolor {
+            let u = uniforms.float4("uColor");
+            for i in 0..4 {
+                c[i] *= u[i];
+            }
+        }
+
+        // fwidth(dist)：用相邻像素的纹理坐标再采样两次
+        let dist = Self::dist(uniforms, v[4], v[5]);
+        let dx = Self::dist(uniforms, v[4] + input.ddx[4], v[5] + input.ddx[5]);
+        let dy = Self::dist(uniforms, v[4] + input.ddy[4], v[5] + input.ddy[5]);
+        let d = (dx - dist).abs() + (dy - dist).abs();
+        let d = d.max(f32::EPSILON);
+        let mut a = if self.fs_text_shadow_blur {
+            // 模糊阴影： 在字形边缘两侧的距离场上做平滑过渡
+            let k = uniforms.float1("blur").max(d).min(0.5);
+            smoothstep(-k, k, dist - 0.5)
+        } else {
+            smoothstep(-d, d, dist - 0.5)
+        };
+
+        if self.fs_stroke {
+            let stroke_color = uniforms.float4("strokeColor");
+            for i in 0..4 {
+                c[i] = mix(stroke_color[i], c[i], a);
+            }
+            a = smoothstep(-d, d, dist - (0.5 - uniforms.float1("strokeSize")));
+        }
+        if self.fs_hsv {
+            apply_hsv(&mut c, uniforms.float3("hsvValue"));
+        }
+        if self.fs_gray {
+            apply_gray(&mut c, [0.299, 0.587, 0.114]);
+        }
+        if self.fs_clip_box && !clip_box_test(&v[6..8]) {
+            c[3] = 0.0;
+        }
+        Some([c[0], c[1], c[2], a * c[3] * uniforms.float1("alpha")])
+    }
+}
+
+// ============================== canvas_text
+
+pub struct CanvasTextShader {
+    vs_vertex_color: bool,
+    vs_clip_box: bool,
+    vs_text_shadow_blur: bool,
+
+    fs_ucolor: bool,
+    fs_vertex_color: bool,
+    fs_clip: bool,
+    fs_clip_box: bool,
+    fs_hsv: bool,
+    fs_gray: bool,
+    fs_text_shadow_blur: bool,
+}
+
+impl CanvasTextShader {
+    pub fn create(defines: &ShaderDefines) -> Box<dyn SoftShader> {
+        Box::new(CanvasTextShader {
+            vs_vertex_color: defines.has_vs("VERTEX_COLOR"),
+            vs_clip_box: defines.has_vs("CLIP_BOX"),
+            vs_text_shadow_blur: defines.has_vs("TEXT_SHADOW_BLUR"),
+            fs_ucolor: defines.has_fs("UCOLOR"),
+            fs_vertex_color: defines.has_fs("VERTEX_COLOR"),
+            fs_clip: defines.has_fs("CLIP"),
+            fs_clip_box: defines.has_fs("CLIP_BOX"),
+            fs_hsv: defines.has_fs("HSV"),
+            fs_gray: defines.has_fs("GRAY"),
+            fs_text_shadow_blur: defines.has_fs("TEXT_SHADOW_BLUR"),
+        })
+    }
+
+    /**
+     * 模糊阴影的覆盖率： 在字形所在的纹理范围内做7x7的高斯采样（半径为2倍标准差）， 不会采样到相邻的字形
+     * texel: 纹素坐标， rect: 字形在纹理中的范围(左, 上, 右, 下)
+     */
+    fn blur_cover(uniforms: &Uniforms, texel: &[f32], rect: &[f32]) -> f32 {
+        let blur = uniforms.float1("blur");
+        let size = uniforms.float2("textureSize");
+        let (min, max) = ([rect[0] + 0.5, rect[1] + 0.5], [rect[2] - 0.5, rect[3] - 0.5]);
+        let (mut cover, mut total) = (0.0, 0.0);
+        for i in -3i32..4 {
+            for j in -3i32..4 {
+                let w = (-((i * i + j * j) as f32) * 2.0 / 9.0).exp();
+                let u = (texel[0] + i as f32 * blur / 3.0).max(min[0]).min(max[0]);
+                let v = (texel[1] + j as f32 * blur / 3.0).max(min[1]).min(max[1]);
+                let s = uniforms.texture2d("texture", u / size[0], v / size[1]);
+                cover += w * (s[3] - s[2]).max(0.0).min(1.0);
+                total += w;
+            }
+        }
+        cover / total
+    }
+}
+
+// varyings: vColor(0..4), vUV(4..6), vClipBox(6..8), vTexel(8..10), vRect(10..14)
+impl SoftShader for CanvasTextShader {
+    fn varying_count(&self) -> usize {
+        14
+    }
+
+    fn vertex(&self, input: &VertexInput, uniforms: &Uniforms, varyings: &mut [f32]) -> [f32; 4] {
+        let position = input.get(&AttributeName::Position);
+        let uv = input.get(&AttributeName::UV0);
+        let world = uniforms.mat4("worldMatrix");
+        let view = uniforms.mat4("viewMatrix");
+        let project = uniforms.mat4("projectMatrix");
+
+        // 世界坐标取整，避免文字模糊
+        let mut p1 = mul_mat4(&world, [position[0], position[1], 1.0, 1.0]);
+        p1[0] = p1[0].ceil();
+        p1[1] = p1[1].ceil();
+        let p1 = mul_mat4(&view, p1);
+        let p = mul_mat4(&project, p1);
+
+        if self.vs_vertex_color {
+            varyings[0..4].copy_from_slice(&input.get(&AttributeName::Color));
+        }
+        let size = uniforms.float2("textureSize");
+        varyings[4] = uv[0] / size[0];
+        varyings[5] = uv[1] / size[1];
+        if self.vs_clip_box {
+            clip_box_varying(uniforms, &p1, &mut varyings[6..8]);
+        }
+        if self.vs_text_shadow_blur {
+            let (uv1, uv2) = (input.get(&AttributeName::UV1), input.get(&AttributeName::UV2));
+            varyings[8] = uv[0];
+            varyings[9] = uv[1];
+            varyings[10..14].copy_from_slice(&[uv1[0], uv1[1], uv2[0], uv2[1]]);
+        }
+        [p[0], p[1], world[14], 1.0]
+    }
+
+    fn fragment(&self, input: &FragmentInput, uniforms: &Uniforms) -> Option<[f32; 4]> {
+        if self.fs_clip && !clip_test(input, uniforms) {
+            return None;
+        }
+        let v = input.varyings;
+        let mut c = [1.0f32; 4];
+        if self.fs_vertex_color {
+            for i in 0..4 {
+                c[i] *= v[i];
+            }
+        }
+        if self.fs_ucolor {
+            let u = uniforms.float4("uColor");
+            for i in 0..4 {
+                c[i] *= u[i];
+            }
+        }
+
+        let alpha = uniforms.float1("alpha");
+        if self.fs_text_shadow_blur {
+            let cover = Self::blur_cover(uniforms, &v[8..10], &v[10..14]);
+            for i in 0..3 {
+                c[i] = alpha * c[i] * c[3] * cover;
+            }
+            c[3] *= cover;
+        } else {
+            let sample = uniforms.texture2d("texture", v[4], v[5]);
+            let stroke_color = uniforms.float4("strokeColor");
+            for i in 0..4 {
+                c[i] = alpha * (sample[0] * stroke_color[i] + sample[1] * c[i]);
+            }
+            c[3] = (sample[3] - sample[2]).max(0.0).min(1.0);
+        }
+
+        if self.fs_hsv {
+            apply_hsv(&mut c, uniforms.float3("hsvValue"));
+        }
+        if self.fs_gray {
+            apply_gray(&mut c, [0.299, 0.587, 0.114]);
+        }
+        if self.fs_clip_box && !clip_box_test(&v[6..8]) {
+            c[3] = 0.0;
+        }
+        Some([c[0], c[1], c[2], c[3] * alpha])
+    }
+}
+
+// ============================== clip
+
+/**
+ * 把每个裁剪面的位标记写入裁剪纹理
+ * 第i个面写入第i位：rgba每个通道8位，配合加法混合使用
+ */
+pub struct ClipShader;
+
+impl ClipShader {
+    pub fn create(_defines: &ShaderDefines) -> Box<dyn SoftShader> {
+        Box::new(ClipShader)
+    }
+}
+
+// varyings: skinIndex(0)
+impl SoftShader for ClipShader {
+    fn varying_count(&self) -> usize {
+        1
+    }
+
+    fn vertex(&self, input: &VertexInput, uniforms: &Uniforms, varyings: &mut [f32]) -> [f32; 4] {
+        let position = input.get(&AttributeName::Position);
+        let skin_index = input.get(&AttributeName::SkinIndex)[0];
+        varyings[0] = skin_index;
+        if skin_index < uniforms.float1("meshNum") {
+            let view = uniforms.mat4("viewMatrix");
+            let project = uniforms.mat4("projectMatrix");
+            mul_mat4(
+                &project,
+                mul_mat4(&view, [position[0], position[1], position[2], 1.0]),
+            )
+        } else {
+            [2.0, 2.0, 2.0, 1.0]
+        }
+    }
+
+    fn fragment(&self, input: &FragmentInput, _uniforms: &Uniforms) -> Option<[f32; 4]> {
+        let index = input.varyings[0].round().max(0.0) as usize;
+        if index >= 32 {
+            return None;
+        }
+        let mut c = [0.0; 4];
+        c[index / 8] = (1 << (index % 8)) as f32 / 255.0;
+        Some(c)
+    }
+}
@@ -0,0 +1,301 @@
+use hal_core::{
+    DataFormat, PixelChanel, PixelFormat, PixelStore, SamplerDesc, TextureData,
+    TextureFilterMode, TextureWrapMode,
+};
+
+/**
+ * 软件纹理
+ * 像素统一按rgba的f32存放，行号和gl一致：第0行是纹理坐标v=0的那一行
+ * 数据格式是字节的纹理，写入时会量化到 1/255，以便和gpu的结果一致
+ */
+pub struct NullTextureImpl {
+    pub width: u32,
+    pub height: u32,
+    pub mipmap_level: u32,
+    pub pixel_format: PixelFormat,
+    pub data_format: DataFormat,
+    pub is_gen_mipmap: bool,
+    pub data: Vec<f32>,
+
+    // pixel_storei的状态
+    pub flip_y: bool,
+    pub premultiply_alpha: bool,
+    pub unpack_alignment: usize,
+}
+
+impl NullTextureImpl {
+    pub fn new_2d(
+        mipmap_level: u32,
+        width: u32,
+        height: u32,
+        pformat: PixelFormat,
+        dformat: DataFormat,
+        is_gen_mipmap: bool,
+        data: Option<TextureData>,
+    ) -> Result<Self, String> {
+        let mut texture = NullTextureImpl {
+            width,
+            height,
+            mipmap_level,
+            pixel_format: pformat,
+            data_format: dformat,
+            is_gen_mipmap,
+            data: vec![0.0; (width * height * 4) as usize],
+            flip_y: false,
+            premultiply_alpha: false,
+            unpack_alignment: 4,
+        };
+        if let Some(data) = data {
+            // 创建时，数据总是铺满整个纹理
+            let data = match data {
+                TextureData::U8(_, _, _, _, v) => TextureData::U8(0, 0, width, height, v),
+                TextureData::F32(_, _, _, _, v) => TextureData::F32(0, 0, width, height, v),
+            };
+            texture.update(0, &data);
+        }
+        Ok(texture)
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn get_render_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+
+    pub fn is_gen_mipmap(&self) -> bool {
+        self.is_gen_mipmap
+    }
+
+    pub fn resize(&mut self, mipmap_level: u32, width: u32, height: u32) {
+        // 软件实现只保存第0层
+        if mipmap_level != 0 {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.data = vec![0.0; (width * height * 4) as usize];
+    }
+
+    /**
+     * 扩展成 width * height，保留原有内容
+     */
+    pub fn extend(&mut self, width: u32, height: u32) {
+        let mut data = vec![0.0; (width * height * 4) as usize];
+        let w = self.width.min(width) as usize;
+        for y in 0..self.height.min(height) as usize {
+            let src = y * self.width as usize * 4;
+            let dst = y * width as usize * 4;
+            data[dst..dst + w * 4].copy_from_slice(&self.data[src..src + w * 4]);
+        }
+        self.width = width;
+        self.height = height;
+        self.data = data;
+    }
+
+    pub fn pixel_storei(&mut self, value: PixelStore) {
+        match value {
+            PixelStore::UnpackFlipYWebgl(r) => self.flip_y = r,
+            PixelStore::UnpackPremultiplyAlphaWebgl(r) => self.premultiply_alpha = r,
+            PixelStore::UnpackAlignment(r) => {
+                self.unpack_alignment = match r {
+                    PixelChanel::One => 1,
+                    PixelChanel::Two => 2,
+                    PixelChanel::Four => 4,
+                    PixelChanel::Eight => 8,
+                }
+            }
+            PixelStore::PackAlignment(_) => (),
+        }
+    }
+
+    pub fn update(&mut self, mipmap_level: u32, data: &TextureData) {
+        if mipmap_level != 0 {
+            return;
+        }
+        let channel = match self.pixel_format {
+            PixelFormat::RGBA => 4,
+            PixelFormat::RGB => 3,
+            PixelFormat::ALPHA | PixelFormat::DEPTH16 => 1,
+        };
+        let (x, y, w, h) = match data {
+            TextureData::U8(x, y, w, h, _) | TextureData::F32(x, y, w, h, _) => {
+                (*x as usize, *y as usize, *w as usize, *h as usize)
+            }
+        };
+        let (row_len, len) = match data {
+            TextureData::U8(_, _, _, _, v) => {
+                let align = self.unpack_alignment;
+                let row = (w * channel).div_ceil(align) * align;
+                if h > 0 && v.len() >= row * (h - 1) + w * channel {
+                    (row, v.len())
+                } else {
+                    (w * channel, v.len())
+                }
+            }
+            TextureData::F32(_, _, _, _, v) => (w * channel, v.len()),
+        };
+
+        for j in 0..h {
+            // 翻转y，只在本次上传的区域内翻转
+            let src_row = if self.flip_y { h - 1 - j } else { j };
+            let dy = y + j;
+            if dy >= self.height as usize {
+                break;
+            }
+            for i in 0..w {
+                let dx = x + i;
+                if dx >= self.width as usize {
+                    break;
+                }
+                let s = src_row * row_len + i * channel;
+                if s + channel > len {
+                    continue;
+                }
+                let mut src = [0.0f32; 4];
+                for c in 0..channel {
+                    src[c] = match data {
+                        TextureData::U8(_, _, _, _, v) => v[s + c] as f32 / 255.0,
+                        TextureData::F32(_, _, _, _, v) => v[s + c],
+                    };
+                }
+                let mut rgba = match self.pixel_format {
+                    PixelFormat::RGBA => src,
+                    PixelFormat::RGB => [src[0], src[1], src[2], 1.0],
+                    PixelFormat::ALPHA => [0.0, 0.0, 0.0, src[0]],
+                    PixelFormat::DEPTH16 => [src[0], src[0], src[0], 1.0],
+                };
+                if self.premultiply_alpha {
+                    rgba[0] *= rgba[3];
+                    rgba[1] *= rgba[3];
+                    rgba[2] *= rgba[3];
+                }
+                self.write_pixel(dx as u32, dy as u32, &rgba);
+            }
+        }
+    }
+
+    /**
+     * 读取区域内的像素，按行存放
+     */
+    pub fn read_region(&self, x: u32, y: u32, width: u32, height: u32) -> Vec<[f32; 4]> {
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for j in 0..height {
+            for i in 0..width {
+                pixels.push(self.read_pixel(x + i, y + j));
+            }
+        }
+        pixels
+    }
+
+    pub fn write_region(&mut self, x: u32, y: u32, width: u32, height: u32, pixels: &[[f32; 4]]) {
+        for j in 0..height {
+            for i in 0..width {
+                if let Some(p) = pixels.get((j * width + i) as usize) {
+                    self.write_pixel(x + i, y + j, p);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn read_pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        if x >= self.width || y >= self.height {
+            return [0.0, 0.0, 0.0, 0.0];
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    #[inline]
+    pub fn write_pixel(&mut self, x: u32, y: u32, value: &[f32; 4]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        let is_byte = match self.data_format {
+            DataFormat::Byte | DataFormat::UnsignedByte => true,
+            _ => false,
+        };
+        for c in 0..4 {
+            let mut v = value[c];
+            if is_byte {
+                v = (v.max(0.0).min(1.0) * 255.0).round() / 255.0;
+            }
+            self.data[i + c] = v;
+        }
+        match self.pixel_format {
+            PixelFormat::RGB => self.data[i + 3] = 1.0,
+            PixelFormat::ALPHA => {
+                self.data[i] = 0.0;
+                self.data[i + 1] = 0.0;
+                self.data[i + 2] = 0.0;
+            }
+            _ => (),
+        }
+    }
+
+    /**
+     * 采样，uv的范围和gl一致
+     * 注：软件实现不计算lod，放大和缩小的过滤方式不同时，用放大的过滤方式
+     */
+    pub fn sample(&self, sampler: &SamplerDesc, u: f32, v: f32) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        let (w, h) = (self.width as i32, self.height as i32);
+        match sampler.mag_filter {
+            TextureFilterMode::Nearest => {
+                let x = wrap((u * w as f32).floor() as i32, w, sampler.u_wrap);
+                let y = wrap((v * h as f32).floor() as i32, h, sampler.v_wrap);
+                self.read_pixel(x as u32, y as u32)
+            }
+            TextureFilterMode::Linear => {
+                let fx = u * w as f32 - 0.5;
+                let fy = v * h as f32 - 0.5;
+                let x0 = fx.floor();
+                let y0 = fy.floor();
+                let (tx, ty) = (fx - x0, fy - y0);
+                let (x0, y0) = (x0 as i32, y0 as i32);
+                let xa = wrap(x0, w, sampler.u_wrap) as u32;
+                let xb = wrap(x0 + 1, w, sampler.u_wrap) as u32;
+                let ya = wrap(y0, h, sampler.v_wrap) as u32;
+                let yb = wrap(y0 + 1, h, sampler.v_wrap) as u32;
+                let p00 = self.read_pixel(xa, ya);
+                let p10 = self.read_pixel(xb, ya);
+                let p01 = self.read_pixel(xa, yb);
+                let p11 = self.read_pixel(xb, yb);
+                let mut r = [0.0; 4];
+                for c in 0..4 {
+                    let top = p00[c] + (p10[c] - p00[c]) * tx;
+                    let bottom = p01[c] + (p11[c] - p01[c]) * tx;
+                    r[c] = top + (bottom - top) * ty;
+                }
+                r
+            }
+        }
+    }
+}
+
+#[inline]
+fn wrap(i: i32, size: i32, mode: TextureWrapMode) -> i32 {
+    match mode {
+        TextureWrapMode::ClampToEdge => i.max(0).min(size - 1),
+        TextureWrapMode::Repeat => ((i % size) + size) % size,
+        TextureWrapMode::MirroredRepeat => {
+            let period = size * 2;
+            let i = ((i % period) + period) % period;
+            if i < size {
+                i
+            } else {
+                period - 1 - i
+            }
+        }
+    }
+}
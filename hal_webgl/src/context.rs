@@ -1308,6 +1308,16 @@ impl WebglHalContext {
 					1024
 				}
 			};
+        // 创建上下文时没有指定stencil: true， 默认渲染目标没有模板缓冲区
+        let stencil_bits = match gl
+            .get_parameter(WebGLRenderingContext::STENCIL_BITS)
+            .try_into(){
+				Ok(r) => r,
+				Err(_r) => {
+					println!("get_parameter fail, paramName: STENCIL_BITS");
+					0
+				}
+			};
         let standard_derivatives = gl
             .get_extension::<OESStandardDerivatives>()
 			.map_or(false, |_v| true);
@@ -1414,6 +1424,7 @@ impl WebglHalContext {
                 depth_texture_extension: depth_texture_extension,
                 vertex_array_object: vertex_array_object,
                 instanced_arrays: instanced_arrays,
+                stencil_bits: stencil_bits,
             },
             Extensions {
                 pvrtc: pvrtc_e,